@@ -368,6 +368,12 @@ extension CameraFrameExtensions on CameraFrame {
         return (width * height * 3) ~/ 2;
       case ImageFormat.BGRA:
         return width * height * 4;
+      case ImageFormat.NV12:
+        return (width * height * 3) ~/ 2;
+      case ImageFormat.YUY2:
+        return width * height * 2;
+      case ImageFormat.GRAY8:
+        return width * height;
     }
   }
 }
@@ -28,6 +28,13 @@ fn main() {
     println!("cargo:rerun-if-changed=src/bridge.rs");
     println!("cargo:rerun-if-changed=build.rs");
 
+    // === gRPC service ===
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/tracking.proto");
+        tonic_build::compile_protos("proto/tracking.proto").expect("Failed to compile proto/tracking.proto");
+    }
+
     // === Platform-specific configuration ===
     let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
     let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
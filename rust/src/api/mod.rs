@@ -6,22 +6,83 @@
 pub mod face_tracker_api;
 pub mod stream_handler;
 
-use flutter_rust_bridge::frb;
+use flutter_rust_bridge::{frb, StreamSink};
 use crate::models::*;
-use crate::error::PluginError;
-use crate::face_tracking::tracker::FaceTracker;
+use crate::models::manager::{self, ModelBlob, ModelDownloadEvent, ModelSet};
+use crate::calibration::profiles::{self, CalibrationProfile};
+use crate::camera::{self, CameraCaptureConfig, CameraDevice};
+use crate::error::{ErrorCode, PluginError};
+use crate::events;
+use crate::face_tracking::acceleration::{self, InferenceOptions};
+use crate::face_tracking::adaptive::{AdaptiveQualityConfig, BatteryState, QualityStep, ThermalState};
+use crate::face_tracking::association::{FaceLifecycleEvent, DEFAULT_TRACK_MEMORY_MS};
+use crate::face_tracking::backend::InferenceBackendKind;
+use crate::face_tracking::benchmark::{self, BenchmarkResult};
+use crate::face_tracking::blink::{BlinkConfig, BlinkEvent, WinkEvent};
+use crate::face_tracking::broadcast::FaceSubscriberOptions;
+use crate::face_tracking::comparison::{self, ModelVariant, ModelVariantReport};
+use crate::face_tracking::conventions::OutputConventions;
+use crate::face_tracking::humanoid::{self, BoneRotation, HumanoidConfig};
+use crate::face_tracking::imu::{ImuFusionConfig, ImuSample};
+use crate::face_tracking::embedding::EmbeddingConfig;
+use crate::face_tracking::expressions::{EyeCalibration, ExpressionCalibration, ExpressionFeature, MouthCalibration, ValueRange};
+use crate::face_tracking::filters::SmoothingConfig;
+use crate::face_tracking::gestures::{GestureConfig, HeadGestureEvent};
+use crate::face_tracking::mesh;
+use crate::face_tracking::attention::AttentionConfig;
+use crate::face_tracking::drowsiness::{DrowsinessConfig, DrowsinessEvent};
+use crate::face_tracking::fusion::ConfidenceFusionConfig;
+use crate::face_tracking::prediction::PredictionConfig;
+use crate::face_tracking::segmentation::{SegmentationConfig, SegmentationMatte};
+use crate::mapping::curves::OutputCurves;
+use crate::face_tracking::preprocessing::PreprocessingConfig;
+use crate::face_tracking::recognition;
+use crate::face_tracking::resampler::{self, ResamplerConfig};
+use crate::face_tracking::stats::DEFAULT_STATS_WINDOW_MS;
+use crate::face_tracking::tracker::{FaceStreamSink, FaceTracker, FRAME_QUEUE_CAPACITY};
+use crate::input::multi::{self, SourceSpec, SourceUpdate};
+use crate::lifecycle::{self, TrackerHandle};
+use crate::logging::{self, LogLevel, LogRecord, LoggingConfig};
+use crate::input::network;
+use crate::input::remote::{self, FrameReceiver, FrameReceiverConfig, FrameSenderConfig};
+use crate::input::{self, still, FrameSource, FrameSourceEvent};
+use crate::input::video::{self, VideoProcessingUpdate};
+use crate::protocols::{self, osf::{self, OsfOutputConfig, OsfSender}, vmc::{self, VmcConfig, VmcSender}};
+use crate::protocols::ifacialmocap::{self, IFacialMocapConfig, IFacialMocapSender};
+use crate::protocols::perfect_sync::{self, BlendshapeValue, PerfectSyncConfig};
+use crate::protocols::vtube_studio::{self, VtsParameterMapping};
+use crate::protocols::websocket::{self, WebSocketServer};
+use crate::session::{self, bvh::{self, BvhExportOptions}, export::{self, SessionExportFormat}, recorder::{self, SessionRecorder}, replay};
+use crate::utils::{compositing::{self, BackgroundImage}, overlay::{self, OverlayOptions}, panic, privacy::{self, PrivacyOptions}, profiling, shared_buffer};
+use crate::utils::threading::{self, ThreadingConfig};
+use crate::validation::ValidationReport;
 use crate::GLOBAL_TRACKER;
 use log::{info, debug, error};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Configuration for the face tracker
+///
+/// Serialized as JSON by `export_config_json`; fields missing from the JSON
+/// take their default values.
 #[frb(dart_metadata=("freezed", "immutable"))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TrackerConfig {
     /// Model type to use for face detection
     pub model_type: ModelType,
+    /// OpenSeeFace landmark model quality from -1 (fastest) to 4; `None`
+    /// uses level 3 for RetinaFace and level 0 for MTCNN
+    pub quality_level: Option<i32>,
+    /// Precision of the landmark model files
+    pub model_precision: ModelPrecision,
     /// Confidence threshold for face detection (0.0 - 1.0)
     pub confidence_threshold: f32,
+    /// Faces whose `FaceQuality::score` is below this are dropped before
+    /// smoothing, statistics and output (0.0 = keep every face)
+    pub min_face_quality: f32,
     /// Maximum number of faces to track simultaneously
     pub max_faces: u32,
     /// Enable facial landmark detection
@@ -30,384 +91,2577 @@ pub struct TrackerConfig {
     pub enable_pose_estimation: bool,
     /// Enable eye gaze tracking
     pub enable_gaze_tracking: bool,
+    /// Add a dense face mesh to each face with landmarks (several hundred
+    /// vertices per face; see `get_face_mesh_topology`)
+    pub enable_face_mesh: bool,
     /// Processing frame rate (FPS)
     pub target_fps: u32,
+    /// Handling of streamed frames that arrive faster than `target_fps`
+    pub frame_rate_policy: FrameRatePolicy,
+    /// Number of pushed frames that can wait for the tracking stream
+    pub queue_capacity: u32,
+    /// Handling of frames pushed while the stream queue is full
+    pub queue_policy: QueuePolicy,
+    /// Maximum width of the image passed to the detector (0 = full resolution)
+    pub detection_width: u32,
+    /// Maximum height of the image passed to the detector (0 = full resolution)
+    pub detection_height: u32,
+    /// Directory to load the models from instead of the downloaded or
+    /// bundled models
+    pub model_path: Option<String>,
+    /// Inference stack running the tracking models
+    pub inference_backend: InferenceBackendKind,
+    /// Hardware backend for the tracking models; must be one of
+    /// `get_available_backends` (falls back to CPU if its delegate fails)
+    pub acceleration: AccelerationBackend,
+    /// Inference runtime tuning (threads, graph optimization, provider order)
+    pub inference: InferenceOptions,
+    /// Runtime and inference thread counts and thread priority
+    pub threading: ThreadingConfig,
+    /// Log file output
+    pub logging: LoggingConfig,
+    /// Run full-frame detection every N frames and track in face regions in
+    /// between (1 = detect every frame)
+    pub detection_interval: u32,
+    /// Face confidence below which a full detection is forced
+    pub redetect_confidence: f32,
+    /// Mirror frames horizontally after rotation (e.g. for front cameras)
+    pub mirror_input: bool,
+    /// How long a lost face keeps its ID for re-identification (ms)
+    pub track_memory_ms: u32,
+    /// Blending of low-confidence landmarks and pose with the previous state
+    pub confidence_fusion: ConfidenceFusionConfig,
+    /// Temporal smoothing of landmarks and head pose, optionally per channel
+    pub smoothing: SmoothingConfig,
+    /// Extrapolation of pose and landmarks to compensate pipeline latency
+    pub prediction: PredictionConfig,
+    /// Deadzone, gain, gamma and clamp of the head pose and expression
+    /// values, applied right before results are emitted
+    pub output_curves: OutputCurves,
+    /// Eye aspect ratio range mapped onto eye openness
+    pub eye_calibration: EyeCalibration,
+    /// Mouth measurement ranges mapped onto the mouth shape values
+    pub mouth_calibration: MouthCalibration,
+    /// Per-user ranges stretched onto the full expression values
+    pub expression_calibration: ExpressionCalibration,
+    /// Blink and wink detection thresholds
+    pub blink_detection: BlinkConfig,
+    /// Nod, shake and tilt recognition
+    pub head_gestures: GestureConfig,
+    /// Attention score from head pose, gaze and eye openness
+    pub attention: AttentionConfig,
+    /// PERCLOS and prolonged eye closure detection
+    pub drowsiness: DrowsinessConfig,
+    /// Length of the rolling window of `TrackingStats::window` (ms)
+    pub stats_window_ms: u32,
+    /// Longest time detection may take on one frame before it is abandoned
+    /// (ms, 0 = no limit)
+    pub frame_timeout_ms: u32,
+    /// Switch to CPU inference after a frame timed out on an accelerator
+    pub fallback_on_timeout: bool,
+    /// Brightening of dim frames before detection (off by default)
+    pub preprocessing: PreprocessingConfig,
+    /// Lower detection resolution, frame rate and extras when the device
+    /// reports heat or a low battery (see `report_device_state`)
+    pub adaptive_quality: AdaptiveQualityConfig,
+    /// Fusion of gyroscope samples into the head pose (see `push_imu_sample`)
+    pub imu_fusion: ImuFusionConfig,
+    /// Axis directions, Euler order and angle unit of the returned pose and
+    /// gaze, and whether coordinates are in pixels or normalized
+    pub output_conventions: OutputConventions,
+    /// Intrinsics of the camera; when set, head pose is solved from the
+    /// landmarks with them instead of a guessed focal length
+    pub camera_intrinsics: Option<CameraIntrinsics>,
+    /// Embedding model adding `Face::embedding` to faces with landmarks
+    /// (requires the `tract` feature; `None` = no embeddings)
+    pub face_embedding: Option<EmbeddingConfig>,
+    /// Person segmentation model producing an alpha matte of each frame
+    /// (requires the `tract` feature; `None` = no segmentation)
+    pub segmentation: Option<SegmentationConfig>,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            model_type: ModelType::RetinaFace,
+            quality_level: None,
+            model_precision: ModelPrecision::Float32,
+            confidence_threshold: 0.8,
+            min_face_quality: 0.0,
+            max_faces: 4,
+            enable_landmarks: true,
+            enable_pose_estimation: true,
+            enable_gaze_tracking: false,
+            enable_face_mesh: false,
+            target_fps: 30,
+            frame_rate_policy: FrameRatePolicy::Drop,
+            queue_capacity: FRAME_QUEUE_CAPACITY,
+            queue_policy: QueuePolicy::DropNewest,
+            detection_width: 640,
+            detection_height: 480,
+            model_path: None,
+            inference_backend: InferenceBackendKind::OpenSeeFace,
+            acceleration: AccelerationBackend::CPU,
+            inference: InferenceOptions::default(),
+            threading: ThreadingConfig::default(),
+            logging: LoggingConfig::default(),
+            detection_interval: 1,
+            redetect_confidence: 0.6,
+            mirror_input: false,
+            track_memory_ms: DEFAULT_TRACK_MEMORY_MS,
+            confidence_fusion: ConfidenceFusionConfig::default(),
+            smoothing: SmoothingConfig::default(),
+            prediction: PredictionConfig::default(),
+            output_curves: OutputCurves::default(),
+            eye_calibration: EyeCalibration::default(),
+            mouth_calibration: MouthCalibration::default(),
+            expression_calibration: ExpressionCalibration::default(),
+            blink_detection: BlinkConfig::default(),
+            head_gestures: GestureConfig::default(),
+            attention: AttentionConfig::default(),
+            drowsiness: DrowsinessConfig::default(),
+            stats_window_ms: DEFAULT_STATS_WINDOW_MS,
+            frame_timeout_ms: 2000,
+            fallback_on_timeout: false,
+            preprocessing: PreprocessingConfig::default(),
+            adaptive_quality: AdaptiveQualityConfig::default(),
+            imu_fusion: ImuFusionConfig::default(),
+            output_conventions: OutputConventions::default(),
+            camera_intrinsics: None,
+            face_embedding: None,
+            segmentation: None,
+        }
+    }
+}
+
+/// Bundled configuration for a typical setup
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Phones on battery: one face, low resolution and frame rate
+    LowPower,
+    /// Everyday use on phones and laptops
+    Balanced,
+    /// Best landmark quality at a normal frame rate
+    Quality,
+    /// Desktop streaming rigs: full resolution, high frame rate, gaze
+    Studio,
+}
+
+impl TrackerConfig {
+    /// Configuration bundled by `preset`
+    ///
+    /// Fields a preset does not concern keep their defaults.
+    #[frb(sync)]
+    pub fn from_preset(preset: Preset) -> TrackerConfig {
+        let base = TrackerConfig::default();
+        match preset {
+            Preset::LowPower => TrackerConfig {
+                quality_level: Some(-1),
+                model_precision: ModelPrecision::Int8,
+                max_faces: 1,
+                enable_gaze_tracking: false,
+                target_fps: 15,
+                detection_width: 320,
+                detection_height: 240,
+                detection_interval: 4,
+                // Heavier smoothing hides the coarser landmarks
+                smoothing: SmoothingConfig { min_cutoff: 0.5, ..SmoothingConfig::default() },
+                ..base
+            },
+            Preset::Balanced => TrackerConfig {
+                quality_level: Some(1),
+                max_faces: 2,
+                enable_gaze_tracking: false,
+                target_fps: 30,
+                detection_interval: 2,
+                ..base
+            },
+            Preset::Quality => TrackerConfig {
+                quality_level: Some(3),
+                max_faces: 2,
+                enable_gaze_tracking: true,
+                target_fps: 30,
+                detection_width: 1280,
+                detection_height: 720,
+                detection_interval: 1,
+                ..base
+            },
+            Preset::Studio => TrackerConfig {
+                quality_level: Some(4),
+                max_faces: 4,
+                enable_gaze_tracking: true,
+                target_fps: 60,
+                frame_rate_policy: FrameRatePolicy::Coalesce,
+                detection_width: 0,
+                detection_height: 0,
+                detection_interval: 1,
+                // Follow fast movement closely at the higher frame rate
+                smoothing: SmoothingConfig { min_cutoff: 1.5, beta: 0.01, ..SmoothingConfig::default() },
+                ..base
+            },
+        }
+    }
+
+    /// Landmark model quality level, resolving the `model_type` default
+    pub fn landmark_quality_level(&self) -> i32 {
+        self.quality_level.unwrap_or(match self.model_type {
+            ModelType::RetinaFace => 3,
+            ModelType::MTCNN => 0,
+        })
+    }
+}
+
+/// Changes to apply to the current tracker configuration
+///
+/// Only fields that are set are changed; `None` keeps the current value.
+/// Fields that are optional in `TrackerConfig` can be set but not cleared
+/// here; use `update_tracker_config` for that.
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Default)]
+pub struct TrackerConfigUpdate {
+    /// Model type to use for face detection
+    pub model_type: Option<ModelType>,
+    /// OpenSeeFace landmark model quality from -1 (fastest) to 4
+    pub quality_level: Option<i32>,
+    /// Precision of the landmark model files
+    pub model_precision: Option<ModelPrecision>,
+    /// Confidence threshold for face detection (0.0 - 1.0)
+    pub confidence_threshold: Option<f32>,
+    /// Faces whose `FaceQuality::score` is below this are dropped before
+    /// smoothing, statistics and output (0.0 = keep every face)
+    pub min_face_quality: Option<f32>,
+    /// Maximum number of faces to track simultaneously
+    pub max_faces: Option<u32>,
+    /// Enable facial landmark detection
+    pub enable_landmarks: Option<bool>,
+    /// Enable head pose estimation
+    pub enable_pose_estimation: Option<bool>,
+    /// Enable eye gaze tracking
+    pub enable_gaze_tracking: Option<bool>,
+    /// Add a dense face mesh to each face with landmarks
+    pub enable_face_mesh: Option<bool>,
+    /// Processing frame rate (FPS)
+    pub target_fps: Option<u32>,
+    /// Handling of streamed frames that arrive faster than `target_fps`
+    pub frame_rate_policy: Option<FrameRatePolicy>,
+    /// Number of pushed frames that can wait for the tracking stream
+    pub queue_capacity: Option<u32>,
+    /// Handling of frames pushed while the stream queue is full
+    pub queue_policy: Option<QueuePolicy>,
+    /// Maximum width of the image passed to the detector (0 = full resolution)
+    pub detection_width: Option<u32>,
+    /// Maximum height of the image passed to the detector (0 = full resolution)
+    pub detection_height: Option<u32>,
+    /// Directory to load the models from instead of the downloaded or
+    /// bundled models
+    pub model_path: Option<String>,
+    /// Inference stack running the tracking models
+    pub inference_backend: Option<InferenceBackendKind>,
+    /// Hardware backend for the tracking models (falls back to CPU)
+    pub acceleration: Option<AccelerationBackend>,
+    /// Inference runtime tuning (threads, graph optimization, provider order)
+    pub inference: Option<InferenceOptions>,
+    /// Runtime and inference thread counts and thread priority
+    pub threading: Option<ThreadingConfig>,
+    /// Log file output
+    pub logging: Option<LoggingConfig>,
+    /// Run full-frame detection every N frames and track in face regions in
+    /// between (1 = detect every frame)
+    pub detection_interval: Option<u32>,
+    /// Face confidence below which a full detection is forced
+    pub redetect_confidence: Option<f32>,
+    /// Mirror frames horizontally after rotation (e.g. for front cameras)
+    pub mirror_input: Option<bool>,
+    /// How long a lost face keeps its ID for re-identification (ms)
+    pub track_memory_ms: Option<u32>,
+    /// Blending of low-confidence landmarks and pose with the previous state
+    pub confidence_fusion: Option<ConfidenceFusionConfig>,
+    /// Temporal smoothing of landmarks and head pose, optionally per channel
+    pub smoothing: Option<SmoothingConfig>,
+    /// Extrapolation of pose and landmarks to compensate pipeline latency
+    pub prediction: Option<PredictionConfig>,
+    /// Deadzone, gain, gamma and clamp of the head pose and expression
+    /// values, applied right before results are emitted
+    pub output_curves: Option<OutputCurves>,
+    /// Eye aspect ratio range mapped onto eye openness
+    pub eye_calibration: Option<EyeCalibration>,
+    /// Mouth measurement ranges mapped onto the mouth shape values
+    pub mouth_calibration: Option<MouthCalibration>,
+    /// Per-user ranges stretched onto the full expression values
+    pub expression_calibration: Option<ExpressionCalibration>,
+    /// Blink and wink detection thresholds
+    pub blink_detection: Option<BlinkConfig>,
+    /// Nod, shake and tilt recognition
+    pub head_gestures: Option<GestureConfig>,
+    /// Attention score from head pose, gaze and eye openness
+    pub attention: Option<AttentionConfig>,
+    /// PERCLOS and prolonged eye closure detection
+    pub drowsiness: Option<DrowsinessConfig>,
+    /// Length of the rolling window of `TrackingStats::window` (ms)
+    pub stats_window_ms: Option<u32>,
+    /// Longest time detection may take on one frame before it is abandoned
+    /// (ms, 0 = no limit)
+    pub frame_timeout_ms: Option<u32>,
+    /// Switch to CPU inference after a frame timed out on an accelerator
+    pub fallback_on_timeout: Option<bool>,
+    /// Brightening of dim frames before detection (off by default)
+    pub preprocessing: Option<PreprocessingConfig>,
+    /// Quality reduction under thermal or battery pressure
+    pub adaptive_quality: Option<AdaptiveQualityConfig>,
+    /// Fusion of gyroscope samples into the head pose
+    pub imu_fusion: Option<ImuFusionConfig>,
+    /// Axis directions, Euler order and angle unit of the returned pose and
+    /// gaze, and whether coordinates are in pixels or normalized
+    pub output_conventions: Option<OutputConventions>,
+    /// Intrinsics of the camera used to solve head pose
+    pub camera_intrinsics: Option<CameraIntrinsics>,
+    /// Embedding model adding `Face::embedding` to faces with landmarks
+    pub face_embedding: Option<EmbeddingConfig>,
+    /// Person segmentation model producing an alpha matte of each frame
+    pub segmentation: Option<SegmentationConfig>,
+}
+
+impl TrackerConfigUpdate {
+    /// Copy the set fields into `config`
+    pub fn apply_to(self, config: &mut TrackerConfig) {
+        if let Some(value) = self.model_type {
+            config.model_type = value;
+        }
+        if let Some(value) = self.quality_level {
+            config.quality_level = Some(value);
+        }
+        if let Some(value) = self.model_precision {
+            config.model_precision = value;
+        }
+        if let Some(value) = self.confidence_threshold {
+            config.confidence_threshold = value;
+        }
+        if let Some(value) = self.min_face_quality {
+            config.min_face_quality = value;
+        }
+        if let Some(value) = self.max_faces {
+            config.max_faces = value;
+        }
+        if let Some(value) = self.enable_landmarks {
+            config.enable_landmarks = value;
+        }
+        if let Some(value) = self.enable_pose_estimation {
+            config.enable_pose_estimation = value;
+        }
+        if let Some(value) = self.enable_gaze_tracking {
+            config.enable_gaze_tracking = value;
+        }
+        if let Some(value) = self.enable_face_mesh {
+            config.enable_face_mesh = value;
+        }
+        if let Some(value) = self.target_fps {
+            config.target_fps = value;
+        }
+        if let Some(value) = self.frame_rate_policy {
+            config.frame_rate_policy = value;
+        }
+        if let Some(value) = self.queue_capacity {
+            config.queue_capacity = value;
+        }
+        if let Some(value) = self.queue_policy {
+            config.queue_policy = value;
+        }
+        if let Some(value) = self.detection_width {
+            config.detection_width = value;
+        }
+        if let Some(value) = self.detection_height {
+            config.detection_height = value;
+        }
+        if let Some(value) = self.model_path {
+            config.model_path = Some(value);
+        }
+        if let Some(value) = self.inference_backend {
+            config.inference_backend = value;
+        }
+        if let Some(value) = self.acceleration {
+            config.acceleration = value;
+        }
+        if let Some(value) = self.inference {
+            config.inference = value;
+        }
+        if let Some(value) = self.threading {
+            config.threading = value;
+        }
+        if let Some(value) = self.logging {
+            config.logging = value;
+        }
+        if let Some(value) = self.detection_interval {
+            config.detection_interval = value;
+        }
+        if let Some(value) = self.redetect_confidence {
+            config.redetect_confidence = value;
+        }
+        if let Some(value) = self.mirror_input {
+            config.mirror_input = value;
+        }
+        if let Some(value) = self.track_memory_ms {
+            config.track_memory_ms = value;
+        }
+        if let Some(value) = self.confidence_fusion {
+            config.confidence_fusion = value;
+        }
+        if let Some(value) = self.smoothing {
+            config.smoothing = value;
+        }
+        if let Some(value) = self.prediction {
+            config.prediction = value;
+        }
+        if let Some(value) = self.output_curves {
+            config.output_curves = value;
+        }
+        if let Some(value) = self.eye_calibration {
+            config.eye_calibration = value;
+        }
+        if let Some(value) = self.mouth_calibration {
+            config.mouth_calibration = value;
+        }
+        if let Some(value) = self.expression_calibration {
+            config.expression_calibration = value;
+        }
+        if let Some(value) = self.blink_detection {
+            config.blink_detection = value;
+        }
+        if let Some(value) = self.head_gestures {
+            config.head_gestures = value;
+        }
+        if let Some(value) = self.attention {
+            config.attention = value;
+        }
+        if let Some(value) = self.drowsiness {
+            config.drowsiness = value;
+        }
+        if let Some(value) = self.stats_window_ms {
+            config.stats_window_ms = value;
+        }
+        if let Some(value) = self.frame_timeout_ms {
+            config.frame_timeout_ms = value;
+        }
+        if let Some(value) = self.fallback_on_timeout {
+            config.fallback_on_timeout = value;
+        }
+        if let Some(value) = self.preprocessing {
+            config.preprocessing = value;
+        }
+        if let Some(value) = self.adaptive_quality {
+            config.adaptive_quality = value;
+        }
+        if let Some(value) = self.imu_fusion {
+            config.imu_fusion = value;
+        }
+        if let Some(value) = self.output_conventions {
+            config.output_conventions = value;
+        }
+        if let Some(value) = self.camera_intrinsics {
+            config.camera_intrinsics = Some(value);
+        }
+        if let Some(value) = self.face_embedding {
+            config.face_embedding = Some(value);
+        }
+        if let Some(value) = self.segmentation {
+            config.segmentation = Some(value);
+        }
+    }
+}
+
+impl PluginError {
+    /// Stable code, category and context of the error for handling in Dart
+    #[frb(sync)]
+    pub fn error_code(&self) -> ErrorCode {
+        self.to_error_code()
+    }
+}
+
+/// Check a configuration without initializing the tracker, listing every
+/// violated constraint with a suggested fix
+#[frb(sync)]
+pub fn validate_config(config: TrackerConfig) -> ValidationReport {
+    panic::guard_or(
+        |e| {
+            let mut report = ValidationReport::new();
+            report.check(false, "config", "could not be validated", e.to_string());
+            report
+        },
+        || check_config(&config),
+    )
+}
+
+pub(crate) fn check_config(config: &TrackerConfig) -> ValidationReport {
+    let mut report = ValidationReport::new();
+    report.check(
+        (0.0..=1.0).contains(&config.confidence_threshold),
+        "confidence_threshold",
+        "must be between 0.0 and 1.0",
+        "Use 0.5",
+    );
+    report.check(
+        (0.0..=1.0).contains(&config.min_face_quality),
+        "min_face_quality",
+        "must be between 0.0 and 1.0",
+        "Use 0.0 to keep every face, or around 0.3 to drop badly blurred or turned faces",
+    );
+    report.check(config.max_faces > 0, "max_faces", "must be greater than 0", "Use 1 for a single performer");
+    report.check(
+        (1..=120).contains(&config.target_fps),
+        "target_fps",
+        "must be between 1 and 120",
+        "Use 30, or 60 on capable hardware",
+    );
+    report.check(
+        (1..=64).contains(&config.queue_capacity),
+        "queue_capacity",
+        "must be between 1 and 64",
+        format!("Use {}; longer queues add latency", FRAME_QUEUE_CAPACITY),
+    );
+    report.check(
+        config.stats_window_ms > 0,
+        "stats_window_ms",
+        "must be greater than 0",
+        format!("Use {}", DEFAULT_STATS_WINDOW_MS),
+    );
+    let quality_level = config.landmark_quality_level();
+    report.check(
+        (manager::MIN_QUALITY_LEVEL..=manager::MAX_QUALITY_LEVEL).contains(&quality_level),
+        "quality_level",
+        &format!("must be between {} and {}", manager::MIN_QUALITY_LEVEL, manager::MAX_QUALITY_LEVEL),
+        "Leave unset to use the model default",
+    );
+    report.check(
+        acceleration::is_available(config.acceleration),
+        "acceleration",
+        "must be supported on this device",
+        "Use a backend from get_available_backends",
+    );
+    report.nest("inference", config.inference.report());
+    report.nest("threading", config.threading.report());
+    report.nest("logging", config.logging.report());
+    report.nest("eye_calibration", config.eye_calibration.report());
+    report.nest("mouth_calibration", config.mouth_calibration.report());
+    report.nest("expression_calibration", config.expression_calibration.report());
+    report.nest("blink_detection", config.blink_detection.report());
+    report.nest("head_gestures", config.head_gestures.report());
+    report.nest("attention", config.attention.report());
+    report.nest("drowsiness", config.drowsiness.report());
+    report.nest("preprocessing", config.preprocessing.report());
+    report.nest("confidence_fusion", config.confidence_fusion.report());
+    report.nest("prediction", config.prediction.report());
+    report.nest("output_curves", config.output_curves.report());
+    report.nest("adaptive_quality", config.adaptive_quality.report());
+    report.nest("imu_fusion", config.imu_fusion.report());
+    if let Some(intrinsics) = &config.camera_intrinsics {
+        report.nest("camera_intrinsics", intrinsics.report());
+    }
+    if let Some(embedding) = &config.face_embedding {
+        report.nest("face_embedding", embedding.report());
+    }
+    if let Some(segmentation) = &config.segmentation {
+        report.nest("segmentation", segmentation.report());
+    }
+    report
+}
+
+/// Camera intrinsics of an undistorted camera with a horizontal field of
+/// view of `fov_degrees` producing `width` x `height` frames
+///
+/// Use this when the camera was not calibrated but its field of view is
+/// known, e.g. from the platform camera API.
+#[frb(sync)]
+pub fn estimate_intrinsics_from_fov(fov_degrees: f32, width: u32, height: u32) -> CameraIntrinsics {
+    CameraIntrinsics::from_fov(fov_degrees, width, height)
+}
+
+/// Initialize the face tracker with configuration
+#[frb(sync)]
+pub fn initialize_tracker(config: TrackerConfig) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Initializing face tracker with config: {:?}", config);
+        
+        check_config(&config).into_result()?;
+        threading::configure(&config.threading);
+        logging::configure(&config.logging)?;
+        
+        // Create the face tracker
+        let tracker = FaceTracker::new(config)?;
+        
+        // Store the tracker globally using the shared runtime
+        crate::block_on(install_tracker(tracker));
+
+        info!("Face tracker initialized successfully");
+        Ok(())
+    })
+}
+
+/// Initialize the face tracker and return a handle owning it
+///
+/// Works like [`initialize_tracker`]; disposing the handle, or letting Dart
+/// garbage-collect it, stops and releases the tracker unless it has been
+/// re-initialized since.
+#[frb(sync)]
+pub fn open_tracker(config: TrackerConfig) -> Result<TrackerHandle, PluginError> {
+    panic::guard(|| {
+        info!("Opening face tracker with config: {:?}", config);
+
+        check_config(&config).into_result()?;
+        threading::configure(&config.threading);
+        logging::configure(&config.logging)?;
+        let tracker = FaceTracker::new(config)?;
+        let generation = crate::block_on(install_tracker(tracker));
+
+        info!("Face tracker opened successfully");
+        Ok(TrackerHandle::new(generation))
+    })
+}
+
+/// Make `tracker` the global tracker, returning its generation
+async fn install_tracker(tracker: FaceTracker) -> u64 {
+    let mut global_tracker = GLOBAL_TRACKER.write().await;
+    *global_tracker = Some(tracker);
+    lifecycle::next_generation()
+}
+
+/// Process a single frame for face detection
+#[frb(sync)]
+pub fn process_frame(frame: CameraFrame) -> Result<Vec<Face>, PluginError> {
+    panic::guard(|| {
+        debug!("Processing frame: {}x{} format: {:?}", frame.width, frame.height, frame.format);
+        
+        check_frame_data(&frame)?;
+        
+        crate::block_on(async {
+            let tracker_guard = GLOBAL_TRACKER.read().await;
+            
+            match tracker_guard.as_ref() {
+                Some(tracker) => {
+                    tracker.process_frame(frame).await
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Process a single frame without blocking the calling isolate
+///
+/// The work runs on the shared worker runtime and the result is delivered
+/// to Dart as a future.
+pub async fn process_frame_async(frame: CameraFrame) -> Result<Vec<Face>, PluginError> {
+    debug!("Processing frame async: {}x{} format: {:?}", frame.width, frame.height, frame.format);
+    
+    panic::guard(|| check_frame_data(&frame))?;
+    
+    crate::runtime()
+        .spawn(panic::guard_async(async move {
+            let tracker_guard = GLOBAL_TRACKER.read().await;
+            
+            match tracker_guard.as_ref() {
+                Some(tracker) => tracker.process_frame(frame).await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        }))
+        .await
+        .map_err(panic::join_error)?
+}
+
+/// Minimum number of bytes a frame of the given format and size must carry
+///
+/// The dimensions come from the caller, so a size that does not fit in 64
+/// bits is rejected rather than wrapped.
+fn expected_frame_size(format: ImageFormat, width: u32, height: u32) -> Result<u64, PluginError> {
+    let pixels = width as u64 * height as u64;
+    let size = match format {
+        ImageFormat::RGB => pixels.checked_mul(3),
+        ImageFormat::RGBA | ImageFormat::BGRA => pixels.checked_mul(4),
+        ImageFormat::YUV420 | ImageFormat::NV21 | ImageFormat::NV12 => pixels.checked_mul(3).map(|size| size / 2),
+        ImageFormat::YUY2 => pixels.checked_mul(2),
+        ImageFormat::GRAY8 => Some(pixels),
+    };
+    size.ok_or_else(|| PluginError::ProcessingError(format!("Frame size {}x{} is too large", width, height)))
+}
+
+/// Validate frame dimensions and data size before handing it to the tracker
+pub(crate) fn check_frame_data(frame: &CameraFrame) -> Result<(), PluginError> {
+    if frame.width == 0 || frame.height == 0 {
+        return Err(PluginError::ProcessingError("Invalid frame dimensions".to_string()));
+    }
+    
+    if frame.image_data.is_empty() {
+        return Err(PluginError::ProcessingError("Empty frame data".to_string()));
+    }
+    
+    let expected_size = expected_frame_size(frame.format, frame.width, frame.height)?;
+    if (frame.image_data.len() as u64) < expected_size {
+        return Err(PluginError::ProcessingError(
+            format!("Frame data size ({}) is smaller than expected ({})", 
+                   frame.image_data.len(), expected_size)
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Process a frame made of separate Y, U and V planes (Android `YUV_420_888`)
+#[frb(sync)]
+pub fn process_planar_frame(frame: PlanarCameraFrame) -> Result<Vec<Face>, PluginError> {
+    panic::guard(|| {
+        debug!("Processing planar frame: {}x{}", frame.width, frame.height);
+        
+        check_planar_frame(&frame)?;
+        
+        crate::block_on(async {
+            let tracker_guard = GLOBAL_TRACKER.read().await;
+            
+            match tracker_guard.as_ref() {
+                Some(tracker) => tracker.process_planar_frame(frame).await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Validate plane count, strides and plane sizes of a planar frame
+fn check_planar_frame(frame: &PlanarCameraFrame) -> Result<(), PluginError> {
+    if frame.width == 0 || frame.height == 0 {
+        return Err(PluginError::ProcessingError("Invalid frame dimensions".to_string()));
+    }
+    
+    if frame.planes.len() != 3 {
+        return Err(PluginError::ProcessingError(
+            format!("Expected 3 planes, got {}", frame.planes.len())
+        ));
+    }
+    
+    let chroma = (frame.width.div_ceil(2), frame.height.div_ceil(2));
+    for (index, plane) in frame.planes.iter().enumerate() {
+        let (columns, rows) = if index == 0 { (frame.width, frame.height) } else { chroma };
+        
+        if plane.pixel_stride == 0 || (rows > 1 && (plane.row_stride as usize) < plane.required_len(columns, 1)) {
+            return Err(PluginError::ProcessingError(
+                format!("Invalid strides for plane {}", index)
+            ));
+        }
+        
+        let expected_size = plane.required_len(columns, rows);
+        if plane.data.len() < expected_size {
+            return Err(PluginError::ProcessingError(
+                format!("Plane {} data size ({}) is smaller than expected ({})",
+                       index, plane.data.len(), expected_size)
+            ));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Cut an upright, eye-aligned crop of a tracked face out of a frame
+///
+/// `face_id` is the ID of a face returned for the last processed frame and
+/// `frame` normally that same frame. Returns `size` x `size` RGB pixels, row
+/// by row, with the eyes level and at fixed positions, e.g. for thumbnails
+/// or as input to other face models.
+#[frb(sync)]
+pub fn extract_aligned_face(frame: CameraFrame, face_id: u32, size: u32) -> Result<Vec<u8>, PluginError> {
+    panic::guard(|| {
+        check_frame_data(&frame)?;
+
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.extract_aligned_face(frame, face_id, size).await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Get a small JPEG picture of a tracked face, e.g. for a face selection UI
+///
+/// The tracker keeps an upright crop of each face from a recent frame,
+/// refreshed a few times a second, so no frame has to be passed back.
+/// `max_size` (1 - 256) bounds the longer side in pixels.
+#[frb(sync)]
+pub fn get_face_thumbnail(face_id: u32, max_size: u32) -> Result<Vec<u8>, PluginError> {
+    panic::guard(|| {
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.face_thumbnail(face_id, max_size).await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Get the person matte of the most recently processed frame
+///
+/// Requires `TrackerConfig::segmentation`; `None` until a frame has been
+/// segmented. Compare `SegmentationMatte::timestamp` with the frame's to
+/// pair them. The matte is oriented like the frame as delivered by the
+/// camera.
+#[frb(sync)]
+pub fn get_segmentation_matte() -> Result<Option<SegmentationMatte>, PluginError> {
+    panic::guard(|| {
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => Ok(tracker.segmentation_matte().await),
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Label a tracked face, e.g. "streamer" or "guest", or remove its label
+/// with `None`
+///
+/// Faces carry their label in `Face::label` from the next frame on, also
+/// while briefly occluded. A label belongs to one face at a time. With
+/// `TrackerConfig::face_embedding`, a face that leaves and returns later is
+/// recognized and gets its label back.
+#[frb(sync)]
+pub fn set_face_label(face_id: u32, label: Option<String>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        if label.as_ref().is_some_and(|label| label.trim().is_empty()) {
+            return Err(PluginError::InvalidConfiguration("Face label must not be empty".to_string()));
+        }
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.set_face_label(face_id, label).await,
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Process multiple frames in batch for better performance
+#[frb(sync)]
+pub fn process_frames_batch(frames: Vec<CameraFrame>) -> Result<Vec<Vec<Face>>, PluginError> {
+    panic::guard(|| {
+        debug!("Processing batch of {} frames", frames.len());
+        
+        if frames.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        if frames.len() > 100 {
+            return Err(PluginError::ProcessingError(
+                "Batch size too large (max 100 frames)".to_string()
+            ));
+        }
+        
+        crate::block_on(async {
+            let tracker_guard = GLOBAL_TRACKER.read().await;
+            
+            match tracker_guard.as_ref() {
+                Some(tracker) => {
+                    let mut results = Vec::with_capacity(frames.len());
+                    
+                    for frame in frames {
+                        match tracker.process_frame(frame).await {
+                            Ok(faces) => results.push(faces),
+                            Err(_) => results.push(Vec::new()), // Continue processing other frames
+                        }
+                    }
+                    
+                    Ok(results)
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Start continuous face tracking with frame stream
+///
+/// Frames submitted with [`push_frame`] are processed in the background and
+/// the detected faces are delivered to the stream. If tracking already runs,
+/// the stream is added as another subscriber; use [`subscribe_face_stream`]
+/// for a rate limit or queue policy of its own.
+pub fn start_face_tracking_stream(sink: StreamSink<Vec<Face>>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting face tracking stream");
+        
+        crate::block_on(async {
+            let mut tracker_guard = GLOBAL_TRACKER.write().await;
+            
+            match tracker_guard.as_mut() {
+                Some(tracker) => {
+                    tracker.start_stream(FaceStreamSink::Faces(sink)).await
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Start continuous face tracking, delivering each frame's faces as one
+/// packed byte buffer
+///
+/// Works like [`start_face_tracking_stream`], but skips building a Dart
+/// object per field, which matters at high frame rates with several faces.
+/// The buffer layout is fixed and versioned; it carries the pose, gaze,
+/// expression, eye, mouth and blink values and the landmark positions, but
+/// not meshes, embeddings or recognized names. Packed and regular streams
+/// can subscribe to the same tracking at once.
+pub fn start_packed_face_tracking_stream(sink: StreamSink<Vec<u8>>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting packed face tracking stream");
+
+        crate::block_on(async {
+            match GLOBAL_TRACKER.write().await.as_mut() {
+                Some(tracker) => tracker.start_stream(FaceStreamSink::Packed(sink)).await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Subscribe to the tracking stream with a rate limit and queue policy of
+/// its own
+///
+/// Each subscriber, e.g. an avatar renderer and a diagnostics panel,
+/// receives the faces of the frames processed by the stream independently;
+/// one falling behind only drops its own frames. Subscribing does not start
+/// tracking; frames flow once [`start_face_tracking_stream`] has been
+/// called. The subscription ends when Dart cancels the stream or tracking
+/// stops.
+pub fn subscribe_face_stream(options: FaceSubscriberOptions, sink: StreamSink<Vec<Face>>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.subscribe(FaceStreamSink::Faces(sink), options);
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Subscribe to the tracking stream as packed buffers, with a rate limit
+/// and queue policy of its own
+///
+/// Like [`subscribe_face_stream`], with each frame's faces in the layout of
+/// [`start_packed_face_tracking_stream`].
+pub fn subscribe_packed_face_stream(options: FaceSubscriberOptions, sink: StreamSink<Vec<u8>>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.subscribe(FaceStreamSink::Packed(sink), options);
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Subscribe to tracker error events
+///
+/// An event is sent for every frame the tracker fails to process, including
+/// frames queued for the tracking stream whose failures are otherwise only
+/// logged. The most recent errors are also part of `TrackerStatus`. A new
+/// subscription replaces the previous one.
+pub fn error_event_stream(sink: StreamSink<TrackerErrorEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        events::subscribe_errors(sink);
+        Ok(())
+    })
+}
+
+/// Set the level of native log output
+///
+/// Applies to the platform console and the log stream. Module directives
+/// set with `set_module_log_filter` keep precedence for their modules.
+#[frb(sync)]
+pub fn set_log_level(level: LogLevel) -> Result<(), PluginError> {
+    panic::guard(|| logging::set_level(level))
+}
+
+/// Set per-module log levels in the `RUST_LOG` syntax
+///
+/// E.g. `flutter_openseeface_plugin::face_tracking=debug,tokio=off`.
+/// Replaces the previous directives; an empty string removes them. Fails
+/// with `InvalidConfiguration` for a malformed filter.
+#[frb(sync)]
+pub fn set_module_log_filter(filter: String) -> Result<(), PluginError> {
+    panic::guard(|| logging::set_module_filter(&filter))
+}
+
+/// Configure log file output
+///
+/// Lets the app log to a file before a tracker is initialized; the
+/// `logging` settings of the tracker configuration replace these once a
+/// tracker is initialized or its configuration updated. Fails with
+/// `InvalidConfiguration` if the file cannot be opened.
+#[frb(sync)]
+pub fn configure_logging(config: LoggingConfig) -> Result<(), PluginError> {
+    panic::guard(|| logging::configure(&config))
+}
+
+/// Subscribe to native log records
+///
+/// Every record passing the log level and module filter is forwarded, so
+/// apps can collect native logs in production builds. A new subscription
+/// replaces the previous one.
+pub fn log_stream(sink: StreamSink<LogRecord>) -> Result<(), PluginError> {
+    panic::guard(|| logging::subscribe(sink))
+}
+
+/// Start recording the time spent in each pipeline stage
+///
+/// Restarts the recording if profiling is already running.
+#[frb(sync)]
+pub fn start_profiling() -> Result<(), PluginError> {
+    panic::guard(profiling::start)
+}
+
+/// Stop profiling and return the recording as Chrome trace JSON
+///
+/// The bytes can be saved as a `.json` file and opened in Perfetto or
+/// chrome://tracing; each frame has its own track with its convert,
+/// detect, inference, embedding, landmark and publish spans. The most recent
+/// 16384 spans are kept. Fails if profiling is not running.
+#[frb(sync)]
+pub fn stop_profiling() -> Result<Vec<u8>, PluginError> {
+    panic::guard(profiling::stop)
+}
+
+/// Subscribe to face appeared and lost events
+///
+/// `FaceAppeared` is sent when a face without a remembered track is
+/// detected, `FaceLost` once a face has not been seen for longer than
+/// `TrackerConfig::track_memory_ms`, and for every face still tracked when
+/// tracking stops. A new subscription replaces the previous one.
+pub fn face_lifecycle_stream(sink: StreamSink<FaceLifecycleEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        events::subscribe_face_lifecycle(sink);
+        Ok(())
+    })
+}
+
+/// Subscribe to blink events
+///
+/// An event is sent each time a closed eye of a tracked face opens again,
+/// unless the closure was a wink, while tracking runs. A new subscription
+/// replaces the previous one.
+pub fn blink_event_stream(sink: StreamSink<BlinkEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        events::subscribe_blinks(sink);
+        Ok(())
+    })
+}
+
+/// Subscribe to wink events
+///
+/// An event is sent when one eye of a tracked face has been held closed
+/// while the other stays open (see `BlinkConfig::wink_min_ms`). Winks are
+/// not reported as blinks. A new subscription replaces the previous one.
+pub fn wink_event_stream(sink: StreamSink<WinkEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        events::subscribe_winks(sink);
+        Ok(())
+    })
+}
+
+/// Subscribe to head gesture events
+///
+/// An event is sent each time a tracked face nods, shakes or tilts its head,
+/// while tracking runs. A new subscription replaces the previous one.
+pub fn head_gesture_event_stream(sink: StreamSink<HeadGestureEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        events::subscribe_head_gestures(sink);
+        Ok(())
+    })
+}
+
+/// Subscribe to drowsiness events
+///
+/// An event is sent when the PERCLOS of a tracked face or the duration of
+/// its current eye closure reaches a higher severity (see
+/// `DrowsinessConfig`). A new subscription replaces the previous one.
+pub fn drowsiness_event_stream(sink: StreamSink<DrowsinessEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        events::subscribe_drowsiness(sink);
+        Ok(())
+    })
+}
+
+/// Queue a frame for the running tracking stream
+///
+/// Returns `false` if the frame was dropped because the queue is full. With
+/// `QueuePolicy::Block` a full queue makes this wait for the stream worker,
+/// blocking the calling isolate.
+#[frb(sync)]
+pub fn push_frame(frame: CameraFrame) -> Result<bool, PluginError> {
+    panic::guard(|| {
+        check_frame_data(&frame)?;
+        
+        crate::block_on(async {
+            // Released before queueing, which may wait under `QueuePolicy::Block`
+            let queue = match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.stream_queue()?,
+                None => return Err(PluginError::TrackerNotInitialized)
+            };
+            queue.push_frame(frame).await
+        })
+    })
+}
+
+/// Allocate the shared frame buffer used by [`push_frame_shared`]
+///
+/// Dart writes pixels directly into the returned memory, so frames do not
+/// have to be copied across the FFI boundary. Creating a new buffer replaces
+/// the previous one.
+#[frb(sync)]
+pub fn create_shared_frame_buffer(slot_count: u32, slot_size: u32) -> Result<SharedFrameBufferInfo, PluginError> {
+    panic::guard(|| {
+        info!("Creating shared frame buffer: {} slots of {} bytes", slot_count, slot_size);
+        shared_buffer::create(slot_count, slot_size)
+    })
+}
+
+/// Reserve a free slot of the shared frame buffer for writing
+///
+/// Returns `None` if every slot is still in use by the tracker.
+#[frb(sync)]
+pub fn acquire_shared_slot() -> Result<Option<u32>, PluginError> {
+    panic::guard(|| Ok(shared_buffer::active()?.acquire_slot()))
+}
+
+/// Queue the frame written into `slot_index` for the running tracking stream
+///
+/// The pixels are read in place. The slot must have been acquired with
+/// [`acquire_shared_slot`] and must not be written again until it is handed
+/// out by a later acquisition. Returns `false` if the frame was dropped.
+#[frb(sync)]
+pub fn push_frame_shared(slot_index: u32, metadata: SharedFrameMetadata) -> Result<bool, PluginError> {
+    panic::guard(|| {
+        let buffer = shared_buffer::active()?;
+        // Validate before submitting, so a rejected slot stays writable
+        check_shared_metadata(&metadata, buffer.info().slot_size)?;
+        let frame = buffer.submit(slot_index, metadata)?;
+        
+        crate::block_on(async {
+            let queue = match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.stream_queue()?,
+                None => return Err(PluginError::TrackerNotInitialized)
+            };
+            queue.push_shared_frame(frame).await
+        })
+    })
+}
+
+/// Free the shared frame buffer
+///
+/// Frames that are still queued keep the memory alive until processed.
+#[frb(sync)]
+pub fn release_shared_frame_buffer() {
+    panic::guard_or(|_| (), shared_buffer::release)
+}
+
+/// Validate shared frame metadata against the slot size
+fn check_shared_metadata(metadata: &SharedFrameMetadata, slot_size: u32) -> Result<(), PluginError> {
+    if metadata.width == 0 || metadata.height == 0 {
+        return Err(PluginError::ProcessingError("Invalid frame dimensions".to_string()));
+    }
+    
+    let expected_size = expected_frame_size(metadata.format, metadata.width, metadata.height)?;
+    if expected_size > slot_size as u64 {
+        return Err(PluginError::ProcessingError(
+            format!("Frame size ({}) exceeds shared slot size ({})", expected_size, slot_size)
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Stop face tracking
+#[frb(sync)]
+pub fn stop_tracking() -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Stopping face tracking");
+        
+        crate::block_on(async {
+            let mut global_tracker = GLOBAL_TRACKER.write().await;
+            
+            if let Some(tracker) = global_tracker.as_mut() {
+                tracker.stop().await?;
+            }
+            
+            *global_tracker = None;
+            lifecycle::next_generation();
+            Ok::<(), PluginError>(())
+        })?;
+
+        info!("Face tracking stopped");
+        Ok(())
+    })
+}
+
+/// Get current tracker status
+#[frb(sync)]
+pub fn get_tracker_status() -> TrackerStatus {
+    panic::guard_or(
+        |e| TrackerStatus { last_error: Some(e.to_string()), ..uninitialized_status() },
+        || crate::block_on(current_status()),
+    )
+}
+
+/// Latest tracker errors with the stage and frame they occurred on
+///
+/// Returns up to `limit` entries, oldest first (0 = all kept entries). The
+/// tracker keeps the last 256 errors; the history is cleared when the
+/// tracker is re-initialized.
+#[frb(sync)]
+pub fn get_error_history(limit: u32) -> Result<Vec<ErrorHistoryEntry>, PluginError> {
+    panic::guard(|| {
+        let limit = match limit {
+            0 => usize::MAX,
+            limit => limit as usize,
+        };
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => Ok(tracker.error_history(limit)),
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Subscribe to tracker status snapshots
+///
+/// A snapshot is sent every `interval_ms` milliseconds from a worker on the
+/// tracker runtime, including while no tracker is initialized. Prefer this
+/// over polling `get_tracker_status`. A new subscription replaces the
+/// previous one.
+pub fn status_stream(sink: StreamSink<TrackerStatus>, interval_ms: u32) -> Result<(), PluginError> {
+    panic::guard(|| {
+        if interval_ms == 0 {
+            return Err(PluginError::InvalidConfiguration(
+                "Status interval must be greater than 0".to_string()
+            ));
+        }
+        events::subscribe_status(sink, Duration::from_millis(interval_ms as u64));
+        Ok(())
+    })
+}
+
+/// Status of the global tracker, or an uninitialized status if there is none
+pub(crate) async fn current_status() -> TrackerStatus {
+    let tracker_guard = GLOBAL_TRACKER.read().await;
+
+    match tracker_guard.as_ref() {
+        Some(tracker) => tracker.get_status().await,
+        None => uninitialized_status(),
+    }
+}
+
+/// Status reported while no tracker is initialized
+fn uninitialized_status() -> TrackerStatus {
+    TrackerStatus {
+        is_initialized: false,
+        is_running: false,
+        frames_processed: 0,
+        average_fps: 0.0,
+        last_error: None,
+        recent_errors: Vec::new(),
+        acceleration_backend: AccelerationBackend::CPU,
+        active_model: None,
+    }
+}
+
+/// Get detailed tracking statistics
+#[frb(sync)]
+pub fn get_tracking_stats() -> TrackingStats {
+    panic::guard_or(|_| TrackingStats::default(), || crate::block_on(current_stats()))
+}
+
+/// Subscribe to tracking statistics snapshots
+///
+/// A snapshot is sent every `interval_ms` milliseconds, with the rolling
+/// window statistics in `TrackingStats::window`. A new subscription replaces
+/// the previous one.
+pub fn stats_stream(sink: StreamSink<TrackingStats>, interval_ms: u32) -> Result<(), PluginError> {
+    panic::guard(|| {
+        if interval_ms == 0 {
+            return Err(PluginError::InvalidConfiguration(
+                "Stats interval must be greater than 0".to_string()
+            ));
+        }
+        events::subscribe_stats(sink, Duration::from_millis(interval_ms as u64));
+        Ok(())
+    })
+}
+
+/// Clear the cumulative and windowed tracking statistics
+#[frb(sync)]
+pub fn reset_stats() -> Result<(), PluginError> {
+    panic::guard(|| {
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.reset_stats().await;
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Report the device's thermal state and battery to the tracker
+///
+/// With `TrackerConfig::adaptive_quality` enabled, the tracker lowers the
+/// detection resolution, frame rate and per-face extras while the device
+/// is hot or low on battery, and restores them step by step once it
+/// recovers. Call this whenever the platform reports a change. Returns the
+/// quality step now in effect.
+#[frb(sync)]
+pub fn report_device_state(thermal: ThermalState, battery: BatteryState) -> Result<QualityStep, PluginError> {
+    panic::guard(|| {
+        if !(0.0..=1.0).contains(&battery.level) {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Battery level must be between 0.0 and 1.0, got {}",
+                battery.level
+            )));
+        }
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => Ok(tracker.report_device_state(thermal, battery)),
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Feed a gyroscope and accelerometer reading to the tracker
+///
+/// With `TrackerConfig::imu_fusion` enabled, the rotation measured between
+/// frames stabilizes the head pose during fast motion and stands in for it
+/// during short visual dropouts. Push readings as they arrive (100 Hz or
+/// more), timestamped on the clock of the camera frames.
+#[frb(sync)]
+pub fn push_imu_sample(sample: ImuSample) -> Result<(), PluginError> {
+    panic::guard(|| {
+        sample.validate()?;
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.push_imu_sample(sample);
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })
+    })
+}
+
+/// Statistics of the global tracker, or empty statistics if there is none
+pub(crate) async fn current_stats() -> TrackingStats {
+    match GLOBAL_TRACKER.read().await.as_ref() {
+        Some(tracker) => tracker.get_stats().await,
+        None => TrackingStats::default(),
+    }
+}
+
+/// Update tracker configuration (requires re-initialization)
+#[frb(sync)]
+pub fn update_tracker_config(new_config: TrackerConfig) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Updating tracker configuration: {:?}", new_config);
+        
+        // Stop current tracker
+        stop_tracking()?;
+        
+        // Initialize with new config
+        initialize_tracker(new_config)?;
+        
+        info!("Tracker configuration updated successfully");
+        Ok(())
+    })
+}
+
+/// Serialize the running tracker's configuration to JSON
+///
+/// The JSON includes calibration changed since initialization, so passing it
+/// to `initialize_tracker_from_json` restores the current setup.
+#[frb(sync)]
+pub fn export_config_json() -> Result<String, PluginError> {
+    panic::guard(|| {
+        let config = crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => Ok(tracker.current_config().await),
+                None => Err(PluginError::TrackerNotInitialized),
+            }
+        })?;
+        serde_json::to_string_pretty(&config)
+            .map_err(|e| PluginError::ProcessingError(format!("Failed to serialize configuration: {}", e)))
+    })
+}
+
+/// Initialize the face tracker with a configuration exported as JSON
+///
+/// Fields missing from the JSON use their default values.
+#[frb(sync)]
+pub fn initialize_tracker_from_json(json: String) -> Result<(), PluginError> {
+    panic::guard(|| {
+        let config: TrackerConfig = serde_json::from_str(&json)
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Invalid configuration JSON: {}", e)))?;
+        initialize_tracker(config)
+    })
+}
+
+/// Change only some fields of the tracker configuration
+///
+/// The fields set in `update` are merged into the running tracker's
+/// configuration, including calibration changed since initialization, and
+/// the tracker is re-initialized with the result. Without a running tracker
+/// the update is applied to the default configuration.
+#[frb(sync)]
+pub fn apply_tracker_config_update(update: TrackerConfigUpdate) -> Result<(), PluginError> {
+    panic::guard(|| {
+        let mut config = crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.current_config().await,
+                None => TrackerConfig::default(),
+            }
+        });
+        update.apply_to(&mut config);
+        update_tracker_config(config)
+    })
+}
+
+/// Calibrate eye openness for the current user
+///
+/// Record `left_eye_ratio`/`right_eye_ratio` from [`EyeState`] once with the
+/// eyes closed and once fully open, and pass them as the ratio range. Takes
+/// effect from the next frame.
+#[frb(sync)]
+pub fn set_eye_calibration(calibration: EyeCalibration) -> Result<(), PluginError> {
+    panic::guard(|| {
+        calibration.validate()?;
+        
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.set_eye_calibration(calibration).await;
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Calibrate the mouth shape values for the current user
+///
+/// Record `width_ratio`/`open_ratio` from [`MouthState`] with a relaxed,
+/// stretched, puckered and fully opened mouth and pass them as the ranges.
+/// Takes effect from the next frame.
+#[frb(sync)]
+pub fn set_mouth_calibration(calibration: MouthCalibration) -> Result<(), PluginError> {
+    panic::guard(|| {
+        calibration.validate()?;
+        
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.set_mouth_calibration(calibration).await;
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Start recording the range of one expression
+///
+/// Have the user move the expression between its neutral and strongest
+/// form, then call [`end_range_calibration`]. Replaces a running capture.
+#[frb(sync)]
+pub fn begin_range_calibration(feature: ExpressionFeature) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting range calibration of {:?}", feature);
+        
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.begin_range_calibration(feature).await;
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Finish the range capture and return the recorded range
+///
+/// Values of the captured expression are stretched from this range onto
+/// 0.0 - 1.0 from the next frame on.
+#[frb(sync)]
+pub fn end_range_calibration() -> Result<ValueRange, PluginError> {
+    panic::guard(|| {
+        let (feature, range) = crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.end_range_calibration().await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })?;
+        
+        info!("Calibrated {:?} range to {:.2} - {:.2}", feature, range.min, range.max);
+        Ok(range)
+    })
+}
+
+/// Persist calibration profiles in `dir` (e.g. the app support directory)
+///
+/// Profiles already stored there become available, and profiles created
+/// before are written to it.
+#[frb(sync)]
+pub fn set_calibration_profile_directory(dir: String) -> Result<(), PluginError> {
+    panic::guard(|| profiles::set_profile_dir(PathBuf::from(dir)))
+}
+
+/// Save the running tracker's calibration as a named profile
+///
+/// Replaces an existing profile of the same name.
+#[frb(sync)]
+pub fn create_calibration_profile(name: String) -> Result<CalibrationProfile, PluginError> {
+    panic::guard(|| {
+        let profile = crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => Ok(tracker.calibration_profile(name).await),
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })?;
+        
+        profiles::save(profile.clone())?;
+        info!("Saved calibration profile {}", profile.name);
+        Ok(profile)
+    })
+}
+
+/// Apply a saved calibration profile to the running tracker
+#[frb(sync)]
+pub fn apply_calibration_profile(name: String) -> Result<(), PluginError> {
+    panic::guard(|| {
+        let profile = profiles::get(&name)?;
+        
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    tracker.apply_calibration_profile(&profile).await;
+                    Ok(())
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })?;
+        
+        info!("Applied calibration profile {}", name);
+        Ok(())
+    })
+}
+
+/// Names of all calibration profiles, sorted
+#[frb(sync)]
+pub fn list_calibration_profiles() -> Vec<String> {
+    panic::guard_or(|_| Vec::new(), profiles::names)
+}
+
+/// Delete a calibration profile, returning whether it existed
+#[frb(sync)]
+pub fn delete_calibration_profile(name: String) -> Result<bool, PluginError> {
+    panic::guard(|| profiles::delete(&name))
+}
+
+/// Serialize a calibration profile for storage by the app
+#[frb(sync)]
+pub fn export_calibration_profile(name: String) -> Result<Vec<u8>, PluginError> {
+    panic::guard(|| profiles::get(&name)?.to_bytes())
+}
+
+/// Add a calibration profile exported with [`export_calibration_profile`]
+#[frb(sync)]
+pub fn import_calibration_profile(data: Vec<u8>) -> Result<CalibrationProfile, PluginError> {
+    panic::guard(|| {
+        let profile = CalibrationProfile::from_bytes(&data)?;
+        profiles::save(profile.clone())?;
+        Ok(profile)
+    })
+}
+
+/// Start gaze-to-screen calibration
+///
+/// Discards the current calibration. Afterwards, have the user look at
+/// points on the screen and call [`add_calibration_point`] for each.
+#[frb(sync)]
+pub fn start_gaze_calibration() -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting gaze calibration");
+        
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.start_gaze_calibration().await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Record the screen point the user is looking at, in normalized
+/// coordinates (0.0 - 1.0)
+///
+/// The point is paired with the gaze of the most recently processed frame.
+/// Returns `true` once enough points (at least three, not all on one line)
+/// are recorded; from then on `EyeGaze.screen_gaze` is reported. More points
+/// improve the fit.
+#[frb(sync)]
+pub fn add_calibration_point(screen_x: f32, screen_y: f32) -> Result<bool, PluginError> {
+    panic::guard(|| {
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.add_gaze_calibration_point(screen_x, screen_y).await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Get the triangulation of the dense face meshes
+///
+/// The topology never changes, so it only needs to be fetched once; faces
+/// then carry just the vertex positions when `enable_face_mesh` is set.
+#[frb(sync)]
+pub fn get_face_mesh_topology() -> FaceMeshTopology {
+    panic::guard_or(
+        |_| FaceMeshTopology { vertex_count: 0, triangles: Vec::new(), uvs: Vec::new() },
+        mesh::topology,
+    )
+}
+
+/// Get the indices of a facial region's points in a landmark layout
+///
+/// With `LandmarkModel::Ibug68` the indices refer to `FacialLandmarks::points`,
+/// with `LandmarkModel::FaceMesh` to `FaceMesh::vertices`, so apps can pick
+/// out e.g. the eyes without hard-coding the layouts.
+#[frb(sync)]
+pub fn get_landmark_indices(region: LandmarkRegion, model: LandmarkModel) -> Vec<u32> {
+    panic::guard_or(|_| Vec::new(), || match model {
+        LandmarkModel::Ibug68 => region.landmark_range().map(|i| i as u32).collect(),
+        LandmarkModel::FaceMesh => mesh::region_indices(region),
+    })
+}
+
+/// Persist the registered people in `dir` (e.g. the app support directory)
+///
+/// People already stored there become available, and people registered
+/// afterwards are saved there.
+#[frb(sync)]
+pub fn set_person_database_directory(dir: String) -> Result<(), PluginError> {
+    panic::guard(|| recognition::set_database_dir(PathBuf::from(dir)))
+}
+
+/// Register a person from frames showing their face, so tracked faces that
+/// match get `Face::recognized_name`
+///
+/// The most confident face of each frame is used; a few frames with
+/// slightly different head poses give the most reliable recognition.
+/// Requires `TrackerConfig::face_embedding`. Replaces an existing person of
+/// the same name. Returns the number of frames a face was found in.
+#[frb(sync)]
+pub fn register_person(name: String, frames: Vec<CameraFrame>) -> Result<u32, PluginError> {
+    panic::guard(|| {
+        for frame in &frames {
+            check_frame_data(frame)?;
+        }
+
+        let embeddings = crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    let mut embeddings = Vec::with_capacity(frames.len());
+                    for frame in frames {
+                        embeddings.extend(tracker.face_embedding(frame).await?);
+                    }
+                    Ok(embeddings)
+                }
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })?;
+
+        let count = embeddings.len() as u32;
+        recognition::register(&name, embeddings)?;
+        info!("Registered {} from {} frames", name, count);
+        Ok(count)
+    })
+}
+
+/// Remove a registered person, returning whether they were registered
+#[frb(sync)]
+pub fn unregister_person(name: String) -> Result<bool, PluginError> {
+    panic::guard(|| recognition::unregister(&name))
+}
+
+/// Names of all registered people, sorted
+#[frb(sync)]
+pub fn list_registered_people() -> Vec<String> {
+    panic::guard_or(|_| Vec::new(), recognition::names)
+}
+
+/// Check if tracker supports a specific feature
+#[frb(sync)]
+pub fn is_feature_supported(feature: TrackerFeature) -> bool {
+    panic::guard_or(|_| false, || match feature {
+        TrackerFeature::FaceDetection => true,
+        TrackerFeature::LandmarkDetection => true,
+        TrackerFeature::PoseEstimation => true,
+        TrackerFeature::GazeTracking => true, // openseeface-rs supports this
+        TrackerFeature::ExpressionDetection => true,
+        TrackerFeature::AgeEstimation => false,
+        TrackerFeature::GenderDetection => false,
+        TrackerFeature::EmotionDetection => false,
+    })
+}
+
+/// Get the acceleration backends supported on this device
+///
+/// Only backends the inference stack can initialize are listed. The list
+/// always ends with `AccelerationBackend::CPU`, which every other backend
+/// falls back to if its delegate fails to initialize.
+#[frb(sync)]
+pub fn get_available_backends() -> Vec<AccelerationBackend> {
+    panic::guard_or(|_| vec![AccelerationBackend::CPU], acceleration::available_backends)
+}
+
+/// Detect faces in a JPEG or PNG file, e.g. a gallery photo
+///
+/// The EXIF orientation is applied, so coordinates refer to the photo as
+/// displayed. Each photo is analyzed on its own: faces get expressions,
+/// quality and (if enabled) meshes and names, but no stable IDs, smoothing
+/// or blink state, and live tracking is not affected.
+#[frb(sync)]
+pub fn process_image_file(path: String) -> Result<Vec<Face>, PluginError> {
+    panic::guard(|| {
+        let bytes = std::fs::read(&path)
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Cannot read {}: {}", path, e)))?;
+        process_image_bytes(bytes)
+    })
+}
+
+/// Detect faces in an encoded JPEG or PNG image
+///
+/// See [`process_image_file`].
+#[frb(sync)]
+pub fn process_image_bytes(bytes: Vec<u8>) -> Result<Vec<Face>, PluginError> {
+    panic::guard(|| {
+        let image = still::decode(&bytes)?;
+        debug!("Processing still image: {}x{}", image.width(), image.height());
+
+        crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.process_still(image).await,
+                None => Err(PluginError::TrackerNotInitialized)
+            }
+        })
+    })
+}
+
+/// Feed frames from an IP camera into the running tracking stream
+///
+/// `url` is an `http(s)://` MJPEG stream or an `rtsp://` stream; RTSP is
+/// decoded with the `ffmpeg` and `ffprobe` tools (desktop only). Faces
+/// arrive on the tracking stream as for pushed frames. The returned stream
+/// reports `Connected` on the first frame and ends with `Ended` or `Failed`.
+/// Closing it or starting another live source stops this one.
+pub fn start_network_source(url: String, sink: StreamSink<FrameSourceEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting network source {}", url);
+        network::start(url, move |event| sink.add(event).is_ok())
+    })
+}
+
+/// Stop the running live frame source (network stream, frame receiver or
+/// native camera), returning whether one was running
+#[frb(sync)]
+pub fn stop_frame_source() -> bool {
+    panic::guard_or(|_| false, input::stop_source)
+}
+
+/// Capture frames from a camera natively and feed them into the running
+/// tracking stream
+///
+/// Frames do not cross the Dart bridge, so the app must not push frames of
+/// its own meanwhile. Requires the `camera` feature (Linux only so far). The
+/// returned stream reports `Connected` on the first frame and ends with
+/// `Ended` or `Failed`. Stop it with [`stop_frame_source`].
+pub fn start_camera_capture(config: CameraCaptureConfig, sink: StreamSink<FrameSourceEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting native capture from camera '{}'", config.device_id);
+        let camera = camera::open(&config)?;
+        input::start_source(async move { Ok(camera) }, move |event| sink.add(event).is_ok());
+        Ok(())
+    })
+}
+
+/// Open the stream that reports the results of every tracked source
+///
+/// Sources are added with [`add_tracking_source`]; each update names the
+/// source it belongs to. Opening the stream again replaces the previous one.
+pub fn open_multi_source_stream(sink: StreamSink<SourceUpdate>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        multi::set_output(move |update| sink.add(update).is_ok());
+        Ok(())
+    })
+}
+
+/// Track an additional frame source with a tracker of its own
+///
+/// Results arrive on the multi-source stream, which must be open, tagged
+/// with `source_id`. Sources do not affect the main tracker or the network
+/// outputs. Each tracker loads its own models.
+pub fn add_tracking_source(source_id: String, source: SourceSpec, config: TrackerConfig) -> Result<(), PluginError> {
+    panic::guard(|| {
+        check_config(&config).into_result()?;
+        multi::add(source_id, source, config)
+    })
+}
+
+/// Stop tracking a source, returning whether it was running
+#[frb(sync)]
+pub fn remove_tracking_source(source_id: String) -> bool {
+    panic::guard_or(|_| false, || multi::remove(&source_id))
+}
+
+/// IDs of the running tracked sources
+#[frb(sync)]
+pub fn list_tracking_sources() -> Vec<String> {
+    panic::guard_or(|_| Vec::new(), multi::source_ids)
+}
+
+/// Stop every tracked source and close the multi-source stream, returning
+/// the number of sources that were running
+#[frb(sync)]
+pub fn close_multi_source_stream() -> u32 {
+    panic::guard_or(|_| 0, || multi::close() as u32)
+}
+
+/// Receive frames sent by another device's [`start_frame_sender`] and feed
+/// them into the running tracking stream
+///
+/// This turns a phone into the camera of a desktop tracker. The returned
+/// stream reports `Connected` on the first frame; the receiver keeps waiting
+/// while no sender is connected. Stop it with [`stop_frame_source`].
+pub fn start_frame_receiver(config: FrameReceiverConfig, sink: StreamSink<FrameSourceEvent>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        let receiver = FrameReceiver::bind(config)?;
+        input::start_source(async move { Ok(Box::new(receiver) as Box<dyn FrameSource>) }, move |event| {
+            sink.add(event).is_ok()
+        });
+        Ok(())
+    })
+}
+
+/// Start sending camera frames to a frame receiver on another device
+///
+/// Frames are passed with [`send_frame_to_receiver`]. Replaces any previous
+/// sender.
+pub fn start_frame_sender(config: FrameSenderConfig) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Sending frames over {:?} to {}:{}", config.transport, config.host, config.port);
+        remote::start_sender(&config)
+    })
+}
+
+/// Compress a camera frame and send it to the frame receiver, returning the
+/// compressed size in bytes
+pub fn send_frame_to_receiver(frame: CameraFrame) -> Result<u32, PluginError> {
+    panic::guard(|| {
+        check_frame_data(&frame)?;
+        remote::send_frame(&frame).map(|bytes| bytes as u32)
+    })
+}
+
+/// Stop sending frames, returning whether a sender was running
+#[frb(sync)]
+pub fn stop_frame_sender() -> bool {
+    panic::guard_or(|_| false, remote::stop_sender)
+}
+
+/// Track faces in every frame of a video file with a tracker of its own
+///
+/// Decoding uses the `ffmpeg` and `ffprobe` tools, which must be installed
+/// (desktop only). The stream starts with the video properties, then
+/// reports the faces of each frame with the progress, and ends with a
+/// `Finished` or `Failed` update. Closing the stream stops processing. The
+/// running tracker and the network outputs are not affected.
+pub fn process_video_file(
+    path: String,
+    config: TrackerConfig,
+    sink: StreamSink<VideoProcessingUpdate>,
+) -> Result<(), PluginError> {
+    panic::guard(|| {
+        check_config(&config).into_result()?;
+        info!("Processing video file {}", path);
+
+        crate::runtime().spawn(async move {
+            let send = |update| sink.add(update).is_ok();
+            let result = panic::guard_async(video::process(PathBuf::from(&path), config, send)).await;
+
+            let update = match result {
+                Ok(frames_processed) => VideoProcessingUpdate::Finished { frames_processed },
+                Err(e) => {
+                    error!("Processing video {} failed: {}", path, e);
+                    VideoProcessingUpdate::Failed { message: e.to_string() }
+                }
+            };
+            let _ = sink.add(update);
+        });
+
+        Ok(())
+    })
+}
+
+/// Download a model set into `target_dir` (e.g. the app support directory)
+///
+/// Progress is reported on the stream, which ends with a `Finished` or
+/// `Failed` event. Files already present are skipped. Trackers initialized
+/// after the download load their models from `target_dir`.
+pub fn download_models(
+    model_set: ModelSet,
+    target_dir: String,
+    sink: StreamSink<ModelDownloadEvent>,
+) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Downloading {:?} models to {}", model_set, target_dir);
+        
+        crate::runtime().spawn(async move {
+            let dir = PathBuf::from(&target_dir);
+            let result = panic::guard_async(manager::download(model_set, &dir, |event| sink.add(event).is_ok())).await;
+            
+            let event = match result {
+                Ok(()) => ModelDownloadEvent::Finished { model_dir: target_dir },
+                Err(e) => {
+                    error!("Model download failed: {}", e);
+                    ModelDownloadEvent::Failed { message: e.to_string() }
+                }
+            };
+            let _ = sink.add(event);
+        });
+        
+        Ok(())
+    })
+}
+
+/// Check whether every file of a model set is present in `dir`
+#[frb(sync)]
+pub fn is_model_set_installed(model_set: ModelSet, dir: String) -> bool {
+    panic::guard_or(|_| false, || manager::is_installed(model_set, Path::new(&dir)))
+}
+
+/// Load models from a directory filled by an earlier [`download_models`]
+///
+/// Takes effect for trackers initialized afterwards.
+#[frb(sync)]
+pub fn set_model_directory(dir: String) -> Result<(), PluginError> {
+    panic::guard(|| {
+        let path = PathBuf::from(&dir);
+        if !path.is_dir() {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Model directory {} does not exist",
+                dir
+            )));
+        }
+        
+        manager::set_model_dir(path);
+        Ok(())
+    })
+}
+
+/// Hand model files over from memory (e.g. Flutter assets)
+///
+/// Trackers initialized afterwards use these models unless
+/// `TrackerConfig::model_path` is set. Files listed in the built-in checksum
+/// manifest are verified first.
+#[frb(sync)]
+pub fn load_models_from_bytes(blobs: Vec<ModelBlob>) -> Result<(), PluginError> {
+    panic::guard(|| manager::load_blobs(blobs))
+}
+
+/// Compare model variants on sample frames
+///
+/// Each variant processes every frame; the first variant is the reference
+/// for the reported landmark deviation. Uses the configuration of the
+/// initialized tracker, or the default configuration.
+pub fn compare_model_variants(
+    frame_samples: Vec<CameraFrame>,
+    variants: Vec<ModelVariant>,
+) -> Result<Vec<ModelVariantReport>, PluginError> {
+    panic::guard(|| {
+        for frame in &frame_samples {
+            check_frame_data(frame)?;
+        }
+        
+        crate::block_on(async {
+            let base = match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => tracker.config().clone(),
+                None => TrackerConfig::default(),
+            };
+            
+            comparison::compare_variants(&base, &frame_samples, &variants).await
+        })
+    })
+}
+
+/// Measure pipeline performance with synthetic frames
+///
+/// Runs a private tracker with `config` for `duration_ms` and reports
+/// per-stage timings, peak memory and the achieved frame rate. The global
+/// tracker and the network outputs are not affected.
+pub fn run_benchmark(config: TrackerConfig, duration_ms: u32) -> Result<BenchmarkResult, PluginError> {
+    panic::guard(|| {
+        info!("Running {}ms benchmark with config: {:?}", duration_ms, config);
+        crate::block_on(benchmark::run(config, duration_ms))
+    })
+}
+
+/// Get the available cameras
+///
+/// The list is enumerated on first use and cached; see
+/// [`refresh_available_cameras`]. Cameras are listed where native capture is
+/// available (the `camera` feature on Linux); elsewhere the list is empty and
+/// the app lists cameras with its camera plugin.
+#[frb(sync)]
+pub fn get_available_cameras() -> Result<Vec<CameraDevice>, PluginError> {
+    panic::guard(|| Ok(camera::cameras()))
+}
+
+/// Enumerate the cameras again, e.g. after one was plugged in
+#[frb(sync)]
+pub fn refresh_available_cameras() -> Result<Vec<CameraDevice>, PluginError> {
+    panic::guard(|| Ok(camera::refresh()))
+}
+
+/// Stream the camera list now and whenever a camera is plugged in or
+/// removed
+///
+/// Closing the stream or calling [`stop_watching_cameras`] stops watching.
+pub fn watch_available_cameras(sink: StreamSink<Vec<CameraDevice>>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        camera::watch(move |cameras| sink.add(cameras).is_ok());
+        Ok(())
+    })
+}
+
+/// Stop watching for camera changes, returning whether a watcher was running
+#[frb(sync)]
+pub fn stop_watching_cameras() -> bool {
+    panic::guard_or(|_| false, camera::stop_watching)
+}
+
+/// Validate camera frame format and dimensions
+#[frb(sync)]
+pub fn validate_frame(frame: CameraFrame) -> Result<bool, PluginError> {
+    panic::guard(|| {
+        if frame.width == 0 || frame.height == 0 {
+            return Ok(false);
+        }
+        
+        if frame.image_data.is_empty() {
+            return Ok(false);
+        }
+        
+        let expected_size = expected_frame_size(frame.format, frame.width, frame.height);
+        Ok(expected_size.is_ok_and(|size| frame.image_data.len() as u64 >= size))
+    })
+}
+
+/// Get recommended configuration for device performance
+///
+/// The first call runs a short probe benchmark (about 1.5 s) to choose the
+/// model quality, detection resolution, face count and frame rate; later
+/// calls reuse the measurement.
+pub fn get_recommended_config() -> TrackerConfig {
+    // Balanced settings, also used if the device cannot be measured
+    let base = TrackerConfig {
+        model_type: ModelType::RetinaFace,
+        confidence_threshold: 0.8,
+        max_faces: 2, // Conservative for performance
+        enable_landmarks: true,
+        enable_pose_estimation: true,
+        enable_gaze_tracking: false, // Disable for better performance
+        target_fps: 30,
+        ..TrackerConfig::default()
+    };
+    
+    let fallback = base.clone();
+    panic::guard_or(move |_| fallback, move || crate::block_on(benchmark::recommended_config(base)))
+}
+
+/// Reset tracker state and clear all cached data
+#[frb(sync)]
+pub fn reset_tracker() -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Resetting tracker state");
+        
+        crate::block_on(async {
+            let mut global_tracker = GLOBAL_TRACKER.write().await;
+            
+            if let Some(tracker) = global_tracker.as_mut() {
+                tracker.stop().await?;
+            }
+            
+            *global_tracker = None;
+            lifecycle::next_generation();
+            Ok::<(), PluginError>(())
+        })?;
+        
+        info!("Tracker state reset successfully");
+        Ok(())
+    })
 }
 
-impl Default for TrackerConfig {
-    fn default() -> Self {
-        Self {
-            model_type: ModelType::RetinaFace,
-            confidence_threshold: 0.8,
-            max_faces: 4,
-            enable_landmarks: true,
-            enable_pose_estimation: true,
-            enable_gaze_tracking: false,
-            target_fps: 30,
-        }
-    }
+/// Dispose of resources and cleanup
+///
+/// Stops the tracker and everything else started from Dart: streams, frame
+/// sources, network outputs, event streams and the shared frame buffer.
+/// Call it when the isolate using the tracker shuts down; after a hot
+/// restart this happens automatically when the library is initialized.
+#[frb(sync)]
+pub fn dispose() -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Disposing face tracker resources");
+        lifecycle::shutdown();
+        Ok(())
+    })
 }
 
-/// Initialize the face tracker with configuration
+/// Draw bounding boxes, landmarks, pose axes and gaze rays of `faces` onto
+/// `frame`
+///
+/// Returns RGBA pixels of the frame size, ready for display. Pose axes and
+/// gaze rays follow the running tracker's `mirror_input`.
 #[frb(sync)]
-pub fn initialize_tracker(config: TrackerConfig) -> Result<(), PluginError> {
-    info!("Initializing face tracker with config: {:?}", config);
-    
-    // Validate configuration
-    if config.confidence_threshold < 0.0 || config.confidence_threshold > 1.0 {
-        return Err(PluginError::InvalidConfiguration(
-            "Confidence threshold must be between 0.0 and 1.0".to_string()
-        ));
-    }
-    
-    if config.max_faces == 0 {
-        return Err(PluginError::InvalidConfiguration(
-            "Max faces must be greater than 0".to_string()
-        ));
-    }
-    
-    if config.target_fps == 0 || config.target_fps > 120 {
-        return Err(PluginError::InvalidConfiguration(
-            "Target FPS must be between 1 and 120".to_string()
-        ));
-    }
-    
-    // Create the face tracker
-    let tracker = FaceTracker::new(config)?;
-    
-    // Store the tracker globally using tokio runtime
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| PluginError::ThreadingError(e.to_string()))?;
-    
-    rt.block_on(async {
-        let mut global_tracker = GLOBAL_TRACKER.write().await;
-        *global_tracker = Some(tracker);
-    });
-
-    info!("Face tracker initialized successfully");
-    Ok(())
+pub fn render_debug_overlay(frame: CameraFrame, faces: Vec<Face>, options: OverlayOptions) -> Result<Vec<u8>, PluginError> {
+    panic::guard(|| {
+        check_frame_data(&frame)?;
+        let mirrored = crate::block_on(async {
+            GLOBAL_TRACKER.read().await.as_ref().is_some_and(|tracker| tracker.config().mirror_input)
+        });
+        overlay::render(&frame, &faces, &options, mirrored)
+    })
 }
 
-/// Process a single frame for face detection
+/// Anonymize `frame` for recording or streaming: blur or remove the
+/// background behind the primary person and pixelate all other `faces`
+///
+/// Returns RGBA pixels of the frame size; removed background is
+/// transparent. Pass the faces tracked in this frame: if the tracker
+/// segmented the frame (see `TrackerConfig::segmentation`), its person
+/// matte separates the background, otherwise the person is estimated from
+/// the primary face's bounding box.
 #[frb(sync)]
-pub fn process_frame(frame: CameraFrame) -> Result<Vec<Face>, PluginError> {
-    debug!("Processing frame: {}x{} format: {:?}", frame.width, frame.height, frame.format);
-    
-    // Validate frame data
-    if frame.width == 0 || frame.height == 0 {
-        return Err(PluginError::ProcessingError("Invalid frame dimensions".to_string()));
-    }
-    
-    if frame.image_data.is_empty() {
-        return Err(PluginError::ProcessingError("Empty frame data".to_string()));
-    }
-    
-    // Check expected data size based on format
-    let expected_size = match frame.format {
-        ImageFormat::RGB => (frame.width * frame.height * 3) as usize,
-        ImageFormat::RGBA | ImageFormat::BGRA => (frame.width * frame.height * 4) as usize,
-        ImageFormat::YUV420 | ImageFormat::NV21 => ((frame.width * frame.height * 3) / 2) as usize,
-    };
-    
-    if frame.image_data.len() < expected_size {
-        return Err(PluginError::ProcessingError(
-            format!("Frame data size ({}) is smaller than expected ({})", 
-                   frame.image_data.len(), expected_size)
-        ));
-    }
-    
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| PluginError::ThreadingError(e.to_string()))?;
-        
-    rt.block_on(async {
-        let tracker_guard = GLOBAL_TRACKER.read().await;
-        
-        match tracker_guard.as_ref() {
-            Some(tracker) => {
-                tracker.process_frame(frame).await
+pub fn render_privacy_frame(frame: CameraFrame, faces: Vec<Face>, options: PrivacyOptions) -> Result<Vec<u8>, PluginError> {
+    panic::guard(|| {
+        check_frame_data(&frame)?;
+        let (mirrored, matte) = crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => (tracker.config().mirror_input, tracker.segmentation_matte().await),
+                None => (false, None),
             }
-            None => Err(PluginError::TrackerNotInitialized)
-        }
+        });
+        let matte = matte.filter(|matte| matte.timestamp == frame.timestamp);
+        privacy::render(&frame, &faces, &options, mirrored, matte.as_ref())
     })
 }
 
-/// Process multiple frames in batch for better performance
+/// Replace the real background of `frame` with `background`, keeping the
+/// person in front of it
+///
+/// Uses the tracker's person matte of this frame if it was just processed,
+/// otherwise segments the frame on the spot; requires
+/// `TrackerConfig::segmentation`. Returns RGBA pixels of the frame size.
+/// The background is scaled to fill the frame and shown upright.
 #[frb(sync)]
-pub fn process_frames_batch(frames: Vec<CameraFrame>) -> Result<Vec<Vec<Face>>, PluginError> {
-    debug!("Processing batch of {} frames", frames.len());
-    
-    if frames.is_empty() {
-        return Ok(Vec::new());
-    }
-    
-    if frames.len() > 100 {
-        return Err(PluginError::ProcessingError(
-            "Batch size too large (max 100 frames)".to_string()
-        ));
-    }
-    
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| PluginError::ThreadingError(e.to_string()))?;
-        
-    rt.block_on(async {
-        let tracker_guard = GLOBAL_TRACKER.read().await;
-        
-        match tracker_guard.as_ref() {
-            Some(tracker) => {
-                let mut results = Vec::with_capacity(frames.len());
-                
-                for frame in frames {
-                    match tracker.process_frame(frame).await {
-                        Ok(faces) => results.push(faces),
-                        Err(_) => results.push(Vec::new()), // Continue processing other frames
-                    }
+pub fn composite_background(frame: CameraFrame, background: BackgroundImage) -> Result<Vec<u8>, PluginError> {
+    panic::guard(|| {
+        check_frame_data(&frame)?;
+        background.validate()?;
+        let (mirrored, matte) = crate::block_on(async {
+            match GLOBAL_TRACKER.read().await.as_ref() {
+                Some(tracker) => {
+                    let matte = match tracker.segmentation_matte().await {
+                        Some(matte) if matte.timestamp == frame.timestamp => matte,
+                        _ => tracker.segment_frame(frame.clone()).await?,
+                    };
+                    Ok((tracker.config().mirror_input, matte))
                 }
-                
-                Ok(results)
+                None => Err(PluginError::TrackerNotInitialized),
             }
-            None => Err(PluginError::TrackerNotInitialized)
-        }
+        })?;
+        compositing::composite(&frame, &matte, &background, mirrored)
     })
 }
 
-/// Start continuous face tracking with frame stream
-#[frb(stream)]
-pub async fn start_face_tracking_stream() -> Result<impl flutter_rust_bridge::StreamSink<Vec<Face>>, PluginError> {
-    info!("Starting face tracking stream");
-    
-    let tracker_guard = GLOBAL_TRACKER.read().await;
-    
-    match tracker_guard.as_ref() {
-        Some(tracker) => {
-            tracker.start_stream().await
+/// Start sending tracking results to a VMC protocol receiver
+#[frb(sync)]
+pub fn start_vmc_output(config: VmcConfig) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting VMC output to {}:{}", config.host, config.port);
+        
+        let sender = VmcSender::new(config)?;
+        protocols::register_output(vmc::OUTPUT_NAME, Box::new(sender));
+        Ok(())
+    })
+}
+
+/// Stop the VMC protocol output
+#[frb(sync)]
+pub fn stop_vmc_output() -> Result<(), PluginError> {
+    panic::guard(|| {
+        if protocols::remove_output(vmc::OUTPUT_NAME) {
+            info!("VMC output stopped");
         }
-        None => Err(PluginError::TrackerNotInitialized)
-    }
+        Ok(())
+    })
 }
 
-/// Stop face tracking
+/// Convert a face's head pose and gaze into humanoid bone rotations
+///
+/// Returns Neck and Head rotations if the face has a pose and eye rotations
+/// if it has gaze, ready for VRM renderers. Expects faces returned with the
+/// default `output_conventions`.
 #[frb(sync)]
-pub fn stop_tracking() -> Result<(), PluginError> {
-    info!("Stopping face tracking");
-    
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| PluginError::ThreadingError(e.to_string()))?;
-        
-    rt.block_on(async {
-        let mut global_tracker = GLOBAL_TRACKER.write().await;
+pub fn get_humanoid_bone_rotations(face: Face, config: HumanoidConfig) -> Result<Vec<BoneRotation>, PluginError> {
+    panic::guard(|| {
+        config.check()?;
+        Ok(humanoid::bone_rotations(&face, &config))
+    })
+}
+
+/// Compute the 52 Perfect Sync blendshapes of a face
+///
+/// Blendshapes of groups switched off in `config` are left out.
+#[frb(sync)]
+pub fn get_perfect_sync_blendshapes(face: Face, config: PerfectSyncConfig) -> Result<Vec<BlendshapeValue>, PluginError> {
+    panic::guard(|| {
+        config.check()?;
+        Ok(perfect_sync::blendshapes(&face, &config)
+            .into_iter()
+            .map(|(name, value)| BlendshapeValue { name: name.to_string(), value })
+            .collect())
+    })
+}
+
+/// Calibrate the Perfect Sync profile on faces captured while the user
+/// holds a relaxed, neutral expression
+///
+/// Returns `config` with ranges that map the neutral face to 0.0; pass it to
+/// `get_perfect_sync_blendshapes` or `VmcConfig::perfect_sync`.
+#[frb(sync)]
+pub fn calibrate_perfect_sync_neutral(faces: Vec<Face>, config: PerfectSyncConfig) -> Result<PerfectSyncConfig, PluginError> {
+    panic::guard(|| {
+        config.check()?;
+        perfect_sync::capture_neutral(&faces, &config)
+    })
+}
+
+/// Start sending OpenSeeFace UDP packets (VSeeFace compatible)
+#[frb(sync)]
+pub fn start_osf_output(config: OsfOutputConfig) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting OpenSeeFace output to {}:{}", config.host, config.port);
         
-        if let Some(tracker) = global_tracker.as_mut() {
-            tracker.stop().await?;
+        let sender = OsfSender::new(config)?;
+        protocols::register_output(osf::OUTPUT_NAME, Box::new(sender));
+        Ok(())
+    })
+}
+
+/// Stop the OpenSeeFace packet output
+#[frb(sync)]
+pub fn stop_osf_output() -> Result<(), PluginError> {
+    panic::guard(|| {
+        if protocols::remove_output(osf::OUTPUT_NAME) {
+            info!("OpenSeeFace output stopped");
         }
+        Ok(())
+    })
+}
+
+/// Start sending iFacialMocap / MeowFace compatible packets
+#[frb(sync)]
+pub fn start_ifacialmocap_output(config: IFacialMocapConfig) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting iFacialMocap output to {}:{}", config.host, config.port);
         
-        *global_tracker = None;
-    });
+        let sender = IFacialMocapSender::new(config)?;
+        protocols::register_output(ifacialmocap::OUTPUT_NAME, Box::new(sender));
+        Ok(())
+    })
+}
 
-    info!("Face tracking stopped");
-    Ok(())
+/// Stop the iFacialMocap output
+#[frb(sync)]
+pub fn stop_ifacialmocap_output() -> Result<(), PluginError> {
+    panic::guard(|| {
+        if protocols::remove_output(ifacialmocap::OUTPUT_NAME) {
+            info!("iFacialMocap output stopped");
+        }
+        Ok(())
+    })
 }
 
-/// Get current tracker status
+/// Connect to the VTube Studio plugin API and start injecting tracking parameters
+///
+/// Pass a previously returned `auth_token` to skip the approval prompt in
+/// VTube Studio. Returns the token to persist for the next connection.
+pub async fn connect_vtube_studio(
+    url: String,
+    plugin_name: String,
+    auth_token: Option<String>,
+) -> Result<String, PluginError> {
+    info!("Connecting to VTube Studio at {} as {}", url, plugin_name);
+    
+    crate::runtime()
+        .spawn(panic::guard_async(async move {
+            let (output, token) = vtube_studio::connect(&url, &plugin_name, auth_token).await?;
+            protocols::register_output(vtube_studio::OUTPUT_NAME, Box::new(output));
+            Ok(token)
+        }))
+        .await
+        .map_err(panic::join_error)?
+}
+
+/// Disconnect from VTube Studio
 #[frb(sync)]
-pub fn get_tracker_status() -> TrackerStatus {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(async {
-        let tracker_guard = GLOBAL_TRACKER.read().await;
-        
-        match tracker_guard.as_ref() {
-            Some(tracker) => tracker.get_status().await,
-            None => TrackerStatus {
-                is_initialized: false,
-                is_running: false,
-                frames_processed: 0,
-                average_fps: 0.0,
-                last_error: None,
-            }
+pub fn disconnect_vtube_studio() -> Result<(), PluginError> {
+    panic::guard(|| {
+        if protocols::remove_output(vtube_studio::OUTPUT_NAME) {
+            info!("VTube Studio output disconnected");
         }
+        Ok(())
     })
 }
 
-/// Get detailed tracking statistics
+/// Set how tracking values map onto VTube Studio parameters
 #[frb(sync)]
-pub fn get_tracking_stats() -> TrackingStats {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(async {
-        let tracker_guard = GLOBAL_TRACKER.read().await;
-        
-        match tracker_guard.as_ref() {
-            Some(tracker) => {
-                // Get stats from the tracker
-                let stats_guard = tracker.stats.read().await;
-                stats_guard.clone()
-            }
-            None => TrackingStats {
-                total_faces_detected: 0,
-                active_faces: 0,
-                average_confidence: 0.0,
-                processing_times: ProcessingTimes {
-                    detection_ms: 0.0,
-                    landmark_ms: 0.0,
-                    pose_ms: 0.0,
-                    total_ms: 0.0,
-                },
-            }
+pub fn set_vtube_studio_parameter_mapping(mapping: Vec<VtsParameterMapping>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        if mapping.iter().any(|m| m.parameter_id.is_empty()) {
+            return Err(PluginError::InvalidConfiguration(
+                "VTube Studio parameter IDs must not be empty".to_string()
+            ));
         }
+        
+        vtube_studio::set_parameter_mapping(mapping);
+        Ok(())
     })
 }
 
-/// Update tracker configuration (requires re-initialization)
+/// Get the active VTube Studio parameter mapping
 #[frb(sync)]
-pub fn update_tracker_config(new_config: TrackerConfig) -> Result<(), PluginError> {
-    info!("Updating tracker configuration: {:?}", new_config);
-    
-    // Stop current tracker
-    stop_tracking()?;
-    
-    // Initialize with new config
-    initialize_tracker(new_config)?;
-    
-    info!("Tracker configuration updated successfully");
-    Ok(())
+pub fn get_vtube_studio_parameter_mapping() -> Vec<VtsParameterMapping> {
+    panic::guard_or(|_| Vec::new(), vtube_studio::parameter_mapping)
 }
 
-/// Check if tracker supports a specific feature
+/// Serve tracking results to local WebSocket clients on `port`
+///
+/// Clients connect to `ws://127.0.0.1:<port>` and receive every processed
+/// frame as JSON, or MessagePack if they ask for it; see
+/// [`crate::protocols::websocket`] for the message and subscription format.
+/// Stops any previously started server.
 #[frb(sync)]
-pub fn is_feature_supported(feature: TrackerFeature) -> bool {
-    match feature {
-        TrackerFeature::FaceDetection => true,
-        TrackerFeature::LandmarkDetection => true,
-        TrackerFeature::PoseEstimation => true,
-        TrackerFeature::GazeTracking => true, // openseeface-rs supports this
-        TrackerFeature::ExpressionDetection => false, // Not implemented yet
-        TrackerFeature::AgeEstimation => false,
-        TrackerFeature::GenderDetection => false,
-        TrackerFeature::EmotionDetection => false,
-    }
+pub fn start_websocket_server(port: u16) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Starting WebSocket server on port {}", port);
+
+        protocols::remove_output(websocket::OUTPUT_NAME);
+        let server = WebSocketServer::bind(port)?;
+        protocols::register_output(websocket::OUTPUT_NAME, Box::new(server));
+        Ok(())
+    })
 }
 
-/// Get available camera devices (platform-specific)
+/// Stop the WebSocket server, disconnecting its clients
 #[frb(sync)]
-pub fn get_available_cameras() -> Result<Vec<CameraDevice>, PluginError> {
-    // This would typically use platform-specific camera APIs
-    // For now, return a placeholder
-    Ok(vec![
-        CameraDevice {
-            id: "0".to_string(),
-            name: "Default Camera".to_string(),
-            is_front_facing: false,
-            supported_resolutions: vec![
-                Resolution { width: 640, height: 480 },
-                Resolution { width: 1280, height: 720 },
-                Resolution { width: 1920, height: 1080 },
-            ],
-        }
-    ])
+pub fn stop_websocket_server() -> Result<(), PluginError> {
+    panic::guard(|| {
+        protocols::remove_output(websocket::OUTPUT_NAME);
+        Ok(())
+    })
 }
 
-/// Validate camera frame format and dimensions
+/// Record the faces of every processed frame to a session file at `path`
+///
+/// Replaces the file if it exists and stops any previous recording. The
+/// session can be played back with [`replay_session`].
 #[frb(sync)]
-pub fn validate_frame(frame: CameraFrame) -> Result<bool, PluginError> {
-    if frame.width == 0 || frame.height == 0 {
-        return Ok(false);
-    }
-    
-    if frame.image_data.is_empty() {
-        return Ok(false);
-    }
-    
-    // Check expected data size based on format
-    let expected_size = match frame.format {
-        ImageFormat::RGB => (frame.width * frame.height * 3) as usize,
-        ImageFormat::RGBA | ImageFormat::BGRA => (frame.width * frame.height * 4) as usize,
-        ImageFormat::YUV420 | ImageFormat::NV21 => ((frame.width * frame.height * 3) / 2) as usize,
-    };
-    
-    Ok(frame.image_data.len() >= expected_size)
+pub fn start_session_recording(path: String) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Recording session to {}", path);
+
+        let recorder = SessionRecorder::create(Path::new(&path))?;
+        protocols::register_output(recorder::OUTPUT_NAME, Box::new(recorder));
+        Ok(())
+    })
 }
 
-/// Get recommended configuration for device performance
+/// Stop recording the session, returning whether a recording was active
 #[frb(sync)]
-pub fn get_recommended_config() -> TrackerConfig {
-    // This could be enhanced to detect device capabilities
-    // For now, return a balanced configuration
-    TrackerConfig {
-        model_type: ModelType::RetinaFace,
-        confidence_threshold: 0.8,
-        max_faces: 2, // Conservative for performance
-        enable_landmarks: true,
-        enable_pose_estimation: true,
-        enable_gaze_tracking: false, // Disable for better performance
-        target_fps: 30,
-    }
+pub fn stop_session_recording() -> Result<bool, PluginError> {
+    panic::guard(|| Ok(protocols::remove_output(recorder::OUTPUT_NAME)))
 }
 
-/// Reset tracker state and clear all cached data
+/// Play a recorded session back through the output streams
+///
+/// Frames are sent to the active network outputs and to the returned
+/// stream with their original timing, divided by `speed` (e.g. 2.0 plays
+/// twice as fast). Faces on the stream follow the running tracker's
+/// `output_conventions`; no tracker is needed otherwise. Starting another
+/// replay stops this one.
+pub fn replay_session(path: String, speed: f32, sink: StreamSink<Vec<Face>>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        let frames = session::load(Path::new(&path))?;
+        info!("Replaying {} frames from {} at {}x speed", frames.len(), path, speed);
+
+        let conventions = crate::block_on(async {
+            GLOBAL_TRACKER
+                .read()
+                .await
+                .as_ref()
+                .map_or_else(OutputConventions::default, |tracker| tracker.config().output_conventions)
+        });
+        replay::start(frames, speed, conventions, move |faces| sink.add(faces).is_ok())
+    })
+}
+
+/// Stop the running session replay, returning whether one was running
 #[frb(sync)]
-pub fn reset_tracker() -> Result<(), PluginError> {
-    info!("Resetting tracker state");
-    
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| PluginError::ThreadingError(e.to_string()))?;
-        
-    rt.block_on(async {
-        let mut global_tracker = GLOBAL_TRACKER.write().await;
-        
-        if let Some(tracker) = global_tracker.as_mut() {
-            tracker.stop().await?;
-        }
-        
-        *global_tracker = None;
-    });
-    
-    info!("Tracker state reset successfully");
-    Ok(())
+pub fn stop_session_replay() -> bool {
+    panic::guard_or(|_| false, replay::stop)
 }
 
-/// Dispose of resources and cleanup
+/// Stream tracked faces at a fixed rate, interpolated between frames
+///
+/// Faces from live tracking and session replays are emitted at
+/// `config.output_fps` regardless of the camera frame rate, with pose
+/// rotations slerped and other values blended, running `config.delay_ms`
+/// behind the newest frame. Faces follow the running tracker's
+/// `output_conventions`. Starting another resampled stream stops this one.
+pub fn start_resampled_stream(config: ResamplerConfig, sink: StreamSink<Vec<Face>>) -> Result<(), PluginError> {
+    panic::guard(|| {
+        info!("Resampling tracked faces to {} fps", config.output_fps);
+
+        let conventions = crate::block_on(async {
+            GLOBAL_TRACKER
+                .read()
+                .await
+                .as_ref()
+                .map_or_else(OutputConventions::default, |tracker| tracker.config().output_conventions)
+        });
+        resampler::start(config, conventions, move |faces| sink.add(faces).is_ok())
+    })
+}
+
+/// Stop the resampled stream, returning whether one was running
 #[frb(sync)]
-pub fn dispose() -> Result<(), PluginError> {
-    info!("Disposing face tracker resources");
-    reset_tracker()
+pub fn stop_resampled_stream() -> bool {
+    panic::guard_or(|_| false, resampler::stop)
+}
+
+/// Convert the recorded session at `path` for use in other tools, writing
+/// it to `output_path`
+///
+/// CSV has one row per face per frame with the pose, key landmarks and
+/// expression values; JSON Lines has one complete face per line. Returns
+/// the number of faces written.
+#[frb(sync)]
+pub fn export_session(path: String, output_path: String, format: SessionExportFormat) -> Result<u64, PluginError> {
+    panic::guard(|| {
+        let frames = session::load(Path::new(&path))?;
+        let file = std::fs::File::create(&output_path)
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Cannot create {}: {}", output_path, e)))?;
+
+        let rows = export::export(&frames, format, &mut std::io::BufWriter::new(file))?;
+        info!("Exported {} faces from {} to {}", rows, path, output_path);
+        Ok(rows)
+    })
+}
+
+/// Convert the head pose of a face in the recorded session at `path` into a
+/// BVH animation at `output_path`, e.g. for Blender or Maya
+///
+/// Returns the number of animation frames written.
+#[frb(sync)]
+pub fn export_session_bvh(path: String, output_path: String, options: BvhExportOptions) -> Result<u32, PluginError> {
+    panic::guard(|| {
+        let frames = session::load(Path::new(&path))?;
+        let file = std::fs::File::create(&output_path)
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Cannot create {}: {}", output_path, e)))?;
+
+        let count = bvh::export(&frames, &options, &mut std::io::BufWriter::new(file))?;
+        info!("Exported {} BVH frames from {} to {}", count, path, output_path);
+        Ok(count)
+    })
 }
 
 /// Get version information
 #[frb(sync)]
 pub fn get_version_info() -> VersionInfo {
-    VersionInfo {
-        plugin_version: env!("CARGO_PKG_VERSION").to_string(),
-        openseeface_version: "0.1.0".to_string(), // Would get this from openseeface-rs
-        flutter_bridge_version: "2.0".to_string(),
-        build_date: env!("BUILD_DATE").unwrap_or("unknown").to_string(),
-        commit_hash: env!("GIT_HASH").unwrap_or("unknown").to_string(),
-    }
+    panic::guard_or(
+        |_| VersionInfo {
+            plugin_version: env!("CARGO_PKG_VERSION").to_string(),
+            openseeface_version: "unknown".to_string(),
+            flutter_bridge_version: "unknown".to_string(),
+            build_date: "unknown".to_string(),
+            commit_hash: "unknown".to_string(),
+            color_conversion_backend: "unknown".to_string(),
+        },
+        || VersionInfo {
+            plugin_version: env!("CARGO_PKG_VERSION").to_string(),
+            openseeface_version: "0.1.0".to_string(), // Would get this from openseeface-rs
+            flutter_bridge_version: "2.0".to_string(),
+            build_date: option_env!("BUILD_DATE").unwrap_or("unknown").to_string(),
+            commit_hash: option_env!("GIT_HASH").unwrap_or("unknown").to_string(),
+            color_conversion_backend: crate::face_tracking::color::BACKEND.to_string(),
+        },
+    )
 }
 
 /// Warm up the tracker (load models, etc.)
 #[frb(sync)]
 pub fn warmup_tracker() -> Result<(), PluginError> {
-    info!("Warming up tracker");
-    
-    // Create a dummy frame to warm up the tracker
-    let dummy_frame = CameraFrame {
-        image_data: vec![128u8; 640 * 480 * 3], // Gray 640x480 RGB image
-        width: 640,
-        height: 480,
-        format: ImageFormat::RGB,
-        rotation: 0,
-        timestamp: chrono::Utc::now().timestamp_millis(),
-    };
-    
-    // Process the dummy frame to load models
-    match process_frame(dummy_frame) {
-        Ok(_) => {
-            info!("Tracker warmed up successfully");
-            Ok(())
-        }
-        Err(e) => {
-            error!("Failed to warm up tracker: {}", e);
-            Err(e)
+    panic::guard(|| {
+        info!("Warming up tracker");
+        
+        // Create a dummy frame to warm up the tracker
+        let dummy_frame = CameraFrame {
+            image_data: vec![128u8; 640 * 480 * 3], // Gray 640x480 RGB image
+            width: 640,
+            height: 480,
+            format: ImageFormat::RGB,
+            rotation: 0,
+            intrinsics: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        };
+        
+        // Process the dummy frame to load models
+        match process_frame(dummy_frame) {
+            Ok(_) => {
+                info!("Tracker warmed up successfully");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to warm up tracker: {}", e);
+                Err(e)
+            }
         }
-    }
+    })
 }
 
 // Additional helper types for the API
@@ -426,24 +2680,6 @@ pub enum TrackerFeature {
     EmotionDetection,
 }
 
-/// Camera device information
-#[frb(dart_metadata=("freezed", "immutable"))]
-#[derive(Debug, Clone)]
-pub struct CameraDevice {
-    pub id: String,
-    pub name: String,
-    pub is_front_facing: bool,
-    pub supported_resolutions: Vec<Resolution>,
-}
-
-/// Resolution information
-#[frb(dart_metadata=("freezed", "immutable"))]
-#[derive(Debug, Clone, Copy)]
-pub struct Resolution {
-    pub width: u32,
-    pub height: u32,
-}
-
 /// Version information
 #[frb(dart_metadata=("freezed", "immutable"))]
 #[derive(Debug, Clone)]
@@ -453,6 +2689,8 @@ pub struct VersionInfo {
     pub flutter_bridge_version: String,
     pub build_date: String,
     pub commit_hash: String,
+    /// YUV conversion backend ("portable" or "yuv")
+    pub color_conversion_backend: String,
 }
 
 #[cfg(test)]
@@ -469,6 +2707,71 @@ mod tests {
         assert!(config.enable_pose_estimation);
         assert!(!config.enable_gaze_tracking);
         assert_eq!(config.target_fps, 30);
+        assert!(config.smoothing.enabled);
+    }
+
+    #[test]
+    fn test_config_update_changes_only_set_fields() {
+        let mut config = TrackerConfig::default();
+        TrackerConfigUpdate {
+            confidence_threshold: Some(0.5),
+            quality_level: Some(1),
+            ..TrackerConfigUpdate::default()
+        }
+        .apply_to(&mut config);
+
+        assert_eq!(config.confidence_threshold, 0.5);
+        assert_eq!(config.quality_level, Some(1));
+        assert_eq!(config.max_faces, TrackerConfig::default().max_faces);
+        assert_eq!(config.smoothing, SmoothingConfig::default());
+    }
+
+    #[test]
+    fn test_presets_are_valid() {
+        for preset in [Preset::LowPower, Preset::Balanced, Preset::Quality, Preset::Studio] {
+            let config = TrackerConfig::from_preset(preset);
+            let quality_level = config.landmark_quality_level();
+            assert!((manager::MIN_QUALITY_LEVEL..=manager::MAX_QUALITY_LEVEL).contains(&quality_level));
+            assert!(config.target_fps > 0 && config.target_fps <= 120);
+            assert!(config.detection_interval >= 1);
+        }
+        assert!(TrackerConfig::from_preset(Preset::LowPower).target_fps < TrackerConfig::from_preset(Preset::Studio).target_fps);
+    }
+
+    #[test]
+    fn test_config_json_round_trip() {
+        let config = TrackerConfig::from_preset(Preset::Studio);
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: TrackerConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+        // Settings saved by older versions lack newer fields
+        let partial: TrackerConfig = serde_json::from_str(r#"{"max_faces": 1, "target_fps": 24}"#).unwrap();
+        assert_eq!((partial.max_faces, partial.target_fps), (1, 24));
+        assert_eq!(partial.track_memory_ms, DEFAULT_TRACK_MEMORY_MS);
+
+        assert!(matches!(
+            initialize_tracker_from_json("{not json".to_string()),
+            Err(PluginError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_config_reports_every_violation() {
+        assert!(validate_config(TrackerConfig::default()).is_valid());
+
+        let config = TrackerConfig {
+            max_faces: 0,
+            target_fps: 500,
+            blink_detection: BlinkConfig { close_threshold: 0.9, ..BlinkConfig::default() },
+            ..TrackerConfig::default()
+        };
+        let fields: Vec<_> = validate_config(config)
+            .violations
+            .into_iter()
+            .map(|v| v.field)
+            .collect();
+        assert_eq!(fields, vec!["max_faces", "target_fps", "blink_detection.close_threshold"]);
     }
 
     #[test]
@@ -484,6 +2787,7 @@ mod tests {
         assert!(is_feature_supported(TrackerFeature::FaceDetection));
         assert!(is_feature_supported(TrackerFeature::LandmarkDetection));
         assert!(is_feature_supported(TrackerFeature::PoseEstimation));
+        assert!(is_feature_supported(TrackerFeature::ExpressionDetection));
         assert!(!is_feature_supported(TrackerFeature::EmotionDetection));
     }
 
@@ -495,6 +2799,7 @@ mod tests {
             height: 480,
             format: ImageFormat::RGB,
             rotation: 0,
+            intrinsics: None,
             timestamp: 0,
         };
         
@@ -506,14 +2811,48 @@ mod tests {
             height: 480,
             format: ImageFormat::RGB,
             rotation: 0,
+            intrinsics: None,
             timestamp: 0,
         };
         
         assert!(!validate_frame(invalid_frame).unwrap());
     }
 
-    #[tokio::test]
-    async fn test_tracker_lifecycle() {
+    #[test]
+    fn test_process_frame_async_rejects_invalid_frame() {
+        let frame = CameraFrame {
+            image_data: Vec::new(),
+            width: 640,
+            height: 480,
+            format: ImageFormat::RGB,
+            rotation: 0,
+            intrinsics: None,
+            timestamp: 0,
+        };
+        
+        let result = crate::block_on(process_frame_async(frame));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_shared_metadata_rejects_overflowing_size() {
+        // 65536 * 65536 * 4 wraps to 0 in 32 bits
+        let metadata = SharedFrameMetadata {
+            width: 65536,
+            height: 65536,
+            format: ImageFormat::RGBA,
+            timestamp: 0,
+            rotation: 0,
+            intrinsics: None,
+        };
+        assert!(matches!(check_shared_metadata(&metadata, 1024), Err(PluginError::ProcessingError(_))));
+
+        let metadata = SharedFrameMetadata { width: 16, height: 16, ..metadata };
+        assert!(check_shared_metadata(&metadata, 1024).is_ok());
+    }
+
+    #[test]
+    fn test_tracker_lifecycle() {
         let config = TrackerConfig::default();
         
         // Test initialization
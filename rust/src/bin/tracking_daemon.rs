@@ -0,0 +1,41 @@
+//! Standalone tracking daemon serving the gRPC tracking service
+//!
+//! Usage: `tracking-daemon [address] [config.json]`
+//!
+//! Listens on 127.0.0.1:50051 by default. The optional configuration file
+//! uses the JSON format of `export_config_json`; missing fields use their
+//! default values.
+
+use flutter_openseeface_plugin::api::TrackerConfig;
+use flutter_openseeface_plugin::error::PluginError;
+use flutter_openseeface_plugin::grpc;
+use std::net::SocketAddr;
+
+fn main() {
+    env_logger::init();
+    if let Err(e) = run() {
+        log::error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), PluginError> {
+    let mut args = std::env::args().skip(1);
+    let address: SocketAddr = args
+        .next()
+        .as_deref()
+        .unwrap_or(grpc::DEFAULT_ADDRESS)
+        .parse()
+        .map_err(|e| PluginError::InvalidConfiguration(format!("Invalid listen address: {}", e)))?;
+    let config = match args.next() {
+        Some(path) => {
+            let json = std::fs::read_to_string(&path)
+                .map_err(|e| PluginError::InvalidConfiguration(format!("Cannot read {}: {}", path, e)))?;
+            serde_json::from_str(&json)
+                .map_err(|e| PluginError::InvalidConfiguration(format!("Invalid configuration JSON: {}", e)))?
+        }
+        None => TrackerConfig::default(),
+    };
+
+    flutter_openseeface_plugin::create_runtime().block_on(grpc::serve(address, config))
+}
@@ -0,0 +1,8 @@
+//! User calibration
+//!
+//! Per-user calibration of the landmark-derived values lives with the
+//! processing stages (see [`crate::face_tracking::expressions`]); this module
+//! records calibration from live tracking and stores and restores it.
+
+pub mod profiles;
+pub mod range;
@@ -0,0 +1,233 @@
+//! Named calibration profiles
+//!
+//! A profile stores one user's calibration under a name so it can be applied
+//! again later, e.g. when several people share a device. Profiles are kept in
+//! memory and, once a profile directory is registered, persisted there as one
+//! JSON file per profile. Apps that manage their own storage can export and
+//! import profiles as bytes instead.
+
+use crate::error::PluginError;
+use crate::face_tracking::expressions::{EyeCalibration, ExpressionCalibration, MouthCalibration};
+use flutter_rust_bridge::frb;
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File extension of persisted profiles
+pub const PROFILE_EXTENSION: &str = "json";
+
+lazy_static! {
+    static ref PROFILES: RwLock<ProfileStore> = RwLock::new(ProfileStore::default());
+}
+
+/// A user's calibration stored under a name
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationProfile {
+    /// Profile name, also used as the file name
+    pub name: String,
+    /// Eye openness calibration
+    pub eyes: EyeCalibration,
+    /// Mouth shape calibration
+    pub mouth: MouthCalibration,
+    /// Expression value ranges
+    #[serde(default)]
+    pub expressions: ExpressionCalibration,
+}
+
+impl CalibrationProfile {
+    /// Check the name and the calibration values
+    pub fn validate(&self) -> Result<(), PluginError> {
+        if self.name.is_empty() || self.name.contains(['/', '\\']) || self.name.starts_with('.') {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Invalid calibration profile name '{}'",
+                self.name
+            )));
+        }
+        self.eyes.validate()?;
+        self.mouth.validate()?;
+        self.expressions.validate()
+    }
+
+    /// Serialize the profile to JSON bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PluginError> {
+        serde_json::to_vec_pretty(self)
+            .map_err(|e| PluginError::ProcessingError(format!("Failed to serialize profile: {}", e)))
+    }
+
+    /// Parse and validate a profile from JSON bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PluginError> {
+        let profile: Self = serde_json::from_slice(bytes)
+            .map_err(|e| PluginError::CalibrationError(format!("Invalid calibration profile: {}", e)))?;
+        profile.validate()?;
+        Ok(profile)
+    }
+}
+
+/// Profiles by name, optionally backed by a directory
+#[derive(Debug, Default)]
+pub struct ProfileStore {
+    dir: Option<PathBuf>,
+    profiles: BTreeMap<String, CalibrationProfile>,
+}
+
+impl ProfileStore {
+    /// Persist profiles in `dir` and load the profiles already stored there
+    ///
+    /// Profiles in memory are written to the directory too. Files that cannot
+    /// be read as a profile are skipped.
+    pub fn open_dir(&mut self, dir: PathBuf) -> Result<(), PluginError> {
+        let entries = std::fs::read_dir(&dir).map_err(|e| {
+            PluginError::InvalidConfiguration(format!("Cannot read profile directory {}: {}", dir.display(), e))
+        })?;
+
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.extension().and_then(|e| e.to_str()) != Some(PROFILE_EXTENSION) {
+                continue;
+            }
+            let profile = std::fs::read(&path)
+                .map_err(|e| PluginError::ProcessingError(e.to_string()))
+                .and_then(|bytes| CalibrationProfile::from_bytes(&bytes));
+            match profile {
+                Ok(profile) => {
+                    self.profiles.insert(profile.name.clone(), profile);
+                }
+                Err(e) => warn!("Skipping calibration profile {}: {}", path.display(), e),
+            }
+        }
+
+        for profile in self.profiles.values() {
+            write_profile(&dir, profile)?;
+        }
+        self.dir = Some(dir);
+        Ok(())
+    }
+
+    /// Add or replace a profile
+    pub fn save(&mut self, profile: CalibrationProfile) -> Result<(), PluginError> {
+        profile.validate()?;
+        if let Some(dir) = &self.dir {
+            write_profile(dir, &profile)?;
+        }
+        self.profiles.insert(profile.name.clone(), profile);
+        Ok(())
+    }
+
+    /// Look up a profile by name
+    pub fn get(&self, name: &str) -> Option<CalibrationProfile> {
+        self.profiles.get(name).cloned()
+    }
+
+    /// Names of all profiles, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    /// Remove a profile, returning whether it existed
+    pub fn delete(&mut self, name: &str) -> Result<bool, PluginError> {
+        if self.profiles.remove(name).is_none() {
+            return Ok(false);
+        }
+        if let Some(dir) = &self.dir {
+            let path = profile_path(dir, name);
+            std::fs::remove_file(&path).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(PluginError::ProcessingError(format!("Failed to delete {}: {}", path.display(), e))),
+            })?;
+        }
+        Ok(true)
+    }
+}
+
+/// Persist profiles in `dir` from now on, loading the profiles stored there
+pub fn set_profile_dir(dir: PathBuf) -> Result<(), PluginError> {
+    PROFILES.write().unwrap().open_dir(dir)
+}
+
+/// Add or replace a profile
+pub fn save(profile: CalibrationProfile) -> Result<(), PluginError> {
+    PROFILES.write().unwrap().save(profile)
+}
+
+/// Look up a profile by name
+pub fn get(name: &str) -> Result<CalibrationProfile, PluginError> {
+    PROFILES.read().unwrap().get(name).ok_or_else(|| unknown_profile(name))
+}
+
+/// Names of all profiles, sorted
+pub fn names() -> Vec<String> {
+    PROFILES.read().unwrap().names()
+}
+
+/// Remove a profile, returning whether it existed
+pub fn delete(name: &str) -> Result<bool, PluginError> {
+    PROFILES.write().unwrap().delete(name)
+}
+
+fn profile_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.{}", name, PROFILE_EXTENSION))
+}
+
+fn write_profile(dir: &Path, profile: &CalibrationProfile) -> Result<(), PluginError> {
+    let path = profile_path(dir, &profile.name);
+    std::fs::write(&path, profile.to_bytes()?)
+        .map_err(|e| PluginError::ProcessingError(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+fn unknown_profile(name: &str) -> PluginError {
+    PluginError::CalibrationError(format!("Unknown calibration profile '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> CalibrationProfile {
+        CalibrationProfile {
+            name: name.to_string(),
+            eyes: EyeCalibration { closed_ratio: 0.12, open_ratio: 0.28 },
+            mouth: MouthCalibration::default(),
+            expressions: ExpressionCalibration::default(),
+        }
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let original = profile("streamer");
+        let restored = CalibrationProfile::from_bytes(&original.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored, original);
+
+        assert!(CalibrationProfile::from_bytes(b"{}").is_err());
+
+        // Profiles saved before expression ranges existed still load
+        let mut legacy: serde_json::Value = serde_json::from_slice(&original.to_bytes().unwrap()).unwrap();
+        legacy.as_object_mut().unwrap().remove("expressions");
+        let restored = CalibrationProfile::from_bytes(legacy.to_string().as_bytes()).unwrap();
+        assert_eq!(restored.expressions, ExpressionCalibration::default());
+        assert!(profile("../escape").validate().is_err());
+    }
+
+    #[test]
+    fn test_profiles_persist_in_directory() {
+        let dir = std::env::temp_dir().join(format!("osf_profiles_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut store = ProfileStore::default();
+        store.save(profile("guest")).unwrap();
+        store.open_dir(dir.clone()).unwrap();
+        store.save(profile("streamer")).unwrap();
+        assert!(store.delete("guest").unwrap());
+        assert!(!store.delete("guest").unwrap());
+
+        // A fresh store sees what the first one left on disk
+        let mut reopened = ProfileStore::default();
+        reopened.open_dir(dir.clone()).unwrap();
+        assert_eq!(reopened.names(), vec!["streamer".to_string()]);
+        assert_eq!(reopened.get("streamer"), Some(profile("streamer")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
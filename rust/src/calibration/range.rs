@@ -0,0 +1,99 @@
+//! Expression range capture
+//!
+//! While a capture runs, the user moves one expression through its full
+//! range (e.g. from a neutral face to their widest smile). The lowest and
+//! highest raw values seen become that expression's [`ValueRange`], so later
+//! output uses the full 0.0 - 1.0 range even for subtle expressions.
+
+use crate::error::PluginError;
+use crate::face_tracking::expressions::{feature_value, ExpressionFeature, ValueRange};
+use crate::models::*;
+
+/// Smallest raw value span accepted as a calibrated range
+pub const MIN_RANGE_SPAN: f32 = 0.05;
+
+/// An ongoing min/max capture of one expression
+#[derive(Debug)]
+pub struct RangeCapture {
+    feature: ExpressionFeature,
+    range: Option<ValueRange>,
+}
+
+impl RangeCapture {
+    /// Start capturing the range of `feature`
+    pub fn new(feature: ExpressionFeature) -> Self {
+        Self { feature, range: None }
+    }
+
+    /// Expression being captured
+    pub fn feature(&self) -> ExpressionFeature {
+        self.feature
+    }
+
+    /// Record the raw expression value of the most confident face
+    pub fn observe(&mut self, faces: &[Face]) {
+        let value = faces
+            .iter()
+            .filter(|face| face.expressions.is_some())
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+            .and_then(|face| face.expressions.as_ref())
+            .map(|expressions| feature_value(expressions, self.feature));
+
+        if let Some(value) = value {
+            self.range = Some(match self.range {
+                Some(range) => ValueRange { min: range.min.min(value), max: range.max.max(value) },
+                None => ValueRange { min: value, max: value },
+            });
+        }
+    }
+
+    /// Finish the capture and return the recorded range
+    ///
+    /// Fails if no face was seen or the expression barely changed.
+    pub fn finish(self) -> Result<ValueRange, PluginError> {
+        match self.range {
+            Some(range) if range.max - range.min >= MIN_RANGE_SPAN => Ok(range),
+            Some(_) => Err(PluginError::CalibrationError(format!(
+                "{:?} barely changed during range calibration",
+                self.feature
+            ))),
+            None => Err(PluginError::CalibrationError(
+                "No face was tracked during range calibration".to_string()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_smiling(smile: f32, confidence: f32) -> Face {
+        Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence,
+            expressions: Some(Expressions { smile, brow_raise_left: 0.0, brow_raise_right: 0.0, mouth_open: 0.0 }),
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_records_min_and_max() {
+        let mut capture = RangeCapture::new(ExpressionFeature::Smile);
+        for smile in [0.2, 0.05, 0.4, 0.3] {
+            // The less confident face is ignored
+            capture.observe(&[face_smiling(0.9, 0.5), face_smiling(smile, 0.9)]);
+        }
+        assert_eq!(capture.finish().unwrap(), ValueRange { min: 0.05, max: 0.4 });
+    }
+
+    #[test]
+    fn test_rejects_unchanged_expression() {
+        let mut capture = RangeCapture::new(ExpressionFeature::Smile);
+        capture.observe(&[face_smiling(0.2, 1.0)]);
+        capture.observe(&[face_smiling(0.21, 1.0)]);
+        assert!(capture.finish().is_err());
+
+        assert!(RangeCapture::new(ExpressionFeature::MouthOpen).finish().is_err());
+    }
+}
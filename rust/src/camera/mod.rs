@@ -0,0 +1,233 @@
+//! Native camera capture
+//!
+//! Reads frames straight from the platform camera API into the tracking
+//! stream, so they never cross the Dart bridge. Capture is built with the
+//! `camera` cargo feature. V4L2 on Linux is the only backend so far; on
+//! other platforms, and in builds without the feature, opening a camera
+//! fails and the app keeps pushing frames from Dart.
+//!
+//! The camera list is enumerated once and cached; it is rebuilt on request
+//! or, while a watcher runs, whenever a camera is plugged in or removed.
+
+#[cfg(all(feature = "camera", target_os = "linux"))]
+pub mod v4l2;
+
+use crate::error::PluginError;
+use crate::input::FrameSource;
+use flutter_rust_bridge::frb;
+use lazy_static::lazy_static;
+use std::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+#[cfg(all(feature = "camera", target_os = "linux"))]
+use v4l2::{device_ids, list_devices};
+
+/// How often the watcher looks for added or removed cameras
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    static ref CAMERAS: RwLock<Option<Vec<CameraDevice>>> = RwLock::new(None);
+    static ref WATCHER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Direction a camera faces
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraFacing {
+    /// Facing the user
+    Front,
+    /// Facing away from the user
+    Back,
+    /// A separate camera, e.g. a USB webcam
+    External,
+    Unknown,
+}
+
+/// Camera device information
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraDevice {
+    /// ID to pass to `start_camera_capture` (the device path on Linux)
+    pub id: String,
+    pub name: String,
+    pub facing: CameraFacing,
+    /// Distinct frame sizes over all modes
+    pub supported_resolutions: Vec<Resolution>,
+    pub modes: Vec<CameraMode>,
+}
+
+impl CameraDevice {
+    pub fn new(id: String, name: String, facing: CameraFacing, modes: Vec<CameraMode>) -> Self {
+        let mut supported_resolutions: Vec<Resolution> = Vec::new();
+        for mode in &modes {
+            let resolution = Resolution { width: mode.width, height: mode.height };
+            if !supported_resolutions.contains(&resolution) {
+                supported_resolutions.push(resolution);
+            }
+        }
+        Self { id, name, facing, supported_resolutions, modes }
+    }
+}
+
+/// A frame size a camera delivers, with its frame rates
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraMode {
+    pub width: u32,
+    pub height: u32,
+    /// Pixel format as a FourCC code, e.g. "YUYV" or "MJPG"
+    pub pixel_format: String,
+    /// Supported frame rates (FPS), fastest first
+    pub frame_rates: Vec<f32>,
+}
+
+/// Resolution information
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Native capture settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraCaptureConfig {
+    /// Camera to open, as reported by `get_available_cameras`; empty for
+    /// the default camera
+    pub device_id: String,
+    /// Requested frame size; the camera may pick the closest it supports
+    pub width: u32,
+    pub height: u32,
+    /// Requested frame rate (FPS)
+    pub frame_rate: u32,
+}
+
+impl Default for CameraCaptureConfig {
+    fn default() -> Self {
+        Self { device_id: String::new(), width: 640, height: 480, frame_rate: 30 }
+    }
+}
+
+/// Open the configured camera and start capturing
+pub fn open(config: &CameraCaptureConfig) -> Result<Box<dyn FrameSource>, PluginError> {
+    if config.width == 0 || config.height == 0 || !(1..=240).contains(&config.frame_rate) {
+        return Err(PluginError::InvalidConfiguration(
+            "Camera capture needs a frame size and a frame rate between 1 and 240".to_string(),
+        ));
+    }
+    open_backend(config)
+}
+
+#[cfg(all(feature = "camera", target_os = "linux"))]
+fn open_backend(config: &CameraCaptureConfig) -> Result<Box<dyn FrameSource>, PluginError> {
+    Ok(Box::new(v4l2::V4l2Camera::open(config)?))
+}
+
+#[cfg(all(feature = "camera", not(target_os = "linux")))]
+fn open_backend(_config: &CameraCaptureConfig) -> Result<Box<dyn FrameSource>, PluginError> {
+    Err(PluginError::InvalidConfiguration(
+        "Native camera capture is not available on this platform; push frames from Dart".to_string(),
+    ))
+}
+
+#[cfg(not(feature = "camera"))]
+fn open_backend(_config: &CameraCaptureConfig) -> Result<Box<dyn FrameSource>, PluginError> {
+    Err(PluginError::InvalidConfiguration(
+        "Native camera capture requires building with the `camera` feature".to_string(),
+    ))
+}
+
+/// The cached camera list, enumerated on first use
+pub fn cameras() -> Vec<CameraDevice> {
+    if let Some(cameras) = CAMERAS.read().unwrap().as_ref() {
+        return cameras.clone();
+    }
+    refresh()
+}
+
+/// Enumerate the cameras again and update the cache
+pub fn refresh() -> Vec<CameraDevice> {
+    let cameras = list_devices();
+    *CAMERAS.write().unwrap() = Some(cameras.clone());
+    cameras
+}
+
+/// Send the camera list to `send` now and whenever a camera is added or
+/// removed, replacing any previous watcher
+///
+/// Stops once `send` reports the app's stream closed.
+pub fn watch<S>(send: S)
+where
+    S: Fn(Vec<CameraDevice>) -> bool + Send + 'static,
+{
+    let watcher = crate::runtime().spawn(async move {
+        let mut known = device_ids();
+        if !send(refresh()) {
+            return;
+        }
+        let mut ticker = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            ticker.tick().await;
+            // Listing device nodes is cheap; opening every camera is not
+            let ids = device_ids();
+            if ids == known {
+                continue;
+            }
+            known = ids;
+            log::info!("Camera devices changed");
+            if !send(refresh()) {
+                return;
+            }
+        }
+    });
+
+    if let Some(previous) = WATCHER.lock().unwrap().replace(watcher) {
+        previous.abort();
+    }
+}
+
+/// Stop watching for camera changes, returning whether a watcher was running
+pub fn stop_watching() -> bool {
+    match WATCHER.lock().unwrap().take() {
+        Some(watcher) => {
+            let running = !watcher.is_finished();
+            watcher.abort();
+            running
+        }
+        None => false,
+    }
+}
+
+/// Cameras are only enumerated where native capture is available; mobile
+/// apps list them with the Flutter camera plugin
+#[cfg(not(all(feature = "camera", target_os = "linux")))]
+fn list_devices() -> Vec<CameraDevice> {
+    Vec::new()
+}
+
+#[cfg(not(all(feature = "camera", target_os = "linux")))]
+fn device_ids() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolutions_are_distinct() {
+        let mode = |width, height, pixel_format: &str| CameraMode {
+            width,
+            height,
+            pixel_format: pixel_format.to_string(),
+            frame_rates: vec![30.0],
+        };
+        let modes = vec![mode(640, 480, "YUYV"), mode(1280, 720, "YUYV"), mode(640, 480, "MJPG")];
+        let device = CameraDevice::new("/dev/video0".to_string(), "Webcam".to_string(), CameraFacing::External, modes);
+        assert_eq!(
+            device.supported_resolutions,
+            vec![Resolution { width: 640, height: 480 }, Resolution { width: 1280, height: 720 }]
+        );
+    }
+}
@@ -0,0 +1,697 @@
+//! Video4Linux2 capture
+//!
+//! Streams memory-mapped buffers from a `/dev/videoN` device on a thread of
+//! its own. YUYV and NV12 frames are passed on as they are and converted by
+//! the tracker; MJPEG frames are decoded to RGB first.
+
+use super::{CameraCaptureConfig, CameraDevice, CameraFacing, CameraMode};
+use crate::error::PluginError;
+use crate::input::{still, FrameSource};
+use crate::models::*;
+use futures::future::{BoxFuture, FutureExt};
+use log::{debug, info, warn};
+use std::ffi::CString;
+use std::io;
+use std::mem::size_of;
+use tokio::sync::mpsc;
+
+/// Device opened when no camera ID is given
+pub const DEFAULT_DEVICE: &str = "/dev/video0";
+
+/// Buffers the driver fills while the previous frame is processed
+const BUFFER_COUNT: u32 = 4;
+
+/// How often the capture thread checks whether it is still needed
+const POLL_TIMEOUT_MS: i32 = 200;
+
+const BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const MEMORY_MMAP: u32 = 1;
+const FIELD_NONE: u32 = 1;
+const CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+const CAP_STREAMING: u32 = 0x0400_0000;
+const CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+const PIX_YUYV: u32 = fourcc(b"YUYV");
+const PIX_NV12: u32 = fourcc(b"NV12");
+const PIX_MJPEG: u32 = fourcc(b"MJPG");
+
+const fn ioctl_code(read: bool, write: bool, nr: u32, size: usize) -> libc::Ioctl {
+    let dir = (read as u32) << 1 | write as u32;
+    (dir << 30 | (size as u32) << 16 | (b'V' as u32) << 8 | nr) as libc::Ioctl
+}
+
+const VIDIOC_QUERYCAP: libc::Ioctl = ioctl_code(true, false, 0, size_of::<Capability>());
+const VIDIOC_ENUM_FMT: libc::Ioctl = ioctl_code(true, true, 2, size_of::<FormatDescription>());
+const VIDIOC_S_FMT: libc::Ioctl = ioctl_code(true, true, 5, size_of::<Format>());
+const VIDIOC_REQBUFS: libc::Ioctl = ioctl_code(true, true, 8, size_of::<RequestBuffers>());
+const VIDIOC_QUERYBUF: libc::Ioctl = ioctl_code(true, true, 9, size_of::<Buffer>());
+const VIDIOC_QBUF: libc::Ioctl = ioctl_code(true, true, 15, size_of::<Buffer>());
+const VIDIOC_DQBUF: libc::Ioctl = ioctl_code(true, true, 17, size_of::<Buffer>());
+const VIDIOC_STREAMON: libc::Ioctl = ioctl_code(false, true, 18, size_of::<libc::c_int>());
+const VIDIOC_STREAMOFF: libc::Ioctl = ioctl_code(false, true, 19, size_of::<libc::c_int>());
+const VIDIOC_S_PARM: libc::Ioctl = ioctl_code(true, true, 22, size_of::<StreamParm>());
+const VIDIOC_ENUM_FRAMESIZES: libc::Ioctl = ioctl_code(true, true, 74, size_of::<FrameSizeEnum>());
+const VIDIOC_ENUM_FRAMEINTERVALS: libc::Ioctl = ioctl_code(true, true, 75, size_of::<FrameIntervalEnum>());
+
+const ENUM_TYPE_DISCRETE: u32 = 1;
+
+/// Frame sizes reported for cameras with a continuous range of sizes
+const COMMON_SIZES: [(u32, u32); 5] = [(320, 240), (640, 480), (1280, 720), (1920, 1080), (3840, 2160)];
+
+// Kernel structures from <linux/videodev2.h>
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    private: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union FormatData {
+    pix: PixFormat,
+    // Other members hold pointers, so the union is 8-byte aligned
+    raw: [u64; 25],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Format {
+    buf_type: u32,
+    fmt: FormatData,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RequestBuffers {
+    count: u32,
+    buf_type: u32,
+    memory: u32,
+    capabilities: u32,
+    flags: u8,
+    reserved: [u8; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union BufferLocation {
+    offset: u32,
+    userptr: libc::c_ulong,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Buffer {
+    index: u32,
+    buf_type: u32,
+    bytesused: u32,
+    flags: u32,
+    field: u32,
+    timestamp: libc::timeval,
+    timecode: [u32; 4],
+    sequence: u32,
+    memory: u32,
+    m: BufferLocation,
+    length: u32,
+    reserved2: u32,
+    request_fd: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Fraction {
+    numerator: u32,
+    denominator: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CaptureParm {
+    capability: u32,
+    capturemode: u32,
+    timeperframe: Fraction,
+    extendedmode: u32,
+    readbuffers: u32,
+    reserved: [u32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union StreamParmData {
+    capture: CaptureParm,
+    raw: [u8; 200],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct StreamParm {
+    buf_type: u32,
+    parm: StreamParmData,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FormatDescription {
+    index: u32,
+    buf_type: u32,
+    flags: u32,
+    description: [u8; 32],
+    pixelformat: u32,
+    mbus_code: u32,
+    reserved: [u32; 3],
+}
+
+/// Discrete size, or min/max/step width then min/max/step height
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FrameSizeEnum {
+    index: u32,
+    pixel_format: u32,
+    size_type: u32,
+    sizes: [u32; 6],
+    reserved: [u32; 2],
+}
+
+/// Discrete interval, or min/max/step intervals
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FrameIntervalEnum {
+    index: u32,
+    pixel_format: u32,
+    width: u32,
+    height: u32,
+    interval_type: u32,
+    intervals: [Fraction; 3],
+    reserved: [u32; 2],
+}
+
+/// All-zero value of a kernel structure
+fn zeroed<T: Copy>() -> T {
+    // SAFETY: only used for the plain-data structures above, for which all
+    // zero bytes are a valid value
+    unsafe { std::mem::zeroed() }
+}
+
+/// Decode a NUL-padded string field
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// An open V4L2 device
+struct Device {
+    fd: libc::c_int,
+    path: String,
+}
+
+impl Device {
+    fn open(path: &str) -> Result<Self, PluginError> {
+        let c_path = CString::new(path).map_err(|_| PluginError::InvalidConfiguration(format!("Invalid camera {}", path)))?;
+        // SAFETY: `c_path` is a valid NUL-terminated string
+        let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_RDWR | libc::O_NONBLOCK | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Cannot open camera {}: {}",
+                path,
+                io::Error::last_os_error()
+            )));
+        }
+        Ok(Self { fd, path: path.to_string() })
+    }
+
+    /// Issue an ioctl, retrying when interrupted by a signal
+    fn ioctl<T>(&self, request: libc::Ioctl, arg: &mut T) -> io::Result<()> {
+        loop {
+            // SAFETY: every request code is built from the size of the
+            // structure passed with it
+            if unsafe { libc::ioctl(self.fd, request, arg as *mut T) } >= 0 {
+                return Ok(());
+            }
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(error);
+            }
+        }
+    }
+
+    fn error(&self, action: &str, e: io::Error) -> PluginError {
+        PluginError::ProcessingError(format!("Camera {}: {} failed: {}", self.path, action, e))
+    }
+
+    fn capability(&self) -> Result<Capability, PluginError> {
+        let mut capability: Capability = zeroed();
+        self.ioctl(VIDIOC_QUERYCAP, &mut capability)
+            .map_err(|e| self.error("querying capabilities", e))?;
+        Ok(capability)
+    }
+
+    /// Whether the device captures video by streaming
+    fn is_capture_device(capability: &Capability) -> bool {
+        let caps = match capability.capabilities & CAP_DEVICE_CAPS {
+            0 => capability.capabilities,
+            _ => capability.device_caps,
+        };
+        caps & CAP_VIDEO_CAPTURE != 0 && caps & CAP_STREAMING != 0
+    }
+
+    /// Pixel formats the device offers, in the order it reports them
+    fn pixel_formats(&self) -> Vec<u32> {
+        (0..)
+            .map_while(|index| {
+                let mut description = FormatDescription { index, buf_type: BUF_TYPE_VIDEO_CAPTURE, ..zeroed() };
+                self.ioctl(VIDIOC_ENUM_FMT, &mut description).ok()?;
+                Some(description.pixelformat)
+            })
+            .collect()
+    }
+
+    /// Frame sizes offered in `pixel_format`
+    fn frame_sizes(&self, pixel_format: u32) -> Vec<(u32, u32)> {
+        let mut sizes = Vec::new();
+        for index in 0.. {
+            let mut size = FrameSizeEnum { index, pixel_format, ..zeroed() };
+            if self.ioctl(VIDIOC_ENUM_FRAMESIZES, &mut size).is_err() {
+                break;
+            }
+            if size.size_type == ENUM_TYPE_DISCRETE {
+                sizes.push((size.sizes[0], size.sizes[1]));
+                continue;
+            }
+            // A range: report the common sizes it contains and its maximum
+            let [min_width, max_width, _, min_height, max_height, _] = size.sizes;
+            sizes.extend(COMMON_SIZES.iter().copied().filter(|&(width, height)| {
+                (min_width..=max_width).contains(&width) && (min_height..=max_height).contains(&height)
+            }));
+            sizes.push((max_width, max_height));
+            break;
+        }
+        sizes.dedup();
+        sizes
+    }
+
+    /// Frame rates offered for `width` x `height` in `pixel_format`
+    fn frame_rates(&self, pixel_format: u32, width: u32, height: u32) -> Vec<f32> {
+        let fps = |interval: Fraction| match interval.numerator {
+            0 => None,
+            numerator => Some(interval.denominator as f32 / numerator as f32),
+        };
+        let mut rates = Vec::new();
+        for index in 0.. {
+            let mut interval = FrameIntervalEnum { index, pixel_format, width, height, ..zeroed() };
+            if self.ioctl(VIDIOC_ENUM_FRAMEINTERVALS, &mut interval).is_err() {
+                break;
+            }
+            if interval.interval_type == ENUM_TYPE_DISCRETE {
+                rates.extend(fps(interval.intervals[0]));
+                continue;
+            }
+            // A range of intervals: report its fastest and slowest rates
+            let [min, max, _] = interval.intervals;
+            rates.extend(fps(min));
+            rates.extend(fps(max));
+            break;
+        }
+        rates.sort_by(|a, b| b.total_cmp(a));
+        rates.dedup();
+        rates
+    }
+
+    /// Ask for `width` x `height` in one of `formats`, returning the format
+    /// the driver settled on
+    fn set_format(&self, width: u32, height: u32, formats: &[u32]) -> Result<PixFormat, PluginError> {
+        for &pixelformat in formats {
+            let mut format: Format = zeroed();
+            format.buf_type = BUF_TYPE_VIDEO_CAPTURE;
+            format.fmt.pix = PixFormat { width, height, pixelformat, field: FIELD_NONE, ..zeroed() };
+            if let Err(e) = self.ioctl(VIDIOC_S_FMT, &mut format) {
+                return Err(self.error("setting the format", e));
+            }
+            // SAFETY: the driver fills `pix` for video capture buffers
+            let pix = unsafe { format.fmt.pix };
+            if pix.pixelformat == pixelformat {
+                return Ok(pix);
+            }
+        }
+        Err(PluginError::UnsupportedImageFormat(format!(
+            "Camera {} offers neither YUYV, NV12 nor MJPEG",
+            self.path
+        )))
+    }
+
+    /// Request a frame rate; drivers pick the closest one they support
+    fn set_frame_rate(&self, frame_rate: u32) {
+        let mut parm: StreamParm = zeroed();
+        parm.buf_type = BUF_TYPE_VIDEO_CAPTURE;
+        parm.parm.capture = CaptureParm {
+            timeperframe: Fraction { numerator: 1, denominator: frame_rate },
+            ..zeroed()
+        };
+        if let Err(e) = self.ioctl(VIDIOC_S_PARM, &mut parm) {
+            debug!("Camera {} does not accept a frame rate: {}", self.path, e);
+        }
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        // SAFETY: the descriptor was opened by `Device::open` and is closed once
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// A driver buffer mapped into memory
+struct Mapping {
+    ptr: *mut libc::c_void,
+    length: usize,
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: the region was mapped by `Stream::start` with this length
+        unsafe { libc::munmap(self.ptr, self.length) };
+    }
+}
+
+/// A streaming device with its mapped buffers
+struct Stream {
+    // Mappings are unmapped before the device is closed
+    mappings: Vec<Mapping>,
+    device: Device,
+    format: PixFormat,
+}
+
+// SAFETY: the mappings are plain memory owned by the stream, which is used
+// by one thread at a time
+unsafe impl Send for Stream {}
+
+impl Stream {
+    fn start(device: Device, format: PixFormat) -> Result<Self, PluginError> {
+        let mut request = RequestBuffers {
+            count: BUFFER_COUNT,
+            buf_type: BUF_TYPE_VIDEO_CAPTURE,
+            memory: MEMORY_MMAP,
+            ..zeroed()
+        };
+        device.ioctl(VIDIOC_REQBUFS, &mut request).map_err(|e| device.error("requesting buffers", e))?;
+
+        let mut stream = Self { mappings: Vec::new(), device, format };
+        for index in 0..request.count {
+            let mut buffer = stream.buffer(index);
+            stream.device.ioctl(VIDIOC_QUERYBUF, &mut buffer).map_err(|e| stream.device.error("querying a buffer", e))?;
+            // SAFETY: the driver fills `offset` for memory-mapped buffers, and
+            // the mapping covers exactly the buffer it reported
+            let ptr = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    buffer.length as usize,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    stream.device.fd,
+                    buffer.m.offset as libc::off_t,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(stream.device.error("mapping a buffer", io::Error::last_os_error()));
+            }
+            stream.mappings.push(Mapping { ptr, length: buffer.length as usize });
+            stream.device.ioctl(VIDIOC_QBUF, &mut buffer).map_err(|e| stream.device.error("queueing a buffer", e))?;
+        }
+
+        let mut buf_type = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+        stream.device.ioctl(VIDIOC_STREAMON, &mut buf_type).map_err(|e| stream.device.error("starting the stream", e))?;
+        Ok(stream)
+    }
+
+    fn buffer(&self, index: u32) -> Buffer {
+        Buffer { index, buf_type: BUF_TYPE_VIDEO_CAPTURE, memory: MEMORY_MMAP, ..zeroed() }
+    }
+
+    /// Wait up to `timeout_ms` for the next frame and copy it out
+    fn next(&self, timeout_ms: i32) -> Result<Option<Vec<u8>>, PluginError> {
+        let mut poll = libc::pollfd { fd: self.device.fd, events: libc::POLLIN, revents: 0 };
+        // SAFETY: `poll` is a valid array of one descriptor
+        let ready = unsafe { libc::poll(&mut poll, 1, timeout_ms) };
+        if ready < 0 {
+            let error = io::Error::last_os_error();
+            return match error.kind() {
+                io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(self.device.error("waiting for a frame", error)),
+            };
+        }
+        if ready == 0 {
+            return Ok(None);
+        }
+
+        let mut buffer = self.buffer(0);
+        match self.device.ioctl(VIDIOC_DQBUF, &mut buffer) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(e) => return Err(self.device.error("reading a frame", e)),
+        }
+        let mapping = &self.mappings[buffer.index as usize];
+        let length = (buffer.bytesused as usize).min(mapping.length);
+        // SAFETY: the dequeued buffer belongs to us until it is queued again
+        let data = unsafe { std::slice::from_raw_parts(mapping.ptr as *const u8, length) }.to_vec();
+        self.device.ioctl(VIDIOC_QBUF, &mut buffer).map_err(|e| self.device.error("queueing a buffer", e))?;
+        Ok(Some(data))
+    }
+
+    /// Turn captured bytes into a camera frame
+    fn frame(&self, data: Vec<u8>) -> Result<CameraFrame, PluginError> {
+        let (width, height) = (self.format.width, self.format.height);
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let (image_data, format) = match self.format.pixelformat {
+            PIX_MJPEG => {
+                let image = still::decode(&data)?;
+                return Ok(CameraFrame {
+                    width: image.width(),
+                    height: image.height(),
+                    image_data: image.into_raw(),
+                    format: ImageFormat::RGB,
+                    timestamp,
+                    rotation: 0,
+                    intrinsics: None,
+                });
+            }
+            PIX_YUYV => (pack_rows(data, width as usize * 2, self.format.bytesperline as usize, height as usize), ImageFormat::YUY2),
+            // Both planes share the luma stride
+            _ => (pack_rows(data, width as usize, self.format.bytesperline as usize, height as usize * 3 / 2), ImageFormat::NV12),
+        };
+        Ok(CameraFrame { image_data, width, height, format, timestamp, rotation: 0, intrinsics: None })
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        let mut buf_type = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+        let _ = self.device.ioctl(VIDIOC_STREAMOFF, &mut buf_type);
+    }
+}
+
+/// Drop the padding at the end of each row
+fn pack_rows(data: Vec<u8>, row_bytes: usize, stride: usize, rows: usize) -> Vec<u8> {
+    if stride <= row_bytes {
+        return data;
+    }
+    data.chunks(stride).take(rows).flat_map(|row| &row[..row_bytes.min(row.len())]).copied().collect()
+}
+
+/// Frames captured from a V4L2 device
+pub struct V4l2Camera {
+    description: String,
+    frames: mpsc::Receiver<Result<CameraFrame, PluginError>>,
+}
+
+impl V4l2Camera {
+    /// Open and configure the device, then start capturing on a thread
+    pub fn open(config: &CameraCaptureConfig) -> Result<Self, PluginError> {
+        let path = match config.device_id.as_str() {
+            "" => DEFAULT_DEVICE,
+            id => id,
+        };
+        let device = Device::open(path)?;
+        let capability = device.capability()?;
+        if !Device::is_capture_device(&capability) {
+            return Err(PluginError::InvalidConfiguration(format!("{} is not a streaming capture device", path)));
+        }
+
+        // Above VGA, USB 2 cameras only reach full frame rate with MJPEG
+        let formats = match config.width * config.height > 640 * 480 {
+            true => [PIX_MJPEG, PIX_YUYV, PIX_NV12],
+            false => [PIX_YUYV, PIX_NV12, PIX_MJPEG],
+        };
+        let format = device.set_format(config.width, config.height, &formats)?;
+        device.set_frame_rate(config.frame_rate);
+        let stream = Stream::start(device, format)?;
+
+        let description = format!(
+            "camera {} ({}, {}x{} {})",
+            path,
+            c_string(&capability.card),
+            format.width,
+            format.height,
+            String::from_utf8_lossy(&format.pixelformat.to_le_bytes())
+        );
+        info!("Capturing from {}", description);
+
+        let (sender, frames) = mpsc::channel(1);
+        std::thread::spawn(move || capture(stream, sender));
+        Ok(Self { description, frames })
+    }
+}
+
+impl FrameSource for V4l2Camera {
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn next_frame(&mut self) -> BoxFuture<'_, Result<Option<CameraFrame>, PluginError>> {
+        async move { self.frames.recv().await.transpose() }.boxed()
+    }
+}
+
+/// Paths of the video device nodes, in device order
+pub fn device_ids() -> Vec<String> {
+    let mut nodes: Vec<(u32, String)> = std::fs::read_dir("/dev")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    let name = entry.ok()?.file_name().into_string().ok()?;
+                    let number = name.strip_prefix("video")?.parse().ok()?;
+                    Some((number, format!("/dev/{}", name)))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    nodes.sort();
+    nodes.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Describe every capture device
+///
+/// Cameras often expose a second node for metadata, which is skipped, as
+/// are devices that cannot be opened.
+pub fn list_devices() -> Vec<CameraDevice> {
+    device_ids()
+        .into_iter()
+        .filter_map(|path| match describe(&path) {
+            Ok(device) => device,
+            Err(e) => {
+                debug!("Skipping {}: {}", path, e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn describe(path: &str) -> Result<Option<CameraDevice>, PluginError> {
+    let device = Device::open(path)?;
+    let capability = device.capability()?;
+    if !Device::is_capture_device(&capability) {
+        return Ok(None);
+    }
+
+    let mut modes = Vec::new();
+    for pixel_format in device.pixel_formats() {
+        if ![PIX_YUYV, PIX_NV12, PIX_MJPEG].contains(&pixel_format) {
+            continue;
+        }
+        for (width, height) in device.frame_sizes(pixel_format) {
+            modes.push(CameraMode {
+                width,
+                height,
+                pixel_format: String::from_utf8_lossy(&pixel_format.to_le_bytes()).into_owned(),
+                frame_rates: device.frame_rates(pixel_format, width, height),
+            });
+        }
+    }
+
+    // Laptop cameras are USB devices too, so the facing is not known
+    let facing = match c_string(&capability.bus_info).starts_with("usb-") {
+        true => CameraFacing::External,
+        false => CameraFacing::Unknown,
+    };
+    Ok(Some(CameraDevice::new(path.to_string(), c_string(&capability.card), facing, modes)))
+}
+
+/// Read frames until the receiver is dropped, skipping frames while the
+/// previous one is still waiting
+fn capture(stream: Stream, sender: mpsc::Sender<Result<CameraFrame, PluginError>>) {
+    while !sender.is_closed() {
+        let data = match stream.next(POLL_TIMEOUT_MS) {
+            Ok(Some(data)) => data,
+            Ok(None) => continue,
+            Err(e) => {
+                let _ = sender.blocking_send(Err(e));
+                return;
+            }
+        };
+        if sender.capacity() == 0 {
+            continue;
+        }
+        match stream.frame(data) {
+            Ok(frame) => {
+                let _ = sender.try_send(Ok(frame));
+            }
+            Err(e) => warn!("Dropping camera frame: {}", e),
+        }
+    }
+    debug!("Capture from {} stopped", stream.device.path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_structures_match_kernel_layout() {
+        assert_eq!(size_of::<Capability>(), 104);
+        assert_eq!(size_of::<Format>(), 208);
+        assert_eq!(size_of::<RequestBuffers>(), 20);
+        assert_eq!(size_of::<Buffer>(), 88);
+        assert_eq!(size_of::<StreamParm>(), 204);
+        assert_eq!(size_of::<FormatDescription>(), 64);
+        assert_eq!(size_of::<FrameSizeEnum>(), 44);
+        assert_eq!(size_of::<FrameIntervalEnum>(), 52);
+        // Values from the kernel headers
+        assert_eq!(VIDIOC_QUERYCAP, 0x8068_5600u32 as libc::Ioctl);
+        assert_eq!(VIDIOC_S_FMT, 0xC0D0_5605u32 as libc::Ioctl);
+        assert_eq!(VIDIOC_DQBUF, 0xC058_5611u32 as libc::Ioctl);
+        assert_eq!(VIDIOC_STREAMON, 0x4004_5612u32 as libc::Ioctl);
+    }
+
+    #[test]
+    fn test_packs_padded_rows() {
+        let padded = vec![1, 2, 0, 0, 3, 4, 0, 0];
+        assert_eq!(pack_rows(padded, 2, 4, 2), vec![1, 2, 3, 4]);
+        assert_eq!(pack_rows(vec![1, 2, 3, 4], 2, 2, 2), vec![1, 2, 3, 4]);
+    }
+}
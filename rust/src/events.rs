@@ -0,0 +1,172 @@
+//! Tracking event streams
+//!
+//! Events derived from the tracking results are pushed to their Dart stream
+//! as they happen, so the app does not have to diff every frame payload.
+//! Status and statistics snapshots are pushed at a fixed interval by a
+//! worker task instead.
+//! Each stream has at most one subscriber; subscribing again replaces it.
+
+use crate::face_tracking::association::FaceLifecycleEvent;
+use crate::face_tracking::blink::{BlinkEvent, WinkEvent};
+use crate::face_tracking::drowsiness::DrowsinessEvent;
+use crate::face_tracking::gestures::HeadGestureEvent;
+use crate::models::{TrackerErrorEvent, TrackerStatus, TrackingStats};
+use flutter_rust_bridge::StreamSink;
+use futures::FutureExt;
+use lazy_static::lazy_static;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, MissedTickBehavior};
+
+lazy_static! {
+    static ref FACE_LIFECYCLE_SINK: RwLock<Option<StreamSink<FaceLifecycleEvent>>> = RwLock::new(None);
+    static ref BLINK_SINK: RwLock<Option<StreamSink<BlinkEvent>>> = RwLock::new(None);
+    static ref WINK_SINK: RwLock<Option<StreamSink<WinkEvent>>> = RwLock::new(None);
+    static ref HEAD_GESTURE_SINK: RwLock<Option<StreamSink<HeadGestureEvent>>> = RwLock::new(None);
+    static ref DROWSINESS_SINK: RwLock<Option<StreamSink<DrowsinessEvent>>> = RwLock::new(None);
+    static ref ERROR_SINK: RwLock<Option<StreamSink<TrackerErrorEvent>>> = RwLock::new(None);
+    static ref STATUS_WORKER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+    static ref STATS_WORKER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Deliver face appeared and lost events to `sink`
+pub fn subscribe_face_lifecycle(sink: StreamSink<FaceLifecycleEvent>) {
+    *FACE_LIFECYCLE_SINK.write().unwrap() = Some(sink);
+}
+
+/// Send face lifecycle events to the subscriber, if any
+pub(crate) fn emit_face_lifecycle(events: &[FaceLifecycleEvent]) {
+    emit(&FACE_LIFECYCLE_SINK, events, "Face lifecycle");
+}
+
+/// Deliver blink events to `sink`
+pub fn subscribe_blinks(sink: StreamSink<BlinkEvent>) {
+    *BLINK_SINK.write().unwrap() = Some(sink);
+}
+
+/// Send blink events to the subscriber, if any
+pub(crate) fn emit_blinks(events: &[BlinkEvent]) {
+    emit(&BLINK_SINK, events, "Blink");
+}
+
+/// Deliver wink events to `sink`
+pub fn subscribe_winks(sink: StreamSink<WinkEvent>) {
+    *WINK_SINK.write().unwrap() = Some(sink);
+}
+
+/// Send wink events to the subscriber, if any
+pub(crate) fn emit_winks(events: &[WinkEvent]) {
+    emit(&WINK_SINK, events, "Wink");
+}
+
+/// Deliver head gesture events to `sink`
+pub fn subscribe_head_gestures(sink: StreamSink<HeadGestureEvent>) {
+    *HEAD_GESTURE_SINK.write().unwrap() = Some(sink);
+}
+
+/// Send head gesture events to the subscriber, if any
+pub(crate) fn emit_head_gestures(events: &[HeadGestureEvent]) {
+    emit(&HEAD_GESTURE_SINK, events, "Head gesture");
+}
+
+/// Deliver drowsiness events to `sink`
+pub fn subscribe_drowsiness(sink: StreamSink<DrowsinessEvent>) {
+    *DROWSINESS_SINK.write().unwrap() = Some(sink);
+}
+
+/// Send drowsiness events to the subscriber, if any
+pub(crate) fn emit_drowsiness(events: &[DrowsinessEvent]) {
+    emit(&DROWSINESS_SINK, events, "Drowsiness");
+}
+
+/// Deliver tracker error events to `sink`
+pub fn subscribe_errors(sink: StreamSink<TrackerErrorEvent>) {
+    *ERROR_SINK.write().unwrap() = Some(sink);
+}
+
+/// Send a tracker error event to the subscriber, if any
+pub(crate) fn emit_error(event: &TrackerErrorEvent) {
+    emit(&ERROR_SINK, std::slice::from_ref(event), "Error");
+}
+
+/// Send `events` to the subscriber of `sink`, if any
+///
+/// The subscriber is dropped once its stream has been closed on the Dart side.
+fn emit<T: Clone>(sink: &RwLock<Option<StreamSink<T>>>, events: &[T], name: &str) {
+    if events.is_empty() {
+        return;
+    }
+
+    let mut sink = sink.write().unwrap();
+    if let Some(subscriber) = sink.as_ref() {
+        if events.iter().any(|event| subscriber.add(event.clone()).is_err()) {
+            log::debug!("{} event stream closed", name);
+            *sink = None;
+        }
+    }
+}
+
+/// Push a tracker status snapshot to `sink` every `interval`
+///
+/// Snapshots are sent whether or not a tracker is initialized, so the app
+/// also sees the tracker being stopped and started again.
+pub fn subscribe_status(sink: StreamSink<TrackerStatus>, interval: Duration) {
+    let worker = run_periodic(interval, crate::api::current_status, move |status| sink.add(status).is_ok());
+    replace_worker(&STATUS_WORKER, worker);
+}
+
+/// Push a tracking statistics snapshot to `sink` every `interval`
+pub fn subscribe_stats(sink: StreamSink<TrackingStats>, interval: Duration) {
+    let worker = run_periodic(interval, crate::api::current_stats, move |stats| sink.add(stats).is_ok());
+    replace_worker(&STATS_WORKER, worker);
+}
+
+/// Drop every event subscriber and stop the snapshot workers
+pub(crate) fn unsubscribe_all() {
+    *FACE_LIFECYCLE_SINK.write().unwrap() = None;
+    *BLINK_SINK.write().unwrap() = None;
+    *WINK_SINK.write().unwrap() = None;
+    *HEAD_GESTURE_SINK.write().unwrap() = None;
+    *DROWSINESS_SINK.write().unwrap() = None;
+    *ERROR_SINK.write().unwrap() = None;
+    for slot in [&*STATUS_WORKER, &*STATS_WORKER] {
+        if let Some(worker) = slot.lock().unwrap().take() {
+            worker.abort();
+        }
+    }
+}
+
+/// Start `worker` on the shared runtime, stopping the one it replaces
+fn replace_worker(slot: &Mutex<Option<JoinHandle<()>>>, worker: impl Future<Output = ()> + Send + 'static) {
+    let worker = crate::runtime().spawn(worker);
+    if let Some(previous) = slot.lock().unwrap().replace(worker) {
+        previous.abort();
+    }
+}
+
+/// Worker loop: send a snapshot every `interval` until `send` reports the
+/// Dart stream closed
+///
+/// A panic while taking a snapshot skips that snapshot.
+async fn run_periodic<T, S, F>(interval: Duration, snapshot: impl Fn() -> F, send: S)
+where
+    F: Future<Output = T>,
+    S: Fn(T) -> bool,
+{
+    // A slow subscriber gets fewer snapshots rather than a burst of stale ones
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        let snapshot = match AssertUnwindSafe(snapshot()).catch_unwind().await {
+            Ok(snapshot) => snapshot,
+            Err(_) => continue,
+        };
+        if !send(snapshot) {
+            log::debug!("Snapshot stream closed");
+            break;
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Hardware acceleration backends
+//!
+//! Each platform offers at most one accelerator delegate on top of the CPU
+//! backend. The requested backend is initialized when the tracker is created;
+//! requesting a backend the device cannot run is an error, while a delegate
+//! that fails to initialize falls back to the CPU so tracking keeps working.
+//!
+//! [`InferenceOptions`] lets power users override the runtime defaults per
+//! device, including a priority list of execution providers to try in order.
+
+use crate::error::PluginError;
+use crate::models::AccelerationBackend;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Largest accepted inference thread count
+pub const MAX_INFERENCE_THREADS: u32 = 64;
+
+/// ONNX Runtime graph optimization level
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraphOptimizationLevel {
+    /// No graph optimizations
+    Disabled,
+    /// Redundant node elimination and constant folding
+    Basic,
+    /// Basic plus complex node fusions
+    Extended,
+    /// All optimizations, including layout changes (runtime default)
+    All,
+}
+
+/// Fine-grained inference runtime options
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InferenceOptions {
+    /// Threads used within a single model run (0 = runtime default)
+    pub num_threads: u32,
+    /// Graph optimization applied when the models are loaded; the inference
+    /// backends only support `All` so far
+    pub graph_optimization: GraphOptimizationLevel,
+    /// Execution providers in order of priority, each one of
+    /// `available_backends`; empty uses the tracker's `acceleration`
+    /// backend. CPU is always tried last.
+    pub execution_providers: Vec<AccelerationBackend>,
+}
+
+impl Default for InferenceOptions {
+    fn default() -> Self {
+        Self {
+            num_threads: 0,
+            graph_optimization: GraphOptimizationLevel::All,
+            execution_providers: Vec::new(),
+        }
+    }
+}
+
+impl InferenceOptions {
+    /// Check the options against the supported ranges
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the supported ranges
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            self.num_threads <= MAX_INFERENCE_THREADS,
+            "num_threads",
+            &format!("must be at most {}", MAX_INFERENCE_THREADS),
+            "Use 0 for the runtime default",
+        );
+        report.check(
+            self.graph_optimization == GraphOptimizationLevel::All,
+            "graph_optimization",
+            "must be All, the only level the inference backends support",
+            "Use All",
+        );
+        report.check(
+            self.execution_providers.iter().all(|backend| is_available(*backend)),
+            "execution_providers",
+            "must only list backends supported on this device",
+            "Use backends from get_available_backends",
+        );
+        report
+    }
+
+    /// Backends to try in order, given the tracker's `acceleration` setting
+    pub fn provider_chain(&self, acceleration: AccelerationBackend) -> Vec<AccelerationBackend> {
+        let mut chain = Vec::new();
+        let requested = if self.execution_providers.is_empty() {
+            std::slice::from_ref(&acceleration)
+        } else {
+            &self.execution_providers
+        };
+
+        for &backend in requested.iter().chain(std::iter::once(&AccelerationBackend::CPU)) {
+            if !chain.contains(&backend) {
+                chain.push(backend);
+            }
+        }
+        chain
+    }
+}
+
+/// Backends supported on the current device, CPU last
+///
+/// Neither openseeface-rs nor tract can attach a delegate (NNAPI, Core ML,
+/// DirectML) yet, so the CPU is the only backend that initializes.
+pub fn available_backends() -> Vec<AccelerationBackend> {
+    vec![AccelerationBackend::CPU]
+}
+
+/// Whether `backend` can be used on the current device
+pub fn is_available(backend: AccelerationBackend) -> bool {
+    available_backends().contains(&backend)
+}
+
+/// Initialize the first backend in `chain` that works
+///
+/// `init` creates the inference runtime on the given backend. Fails without
+/// trying any backend if the chain holds one the device does not support.
+/// Returns the runtime together with the backend it actually runs on.
+pub fn initialize_with_fallback<T>(
+    chain: &[AccelerationBackend],
+    mut init: impl FnMut(AccelerationBackend) -> Result<T, PluginError>,
+) -> Result<(T, AccelerationBackend), PluginError> {
+    if let Some(backend) = chain.iter().find(|backend| !is_available(**backend)) {
+        return Err(PluginError::InvalidConfiguration(format!(
+            "{:?} acceleration is not supported on this device",
+            backend
+        )));
+    }
+
+    let mut last_error = None;
+    for &backend in chain {
+        match init(backend) {
+            Ok(runtime) => return Ok((runtime, backend)),
+            Err(e) => {
+                warn!("Failed to initialize {:?} backend: {}", backend, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        PluginError::TrackerInitialization("No usable acceleration backend".to_string())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_always_available() {
+        assert_eq!(available_backends().last(), Some(&AccelerationBackend::CPU));
+        assert!(is_available(AccelerationBackend::CPU));
+    }
+
+    #[test]
+    fn test_falls_back_to_cpu() {
+        let chain = InferenceOptions::default().provider_chain(available_backends()[0]);
+        let (_, backend) = initialize_with_fallback(&chain, |backend| match backend {
+            AccelerationBackend::CPU => Ok(()),
+            _ => Err(PluginError::TrackerInitialization("delegate failed".to_string())),
+        })
+        .unwrap();
+        assert_eq!(backend, AccelerationBackend::CPU);
+    }
+
+    #[test]
+    fn test_unsupported_backend_is_rejected() {
+        // Every advertised backend initializes; the rest fail up front
+        let unsupported = [AccelerationBackend::NNAPI, AccelerationBackend::CoreML, AccelerationBackend::DirectML]
+            .into_iter()
+            .find(|backend| !is_available(*backend))
+            .unwrap();
+        let mut attempted = Vec::new();
+        let result = initialize_with_fallback(&[unsupported, AccelerationBackend::CPU], |backend| {
+            attempted.push(backend);
+            Ok(())
+        });
+        assert!(matches!(result, Err(PluginError::InvalidConfiguration(_))));
+        assert!(attempted.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_unsupported_options() {
+        assert!(InferenceOptions::default().validate().is_ok());
+
+        let options = InferenceOptions {
+            graph_optimization: GraphOptimizationLevel::Basic,
+            execution_providers: vec![AccelerationBackend::DirectML],
+            ..InferenceOptions::default()
+        };
+        let fields: Vec<_> = options.report().violations.into_iter().map(|violation| violation.field).collect();
+        assert_eq!(fields, vec!["graph_optimization", "execution_providers"]);
+    }
+
+    #[test]
+    fn test_provider_chain_priority() {
+        let options = InferenceOptions {
+            execution_providers: vec![AccelerationBackend::DirectML, AccelerationBackend::CPU, AccelerationBackend::NNAPI],
+            ..InferenceOptions::default()
+        };
+        assert_eq!(
+            options.provider_chain(AccelerationBackend::CoreML),
+            vec![AccelerationBackend::DirectML, AccelerationBackend::CPU, AccelerationBackend::NNAPI]
+        );
+        assert_eq!(
+            InferenceOptions::default().provider_chain(AccelerationBackend::CoreML),
+            vec![AccelerationBackend::CoreML, AccelerationBackend::CPU]
+        );
+    }
+}
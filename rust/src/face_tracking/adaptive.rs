@@ -0,0 +1,214 @@
+//! Thermal and battery aware quality
+//!
+//! Phones throttle hard once they overheat, and a streaming session should
+//! not drain the last of the battery. The app reports the platform's
+//! thermal state and battery level; the controller picks a [`QualityStep`]
+//! that scales down the detection resolution and frame rate and drops the
+//! optional per-face extras. It steps down as soon as conditions worsen and
+//! back up one step at a time once they have stayed better for a cooldown,
+//! so quality does not oscillate around a threshold.
+
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tokio::time::{Duration, Instant};
+
+/// Thermal state reported by the platform
+///
+/// Mirrors iOS `ProcessInfo.ThermalState`; Android thermal status values
+/// map onto it (light and moderate to `Fair`, severe to `Serious`, critical
+/// and above to `Critical`).
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ThermalState {
+    Nominal,
+    Fair,
+    Serious,
+    Critical,
+}
+
+/// Battery condition reported by the platform
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BatteryState {
+    /// Charge level (0.0 - 1.0)
+    pub level: f32,
+    /// Whether the device is plugged in
+    pub charging: bool,
+}
+
+/// Adaptive quality settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveQualityConfig {
+    /// Follow the reported device state (off by default)
+    pub enabled: bool,
+    /// Battery level below which quality is reduced when not charging
+    /// (0.0 - 1.0); below half of it quality drops further
+    pub low_battery_level: f32,
+    /// How long conditions must stay better before quality steps back up (ms)
+    pub cooldown_ms: u32,
+}
+
+impl Default for AdaptiveQualityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            low_battery_level: 0.2,
+            cooldown_ms: 30_000,
+        }
+    }
+}
+
+impl AdaptiveQualityConfig {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            (0.0..=1.0).contains(&self.low_battery_level),
+            "low_battery_level",
+            "must be between 0.0 and 1.0",
+            "Use 0.2",
+        );
+        report
+    }
+}
+
+/// How far tracking quality is reduced
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum QualityStep {
+    /// Configured quality
+    Full,
+    /// 75% detection resolution and frame rate
+    Reduced,
+    /// Half detection resolution and frame rate, without face meshes and
+    /// embeddings
+    Low,
+    /// Half detection resolution, a third of the frame rate, without face
+    /// meshes and embeddings
+    Minimal,
+}
+
+impl QualityStep {
+    const ALL: [QualityStep; 4] = [QualityStep::Full, QualityStep::Reduced, QualityStep::Low, QualityStep::Minimal];
+
+    /// Factor applied to the detection resolution
+    pub fn resolution_scale(self) -> f32 {
+        match self {
+            QualityStep::Full => 1.0,
+            QualityStep::Reduced => 0.75,
+            QualityStep::Low | QualityStep::Minimal => 0.5,
+        }
+    }
+
+    /// Factor applied to the target frame rate
+    pub fn frame_rate_scale(self) -> f32 {
+        match self {
+            QualityStep::Full => 1.0,
+            QualityStep::Reduced => 0.75,
+            QualityStep::Low => 0.5,
+            QualityStep::Minimal => 1.0 / 3.0,
+        }
+    }
+
+    /// Whether face meshes and embeddings are computed
+    pub fn extras_enabled(self) -> bool {
+        self <= QualityStep::Reduced
+    }
+
+    fn better(self) -> Self {
+        Self::ALL[(self as usize).saturating_sub(1)]
+    }
+}
+
+/// Picks the quality step from the reported device state
+#[derive(Debug)]
+pub struct AdaptiveController {
+    config: AdaptiveQualityConfig,
+    step: QualityStep,
+    /// Step the last reported state calls for
+    target: QualityStep,
+    /// When the step last changed
+    changed_at: Option<Instant>,
+}
+
+impl AdaptiveController {
+    pub fn new(config: AdaptiveQualityConfig) -> Self {
+        Self { config, step: QualityStep::Full, target: QualityStep::Full, changed_at: None }
+    }
+
+    /// Take a new device state into account, returning the current step
+    pub fn report(&mut self, thermal: ThermalState, battery: BatteryState, now: Instant) -> QualityStep {
+        if !self.config.enabled {
+            return QualityStep::Full;
+        }
+        let thermal_step = match thermal {
+            ThermalState::Nominal => QualityStep::Full,
+            ThermalState::Fair => QualityStep::Reduced,
+            ThermalState::Serious => QualityStep::Low,
+            ThermalState::Critical => QualityStep::Minimal,
+        };
+        let battery_step = match battery.level {
+            _ if battery.charging => QualityStep::Full,
+            level if level < self.config.low_battery_level / 2.0 => QualityStep::Low,
+            level if level < self.config.low_battery_level => QualityStep::Reduced,
+            _ => QualityStep::Full,
+        };
+        self.target = thermal_step.max(battery_step);
+        if self.target > self.step {
+            info!("Reducing tracking quality to {:?} ({:?}, battery {:.0}%)", self.target, thermal, battery.level * 100.0);
+            self.step = self.target;
+            self.changed_at = Some(now);
+        }
+        self.step(now)
+    }
+
+    /// Current step, moving one step back up if conditions have been better
+    /// for the cooldown
+    pub fn step(&mut self, now: Instant) -> QualityStep {
+        if !self.config.enabled {
+            return QualityStep::Full;
+        }
+        let cooled_down = self
+            .changed_at
+            .is_none_or(|changed_at| now.duration_since(changed_at) >= Duration::from_millis(self.config.cooldown_ms as u64));
+        if self.target < self.step && cooled_down {
+            self.step = self.step.better();
+            self.changed_at = Some(now);
+            info!("Raising tracking quality to {:?}", self.step);
+        }
+        self.step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_down_at_once_and_up_after_cooldown() {
+        let config = AdaptiveQualityConfig { enabled: true, cooldown_ms: 1000, ..AdaptiveQualityConfig::default() };
+        let mut controller = AdaptiveController::new(config);
+        let start = Instant::now();
+        let full_battery = BatteryState { level: 0.9, charging: false };
+
+        assert_eq!(controller.report(ThermalState::Critical, full_battery, start), QualityStep::Minimal);
+        assert_eq!(controller.report(ThermalState::Nominal, full_battery, start), QualityStep::Minimal);
+        let later = start + Duration::from_millis(1000);
+        assert_eq!(controller.step(later), QualityStep::Low);
+        assert_eq!(controller.step(later + Duration::from_millis(500)), QualityStep::Low);
+        assert_eq!(controller.step(later + Duration::from_millis(1000)), QualityStep::Reduced);
+
+        // A low battery holds quality down unless charging
+        let low_battery = BatteryState { level: 0.15, charging: false };
+        assert_eq!(controller.report(ThermalState::Nominal, low_battery, later + Duration::from_secs(5)), QualityStep::Reduced);
+        let charging = BatteryState { charging: true, ..low_battery };
+        assert_eq!(controller.report(ThermalState::Nominal, charging, later + Duration::from_secs(10)), QualityStep::Full);
+
+        let disabled = AdaptiveController::new(AdaptiveQualityConfig::default()).report(ThermalState::Critical, low_battery, start);
+        assert_eq!(disabled, QualityStep::Full);
+    }
+}
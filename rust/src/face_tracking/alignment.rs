@@ -0,0 +1,172 @@
+//! Aligned face crops
+//!
+//! Thumbnails, avatars and secondary models (e.g. face embeddings) want the
+//! face upright, centered and at a fixed scale. A similarity transform
+//! (rotation, uniform scale and translation) maps the eye centers and nose
+//! tip onto fixed positions in the crop, the same layout ArcFace-style
+//! recognition models are trained on.
+
+use crate::error::PluginError;
+use crate::models::*;
+use image::{Rgb, RgbImage};
+
+/// Largest supported crop side (pixels)
+pub const MAX_ALIGNED_SIZE: u32 = 1024;
+
+/// Positions of the image-left eye, image-right eye and nose tip in a crop
+/// of side 1
+const TEMPLATE: [[f32; 2]; 3] = [[0.342, 0.462], [0.656, 0.460], [0.5, 0.640]];
+
+/// Map from crop coordinates to frame coordinates:
+/// `x' = a x - b y + tx`, `y' = b x + a y + ty`, with `y` negated first
+/// when `mirrored`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Similarity {
+    a: f32,
+    b: f32,
+    tx: f32,
+    ty: f32,
+    mirrored: bool,
+}
+
+impl Similarity {
+    /// Least-squares fit of `from` onto `to`
+    fn fit(from: &[[f32; 2]], to: &[[f32; 2]], mirrored: bool) -> Self {
+        let from: Vec<[f32; 2]> = from.iter().map(|&[x, y]| [x, if mirrored { -y } else { y }]).collect();
+        let mean = |points: &[[f32; 2]]| {
+            let n = points.len() as f32;
+            [points.iter().map(|p| p[0]).sum::<f32>() / n, points.iter().map(|p| p[1]).sum::<f32>() / n]
+        };
+        let (from_mean, to_mean) = (mean(&from), mean(to));
+
+        let (mut dot, mut det, mut norm) = (0.0, 0.0, 0.0);
+        for (p, q) in from.iter().zip(to) {
+            let (x, y) = (p[0] - from_mean[0], p[1] - from_mean[1]);
+            let (u, v) = (q[0] - to_mean[0], q[1] - to_mean[1]);
+            dot += x * u + y * v;
+            det += x * v - y * u;
+            norm += x * x + y * y;
+        }
+        let (a, b) = if norm > 0.0 { (dot / norm, det / norm) } else { (1.0, 0.0) };
+
+        Self {
+            a,
+            b,
+            tx: to_mean[0] - (a * from_mean[0] - b * from_mean[1]),
+            ty: to_mean[1] - (b * from_mean[0] + a * from_mean[1]),
+            mirrored,
+        }
+    }
+
+    fn apply(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let y = if self.mirrored { -y } else { y };
+        [self.a * x - self.b * y + self.tx, self.b * x + self.a * y + self.ty]
+    }
+
+    /// Sum of squared distances between the mapped `from` and `to`
+    fn error(&self, from: &[[f32; 2]], to: &[[f32; 2]]) -> f32 {
+        from.iter()
+            .zip(to)
+            .map(|(&p, q)| {
+                let [x, y] = self.apply(p);
+                (x - q[0]).powi(2) + (y - q[1]).powi(2)
+            })
+            .sum()
+    }
+}
+
+/// Cut an upright, eye-aligned `size` x `size` crop of a face out of `image`
+///
+/// `landmarks` are the face's 68 landmarks in `image` coordinates. Faces
+/// tracked in mirrored frames are cut out mirrored, as they were tracked.
+pub fn align_face(image: &RgbImage, landmarks: &FacialLandmarks, size: u32) -> Result<RgbImage, PluginError> {
+    if size == 0 || size > MAX_ALIGNED_SIZE {
+        return Err(PluginError::InvalidConfiguration(format!(
+            "Aligned face size must be between 1 and {}",
+            MAX_ALIGNED_SIZE
+        )));
+    }
+    let (Some(right_eye), Some(left_eye)) = (landmarks.right_eye(), landmarks.left_eye()) else {
+        return Err(PluginError::ProcessingError("Face alignment needs all 68 landmarks".to_string()));
+    };
+
+    let center = |points: &[Point2D]| {
+        let n = points.len() as f32;
+        [points.iter().map(|p| p.x).sum::<f32>() / n, points.iter().map(|p| p.y).sum::<f32>() / n]
+    };
+    let nose = landmarks.points[30];
+    let observed = [center(right_eye), center(left_eye), [nose.x, nose.y]];
+    let template = TEMPLATE.map(|[x, y]| [x * size as f32, y * size as f32]);
+
+    // Landmarks from mirrored frames need a reflection to come out upright
+    let transform = [false, true]
+        .map(|mirrored| Similarity::fit(&template, &observed, mirrored))
+        .into_iter()
+        .min_by(|s, t| s.error(&template, &observed).total_cmp(&t.error(&template, &observed)))
+        .unwrap();
+
+    Ok(RgbImage::from_fn(size, size, |x, y| {
+        let [sx, sy] = transform.apply([x as f32 + 0.5, y as f32 + 0.5]);
+        sample(image, sx - 0.5, sy - 0.5)
+    }))
+}
+
+/// Bilinear sample at pixel coordinates, black outside the image
+fn sample(image: &RgbImage, x: f32, y: f32) -> Rgb<u8> {
+    let (width, height) = image.dimensions();
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+
+    let pixel = |px: f32, py: f32| -> [f32; 3] {
+        if px < 0.0 || py < 0.0 || px >= width as f32 || py >= height as f32 {
+            return [0.0; 3];
+        }
+        image.get_pixel(px as u32, py as u32).0.map(f32::from)
+    };
+    let (p00, p10, p01, p11) = (pixel(x0, y0), pixel(x0 + 1.0, y0), pixel(x0, y0 + 1.0), pixel(x0 + 1.0, y0 + 1.0));
+
+    Rgb(std::array::from_fn(|c| {
+        let top = p00[c] * (1.0 - fx) + p10[c] * fx;
+        let bottom = p01[c] * (1.0 - fx) + p11[c] * fx;
+        (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Landmarks with the eye centers and nose tip at the given points
+    fn landmarks(right_eye: [f32; 2], left_eye: [f32; 2], nose: [f32; 2]) -> FacialLandmarks {
+        let mut points = vec![Point2D { x: 0.0, y: 0.0 }; 68];
+        points[36..42].fill(Point2D { x: right_eye[0], y: right_eye[1] });
+        points[42..48].fill(Point2D { x: left_eye[0], y: left_eye[1] });
+        points[30] = Point2D { x: nose[0], y: nose[1] };
+        FacialLandmarks { confidences: vec![1.0; 68], points }
+    }
+
+    #[test]
+    fn test_fit_recovers_rotation() {
+        let template = TEMPLATE.map(|[x, y]| [x * 100.0, y * 100.0]);
+        // The template rotated by 90 degrees, doubled and shifted
+        let observed = template.map(|[x, y]| [-2.0 * y + 300.0, 2.0 * x + 50.0]);
+
+        let transform = Similarity::fit(&template, &observed, false);
+        assert!((transform.a - 0.0).abs() < 1e-4 && (transform.b - 2.0).abs() < 1e-4);
+        assert!(transform.error(&template, &observed) < 1e-3);
+    }
+
+    #[test]
+    fn test_crop_is_upright() {
+        // Mark the left half of the frame red and the right half blue; the
+        // face is upside down, so red must end up on the right of the crop
+        let image = RgbImage::from_fn(200, 200, |x, _| if x < 100 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) });
+        let face = landmarks([130.0, 100.0], [70.0, 100.0], [100.0, 80.0]);
+
+        let crop = align_face(&image, &face, 64).unwrap();
+        assert_eq!(crop.dimensions(), (64, 64));
+        assert_eq!(crop.get_pixel(5, 32), &Rgb([0, 0, 255]));
+        assert_eq!(crop.get_pixel(58, 32), &Rgb([255, 0, 0]));
+        assert!(align_face(&image, &face, 0).is_err());
+    }
+}
@@ -0,0 +1,579 @@
+//! Detection-to-track association
+//!
+//! openseeface-rs reports faces in detection order, which changes whenever a
+//! face moves or another one appears. This module matches each frame's
+//! detections to the existing tracks by bounding box overlap (IoU) and
+//! landmark distance using an optimal (Hungarian) assignment, so every
+//! physical face keeps a persistent ID while it stays visible.
+//!
+//! Tracks whose face disappears are remembered for a configurable time. While
+//! lost, their bounding box is extrapolated with the last observed velocity so
+//! a face that reappears after a brief occlusion gets its old ID back.
+//!
+//! Creating a track and forgetting an expired one are reported as
+//! [`FaceLifecycleEvent`]s.
+//!
+//! Tracks can carry a label set by the app, e.g. "streamer" and "guest".
+//! The label follows the track through occlusions; with face embeddings
+//! enabled it also outlives the track and is given back to the first new
+//! face whose embedding matches the labelled face's.
+
+use crate::face_tracking::embedding;
+use crate::models::*;
+use flutter_rust_bridge::frb;
+
+/// Minimum IoU for a detection to continue an existing track
+pub const DEFAULT_MIN_IOU: f32 = 0.3;
+
+/// Maximum normalized landmark distance for a detection to continue a track
+/// when the boxes barely overlap (fast motion)
+const MAX_LANDMARK_DISTANCE: f32 = 0.25;
+
+/// Default time a lost track is remembered for re-identification
+pub const DEFAULT_TRACK_MEMORY_MS: u32 = 1000;
+
+/// Extra cost for continuing a lost track, so visible tracks are preferred
+const LOST_TRACK_PENALTY: f32 = 0.5;
+
+/// Longest extrapolation applied to a lost track's bounding box
+const MAX_PREDICTION_MS: f32 = 500.0;
+
+/// Default lowest embedding similarity at which a new face gets the label
+/// of an ended track
+pub const DEFAULT_LABEL_SIMILARITY: f32 = 0.45;
+
+/// Most labels of ended tracks kept for re-identification
+const MAX_DEPARTED_LABELS: usize = 16;
+
+/// Start or end of a face track
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaceLifecycleEvent {
+    /// A face without a remembered track was detected
+    FaceAppeared {
+        /// ID of the new track
+        id: u32,
+    },
+    /// A face has not been detected for longer than the track memory
+    FaceLost {
+        /// ID of the ended track
+        id: u32,
+        /// Timestamp of the last frame the face was detected in
+        last_seen_timestamp: i64,
+    },
+}
+
+/// A face being tracked across frames
+#[derive(Debug, Clone)]
+struct Track {
+    id: u32,
+    bounding_box: BoundingBox,
+    landmarks: Option<Vec<Point2D>>,
+    /// Bounding box velocity in pixels per millisecond
+    velocity: (f32, f32),
+    /// Timestamp of the last frame the face was detected in
+    last_seen: i64,
+    /// Label set by the app
+    label: Option<String>,
+    /// Latest embedding of the face, for handing the label on
+    embedding: Option<Vec<f32>>,
+}
+
+impl Track {
+    fn new(face: &Face, timestamp: i64) -> Self {
+        Self {
+            id: face.id,
+            bounding_box: face.bounding_box,
+            landmarks: face.landmarks.as_ref().map(|l| l.points.clone()),
+            velocity: (0.0, 0.0),
+            last_seen: timestamp,
+            label: None,
+            embedding: face.embedding.clone(),
+        }
+    }
+
+    /// Continue the track with a new detection
+    fn update(&mut self, face: &Face, timestamp: i64) {
+        let elapsed = (timestamp - self.last_seen) as f32;
+        if elapsed > 0.0 {
+            self.velocity = (
+                (face.bounding_box.x - self.bounding_box.x) / elapsed,
+                (face.bounding_box.y - self.bounding_box.y) / elapsed,
+            );
+        }
+        self.bounding_box = face.bounding_box;
+        self.landmarks = face.landmarks.as_ref().map(|l| l.points.clone());
+        if face.embedding.is_some() {
+            self.embedding = face.embedding.clone();
+        }
+        self.last_seen = timestamp;
+    }
+
+    /// Bounding box extrapolated to `timestamp` with the last velocity
+    fn predicted_box(&self, timestamp: i64) -> BoundingBox {
+        let elapsed = ((timestamp - self.last_seen) as f32).clamp(0.0, MAX_PREDICTION_MS);
+        BoundingBox {
+            x: self.bounding_box.x + self.velocity.0 * elapsed,
+            y: self.bounding_box.y + self.velocity.1 * elapsed,
+            ..self.bounding_box
+        }
+    }
+}
+
+/// Assigns persistent IDs to detected faces
+pub struct FaceAssociator {
+    tracks: Vec<Track>,
+    next_id: u32,
+    min_iou: f32,
+    /// How long lost tracks are kept for re-identification (ms)
+    memory_ms: u32,
+    /// Timestamp of the last processed frame
+    last_timestamp: Option<i64>,
+    /// Labels of ended tracks with the last embedding of their face
+    departed_labels: Vec<(String, Vec<f32>)>,
+    /// Lowest embedding similarity at which a departed label is handed on
+    label_similarity: f32,
+}
+
+impl FaceAssociator {
+    pub fn new(min_iou: f32, memory_ms: u32) -> Self {
+        Self {
+            tracks: Vec::new(),
+            next_id: 0,
+            min_iou,
+            memory_ms,
+            last_timestamp: None,
+            departed_labels: Vec::new(),
+            label_similarity: DEFAULT_LABEL_SIMILARITY,
+        }
+    }
+
+    /// Hand labels of ended tracks on at `similarity` instead of
+    /// [`DEFAULT_LABEL_SIMILARITY`]
+    pub fn with_label_similarity(mut self, similarity: f32) -> Self {
+        self.label_similarity = similarity;
+        self
+    }
+
+    /// Replace the IDs of `faces` detected at `timestamp` with persistent track IDs
+    ///
+    /// Detections that match no visible or recently lost track start a new one.
+    /// Tracks that have not been seen for longer than the track memory are ended.
+    /// Every face gets the label of its track. Returns the tracks started and
+    /// ended by this frame.
+    pub fn assign_ids(&mut self, faces: &mut [Face], timestamp: i64) -> Vec<FaceLifecycleEvent> {
+        let memory_ms = self.memory_ms as i64;
+        let (remembered, ended): (Vec<Track>, Vec<Track>) = std::mem::take(&mut self.tracks)
+            .into_iter()
+            .partition(|track| timestamp - track.last_seen <= memory_ms);
+        self.tracks = remembered;
+        let mut events = Vec::new();
+        for track in ended {
+            events.push(FaceLifecycleEvent::FaceLost { id: track.id, last_seen_timestamp: track.last_seen });
+            self.depart(track);
+        }
+
+        let costs: Vec<Vec<f32>> = faces
+            .iter()
+            .map(|face| {
+                self.tracks
+                    .iter()
+                    .map(|track| self.match_cost(face, track, timestamp))
+                    .collect()
+            })
+            .collect();
+
+        let assignment = hungarian(&costs, self.tracks.len());
+
+        let mut new_tracks = Vec::new();
+        for (face, track_index) in faces.iter_mut().zip(assignment) {
+            match track_index {
+                Some(t) => {
+                    face.id = self.tracks[t].id;
+                    self.tracks[t].update(face, timestamp);
+                    face.label = self.tracks[t].label.clone();
+                }
+                None => {
+                    face.id = self.allocate_id();
+                    events.push(FaceLifecycleEvent::FaceAppeared { id: face.id });
+                    let mut track = Track::new(face, timestamp);
+                    track.label = face.embedding.as_deref().and_then(|embedding| self.claim_label(embedding));
+                    face.label = track.label.clone();
+                    new_tracks.push(track);
+                }
+            }
+        }
+        self.tracks.extend(new_tracks);
+        self.last_timestamp = Some(timestamp);
+        events
+    }
+
+    /// IDs of tracks that are lost but still remembered
+    pub fn lost_track_ids(&self) -> Vec<u32> {
+        self.tracks
+            .iter()
+            .filter(|track| !self.is_visible(track))
+            .map(|track| track.id)
+            .collect()
+    }
+
+    /// Label the track `id`, or remove its label with `None`
+    ///
+    /// A label belongs to one face at a time; giving it to another face
+    /// takes it from the previous one. Returns `false` if no visible or
+    /// lost track has the ID.
+    pub fn set_label(&mut self, id: u32, label: Option<String>) -> bool {
+        if !self.tracks.iter().any(|track| track.id == id) {
+            return false;
+        }
+        if let Some(label) = &label {
+            self.departed_labels.retain(|(departed, _)| departed != label);
+            for track in self.tracks.iter_mut().filter(|track| track.label.as_ref() == Some(label)) {
+                track.label = None;
+            }
+        }
+        if let Some(track) = self.tracks.iter_mut().find(|track| track.id == id) {
+            track.label = label;
+        }
+        true
+    }
+
+    /// Forget all tracks, returning a `FaceLost` event for each of them
+    ///
+    /// Labels of faces with an embedding are kept for re-identification.
+    pub fn reset(&mut self) -> Vec<FaceLifecycleEvent> {
+        let mut events = Vec::new();
+        for track in std::mem::take(&mut self.tracks) {
+            events.push(FaceLifecycleEvent::FaceLost { id: track.id, last_seen_timestamp: track.last_seen });
+            self.depart(track);
+        }
+        self.last_timestamp = None;
+        events
+    }
+
+    /// Keep the label of an ended track for the next face that matches it
+    fn depart(&mut self, track: Track) {
+        let (Some(label), Some(embedding)) = (track.label, track.embedding) else {
+            return;
+        };
+        if self.departed_labels.len() == MAX_DEPARTED_LABELS {
+            self.departed_labels.remove(0);
+        }
+        self.departed_labels.push((label, embedding));
+    }
+
+    /// Take the departed label whose face matches `embedding` best
+    fn claim_label(&mut self, embedding: &[f32]) -> Option<String> {
+        let (index, _) = self
+            .departed_labels
+            .iter()
+            .map(|(_, departed)| embedding::similarity(embedding, departed))
+            .enumerate()
+            .filter(|(_, similarity)| *similarity >= self.label_similarity)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        Some(self.departed_labels.remove(index).0)
+    }
+
+    fn allocate_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        id
+    }
+
+    /// Whether the track was detected in the last processed frame
+    fn is_visible(&self, track: &Track) -> bool {
+        self.last_timestamp == Some(track.last_seen)
+    }
+
+    /// Cost of continuing `track` with `face`, or `f32::INFINITY` if they cannot match
+    fn match_cost(&self, face: &Face, track: &Track, timestamp: i64) -> f32 {
+        if !self.is_visible(track) {
+            return self.lost_match_cost(face, track, timestamp);
+        }
+
+        let overlap = iou(&face.bounding_box, &track.bounding_box);
+        let landmark_distance = match (&face.landmarks, &track.landmarks) {
+            (Some(landmarks), Some(previous)) if landmarks.points.len() == previous.len() => {
+                Some(normalized_landmark_distance(&landmarks.points, previous, &track.bounding_box))
+            }
+            _ => None,
+        };
+
+        let close_landmarks = landmark_distance.is_some_and(|d| d < MAX_LANDMARK_DISTANCE);
+        if overlap < self.min_iou && !close_landmarks {
+            return f32::INFINITY;
+        }
+
+        match landmark_distance {
+            Some(distance) => 0.5 * (1.0 - overlap) + 0.5 * distance.min(1.0),
+            None => 1.0 - overlap,
+        }
+    }
+
+    /// Cost of re-identifying a lost track against its predicted position
+    fn lost_match_cost(&self, face: &Face, track: &Track, timestamp: i64) -> f32 {
+        let predicted = track.predicted_box(timestamp);
+        let overlap = iou(&face.bounding_box, &predicted);
+
+        // Faces often reappear somewhat off the prediction, so also accept a
+        // center within half a face size
+        let size = predicted.width.max(predicted.height).max(1.0);
+        let dx = (face.bounding_box.x + face.bounding_box.width / 2.0) - (predicted.x + predicted.width / 2.0);
+        let dy = (face.bounding_box.y + face.bounding_box.height / 2.0) - (predicted.y + predicted.height / 2.0);
+        let center_distance = (dx * dx + dy * dy).sqrt() / size;
+
+        if overlap < self.min_iou / 2.0 && center_distance > 0.5 {
+            return f32::INFINITY;
+        }
+
+        LOST_TRACK_PENALTY + 0.5 * (1.0 - overlap) + 0.5 * center_distance.min(1.0)
+    }
+}
+
+/// Intersection over union of two bounding boxes
+pub fn iou(a: &BoundingBox, b: &BoundingBox) -> f32 {
+    let left = a.x.max(b.x);
+    let top = a.y.max(b.y);
+    let right = (a.x + a.width).min(b.x + b.width);
+    let bottom = (a.y + a.height).min(b.y + b.height);
+
+    let intersection = (right - left).max(0.0) * (bottom - top).max(0.0);
+    let union = a.width * a.height + b.width * b.height - intersection;
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+/// Mean landmark displacement relative to the face size
+fn normalized_landmark_distance(current: &[Point2D], previous: &[Point2D], bounding_box: &BoundingBox) -> f32 {
+    let scale = (bounding_box.width.powi(2) + bounding_box.height.powi(2)).sqrt();
+    if current.is_empty() || scale <= 0.0 {
+        return f32::INFINITY;
+    }
+
+    let total: f32 = current
+        .iter()
+        .zip(previous)
+        .map(|(a, b)| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt())
+        .sum();
+    total / current.len() as f32 / scale
+}
+
+/// Minimum-cost assignment of rows (detections) to columns (tracks)
+///
+/// Returns the assigned column for every row, or `None` when the row is left
+/// unassigned or only infinite-cost (forbidden) columns were available.
+fn hungarian(costs: &[Vec<f32>], columns: usize) -> Vec<Option<usize>> {
+    let rows = costs.len();
+    if rows == 0 || columns == 0 {
+        return vec![None; rows];
+    }
+
+    // Square matrix padded with dummy entries; forbidden pairs get a large
+    // finite cost so the algorithm stays well defined
+    const FORBIDDEN: f64 = 1e6;
+    let n = rows.max(columns);
+    let cost = |i: usize, j: usize| -> f64 {
+        if i < rows && j < columns {
+            let c = costs[i][j];
+            if c.is_finite() { c as f64 } else { FORBIDDEN }
+        } else {
+            FORBIDDEN / 2.0
+        }
+    };
+
+    // Classic O(n^3) Kuhn-Munkres with potentials (1-based indices)
+    let mut u = vec![0.0f64; n + 1];
+    let mut v = vec![0.0f64; n + 1];
+    let mut matched_row = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+
+    for i in 1..=n {
+        matched_row[0] = i;
+        let mut j0 = 0;
+        let mut min_to = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+
+        loop {
+            used[j0] = true;
+            let i0 = matched_row[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+
+            for j in 1..=n {
+                if !used[j] {
+                    let reduced = cost(i0 - 1, j - 1) - u[i0] - v[j];
+                    if reduced < min_to[j] {
+                        min_to[j] = reduced;
+                        way[j] = j0;
+                    }
+                    if min_to[j] < delta {
+                        delta = min_to[j];
+                        j1 = j;
+                    }
+                }
+            }
+
+            for j in 0..=n {
+                if used[j] {
+                    u[matched_row[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_to[j] -= delta;
+                }
+            }
+
+            j0 = j1;
+            if matched_row[j0] == 0 {
+                break;
+            }
+        }
+
+        loop {
+            let j1 = way[j0];
+            matched_row[j0] = matched_row[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut assignment = vec![None; rows];
+    for j in 1..=n {
+        let i = matched_row[j];
+        if i >= 1 && i <= rows && j <= columns && costs[i - 1][j - 1].is_finite() {
+            assignment[i - 1] = Some(j - 1);
+        }
+    }
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_at(x: f32, y: f32) -> Face {
+        Face {
+            bounding_box: BoundingBox { x, y, width: 100.0, height: 100.0 },
+            confidence: 0.9,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_iou() {
+        let a = BoundingBox { x: 0.0, y: 0.0, width: 10.0, height: 10.0 };
+        let b = BoundingBox { x: 5.0, y: 0.0, width: 10.0, height: 10.0 };
+        assert!((iou(&a, &a) - 1.0).abs() < 1e-6);
+        assert!((iou(&a, &b) - 50.0 / 150.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hungarian_prefers_global_optimum() {
+        // Greedy would take (0, 0) and force (1, 1) at cost 10
+        let costs = vec![vec![1.0, 2.0], vec![2.0, 10.0]];
+        assert_eq!(hungarian(&costs, 2), vec![Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn test_hungarian_forbidden_pairs() {
+        let costs = vec![vec![f32::INFINITY], vec![0.5]];
+        assert_eq!(hungarian(&costs, 1), vec![None, Some(0)]);
+    }
+
+    #[test]
+    fn test_ids_survive_reordering() {
+        let mut associator = FaceAssociator::new(DEFAULT_MIN_IOU, DEFAULT_TRACK_MEMORY_MS);
+
+        let mut first = vec![face_at(0.0, 0.0), face_at(300.0, 0.0)];
+        associator.assign_ids(&mut first, 0);
+        let (left_id, right_id) = (first[0].id, first[1].id);
+        assert_ne!(left_id, right_id);
+
+        // Same faces, slightly moved and reported in the opposite order
+        let mut second = vec![face_at(305.0, 2.0), face_at(4.0, 1.0)];
+        associator.assign_ids(&mut second, 33);
+        assert_eq!(second[0].id, right_id);
+        assert_eq!(second[1].id, left_id);
+    }
+
+    #[test]
+    fn test_new_face_gets_new_id() {
+        let mut associator = FaceAssociator::new(DEFAULT_MIN_IOU, DEFAULT_TRACK_MEMORY_MS);
+
+        let mut first = vec![face_at(0.0, 0.0)];
+        associator.assign_ids(&mut first, 0);
+
+        let mut second = vec![face_at(0.0, 0.0), face_at(500.0, 500.0)];
+        associator.assign_ids(&mut second, 33);
+        assert_eq!(second[0].id, first[0].id);
+        assert_ne!(second[1].id, first[0].id);
+    }
+
+    #[test]
+    fn test_reidentification_after_occlusion() {
+        let mut associator = FaceAssociator::new(DEFAULT_MIN_IOU, DEFAULT_TRACK_MEMORY_MS);
+
+        let mut first = vec![face_at(0.0, 0.0)];
+        associator.assign_ids(&mut first, 0);
+        let id = first[0].id;
+
+        // Face hidden for a few frames
+        associator.assign_ids(&mut [], 33);
+        associator.assign_ids(&mut [], 66);
+        assert_eq!(associator.lost_track_ids(), vec![id]);
+
+        let mut back = vec![face_at(20.0, 10.0)];
+        associator.assign_ids(&mut back, 400);
+        assert_eq!(back[0].id, id);
+    }
+
+    #[test]
+    fn test_track_forgotten_after_memory() {
+        let mut associator = FaceAssociator::new(DEFAULT_MIN_IOU, 200);
+
+        let mut first = vec![face_at(0.0, 0.0)];
+        let appeared = associator.assign_ids(&mut first, 0);
+        assert_eq!(appeared, vec![FaceLifecycleEvent::FaceAppeared { id: first[0].id }]);
+
+        assert!(associator.assign_ids(&mut [], 100).is_empty());
+        let mut back = vec![face_at(0.0, 0.0)];
+        let events = associator.assign_ids(&mut back, 500);
+        assert_ne!(back[0].id, first[0].id);
+        assert_eq!(events, vec![
+            FaceLifecycleEvent::FaceLost { id: first[0].id, last_seen_timestamp: 0 },
+            FaceLifecycleEvent::FaceAppeared { id: back[0].id },
+        ]);
+
+        // Resetting ends the remaining track
+        assert_eq!(associator.reset(), vec![FaceLifecycleEvent::FaceLost { id: back[0].id, last_seen_timestamp: 500 }]);
+        assert!(associator.reset().is_empty());
+    }
+
+    #[test]
+    fn test_label_outlives_track_with_matching_embedding() {
+        let mut associator = FaceAssociator::new(DEFAULT_MIN_IOU, 200);
+        let with_embedding = |x: f32, embedding: [f32; 2]| Face { embedding: Some(embedding.to_vec()), ..face_at(x, 0.0) };
+
+        let mut first = vec![with_embedding(0.0, [1.0, 0.0]), with_embedding(300.0, [0.0, 1.0])];
+        associator.assign_ids(&mut first, 0);
+        assert!(associator.set_label(first[0].id, Some("streamer".to_string())));
+        assert!(!associator.set_label(99, Some("guest".to_string())));
+
+        let mut second = vec![with_embedding(2.0, [1.0, 0.0]), with_embedding(302.0, [0.0, 1.0])];
+        associator.assign_ids(&mut second, 33);
+        assert_eq!(second[0].label.as_deref(), Some("streamer"));
+        assert_eq!(second[1].label, None);
+
+        // Both leave for longer than the track memory and come back swapped
+        associator.assign_ids(&mut [], 100);
+        let mut back = vec![with_embedding(0.0, [0.0, 1.0]), with_embedding(300.0, [0.8, 0.6])];
+        associator.assign_ids(&mut back, 1_000);
+        assert_ne!(back[1].id, first[0].id);
+        assert_eq!(back[0].label, None);
+        assert_eq!(back[1].label.as_deref(), Some("streamer"));
+    }
+}
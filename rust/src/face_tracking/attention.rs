@@ -0,0 +1,183 @@
+//! Attention estimation
+//!
+//! Presenter feedback and accessibility apps want to know whether the user
+//! is engaged with the screen, not where exactly they look. Each frame gets
+//! an instant score from how far the head is turned away from the camera,
+//! how far the gaze points away from it and how open the eyes are; the
+//! reported [`Face::attention_score`] is the mean over a time window, so a
+//! blink or a quick glance aside only dips it slightly while looking away
+//! for a while brings it down.
+
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Longest accepted averaging window (ms)
+pub const MAX_ATTENTION_WINDOW_MS: u32 = 60_000;
+
+/// Attention estimation settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttentionConfig {
+    /// Add `Face::attention_score` to faces with a pose
+    pub enabled: bool,
+    /// Time the score is averaged over (ms)
+    pub window_ms: u32,
+    /// Head rotation away from the camera at which attention drops to 0.0
+    /// (degrees, combined yaw and pitch)
+    pub max_head_angle: f32,
+    /// Gaze angle away from the camera at which attention drops to 0.0
+    /// (degrees)
+    pub max_gaze_angle: f32,
+}
+
+impl Default for AttentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_ms: 2000,
+            max_head_angle: 40.0,
+            max_gaze_angle: 25.0,
+        }
+    }
+}
+
+impl AttentionConfig {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            (1..=MAX_ATTENTION_WINDOW_MS).contains(&self.window_ms),
+            "window_ms",
+            format!("must be between 1 and {}", MAX_ATTENTION_WINDOW_MS).as_str(),
+            "Use 2000",
+        );
+        report.check(
+            self.max_head_angle > 0.0 && self.max_head_angle <= 90.0,
+            "max_head_angle",
+            "must be greater than 0.0 and at most 90.0",
+            "Use 40",
+        );
+        report.check(
+            self.max_gaze_angle > 0.0 && self.max_gaze_angle <= 90.0,
+            "max_gaze_angle",
+            "must be greater than 0.0 and at most 90.0",
+            "Use 25",
+        );
+        report
+    }
+
+    /// Attention in a single frame (0.0 - 1.0)
+    fn instant_score(&self, pose: &HeadPose, gaze: Option<&EyeGaze>, eyes: Option<&EyeState>) -> f32 {
+        // Falls off quadratically, so small movements barely count
+        let falloff = |angle: f32, max: f32| (1.0 - (angle / max).powi(2)).max(0.0);
+        let head_angle = (pose.yaw * pose.yaw + pose.pitch * pose.pitch).sqrt();
+        let head = falloff(head_angle, self.max_head_angle);
+
+        // Gaze points along +z when looking into the camera
+        let gaze = gaze.map_or(1.0, |gaze| {
+            let d = gaze.combined_direction;
+            let length = (d.x * d.x + d.y * d.y + d.z * d.z).sqrt();
+            if length > 0.0 {
+                falloff((d.z / length).clamp(-1.0, 1.0).acos().to_degrees(), self.max_gaze_angle)
+            } else {
+                1.0
+            }
+        });
+
+        let eyes = eyes.map_or(1.0, |eyes| ((eyes.left_eye_openness + eyes.right_eye_openness) / 2.0).clamp(0.0, 1.0));
+        head * gaze * eyes
+    }
+}
+
+/// Per-face attention over the configured window
+pub struct AttentionEstimator {
+    config: AttentionConfig,
+    /// Instant scores of each face by timestamp
+    faces: HashMap<u32, VecDeque<(i64, f32)>>,
+}
+
+impl AttentionEstimator {
+    pub fn new(config: AttentionConfig) -> Self {
+        Self { config, faces: HashMap::new() }
+    }
+
+    /// Set `Face::attention_score` on all faces with a pose
+    ///
+    /// History of faces that are no longer present is discarded.
+    pub fn apply(&mut self, faces: &mut [Face]) {
+        if !self.config.enabled {
+            return;
+        }
+        self.faces.retain(|id, _| faces.iter().any(|face| face.id == *id));
+
+        let window = self.config.window_ms as i64;
+        for face in faces.iter_mut() {
+            let Some(pose) = &face.pose else {
+                self.faces.remove(&face.id);
+                continue;
+            };
+            let score = self.config.instant_score(pose, face.gaze.as_ref(), face.eyes.as_ref());
+            let history = self.faces.entry(face.id).or_default();
+            history.push_back((face.timestamp, score));
+            while history.front().is_some_and(|&(timestamp, _)| face.timestamp - timestamp >= window) {
+                history.pop_front();
+            }
+            face.attention_score = Some(history.iter().map(|(_, score)| score).sum::<f32>() / history.len() as f32);
+        }
+    }
+
+    /// Forget the history of all faces
+    pub fn reset(&mut self) {
+        self.faces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(yaw: f32, openness: f32, timestamp: i64) -> Face {
+        Face {
+            id: 1,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            pose: Some(HeadPose::from_euler(0.0, yaw, 0.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0)),
+            eyes: Some(EyeState {
+                left_eye_openness: openness,
+                right_eye_openness: openness,
+                left_eye_ratio: 0.3,
+                right_eye_ratio: 0.3,
+            }),
+            timestamp,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_attention_averages_over_window() {
+        let mut estimator = AttentionEstimator::new(AttentionConfig { window_ms: 1000, ..AttentionConfig::default() });
+        let mut score = |yaw, openness, timestamp| {
+            let mut faces = vec![face(yaw, openness, timestamp)];
+            estimator.apply(&mut faces);
+            faces[0].attention_score.unwrap()
+        };
+
+        assert_eq!(score(0.0, 1.0, 0), 1.0);
+        // A blink only dips the average
+        assert_eq!(score(0.0, 0.0, 100), 0.5);
+        assert!((score(0.0, 1.0, 200) - 2.0 / 3.0).abs() < 1e-6);
+        // Looking away for longer than the window drops it to zero
+        for timestamp in (300..=1300).step_by(100) {
+            score(60.0, 1.0, timestamp);
+        }
+        assert_eq!(score(60.0, 1.0, 1400), 0.0);
+        // Half the maximum head angle keeps three quarters of the attention
+        let config = AttentionConfig::default();
+        let pose = HeadPose::from_euler(0.0, 20.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0);
+        assert!((config.instant_score(&pose, None, None) - 0.75).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,147 @@
+//! Detection with a deadline
+//!
+//! Inference runs on a blocking thread, so a detection that hangs (e.g. in a
+//! GPU delegate) cannot stall the runtime. When a frame misses its deadline
+//! the detection is abandoned rather than cancelled: it keeps its backend
+//! locked until it returns, so later frames time out as well until the
+//! backend is replaced, e.g. by a CPU fallback.
+
+use super::InferenceBackend;
+use crate::error::PluginError;
+use crate::models::*;
+use crate::utils::buffer_pool::recycle_image;
+use crate::utils::panic;
+use image::DynamicImage;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+
+/// The backend currently used for detection
+#[derive(Clone)]
+struct ActiveBackend {
+    backend: Arc<Mutex<Box<dyn InferenceBackend>>>,
+    acceleration: AccelerationBackend,
+    model: String,
+}
+
+/// Shared, replaceable inference backend
+pub struct BackendHandle {
+    active: RwLock<ActiveBackend>,
+}
+
+impl BackendHandle {
+    /// Wrap a backend running on `acceleration`
+    pub fn new(backend: Box<dyn InferenceBackend>, acceleration: AccelerationBackend) -> Self {
+        Self {
+            active: RwLock::new(ActiveBackend::new(backend, acceleration)),
+        }
+    }
+
+    /// Acceleration backend the current backend runs on
+    pub fn acceleration(&self) -> AccelerationBackend {
+        self.active.read().unwrap().acceleration
+    }
+
+    /// Model the current backend is running
+    pub fn active_model(&self) -> String {
+        self.active.read().unwrap().model.clone()
+    }
+
+    /// Use `backend` for all following detections
+    ///
+    /// A detection still running on the previous backend is left to finish.
+    pub fn replace(&self, backend: Box<dyn InferenceBackend>, acceleration: AccelerationBackend) {
+        *self.active.write().unwrap() = ActiveBackend::new(backend, acceleration);
+    }
+
+    /// Detect faces in each image, one result per image
+    ///
+    /// The images are returned to the frame buffer pool afterwards. Fails
+    /// with [`PluginError::Timeout`] if waiting for the backend and running
+    /// it takes longer than `timeout`.
+    pub async fn detect_all(
+        &self,
+        images: Vec<DynamicImage>,
+        timestamp: i64,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Vec<Face>>, PluginError> {
+        let backend = self.active.read().unwrap().backend.clone();
+        let detection = async move {
+            let mut backend = backend.lock_owned().await;
+            tokio::task::spawn_blocking(move || {
+                let faces = panic::guard(|| images.iter().map(|image| backend.detect(image, timestamp)).collect());
+                images.into_iter().for_each(recycle_image);
+                faces
+            })
+            .await
+            .map_err(|e| PluginError::ThreadingError(format!("Detection task failed: {}", e)))?
+        };
+
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, detection).await.unwrap_or(Err(PluginError::Timeout {
+                timeout_ms: timeout.as_millis() as u32,
+            })),
+            None => detection.await,
+        }
+    }
+}
+
+impl ActiveBackend {
+    fn new(backend: Box<dyn InferenceBackend>, acceleration: AccelerationBackend) -> Self {
+        Self {
+            model: backend.active_model(),
+            backend: Arc::new(Mutex::new(backend)),
+            acceleration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Backend taking `delay` per image and finding no faces
+    struct SlowBackend {
+        delay: Duration,
+    }
+
+    impl InferenceBackend for SlowBackend {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        fn active_model(&self) -> String {
+            format!("slow-{}ms", self.delay.as_millis())
+        }
+
+        fn detect(&mut self, _image: &DynamicImage, _timestamp: i64) -> Result<Vec<Face>, PluginError> {
+            std::thread::sleep(self.delay);
+            Ok(Vec::new())
+        }
+    }
+
+    fn image() -> DynamicImage {
+        DynamicImage::new_rgb8(8, 8)
+    }
+
+    #[test]
+    fn test_hung_detection_times_out_until_replaced() {
+        let handle = BackendHandle::new(Box::new(SlowBackend { delay: Duration::from_millis(300) }), AccelerationBackend::NNAPI);
+        let timeout = Some(Duration::from_millis(50));
+
+        crate::block_on(async {
+            let result = handle.detect_all(vec![image()], 0, timeout).await;
+            assert!(matches!(result, Err(PluginError::Timeout { timeout_ms: 50 })));
+
+            // The abandoned detection still holds the backend
+            let result = handle.detect_all(vec![image()], 33, timeout).await;
+            assert!(matches!(result, Err(PluginError::Timeout { .. })));
+
+            handle.replace(Box::new(SlowBackend { delay: Duration::ZERO }), AccelerationBackend::CPU);
+            let faces = handle.detect_all(vec![image(), image()], 66, timeout).await.unwrap();
+            assert_eq!(faces.len(), 2);
+            assert_eq!(handle.acceleration(), AccelerationBackend::CPU);
+            assert_eq!(handle.active_model(), "slow-0ms");
+        });
+    }
+}
@@ -0,0 +1,83 @@
+//! Pluggable inference backends
+//!
+//! The tracker only needs "image in, faces out" from the models. Each backend
+//! wraps one inference stack behind [`InferenceBackend`], so models can be
+//! swapped without touching the tracker or API layers. The backend is picked
+//! at runtime through `TrackerConfig::inference_backend`.
+
+pub mod handle;
+pub mod openseeface;
+#[cfg(feature = "tract")]
+pub mod tract;
+
+use crate::api::TrackerConfig;
+use crate::error::PluginError;
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// Inference stack used to run the tracking models
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InferenceBackendKind {
+    /// openseeface-rs detection, landmarks, pose and gaze
+    OpenSeeFace,
+    /// Pure-Rust tract runtime with an UltraFace ONNX detector
+    /// (bounding boxes only; requires the `tract` feature)
+    Tract {
+        /// Path to the UltraFace RFB-320 ONNX model, or the name of a model
+        /// loaded with `load_models_from_bytes`
+        model_path: String,
+    },
+}
+
+/// Face detection and landmark inference on upright RGB images
+pub trait InferenceBackend: Send + Sync {
+    /// Name reported in logs
+    fn name(&self) -> &'static str;
+
+    /// Model the backend is running, as reported in the tracker status
+    fn active_model(&self) -> String;
+
+    /// Find faces in `image`
+    ///
+    /// Coordinates are in image pixels and IDs are detection indices;
+    /// persistent IDs are assigned by the tracker afterwards.
+    fn detect(&mut self, image: &DynamicImage, timestamp: i64) -> Result<Vec<Face>, PluginError>;
+}
+
+/// Create the configured backend on an acceleration backend
+pub fn create(config: &TrackerConfig, acceleration: AccelerationBackend) -> Result<Box<dyn InferenceBackend>, PluginError> {
+    match &config.inference_backend {
+        InferenceBackendKind::OpenSeeFace => {
+            Ok(Box::new(openseeface::OpenSeeFaceBackend::new(config, acceleration)?))
+        }
+        #[cfg(feature = "tract")]
+        InferenceBackendKind::Tract { model_path } => {
+            Ok(Box::new(tract::TractBackend::new(model_path, config, acceleration)?))
+        }
+        #[cfg(not(feature = "tract"))]
+        InferenceBackendKind::Tract { .. } => Err(PluginError::InvalidConfiguration(
+            "The tract backend requires building with the `tract` feature".to_string(),
+        )),
+    }
+}
+
+
+#[cfg(all(test, not(feature = "tract")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tract_requires_feature() {
+        let config = TrackerConfig {
+            inference_backend: InferenceBackendKind::Tract { model_path: "ultraface.onnx".to_string() },
+            ..TrackerConfig::default()
+        };
+        assert!(matches!(
+            create(&config, AccelerationBackend::CPU),
+            Err(PluginError::InvalidConfiguration(_))
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+//! openseeface-rs inference backend
+
+use super::InferenceBackend;
+use crate::api::TrackerConfig;
+use crate::error::PluginError;
+use crate::face_tracking::acceleration::GraphOptimizationLevel;
+use crate::models::manager::{self, ModelSet};
+use crate::models::*;
+use image::DynamicImage;
+use openseeface::{Tracker as OpenSeeFaceTracker, TrackerConfig as OSFConfig};
+
+/// Detection, landmarks, pose and gaze from openseeface-rs
+pub struct OpenSeeFaceBackend {
+    tracker: OpenSeeFaceTracker,
+    /// File name of the landmark model in use
+    landmark_model: String,
+    enable_landmarks: bool,
+    enable_pose_estimation: bool,
+    enable_gaze_tracking: bool,
+}
+
+impl OpenSeeFaceBackend {
+    /// Create the openseeface-rs tracker on an acceleration backend
+    pub fn new(config: &TrackerConfig, acceleration: AccelerationBackend) -> Result<Self, PluginError> {
+        // openseeface-rs builds its inference sessions with the default CPU
+        // execution provider and has no option to attach a delegate yet
+        if acceleration != AccelerationBackend::CPU {
+            return Err(PluginError::TrackerInitialization(format!(
+                "{:?} delegate is not supported by openseeface-rs",
+                acceleration
+            )));
+        }
+
+        let quality_level = config.landmark_quality_level();
+        let landmark_model = manager::landmark_model(quality_level).ok_or_else(|| {
+            PluginError::InvalidConfiguration(format!("Unknown model quality level {}", quality_level))
+        })?;
+
+        // Resolve and verify the models before openseeface-rs fails on them
+        let model_dir = manager::resolve_model_dir(
+            config.model_path.as_deref(),
+            ModelSet::for_quality_level(quality_level),
+        )?;
+        
+        let landmark_model = manager::variant_file(landmark_model, config.model_precision);
+        if config.model_precision != ModelPrecision::Float32 {
+            match &model_dir {
+                Some(dir) if dir.join(&landmark_model).is_file() => manager::verify_file(dir, &landmark_model)?,
+                _ => {
+                    return Err(PluginError::InvalidConfiguration(format!(
+                        "Quantized model {} not found; provide it through model_path or load_models_from_bytes",
+                        landmark_model
+                    )))
+                }
+            }
+        }
+
+        // The graph optimization level is fixed inside openseeface-rs
+        if config.inference.graph_optimization != GraphOptimizationLevel::All {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Graph optimization {:?} is not supported by openseeface-rs",
+                config.inference.graph_optimization
+            )));
+        }
+
+        // Convert our config to OpenSeeFace config
+        let osf_config = OSFConfig {
+            // openseeface-rs selects the landmark model by name
+            model_name: landmark_model.trim_end_matches(".onnx").to_string(),
+            confidence_threshold: config.confidence_threshold,
+            max_faces: config.max_faces as usize,
+            threads: config.threading.resolved_inference_threads(config.inference.num_threads),
+            // Custom, bundled or downloaded models, if any
+            model_dir,
+            // Additional openseeface-rs specific settings
+            ..Default::default()
+        };
+
+        let tracker = OpenSeeFaceTracker::new(osf_config)
+            .map_err(|e| PluginError::TrackerInitialization(format!("Failed to create tracker: {}", e)))?;
+
+        Ok(Self {
+            tracker,
+            landmark_model,
+            enable_landmarks: config.enable_landmarks,
+            enable_pose_estimation: config.enable_pose_estimation,
+            enable_gaze_tracking: config.enable_gaze_tracking,
+        })
+    }
+}
+
+impl InferenceBackend for OpenSeeFaceBackend {
+    fn name(&self) -> &'static str {
+        "openseeface-rs"
+    }
+
+    fn active_model(&self) -> String {
+        self.landmark_model.clone()
+    }
+
+    fn detect(&mut self, image: &DynamicImage, timestamp: i64) -> Result<Vec<Face>, PluginError> {
+        // openseeface-rs expects the current timestamp
+        let now = chrono::Utc::now().timestamp_millis();
+        self.tracker.detect(image, now)
+            .map_err(|e| PluginError::ProcessingError(format!("Detection failed: {}", e)))?;
+
+        let mut faces = Vec::new();
+        
+        // Get faces from openseeface-rs tracker
+        for (id, osf_face) in self.tracker.faces().iter().enumerate() {
+            let bounding_box = BoundingBox {
+                x: osf_face.bbox.x,
+                y: osf_face.bbox.y,
+                width: osf_face.bbox.width,
+                height: osf_face.bbox.height,
+            };
+
+            // Convert landmarks if enabled and available
+            let landmarks = if self.enable_landmarks && !osf_face.landmarks.is_empty() {
+                let points: Vec<Point2D> = osf_face.landmarks
+                    .iter()
+                    .map(|lm| Point2D { x: lm.x, y: lm.y })
+                    .collect();
+                
+                // openseeface-rs provides confidence per face, not per landmark
+                let confidences = vec![osf_face.confidence; points.len()];
+                
+                Some(FacialLandmarks { points, confidences })
+            } else {
+                None
+            };
+
+            // Convert pose if enabled and available
+            let pose = if self.enable_pose_estimation && osf_face.pose.is_some() {
+                let osf_pose = osf_face.pose.as_ref().unwrap();
+                Some(HeadPose::from_euler(
+                    osf_pose.rotation.x,
+                    osf_pose.rotation.y,
+                    osf_pose.rotation.z,
+                    Point3D {
+                        x: osf_pose.translation.x,
+                        y: osf_pose.translation.y,
+                        z: osf_pose.translation.z,
+                    },
+                    osf_pose.confidence,
+                ))
+            } else {
+                None
+            };
+
+            // Eye gaze tracking (if supported by openseeface-rs)
+            let gaze = if self.enable_gaze_tracking {
+                // Check if openseeface-rs provides gaze data
+                if let Some(osf_gaze) = &osf_face.gaze {
+                    Some(EyeGaze {
+                        left_eye_direction: Point3D {
+                            x: osf_gaze.left_eye.x,
+                            y: osf_gaze.left_eye.y,
+                            z: osf_gaze.left_eye.z,
+                        },
+                        right_eye_direction: Point3D {
+                            x: osf_gaze.right_eye.x,
+                            y: osf_gaze.right_eye.y,
+                            z: osf_gaze.right_eye.z,
+                        },
+                        combined_direction: Point3D {
+                            x: (osf_gaze.left_eye.x + osf_gaze.right_eye.x) / 2.0,
+                            y: (osf_gaze.left_eye.y + osf_gaze.right_eye.y) / 2.0,
+                            z: (osf_gaze.left_eye.z + osf_gaze.right_eye.z) / 2.0,
+                        },
+                        confidence: osf_gaze.confidence,
+                        screen_gaze: None,
+                    })
+                } else {
+                    // Fallback: estimate gaze from eye landmarks if available
+                    None
+                }
+            } else {
+                None
+            };
+
+            faces.push(Face {
+                id: id as u32,
+                bounding_box,
+                confidence: osf_face.confidence,
+                landmarks,
+                pose,
+                gaze,
+                timestamp,
+                ..Face::default()
+            });
+        }
+
+        Ok(faces)
+    }
+}
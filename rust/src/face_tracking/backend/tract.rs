@@ -0,0 +1,143 @@
+//! tract inference backend
+//!
+//! Runs the UltraFace RFB-320 detector with the pure-Rust tract runtime, so
+//! no native ONNX Runtime library is needed. UltraFace only predicts bounding
+//! boxes; faces from this backend carry no landmarks, pose or gaze.
+
+use super::InferenceBackend;
+use crate::api::TrackerConfig;
+use crate::error::PluginError;
+use crate::face_tracking::association::iou;
+use crate::models::manager;
+use crate::models::*;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use tract_onnx::prelude::*;
+
+/// Input width expected by UltraFace RFB-320
+const INPUT_WIDTH: usize = 320;
+
+/// Input height expected by UltraFace RFB-320
+const INPUT_HEIGHT: usize = 240;
+
+/// Overlap above which the weaker of two detections is suppressed
+const NMS_IOU: f32 = 0.3;
+
+/// UltraFace detection on the tract runtime
+pub struct TractBackend {
+    model: TypedRunnableModel<TypedModel>,
+    model_path: String,
+    confidence_threshold: f32,
+    max_faces: usize,
+}
+
+impl TractBackend {
+    /// Load and optimize the model at `model_path`, or the model blob of
+    /// that name
+    pub fn new(model_path: &str, config: &TrackerConfig, acceleration: AccelerationBackend) -> Result<Self, PluginError> {
+        // tract only runs on the CPU
+        if acceleration != AccelerationBackend::CPU {
+            return Err(PluginError::TrackerInitialization(format!(
+                "{:?} delegate is not supported by tract",
+                acceleration
+            )));
+        }
+
+        let model = load_tract_model(model_path, [1, 3, INPUT_HEIGHT, INPUT_WIDTH])?;
+
+        Ok(Self {
+            model,
+            model_path: model_path.to_string(),
+            confidence_threshold: config.confidence_threshold,
+            max_faces: config.max_faces as usize,
+        })
+    }
+}
+
+/// Load and optimize the ONNX model at `model_path`, or the model blob of
+/// that name, for a single f32 input of `input_shape`
+pub(crate) fn load_tract_model(
+    model_path: &str,
+    input_shape: [usize; 4],
+) -> Result<TypedRunnableModel<TypedModel>, PluginError> {
+    // Models handed over from Dart are looked up by file name
+    let onnx = tract_onnx::onnx();
+    let model = match manager::blob(model_path) {
+        Some(data) => onnx.model_for_read(&mut data.as_slice()),
+        None => onnx.model_for_path(model_path),
+    };
+    model
+        .and_then(|model| model.with_input_fact(0, f32::fact(input_shape).into()))
+        .and_then(|model| model.into_optimized())
+        .and_then(|model| model.into_runnable())
+        .map_err(|e| PluginError::TrackerInitialization(format!("Failed to load {}: {}", model_path, e)))
+}
+
+impl InferenceBackend for TractBackend {
+    fn name(&self) -> &'static str {
+        "tract"
+    }
+
+    fn active_model(&self) -> String {
+        self.model_path.clone()
+    }
+
+    fn detect(&mut self, image: &DynamicImage, timestamp: i64) -> Result<Vec<Face>, PluginError> {
+        let input = image
+            .resize_exact(INPUT_WIDTH as u32, INPUT_HEIGHT as u32, FilterType::Triangle)
+            .to_rgb8();
+
+        // Normalize to roughly [-1, 1] in NCHW layout
+        let tensor: Tensor = tract_ndarray::Array4::from_shape_fn((1, 3, INPUT_HEIGHT, INPUT_WIDTH), |(_, c, y, x)| {
+            (input.get_pixel(x as u32, y as u32)[c] as f32 - 127.0) / 128.0
+        })
+        .into();
+
+        let outputs = self
+            .model
+            .run(tvec!(tensor.into()))
+            .map_err(|e| PluginError::ProcessingError(format!("Detection failed: {}", e)))?;
+
+        // scores: [1, N, 2] (background, face), boxes: [1, N, 4] normalized corners
+        let (scores, boxes) = match (outputs[0].to_array_view::<f32>(), outputs[1].to_array_view::<f32>()) {
+            (Ok(scores), Ok(boxes)) => (scores, boxes),
+            _ => return Err(PluginError::ProcessingError("Unexpected UltraFace output".to_string())),
+        };
+
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let mut candidates: Vec<(f32, BoundingBox)> = (0..scores.shape()[1])
+            .filter(|&i| scores[[0, i, 1]] >= self.confidence_threshold)
+            .map(|i| {
+                let x1 = boxes[[0, i, 0]].clamp(0.0, 1.0) * width;
+                let y1 = boxes[[0, i, 1]].clamp(0.0, 1.0) * height;
+                let x2 = boxes[[0, i, 2]].clamp(0.0, 1.0) * width;
+                let y2 = boxes[[0, i, 3]].clamp(0.0, 1.0) * height;
+                (scores[[0, i, 1]], BoundingBox { x: x1, y: y1, width: x2 - x1, height: y2 - y1 })
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        // Greedy non-maximum suppression
+        let mut kept: Vec<(f32, BoundingBox)> = Vec::new();
+        for (score, bounding_box) in candidates {
+            if kept.len() == self.max_faces {
+                break;
+            }
+            if kept.iter().all(|(_, other)| iou(&bounding_box, other) < NMS_IOU) {
+                kept.push((score, bounding_box));
+            }
+        }
+
+        Ok(kept
+            .into_iter()
+            .enumerate()
+            .map(|(id, (confidence, bounding_box))| Face {
+                id: id as u32,
+                bounding_box,
+                confidence,
+                timestamp,
+                ..Face::default()
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,260 @@
+//! Pipeline micro-benchmark
+//!
+//! Feeds synthetic camera frames through a private tracker for a fixed time
+//! and reports how long each stage took. Results are never sent to the
+//! network outputs, so a benchmark can run next to a live session.
+//!
+//! A short probe run with a reference configuration measures the device's
+//! throughput for [`recommended_config`]; its result is cached for the
+//! lifetime of the process.
+
+use crate::api::TrackerConfig;
+use crate::error::PluginError;
+use crate::face_tracking::tracker::FaceTracker;
+use crate::models::*;
+use crate::utils::memory;
+use flutter_rust_bridge::frb;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+/// Width of the synthetic frames (a typical 720p camera)
+pub const SYNTHETIC_WIDTH: u32 = 1280;
+
+/// Height of the synthetic frames
+pub const SYNTHETIC_HEIGHT: u32 = 720;
+
+/// Longest accepted benchmark duration (ms)
+pub const MAX_BENCHMARK_MS: u32 = 60_000;
+
+/// Distinct synthetic frames cycled through during a run
+const SYNTHETIC_FRAME_COUNT: usize = 4;
+
+/// Duration of the probe run behind `recommended_config` (ms)
+pub const PROBE_DURATION_MS: u32 = 1500;
+
+lazy_static! {
+    /// Result of the first successful probe run
+    static ref PROBE_RESULT: Mutex<Option<BenchmarkResult>> = Mutex::new(None);
+}
+
+/// Settings chosen for a range of measured throughput
+struct PerformanceTier {
+    /// Minimum probe frame rate for this tier
+    min_fps: f32,
+    quality_level: i32,
+    detection_width: u32,
+    detection_height: u32,
+    max_faces: u32,
+    target_fps: u32,
+}
+
+/// Tiers from fastest to slowest devices
+///
+/// Only quality levels 3 and 0 are used, as the standard and lightweight
+/// model sets contain them.
+const PERFORMANCE_TIERS: [PerformanceTier; 4] = [
+    PerformanceTier { min_fps: 90.0, quality_level: 3, detection_width: 640, detection_height: 480, max_faces: 4, target_fps: 60 },
+    PerformanceTier { min_fps: 45.0, quality_level: 3, detection_width: 640, detection_height: 480, max_faces: 2, target_fps: 30 },
+    PerformanceTier { min_fps: 25.0, quality_level: 0, detection_width: 480, detection_height: 360, max_faces: 1, target_fps: 30 },
+    PerformanceTier { min_fps: 0.0, quality_level: 0, detection_width: 320, detection_height: 240, max_faces: 1, target_fps: 15 },
+];
+
+/// Lowest frame rate ever recommended
+const MIN_RECOMMENDED_FPS: u32 = 10;
+
+/// Measurements of a benchmark run
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    /// Frames pushed through the pipeline
+    pub frames_processed: u32,
+    /// Wall-clock duration of the run (ms)
+    pub duration_ms: f32,
+    /// Frames processed per second
+    pub achieved_fps: f32,
+    /// Average time per stage
+    pub mean_times: ProcessingTimes,
+    /// Slowest frame (ms)
+    pub max_frame_ms: f32,
+    /// Peak resident memory of the process, if the platform reports it
+    pub peak_memory_bytes: Option<u64>,
+}
+
+/// Run synthetic frames through the full pipeline for `duration_ms`
+pub async fn run(config: TrackerConfig, duration_ms: u32) -> Result<BenchmarkResult, PluginError> {
+    if duration_ms == 0 || duration_ms > MAX_BENCHMARK_MS {
+        return Err(PluginError::InvalidConfiguration(format!(
+            "Benchmark duration must be between 1 and {} ms",
+            MAX_BENCHMARK_MS
+        )));
+    }
+
+    let tracker = FaceTracker::new(config)?.without_output();
+    let frames: Vec<CameraFrame> = (0..SYNTHETIC_FRAME_COUNT).map(synthetic_frame).collect();
+
+    let duration = Duration::from_millis(duration_ms as u64);
+    let start = Instant::now();
+    let mut sum = ProcessingTimes::default();
+    let mut max_frame_ms: f32 = 0.0;
+    let mut count = 0u32;
+
+    while start.elapsed() < duration {
+        tracker.process_frame(frames[count as usize % frames.len()].clone()).await?;
+
+        let times = tracker.get_stats().await.processing_times;
+        sum.conversion_ms += times.conversion_ms;
+        sum.preprocessing_ms += times.preprocessing_ms;
+        sum.detection_ms += times.detection_ms;
+        sum.landmark_ms += times.landmark_ms;
+        sum.pose_ms += times.pose_ms;
+        sum.total_ms += times.total_ms;
+        max_frame_ms = max_frame_ms.max(times.total_ms);
+        count += 1;
+    }
+
+    let elapsed_ms = start.elapsed().as_secs_f32() * 1000.0;
+    let frames_processed = count.max(1) as f32;
+
+    Ok(BenchmarkResult {
+        frames_processed: count,
+        duration_ms: elapsed_ms,
+        achieved_fps: count as f32 * 1000.0 / elapsed_ms,
+        mean_times: ProcessingTimes {
+            conversion_ms: sum.conversion_ms / frames_processed,
+            preprocessing_ms: sum.preprocessing_ms / frames_processed,
+            detection_ms: sum.detection_ms / frames_processed,
+            landmark_ms: sum.landmark_ms / frames_processed,
+            pose_ms: sum.pose_ms / frames_processed,
+            total_ms: sum.total_ms / frames_processed,
+        },
+        max_frame_ms,
+        peak_memory_bytes: memory::peak_resident_bytes(),
+    })
+}
+
+/// Configuration the probe run measures
+fn probe_config() -> TrackerConfig {
+    TrackerConfig {
+        quality_level: Some(3),
+        detection_width: 640,
+        detection_height: 480,
+        max_faces: 1,
+        ..TrackerConfig::default()
+    }
+}
+
+/// Run the probe benchmark once and cache its result
+pub async fn probe() -> Result<BenchmarkResult, PluginError> {
+    let mut cached = PROBE_RESULT.lock().await;
+    if let Some(result) = cached.as_ref() {
+        return Ok(result.clone());
+    }
+
+    let result = run(probe_config(), PROBE_DURATION_MS).await?;
+    info!("Probe benchmark: {:.1} fps, {:.2} ms per frame", result.achieved_fps, result.mean_times.total_ms);
+    *cached = Some(result.clone());
+    Ok(result)
+}
+
+/// Adapt `base` to the throughput measured by a probe run
+pub fn recommend(base: TrackerConfig, probe: &BenchmarkResult) -> TrackerConfig {
+    let tier = PERFORMANCE_TIERS
+        .iter()
+        .find(|tier| probe.achieved_fps >= tier.min_fps)
+        .unwrap_or(&PERFORMANCE_TIERS[PERFORMANCE_TIERS.len() - 1]);
+
+    // Leave headroom for the camera and the app itself
+    let sustainable_fps = (probe.achieved_fps * 0.8) as u32;
+
+    TrackerConfig {
+        quality_level: Some(tier.quality_level),
+        detection_width: tier.detection_width,
+        detection_height: tier.detection_height,
+        max_faces: tier.max_faces,
+        target_fps: tier.target_fps.min(sustainable_fps).max(MIN_RECOMMENDED_FPS),
+        ..base
+    }
+}
+
+/// Recommended configuration for this device, based on the probe run
+///
+/// Falls back to `base` if the probe cannot run (e.g. models are missing).
+pub async fn recommended_config(base: TrackerConfig) -> TrackerConfig {
+    match probe().await {
+        Ok(result) => recommend(base, &result),
+        Err(e) => {
+            warn!("Probe benchmark failed, using default recommendation: {}", e);
+            base
+        }
+    }
+}
+
+/// YUV420 frame with a gradient shifted by `index`, so frames differ
+fn synthetic_frame(index: usize) -> CameraFrame {
+    let (width, height) = (SYNTHETIC_WIDTH as usize, SYNTHETIC_HEIGHT as usize);
+    let shift = index * 16;
+
+    let mut image_data = Vec::with_capacity(width * height * 3 / 2);
+    for y in 0..height {
+        image_data.extend((0..width).map(|x| ((x + y + shift) & 0xff) as u8));
+    }
+    // Neutral chroma for both quarter-size planes
+    image_data.resize(width * height * 3 / 2, 128);
+
+    CameraFrame {
+        image_data,
+        width: SYNTHETIC_WIDTH,
+        height: SYNTHETIC_HEIGHT,
+        format: ImageFormat::YUV420,
+        timestamp: index as i64,
+        rotation: 0,
+        intrinsics: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_frame_size() {
+        let frame = synthetic_frame(1);
+        assert_eq!(frame.image_data.len(), (SYNTHETIC_WIDTH * SYNTHETIC_HEIGHT * 3 / 2) as usize);
+        assert_ne!(frame.image_data[..64], synthetic_frame(0).image_data[..64]);
+    }
+
+    fn probe_result(achieved_fps: f32) -> BenchmarkResult {
+        BenchmarkResult {
+            frames_processed: 100,
+            duration_ms: 1000.0,
+            achieved_fps,
+            mean_times: ProcessingTimes::default(),
+            max_frame_ms: 0.0,
+            peak_memory_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_recommendation_tiers() {
+        let fast = recommend(TrackerConfig::default(), &probe_result(200.0));
+        assert_eq!((fast.quality_level, fast.max_faces, fast.target_fps), (Some(3), 4, 60));
+
+        let medium = recommend(TrackerConfig::default(), &probe_result(50.0));
+        assert_eq!((medium.max_faces, medium.target_fps), (2, 30));
+
+        // Target frame rate is capped by what the device sustains
+        let slow = recommend(TrackerConfig::default(), &probe_result(30.0));
+        assert_eq!((slow.quality_level, slow.detection_width, slow.target_fps), (Some(0), 480, 24));
+
+        let very_slow = recommend(TrackerConfig::default(), &probe_result(5.0));
+        assert_eq!((very_slow.detection_width, very_slow.target_fps), (320, MIN_RECOMMENDED_FPS));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_duration() {
+        assert!(run(TrackerConfig::default(), 0).await.is_err());
+        assert!(run(TrackerConfig::default(), MAX_BENCHMARK_MS + 1).await.is_err());
+    }
+}
@@ -0,0 +1,285 @@
+//! Blink detection
+//!
+//! Tracks the open/closed state of each eye from its calibrated openness
+//! (see [`Face::eyes`]). Separate close and reopen thresholds (hysteresis) keep landmark
+//! jitter around a single threshold from producing spurious blinks.
+//!
+//! An eye that stays closed for a while with the other eye open the whole
+//! time is a wink and emits a [`WinkEvent`] as soon as it has been held long
+//! enough. Every other closure emits a [`BlinkEvent`] when the eye opens
+//! again. The hold time also absorbs the frame or two by which the eyes of a
+//! normal blink close apart.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Blink detection configuration
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlinkConfig {
+    /// Enable blink detection
+    pub enabled: bool,
+    /// Eye openness (0.0 - 1.0) below which an open eye counts as closed
+    pub close_threshold: f32,
+    /// Eye openness (0.0 - 1.0) above which a closed eye counts as open again
+    pub open_threshold: f32,
+    /// How long one eye must stay closed alone to count as a wink (ms)
+    pub wink_min_ms: u32,
+}
+
+impl Default for BlinkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            close_threshold: 0.3,
+            open_threshold: 0.5,
+            wink_min_ms: 200,
+        }
+    }
+}
+
+impl BlinkConfig {
+    /// Check that the thresholds form a valid hysteresis band
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the threshold constraints
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check((0.0..=1.0).contains(&self.close_threshold), "close_threshold", "must be between 0.0 and 1.0", "Use 0.3");
+        report.check((0.0..=1.0).contains(&self.open_threshold), "open_threshold", "must be between 0.0 and 1.0", "Use 0.5");
+        report.check(
+            self.close_threshold < self.open_threshold,
+            "close_threshold",
+            "must be below open_threshold",
+            "Keep a gap of about 0.2 between the thresholds",
+        );
+        report
+    }
+}
+
+/// A completed blink of one eye
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlinkEvent {
+    /// ID of the blinking face
+    pub face_id: u32,
+    /// Eye that blinked
+    pub eye: EyeSide,
+    /// How long the eye was closed
+    pub duration_ms: u32,
+}
+
+/// A wink: one eye held closed while the other stays open
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WinkEvent {
+    /// ID of the winking face
+    pub face_id: u32,
+    /// Eye that winked
+    pub eye: EyeSide,
+}
+
+/// Blinks and winks recognized in one frame
+#[derive(Debug, Default, PartialEq)]
+pub struct EyeEvents {
+    /// Completed blinks
+    pub blinks: Vec<BlinkEvent>,
+    /// Started winks
+    pub winks: Vec<WinkEvent>,
+}
+
+/// An ongoing closure of one eye
+#[derive(Debug, Clone, Copy)]
+struct Closure {
+    /// Timestamp at which the eye closed
+    since: i64,
+    /// Whether the other eye was closed at some point meanwhile
+    bilateral: bool,
+    /// Whether the closure was already reported as a wink
+    winked: bool,
+}
+
+/// Per-face blink state machine
+#[derive(Debug, Default)]
+struct FaceBlinks {
+    /// Closure of each eye, indexed left then right
+    closures: [Option<Closure>; 2],
+}
+
+/// Blink detection across frames
+#[derive(Debug)]
+pub struct BlinkDetector {
+    config: BlinkConfig,
+    faces: HashMap<u32, FaceBlinks>,
+}
+
+impl BlinkDetector {
+    /// Create a detector with the given thresholds
+    pub fn new(config: BlinkConfig) -> Self {
+        Self {
+            config,
+            faces: HashMap::new(),
+        }
+    }
+
+    /// Update the blink state of all faces and return the completed blinks
+    /// and started winks
+    ///
+    /// Sets [`Face::blink`] for every face with an eye state. State of
+    /// faces that are no longer present is discarded.
+    pub fn update(&mut self, faces: &mut [Face]) -> EyeEvents {
+        let mut events = EyeEvents::default();
+        if !self.config.enabled {
+            return events;
+        }
+
+        self.faces.retain(|id, _| faces.iter().any(|f| f.id == *id));
+
+        const EYES: [EyeSide; 2] = [EyeSide::Left, EyeSide::Right];
+        for face in faces.iter_mut() {
+            let openness = match face.eyes {
+                Some(eyes) => [eyes.left_eye_openness, eyes.right_eye_openness],
+                None => continue,
+            };
+            let state = self.faces.entry(face.id).or_default();
+
+            for (i, eye) in EYES.into_iter().enumerate() {
+                match state.closures[i] {
+                    None if openness[i] < self.config.close_threshold => {
+                        state.closures[i] = Some(Closure { since: face.timestamp, bilateral: false, winked: false });
+                    }
+                    Some(closure) if openness[i] > self.config.open_threshold => {
+                        state.closures[i] = None;
+                        if !closure.winked {
+                            events.blinks.push(BlinkEvent {
+                                face_id: face.id,
+                                eye,
+                                duration_ms: (face.timestamp - closure.since).max(0) as u32,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let both_closed = state.closures.iter().all(Option::is_some);
+            for (i, eye) in EYES.into_iter().enumerate() {
+                if let Some(closure) = state.closures[i].as_mut() {
+                    closure.bilateral |= both_closed;
+                    let held = face.timestamp - closure.since >= self.config.wink_min_ms as i64;
+                    if held && !closure.bilateral && !closure.winked {
+                        closure.winked = true;
+                        events.winks.push(WinkEvent { face_id: face.id, eye });
+                    }
+                }
+            }
+
+            face.blink = Some(BlinkState {
+                left_eye_closed: state.closures[0].is_some(),
+                right_eye_closed: state.closures[1].is_some(),
+            });
+        }
+
+        events
+    }
+
+    /// Forget all blink state
+    pub fn reset(&mut self) {
+        self.faces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A face whose eyes have the given openness (0.0 - 1.0)
+    fn face(left: f32, right: f32, timestamp: i64) -> Face {
+        Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            eyes: Some(EyeState {
+                left_eye_openness: left,
+                right_eye_openness: right,
+                left_eye_ratio: 0.0,
+                right_eye_ratio: 0.0,
+            }),
+            timestamp,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_blink_reports_duration() {
+        let mut detector = BlinkDetector::new(BlinkConfig::default());
+
+        assert_eq!(detector.update(&mut [face(1.0, 1.0, 0)]), EyeEvents::default());
+
+        let mut closed = [face(0.1, 0.9, 100)];
+        assert_eq!(detector.update(&mut closed), EyeEvents::default());
+        assert_eq!(closed[0].blink, Some(BlinkState { left_eye_closed: true, right_eye_closed: false }));
+
+        let events = detector.update(&mut [face(0.9, 0.9, 250)]);
+        assert_eq!(events.blinks, vec![BlinkEvent { face_id: 0, eye: EyeSide::Left, duration_ms: 150 }]);
+    }
+
+    #[test]
+    fn test_wink_is_not_a_blink() {
+        let mut detector = BlinkDetector::new(BlinkConfig::default());
+        detector.update(&mut [face(1.0, 1.0, 0)]);
+
+        // Right eye held closed alone
+        assert_eq!(detector.update(&mut [face(1.0, 0.0, 100)]), EyeEvents::default());
+        let events = detector.update(&mut [face(1.0, 0.0, 300)]);
+        assert_eq!(events.winks, vec![WinkEvent { face_id: 0, eye: EyeSide::Right }]);
+        assert!(detector.update(&mut [face(1.0, 0.0, 400)]).winks.is_empty());
+        assert_eq!(detector.update(&mut [face(1.0, 1.0, 500)]), EyeEvents::default());
+    }
+
+    #[test]
+    fn test_staggered_long_blink_is_not_a_wink() {
+        let mut detector = BlinkDetector::new(BlinkConfig::default());
+
+        // The left eye closes a frame before the right one
+        let mut events = EyeEvents::default();
+        for (left, right, timestamp) in [(0.0, 1.0, 0), (0.0, 0.0, 33), (0.0, 0.0, 400), (1.0, 1.0, 433)] {
+            let frame = detector.update(&mut [face(left, right, timestamp)]);
+            events.blinks.extend(frame.blinks);
+            events.winks.extend(frame.winks);
+        }
+        assert!(events.winks.is_empty());
+        assert_eq!(events.blinks.len(), 2);
+    }
+
+    #[test]
+    fn test_hysteresis_ignores_jitter() {
+        let mut detector = BlinkDetector::new(BlinkConfig::default());
+
+        // Hovering between the thresholds neither closes an open eye...
+        for (i, openness) in [0.4, 0.35, 0.45, 0.4].into_iter().enumerate() {
+            assert_eq!(detector.update(&mut [face(openness, 1.0, i as i64 * 33)]), EyeEvents::default());
+        }
+
+        // ...nor reopens a closed one
+        detector.update(&mut [face(0.1, 1.0, 200)]);
+        for (i, openness) in [0.35, 0.45, 0.4].into_iter().enumerate() {
+            let mut faces = [face(openness, 1.0, 233 + i as i64 * 33)];
+            assert!(detector.update(&mut faces).blinks.is_empty());
+            assert_eq!(faces[0].blink.map(|b| b.left_eye_closed), Some(true));
+        }
+    }
+
+    #[test]
+    fn test_rejects_inverted_thresholds() {
+        let config = BlinkConfig { close_threshold: 0.6, open_threshold: 0.4, ..BlinkConfig::default() };
+        assert!(config.validate().is_err());
+        assert!(BlinkConfig::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,163 @@
+//! Face stream fan-out
+//!
+//! The tracking stream can feed several Dart subscribers at once, e.g. an
+//! avatar renderer taking every frame and a diagnostics panel refreshing a
+//! few times a second. Each subscriber gets its own small queue and delivery
+//! task, so a slow subscriber only loses its own frames under its
+//! [`QueuePolicy`] and packing buffers for one does not delay the others.
+//! A subscriber is removed once Dart cancels its stream.
+
+use crate::face_tracking::frame_queue::FrameQueue;
+use crate::face_tracking::tracker::FaceStreamSink;
+use crate::models::{Face, QueuePolicy};
+use flutter_rust_bridge::frb;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
+
+/// Frames waiting for one subscriber
+const SUBSCRIBER_QUEUE_CAPACITY: usize = 2;
+
+/// How one subscriber receives the tracking stream
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FaceSubscriberOptions {
+    /// Most frames per second delivered to this subscriber (0 = every frame)
+    pub max_fps: u32,
+    /// What happens to frames while the subscriber falls behind; `Block`
+    /// slows tracking down to this subscriber's pace
+    pub queue_policy: QueuePolicy,
+}
+
+impl Default for FaceSubscriberOptions {
+    fn default() -> Self {
+        Self {
+            max_fps: 0,
+            queue_policy: QueuePolicy::DropOldest,
+        }
+    }
+}
+
+/// Faces of one processed frame, shared by all subscribers
+struct FaceFrame {
+    faces: Vec<Face>,
+    timestamp: i64,
+}
+
+struct Subscriber {
+    queue: Arc<FrameQueue<Arc<FaceFrame>>>,
+    /// Shortest time between two delivered frames
+    min_interval: Option<Duration>,
+    last_delivered: Option<Instant>,
+}
+
+impl Subscriber {
+    /// Whether a frame published at `now` is due under `max_fps`
+    ///
+    /// Frames up to 10% early are accepted, as in the stream's frame throttle.
+    fn admit(&mut self, now: Instant) -> bool {
+        if let (Some(interval), Some(last)) = (self.min_interval, self.last_delivered) {
+            if now + interval / 10 < last + interval {
+                return false;
+            }
+        }
+        self.last_delivered = Some(now);
+        true
+    }
+}
+
+/// Subscribers of the tracking stream
+#[derive(Default)]
+pub struct FaceBroadcast {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl FaceBroadcast {
+    /// Deliver the faces of every published frame to `sink`
+    pub fn subscribe(&self, sink: FaceStreamSink, options: FaceSubscriberOptions) {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let queue = Arc::new(FrameQueue::new(SUBSCRIBER_QUEUE_CAPACITY, options.queue_policy, dropped.clone()));
+        crate::runtime().spawn(deliver(queue.clone(), sink, dropped));
+
+        self.subscribers.lock().unwrap().push(Subscriber {
+            queue,
+            min_interval: (options.max_fps > 0).then(|| Duration::from_secs_f64(1.0 / options.max_fps as f64)),
+            last_delivered: None,
+        });
+    }
+
+    /// Number of open subscriptions
+    pub fn subscriber_count(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| !subscriber.queue.is_closed());
+        subscribers.len()
+    }
+
+    /// Hand the faces of a frame to every subscriber that is due
+    ///
+    /// Waits only for subscribers with [`QueuePolicy::Block`] that are full.
+    pub async fn publish(&self, faces: Vec<Face>, timestamp: i64) {
+        let now = Instant::now();
+        // Queues are pushed outside the lock, since a push may wait
+        let due: Vec<_> = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            subscribers.retain(|subscriber| !subscriber.queue.is_closed());
+            subscribers
+                .iter_mut()
+                .filter_map(|subscriber| subscriber.admit(now).then(|| subscriber.queue.clone()))
+                .collect()
+        };
+        if due.is_empty() {
+            return;
+        }
+
+        let frame = Arc::new(FaceFrame { faces, timestamp });
+        for queue in due {
+            // A closed queue belongs to a subscriber that just went away
+            let _ = queue.push(frame.clone()).await;
+        }
+    }
+
+    /// End every subscription
+    pub fn close(&self) {
+        for subscriber in self.subscribers.lock().unwrap().drain(..) {
+            subscriber.queue.close();
+        }
+    }
+}
+
+/// Delivery task of one subscriber
+async fn deliver(queue: Arc<FrameQueue<Arc<FaceFrame>>>, sink: FaceStreamSink, dropped: Arc<AtomicU64>) {
+    while let Some(frame) = queue.pop().await {
+        if !sink.send(&frame.faces, frame.timestamp) {
+            debug!("Face stream subscriber closed");
+            queue.close();
+            break;
+        }
+    }
+    debug!("Face stream subscription ended, {} frames dropped", dropped.load(Ordering::Relaxed));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_fps_skips_early_frames() {
+        let mut subscriber = Subscriber {
+            queue: Arc::new(FrameQueue::new(1, QueuePolicy::DropOldest, Arc::new(AtomicU64::new(0)))),
+            min_interval: Some(Duration::from_millis(100)),
+            last_delivered: None,
+        };
+        let start = Instant::now();
+
+        assert!(subscriber.admit(start));
+        assert!(!subscriber.admit(start + Duration::from_millis(50)));
+        assert!(subscriber.admit(start + Duration::from_millis(95)));
+        assert!(!subscriber.admit(start + Duration::from_millis(150)));
+        assert!(subscriber.admit(start + Duration::from_millis(200)));
+    }
+}
@@ -0,0 +1,394 @@
+//! YUV to RGB color conversion
+//!
+//! Converters for the YUV layouts delivered by cameras. The default backend is
+//! portable Rust; building with the `yuv` cargo feature routes conversions
+//! through the SIMD-accelerated `yuv` crate instead. Both backends use full
+//! range BT.601 coefficients, so results only differ by rounding.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::utils::buffer_pool::FRAME_BUFFERS;
+
+#[cfg(not(feature = "yuv"))]
+use portable as backend;
+#[cfg(feature = "yuv")]
+use yuv_backend as backend;
+
+/// Name of the active conversion backend
+pub const BACKEND: &str = if cfg!(feature = "yuv") { "yuv" } else { "portable" };
+
+/// Borrowed view of one image plane
+#[derive(Debug, Clone, Copy)]
+pub struct PlaneView<'a> {
+    pub data: &'a [u8],
+    /// Bytes between the starts of two consecutive rows
+    pub row_stride: usize,
+    /// Bytes between two consecutive samples in a row
+    pub pixel_stride: usize,
+}
+
+impl<'a> From<&'a ImagePlane> for PlaneView<'a> {
+    fn from(plane: &'a ImagePlane) -> Self {
+        Self {
+            data: &plane.data,
+            row_stride: plane.row_stride as usize,
+            pixel_stride: plane.pixel_stride as usize,
+        }
+    }
+}
+
+/// Convert pixel data of any supported format into a pooled RGB buffer
+pub fn to_rgb(data: &[u8], width: u32, height: u32, format: ImageFormat) -> Result<Vec<u8>, PluginError> {
+    let pixel_count = (width * height) as usize;
+    
+    let rgb_data = match format {
+        ImageFormat::RGB => {
+            let mut rgb_data = FRAME_BUFFERS.acquire(pixel_count * 3);
+            rgb_data.extend_from_slice(&data[..(pixel_count * 3).min(data.len())]);
+            rgb_data
+        }
+        ImageFormat::RGBA => {
+            // Convert RGBA to RGB
+            let mut rgb_data = FRAME_BUFFERS.acquire(pixel_count * 3);
+            for p in data.chunks_exact(4).take(pixel_count) {
+                rgb_data.extend_from_slice(&[p[0], p[1], p[2]]);
+            }
+            rgb_data
+        }
+        // Convert YUV420 to RGB
+        ImageFormat::YUV420 => i420_to_rgb(data, width, height)?,
+        // Similar to YUV420 but with interleaved chroma
+        ImageFormat::NV21 => nv21_to_rgb(data, width, height)?,
+        // Same layout as NV21 with U and V swapped
+        ImageFormat::NV12 => nv12_to_rgb(data, width, height)?,
+        ImageFormat::YUY2 => yuy2_to_rgb(data, width, height)?,
+        ImageFormat::GRAY8 => {
+            // Replicate luminance into all three channels
+            let mut rgb_data = FRAME_BUFFERS.acquire(pixel_count * 3);
+            for &l in data.iter().take(pixel_count) {
+                rgb_data.extend_from_slice(&[l, l, l]);
+            }
+            rgb_data
+        }
+        ImageFormat::BGRA => {
+            // Convert BGRA to RGB, swapping B and R channels
+            let mut rgb_data = FRAME_BUFFERS.acquire(pixel_count * 3);
+            for p in data.chunks_exact(4).take(pixel_count) {
+                rgb_data.extend_from_slice(&[p[2], p[1], p[0]]);
+            }
+            rgb_data
+        }
+    };
+    
+    Ok(rgb_data)
+}
+
+/// Convert packed I420 (Y plane, then U and V quarter planes) to RGB
+pub fn i420_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+    let y_size = (width * height) as usize;
+    if data.len() < y_size + 2 * (y_size / 4) {
+        return Err(PluginError::ImageConversion("Invalid YUV420 data size".to_string()));
+    }
+
+    backend::i420_to_rgb(data, width, height)
+}
+
+/// Convert NV21 (Y plane, then interleaved V/U, Android camera) to RGB
+pub fn nv21_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+    check_semi_planar_size(data, width, height, "NV21")?;
+    backend::semi_planar_to_rgb(data, width, height, true)
+}
+
+/// Convert NV12 (Y plane, then interleaved U/V, Windows Media Foundation) to RGB
+pub fn nv12_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+    check_semi_planar_size(data, width, height, "NV12")?;
+    backend::semi_planar_to_rgb(data, width, height, false)
+}
+
+/// Convert YUY2 (packed Y0 U Y1 V, 4:2:2) to RGB
+pub fn yuy2_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+    if data.len() < (width * height * 2) as usize {
+        return Err(PluginError::ImageConversion("Invalid YUY2 data size".to_string()));
+    }
+
+    backend::yuy2_to_rgb(data, width, height)
+}
+
+/// Convert a three-plane YUV 4:2:0 frame to RGB, honoring row and pixel strides
+pub fn planar_yuv420_to_rgb(frame: &PlanarCameraFrame) -> Result<Vec<u8>, PluginError> {
+    let (y_plane, u_plane, v_plane) = match frame.planes.as_slice() {
+        [y, u, v] => (y, u, v),
+        planes => {
+            return Err(PluginError::ImageConversion(format!(
+                "Planar YUV420 frame needs 3 planes, got {}",
+                planes.len()
+            )))
+        }
+    };
+
+    let (width, height) = (frame.width, frame.height);
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+
+    if y_plane.data.len() < y_plane.required_len(width, height)
+        || u_plane.data.len() < u_plane.required_len(chroma_width, chroma_height)
+        || v_plane.data.len() < v_plane.required_len(chroma_width, chroma_height)
+    {
+        return Err(PluginError::ImageConversion("Invalid planar YUV420 data size".to_string()));
+    }
+
+    backend::planar_to_rgb(y_plane.into(), u_plane.into(), v_plane.into(), width, height)
+}
+
+fn check_semi_planar_size(data: &[u8], width: u32, height: u32, format: &str) -> Result<(), PluginError> {
+    let y_size = (width * height) as usize;
+    if data.len() < y_size + y_size / 2 {
+        return Err(PluginError::ImageConversion(format!("Invalid {} data size", format)));
+    }
+    Ok(())
+}
+
+/// Hand-written converters, always available as a fallback
+#[cfg_attr(feature = "yuv", allow(dead_code))]
+mod portable {
+    use super::{PlaneView, FRAME_BUFFERS};
+    use crate::error::PluginError;
+
+    /// Convert a single YUV sample to RGB using the standard (BT.601) coefficients
+    pub fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+        let y = y as f32;
+        let u = u as f32 - 128.0;
+        let v = v as f32 - 128.0;
+
+        [
+            (y + 1.402 * v).clamp(0.0, 255.0) as u8,
+            (y - 0.344 * u - 0.714 * v).clamp(0.0, 255.0) as u8,
+            (y + 1.772 * u).clamp(0.0, 255.0) as u8,
+        ]
+    }
+
+    /// Convert any 4:2:0 layout described by three plane views
+    pub fn planar_to_rgb(
+        y_plane: PlaneView,
+        u_plane: PlaneView,
+        v_plane: PlaneView,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, PluginError> {
+        let mut rgb_data = FRAME_BUFFERS.acquire((width * height * 3) as usize);
+
+        for y in 0..height as usize {
+            let y_row = y * y_plane.row_stride;
+            let u_row = (y / 2) * u_plane.row_stride;
+            let v_row = (y / 2) * v_plane.row_stride;
+
+            for x in 0..width as usize {
+                let y_val = y_plane.data[y_row + x * y_plane.pixel_stride];
+                let u_val = u_plane.data[u_row + (x / 2) * u_plane.pixel_stride];
+                let v_val = v_plane.data[v_row + (x / 2) * v_plane.pixel_stride];
+
+                rgb_data.extend_from_slice(&yuv_to_rgb(y_val, u_val, v_val));
+            }
+        }
+
+        Ok(rgb_data)
+    }
+
+    pub fn i420_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+        let y_size = (width * height) as usize;
+        let uv_size = y_size / 4;
+        let chroma_stride = (width / 2) as usize;
+
+        planar_to_rgb(
+            PlaneView { data, row_stride: width as usize, pixel_stride: 1 },
+            PlaneView { data: &data[y_size..], row_stride: chroma_stride, pixel_stride: 1 },
+            PlaneView { data: &data[y_size + uv_size..], row_stride: chroma_stride, pixel_stride: 1 },
+            width,
+            height,
+        )
+    }
+
+    /// NV21 and NV12 are planar 4:2:0 with U and V interleaved in one plane
+    pub fn semi_planar_to_rgb(data: &[u8], width: u32, height: u32, v_first: bool) -> Result<Vec<u8>, PluginError> {
+        let y_size = (width * height) as usize;
+        let (u_offset, v_offset) = if v_first { (1, 0) } else { (0, 1) };
+
+        planar_to_rgb(
+            PlaneView { data, row_stride: width as usize, pixel_stride: 1 },
+            PlaneView { data: &data[y_size + u_offset..], row_stride: width as usize, pixel_stride: 2 },
+            PlaneView { data: &data[y_size + v_offset..], row_stride: width as usize, pixel_stride: 2 },
+            width,
+            height,
+        )
+    }
+
+    pub fn yuy2_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+        let mut rgb_data = FRAME_BUFFERS.acquire((width * height * 3) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let macropixel = ((y * width + (x & !1)) * 2) as usize;
+                let y_val = data[macropixel + if x & 1 == 0 { 0 } else { 2 }];
+                let u_val = data[macropixel + 1];
+                let v_val = data[macropixel + 3];
+
+                rgb_data.extend_from_slice(&yuv_to_rgb(y_val, u_val, v_val));
+            }
+        }
+
+        Ok(rgb_data)
+    }
+}
+
+/// Converters backed by the `yuv` crate
+#[cfg(feature = "yuv")]
+mod yuv_backend {
+    use super::{portable, PlaneView, FRAME_BUFFERS};
+    use crate::error::PluginError;
+    use yuv::{
+        YuvBiPlanarImage, YuvConversionMode, YuvError, YuvPackedImage, YuvPlanarImage, YuvRange, YuvStandardMatrix,
+    };
+
+    const RANGE: YuvRange = YuvRange::Full;
+    const MATRIX: YuvStandardMatrix = YuvStandardMatrix::Bt601;
+
+    fn conversion_error(e: YuvError) -> PluginError {
+        PluginError::ImageConversion(format!("yuv conversion failed: {}", e))
+    }
+
+    pub fn planar_to_rgb(
+        y_plane: PlaneView,
+        u_plane: PlaneView,
+        v_plane: PlaneView,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, PluginError> {
+        // Interleaved chroma (pixel stride 2) arrives as two separate buffers,
+        // which the yuv crate has no layout for
+        if y_plane.pixel_stride != 1 || u_plane.pixel_stride != 1 || v_plane.pixel_stride != 1 {
+            return portable::planar_to_rgb(y_plane, u_plane, v_plane, width, height);
+        }
+
+        let image = YuvPlanarImage {
+            y_plane: y_plane.data,
+            y_stride: y_plane.row_stride as u32,
+            u_plane: u_plane.data,
+            u_stride: u_plane.row_stride as u32,
+            v_plane: v_plane.data,
+            v_stride: v_plane.row_stride as u32,
+            width,
+            height,
+        };
+
+        let mut rgb_data = FRAME_BUFFERS.acquire_zeroed((width * height * 3) as usize);
+        yuv::yuv420_to_rgb(&image, &mut rgb_data, width * 3, RANGE, MATRIX).map_err(conversion_error)?;
+        Ok(rgb_data)
+    }
+
+    pub fn i420_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+        let y_size = (width * height) as usize;
+        let uv_size = y_size / 4;
+        let chroma_stride = (width / 2) as usize;
+
+        planar_to_rgb(
+            PlaneView { data: &data[..y_size], row_stride: width as usize, pixel_stride: 1 },
+            PlaneView { data: &data[y_size..y_size + uv_size], row_stride: chroma_stride, pixel_stride: 1 },
+            PlaneView { data: &data[y_size + uv_size..y_size + 2 * uv_size], row_stride: chroma_stride, pixel_stride: 1 },
+            width,
+            height,
+        )
+    }
+
+    pub fn semi_planar_to_rgb(data: &[u8], width: u32, height: u32, v_first: bool) -> Result<Vec<u8>, PluginError> {
+        let y_size = (width * height) as usize;
+        let image = YuvBiPlanarImage {
+            y_plane: &data[..y_size],
+            y_stride: width,
+            uv_plane: &data[y_size..y_size + y_size / 2],
+            uv_stride: width,
+            width,
+            height,
+        };
+
+        let mut rgb_data = FRAME_BUFFERS.acquire_zeroed(y_size * 3);
+        let result = if v_first {
+            yuv::yuv_nv21_to_rgb(&image, &mut rgb_data, width * 3, RANGE, MATRIX, YuvConversionMode::Balanced)
+        } else {
+            yuv::yuv_nv12_to_rgb(&image, &mut rgb_data, width * 3, RANGE, MATRIX, YuvConversionMode::Balanced)
+        };
+        result.map_err(conversion_error)?;
+        Ok(rgb_data)
+    }
+
+    pub fn yuy2_to_rgb(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
+        let image = YuvPackedImage {
+            yuy: &data[..(width * height * 2) as usize],
+            yuy_stride: width * 2,
+            width,
+            height,
+        };
+
+        let mut rgb_data = FRAME_BUFFERS.acquire_zeroed((width * height * 3) as usize);
+        yuv::yuyv422_to_rgb(&image, &mut rgb_data, width * 3, RANGE, MATRIX).map_err(conversion_error)?;
+        Ok(rgb_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yuv420_conversion() {
+        // Test with minimal valid YUV420 data
+        let width = 4;
+        let height = 4;
+        let y_size = (width * height) as usize;
+        let uv_size = y_size / 4;
+        let yuv_data = vec![128u8; y_size + 2 * uv_size]; // Gray image
+
+        let result = i420_to_rgb(&yuv_data, width, height);
+        assert!(result.is_ok());
+
+        let rgb_data = result.unwrap();
+        assert_eq!(rgb_data.len(), (width * height * 3) as usize);
+    }
+
+    #[test]
+    fn test_nv12_and_yuy2_conversion() {
+        let width = 4;
+        let height = 2;
+
+        // Neutral chroma gives a gray image in both layouts
+        let nv12_data = vec![128u8; (width * height * 3 / 2) as usize];
+        let rgb_data = nv12_to_rgb(&nv12_data, width, height).unwrap();
+        assert_eq!(rgb_data.len(), (width * height * 3) as usize);
+        assert!(rgb_data.iter().all(|&c| c == 128));
+
+        let yuy2_data = [10u8, 128, 200, 128].repeat((width * height / 2) as usize);
+        let rgb_data = yuy2_to_rgb(&yuy2_data, width, height).unwrap();
+        assert_eq!(&rgb_data[0..6], &[10, 10, 10, 200, 200, 200]);
+
+        assert!(yuy2_to_rgb(&yuy2_data[..4], width, height).is_err());
+    }
+
+    #[test]
+    fn test_planar_conversion_honors_strides() {
+        // 2x2 frame with padded Y rows and interleaved chroma (pixel stride 2)
+        let frame = PlanarCameraFrame {
+            planes: vec![
+                ImagePlane { data: vec![10, 20, 0, 0, 30, 40], row_stride: 4, pixel_stride: 1 },
+                ImagePlane { data: vec![128, 128], row_stride: 2, pixel_stride: 2 },
+                ImagePlane { data: vec![128], row_stride: 2, pixel_stride: 2 },
+            ],
+            width: 2,
+            height: 2,
+            timestamp: 0,
+            rotation: 0,
+            intrinsics: None,
+        };
+
+        let rgb_data = planar_yuv420_to_rgb(&frame).unwrap();
+        let luma: Vec<u8> = rgb_data.chunks(3).map(|p| p[0]).collect();
+        assert_eq!(luma, vec![10, 20, 30, 40]);
+    }
+}
@@ -0,0 +1,159 @@
+//! Model variant comparison
+//!
+//! Runs the same sample frames through several model variants (quality level
+//! and precision) and reports how fast each one is and how far its landmarks
+//! drift from the first, reference variant. Integrators use this to pick a
+//! variant per device class, e.g. whether int8 models are accurate enough.
+
+use crate::api::TrackerConfig;
+use crate::error::PluginError;
+use crate::face_tracking::association::iou;
+use crate::face_tracking::tracker::FaceTracker;
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use tokio::time::Instant;
+
+/// Minimum overlap for a face to be compared with a reference face
+const MIN_MATCH_IOU: f32 = 0.3;
+
+/// A model variant to compare
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelVariant {
+    /// Landmark model quality level (-1 to 4)
+    pub quality_level: i32,
+    /// Model file precision
+    pub precision: ModelPrecision,
+}
+
+/// Measurements for one model variant
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelVariantReport {
+    pub variant: ModelVariant,
+    /// Average processing time per frame (ms)
+    pub mean_latency_ms: f32,
+    /// Slowest frame (ms)
+    pub max_latency_ms: f32,
+    /// Faces found over all sample frames
+    pub faces_detected: u32,
+    /// Mean landmark distance from the reference variant, relative to the
+    /// face size (`None` for the reference or if no faces could be matched)
+    pub landmark_deviation: Option<f32>,
+}
+
+/// Run `frames` through each variant, using the first one as reference
+///
+/// Smoothing and region tracking are disabled so every frame is measured on
+/// its own.
+pub async fn compare_variants(
+    base: &TrackerConfig,
+    frames: &[CameraFrame],
+    variants: &[ModelVariant],
+) -> Result<Vec<ModelVariantReport>, PluginError> {
+    let mut reference: Option<Vec<Vec<Face>>> = None;
+    let mut reports = Vec::with_capacity(variants.len());
+
+    for &variant in variants {
+        let mut config = base.clone();
+        config.quality_level = Some(variant.quality_level);
+        config.model_precision = variant.precision;
+        config.detection_interval = 1;
+        config.smoothing.enabled = false;
+        let tracker = FaceTracker::new(config)?;
+
+        let mut results = Vec::with_capacity(frames.len());
+        let mut total_ms = 0.0;
+        let mut max_latency_ms: f32 = 0.0;
+        for frame in frames {
+            let start = Instant::now();
+            let faces = tracker.analyze_frame(frame.clone()).await?;
+            let elapsed = start.elapsed().as_secs_f32() * 1000.0;
+            total_ms += elapsed;
+            max_latency_ms = max_latency_ms.max(elapsed);
+            results.push(faces);
+        }
+
+        reports.push(ModelVariantReport {
+            variant,
+            mean_latency_ms: if frames.is_empty() { 0.0 } else { total_ms / frames.len() as f32 },
+            max_latency_ms,
+            faces_detected: results.iter().map(|faces| faces.len() as u32).sum(),
+            landmark_deviation: reference.as_ref().and_then(|reference| landmark_deviation(reference, &results)),
+        });
+
+        if reference.is_none() {
+            reference = Some(results);
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Mean relative landmark distance between matched faces of two runs
+fn landmark_deviation(reference: &[Vec<Face>], results: &[Vec<Face>]) -> Option<f32> {
+    let mut total = 0.0;
+    let mut count = 0;
+
+    for (expected_faces, faces) in reference.iter().zip(results) {
+        for expected in expected_faces {
+            let matched = faces
+                .iter()
+                .map(|face| (iou(&expected.bounding_box, &face.bounding_box), face))
+                .filter(|(overlap, _)| *overlap >= MIN_MATCH_IOU)
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+
+            let (Some(expected_landmarks), Some((_, face))) = (&expected.landmarks, matched) else {
+                continue;
+            };
+            let Some(landmarks) = &face.landmarks else {
+                continue;
+            };
+            if landmarks.points.len() != expected_landmarks.points.len() || landmarks.points.is_empty() {
+                continue;
+            }
+
+            let size = expected.bounding_box.width.hypot(expected.bounding_box.height).max(1.0);
+            let distance: f32 = expected_landmarks
+                .points
+                .iter()
+                .zip(&landmarks.points)
+                .map(|(a, b)| (a.x - b.x).hypot(a.y - b.y))
+                .sum();
+            total += distance / landmarks.points.len() as f32 / size;
+            count += 1;
+        }
+    }
+
+    (count > 0).then(|| total / count as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_with_landmarks(offset: f32) -> Face {
+        Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 30.0, height: 40.0 },
+            confidence: 1.0,
+            landmarks: Some(FacialLandmarks {
+                points: vec![Point2D { x: 10.0 + offset, y: 10.0 }, Point2D { x: 20.0 + offset, y: 20.0 }],
+                confidences: vec![1.0, 1.0],
+            }),
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_landmark_deviation() {
+        let reference = vec![vec![face_with_landmarks(0.0)]];
+
+        // Shifting every landmark by 5px on a 50px face deviates by 10%
+        let shifted = vec![vec![face_with_landmarks(5.0)]];
+        let deviation = landmark_deviation(&reference, &shifted).unwrap();
+        assert!((deviation - 0.1).abs() < 1e-6);
+
+        // Nothing to compare against
+        assert_eq!(landmark_deviation(&reference, &[vec![]]), None);
+    }
+}
@@ -0,0 +1,293 @@
+//! Output coordinate conventions
+//!
+//! The tracker works in a single coordinate frame: head rotation as pitch
+//! (X), yaw (Y) and roll (Z) in degrees, applied Z, then X, then Y as in
+//! Unity. Avatar rigs built for VMC, ARKit or other engines expect different
+//! axis directions, Euler orders and angle units. [`OutputConventions`]
+//! converts pose and gaze, and optionally normalizes landmark and bounding box
+//! coordinates, just before faces are returned to the app; internal
+//! processing and the network outputs, which follow their own protocols, are
+//! unaffected.
+
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+
+/// Order in which the Euler rotations are applied, first axis first
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EulerOrder {
+    XYZ,
+    XZY,
+    YXZ,
+    YZX,
+    /// Roll, then pitch, then yaw (Unity, the tracker's own order)
+    ZXY,
+    ZYX,
+}
+
+impl EulerOrder {
+    /// Axis indices in application order
+    fn axes(self) -> [usize; 3] {
+        match self {
+            EulerOrder::XYZ => [0, 1, 2],
+            EulerOrder::XZY => [0, 2, 1],
+            EulerOrder::YXZ => [1, 0, 2],
+            EulerOrder::YZX => [1, 2, 0],
+            EulerOrder::ZXY => [2, 0, 1],
+            EulerOrder::ZYX => [2, 1, 0],
+        }
+    }
+}
+
+/// Unit of the pitch, yaw and roll angles
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AngleUnit {
+    Degrees,
+    Radians,
+}
+
+/// Space of the returned landmark, mesh and bounding box coordinates
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoordinateSpace {
+    /// Pixels of the frame as delivered by the camera
+    Pixels,
+    /// Fractions of the frame width and height (0.0 - 1.0), independent of
+    /// the frame resolution
+    Normalized,
+}
+
+/// Coordinate conventions of the returned faces
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConventions {
+    /// Negate the X axis (right instead of left, or vice versa)
+    pub flip_x: bool,
+    /// Negate the Y axis
+    pub flip_y: bool,
+    /// Negate the Z axis (e.g. to switch handedness)
+    pub flip_z: bool,
+    /// Order the returned Euler angles are decomposed in
+    pub euler_order: EulerOrder,
+    /// Unit of the returned Euler angles
+    pub angle_unit: AngleUnit,
+    /// Space of the returned landmarks, meshes and bounding boxes
+    pub coordinate_space: CoordinateSpace,
+}
+
+impl Default for OutputConventions {
+    fn default() -> Self {
+        Self {
+            flip_x: false,
+            flip_y: false,
+            flip_z: false,
+            euler_order: EulerOrder::ZXY,
+            angle_unit: AngleUnit::Degrees,
+            coordinate_space: CoordinateSpace::Pixels,
+        }
+    }
+}
+
+impl OutputConventions {
+    /// Whether faces are returned in the tracker's own conventions
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Convert `faces` found in a `width` x `height` frame into these
+    /// conventions
+    pub fn apply(&self, faces: &mut [Face], width: u32, height: u32) {
+        if self.is_identity() {
+            return;
+        }
+        for face in faces.iter_mut() {
+            if self.coordinate_space == CoordinateSpace::Normalized {
+                normalize(face, width as f32, height as f32);
+            }
+            if let Some(pose) = face.pose.as_mut() {
+                self.convert_pose(pose);
+            }
+            if let Some(gaze) = face.gaze.as_mut() {
+                for direction in [
+                    &mut gaze.left_eye_direction,
+                    &mut gaze.right_eye_direction,
+                    &mut gaze.combined_direction,
+                ] {
+                    *direction = self.flip(*direction);
+                }
+            }
+        }
+    }
+
+    fn signs(&self) -> [f32; 3] {
+        [self.flip_x, self.flip_y, self.flip_z].map(|flip| if flip { -1.0 } else { 1.0 })
+    }
+
+    fn flip(&self, point: Point3D) -> Point3D {
+        let [sx, sy, sz] = self.signs();
+        Point3D { x: sx * point.x, y: sy * point.y, z: sz * point.z }
+    }
+
+    fn convert_pose(&self, pose: &mut HeadPose) {
+        // Mirroring the axes turns a rotation about `n` into one about
+        // `det * S * n` by the same angle
+        let [sx, sy, sz] = self.signs();
+        let det = sx * sy * sz;
+        let q = pose.rotation_quaternion;
+        let q = Quaternion { x: det * sx * q.x, y: det * sy * q.y, z: det * sz * q.z, w: q.w };
+
+        let angles = decompose(&rotation_matrix(q), self.euler_order);
+        let [pitch, yaw, roll] = match self.angle_unit {
+            AngleUnit::Degrees => angles.map(f32::to_degrees),
+            AngleUnit::Radians => angles,
+        };
+
+        pose.pitch = pitch;
+        pose.yaw = yaw;
+        pose.roll = roll;
+        pose.rotation_quaternion = q;
+        pose.translation = self.flip(pose.translation);
+    }
+}
+
+/// Scale pixel coordinates into fractions of the frame size
+fn normalize(face: &mut Face, width: f32, height: f32) {
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+    let bbox = &mut face.bounding_box;
+    bbox.x /= width;
+    bbox.y /= height;
+    bbox.width /= width;
+    bbox.height /= height;
+    let landmarks = face.landmarks.iter_mut().flat_map(|landmarks| landmarks.points.iter_mut());
+    let mesh = face.mesh.iter_mut().flat_map(|mesh| mesh.vertices.iter_mut());
+    for point in landmarks.chain(mesh) {
+        point.x /= width;
+        point.y /= height;
+    }
+}
+
+/// Rotation matrix of a unit quaternion, acting on column vectors
+pub(crate) fn rotation_matrix(q: Quaternion) -> [[f32; 3]; 3] {
+    let Quaternion { x, y, z, w } = q;
+    [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+        [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+        [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+    ]
+}
+
+/// Angles about X, Y and Z (radians) that compose `m` when applied in `order`
+pub(crate) fn decompose(m: &[[f32; 3]; 3], order: EulerOrder) -> [f32; 3] {
+    let [i, j, k] = order.axes();
+    // Cyclic orders (XYZ, YZX, ZXY) and the others differ only in sign
+    let s = if (j + 3 - i) % 3 == 1 { 1.0 } else { -1.0 };
+
+    let sin_j = (-s * m[k][i]).clamp(-1.0, 1.0);
+    let mut angles = [0.0; 3];
+    angles[j] = sin_j.asin();
+    if sin_j.abs() < 1.0 - 1e-6 {
+        angles[i] = (s * m[k][j]).atan2(m[k][k]);
+        angles[k] = (s * m[j][i]).atan2(m[i][i]);
+    } else {
+        // Gimbal lock: the first and last rotation share an axis, so the
+        // whole turn is attributed to the first
+        angles[i] = (-s * m[j][k]).atan2(m[j][j]);
+    }
+    angles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axis_rotation(axis: usize, angle: f32) -> [[f32; 3]; 3] {
+        let (s, c) = angle.sin_cos();
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut m = [[0.0; 3]; 3];
+        m[axis][axis] = 1.0;
+        m[a][a] = c;
+        m[a][b] = -s;
+        m[b][a] = s;
+        m[b][b] = c;
+        m
+    }
+
+    fn multiply(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+        let mut m = [[0.0; 3]; 3];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|n| a[r][n] * b[n][c]).sum();
+            }
+        }
+        m
+    }
+
+    fn pose(pitch: f32, yaw: f32, roll: f32) -> HeadPose {
+        HeadPose::from_euler(pitch, yaw, roll, Point3D { x: 1.0, y: 2.0, z: 3.0 }, 1.0)
+    }
+
+    #[test]
+    fn test_decomposes_every_order() {
+        let angles = [0.3f32, -0.5, 0.8];
+        for order in [EulerOrder::XYZ, EulerOrder::XZY, EulerOrder::YXZ, EulerOrder::YZX, EulerOrder::ZXY, EulerOrder::ZYX] {
+            let [i, j, k] = order.axes();
+            let m = multiply(
+                &axis_rotation(k, angles[k]),
+                &multiply(&axis_rotation(j, angles[j]), &axis_rotation(i, angles[i])),
+            );
+            let decomposed = decompose(&m, order);
+            for axis in 0..3 {
+                assert!((decomposed[axis] - angles[axis]).abs() < 1e-5, "{:?}: {:?}", order, decomposed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_radians_in_tracker_order() {
+        let conventions = OutputConventions { angle_unit: AngleUnit::Radians, ..OutputConventions::default() };
+        let mut converted = pose(10.0, -20.0, 30.0);
+        conventions.convert_pose(&mut converted);
+        assert!((converted.pitch - 10f32.to_radians()).abs() < 1e-5);
+        assert!((converted.yaw - (-20f32).to_radians()).abs() < 1e-5);
+        assert!((converted.roll - 30f32.to_radians()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_flipping_x_mirrors_yaw_and_roll() {
+        let conventions = OutputConventions { flip_x: true, ..OutputConventions::default() };
+        let mut converted = pose(10.0, -20.0, 30.0);
+        conventions.convert_pose(&mut converted);
+        assert!((converted.pitch - 10.0).abs() < 1e-3);
+        assert!((converted.yaw - 20.0).abs() < 1e-3);
+        assert!((converted.roll + 30.0).abs() < 1e-3);
+        assert_eq!(converted.translation, Point3D { x: -1.0, y: 2.0, z: 3.0 });
+
+        let expected = Quaternion::from_euler(10.0, 20.0, -30.0);
+        let q = converted.rotation_quaternion;
+        assert!((q.x - expected.x).abs() < 1e-5 && (q.y - expected.y).abs() < 1e-5);
+        assert!((q.z - expected.z).abs() < 1e-5 && (q.w - expected.w).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_normalized_coordinates() {
+        let mut faces = vec![Face {
+            bounding_box: BoundingBox { x: 160.0, y: 120.0, width: 320.0, height: 240.0 },
+            confidence: 1.0,
+            landmarks: Some(FacialLandmarks {
+                points: vec![Point2D { x: 320.0, y: 480.0 }],
+                confidences: vec![1.0],
+            }),
+            ..Face::default()
+        }];
+        let conventions = OutputConventions { coordinate_space: CoordinateSpace::Normalized, ..OutputConventions::default() };
+        conventions.apply(&mut faces, 640, 480);
+
+        assert_eq!(faces[0].bounding_box, BoundingBox { x: 0.25, y: 0.25, width: 0.5, height: 0.5 });
+        assert_eq!(faces[0].landmarks.as_ref().unwrap().points[0], Point2D { x: 0.5, y: 1.0 });
+    }
+}
@@ -0,0 +1,377 @@
+//! Drowsiness detection
+//!
+//! Driver-monitoring style apps watch for two signs of fatigue: PERCLOS,
+//! the share of time the eyes are (nearly) closed over a longer window, and
+//! single closures held far longer than a blink. Both are measured per face
+//! from the eye openness; an event is sent when either crosses its warning
+//! or critical threshold, not on every frame.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Longest accepted PERCLOS window (ms)
+pub const MAX_PERCLOS_WINDOW_MS: u32 = 600_000;
+
+/// How far PERCLOS must fall below a reported threshold before crossing it
+/// again is reported anew
+const PERCLOS_REARM_MARGIN: f32 = 0.05;
+
+/// Sign of drowsiness that triggered an event
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrowsinessSign {
+    /// Share of time with closed eyes over the PERCLOS window
+    Perclos,
+    /// A single eye closure held for long
+    ProlongedClosure,
+}
+
+/// How severe the detected drowsiness is
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DrowsinessSeverity {
+    /// The warning threshold was reached
+    Warning,
+    /// The critical threshold was reached
+    Critical,
+}
+
+/// Drowsiness detection configuration
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrowsinessConfig {
+    /// Enable drowsiness detection
+    pub enabled: bool,
+    /// Eye openness (0.0 - 1.0) below which the eyes count as closed; 0.2
+    /// corresponds to the common "80 % closed" PERCLOS definition
+    pub closed_threshold: f32,
+    /// Time PERCLOS is measured over (ms); nothing is reported before a
+    /// face has been tracked this long
+    pub perclos_window_ms: u32,
+    /// PERCLOS (0.0 - 1.0) at which a warning is sent
+    pub perclos_warning: f32,
+    /// PERCLOS (0.0 - 1.0) at which a critical event is sent
+    pub perclos_critical: f32,
+    /// Closure duration at which a warning is sent (ms)
+    pub closure_warning_ms: u32,
+    /// Closure duration at which a critical event is sent (ms)
+    pub closure_critical_ms: u32,
+}
+
+impl Default for DrowsinessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            closed_threshold: 0.2,
+            perclos_window_ms: 60_000,
+            perclos_warning: 0.15,
+            perclos_critical: 0.3,
+            closure_warning_ms: 1000,
+            closure_critical_ms: 2000,
+        }
+    }
+}
+
+impl DrowsinessConfig {
+    /// Check the thresholds and window
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the threshold and window constraints
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            self.closed_threshold > 0.0 && self.closed_threshold < 1.0,
+            "closed_threshold",
+            "must be between 0.0 and 1.0 (exclusive)",
+            "Use 0.2",
+        );
+        report.check(
+            (1..=MAX_PERCLOS_WINDOW_MS).contains(&self.perclos_window_ms),
+            "perclos_window_ms",
+            format!("must be between 1 and {}", MAX_PERCLOS_WINDOW_MS).as_str(),
+            "Use 60000",
+        );
+        report.check(
+            self.perclos_warning > 0.0 && self.perclos_warning <= 1.0,
+            "perclos_warning",
+            "must be greater than 0.0 and at most 1.0",
+            "Use 0.15",
+        );
+        report.check(
+            self.perclos_critical >= self.perclos_warning && self.perclos_critical <= 1.0,
+            "perclos_critical",
+            "must be at least perclos_warning and at most 1.0",
+            "Use 0.3",
+        );
+        report.check(self.closure_warning_ms > 0, "closure_warning_ms", "must be greater than 0", "Use 1000");
+        report.check(
+            self.closure_critical_ms >= self.closure_warning_ms,
+            "closure_critical_ms",
+            "must be at least closure_warning_ms",
+            "Use 2000",
+        );
+        report
+    }
+
+    /// Severity of a PERCLOS value, if any
+    fn perclos_severity(&self, perclos: f32) -> Option<DrowsinessSeverity> {
+        if perclos >= self.perclos_critical {
+            Some(DrowsinessSeverity::Critical)
+        } else if perclos >= self.perclos_warning {
+            Some(DrowsinessSeverity::Warning)
+        } else {
+            None
+        }
+    }
+
+    /// Severity of a closure held for `duration` ms, if any
+    fn closure_severity(&self, duration: i64) -> Option<DrowsinessSeverity> {
+        if duration >= self.closure_critical_ms as i64 {
+            Some(DrowsinessSeverity::Critical)
+        } else if duration >= self.closure_warning_ms as i64 {
+            Some(DrowsinessSeverity::Warning)
+        } else {
+            None
+        }
+    }
+}
+
+/// Detected drowsiness of a face
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrowsinessEvent {
+    /// ID of the drowsy face
+    pub face_id: u32,
+    /// Sign that crossed its threshold
+    pub sign: DrowsinessSign,
+    /// Threshold the sign reached
+    pub severity: DrowsinessSeverity,
+    /// PERCLOS over the window so far (0.0 - 1.0)
+    pub perclos: f32,
+    /// Duration of the current eye closure (ms), 0 if the eyes are open
+    pub closure_ms: u32,
+    /// Timestamp of the frame that triggered the event
+    pub timestamp: i64,
+}
+
+/// Eye closure history of one face
+#[derive(Debug, Default)]
+struct FaceDrowsiness {
+    /// Timestamp of the first frame of the face
+    since: i64,
+    /// Timestamp and closed state of the frames in the window
+    samples: VecDeque<(i64, bool)>,
+    /// Time with closed eyes between the samples in the window (ms)
+    closed_ms: i64,
+    /// Timestamp since which the eyes are closed
+    closed_since: Option<i64>,
+    /// Highest PERCLOS severity reported since PERCLOS last dropped well
+    /// below its threshold
+    perclos_reported: Option<DrowsinessSeverity>,
+    /// Highest severity reported for the current closure
+    closure_reported: Option<DrowsinessSeverity>,
+}
+
+impl FaceDrowsiness {
+    /// Add a frame, dropping frames that left the window
+    ///
+    /// Each interval between frames counts as closed if its first frame was.
+    fn push(&mut self, timestamp: i64, closed: bool, window: i64) {
+        if let Some(&(last, last_closed)) = self.samples.back() {
+            if last_closed {
+                self.closed_ms += timestamp - last;
+            }
+        }
+        self.samples.push_back((timestamp, closed));
+        while self.samples.len() > 1 {
+            let ((first, first_closed), (second, _)) = (self.samples[0], self.samples[1]);
+            if timestamp - second < window {
+                break;
+            }
+            if first_closed {
+                self.closed_ms -= second - first;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Share of the covered time with closed eyes
+    fn perclos(&self) -> f32 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(&(first, _)), Some(&(last, _))) if last > first => self.closed_ms as f32 / (last - first) as f32,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Drowsiness detection across frames
+#[derive(Debug)]
+pub struct DrowsinessAnalyzer {
+    config: DrowsinessConfig,
+    faces: HashMap<u32, FaceDrowsiness>,
+}
+
+impl DrowsinessAnalyzer {
+    /// Create an analyzer with the given configuration
+    pub fn new(config: DrowsinessConfig) -> Self {
+        Self { config, faces: HashMap::new() }
+    }
+
+    /// Track the eye closure of all faces and return the drowsiness events
+    ///
+    /// State of faces that are no longer present is discarded.
+    pub fn update(&mut self, faces: &[Face]) -> Vec<DrowsinessEvent> {
+        let mut events = Vec::new();
+        if !self.config.enabled {
+            return events;
+        }
+
+        self.faces.retain(|id, _| faces.iter().any(|f| f.id == *id));
+
+        let config = self.config;
+        let window = config.perclos_window_ms as i64;
+        for face in faces {
+            let eyes = match &face.eyes {
+                Some(eyes) => eyes,
+                None => continue,
+            };
+            let closed = (eyes.left_eye_openness + eyes.right_eye_openness) / 2.0 < config.closed_threshold;
+            let state = self.faces.entry(face.id).or_insert_with(|| FaceDrowsiness {
+                since: face.timestamp,
+                ..FaceDrowsiness::default()
+            });
+            state.push(face.timestamp, closed, window);
+
+            state.closed_since = match state.closed_since {
+                _ if !closed => None,
+                None => Some(face.timestamp),
+                since => since,
+            };
+            let closure_ms = state.closed_since.map_or(0, |since| face.timestamp - since);
+            let perclos = state.perclos();
+            let mut event = |sign, severity| {
+                events.push(DrowsinessEvent {
+                    face_id: face.id,
+                    sign,
+                    severity,
+                    perclos,
+                    closure_ms: closure_ms.clamp(0, u32::MAX as i64) as u32,
+                    timestamp: face.timestamp,
+                });
+            };
+
+            // Each closure is reported once per severity it reaches
+            if closed {
+                let severity = config.closure_severity(closure_ms);
+                if severity > state.closure_reported {
+                    event(DrowsinessSign::ProlongedClosure, severity.unwrap());
+                    state.closure_reported = severity;
+                }
+            } else {
+                state.closure_reported = None;
+            }
+
+            // PERCLOS is only meaningful once the window is covered; rising
+            // severity is reported, and re-armed once PERCLOS has fallen the
+            // margin below its threshold, so hovering around it stays quiet
+            if face.timestamp - state.since >= window {
+                let severity = config.perclos_severity(perclos);
+                if severity > state.perclos_reported {
+                    event(DrowsinessSign::Perclos, severity.unwrap());
+                    state.perclos_reported = severity;
+                } else {
+                    let rearmed = config.perclos_severity(perclos + PERCLOS_REARM_MARGIN);
+                    state.perclos_reported = state.perclos_reported.min(rearmed);
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Forget all drowsiness state
+    pub fn reset(&mut self) {
+        self.faces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_with_eyes(openness: f32, timestamp: i64) -> Face {
+        Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            eyes: Some(EyeState {
+                left_eye_openness: openness,
+                right_eye_openness: openness,
+                left_eye_ratio: 0.3,
+                right_eye_ratio: 0.3,
+            }),
+            timestamp,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_reports_perclos_and_prolonged_closure() {
+        let config = DrowsinessConfig { perclos_window_ms: 1000, ..DrowsinessConfig::default() };
+        let mut analyzer = DrowsinessAnalyzer::new(config);
+        let mut run = |openness: &dyn Fn(i64) -> f32, range: std::ops::Range<i64>| {
+            range
+                .step_by(50)
+                .flat_map(|t| analyzer.update(&[face_with_eyes(openness(t), t)]))
+                .collect::<Vec<_>>()
+        };
+
+        // Blinking for 100 of every 500 ms: 20 % PERCLOS, a warning once the
+        // window is covered, without any prolonged closure
+        let events = run(&|t| if t % 500 < 100 { 0.0 } else { 1.0 }, 0..2000);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].sign, events[0].severity), (DrowsinessSign::Perclos, DrowsinessSeverity::Warning));
+        assert!((events[0].perclos - 0.2).abs() < 0.06);
+
+        // Closing the eyes: critical PERCLOS, then the closure reaches each
+        // severity once
+        let events = run(&|_| 0.1, 2000..4200);
+        let signs: Vec<_> = events.iter().map(|e| (e.sign, e.severity)).collect();
+        assert_eq!(
+            signs,
+            [
+                (DrowsinessSign::Perclos, DrowsinessSeverity::Critical),
+                (DrowsinessSign::ProlongedClosure, DrowsinessSeverity::Warning),
+                (DrowsinessSign::ProlongedClosure, DrowsinessSeverity::Critical),
+            ]
+        );
+        assert_eq!(events[2].closure_ms, 2000);
+    }
+
+    #[test]
+    fn test_perclos_around_threshold_reports_once() {
+        let config = DrowsinessConfig { perclos_window_ms: 1000, ..DrowsinessConfig::default() };
+        let mut analyzer = DrowsinessAnalyzer::new(config);
+        let mut run = |openness: &dyn Fn(i64) -> f32, range: std::ops::Range<i64>| {
+            range
+                .step_by(50)
+                .flat_map(|t| analyzer.update(&[face_with_eyes(openness(t), t)]))
+                .filter(|e| e.sign == DrowsinessSign::Perclos)
+                .count()
+        };
+
+        // Blinking for 100 of every 700 ms moves PERCLOS across the warning
+        // threshold with every blink
+        let blinking = |t: i64| if t % 700 < 100 { 0.0 } else { 1.0 };
+        assert_eq!(run(&blinking, 0..6000), 1);
+        // Open eyes bring it well below, so the next crossing is reported
+        assert_eq!(run(&|_| 1.0, 6000..8000), 0);
+        assert_eq!(run(&blinking, 8000..10000), 1);
+    }
+}
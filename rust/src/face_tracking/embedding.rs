@@ -0,0 +1,176 @@
+//! Face embeddings
+//!
+//! An ArcFace- or FaceNet-style ONNX model turns an aligned face crop into a
+//! fixed-length vector; crops of the same person give vectors pointing in
+//! similar directions. The model runs on tract, so embeddings require the
+//! `tract` feature. It is only loaded once the first face needs an
+//! embedding, keeping startup unaffected for apps that do not use it.
+
+use crate::error::PluginError;
+use crate::face_tracking::alignment;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "tract")]
+use crate::face_tracking::backend::tract::load_tract_model;
+#[cfg(feature = "tract")]
+use tract_onnx::prelude::*;
+
+/// Embedding model settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    /// Path to the ONNX model, or the name of a model loaded with
+    /// `load_models_from_bytes`
+    ///
+    /// The model takes one `[1, 3, input_size, input_size]` RGB tensor
+    /// scaled to -1.0 - 1.0 and returns the embedding (e.g. 128 or 512
+    /// values) as its first output.
+    pub model_path: String,
+    /// Side of the aligned face crop the model expects (pixels)
+    pub input_size: u32,
+    /// Lowest cosine similarity (0.0 - 1.0) at which a face is recognized
+    /// as a registered person
+    pub recognition_threshold: f32,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            model_path: String::new(),
+            input_size: 112,
+            recognition_threshold: 0.45,
+        }
+    }
+}
+
+impl EmbeddingConfig {
+    /// Validate the model settings
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the model settings
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(!self.model_path.is_empty(), "model_path", "must not be empty", "Name an ArcFace-style ONNX model");
+        report.check(
+            (32..=alignment::MAX_ALIGNED_SIZE).contains(&self.input_size),
+            "input_size",
+            &format!("must be between 32 and {}", alignment::MAX_ALIGNED_SIZE),
+            "Use 112 for ArcFace models",
+        );
+        report.check(
+            (0.0..=1.0).contains(&self.recognition_threshold),
+            "recognition_threshold",
+            "must be between 0.0 and 1.0",
+            "Use 0.45 for ArcFace models; raise it if different people are confused",
+        );
+        report
+    }
+}
+
+/// A loaded embedding model
+#[derive(Clone)]
+#[cfg_attr(not(feature = "tract"), allow(dead_code))]
+pub struct FaceEmbedder {
+    #[cfg(feature = "tract")]
+    model: TypedRunnableModel<TypedModel>,
+    input_size: u32,
+}
+
+impl FaceEmbedder {
+    /// Load and optimize the configured model
+    #[cfg(feature = "tract")]
+    pub fn load(config: &EmbeddingConfig) -> Result<Self, PluginError> {
+        config.validate()?;
+        let size = config.input_size as usize;
+        let model = load_tract_model(&config.model_path, [1, 3, size, size])?;
+
+        Ok(Self { model, input_size: config.input_size })
+    }
+
+    /// Load and optimize the configured model
+    #[cfg(not(feature = "tract"))]
+    pub fn load(_config: &EmbeddingConfig) -> Result<Self, PluginError> {
+        Err(PluginError::InvalidConfiguration(
+            "Face embeddings require building with the `tract` feature".to_string(),
+        ))
+    }
+
+    /// Embedding of the face with `landmarks` in `image`, scaled to unit length
+    pub fn embed(&self, image: &RgbImage, landmarks: &FacialLandmarks) -> Result<Vec<f32>, PluginError> {
+        let crop = alignment::align_face(image, landmarks, self.input_size)?;
+        self.run(&crop).map(normalize)
+    }
+
+    #[cfg(feature = "tract")]
+    fn run(&self, crop: &RgbImage) -> Result<Vec<f32>, PluginError> {
+        let size = self.input_size as usize;
+        let tensor: Tensor = tract_ndarray::Array4::from_shape_fn((1, 3, size, size), |(_, c, y, x)| {
+            (crop.get_pixel(x as u32, y as u32)[c] as f32 - 127.5) / 127.5
+        })
+        .into();
+
+        let outputs = self
+            .model
+            .run(tvec!(tensor.into()))
+            .map_err(|e| PluginError::ProcessingError(format!("Face embedding failed: {}", e)))?;
+        let embedding = outputs[0]
+            .to_array_view::<f32>()
+            .map_err(|_| PluginError::ProcessingError("Unexpected embedding model output".to_string()))?;
+        Ok(embedding.iter().copied().collect())
+    }
+
+    #[cfg(not(feature = "tract"))]
+    fn run(&self, _crop: &RgbImage) -> Result<Vec<f32>, PluginError> {
+        unreachable!("embedders cannot be loaded without the tract feature")
+    }
+
+    /// Add embeddings to the faces with landmarks, found in `image`
+    pub fn apply(&self, image: &RgbImage, faces: &mut [Face]) -> Result<(), PluginError> {
+        for face in faces.iter_mut() {
+            if let Some(landmarks) = &face.landmarks {
+                face.embedding = Some(self.embed(image, landmarks)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scale `vector` to unit length, so embeddings compare by dot product
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let length = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if length > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= length);
+    }
+    vector
+}
+
+/// Cosine similarity of two unit-length embeddings (-1.0 - 1.0)
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embeddings_have_unit_length() {
+        let embedding = normalize(vec![3.0, 4.0]);
+        assert_eq!(embedding, vec![0.6, 0.8]);
+        assert!((similarity(&embedding, &embedding) - 1.0).abs() < 1e-6);
+        assert_eq!(normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+    }
+
+    #[cfg(not(feature = "tract"))]
+    #[test]
+    fn test_embedding_requires_feature() {
+        let config = EmbeddingConfig { model_path: "arcface.onnx".to_string(), ..EmbeddingConfig::default() };
+        assert!(matches!(FaceEmbedder::load(&config), Err(PluginError::InvalidConfiguration(_))));
+    }
+}
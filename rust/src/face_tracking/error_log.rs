@@ -0,0 +1,165 @@
+//! Recent tracker errors
+//!
+//! Frames are usually processed on a worker task, so a failure never reaches
+//! the app as a returned error. The [`ErrorLog`] keeps a ring buffer of the
+//! latest errors together with the stage and frame they occurred on, serves
+//! the most recent ones to the tracker status and hands each one to the
+//! error event stream. The history makes failures in the field diagnosable
+//! without a debugger attached.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::protocols::FrameInfo;
+use std::collections::VecDeque;
+
+/// Number of errors kept for the tracker status
+pub const MAX_RECENT_ERRORS: usize = 16;
+
+/// Number of errors kept in the history
+pub const MAX_ERROR_HISTORY: usize = 256;
+
+/// Where a recorded error came from
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorOrigin {
+    pub stage: ErrorStage,
+    pub frame: Option<ErrorFrameInfo>,
+    pub streamed: bool,
+}
+
+impl ErrorFrameInfo {
+    /// Metadata of a frame passed from Dart
+    pub fn of_camera_frame(frame: &CameraFrame) -> Self {
+        Self { width: frame.width, height: frame.height, timestamp: frame.timestamp, format: Some(frame.format) }
+    }
+
+    /// Metadata of a frame in a shared buffer slot
+    pub fn of_shared_frame(metadata: &SharedFrameMetadata) -> Self {
+        Self {
+            width: metadata.width,
+            height: metadata.height,
+            timestamp: metadata.timestamp,
+            format: Some(metadata.format),
+        }
+    }
+
+    /// Metadata of a frame past conversion
+    pub fn of_frame_info(info: &FrameInfo) -> Self {
+        Self { width: info.width, height: info.height, timestamp: info.timestamp, format: None }
+    }
+}
+
+impl ErrorSeverity {
+    /// Severity of a failed frame
+    ///
+    /// Errors caused by the frame itself only affect that frame; everything
+    /// else points at a problem in the tracker.
+    pub fn of(error: &PluginError) -> Self {
+        match error {
+            PluginError::ImageConversion(_)
+            | PluginError::UnsupportedImageFormat(_)
+            | PluginError::InvalidConfiguration(_) => ErrorSeverity::Warning,
+            _ => ErrorSeverity::Error,
+        }
+    }
+}
+
+/// The most recent tracker errors, oldest first
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorHistoryEntry>,
+}
+
+impl ErrorLog {
+    /// Create an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `error` at `timestamp`, dropping the oldest entry when full
+    pub fn record(&mut self, error: &PluginError, timestamp: i64, origin: ErrorOrigin) -> TrackerErrorEvent {
+        let event = TrackerErrorEvent {
+            timestamp,
+            severity: ErrorSeverity::of(error),
+            code: error.code(),
+            message: error.to_string(),
+        };
+        if self.entries.len() == MAX_ERROR_HISTORY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ErrorHistoryEntry {
+            error: event.clone(),
+            stage: origin.stage,
+            frame: origin.frame,
+            streamed: origin.streamed,
+        });
+        event
+    }
+
+    /// Most recent error, if any
+    pub fn last(&self) -> Option<&TrackerErrorEvent> {
+        self.entries.back().map(|entry| &entry.error)
+    }
+
+    /// The [`MAX_RECENT_ERRORS`] most recent errors, oldest first
+    pub fn recent(&self) -> Vec<TrackerErrorEvent> {
+        self.history(MAX_RECENT_ERRORS).into_iter().map(|entry| entry.error).collect()
+    }
+
+    /// Up to `limit` of the latest history entries, oldest first
+    pub fn history(&self, limit: usize) -> Vec<ErrorHistoryEntry> {
+        let skip = self.entries.len().saturating_sub(limit);
+        self.entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin(stage: ErrorStage) -> ErrorOrigin {
+        ErrorOrigin { stage, frame: None, streamed: false }
+    }
+
+    #[test]
+    fn test_keeps_most_recent_errors() {
+        let mut log = ErrorLog::new();
+        for i in 0..MAX_RECENT_ERRORS as i64 + 2 {
+            log.record(&PluginError::ProcessingError(format!("failure {}", i)), i, origin(ErrorStage::Detection));
+        }
+
+        let recent = log.recent();
+        assert_eq!(recent.len(), MAX_RECENT_ERRORS);
+        assert_eq!(recent[0].timestamp, 2);
+        assert_eq!(log.last().unwrap().message, format!("Processing error: failure {}", MAX_RECENT_ERRORS + 1));
+    }
+
+    #[test]
+    fn test_history_is_a_ring_buffer() {
+        let mut log = ErrorLog::new();
+        let frame = ErrorFrameInfo { width: 640, height: 480, timestamp: 7, format: Some(ImageFormat::NV21) };
+        for i in 0..MAX_ERROR_HISTORY as i64 + 10 {
+            let stage = if i % 2 == 0 { ErrorStage::Conversion } else { ErrorStage::Analysis };
+            log.record(
+                &PluginError::ImageConversion("truncated".to_string()),
+                i,
+                ErrorOrigin { stage, frame: Some(frame), streamed: true },
+            );
+        }
+
+        assert_eq!(log.history(usize::MAX).len(), MAX_ERROR_HISTORY);
+        assert_eq!(log.history(usize::MAX)[0].error.timestamp, 10);
+        let latest = log.history(2);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[1].error.timestamp, MAX_ERROR_HISTORY as i64 + 9);
+        assert_eq!(latest[1].stage, ErrorStage::Analysis);
+        assert_eq!(latest[1].frame, Some(frame));
+        assert!(latest[1].streamed);
+    }
+
+    #[test]
+    fn test_severity_from_error() {
+        let bad_frame = PluginError::ImageConversion("truncated".to_string());
+        assert_eq!(ErrorSeverity::of(&bad_frame), ErrorSeverity::Warning);
+        assert_eq!(ErrorSeverity::of(&PluginError::ProcessingError("model".to_string())), ErrorSeverity::Error);
+    }
+}
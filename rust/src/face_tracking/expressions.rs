@@ -0,0 +1,557 @@
+//! Expression feature extraction
+//!
+//! Derives normalized expression values (smile, brow raise, mouth opening),
+//! per-eye openness and mouth shape from the geometry of the 68 facial
+//! landmarks. Distances are measured
+//! relative to the span between the outer eye corners and along the face's
+//! own vertical axis, so the values hold up under scale changes and head roll.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+
+/// Brow to eye gap relative to the eye span for a neutral brow
+const NEUTRAL_BROW_GAP: f32 = 0.25;
+
+/// Additional brow gap of a fully raised brow
+const BROW_RAISE_RANGE: f32 = 0.1;
+
+/// Mouth corner lift relative to the eye span for a full smile
+const SMILE_CORNER_LIFT: f32 = 0.08;
+
+/// Eye aspect ratio range mapped onto eye openness, adjustable per user
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EyeCalibration {
+    /// Eye aspect ratio of a closed eye (openness 0.0)
+    pub closed_ratio: f32,
+    /// Eye aspect ratio of a fully open eye (openness 1.0)
+    pub open_ratio: f32,
+}
+
+impl Default for EyeCalibration {
+    fn default() -> Self {
+        // Typical ratios across users: about 0.3 open and 0.1 closed
+        Self {
+            closed_ratio: 0.1,
+            open_ratio: 0.3,
+        }
+    }
+}
+
+impl EyeCalibration {
+    /// Check that the ratio range is not empty
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the ratio constraints
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(self.closed_ratio >= 0.0, "closed_ratio", "must not be negative", "Recalibrate with closed eyes");
+        report.check(
+            self.open_ratio > self.closed_ratio,
+            "open_ratio",
+            "must be above closed_ratio",
+            "Recalibrate with open and closed eyes",
+        );
+        report
+    }
+
+    /// Map an eye aspect ratio to openness (0.0 closed - 1.0 open)
+    pub fn openness(&self, ratio: f32) -> f32 {
+        ((ratio - self.closed_ratio) / (self.open_ratio - self.closed_ratio)).clamp(0.0, 1.0)
+    }
+}
+
+/// Mouth measurements mapped onto the mouth shape values, adjustable per user
+///
+/// Ratios are relative to the distance between the outer eye corners.
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MouthCalibration {
+    /// Mouth width ratio with a relaxed mouth
+    pub neutral_width_ratio: f32,
+    /// Mouth width ratio of a fully stretched mouth (width 1.0)
+    pub wide_width_ratio: f32,
+    /// Mouth width ratio of fully puckered lips (pucker 1.0)
+    pub pucker_width_ratio: f32,
+    /// Inner lip gap ratio of a fully opened jaw (jaw open 1.0)
+    pub open_gap_ratio: f32,
+}
+
+impl Default for MouthCalibration {
+    fn default() -> Self {
+        // A relaxed mouth is about half the eye span wide
+        Self {
+            neutral_width_ratio: 0.5,
+            wide_width_ratio: 0.8,
+            pucker_width_ratio: 0.35,
+            open_gap_ratio: 0.3,
+        }
+    }
+}
+
+impl MouthCalibration {
+    /// Check that the width ratios are ordered and the gap range is not empty
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the ratio constraints
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(self.pucker_width_ratio > 0.0, "pucker_width_ratio", "must be positive", "Recalibrate with a puckered mouth");
+        report.check(
+            self.pucker_width_ratio < self.neutral_width_ratio,
+            "neutral_width_ratio",
+            "must be above pucker_width_ratio",
+            "Recalibrate with a neutral mouth",
+        );
+        report.check(
+            self.neutral_width_ratio < self.wide_width_ratio,
+            "wide_width_ratio",
+            "must be above neutral_width_ratio",
+            "Recalibrate with a wide smile",
+        );
+        report.check(self.open_gap_ratio > 0.0, "open_gap_ratio", "must be positive", "Recalibrate with an open mouth");
+        report
+    }
+}
+
+/// Expression value that can be range calibrated
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpressionFeature {
+    /// [`Expressions::smile`]
+    Smile,
+    /// [`Expressions::brow_raise_left`]
+    BrowRaiseLeft,
+    /// [`Expressions::brow_raise_right`]
+    BrowRaiseRight,
+    /// [`Expressions::mouth_open`]
+    MouthOpen,
+}
+
+/// Range of raw values stretched onto 0.0 - 1.0
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ValueRange {
+    /// Raw value mapped to 0.0
+    pub min: f32,
+    /// Raw value mapped to 1.0
+    pub max: f32,
+}
+
+impl Default for ValueRange {
+    fn default() -> Self {
+        Self { min: 0.0, max: 1.0 }
+    }
+}
+
+impl ValueRange {
+    /// Map a raw value onto 0.0 - 1.0
+    pub fn rescale(&self, value: f32) -> f32 {
+        ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+}
+
+/// Per-user range of each expression value, e.g. for people with subtle
+/// expressions whose raw values never reach 1.0
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct ExpressionCalibration {
+    /// Range of the raw smile value
+    pub smile: ValueRange,
+    /// Range of the raw left brow raise value
+    pub brow_raise_left: ValueRange,
+    /// Range of the raw right brow raise value
+    pub brow_raise_right: ValueRange,
+    /// Range of the raw mouth open value
+    pub mouth_open: ValueRange,
+}
+
+impl ExpressionCalibration {
+    /// Check that every range lies within 0.0 - 1.0 and is not empty
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every range that is empty or leaves 0.0 - 1.0
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        for (feature, field) in [
+            (ExpressionFeature::Smile, "smile"),
+            (ExpressionFeature::BrowRaiseLeft, "brow_raise_left"),
+            (ExpressionFeature::BrowRaiseRight, "brow_raise_right"),
+            (ExpressionFeature::MouthOpen, "mouth_open"),
+        ] {
+            let range = self.range(feature);
+            report.check(
+                range.min >= 0.0 && range.max <= 1.0 && range.min < range.max,
+                field,
+                "must be a non-empty range within 0.0 - 1.0",
+                "Run range calibration again",
+            );
+        }
+        report
+    }
+
+    /// Range of one expression value
+    pub fn range(&self, feature: ExpressionFeature) -> ValueRange {
+        match feature {
+            ExpressionFeature::Smile => self.smile,
+            ExpressionFeature::BrowRaiseLeft => self.brow_raise_left,
+            ExpressionFeature::BrowRaiseRight => self.brow_raise_right,
+            ExpressionFeature::MouthOpen => self.mouth_open,
+        }
+    }
+
+    /// Replace the range of one expression value
+    pub fn set_range(&mut self, feature: ExpressionFeature, range: ValueRange) {
+        match feature {
+            ExpressionFeature::Smile => self.smile = range,
+            ExpressionFeature::BrowRaiseLeft => self.brow_raise_left = range,
+            ExpressionFeature::BrowRaiseRight => self.brow_raise_right = range,
+            ExpressionFeature::MouthOpen => self.mouth_open = range,
+        }
+    }
+
+    /// Stretch raw expression values onto their calibrated ranges
+    pub fn rescale(&self, expressions: &mut Expressions) {
+        expressions.smile = self.smile.rescale(expressions.smile);
+        expressions.brow_raise_left = self.brow_raise_left.rescale(expressions.brow_raise_left);
+        expressions.brow_raise_right = self.brow_raise_right.rescale(expressions.brow_raise_right);
+        expressions.mouth_open = self.mouth_open.rescale(expressions.mouth_open);
+    }
+}
+
+/// Raw value of one expression
+pub fn feature_value(expressions: &Expressions, feature: ExpressionFeature) -> f32 {
+    match feature {
+        ExpressionFeature::Smile => expressions.smile,
+        ExpressionFeature::BrowRaiseLeft => expressions.brow_raise_left,
+        ExpressionFeature::BrowRaiseRight => expressions.brow_raise_right,
+        ExpressionFeature::MouthOpen => expressions.mouth_open,
+    }
+}
+
+/// Compute expression values from 68-point landmarks
+///
+/// Returns `None` if fewer than 68 landmarks are available or the eye
+/// corners coincide.
+pub fn extract(landmarks: &FacialLandmarks) -> Option<Expressions> {
+    let points = &landmarks.points;
+    if points.len() < 68 {
+        return None;
+    }
+
+    let eye_span = distance(points[36], points[45]);
+    if eye_span <= 0.0 {
+        return None;
+    }
+
+    // Unit vector pointing towards the top of the head, perpendicular to the eye line
+    let up = Point2D {
+        x: (points[45].y - points[36].y) / eye_span,
+        y: (points[36].x - points[45].x) / eye_span,
+    };
+    let lift = |from: Point2D, to: Point2D| ((to.x - from.x) * up.x + (to.y - from.y) * up.y) / eye_span;
+
+    let brow_raise = |brow: &[Point2D], eye: &[Point2D]| {
+        let gap = lift(centroid(eye), centroid(brow));
+        ((gap - NEUTRAL_BROW_GAP) / BROW_RAISE_RANGE).clamp(0.0, 1.0)
+    };
+
+    // Smiling both widens the mouth and pulls its corners up
+    let lip_center = centroid(&[points[51], points[57]]);
+    let corners = centroid(&[points[48], points[54]]);
+    let corner_lift = (lift(lip_center, corners) / SMILE_CORNER_LIFT).clamp(0.0, 1.0);
+
+    Some(Expressions {
+        smile: (mouth_wideness(landmarks) + corner_lift) / 2.0,
+        brow_raise_left: brow_raise(landmarks.left_eyebrow()?, landmarks.left_eye()?),
+        brow_raise_right: brow_raise(landmarks.right_eyebrow()?, landmarks.right_eye()?),
+        mouth_open: mouth_openness(landmarks),
+    })
+}
+
+/// Compute the state of both eyes from 68-point landmarks
+pub fn eye_state(landmarks: &FacialLandmarks, calibration: &EyeCalibration) -> Option<EyeState> {
+    if landmarks.points.len() < 68 {
+        return None;
+    }
+
+    let left_eye_ratio = eye_aspect_ratio(landmarks.left_eye()?);
+    let right_eye_ratio = eye_aspect_ratio(landmarks.right_eye()?);
+    Some(EyeState {
+        left_eye_openness: calibration.openness(left_eye_ratio),
+        right_eye_openness: calibration.openness(right_eye_ratio),
+        left_eye_ratio,
+        right_eye_ratio,
+    })
+}
+
+/// Compute the mouth shape from 68-point landmarks
+///
+/// Returns `None` if fewer than 68 landmarks are available or the eye
+/// corners coincide.
+pub fn mouth_state(landmarks: &FacialLandmarks, calibration: &MouthCalibration) -> Option<MouthState> {
+    let points = &landmarks.points;
+    if points.len() < 68 {
+        return None;
+    }
+
+    let eye_span = distance(points[36], points[45]);
+    if eye_span <= 0.0 {
+        return None;
+    }
+
+    let mouth_width = distance(points[48], points[54]);
+    let inner_gap = distance(points[62], points[66]);
+    let width_ratio = mouth_width / eye_span;
+    let open_ratio = inner_gap / eye_span;
+
+    let range = |value: f32, from: f32, to: f32| ((value - from) / (to - from)).clamp(0.0, 1.0);
+    let pucker = range(width_ratio, calibration.neutral_width_ratio, calibration.pucker_width_ratio);
+
+    // A funnel is a narrowed mouth with the lips parted into an "O", about
+    // half as high as wide
+    let roundness = if mouth_width > 0.0 { (inner_gap / mouth_width / 0.5).clamp(0.0, 1.0) } else { 0.0 };
+
+    Some(MouthState {
+        jaw_open: range(open_ratio, 0.0, calibration.open_gap_ratio),
+        mouth_width: range(width_ratio, calibration.neutral_width_ratio, calibration.wide_width_ratio),
+        pucker,
+        funnel: pucker.min(roundness),
+        width_ratio,
+        open_ratio,
+    })
+}
+
+/// Fill in the expression values, eye state and mouth shape of all faces
+/// with landmarks
+pub fn apply(faces: &mut [Face], eyes: &EyeCalibration, mouth: &MouthCalibration) {
+    for face in faces.iter_mut() {
+        let landmarks = face.landmarks.as_ref();
+        face.expressions = landmarks.and_then(extract);
+        face.eyes = landmarks.and_then(|l| eye_state(l, eyes));
+        face.mouth = landmarks.and_then(|l| mouth_state(l, mouth));
+    }
+}
+
+/// Eye aspect ratio (lid distance over eye width) from the six landmarks of one eye
+pub(crate) fn eye_aspect_ratio(eye: &[Point2D]) -> f32 {
+    let horizontal = distance(eye[0], eye[3]);
+    if horizontal <= 0.0 {
+        return 0.0;
+    }
+    (distance(eye[1], eye[5]) + distance(eye[2], eye[4])) / (2.0 * horizontal)
+}
+
+/// Eye openness (0.0 closed - 1.0 open) from the six landmarks of one eye,
+/// using the default calibration
+pub(crate) fn eye_openness(eye: &[Point2D]) -> f32 {
+    EyeCalibration::default().openness(eye_aspect_ratio(eye))
+}
+
+/// Mouth openness (0.0 closed - 1.0 wide open) from 68-point landmarks
+pub(crate) fn mouth_openness(landmarks: &FacialLandmarks) -> f32 {
+    let points = &landmarks.points;
+    let mouth_width = distance(points[48], points[54]);
+    if mouth_width <= 0.0 {
+        return 0.0;
+    }
+    // Inner lip gap relative to mouth width, about 0.6 when fully open
+    (distance(points[62], points[66]) / mouth_width / 0.6).clamp(0.0, 1.0)
+}
+
+/// Mouth width relative to the distance between the outer eye corners (0.0 - 1.0)
+pub(crate) fn mouth_wideness(landmarks: &FacialLandmarks) -> f32 {
+    let points = &landmarks.points;
+    let eye_span = distance(points[36], points[45]);
+    if eye_span <= 0.0 {
+        return 0.0;
+    }
+    // A neutral mouth is about half the eye span, a wide smile about 0.8
+    ((distance(points[48], points[54]) / eye_span - 0.5) / 0.3).clamp(0.0, 1.0)
+}
+
+fn distance(a: Point2D, b: Point2D) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+fn centroid(points: &[Point2D]) -> Point2D {
+    let n = points.len() as f32;
+    Point2D {
+        x: points.iter().map(|p| p.x).sum::<f32>() / n,
+        y: points.iter().map(|p| p.y).sum::<f32>() / n,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A neutral frontal face with an eye span of 60 pixels
+    fn neutral_landmarks() -> FacialLandmarks {
+        let mut points = vec![Point2D { x: 0.0, y: 0.0 }; 68];
+        let p = |x: f32, y: f32| Point2D { x, y };
+
+        // Eyes: outer corner, upper lid, inner corner, lower lid
+        points[36..42].copy_from_slice(&[p(30.0, 50.0), p(36.0, 46.0), p(44.0, 46.0), p(50.0, 50.0), p(44.0, 54.0), p(36.0, 54.0)]);
+        points[42..48].copy_from_slice(&[p(70.0, 50.0), p(76.0, 46.0), p(84.0, 46.0), p(90.0, 50.0), p(84.0, 54.0), p(76.0, 54.0)]);
+
+        // Brows 15 pixels (a quarter of the eye span) above the eyes
+        for (i, x) in [32.0, 36.0, 40.0, 44.0, 48.0].iter().enumerate() {
+            points[17 + i] = p(*x, 35.0);
+            points[22 + i] = p(*x + 40.0, 35.0);
+        }
+
+        // Closed mouth, half the eye span wide
+        points[48] = p(45.0, 100.0);
+        points[54] = p(75.0, 100.0);
+        points[51] = p(60.0, 97.0);
+        points[57] = p(60.0, 103.0);
+        points[62] = p(60.0, 100.0);
+        points[66] = p(60.0, 100.0);
+
+        FacialLandmarks { points, confidences: vec![1.0; 68] }
+    }
+
+    #[test]
+    fn test_eye_openness_range() {
+        let open = [
+            Point2D { x: 0.0, y: 0.0 },
+            Point2D { x: 1.0, y: -0.6 },
+            Point2D { x: 2.0, y: -0.6 },
+            Point2D { x: 3.0, y: 0.0 },
+            Point2D { x: 2.0, y: 0.6 },
+            Point2D { x: 1.0, y: 0.6 },
+        ];
+        assert!((eye_openness(&open) - 1.0).abs() < 1e-5);
+
+        let closed: Vec<Point2D> = open.iter().map(|p| Point2D { x: p.x, y: 0.0 }).collect();
+        assert_eq!(eye_openness(&closed), 0.0);
+    }
+
+    #[test]
+    fn test_neutral_face() {
+        let expressions = extract(&neutral_landmarks()).unwrap();
+        assert!(expressions.smile < 1e-4);
+        assert!(expressions.brow_raise_left < 1e-4);
+        assert!(expressions.brow_raise_right < 1e-4);
+        assert_eq!(expressions.mouth_open, 0.0);
+    }
+
+    #[test]
+    fn test_raised_brow_and_smile() {
+        let mut landmarks = neutral_landmarks();
+        for point in &mut landmarks.points[22..27] {
+            point.y -= 6.0;
+        }
+        landmarks.points[48] = Point2D { x: 36.0, y: 95.0 };
+        landmarks.points[54] = Point2D { x: 84.0, y: 95.0 };
+
+        let expressions = extract(&landmarks).unwrap();
+        assert!((expressions.brow_raise_left - 1.0).abs() < 1e-4);
+        assert!(expressions.brow_raise_right < 1e-4);
+        assert!((expressions.smile - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_invariant_to_head_roll() {
+        let mut landmarks = neutral_landmarks();
+        for point in &mut landmarks.points[17..22] {
+            point.y -= 3.0;
+        }
+        let upright = extract(&landmarks).unwrap();
+
+        let (sin, cos) = 30f32.to_radians().sin_cos();
+        for point in &mut landmarks.points {
+            *point = Point2D {
+                x: point.x * cos - point.y * sin,
+                y: point.x * sin + point.y * cos,
+            };
+        }
+        let rolled = extract(&landmarks).unwrap();
+
+        assert!((upright.brow_raise_right - 0.5).abs() < 1e-4);
+        assert!((rolled.brow_raise_right - upright.brow_raise_right).abs() < 1e-4);
+        assert!((rolled.smile - upright.smile).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_calibrated_eye_openness() {
+        // The neutral eyes have an aspect ratio of 0.4
+        let landmarks = neutral_landmarks();
+        let eyes = eye_state(&landmarks, &EyeCalibration::default()).unwrap();
+        assert!((eyes.left_eye_ratio - 0.4).abs() < 1e-5);
+        assert_eq!(eyes.left_eye_openness, 1.0);
+
+        let calibration = EyeCalibration { closed_ratio: 0.2, open_ratio: 0.6 };
+        let eyes = eye_state(&landmarks, &calibration).unwrap();
+        assert!((eyes.left_eye_openness - 0.5).abs() < 1e-5);
+        assert!((eyes.right_eye_openness - 0.5).abs() < 1e-5);
+
+        assert!(EyeCalibration { closed_ratio: 0.3, open_ratio: 0.3 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_mouth_shapes() {
+        let calibration = MouthCalibration::default();
+        let mut landmarks = neutral_landmarks();
+        let neutral = mouth_state(&landmarks, &calibration).unwrap();
+        assert!((neutral.width_ratio - 0.5).abs() < 1e-5);
+        assert_eq!((neutral.jaw_open, neutral.mouth_width, neutral.pucker, neutral.funnel), (0.0, 0.0, 0.0, 0.0));
+
+        // Open jaw: inner lips 18 pixels apart
+        landmarks.points[62].y = 91.0;
+        landmarks.points[66].y = 109.0;
+        let open = mouth_state(&landmarks, &calibration).unwrap();
+        assert!((open.jaw_open - 1.0).abs() < 1e-5);
+        assert_eq!(open.pucker, 0.0);
+        assert_eq!(open.funnel, 0.0);
+
+        // "O" shape: narrowed to 0.35 of the eye span with the lips parted
+        landmarks.points[48].x = 49.5;
+        landmarks.points[54].x = 70.5;
+        let funnel = mouth_state(&landmarks, &calibration).unwrap();
+        assert!((funnel.pucker - 1.0).abs() < 1e-4);
+        assert!((funnel.funnel - 1.0).abs() < 1e-4);
+
+        // A user with a narrower relaxed mouth reads as neutral after calibration
+        let narrow = MouthCalibration { neutral_width_ratio: 0.35, pucker_width_ratio: 0.2, ..calibration };
+        assert!(mouth_state(&landmarks, &narrow).unwrap().pucker < 1e-4);
+
+        assert!(MouthCalibration { pucker_width_ratio: 0.6, ..calibration }.validate().is_err());
+        assert!(calibration.validate().is_ok());
+    }
+
+    #[test]
+    fn test_expression_calibration_rescales() {
+        let mut calibration = ExpressionCalibration::default();
+        calibration.set_range(ExpressionFeature::Smile, ValueRange { min: 0.1, max: 0.3 });
+
+        let mut expressions = Expressions { smile: 0.2, brow_raise_left: 0.4, brow_raise_right: 0.0, mouth_open: 1.0 };
+        calibration.rescale(&mut expressions);
+        assert!((expressions.smile - 0.5).abs() < 1e-5);
+        assert_eq!(expressions.brow_raise_left, 0.4);
+
+        calibration.set_range(ExpressionFeature::MouthOpen, ValueRange { min: 0.5, max: 0.5 });
+        assert!(calibration.validate().is_err());
+    }
+
+    #[test]
+    fn test_requires_68_landmarks() {
+        let landmarks = FacialLandmarks {
+            points: vec![Point2D { x: 0.0, y: 0.0 }; 5],
+            confidences: vec![1.0; 5],
+        };
+        assert!(extract(&landmarks).is_none());
+        assert!(eye_state(&landmarks, &EyeCalibration::default()).is_none());
+        assert!(mouth_state(&landmarks, &MouthCalibration::default()).is_none());
+    }
+}
@@ -0,0 +1,582 @@
+//! Temporal smoothing filters
+//!
+//! Raw landmarks and head pose jitter from frame to frame. This module provides
+//! One Euro, Kalman and exponential moving average filters that are applied
+//! per landmark coordinate and per pose component of every tracked face.
+//!
+//! Features need different amounts of smoothing: gaze looks calm only when
+//! filtered heavily, while a lagging jaw is immediately noticeable. Each
+//! [`SmoothingChannel`] can therefore have its own filter settings; pose and
+//! landmarks fall back to the shared settings, while eye state, mouth shape
+//! and gaze are only filtered when configured.
+
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Smoothing filter algorithm
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterType {
+    /// One Euro filter: adaptive low-pass, low lag during fast motion
+    OneEuro,
+    /// Constant-velocity Kalman filter
+    Kalman,
+    /// Exponential moving average
+    Ema,
+}
+
+/// Group of output values sharing filter settings
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SmoothingChannel {
+    /// Head rotation and translation
+    Pose,
+    /// Landmark positions, and through them every value derived from them
+    Landmarks,
+    /// Eye openness and aspect ratios
+    Eyes,
+    /// Mouth shape, mouth openness and smile
+    Mouth,
+    /// Eye gaze directions
+    Gaze,
+}
+
+/// Filter settings of a single smoothing channel
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelSmoothing {
+    /// Filter this channel (false passes it through unfiltered)
+    pub enabled: bool,
+    /// Filter algorithm
+    pub filter_type: FilterType,
+    /// One Euro minimum cutoff frequency (Hz), lower is smoother
+    pub min_cutoff: f32,
+    /// One Euro speed coefficient, higher reduces lag during fast motion
+    pub beta: f32,
+    /// One Euro derivative cutoff frequency (Hz)
+    pub derivative_cutoff: f32,
+    /// Kalman process noise
+    pub process_noise: f32,
+    /// Kalman measurement noise
+    pub measurement_noise: f32,
+    /// EMA smoothing factor (0.0 - 1.0), higher follows input more closely
+    pub ema_alpha: f32,
+}
+
+impl Default for ChannelSmoothing {
+    fn default() -> Self {
+        SmoothingConfig::default().shared()
+    }
+}
+
+/// Smoothing configuration for landmarks and head pose
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmoothingConfig {
+    /// Enable smoothing (disable for benchmarking raw output)
+    pub enabled: bool,
+    /// Filter algorithm
+    pub filter_type: FilterType,
+    /// One Euro minimum cutoff frequency (Hz), lower is smoother
+    pub min_cutoff: f32,
+    /// One Euro speed coefficient, higher reduces lag during fast motion
+    pub beta: f32,
+    /// One Euro derivative cutoff frequency (Hz)
+    pub derivative_cutoff: f32,
+    /// Kalman process noise
+    pub process_noise: f32,
+    /// Kalman measurement noise
+    pub measurement_noise: f32,
+    /// EMA smoothing factor (0.0 - 1.0), higher follows input more closely
+    pub ema_alpha: f32,
+    /// Head pose settings (`None` = the shared settings above)
+    pub pose: Option<ChannelSmoothing>,
+    /// Landmark settings (`None` = the shared settings above)
+    pub landmarks: Option<ChannelSmoothing>,
+    /// Eye state settings (`None` = only derived from smoothed landmarks)
+    pub eyes: Option<ChannelSmoothing>,
+    /// Mouth settings (`None` = only derived from smoothed landmarks)
+    pub mouth: Option<ChannelSmoothing>,
+    /// Gaze settings (`None` = not filtered)
+    pub gaze: Option<ChannelSmoothing>,
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            filter_type: FilterType::OneEuro,
+            min_cutoff: 1.0,
+            beta: 0.007,
+            derivative_cutoff: 1.0,
+            process_noise: 1e-3,
+            measurement_noise: 1e-2,
+            ema_alpha: 0.5,
+            pose: None,
+            landmarks: None,
+            eyes: None,
+            mouth: None,
+            gaze: None,
+        }
+    }
+}
+
+impl SmoothingConfig {
+    /// The shared settings as channel settings
+    pub fn shared(&self) -> ChannelSmoothing {
+        ChannelSmoothing {
+            enabled: true,
+            filter_type: self.filter_type,
+            min_cutoff: self.min_cutoff,
+            beta: self.beta,
+            derivative_cutoff: self.derivative_cutoff,
+            process_noise: self.process_noise,
+            measurement_noise: self.measurement_noise,
+            ema_alpha: self.ema_alpha,
+        }
+    }
+
+    /// Settings `channel` is filtered with, `None` if it is not filtered
+    pub fn channel(&self, channel: SmoothingChannel) -> Option<ChannelSmoothing> {
+        if !self.enabled {
+            return None;
+        }
+        let settings = match channel {
+            SmoothingChannel::Pose => Some(self.pose.unwrap_or_else(|| self.shared())),
+            SmoothingChannel::Landmarks => Some(self.landmarks.unwrap_or_else(|| self.shared())),
+            SmoothingChannel::Eyes => self.eyes,
+            SmoothingChannel::Mouth => self.mouth,
+            SmoothingChannel::Gaze => self.gaze,
+        };
+        settings.filter(|settings| settings.enabled)
+    }
+}
+
+/// One Euro filter (Casiez et al. 2012)
+#[derive(Debug, Clone)]
+pub struct OneEuroFilter {
+    min_cutoff: f32,
+    beta: f32,
+    derivative_cutoff: f32,
+    previous: Option<f32>,
+    derivative: f32,
+}
+
+impl OneEuroFilter {
+    pub fn new(min_cutoff: f32, beta: f32, derivative_cutoff: f32) -> Self {
+        Self {
+            min_cutoff,
+            beta,
+            derivative_cutoff,
+            previous: None,
+            derivative: 0.0,
+        }
+    }
+
+    fn alpha(cutoff: f32, dt: f32) -> f32 {
+        let tau = 1.0 / (2.0 * std::f32::consts::PI * cutoff);
+        1.0 / (1.0 + tau / dt)
+    }
+
+    pub fn filter(&mut self, value: f32, dt: f32) -> f32 {
+        let previous = match self.previous {
+            Some(previous) => previous,
+            None => {
+                self.previous = Some(value);
+                return value;
+            }
+        };
+
+        let raw_derivative = (value - previous) / dt;
+        let alpha_d = Self::alpha(self.derivative_cutoff, dt);
+        self.derivative += alpha_d * (raw_derivative - self.derivative);
+
+        let cutoff = self.min_cutoff + self.beta * self.derivative.abs();
+        let filtered = previous + Self::alpha(cutoff, dt) * (value - previous);
+        self.previous = Some(filtered);
+        filtered
+    }
+}
+
+/// One-dimensional constant-velocity Kalman filter
+#[derive(Debug, Clone)]
+pub struct KalmanFilter {
+    process_noise: f32,
+    measurement_noise: f32,
+    state: Option<[f32; 2]>,
+    covariance: [[f32; 2]; 2],
+}
+
+impl KalmanFilter {
+    pub fn new(process_noise: f32, measurement_noise: f32) -> Self {
+        Self {
+            process_noise,
+            measurement_noise,
+            state: None,
+            covariance: [[1.0, 0.0], [0.0, 1.0]],
+        }
+    }
+
+    pub fn filter(&mut self, value: f32, dt: f32) -> f32 {
+        let [position, velocity] = match self.state {
+            Some(state) => state,
+            None => {
+                self.state = Some([value, 0.0]);
+                return value;
+            }
+        };
+
+        // Predict
+        let predicted = [position + velocity * dt, velocity];
+        let p = self.covariance;
+        let q = self.process_noise;
+        let p00 = p[0][0] + dt * (p[1][0] + p[0][1]) + dt * dt * p[1][1] + q;
+        let p01 = p[0][1] + dt * p[1][1];
+        let p10 = p[1][0] + dt * p[1][1];
+        let p11 = p[1][1] + q;
+
+        // Update
+        let innovation = value - predicted[0];
+        let s = p00 + self.measurement_noise;
+        let k0 = p00 / s;
+        let k1 = p10 / s;
+
+        let state = [predicted[0] + k0 * innovation, predicted[1] + k1 * innovation];
+        self.covariance = [
+            [(1.0 - k0) * p00, (1.0 - k0) * p01],
+            [p10 - k1 * p00, p11 - k1 * p01],
+        ];
+        self.state = Some(state);
+        state[0]
+    }
+}
+
+/// Exponential moving average
+#[derive(Debug, Clone)]
+pub struct EmaFilter {
+    alpha: f32,
+    previous: Option<f32>,
+}
+
+impl EmaFilter {
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            previous: None,
+        }
+    }
+
+    pub fn filter(&mut self, value: f32, _dt: f32) -> f32 {
+        let filtered = match self.previous {
+            Some(previous) => previous + self.alpha * (value - previous),
+            None => value,
+        };
+        self.previous = Some(filtered);
+        filtered
+    }
+}
+
+/// A scalar filter of any supported type
+#[derive(Debug, Clone)]
+pub enum ScalarFilter {
+    OneEuro(OneEuroFilter),
+    Kalman(KalmanFilter),
+    Ema(EmaFilter),
+}
+
+impl ScalarFilter {
+    /// Create a filter from the settings of a channel
+    pub fn from_config(config: &ChannelSmoothing) -> Self {
+        match config.filter_type {
+            FilterType::OneEuro => ScalarFilter::OneEuro(OneEuroFilter::new(
+                config.min_cutoff,
+                config.beta,
+                config.derivative_cutoff,
+            )),
+            FilterType::Kalman => ScalarFilter::Kalman(KalmanFilter::new(
+                config.process_noise,
+                config.measurement_noise,
+            )),
+            FilterType::Ema => ScalarFilter::Ema(EmaFilter::new(config.ema_alpha)),
+        }
+    }
+
+    /// Filter a new sample taken `dt` seconds after the previous one
+    pub fn filter(&mut self, value: f32, dt: f32) -> f32 {
+        match self {
+            ScalarFilter::OneEuro(f) => f.filter(value, dt),
+            ScalarFilter::Kalman(f) => f.filter(value, dt),
+            ScalarFilter::Ema(f) => f.filter(value, dt),
+        }
+    }
+}
+
+/// Filters of one channel of a face, created when first needed
+#[derive(Default)]
+struct ChannelFilters(Vec<ScalarFilter>);
+
+impl ChannelFilters {
+    /// Filter `values` in place, (re)creating the filters if their number
+    /// changed
+    fn apply<const N: usize>(&mut self, settings: &ChannelSmoothing, values: [&mut f32; N], dt: f32) {
+        self.apply_all(settings, values.into_iter(), N, dt);
+    }
+
+    fn apply_all<'a>(
+        &mut self,
+        settings: &ChannelSmoothing,
+        values: impl Iterator<Item = &'a mut f32>,
+        count: usize,
+        dt: f32,
+    ) {
+        if self.0.len() != count {
+            self.0 = (0..count).map(|_| ScalarFilter::from_config(settings)).collect();
+        }
+        for (filter, value) in self.0.iter_mut().zip(values) {
+            *value = filter.filter(*value, dt);
+        }
+    }
+}
+
+/// Filter state for a single tracked face
+struct FaceFilters {
+    landmarks: ChannelFilters,
+    pose: ChannelFilters,
+    gaze: ChannelFilters,
+    eyes: ChannelFilters,
+    mouth: ChannelFilters,
+    /// Filtered pitch, yaw and roll of the previous frame, not wrapped into
+    /// -180 - 180 degrees
+    angles: Option<[f32; 3]>,
+    last_timestamp: i64,
+    /// Interval before the current frame (s)
+    dt: f32,
+}
+
+/// Per-face filter bank applied to tracker output
+pub struct FaceSmoother {
+    config: SmoothingConfig,
+    faces: HashMap<u32, FaceFilters>,
+    /// Frame interval used when timestamps are missing or not increasing
+    default_dt: f32,
+}
+
+impl FaceSmoother {
+    pub fn new(config: SmoothingConfig, target_fps: u32) -> Self {
+        Self {
+            config,
+            faces: HashMap::new(),
+            default_dt: 1.0 / target_fps.max(1) as f32,
+        }
+    }
+
+    /// Smooth landmarks, pose and gaze of all faces in place
+    ///
+    /// Filter state of faces that are no longer present is discarded.
+    pub fn apply(&mut self, faces: &mut [Face]) {
+        if !self.config.enabled {
+            return;
+        }
+
+        self.faces.retain(|id, _| faces.iter().any(|f| f.id == *id));
+
+        let landmark_settings = self.config.channel(SmoothingChannel::Landmarks);
+        let pose_settings = self.config.channel(SmoothingChannel::Pose);
+        let gaze_settings = self.config.channel(SmoothingChannel::Gaze);
+        for face in faces.iter_mut() {
+            let state = self.faces.entry(face.id).or_insert_with(|| FaceFilters {
+                landmarks: ChannelFilters::default(),
+                pose: ChannelFilters::default(),
+                gaze: ChannelFilters::default(),
+                eyes: ChannelFilters::default(),
+                mouth: ChannelFilters::default(),
+                angles: None,
+                last_timestamp: face.timestamp,
+                dt: 0.0,
+            });
+
+            let elapsed = (face.timestamp - state.last_timestamp) as f32 / 1000.0;
+            let dt = if elapsed > 0.0 { elapsed } else { self.default_dt };
+            state.last_timestamp = face.timestamp;
+            state.dt = dt;
+
+            if let (Some(landmarks), Some(settings)) = (face.landmarks.as_mut(), &landmark_settings) {
+                let count = landmarks.points.len() * 2;
+                let values = landmarks.points.iter_mut().flat_map(|point| [&mut point.x, &mut point.y]);
+                state.landmarks.apply_all(settings, values, count, dt);
+            }
+
+            if let (Some(pose), Some(settings)) = (face.pose.as_mut(), &pose_settings) {
+                // Angles are filtered next to the previous output, so a head
+                // turning past 180 degrees does not sweep back through 0
+                if let Some(previous) = state.angles {
+                    for (angle, previous) in [&mut pose.pitch, &mut pose.yaw, &mut pose.roll].into_iter().zip(previous) {
+                        *angle = previous + (*angle - previous + 180.0).rem_euclid(360.0) - 180.0;
+                    }
+                }
+                let values = [
+                    &mut pose.pitch,
+                    &mut pose.yaw,
+                    &mut pose.roll,
+                    &mut pose.translation.x,
+                    &mut pose.translation.y,
+                    &mut pose.translation.z,
+                ];
+                state.pose.apply(settings, values, dt);
+                state.angles = Some([pose.pitch, pose.yaw, pose.roll]);
+                for angle in [&mut pose.pitch, &mut pose.yaw, &mut pose.roll] {
+                    *angle = (*angle + 180.0).rem_euclid(360.0) - 180.0;
+                }
+                pose.update_quaternion();
+            }
+
+            if let (Some(gaze), Some(settings)) = (face.gaze.as_mut(), &gaze_settings) {
+                let values = [&mut gaze.left_eye_direction, &mut gaze.right_eye_direction, &mut gaze.combined_direction]
+                    .into_iter()
+                    .flat_map(|direction| [&mut direction.x, &mut direction.y, &mut direction.z]);
+                state.gaze.apply_all(settings, values, 9, dt);
+            }
+        }
+    }
+
+    /// Smooth the eye state, mouth shape and expressions derived from the
+    /// landmarks, with the eyes and mouth channel settings
+    ///
+    /// Runs after [`FaceSmoother::apply`] on the same faces.
+    pub fn apply_derived(&mut self, faces: &mut [Face]) {
+        let eye_settings = self.config.channel(SmoothingChannel::Eyes);
+        let mouth_settings = self.config.channel(SmoothingChannel::Mouth);
+        if eye_settings.is_none() && mouth_settings.is_none() {
+            return;
+        }
+
+        for face in faces.iter_mut() {
+            let Some(state) = self.faces.get_mut(&face.id) else {
+                continue;
+            };
+            let dt = state.dt;
+
+            if let (Some(eyes), Some(settings)) = (face.eyes.as_mut(), &eye_settings) {
+                let values = [
+                    &mut eyes.left_eye_openness,
+                    &mut eyes.right_eye_openness,
+                    &mut eyes.left_eye_ratio,
+                    &mut eyes.right_eye_ratio,
+                ];
+                state.eyes.apply(settings, values, dt);
+            }
+
+            if let (Some(mouth), Some(settings)) = (face.mouth.as_mut(), &mouth_settings) {
+                let mut mouth_open = face.expressions.map_or(0.0, |expressions| expressions.mouth_open);
+                let mut smile = face.expressions.map_or(0.0, |expressions| expressions.smile);
+                let values = [
+                    &mut mouth.jaw_open,
+                    &mut mouth.mouth_width,
+                    &mut mouth.pucker,
+                    &mut mouth.funnel,
+                    &mut mouth.width_ratio,
+                    &mut mouth.open_ratio,
+                    &mut mouth_open,
+                    &mut smile,
+                ];
+                state.mouth.apply(settings, values, dt);
+                if let Some(expressions) = face.expressions.as_mut() {
+                    expressions.mouth_open = mouth_open;
+                    expressions.smile = smile;
+                }
+            }
+        }
+    }
+
+    /// Forget all filter state
+    pub fn reset(&mut self) {
+        self.faces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jitter(i: usize) -> f32 {
+        [1.0, -1.0][i % 2]
+    }
+
+    #[test]
+    fn test_one_euro_reduces_jitter() {
+        let mut filter = OneEuroFilter::new(1.0, 0.0, 1.0);
+        let outputs: Vec<f32> = (0..30).map(|i| filter.filter(10.0 + jitter(i), 1.0 / 30.0)).collect();
+        let tail_spread = outputs[20..].iter().fold(0.0f32, |m, v| m.max((v - 10.0).abs()));
+        assert!(tail_spread < 1.0);
+    }
+
+    #[test]
+    fn test_kalman_converges() {
+        let mut filter = KalmanFilter::new(1e-3, 1e-2);
+        let mut value = 0.0;
+        for _ in 0..100 {
+            value = filter.filter(5.0, 1.0 / 30.0);
+        }
+        assert!((value - 5.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_ema_first_sample_passthrough() {
+        let mut filter = EmaFilter::new(0.5);
+        assert_eq!(filter.filter(4.0, 0.0), 4.0);
+        assert_eq!(filter.filter(8.0, 0.0), 6.0);
+    }
+
+    #[test]
+    fn test_channels_use_their_own_settings() {
+        let light = ChannelSmoothing { filter_type: FilterType::Ema, ema_alpha: 1.0, ..ChannelSmoothing::default() };
+        let heavy = ChannelSmoothing { filter_type: FilterType::Ema, ema_alpha: 0.1, ..ChannelSmoothing::default() };
+        let config = SmoothingConfig { mouth: Some(light), gaze: Some(heavy), ..SmoothingConfig::default() };
+        assert_eq!(config.channel(SmoothingChannel::Pose), Some(config.shared()));
+        assert_eq!(config.channel(SmoothingChannel::Eyes), None);
+
+        let mut mouth = ChannelFilters::default();
+        let mut gaze = ChannelFilters::default();
+        let (mut jaw, mut yaw) = (0.0, 0.0);
+        mouth.apply(&light, [&mut jaw], 0.033);
+        gaze.apply(&heavy, [&mut yaw], 0.033);
+        let (mut jaw, mut yaw) = (1.0, 1.0);
+        mouth.apply(&light, [&mut jaw], 0.033);
+        gaze.apply(&heavy, [&mut yaw], 0.033);
+        assert_eq!(jaw, 1.0);
+        assert!((yaw - 0.1).abs() < 1e-6);
+
+        let disabled = SmoothingConfig { enabled: false, ..config };
+        assert_eq!(disabled.channel(SmoothingChannel::Mouth), None);
+    }
+
+    #[test]
+    fn test_pose_smoothing_wraps_around() {
+        let mut smoother = FaceSmoother::new(SmoothingConfig::default(), 30);
+        let origin = Point3D { x: 0.0, y: 0.0, z: 0.0 };
+        for (i, yaw) in [179.0, -179.0, 179.0, -179.0, -175.0].into_iter().enumerate() {
+            let pose = HeadPose::from_euler(0.0, yaw, -yaw, origin, 1.0);
+            let mut faces = vec![Face { pose: Some(pose), timestamp: i as i64 * 33, ..Face::default() }];
+            smoother.apply(&mut faces);
+
+            // Stays near the back of the circle and within range
+            let pose = faces[0].pose.unwrap();
+            for angle in [pose.yaw, pose.roll] {
+                assert!(angle.abs() > 170.0 && (-180.0..180.0).contains(&angle), "smoothed to {}", angle);
+            }
+        }
+    }
+
+    #[test]
+    fn test_disabled_smoother_is_noop() {
+        let config = SmoothingConfig { enabled: false, ..Default::default() };
+        let mut smoother = FaceSmoother::new(config, 30);
+        let mut faces: Vec<Face> = Vec::new();
+        smoother.apply(&mut faces);
+        assert!(smoother.faces.is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! Bounded frame queue
+//!
+//! Frames pushed for the tracking stream wait here for the stream worker.
+//! The queue holds a fixed number of frames so a slow consumer cannot grow
+//! it without limit; what happens to a frame arriving at a full queue is
+//! chosen by the [`QueuePolicy`].
+
+use crate::error::PluginError;
+use crate::models::QueuePolicy;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Bounded queue between frame producers and the stream worker
+pub struct FrameQueue<T> {
+    state: Mutex<QueueState<T>>,
+    capacity: usize,
+    policy: QueuePolicy,
+    /// Frames dropped because the queue was full
+    dropped: Arc<AtomicU64>,
+    /// Woken when a frame was queued or the queue closed
+    available: Notify,
+    /// Woken when a frame was taken or the queue closed
+    space: Notify,
+}
+
+struct QueueState<T> {
+    frames: VecDeque<T>,
+    closed: bool,
+}
+
+impl<T> FrameQueue<T> {
+    /// Queue of `capacity` frames, counting dropped frames in `dropped`
+    pub fn new(capacity: usize, policy: QueuePolicy, dropped: Arc<AtomicU64>) -> Self {
+        Self {
+            state: Mutex::new(QueueState { frames: VecDeque::with_capacity(capacity), closed: false }),
+            capacity: capacity.max(1),
+            policy,
+            dropped,
+            available: Notify::new(),
+            space: Notify::new(),
+        }
+    }
+
+    /// Queue a frame
+    ///
+    /// Returns `Ok(false)` if the frame was dropped. With
+    /// [`QueuePolicy::Block`] this waits until the worker takes a frame.
+    pub async fn push(&self, frame: T) -> Result<bool, PluginError> {
+        let mut frame = Some(frame);
+        loop {
+            let space = self.space.notified();
+            tokio::pin!(space);
+            space.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return Err(PluginError::ProcessingError("Tracking stream has been closed".to_string()));
+                }
+                if state.frames.len() >= self.capacity {
+                    match self.policy {
+                        QueuePolicy::DropNewest => {
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                            return Ok(false);
+                        }
+                        QueuePolicy::DropOldest => {
+                            state.frames.pop_front();
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                        }
+                        QueuePolicy::Block => {}
+                    }
+                }
+                if state.frames.len() < self.capacity {
+                    state.frames.extend(frame.take());
+                    self.available.notify_one();
+                    return Ok(true);
+                }
+            }
+            space.await;
+        }
+    }
+
+    /// Take the oldest frame, waiting for one to arrive
+    ///
+    /// Returns `None` once the queue is closed; frames still queued are
+    /// dropped.
+    pub async fn pop(&self) -> Option<T> {
+        loop {
+            let available = self.available.notified();
+            tokio::pin!(available);
+            available.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return None;
+                }
+                if let Some(frame) = state.frames.pop_front() {
+                    self.space.notify_one();
+                    return Some(frame);
+                }
+            }
+            available.await;
+        }
+    }
+
+    /// Take the oldest frame if one is queued
+    pub fn try_pop(&self) -> Option<T> {
+        let frame = self.state.lock().unwrap().frames.pop_front();
+        if frame.is_some() {
+            self.space.notify_one();
+        }
+        frame
+    }
+
+    /// Number of frames waiting
+    pub fn depth(&self) -> usize {
+        self.state.lock().unwrap().frames.len()
+    }
+
+    /// Whether the queue has been closed
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// Drop the queued frames and end waiting producers and the worker
+    pub fn close(&self) {
+        let frames = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            std::mem::take(&mut state.frames)
+        };
+        // Dropping frames may release shared buffer slots; not under the lock
+        drop(frames);
+        self.available.notify_waiters();
+        self.space.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(policy: QueuePolicy) -> FrameQueue<u32> {
+        FrameQueue::new(2, policy, Arc::new(AtomicU64::new(0)))
+    }
+
+    #[tokio::test]
+    async fn test_overflow_policies() {
+        let newest = queue(QueuePolicy::DropNewest);
+        let oldest = queue(QueuePolicy::DropOldest);
+        for frame in 1..=3 {
+            newest.push(frame).await.unwrap();
+            oldest.push(frame).await.unwrap();
+        }
+        assert_eq!([newest.try_pop(), newest.try_pop()], [Some(1), Some(2)]);
+        assert_eq!([oldest.try_pop(), oldest.try_pop()], [Some(2), Some(3)]);
+        assert_eq!(newest.dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(oldest.dropped.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_space() {
+        let queue = Arc::new(queue(QueuePolicy::Block));
+        queue.push(1).await.unwrap();
+        queue.push(2).await.unwrap();
+
+        let producer = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.push(3).await }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(queue.depth(), 2);
+
+        assert_eq!(queue.pop().await, Some(1));
+        assert!(producer.await.unwrap().unwrap());
+        assert_eq!(queue.depth(), 2);
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 0);
+
+        queue.close();
+        assert_eq!(queue.pop().await, None);
+        assert!(queue.push(4).await.is_err());
+    }
+}
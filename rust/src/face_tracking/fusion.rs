@@ -0,0 +1,217 @@
+//! Confidence-weighted temporal fusion
+//!
+//! In poor lighting the detector's confidence dips and its landmarks and
+//! pose jump around; taken at face value, the avatar snaps to every bad
+//! measurement. [`ConfidenceFusion`] treats confidence as a measurement
+//! weight instead: each value is blended between the new measurement and
+//! the previous fused value carried forward by its velocity, leaning on the
+//! measurement in proportion to confidence. Confident detections pass
+//! through unchanged. The stage runs right after ID assignment, so every
+//! later stage sees the fused values.
+
+use crate::face_tracking::imu::blend_angle;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Gap between frames after which a face's state is taken afresh (s)
+const MAX_GAP: f32 = 0.25;
+
+/// Confidence fusion settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfidenceFusionConfig {
+    /// Blend low-confidence measurements with the previous state
+    pub enabled: bool,
+    /// Confidence from which measurements are used as is (0.0 - 1.0)
+    pub full_confidence: f32,
+    /// Smallest weight of a measurement (0.0 - 1.0), so the output keeps
+    /// following the face even at very low confidence
+    pub min_weight: f32,
+}
+
+impl Default for ConfidenceFusionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            full_confidence: 0.6,
+            min_weight: 0.1,
+        }
+    }
+}
+
+impl ConfidenceFusionConfig {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            self.full_confidence > 0.0 && self.full_confidence <= 1.0,
+            "full_confidence",
+            "must be greater than 0.0 and at most 1.0",
+            "Use 0.6",
+        );
+        report.check(
+            self.min_weight > 0.0 && self.min_weight <= 1.0,
+            "min_weight",
+            "must be greater than 0.0 and at most 1.0",
+            "Use 0.1",
+        );
+        report
+    }
+
+    /// Weight of a measurement made with `confidence`
+    fn weight(&self, confidence: f32) -> f32 {
+        (confidence / self.full_confidence).clamp(self.min_weight, 1.0)
+    }
+}
+
+/// Fused value and its rate of change
+#[derive(Debug, Clone, Copy, Default)]
+struct FusedValue {
+    value: f32,
+    velocity: f32,
+}
+
+impl FusedValue {
+    /// Fuse a measurement taken `dt` seconds after the previous one
+    fn fuse(&mut self, measured: f32, weight: f32, dt: f32) -> f32 {
+        let predicted = self.value + self.velocity * dt;
+        let fused = predicted + (measured - predicted) * weight;
+        self.velocity = (fused - self.value) / dt;
+        self.value = fused;
+        fused
+    }
+
+    /// Fuse an angle in degrees, through the shorter way around the circle
+    fn fuse_angle(&mut self, measured: f32, weight: f32, dt: f32) -> f32 {
+        let predicted = self.value + self.velocity * dt;
+        let fused = blend_angle(measured, predicted, 1.0 - weight);
+        let change = (fused - self.value + 180.0).rem_euclid(360.0) - 180.0;
+        self.velocity = change / dt;
+        self.value = fused;
+        fused
+    }
+}
+
+struct FaceState {
+    /// Pitch, yaw, roll and translation
+    pose: Option<[FusedValue; 6]>,
+    /// x and y of every landmark
+    landmarks: Vec<FusedValue>,
+    timestamp: i64,
+}
+
+/// Per-face blending of low-confidence measurements with the previous state
+pub struct ConfidenceFusion {
+    config: ConfidenceFusionConfig,
+    faces: HashMap<u32, FaceState>,
+}
+
+impl ConfidenceFusion {
+    pub fn new(config: ConfidenceFusionConfig) -> Self {
+        Self { config, faces: HashMap::new() }
+    }
+
+    /// Fuse the landmarks and pose of all faces in place
+    ///
+    /// Landmarks are weighted by the face confidence, the pose by its own
+    /// confidence. State of faces that are no longer present is discarded.
+    pub fn apply(&mut self, faces: &mut [Face]) {
+        if !self.config.enabled {
+            return;
+        }
+        self.faces.retain(|id, _| faces.iter().any(|face| face.id == *id));
+
+        let config = &self.config;
+        for face in faces.iter_mut() {
+            let state = self.faces.entry(face.id).or_insert_with(|| FaceState {
+                pose: None,
+                landmarks: Vec::new(),
+                timestamp: face.timestamp,
+            });
+            let dt = (face.timestamp - state.timestamp) as f32 / 1000.0;
+            state.timestamp = face.timestamp;
+            let fresh = dt <= 0.0 || dt > MAX_GAP;
+
+            match face.pose.as_mut() {
+                Some(pose) => {
+                    let weight = config.weight(pose.confidence);
+                    match state.pose.as_mut().filter(|_| !fresh) {
+                        Some(fused) => {
+                            pose.pitch = fused[0].fuse_angle(pose.pitch, weight, dt);
+                            pose.yaw = fused[1].fuse_angle(pose.yaw, weight, dt);
+                            pose.roll = fused[2].fuse_angle(pose.roll, weight, dt);
+                            pose.translation.x = fused[3].fuse(pose.translation.x, weight, dt);
+                            pose.translation.y = fused[4].fuse(pose.translation.y, weight, dt);
+                            pose.translation.z = fused[5].fuse(pose.translation.z, weight, dt);
+                            pose.update_quaternion();
+                        }
+                        None => {
+                            let t = pose.translation;
+                            state.pose = Some(
+                                [pose.pitch, pose.yaw, pose.roll, t.x, t.y, t.z]
+                                    .map(|value| FusedValue { value, velocity: 0.0 }),
+                            );
+                        }
+                    }
+                }
+                None => state.pose = None,
+            }
+
+            match face.landmarks.as_mut() {
+                Some(landmarks) if !fresh && state.landmarks.len() == landmarks.points.len() * 2 => {
+                    let weight = config.weight(face.confidence);
+                    for (point, fused) in landmarks.points.iter_mut().zip(state.landmarks.chunks_exact_mut(2)) {
+                        point.x = fused[0].fuse(point.x, weight, dt);
+                        point.y = fused[1].fuse(point.y, weight, dt);
+                    }
+                }
+                Some(landmarks) => {
+                    state.landmarks = landmarks
+                        .points
+                        .iter()
+                        .flat_map(|point| [point.x, point.y])
+                        .map(|value| FusedValue { value, velocity: 0.0 })
+                        .collect();
+                }
+                None => state.landmarks.clear(),
+            }
+        }
+    }
+
+    /// Forget the state of all faces
+    pub fn reset(&mut self) {
+        self.faces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_confidence_leans_on_previous_state() {
+        let config = ConfidenceFusionConfig::default();
+        let dt = 1.0 / 30.0;
+
+        // Moving steadily at 30 units/s with full confidence
+        let mut fused = FusedValue::default();
+        for frame in 1..=10 {
+            assert_eq!(fused.fuse(frame as f32, config.weight(0.9), dt), frame as f32);
+        }
+
+        // A jump measured at a third of the full confidence moves the output
+        // only a third of the way from the continued motion
+        let value = fused.fuse(41.0, config.weight(0.2), dt);
+        assert!((value - 21.0).abs() < 1e-3, "fused {}", value);
+
+        // Even without confidence the measurement keeps a small weight
+        assert_eq!(config.weight(0.0), config.min_weight);
+
+        let mut angle = FusedValue { value: 179.0, velocity: 0.0 };
+        assert!((angle.fuse_angle(-179.0, 0.5, dt).abs() - 180.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,211 @@
+//! Gaze-to-screen mapping
+//!
+//! During calibration the user looks at known points on the screen and each
+//! point is paired with the gaze direction observed at that moment. Once
+//! enough points are recorded, an affine map from gaze angles to normalized
+//! screen coordinates is fit by least squares and applied to every face's
+//! gaze as [`EyeGaze::screen_gaze`].
+
+use crate::error::PluginError;
+use crate::models::*;
+
+/// Calibration points needed before screen gaze is reported
+pub const MIN_CALIBRATION_POINTS: usize = 3;
+
+/// Affine map from (yaw, pitch) in radians to screen (x, y)
+type Mapping = [[f64; 3]; 2];
+
+/// Gaze calibration and screen mapping
+#[derive(Debug, Default)]
+pub struct GazeMapper {
+    /// Gaze angles of the most confident face in the last frame
+    last_angles: Option<[f64; 2]>,
+    /// Recorded (gaze angles, screen point) pairs while calibrating
+    samples: Option<Vec<([f64; 2], Point2D)>>,
+    /// Fitted mapping, if calibrated
+    mapping: Option<Mapping>,
+}
+
+impl GazeMapper {
+    /// Create an uncalibrated mapper
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Discard the current calibration and start recording points
+    pub fn start_calibration(&mut self) {
+        self.samples = Some(Vec::new());
+        self.mapping = None;
+    }
+
+    /// Pair the current gaze with the normalized screen point being looked at
+    ///
+    /// Returns whether enough points are recorded for screen gaze to be
+    /// reported. Points that add no new information (e.g. all on one line)
+    /// are kept but do not complete the calibration.
+    pub fn add_point(&mut self, screen_x: f32, screen_y: f32) -> Result<bool, PluginError> {
+        if !(0.0..=1.0).contains(&screen_x) || !(0.0..=1.0).contains(&screen_y) {
+            return Err(PluginError::CalibrationError(
+                "Screen coordinates must be normalized to 0.0 - 1.0".to_string()
+            ));
+        }
+        let samples = self.samples.as_mut().ok_or_else(|| {
+            PluginError::CalibrationError("Gaze calibration has not been started".to_string())
+        })?;
+        let angles = self.last_angles.ok_or_else(|| {
+            PluginError::CalibrationError("No gaze was tracked in the last frame".to_string())
+        })?;
+
+        samples.push((angles, Point2D { x: screen_x, y: screen_y }));
+        if samples.len() >= MIN_CALIBRATION_POINTS {
+            self.mapping = fit(samples);
+        }
+        Ok(self.mapping.is_some())
+    }
+
+    /// Record the current gaze and set the screen gaze of all faces
+    pub fn apply(&mut self, faces: &mut [Face]) {
+        self.last_angles = faces
+            .iter()
+            .filter_map(|face| face.gaze.as_ref())
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+            .map(|gaze| angles(&gaze.combined_direction));
+
+        if let Some(mapping) = &self.mapping {
+            for gaze in faces.iter_mut().filter_map(|face| face.gaze.as_mut()) {
+                let [yaw, pitch] = angles(&gaze.combined_direction);
+                let project = |row: &[f64; 3]| (row[0] * yaw + row[1] * pitch + row[2]).clamp(0.0, 1.0) as f32;
+                gaze.screen_gaze = Some(Point2D {
+                    x: project(&mapping[0]),
+                    y: project(&mapping[1]),
+                });
+            }
+        }
+    }
+
+    /// Forget the last observed gaze, keeping the calibration
+    pub fn reset(&mut self) {
+        self.last_angles = None;
+    }
+}
+
+/// Yaw and pitch of a gaze direction in radians
+fn angles(direction: &Point3D) -> [f64; 2] {
+    let (x, y, z) = (direction.x as f64, direction.y as f64, direction.z as f64);
+    [x.atan2(z), (-y).atan2((x * x + z * z).sqrt())]
+}
+
+/// Least-squares affine fit of screen points to gaze angles
+///
+/// Returns `None` if the gaze angles do not span two dimensions.
+fn fit(samples: &[([f64; 2], Point2D)]) -> Option<Mapping> {
+    // Normal equations: (XᵀX) w = Xᵀt with rows X = [yaw, pitch, 1]
+    let mut normal = [[0.0; 3]; 3];
+    let mut rhs = [[0.0; 3]; 2];
+    for ([yaw, pitch], screen) in samples {
+        let row = [*yaw, *pitch, 1.0];
+        for i in 0..3 {
+            for j in 0..3 {
+                normal[i][j] += row[i] * row[j];
+            }
+            rhs[0][i] += row[i] * screen.x as f64;
+            rhs[1][i] += row[i] * screen.y as f64;
+        }
+    }
+
+    let det = determinant(&normal);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+
+    // Cramer's rule for each screen axis
+    let mut mapping = [[0.0; 3]; 2];
+    for (axis, target) in rhs.iter().enumerate() {
+        for k in 0..3 {
+            let mut replaced = normal;
+            for (i, row) in replaced.iter_mut().enumerate() {
+                row[k] = target[i];
+            }
+            mapping[axis][k] = determinant(&replaced) / det;
+        }
+    }
+    Some(mapping)
+}
+
+fn determinant(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A face gazing at the given yaw and pitch (radians)
+    fn face_gazing(yaw: f32, pitch: f32) -> Face {
+        let direction = Point3D {
+            x: yaw.sin() * pitch.cos(),
+            y: -pitch.sin(),
+            z: yaw.cos() * pitch.cos(),
+        };
+        Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            gaze: Some(EyeGaze {
+                left_eye_direction: direction,
+                right_eye_direction: direction,
+                combined_direction: direction,
+                confidence: 1.0,
+                screen_gaze: None,
+            }),
+            ..Face::default()
+        }
+    }
+
+    fn look_at(mapper: &mut GazeMapper, yaw: f32, pitch: f32) -> Option<Point2D> {
+        let mut faces = [face_gazing(yaw, pitch)];
+        mapper.apply(&mut faces);
+        faces[0].gaze.and_then(|g| g.screen_gaze)
+    }
+
+    #[test]
+    fn test_maps_gaze_after_calibration() {
+        let mut mapper = GazeMapper::new();
+        mapper.start_calibration();
+
+        // Screen corners at +-0.3 rad yaw and +-0.2 rad pitch
+        let corners = [(-0.3, 0.2, 0.0, 0.0), (0.3, 0.2, 1.0, 0.0), (-0.3, -0.2, 0.0, 1.0), (0.3, -0.2, 1.0, 1.0)];
+        let mut calibrated = Vec::new();
+        for (yaw, pitch, x, y) in corners {
+            look_at(&mut mapper, yaw, pitch);
+            calibrated.push(mapper.add_point(x, y).unwrap());
+        }
+        assert_eq!(calibrated, vec![false, false, true, true]);
+
+        let center = look_at(&mut mapper, 0.0, 0.0).unwrap();
+        assert!((center.x - 0.5).abs() < 0.02);
+        assert!((center.y - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_collinear_points_do_not_calibrate() {
+        let mut mapper = GazeMapper::new();
+        mapper.start_calibration();
+        for (i, yaw) in [-0.2, 0.0, 0.2].into_iter().enumerate() {
+            look_at(&mut mapper, yaw, 0.0);
+            assert!(!mapper.add_point(i as f32 / 2.0, 0.5).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_requires_started_calibration_and_gaze() {
+        let mut mapper = GazeMapper::new();
+        look_at(&mut mapper, 0.0, 0.0);
+        assert!(mapper.add_point(0.5, 0.5).is_err());
+
+        mapper.start_calibration();
+        mapper.apply(&mut []);
+        assert!(mapper.add_point(0.5, 0.5).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Head gesture recognition
+//!
+//! Recognizes nods (pitch oscillation), shakes (yaw oscillation) and tilts
+//! (roll held away from upright) from the head pose over time. Oscillations
+//! are found by counting direction reversals whose swing exceeds an
+//! amplitude threshold within a time window; the threshold follows from the
+//! configured sensitivity.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Swing amplitude in degrees needed at sensitivity 0.0
+const MAX_SWING_DEGREES: f32 = 20.0;
+
+/// Swing amplitude in degrees needed at sensitivity 1.0
+const MIN_SWING_DEGREES: f32 = 4.0;
+
+/// Swings (direction changes) that make up a nod or shake
+const SWINGS_PER_GESTURE: usize = 2;
+
+/// How long the head must stay tilted
+const TILT_HOLD_MS: i64 = 300;
+
+/// Recognized head gesture
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadGesture {
+    /// Head moved down and up
+    Nod,
+    /// Head turned side to side
+    Shake,
+    /// Head held tilted towards a shoulder
+    Tilt,
+}
+
+/// Head gesture recognition configuration
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GestureConfig {
+    /// Enable gesture recognition
+    pub enabled: bool,
+    /// Sensitivity (0.0 - 1.0); higher values recognize smaller movements
+    pub sensitivity: f32,
+    /// Time within which the swings of a nod or shake must happen (ms)
+    pub window_ms: u32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sensitivity: 0.5,
+            window_ms: 1000,
+        }
+    }
+}
+
+impl GestureConfig {
+    /// Check the sensitivity and window
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the sensitivity and window constraints
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check((0.0..=1.0).contains(&self.sensitivity), "sensitivity", "must be between 0.0 and 1.0", "Use 0.5");
+        report.check(self.window_ms > 0, "window_ms", "must be greater than 0", "Use 1000");
+        report
+    }
+
+    /// Swing amplitude in degrees a gesture needs
+    fn threshold(&self) -> f32 {
+        MAX_SWING_DEGREES - (MAX_SWING_DEGREES - MIN_SWING_DEGREES) * self.sensitivity
+    }
+}
+
+/// A recognized head gesture
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadGestureEvent {
+    /// ID of the gesturing face
+    pub face_id: u32,
+    /// Recognized gesture
+    pub gesture: HeadGesture,
+    /// Recognition confidence (0.0 - 1.0), higher for larger movements
+    pub confidence: f32,
+}
+
+/// Direction reversals of one pose angle
+#[derive(Debug, Default)]
+struct Oscillation {
+    /// Most extreme angle since the last reversal
+    extreme: Option<f32>,
+    /// Whether the angle is currently increasing, once it first moved
+    rising: Option<bool>,
+    /// Timestamp and amplitude of recent swings
+    swings: Vec<(i64, f32)>,
+}
+
+impl Oscillation {
+    /// Track a new angle and return the mean swing amplitude once enough
+    /// swings happened within the window
+    fn update(&mut self, angle: f32, timestamp: i64, threshold: f32, window_ms: i64) -> Option<f32> {
+        let extreme = *self.extreme.get_or_insert(angle);
+        let continues = match self.rising {
+            Some(true) => angle > extreme,
+            Some(false) => angle < extreme,
+            None => false,
+        };
+
+        if continues {
+            // Follow the angle further in the current direction
+            self.extreme = Some(angle);
+        } else if (angle - extreme).abs() >= threshold {
+            // Moved back far enough to count as a swing
+            self.swings.push((timestamp, (angle - extreme).abs()));
+            self.rising = Some(angle > extreme);
+            self.extreme = Some(angle);
+        }
+
+        self.swings.retain(|(t, _)| timestamp - t <= window_ms);
+        if self.swings.len() < SWINGS_PER_GESTURE {
+            return None;
+        }
+
+        let amplitude = self.swings.iter().map(|(_, a)| a).sum::<f32>() / self.swings.len() as f32;
+        self.swings.clear();
+        Some(amplitude)
+    }
+}
+
+/// Per-face gesture state
+#[derive(Debug, Default)]
+struct FaceGestures {
+    pitch: Oscillation,
+    yaw: Oscillation,
+    /// Timestamp since which the head is tilted, and whether it was reported
+    tilted_since: Option<(i64, bool)>,
+}
+
+/// Head gesture recognition across frames
+#[derive(Debug)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    faces: HashMap<u32, FaceGestures>,
+}
+
+impl GestureRecognizer {
+    /// Create a recognizer with the given configuration
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            faces: HashMap::new(),
+        }
+    }
+
+    /// Track the head pose of all faces and return the recognized gestures
+    ///
+    /// State of faces that are no longer present is discarded.
+    pub fn update(&mut self, faces: &[Face]) -> Vec<HeadGestureEvent> {
+        let mut events = Vec::new();
+        if !self.config.enabled {
+            return events;
+        }
+
+        self.faces.retain(|id, _| faces.iter().any(|f| f.id == *id));
+
+        let threshold = self.config.threshold();
+        let window_ms = self.config.window_ms as i64;
+        let confidence = |amplitude: f32| (amplitude / (2.0 * threshold)).min(1.0);
+
+        for face in faces {
+            let pose = match &face.pose {
+                Some(pose) => pose,
+                None => continue,
+            };
+            let state = self.faces.entry(face.id).or_default();
+            let mut event = |gesture, amplitude| {
+                events.push(HeadGestureEvent { face_id: face.id, gesture, confidence: confidence(amplitude) });
+            };
+
+            if let Some(amplitude) = state.pitch.update(pose.pitch, face.timestamp, threshold, window_ms) {
+                event(HeadGesture::Nod, amplitude);
+            }
+            if let Some(amplitude) = state.yaw.update(pose.yaw, face.timestamp, threshold, window_ms) {
+                event(HeadGesture::Shake, amplitude);
+            }
+
+            // A tilt is reported once per tilt, after it was held; it ends
+            // when the head is back within half the threshold
+            let roll = pose.roll.abs();
+            state.tilted_since = match state.tilted_since {
+                None if roll >= threshold => Some((face.timestamp, false)),
+                Some(_) if roll < threshold / 2.0 => None,
+                Some((since, false)) if face.timestamp - since >= TILT_HOLD_MS => {
+                    event(HeadGesture::Tilt, roll);
+                    Some((since, true))
+                }
+                tilted => tilted,
+            };
+        }
+
+        events
+    }
+
+    /// Forget all gesture state
+    pub fn reset(&mut self) {
+        self.faces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_posed(pitch: f32, yaw: f32, roll: f32, timestamp: i64) -> Face {
+        Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            pose: Some(HeadPose::from_euler(pitch, yaw, roll, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0)),
+            timestamp,
+            ..Face::default()
+        }
+    }
+
+    /// Feed pitch/yaw/roll samples 50ms apart and collect the gestures
+    fn run(recognizer: &mut GestureRecognizer, poses: &[(f32, f32, f32)]) -> Vec<HeadGesture> {
+        poses
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &(pitch, yaw, roll))| recognizer.update(&[face_posed(pitch, yaw, roll, i as i64 * 50)]))
+            .map(|event| event.gesture)
+            .collect()
+    }
+
+    #[test]
+    fn test_nod_and_shake() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        let nod: Vec<_> = [0.0, -8.0, -16.0, -8.0, 0.0, 4.0].iter().map(|&p| (p, 0.0, 0.0)).collect();
+        assert_eq!(run(&mut recognizer, &nod), vec![HeadGesture::Nod]);
+
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        let shake: Vec<_> = [0.0, 15.0, 0.0, -15.0, 0.0].iter().map(|&y| (0.0, y, 0.0)).collect();
+        assert_eq!(run(&mut recognizer, &shake), vec![HeadGesture::Shake]);
+    }
+
+    #[test]
+    fn test_sensitivity_controls_amplitude() {
+        let small_nod: Vec<_> = [0.0, -6.0, 0.0, -6.0].iter().map(|&p| (p, 0.0, 0.0)).collect();
+
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        assert!(run(&mut recognizer, &small_nod).is_empty());
+
+        let sensitive = GestureConfig { sensitivity: 1.0, ..GestureConfig::default() };
+        let mut recognizer = GestureRecognizer::new(sensitive);
+        assert_eq!(run(&mut recognizer, &small_nod), vec![HeadGesture::Nod]);
+    }
+
+    #[test]
+    fn test_held_tilt_is_reported_once() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        let tilt: Vec<_> = (0..12).map(|i| (0.0, 0.0, if i < 2 { 0.0 } else { 20.0 })).collect();
+        assert_eq!(run(&mut recognizer, &tilt), vec![HeadGesture::Tilt]);
+
+        // A brief tilt is not reported
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        assert!(run(&mut recognizer, &[(0.0, 0.0, 20.0), (0.0, 0.0, 20.0), (0.0, 0.0, 0.0)]).is_empty());
+    }
+}
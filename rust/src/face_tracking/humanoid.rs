@@ -0,0 +1,181 @@
+//! Humanoid bone mapping
+//!
+//! VRM and other humanoid avatars are driven by bone rotations rather than a
+//! single head pose. The head rotation is split between the Neck and Head
+//! bones, so the neck bends along instead of the head turning on a rigid
+//! neck, and the eye bones follow the gaze. Rotations are local to the parent
+//! bone in the tracker's Unity-style convention, as VMC and Unity/VRM
+//! renderers expect.
+
+use crate::error::PluginError;
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+
+/// Humanoid bone driven by face tracking
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HumanoidBone {
+    Neck,
+    Head,
+    /// Subject's left eye
+    LeftEye,
+    /// Subject's right eye
+    RightEye,
+}
+
+impl HumanoidBone {
+    /// Unity `HumanBodyBones` name, used by the VMC protocol
+    pub fn unity_name(self) -> &'static str {
+        match self {
+            HumanoidBone::Neck => "Neck",
+            HumanoidBone::Head => "Head",
+            HumanoidBone::LeftEye => "LeftEye",
+            HumanoidBone::RightEye => "RightEye",
+        }
+    }
+
+    /// VRM humanoid bone name
+    pub fn vrm_name(self) -> &'static str {
+        match self {
+            HumanoidBone::Neck => "neck",
+            HumanoidBone::Head => "head",
+            HumanoidBone::LeftEye => "leftEye",
+            HumanoidBone::RightEye => "rightEye",
+        }
+    }
+}
+
+/// Rotation of one bone relative to its parent
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoneRotation {
+    pub bone: HumanoidBone,
+    pub rotation: Quaternion,
+}
+
+/// How head pose and gaze map onto humanoid bones
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HumanoidConfig {
+    /// Share of the head rotation applied to the Neck bone (0.0 - 1.0); the
+    /// Head bone turns the rest of the way
+    pub neck_weight: f32,
+    /// Largest sideways eye rotation in degrees
+    pub max_eye_yaw: f32,
+    /// Largest up or down eye rotation in degrees
+    pub max_eye_pitch: f32,
+}
+
+impl Default for HumanoidConfig {
+    fn default() -> Self {
+        Self {
+            neck_weight: 0.3,
+            max_eye_yaw: 25.0,
+            max_eye_pitch: 20.0,
+        }
+    }
+}
+
+impl HumanoidConfig {
+    /// Check that the weight and limits are in range
+    pub fn check(&self) -> Result<(), PluginError> {
+        if !(0.0..=1.0).contains(&self.neck_weight) {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Neck weight must be between 0.0 and 1.0, got {}",
+                self.neck_weight
+            )));
+        }
+        if !(0.0..=90.0).contains(&self.max_eye_yaw) || !(0.0..=90.0).contains(&self.max_eye_pitch) {
+            return Err(PluginError::InvalidConfiguration(
+                "Eye rotation limits must be between 0 and 90 degrees".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Bone rotations for a face: Neck and Head if it has a pose, and both eyes
+/// if it has gaze
+///
+/// Expects the pose and gaze in the tracker's own conventions, i.e. faces
+/// returned with the default `OutputConventions`.
+pub fn bone_rotations(face: &Face, config: &HumanoidConfig) -> Vec<BoneRotation> {
+    let mut bones = Vec::with_capacity(4);
+    if let Some(pose) = &face.pose {
+        let head = pose.rotation_quaternion;
+        let neck = Quaternion::IDENTITY.slerp(head, config.neck_weight);
+        bones.push(BoneRotation { bone: HumanoidBone::Neck, rotation: neck });
+        bones.push(BoneRotation { bone: HumanoidBone::Head, rotation: neck.conjugate().multiply(head) });
+    }
+    if let Some(gaze) = &face.gaze {
+        bones.push(BoneRotation {
+            bone: HumanoidBone::LeftEye,
+            rotation: eye_rotation(&gaze.left_eye_direction, config),
+        });
+        bones.push(BoneRotation {
+            bone: HumanoidBone::RightEye,
+            rotation: eye_rotation(&gaze.right_eye_direction, config),
+        });
+    }
+    bones
+}
+
+/// Eye bone rotation looking along a head-relative gaze direction, where
+/// +Z looks straight ahead
+fn eye_rotation(direction: &Point3D, config: &HumanoidConfig) -> Quaternion {
+    let yaw = direction.x.atan2(direction.z).to_degrees();
+    let pitch = (-direction.y)
+        .atan2((direction.x * direction.x + direction.z * direction.z).sqrt())
+        .to_degrees();
+    Quaternion::from_euler(
+        pitch.clamp(-config.max_eye_pitch, config.max_eye_pitch),
+        yaw.clamp(-config.max_eye_yaw, config.max_eye_yaw),
+        0.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Quaternion, b: Quaternion) {
+        let dot: f32 = a.to_array().iter().zip(b.to_array()).map(|(a, b)| a * b).sum();
+        assert!(dot.abs() > 0.9999, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_neck_and_head_compose_to_pose() {
+        let pose = HeadPose::from_euler(15.0, -40.0, 10.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0);
+        let face = Face {
+            id: 1,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            pose: Some(pose),
+            gaze: Some(EyeGaze {
+                left_eye_direction: Point3D { x: 1.0, y: 0.0, z: 0.0 },
+                right_eye_direction: Point3D { x: 0.0, y: 0.0, z: 1.0 },
+                combined_direction: Point3D { x: 0.5, y: 0.0, z: 0.5 },
+                confidence: 1.0,
+                screen_gaze: None,
+            }),
+            ..Face::default()
+        };
+        let config = HumanoidConfig { neck_weight: 0.4, ..HumanoidConfig::default() };
+        let bones = bone_rotations(&face, &config);
+        let rotation = |bone| bones.iter().find(|b| b.bone == bone).unwrap().rotation;
+
+        let neck = rotation(HumanoidBone::Neck);
+        assert_close(neck.multiply(rotation(HumanoidBone::Head)), pose.rotation_quaternion);
+        // The neck turns 40% of the way
+        let neck_angle = 2.0 * neck.w.acos().to_degrees();
+        let full_angle = 2.0 * pose.rotation_quaternion.w.acos().to_degrees();
+        assert!((neck_angle - 0.4 * full_angle).abs() < 0.01);
+
+        // A sideways glance is clamped to the eye limit
+        assert_close(rotation(HumanoidBone::LeftEye), Quaternion::from_euler(0.0, 25.0, 0.0));
+        assert_close(rotation(HumanoidBone::RightEye), Quaternion::IDENTITY);
+
+        assert!(HumanoidConfig { neck_weight: 1.5, ..HumanoidConfig::default() }.check().is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Gyroscope fusion for head pose
+//!
+//! Visual head pose lags and jitters during fast motion, when frames blur,
+//! and drops out entirely for a frame or two. A gyroscope measures rotation
+//! at a high rate without either problem, but drifts. [`ImuFusion`] runs a
+//! complementary filter: the rotation integrated from the gyroscope since
+//! the previous frame predicts the new pose, and the visual pose pulls the
+//! prediction back, strongly while the sensor is at rest and less during
+//! fast motion. While the visual pose is missing the prediction is used on
+//! its own for up to [`ImuFusionConfig::max_dropout_ms`].
+//!
+//! The sensor is either mounted with the camera (the phone the app runs on),
+//! in which case its rotation shows up as the opposite apparent head
+//! rotation, or worn on the head (e.g. headphones with head tracking).
+//! Angular velocities are expected around the head pose axes: pitch, yaw
+//! and roll as reported in [`HeadPose`]. Increments are added to the Euler
+//! angles, which is accurate for the small rotation between two frames.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Integrated rotation kept for predicting poses
+const MAX_HISTORY: usize = 2048;
+
+/// Standard gravity (m/s²)
+const GRAVITY: f32 = 9.81;
+
+/// Linear acceleration beyond gravity counted as full-speed motion (m/s²)
+const FAST_ACCELERATION: f32 = 4.0;
+
+/// Where the inertial sensor is mounted
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImuMount {
+    /// On the device holding the camera; its rotation moves the camera
+    Camera,
+    /// On the tracked person's head
+    Head,
+}
+
+/// Gyroscope and accelerometer reading
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ImuSample {
+    /// Milliseconds since epoch, on the same clock as frame timestamps
+    pub timestamp: i64,
+    /// Angular velocity around the pitch, yaw and roll axes (rad/s)
+    pub gyroscope: Point3D,
+    /// Acceleration including gravity (m/s²), if available
+    pub accelerometer: Option<Point3D>,
+}
+
+/// IMU fusion settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImuFusionConfig {
+    /// Fuse pushed IMU samples into the head pose (off by default)
+    pub enabled: bool,
+    /// Where the sensor is mounted
+    pub mount: ImuMount,
+    /// Weight of the gyroscope prediction during fast motion (0.0 - 1.0);
+    /// at rest the visual pose is used as is
+    pub gyro_weight: f32,
+    /// Angular speed at which `gyro_weight` applies in full (degrees/s)
+    pub fast_motion_speed: f32,
+    /// Longest time the prediction stands in for a missing visual pose (ms)
+    pub max_dropout_ms: u32,
+    /// Pose confidence below which the visual pose counts as missing
+    pub min_visual_confidence: f32,
+}
+
+impl Default for ImuFusionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mount: ImuMount::Camera,
+            gyro_weight: 0.9,
+            fast_motion_speed: 120.0,
+            max_dropout_ms: 300,
+            min_visual_confidence: 0.3,
+        }
+    }
+}
+
+impl ImuFusionConfig {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            (0.0..=1.0).contains(&self.gyro_weight),
+            "gyro_weight",
+            "must be between 0.0 and 1.0",
+            "Use 0.9",
+        );
+        report.check(self.fast_motion_speed > 0.0, "fast_motion_speed", "must be positive", "Use 120");
+        report.check(
+            (0.0..=1.0).contains(&self.min_visual_confidence),
+            "min_visual_confidence",
+            "must be between 0.0 and 1.0",
+            "Use 0.3",
+        );
+        report
+    }
+}
+
+impl ImuSample {
+    /// Validate the reading
+    pub fn validate(&self) -> Result<(), PluginError> {
+        let finite = |point: &Point3D| point.x.is_finite() && point.y.is_finite() && point.z.is_finite();
+        if !finite(&self.gyroscope) || !self.accelerometer.as_ref().is_none_or(finite) {
+            return Err(PluginError::InvalidConfiguration("IMU sample values must be finite".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Fused pose of one face
+#[derive(Debug, Clone, Copy)]
+struct FaceState {
+    /// Pitch, yaw and roll (degrees)
+    angles: [f32; 3],
+    translation: Point3D,
+    /// Integrated sensor rotation at the last update
+    integrated: [f32; 3],
+    confidence: f32,
+    updated_at: i64,
+    /// Timestamp of the last usable visual pose
+    seen_at: i64,
+}
+
+/// Complementary filter fusing gyroscope rotation with visual head pose
+#[derive(Debug)]
+pub struct ImuFusion {
+    config: ImuFusionConfig,
+    /// Rotation integrated since the first sample (degrees), by timestamp
+    history: VecDeque<(i64, [f32; 3])>,
+    last_sample: Option<ImuSample>,
+    /// How fast the sensor moves relative to `fast_motion_speed` (0.0 - 1.0)
+    motion: f32,
+    faces: HashMap<u32, FaceState>,
+}
+
+impl ImuFusion {
+    pub fn new(config: ImuFusionConfig) -> Self {
+        Self {
+            config,
+            history: VecDeque::new(),
+            last_sample: None,
+            motion: 0.0,
+            faces: HashMap::new(),
+        }
+    }
+
+    /// Integrate a sensor reading; readings older than the previous one are
+    /// ignored
+    pub fn push(&mut self, sample: ImuSample) {
+        if !self.config.enabled {
+            return;
+        }
+        let sign = match self.config.mount {
+            ImuMount::Camera => -1.0,
+            ImuMount::Head => 1.0,
+        };
+        let rate = [sample.gyroscope.x, sample.gyroscope.y, sample.gyroscope.z].map(|w| sign * w.to_degrees());
+
+        let integrated = match (self.last_sample, self.history.back()) {
+            (Some(last), _) if sample.timestamp <= last.timestamp => return,
+            (Some(last), Some(&(_, integrated))) => {
+                let dt = (sample.timestamp - last.timestamp) as f32 / 1000.0;
+                [0, 1, 2].map(|axis| integrated[axis] + rate[axis] * dt)
+            }
+            _ => [0.0; 3],
+        };
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((sample.timestamp, integrated));
+
+        let speed = rate.iter().map(|w| w * w).sum::<f32>().sqrt();
+        let acceleration = sample.accelerometer.map_or(0.0, |a| {
+            ((a.x * a.x + a.y * a.y + a.z * a.z).sqrt() - GRAVITY).abs() / FAST_ACCELERATION
+        });
+        self.motion = (speed / self.config.fast_motion_speed).max(acceleration).clamp(0.0, 1.0);
+        self.last_sample = Some(sample);
+    }
+
+    /// Integrated rotation at `timestamp`, `None` before the first sample
+    fn integrated_at(&self, timestamp: i64) -> Option<[f32; 3]> {
+        let index = self.history.partition_point(|&(t, _)| t <= timestamp);
+        index.checked_sub(1).map(|index| self.history[index].1)
+    }
+
+    /// Fuse the gyroscope rotation into the poses of faces detected at
+    /// `timestamp`
+    ///
+    /// Runs after ID assignment; faces without a usable visual pose get the
+    /// predicted pose while the dropout is short enough.
+    pub fn apply(&mut self, faces: &mut [Face], timestamp: i64) {
+        if !self.config.enabled {
+            return;
+        }
+        let Some(integrated) = self.integrated_at(timestamp) else {
+            return;
+        };
+        let max_dropout = self.config.max_dropout_ms as i64;
+        let weight = self.config.gyro_weight * self.motion;
+
+        for face in faces.iter_mut() {
+            let visual = face.pose.filter(|pose| pose.confidence >= self.config.min_visual_confidence);
+            let predicted = self.faces.get(&face.id).map(|state| {
+                let angles = [0, 1, 2].map(|axis| state.angles[axis] + integrated[axis] - state.integrated[axis]);
+                (angles, *state)
+            });
+
+            let (angles, translation, confidence, seen_at) = match (visual, predicted) {
+                (Some(pose), Some((predicted, _))) => {
+                    let measured = [pose.pitch, pose.yaw, pose.roll];
+                    let angles = [0, 1, 2].map(|axis| blend_angle(measured[axis], predicted[axis], weight));
+                    (angles, pose.translation, pose.confidence, timestamp)
+                }
+                (Some(pose), None) => {
+                    ([pose.pitch, pose.yaw, pose.roll], pose.translation, pose.confidence, timestamp)
+                }
+                (None, Some((predicted, state))) if timestamp - state.seen_at <= max_dropout => {
+                    // Confidence fades out over the dropout; the face keeps
+                    // its last position
+                    let remaining = 1.0 - (timestamp - state.seen_at) as f32 / (max_dropout + 1) as f32;
+                    (predicted, state.translation, state.confidence * remaining, state.seen_at)
+                }
+                (None, _) => {
+                    self.faces.remove(&face.id);
+                    continue;
+                }
+            };
+
+            face.pose = Some(HeadPose::from_euler(angles[0], angles[1], angles[2], translation, confidence));
+            self.faces.insert(
+                face.id,
+                FaceState { angles, translation, integrated, confidence, updated_at: timestamp, seen_at },
+            );
+        }
+        self.faces.retain(|_, state| timestamp - state.updated_at <= max_dropout);
+    }
+
+    /// Forget the sensor history and the fused poses
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+}
+
+/// Blend `measured` towards `predicted` by `weight`, through the shorter way
+/// around the circle
+pub(crate) fn blend_angle(measured: f32, predicted: f32, weight: f32) -> f32 {
+    let difference = (predicted - measured + 180.0).rem_euclid(360.0) - 180.0;
+    let angle = measured + difference * weight;
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A face turned by `yaw` degrees, without a pose for `None`
+    fn face(id: u32, yaw: Option<f32>, timestamp: i64) -> Face {
+        let origin = Point3D { x: 0.0, y: 0.0, z: 0.0 };
+        Face {
+            id,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            pose: yaw.map(|yaw| HeadPose::from_euler(0.0, yaw, 0.0, origin, 0.9)),
+            timestamp,
+            ..Face::default()
+        }
+    }
+
+    fn sample(timestamp: i64, yaw_rate: f32) -> ImuSample {
+        ImuSample {
+            timestamp,
+            gyroscope: Point3D { x: 0.0, y: yaw_rate, z: 0.0 },
+            accelerometer: None,
+        }
+    }
+
+    #[test]
+    fn test_fills_in_and_stabilizes_pose() {
+        let config = ImuFusionConfig { enabled: true, mount: ImuMount::Head, ..ImuFusionConfig::default() };
+        let mut fusion = ImuFusion::new(config);
+        // Head turning at 180 degrees/s
+        let rate = 180f32.to_radians();
+        fusion.push(sample(0, rate));
+        let mut faces = vec![face(1, Some(0.0), 0)];
+        fusion.apply(&mut faces, 0);
+
+        // A dropped visual pose is predicted from the gyroscope
+        fusion.push(sample(100, rate));
+        let mut faces = vec![face(1, None, 100)];
+        fusion.apply(&mut faces, 100);
+        let pose = faces[0].pose.unwrap();
+        assert!((pose.yaw - 18.0).abs() < 1e-3);
+        assert!(pose.confidence < 0.9);
+
+        // During fast motion a lagging visual pose is pulled towards the prediction
+        fusion.push(sample(200, rate));
+        let mut faces = vec![face(1, Some(30.0), 200)];
+        fusion.apply(&mut faces, 200);
+        let yaw = faces[0].pose.unwrap().yaw;
+        assert!((yaw - (30.0 + (36.0 - 30.0) * config.gyro_weight)).abs() < 1e-3);
+
+        // Beyond the dropout limit the face has no pose
+        let mut faces = vec![face(1, None, 600)];
+        fusion.apply(&mut faces, 600);
+        assert!(faces[0].pose.is_none());
+
+        // Angles are blended across the +-180 degree seam
+        assert!((blend_angle(170.0, -170.0, 0.5).abs() - 180.0).abs() < 1e-3);
+    }
+}
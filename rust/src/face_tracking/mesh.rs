@@ -0,0 +1,260 @@
+//! Dense face mesh
+//!
+//! Apps rendering a face overlay need a surface rather than 68 loose points.
+//! The landmarks of an average frontal face are triangulated once (Delaunay)
+//! and every triangle is split into four at its edge midpoints. Each mesh
+//! vertex is either a landmark or the midpoint of two, so a tracked face's
+//! mesh is interpolated from its landmarks without another model.
+
+use crate::models::*;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+lazy_static! {
+    static ref MESH: MeshDefinition = MeshDefinition::build();
+}
+
+/// Triangulation of the average face and how each vertex is derived
+struct MeshDefinition {
+    topology: FaceMeshTopology,
+    /// Landmark pair of each vertex after the 68 landmarks
+    midpoints: Vec<(usize, usize)>,
+}
+
+impl MeshDefinition {
+    fn build() -> Self {
+        let canonical = canonical_landmarks();
+        let base = delaunay(&canonical);
+
+        let mut midpoints = Vec::new();
+        let mut index: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize| {
+            *index.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                midpoints.push((a, b));
+                canonical.len() + midpoints.len() - 1
+            })
+        };
+
+        let mut triangles = Vec::with_capacity(base.len() * 12);
+        for [a, b, c] in base {
+            let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+            for triangle in [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]] {
+                triangles.extend(triangle.map(|i| i as u32));
+            }
+        }
+
+        let uvs = canonical
+            .iter()
+            .copied()
+            .chain(midpoints.iter().map(|&(a, b)| midpoint_of(canonical[a], canonical[b])))
+            .collect::<Vec<_>>();
+
+        Self {
+            topology: FaceMeshTopology { vertex_count: uvs.len() as u32, triangles, uvs },
+            midpoints,
+        }
+    }
+}
+
+/// Triangulation shared by every face mesh
+pub fn topology() -> FaceMeshTopology {
+    MESH.topology.clone()
+}
+
+/// Interpolate the dense mesh of a face from its 68 landmarks
+pub fn build(landmarks: &FacialLandmarks) -> Option<FaceMesh> {
+    if landmarks.points.len() != 68 {
+        return None;
+    }
+    let points = &landmarks.points;
+    let vertices = points
+        .iter()
+        .copied()
+        .chain(MESH.midpoints.iter().map(|&(a, b)| midpoint_of(points[a], points[b])))
+        .collect();
+    Some(FaceMesh { vertices })
+}
+
+/// Mesh vertices of a facial region: its landmarks, then the midpoints
+/// between two of its landmarks
+pub fn region_indices(region: LandmarkRegion) -> Vec<u32> {
+    let range = region.landmark_range();
+    range
+        .clone()
+        .chain(
+            MESH.midpoints
+                .iter()
+                .enumerate()
+                .filter(|(_, (a, b))| range.contains(a) && range.contains(b))
+                .map(|(i, _)| 68 + i),
+        )
+        .map(|i| i as u32)
+        .collect()
+}
+
+/// Add meshes to the faces that have landmarks
+pub fn apply(faces: &mut [Face]) {
+    for face in faces.iter_mut() {
+        face.mesh = face.landmarks.as_ref().and_then(build);
+    }
+}
+
+fn midpoint_of(a: Point2D, b: Point2D) -> Point2D {
+    Point2D { x: (a.x + b.x) / 2.0, y: (a.y + b.y) / 2.0 }
+}
+
+/// Landmarks of an average frontal face in a unit square (Y down)
+fn canonical_landmarks() -> Vec<Point2D> {
+    let mut points = Vec::with_capacity(68);
+    let mut push = |x: f64, y: f64| points.push(Point2D { x: x as f32, y: y as f32 });
+
+    // Jaw (0-16) along the lower half of an ellipse
+    for i in 0..17 {
+        let t = PI * i as f64 / 16.0;
+        push(0.5 - 0.45 * t.cos(), 0.35 + 0.6 * t.sin());
+    }
+    // Eyebrows (17-21, 22-26), arching upwards
+    for start in [0.12, 0.58] {
+        for i in 0..5 {
+            let s = i as f64 / 4.0;
+            push(start + 0.3 * s, 0.22 - 0.05 * (PI * s).sin());
+        }
+    }
+    // Nose bridge (27-30), slightly off the vertical so no three points
+    // are collinear
+    for i in 0..4 {
+        push(0.5 + 0.002 * i as f64, 0.32 + 0.08 * i as f64);
+    }
+    // Nostrils (31-35)
+    for (x, y) in [(0.42, 0.62), (0.46, 0.635), (0.5, 0.645), (0.54, 0.635), (0.58, 0.62)] {
+        push(x, y);
+    }
+    // Eyes (36-41, 42-47) from the left corner over the top
+    for cx in [0.3, 0.7] {
+        for i in 0..6 {
+            let a = PI - PI / 3.0 * i as f64;
+            push(cx + 0.08 * a.cos(), 0.34 - 0.03 * a.sin());
+        }
+    }
+    // Outer lips (48-59) and inner lips (60-67), from the left corner over
+    // the top
+    for i in 0..12 {
+        let a = PI - PI / 6.0 * i as f64;
+        push(0.5 + 0.16 * a.cos(), 0.78 - 0.07 * a.sin());
+    }
+    for i in 0..8 {
+        let a = PI - PI / 4.0 * i as f64;
+        push(0.5 + 0.1 * a.cos(), 0.78 - 0.03 * a.sin());
+    }
+    points
+}
+
+/// Delaunay triangulation (Bowyer-Watson) with counter-clockwise winding
+fn delaunay(points: &[Point2D]) -> Vec<[usize; 3]> {
+    let mut vertices: Vec<[f64; 2]> = points.iter().map(|p| [p.x as f64, p.y as f64]).collect();
+    let n = vertices.len();
+    // Super triangle enclosing the unit square
+    vertices.extend([[-10.0, -10.0], [10.0, -10.0], [0.5, 10.0]]);
+
+    let mut triangles: Vec<[usize; 3]> = vec![oriented(&vertices, [n, n + 1, n + 2])];
+    for p in 0..n {
+        let (bad, good): (Vec<_>, Vec<_>) = triangles
+            .into_iter()
+            .partition(|&t| in_circumcircle(&vertices, t, vertices[p]));
+
+        // The boundary of the removed cavity is made of the edges that
+        // belong to a single removed triangle
+        let mut edges: HashMap<(usize, usize), u32> = HashMap::new();
+        for [a, b, c] in &bad {
+            for (u, v) in [(*a, *b), (*b, *c), (*c, *a)] {
+                *edges.entry((u.min(v), u.max(v))).or_default() += 1;
+            }
+        }
+
+        triangles = good;
+        let mut boundary: Vec<_> = edges.into_iter().filter(|&(_, count)| count == 1).map(|(edge, _)| edge).collect();
+        boundary.sort_unstable();
+        for (u, v) in boundary {
+            triangles.push(oriented(&vertices, [u, v, p]));
+        }
+    }
+
+    triangles.retain(|t| t.iter().all(|&i| i < n));
+    triangles.sort_unstable();
+    triangles
+}
+
+fn cross(v: &[[f64; 2]], [a, b, c]: [usize; 3]) -> f64 {
+    (v[b][0] - v[a][0]) * (v[c][1] - v[a][1]) - (v[b][1] - v[a][1]) * (v[c][0] - v[a][0])
+}
+
+fn oriented(v: &[[f64; 2]], [a, b, c]: [usize; 3]) -> [usize; 3] {
+    if cross(v, [a, b, c]) < 0.0 { [a, c, b] } else { [a, b, c] }
+}
+
+/// Whether `p` lies inside the circumcircle of the counter-clockwise `t`
+fn in_circumcircle(v: &[[f64; 2]], t: [usize; 3], p: [f64; 2]) -> bool {
+    let rows = t.map(|i| {
+        let (dx, dy) = (v[i][0] - p[0], v[i][1] - p[1]);
+        [dx, dy, dx * dx + dy * dy]
+    });
+    let [a, b, c] = rows;
+    let det = a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0]) + a[2] * (b[0] * c[1] - b[1] * c[0]);
+    det > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topology_is_consistent() {
+        let topology = topology();
+        assert!((200..1000).contains(&topology.vertex_count), "{}", topology.vertex_count);
+        assert_eq!(topology.uvs.len(), topology.vertex_count as usize);
+        assert_eq!(topology.triangles.len() % 3, 0);
+        assert!(topology.triangles.iter().all(|&i| i < topology.vertex_count));
+
+        // Every triangle keeps the same winding and covers some area
+        let uvs: Vec<[f64; 2]> = topology.uvs.iter().map(|p| [p.x as f64, p.y as f64]).collect();
+        for triangle in topology.triangles.chunks(3) {
+            let t = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+            assert!(cross(&uvs, t) > 0.0, "{:?}", t);
+        }
+    }
+
+    #[test]
+    fn test_region_indices() {
+        let topology = topology();
+        let eye = region_indices(LandmarkRegion::LeftEye);
+        assert_eq!(eye[..6], [42, 43, 44, 45, 46, 47]);
+        // The eye outline's midpoints lie around the left eye
+        assert!(eye.len() > 6);
+        for &i in &eye[6..] {
+            let uv = topology.uvs[i as usize];
+            assert!((uv.x - 0.7).abs() < 0.1 && (uv.y - 0.34).abs() < 0.05, "{:?}", uv);
+        }
+
+        let lips = region_indices(LandmarkRegion::OuterLips).len() + region_indices(LandmarkRegion::InnerLips).len();
+        assert!(region_indices(LandmarkRegion::Mouth).len() > lips);
+    }
+
+    #[test]
+    fn test_mesh_follows_landmarks() {
+        let points: Vec<Point2D> = canonical_landmarks()
+            .into_iter()
+            .map(|p| Point2D { x: 100.0 + 200.0 * p.x, y: 50.0 + 200.0 * p.y })
+            .collect();
+        let landmarks = FacialLandmarks { confidences: vec![1.0; 68], points: points.clone() };
+
+        let mesh = build(&landmarks).unwrap();
+        let topology = topology();
+        assert_eq!(mesh.vertices.len(), topology.vertex_count as usize);
+        assert_eq!(mesh.vertices[..68], points[..]);
+        for (vertex, uv) in mesh.vertices.iter().zip(&topology.uvs) {
+            assert!((vertex.x - (100.0 + 200.0 * uv.x)).abs() < 1e-3);
+            assert!((vertex.y - (50.0 + 200.0 * uv.y)).abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Core face tracking
+//!
+//! The [`tracker::FaceTracker`] wraps openseeface-rs and post-processes its
+//! detections; the remaining modules implement the individual processing stages.
+
+pub mod acceleration;
+pub mod adaptive;
+pub mod alignment;
+pub mod attention;
+pub mod association;
+pub mod backend;
+pub mod benchmark;
+pub mod blink;
+pub mod broadcast;
+pub mod color;
+pub mod comparison;
+pub mod conventions;
+pub mod drowsiness;
+pub mod embedding;
+pub mod error_log;
+pub mod expressions;
+pub mod filters;
+pub mod frame_queue;
+pub mod fusion;
+pub mod gaze;
+pub mod gestures;
+pub mod humanoid;
+pub mod imu;
+pub mod mesh;
+pub mod orientation;
+pub mod pipeline;
+pub mod pose;
+pub mod prediction;
+pub mod preprocessing;
+pub mod quality;
+pub mod recognition;
+pub mod resampler;
+pub mod scaling;
+pub mod scene;
+pub mod scheduler;
+pub mod segmentation;
+pub mod stats;
+pub mod thumbnails;
+pub mod tracker;
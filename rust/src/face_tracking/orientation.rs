@@ -0,0 +1,289 @@
+//! Frame orientation handling
+//!
+//! Camera sensors are often mounted sideways (portrait Android devices report
+//! `rotation` 90 or 270), and the detector only finds upright faces. Frames
+//! are rotated clockwise by their `rotation` (and optionally mirrored) before
+//! detection, and the resulting landmarks, bounding boxes, head poses and
+//! gaze directions are mapped back into the coordinates of the frame as
+//! delivered by the camera.
+
+use crate::error::PluginError;
+use crate::face_tracking::conventions::{decompose, rotation_matrix, EulerOrder};
+use crate::models::*;
+use image::DynamicImage;
+
+/// Rotation and mirroring applied to a frame before detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameOrientation {
+    /// Clockwise rotation in degrees (0, 90, 180 or 270)
+    rotation: u32,
+    /// Flip the upright image horizontally
+    mirror: bool,
+}
+
+impl FrameOrientation {
+    pub fn new(rotation: u32, mirror: bool) -> Result<Self, PluginError> {
+        match rotation {
+            0 | 90 | 180 | 270 => Ok(Self { rotation, mirror }),
+            _ => Err(PluginError::ProcessingError(format!(
+                "Unsupported frame rotation {} (expected 0, 90, 180 or 270)",
+                rotation
+            ))),
+        }
+    }
+
+    /// Whether the frame is used as delivered
+    pub fn is_identity(&self) -> bool {
+        self.rotation == 0 && !self.mirror
+    }
+
+    /// Rotate and mirror a frame into upright orientation
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        let image = match self.rotation {
+            90 => image.rotate90(),
+            180 => image.rotate180(),
+            270 => image.rotate270(),
+            _ => image,
+        };
+
+        if self.mirror {
+            image.fliph()
+        } else {
+            image
+        }
+    }
+
+    /// Rotate and mirror an upright image back into the orientation of the
+    /// original frame
+    pub fn unmap_image(&self, image: DynamicImage) -> DynamicImage {
+        let image = if self.mirror { image.fliph() } else { image };
+        match self.rotation {
+            90 => image.rotate270(),
+            180 => image.rotate180(),
+            270 => image.rotate90(),
+            _ => image,
+        }
+    }
+
+    /// Map a point in the upright image back to the original frame
+    ///
+    /// `width` and `height` are the dimensions of the original frame.
+    pub fn unmap_point(&self, point: Point2D, width: f32, height: f32) -> Point2D {
+        let upright_width = if matches!(self.rotation, 90 | 270) { height } else { width };
+        let x = if self.mirror { upright_width - point.x } else { point.x };
+        let y = point.y;
+
+        match self.rotation {
+            90 => Point2D { x: y, y: height - x },
+            180 => Point2D { x: width - x, y: height - y },
+            270 => Point2D { x: width - y, y: x },
+            _ => Point2D { x, y },
+        }
+    }
+
+    /// Map a direction in the image plane of the original frame into the
+    /// upright image
+    pub fn map_direction(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x, y) = match self.rotation {
+            90 => (-y, x),
+            180 => (-x, -y),
+            270 => (y, -x),
+            _ => (x, y),
+        };
+        if self.mirror { (-x, y) } else { (x, y) }
+    }
+
+    /// Map a direction in the upright image back into the image plane of
+    /// the original frame, the inverse of [`Self::map_direction`]
+    pub fn unmap_direction(&self, x: f32, y: f32) -> (f32, f32) {
+        let x = if self.mirror { -x } else { x };
+        match self.rotation {
+            90 => (y, -x),
+            180 => (-x, -y),
+            270 => (-y, x),
+            _ => (x, y),
+        }
+    }
+
+    /// Map a vector in the camera axes of the upright image back into the
+    /// camera axes of the original frame; depth is unaffected
+    pub fn unmap_vector(&self, vector: Point3D) -> Point3D {
+        let (x, y) = self.unmap_direction(vector.x, vector.y);
+        Point3D { x, y, z: vector.z }
+    }
+
+    /// Map a head pose in the camera axes of the upright image back into the
+    /// camera axes of the original frame
+    pub fn unmap_pose(&self, pose: &mut HeadPose) {
+        if self.is_identity() {
+            return;
+        }
+
+        // The frame's camera axes are the upright ones turned about the
+        // optical axis (and mirrored) by M, so the face model maps to them
+        // through M R. A mirrored frame shows the mirror image of the
+        // symmetric face model, which makes M R a proper rotation again.
+        let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+            .map(|[x, y, z]| self.unmap_vector(Point3D { x, y, z }));
+        let model_x = if self.mirror { -1.0 } else { 1.0 };
+        let r = rotation_matrix(pose.rotation_quaternion);
+        let rotation: [[f32; 3]; 3] = std::array::from_fn(|row| {
+            std::array::from_fn(|col| {
+                let sign = if col == 0 { model_x } else { 1.0 };
+                sign * axes.iter().zip(&r).map(|(axis, r)| [axis.x, axis.y, axis.z][row] * r[col]).sum::<f32>()
+            })
+        });
+
+        let [pitch, yaw, roll] = decompose(&rotation, EulerOrder::ZXY).map(f32::to_degrees);
+        pose.pitch = pitch;
+        pose.yaw = yaw;
+        pose.roll = roll;
+        pose.update_quaternion();
+        pose.translation = self.unmap_vector(pose.translation);
+    }
+
+    /// Map a bounding box in the upright image back to the original frame
+    pub fn unmap_box(&self, bounding_box: &BoundingBox, width: f32, height: f32) -> BoundingBox {
+        let a = self.unmap_point(Point2D { x: bounding_box.x, y: bounding_box.y }, width, height);
+        let b = self.unmap_point(
+            Point2D {
+                x: bounding_box.x + bounding_box.width,
+                y: bounding_box.y + bounding_box.height,
+            },
+            width,
+            height,
+        );
+
+        BoundingBox {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+            width: (a.x - b.x).abs(),
+            height: (a.y - b.y).abs(),
+        }
+    }
+
+    /// Map the landmarks, bounding boxes, poses and gaze directions of faces
+    /// back to the original frame
+    pub fn unmap_faces(&self, faces: &mut [Face], width: u32, height: u32) {
+        if self.is_identity() {
+            return;
+        }
+
+        let (width, height) = (width as f32, height as f32);
+        for face in faces {
+            face.bounding_box = self.unmap_box(&face.bounding_box, width, height);
+            if let Some(landmarks) = &mut face.landmarks {
+                for point in &mut landmarks.points {
+                    *point = self.unmap_point(*point, width, height);
+                }
+            }
+            if let Some(pose) = &mut face.pose {
+                self.unmap_pose(pose);
+            }
+            if let Some(gaze) = &mut face.gaze {
+                for direction in [
+                    &mut gaze.left_eye_direction,
+                    &mut gaze.right_eye_direction,
+                    &mut gaze.combined_direction,
+                ] {
+                    *direction = self.unmap_vector(*direction);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage};
+
+    /// 4x2 frame with a single marked pixel at (3, 0)
+    fn marked_frame() -> DynamicImage {
+        let mut image = RgbImage::new(4, 2);
+        image.put_pixel(3, 0, Rgb([255, 0, 0]));
+        DynamicImage::ImageRgb8(image)
+    }
+
+    /// Location of the marked pixel in `image`
+    fn marked_pixel(image: &DynamicImage) -> Point2D {
+        let (x, y, _) = image.pixels().find(|(_, _, p)| p[0] == 255).unwrap();
+        Point2D { x: x as f32, y: y as f32 }
+    }
+
+    #[test]
+    fn test_unmap_inverts_apply() {
+        for rotation in [0, 90, 180, 270] {
+            for mirror in [false, true] {
+                let orientation = FrameOrientation::new(rotation, mirror).unwrap();
+                let upright = orientation.apply(marked_frame());
+
+                // Map the center of the marked pixel back to the original frame
+                let pixel = marked_pixel(&upright);
+                let center = Point2D { x: pixel.x + 0.5, y: pixel.y + 0.5 };
+                let original = orientation.unmap_point(center, 4.0, 2.0);
+
+                assert_eq!(original, Point2D { x: 3.5, y: 0.5 }, "rotation {} mirror {}", rotation, mirror);
+            }
+        }
+    }
+
+    #[test]
+    fn test_map_direction_inverts_unmap() {
+        for rotation in [0, 90, 180, 270] {
+            for mirror in [false, true] {
+                let orientation = FrameOrientation::new(rotation, mirror).unwrap();
+                let a = orientation.unmap_point(Point2D { x: 1.0, y: 1.0 }, 4.0, 2.0);
+                let b = orientation.unmap_point(Point2D { x: 2.0, y: 3.0 }, 4.0, 2.0);
+                let direction = orientation.map_direction(b.x - a.x, b.y - a.y);
+                assert_eq!(direction, (1.0, 2.0), "rotation {} mirror {}", rotation, mirror);
+                assert_eq!(orientation.unmap_direction(1.0, 2.0), (b.x - a.x, b.y - a.y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unmap_faces_rotates_pose_and_gaze() {
+        // A face in the upright image, tilted and looking right
+        let orientation = FrameOrientation::new(90, false).unwrap();
+        let gaze_direction = Point3D { x: 1.0, y: 0.0, z: 0.0 };
+        let mut faces = [Face {
+            bounding_box: BoundingBox { x: 10.0, y: 20.0, width: 30.0, height: 40.0 },
+            pose: Some(HeadPose::from_euler(0.0, 0.0, 20.0, Point3D { x: 50.0, y: 0.0, z: 600.0 }, 1.0)),
+            gaze: Some(EyeGaze {
+                left_eye_direction: gaze_direction,
+                right_eye_direction: gaze_direction,
+                combined_direction: gaze_direction,
+                confidence: 1.0,
+                screen_gaze: None,
+            }),
+            ..Face::default()
+        }];
+        orientation.unmap_faces(&mut faces, 480, 640);
+
+        // Upright right is up in the frame, which was turned 90° clockwise
+        let pose = faces[0].pose.unwrap();
+        assert!((pose.roll - -70.0).abs() < 1e-3, "{:?}", pose);
+        assert!(pose.pitch.abs() < 1e-3 && pose.yaw.abs() < 1e-3, "{:?}", pose);
+        assert_eq!(pose.rotation_quaternion, Quaternion::from_euler(pose.pitch, pose.yaw, pose.roll));
+        assert_eq!(pose.translation, Point3D { x: 0.0, y: -50.0, z: 600.0 });
+        let gaze = faces[0].gaze.unwrap();
+        assert_eq!(gaze.combined_direction, Point3D { x: 0.0, y: -1.0, z: 0.0 });
+
+        // A turned head stays turned by the same amount
+        let mut pose = HeadPose::from_euler(0.0, 30.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 600.0 }, 1.0);
+        orientation.unmap_pose(&mut pose);
+        let forward = rotation_matrix(pose.rotation_quaternion)[2][2];
+        assert!((forward - 30f32.to_radians().cos()).abs() < 1e-5, "{:?}", pose);
+
+        // Mirroring turns the head the other way
+        let mut pose = HeadPose::from_euler(10.0, 30.0, 20.0, Point3D { x: 0.0, y: 0.0, z: 600.0 }, 1.0);
+        FrameOrientation::new(0, true).unwrap().unmap_pose(&mut pose);
+        assert!((pose.pitch - 10.0).abs() < 1e-3 && (pose.yaw + 30.0).abs() < 1e-3 && (pose.roll + 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_rejects_invalid_rotation() {
+        assert!(FrameOrientation::new(45, false).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Staged stream pipeline
+//!
+//! Streamed frames pass through three workers connected by channels: the
+//! convert stage turns a queued frame into an upright, preprocessed image,
+//! the detect stage runs the inference backend on it, and the landmark stage
+//! derives expressions, blinks and gestures from the detected landmarks and
+//! publishes the faces to the stream's subscribers. Each stage works on a
+//! different frame, so color conversion of frame N+1 overlaps inference of
+//! frame N on multi-core devices. Only one frame waits between two stages, so
+//! the pipeline raises throughput without queueing frames behind a slow
+//! stage.
+
+use crate::error::PluginError;
+use crate::face_tracking::adaptive::AdaptiveController;
+use crate::face_tracking::broadcast::FaceBroadcast;
+use crate::face_tracking::frame_queue::FrameQueue;
+use crate::face_tracking::tracker::{ConvertedFrame, DetectedFrame, QueuedFrame};
+use crate::models::FrameRatePolicy;
+use log::{debug, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+use tracing::{info_span, Instrument};
+
+/// Frames waiting between two stages
+const STAGE_CAPACITY: usize = 1;
+
+/// Start the stage workers for a stream fed from `queue`
+///
+/// Closing `queue` ends all three stages.
+pub(crate) fn spawn(queue: Arc<FrameQueue<QueuedFrame>>, broadcast: Arc<FaceBroadcast>, throttle: FrameThrottle) {
+    let (converted_tx, converted_rx) = mpsc::channel(STAGE_CAPACITY);
+    let (detected_tx, detected_rx) = mpsc::channel(STAGE_CAPACITY);
+
+    let runtime = crate::runtime();
+    runtime.spawn(run_convert_stage(queue, throttle, converted_tx));
+    runtime.spawn(run_detect_stage(converted_rx, detected_tx));
+    runtime.spawn(run_landmark_stage(detected_rx, broadcast));
+}
+
+/// Take frames off the queue at the target frame rate and convert them
+async fn run_convert_stage(
+    queue: Arc<FrameQueue<QueuedFrame>>,
+    mut throttle: FrameThrottle,
+    output: mpsc::Sender<ConvertedFrame>,
+) {
+    debug!("Convert stage started");
+
+    while let Some(frame) = queue.pop().await {
+        let frame = match throttle.policy {
+            FrameRatePolicy::Drop => {
+                if !throttle.admit(Instant::now()) {
+                    continue;
+                }
+                frame
+            }
+            FrameRatePolicy::Coalesce => throttle.coalesce(frame, &queue).await,
+        };
+
+        let result = match crate::GLOBAL_TRACKER.read().await.as_ref() {
+            Some(tracker) => tracker.convert_queued(frame).await,
+            None => Err(PluginError::TrackerNotInitialized),
+        };
+        match result {
+            Ok(frame) => {
+                if output.send(frame).await.is_err() {
+                    break;
+                }
+            }
+            Err(PluginError::TrackerNotInitialized) => break,
+            Err(e) => warn!("Failed to convert streamed frame: {}", e),
+        }
+    }
+
+    debug!("Convert stage stopped");
+}
+
+/// Run the inference backend on converted frames
+async fn run_detect_stage(mut input: mpsc::Receiver<ConvertedFrame>, output: mpsc::Sender<DetectedFrame>) {
+    debug!("Detect stage started");
+
+    while let Some(frame) = input.recv().await {
+        let result = match crate::GLOBAL_TRACKER.read().await.as_ref() {
+            Some(tracker) => tracker.detect_converted(frame).await,
+            None => Err(PluginError::TrackerNotInitialized),
+        };
+        match result {
+            Ok(frame) => {
+                if output.send(frame).await.is_err() {
+                    break;
+                }
+            }
+            Err(PluginError::TrackerNotInitialized) => break,
+            Err(e) => warn!("Failed to detect faces in streamed frame: {}", e),
+        }
+    }
+
+    debug!("Detect stage stopped");
+}
+
+/// Analyze detected faces and publish them to the subscribers
+async fn run_landmark_stage(mut input: mpsc::Receiver<DetectedFrame>, broadcast: Arc<FaceBroadcast>) {
+    debug!("Landmark stage started");
+
+    while let Some(frame) = input.recv().await {
+        let timestamp = frame.timestamp();
+        let result = match crate::GLOBAL_TRACKER.read().await.as_ref() {
+            Some(tracker) => tracker.finish_detected(frame).await,
+            None => Err(PluginError::TrackerNotInitialized),
+        };
+        match result {
+            Ok(faces) => {
+                broadcast
+                    .publish(faces, timestamp)
+                    .instrument(info_span!("publish", frame = timestamp))
+                    .await
+            }
+            Err(PluginError::TrackerNotInitialized) => break,
+            Err(e) => warn!("Failed to process streamed frame: {}", e),
+        }
+    }
+
+    debug!("Landmark stage stopped");
+}
+
+/// Enforces the target frame rate on streamed frames
+pub(crate) struct FrameThrottle {
+    interval: Duration,
+    /// Lowers the frame rate under thermal or battery pressure
+    adaptive: Option<Arc<Mutex<AdaptiveController>>>,
+    policy: FrameRatePolicy,
+    next_due: Option<Instant>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl FrameThrottle {
+    pub(crate) fn new(target_fps: u32, policy: FrameRatePolicy, dropped: Arc<AtomicU64>) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / target_fps.max(1) as f64),
+            adaptive: None,
+            policy,
+            next_due: None,
+            dropped,
+        }
+    }
+
+    pub(crate) fn with_adaptive(mut self, adaptive: Arc<Mutex<AdaptiveController>>) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Time between frames at the current quality step
+    fn interval(&self) -> Duration {
+        match &self.adaptive {
+            Some(adaptive) => self.interval.div_f32(adaptive.lock().unwrap().step(Instant::now()).frame_rate_scale()),
+            None => self.interval,
+        }
+    }
+
+    /// Whether a frame arriving at `now` should be processed under the drop policy
+    ///
+    /// Frames up to 10% early are accepted so camera jitter at exactly the
+    /// target rate does not drop every other frame.
+    fn admit(&mut self, now: Instant) -> bool {
+        if let Some(next_due) = self.next_due {
+            if now + self.interval() / 10 < next_due {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        }
+        self.schedule_next(now);
+        true
+    }
+
+    /// Advance the schedule after a frame was accepted at `now`
+    fn schedule_next(&mut self, now: Instant) {
+        let interval = self.interval();
+        let behind = now.checked_sub(interval).unwrap_or(now);
+        let next_due = self.next_due.map_or(now, |due| due.max(behind)) + interval;
+        self.next_due = Some(next_due);
+    }
+
+    /// Wait for the next slot, then replace `frame` with the newest queued frame
+    async fn coalesce(&mut self, mut frame: QueuedFrame, queue: &FrameQueue<QueuedFrame>) -> QueuedFrame {
+        if let Some(next_due) = self.next_due {
+            tokio::time::sleep_until(next_due).await;
+        }
+        while let Some(newer) = queue.try_pop() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            frame = newer;
+        }
+        self.schedule_next(Instant::now());
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_drops_early_frames() {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let mut throttle = FrameThrottle::new(10, FrameRatePolicy::Drop, dropped.clone());
+        let start = Instant::now();
+        
+        assert!(throttle.admit(start));
+        assert!(!throttle.admit(start + Duration::from_millis(30)));
+        assert!(throttle.admit(start + Duration::from_millis(95)));
+        assert!(throttle.admit(start + Duration::from_millis(200)));
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+    }
+}
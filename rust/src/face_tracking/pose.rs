@@ -0,0 +1,295 @@
+//! Head pose from landmarks and camera intrinsics
+//!
+//! openseeface-rs solves head pose with a focal length guessed from the frame
+//! width, which skews pitch and yaw on wide-angle and zoomed cameras. When the
+//! intrinsics of the camera are known, [`solve_head_pose`] fits a generic 3D
+//! face model to the landmarks instead (a perspective-n-point solve) and the
+//! result replaces the backend's pose.
+//!
+//! The model and the returned translation are in millimetres, in camera axes:
+//! X right, Y down and Z away from the camera. The fit runs in the axes of
+//! the upright frame; the returned pose is in those of the original frame.
+
+use crate::error::PluginError;
+use crate::face_tracking::conventions::{decompose, EulerOrder};
+use crate::face_tracking::orientation::FrameOrientation;
+use crate::models::*;
+use crate::validation::ValidationReport;
+
+/// Landmarks fitted by the solve with their position on an average face
+///
+/// Nose tip, chin, outer eye corners and mouth corners; the face looks
+/// towards the camera (-Z) when unrotated.
+const MODEL_POINTS: [(usize, [f64; 3]); 6] = [
+    (30, [0.0, 0.0, 0.0]),
+    (8, [0.0, 63.6, 12.5]),
+    (36, [-43.3, -32.7, 26.0]),
+    (45, [43.3, -32.7, 26.0]),
+    (48, [-28.9, 28.9, 24.1]),
+    (54, [28.9, 28.9, 24.1]),
+];
+
+/// Distance between the model's outer eye corners (mm)
+const MODEL_EYE_SPAN: f64 = 86.6;
+
+const MAX_ITERATIONS: usize = 30;
+
+impl CameraIntrinsics {
+    /// Intrinsics of an undistorted camera with a horizontal field of view
+    /// of `fov_degrees` producing `width` x `height` frames
+    pub fn from_fov(fov_degrees: f32, width: u32, height: u32) -> Self {
+        let focal = width as f32 / 2.0 / (fov_degrees.to_radians() / 2.0).tan();
+        Self {
+            fx: focal,
+            fy: focal,
+            cx: width as f32 / 2.0,
+            cy: height as f32 / 2.0,
+            distortion: Vec::new(),
+        }
+    }
+
+    /// Validate the camera parameters
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the parameter constraints
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(self.fx > 0.0, "fx", "must be positive", "Use estimate_intrinsics_from_fov");
+        report.check(self.fy > 0.0, "fy", "must be positive", "Use estimate_intrinsics_from_fov");
+        report.check(
+            self.distortion.len() <= 5,
+            "distortion",
+            "must have at most 5 coefficients",
+            "Pass k1, k2, p1, p2 and k3 only",
+        );
+        report
+    }
+
+    /// Remove lens distortion from a pixel, returning its normalized image
+    /// coordinates (x / z, y / z)
+    fn undistort(&self, point: Point2D) -> [f64; 2] {
+        let x0 = (point.x - self.cx) as f64 / self.fx as f64;
+        let y0 = (point.y - self.cy) as f64 / self.fy as f64;
+        let k = |i: usize| self.distortion.get(i).copied().unwrap_or(0.0) as f64;
+        let (k1, k2, p1, p2, k3) = (k(0), k(1), k(2), k(3), k(4));
+        if [k1, k2, p1, p2, k3].iter().all(|&c| c == 0.0) {
+            return [x0, y0];
+        }
+
+        // Fixed-point inversion of the Brown-Conrady model, as in OpenCV
+        let (mut x, mut y) = (x0, y0);
+        for _ in 0..5 {
+            let r2 = x * x + y * y;
+            let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+            let dx = 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+            let dy = p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+            x = (x0 - dx) / radial;
+            y = (y0 - dy) / radial;
+        }
+        [x, y]
+    }
+}
+
+/// Fit the face model to 68-point `landmarks` in the original frame
+///
+/// `orientation` is the rotation and mirroring that makes the frame upright;
+/// the pose is mapped back into the original frame like the backend's pose.
+/// Returns `None` if the landmarks are incomplete or the fit diverges.
+pub fn solve_head_pose(
+    landmarks: &FacialLandmarks,
+    intrinsics: &CameraIntrinsics,
+    orientation: FrameOrientation,
+    confidence: f32,
+) -> Option<HeadPose> {
+    if landmarks.points.len() < 68 {
+        return None;
+    }
+    let observed: Vec<[f64; 2]> = MODEL_POINTS
+        .iter()
+        .map(|&(index, _)| {
+            let [x, y] = intrinsics.undistort(landmarks.points[index]);
+            let (x, y) = orientation.map_direction(x as f32, y as f32);
+            [x as f64, y as f64]
+        })
+        .collect();
+
+    // Start from a frontal face at the distance implied by the eye span
+    let eye_span = ((observed[3][0] - observed[2][0]).powi(2) + (observed[3][1] - observed[2][1]).powi(2)).sqrt();
+    if eye_span < 1e-6 {
+        return None;
+    }
+    let z = MODEL_EYE_SPAN / eye_span;
+    let mut params = [0.0, 0.0, 0.0, observed[0][0] * z, observed[0][1] * z, z];
+
+    let mut lambda = 1e-3;
+    let mut error = reprojection_error(&params, &observed)?;
+    for _ in 0..MAX_ITERATIONS {
+        let (jacobian, residuals) = linearize(&params, &observed)?;
+        let mut normal = [[0.0; 6]; 6];
+        let mut gradient = [0.0; 6];
+        for (row, residual) in jacobian.iter().zip(&residuals) {
+            for a in 0..6 {
+                gradient[a] -= row[a] * residual;
+                for b in 0..6 {
+                    normal[a][b] += row[a] * row[b];
+                }
+            }
+        }
+        for (a, row) in normal.iter_mut().enumerate() {
+            row[a] *= 1.0 + lambda;
+        }
+
+        let Some(step) = solve6(normal, gradient) else { break };
+        let candidate: [f64; 6] = std::array::from_fn(|i| params[i] + step[i]);
+        match reprojection_error(&candidate, &observed) {
+            Some(candidate_error) if candidate_error < error => {
+                let converged = error - candidate_error < 1e-12;
+                params = candidate;
+                error = candidate_error;
+                lambda = (lambda * 0.1).max(1e-9);
+                if converged {
+                    break;
+                }
+            }
+            _ => lambda *= 10.0,
+        }
+    }
+
+    let rotation = rodrigues([params[0], params[1], params[2]]);
+    let matrix = rotation.map(|row| row.map(|v| v as f32));
+    let [pitch, yaw, roll] = decompose(&matrix, EulerOrder::ZXY).map(f32::to_degrees);
+    let translation = Point3D { x: params[3] as f32, y: params[4] as f32, z: params[5] as f32 };
+    let mut pose = HeadPose::from_euler(pitch, yaw, roll, translation, confidence);
+    orientation.unmap_pose(&mut pose);
+    Some(pose)
+}
+
+/// Rotation matrix of a rotation vector
+fn rodrigues(r: [f64; 3]) -> [[f64; 3]; 3] {
+    let angle = (r[0] * r[0] + r[1] * r[1] + r[2] * r[2]).sqrt();
+    if angle < 1e-12 {
+        return [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    }
+    let [x, y, z] = r.map(|v| v / angle);
+    let (s, c) = angle.sin_cos();
+    let t = 1.0 - c;
+    [
+        [t * x * x + c, t * x * y - s * z, t * x * z + s * y],
+        [t * x * y + s * z, t * y * y + c, t * y * z - s * x],
+        [t * x * z - s * y, t * y * z + s * x, t * z * z + c],
+    ]
+}
+
+/// Differences between the projected model points and the observations
+fn residuals(params: &[f64; 6], observed: &[[f64; 2]]) -> Option<Vec<f64>> {
+    let rotation = rodrigues([params[0], params[1], params[2]]);
+    let mut residuals = Vec::with_capacity(observed.len() * 2);
+    for ((_, model), seen) in MODEL_POINTS.iter().zip(observed) {
+        let p: [f64; 3] = std::array::from_fn(|r| {
+            (0..3).map(|c| rotation[r][c] * model[c]).sum::<f64>() + params[3 + r]
+        });
+        // Points behind the camera mean the fit has diverged
+        if p[2] <= 1e-6 {
+            return None;
+        }
+        residuals.push(p[0] / p[2] - seen[0]);
+        residuals.push(p[1] / p[2] - seen[1]);
+    }
+    Some(residuals)
+}
+
+fn reprojection_error(params: &[f64; 6], observed: &[[f64; 2]]) -> Option<f64> {
+    residuals(params, observed).map(|r| r.iter().map(|v| v * v).sum())
+}
+
+/// Numeric Jacobian of the residuals and the residuals at `params`
+fn linearize(params: &[f64; 6], observed: &[[f64; 2]]) -> Option<(Vec<[f64; 6]>, Vec<f64>)> {
+    let base = residuals(params, observed)?;
+    let mut jacobian = vec![[0.0; 6]; base.len()];
+    for i in 0..6 {
+        let h = if i < 3 { 1e-6 } else { 1e-4 };
+        let mut shifted = *params;
+        shifted[i] += h;
+        let moved = residuals(&shifted, observed)?;
+        for (row, (m, b)) in jacobian.iter_mut().zip(moved.iter().zip(&base)) {
+            row[i] = (m - b) / h;
+        }
+    }
+    Some((jacobian, base))
+}
+
+/// Solve a 6x6 linear system by Gaussian elimination with partial pivoting
+fn solve6(mut a: [[f64; 6]; 6], mut b: [f64; 6]) -> Option<[f64; 6]> {
+    for col in 0..6 {
+        let pivot = (col..6).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-15 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col];
+        for row in col + 1..6 {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; 6];
+    for row in (0..6).rev() {
+        let sum: f64 = (row + 1..6).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Landmarks of the model posed at `params` as seen by `intrinsics`
+    fn project(params: [f64; 6], intrinsics: &CameraIntrinsics) -> FacialLandmarks {
+        let rotation = rodrigues([params[0], params[1], params[2]]);
+        let mut points = vec![Point2D { x: 0.0, y: 0.0 }; 68];
+        for (index, model) in MODEL_POINTS {
+            let p: [f64; 3] = std::array::from_fn(|r| {
+                (0..3).map(|c| rotation[r][c] * model[c]).sum::<f64>() + params[3 + r]
+            });
+            points[index] = Point2D {
+                x: (p[0] / p[2]) as f32 * intrinsics.fx + intrinsics.cx,
+                y: (p[1] / p[2]) as f32 * intrinsics.fy + intrinsics.cy,
+            };
+        }
+        FacialLandmarks { confidences: vec![1.0; 68], points }
+    }
+
+    #[test]
+    fn test_intrinsics_from_fov() {
+        let intrinsics = CameraIntrinsics::from_fov(90.0, 640, 480);
+        assert!((intrinsics.fx - 320.0).abs() < 1e-3);
+        assert_eq!((intrinsics.cx, intrinsics.cy), (320.0, 240.0));
+        assert!(intrinsics.validate().is_ok());
+    }
+
+    #[test]
+    fn test_recovers_pose() {
+        let intrinsics = CameraIntrinsics::from_fov(60.0, 1280, 720);
+        let truth = [0.1, -0.3, 0.05, 40.0, -20.0, 600.0];
+        let landmarks = project(truth, &intrinsics);
+        let orientation = FrameOrientation::new(0, false).unwrap();
+
+        let pose = solve_head_pose(&landmarks, &intrinsics, orientation, 1.0).unwrap();
+        let [pitch, yaw, roll] = decompose(
+            &rodrigues([truth[0], truth[1], truth[2]]).map(|row| row.map(|v| v as f32)),
+            EulerOrder::ZXY,
+        )
+        .map(f32::to_degrees);
+        assert!((pose.pitch - pitch).abs() < 0.1, "{:?}", pose);
+        assert!((pose.yaw - yaw).abs() < 0.1, "{:?}", pose);
+        assert!((pose.roll - roll).abs() < 0.1, "{:?}", pose);
+        assert!((pose.translation.z - 600.0).abs() < 1.0, "{:?}", pose);
+    }
+}
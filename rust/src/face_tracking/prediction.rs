@@ -0,0 +1,216 @@
+//! Latency compensation by extrapolation
+//!
+//! By the time a frame has been captured, tracked and rendered, the avatar
+//! shows where the user was tens of milliseconds ago. The [`Predictor`]
+//! estimates how fast each pose component and landmark coordinate moves and
+//! extrapolates the output by a lead time, so motion appears in sync. It
+//! runs on the smoothed output, right before the results are published;
+//! blinks, gestures and expressions are derived from the measured values.
+
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Longest accepted lead time (ms)
+pub const MAX_LEAD_TIME_MS: u32 = 200;
+
+/// Gap between frames after which a face's motion is estimated afresh (s)
+const MAX_GAP: f32 = 0.25;
+
+/// How motion is estimated
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PredictorType {
+    /// Velocity between the last two frames; reacts immediately but
+    /// amplifies jitter
+    ConstantVelocity,
+    /// Alpha-beta filter; velocity follows changes more smoothly
+    AlphaBeta,
+}
+
+/// Output extrapolation settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PredictionConfig {
+    /// Extrapolate pose and landmarks (off by default)
+    pub enabled: bool,
+    /// Motion estimation
+    pub predictor: PredictorType,
+    /// How far ahead the output is extrapolated (ms)
+    pub lead_time_ms: u32,
+    /// Alpha-beta position gain (0.0 - 1.0), higher follows measurements
+    /// more closely
+    pub alpha: f32,
+    /// Alpha-beta velocity gain (0.0 - 1.0), higher adapts the velocity
+    /// faster
+    pub beta: f32,
+    /// Also extrapolate landmarks, not only the head pose
+    pub landmarks: bool,
+}
+
+impl Default for PredictionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            predictor: PredictorType::AlphaBeta,
+            lead_time_ms: 30,
+            alpha: 0.85,
+            beta: 0.2,
+            landmarks: true,
+        }
+    }
+}
+
+impl PredictionConfig {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            self.lead_time_ms <= MAX_LEAD_TIME_MS,
+            "lead_time_ms",
+            &format!("must be at most {}", MAX_LEAD_TIME_MS),
+            "Use 30",
+        );
+        report.check(
+            self.alpha > 0.0 && self.alpha <= 1.0,
+            "alpha",
+            "must be greater than 0.0 and at most 1.0",
+            "Use 0.85",
+        );
+        report.check((0.0..=1.0).contains(&self.beta), "beta", "must be between 0.0 and 1.0", "Use 0.2");
+        report
+    }
+}
+
+/// Position and velocity estimate of one value
+#[derive(Debug, Clone, Copy, Default)]
+struct Track {
+    position: f32,
+    velocity: f32,
+    initialized: bool,
+}
+
+impl Track {
+    /// Take a measurement `dt` seconds after the previous one and return
+    /// the value extrapolated `lead` seconds ahead
+    fn predict(&mut self, value: f32, dt: f32, lead: f32, config: &PredictionConfig) -> f32 {
+        if !self.initialized || dt > MAX_GAP {
+            *self = Track { position: value, velocity: 0.0, initialized: true };
+            return value;
+        }
+        match config.predictor {
+            PredictorType::ConstantVelocity => {
+                self.velocity = (value - self.position) / dt;
+                self.position = value;
+            }
+            PredictorType::AlphaBeta => {
+                let expected = self.position + self.velocity * dt;
+                let residual = value - expected;
+                self.position = expected + config.alpha * residual;
+                self.velocity += config.beta / dt * residual;
+            }
+        }
+        self.position + self.velocity * lead
+    }
+}
+
+struct FaceTracks {
+    pose: [Track; 6],
+    landmarks: Vec<Track>,
+    last_timestamp: i64,
+}
+
+/// Per-face extrapolation of pose and landmarks
+pub struct Predictor {
+    config: PredictionConfig,
+    faces: HashMap<u32, FaceTracks>,
+}
+
+impl Predictor {
+    pub fn new(config: PredictionConfig) -> Self {
+        Self { config, faces: HashMap::new() }
+    }
+
+    /// Extrapolate the pose and landmarks of all faces in place
+    ///
+    /// Motion estimates of faces that are no longer present are discarded.
+    pub fn apply(&mut self, faces: &mut [Face]) {
+        if !self.config.enabled {
+            return;
+        }
+        self.faces.retain(|id, _| faces.iter().any(|face| face.id == *id));
+
+        let config = &self.config;
+        let lead = config.lead_time_ms as f32 / 1000.0;
+        for face in faces.iter_mut() {
+            let tracks = self.faces.entry(face.id).or_insert_with(|| FaceTracks {
+                pose: [Track::default(); 6],
+                landmarks: Vec::new(),
+                last_timestamp: face.timestamp,
+            });
+            let dt = (face.timestamp - tracks.last_timestamp) as f32 / 1000.0;
+            tracks.last_timestamp = face.timestamp;
+            if dt <= 0.0 {
+                // Only the first frame of a face has no interval
+                tracks.pose = [Track::default(); 6];
+                tracks.landmarks.clear();
+            }
+
+            if let Some(pose) = face.pose.as_mut() {
+                let values = [
+                    &mut pose.pitch,
+                    &mut pose.yaw,
+                    &mut pose.roll,
+                    &mut pose.translation.x,
+                    &mut pose.translation.y,
+                    &mut pose.translation.z,
+                ];
+                for (track, value) in tracks.pose.iter_mut().zip(values) {
+                    *value = track.predict(*value, dt, lead, config);
+                }
+                pose.update_quaternion();
+            }
+
+            if let Some(landmarks) = face.landmarks.as_mut().filter(|_| config.landmarks) {
+                tracks.landmarks.resize(landmarks.points.len() * 2, Track::default());
+                for (i, point) in landmarks.points.iter_mut().enumerate() {
+                    point.x = tracks.landmarks[2 * i].predict(point.x, dt, lead, config);
+                    point.y = tracks.landmarks[2 * i + 1].predict(point.y, dt, lead, config);
+                }
+            }
+        }
+    }
+
+    /// Forget all motion estimates
+    pub fn reset(&mut self) {
+        self.faces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extrapolates_steady_motion() {
+        for predictor in [PredictorType::ConstantVelocity, PredictorType::AlphaBeta] {
+            let config = PredictionConfig { enabled: true, predictor, ..PredictionConfig::default() };
+            let mut track = Track::default();
+            // 90 degrees/s sampled at 30 fps
+            let mut predicted = 0.0;
+            for frame in 0..30 {
+                predicted = track.predict(frame as f32 * 3.0, 1.0 / 30.0, 0.03, &config);
+            }
+            assert!((predicted - (87.0 + 2.7)).abs() < 0.1, "{:?} predicted {}", predictor, predicted);
+        }
+
+        // A long gap restarts the estimate instead of extrapolating across it
+        let config = PredictionConfig { enabled: true, ..PredictionConfig::default() };
+        let mut track = Track::default();
+        track.predict(0.0, 0.0, 0.03, &config);
+        assert_eq!(track.predict(10.0, 1.0, 0.03, &config), 10.0);
+    }
+}
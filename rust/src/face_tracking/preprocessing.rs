@@ -0,0 +1,248 @@
+//! Low-light preprocessing
+//!
+//! Detection and landmark models lose faces in dim rooms long before a
+//! person would. An optional step brightens the frame before detection by
+//! remapping its luma, either with a global gamma curve or with CLAHE
+//! (contrast limited adaptive histogram equalization), which also lifts
+//! faces in unevenly lit scenes. Each pixel's color channels are scaled by
+//! the change of its luma, so hue and saturation are kept.
+
+use crate::error::PluginError;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+/// Luma remapping applied before detection
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreprocessingMode {
+    /// Frames are detected as delivered
+    Off,
+    /// Global gamma curve (`PreprocessingConfig::gamma`)
+    Gamma,
+    /// Contrast limited adaptive histogram equalization on a grid of tiles
+    Clahe,
+}
+
+/// Preprocessing settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreprocessingConfig {
+    /// Remapping to apply
+    pub mode: PreprocessingMode,
+    /// Exponent of the gamma curve; below 1.0 brightens dark frames
+    pub gamma: f32,
+    /// Histogram clip limit of CLAHE, as a multiple of the mean bin height;
+    /// higher values increase contrast and noise
+    pub clahe_clip_limit: f32,
+    /// Number of CLAHE tiles along each side of the frame
+    pub clahe_tiles: u32,
+}
+
+impl Default for PreprocessingConfig {
+    fn default() -> Self {
+        Self {
+            mode: PreprocessingMode::Off,
+            gamma: 0.6,
+            clahe_clip_limit: 2.0,
+            clahe_tiles: 8,
+        }
+    }
+}
+
+impl PreprocessingConfig {
+    /// Validate the preprocessing settings
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the preprocessing settings
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check((0.1..=3.0).contains(&self.gamma), "gamma", "must be between 0.1 and 3.0", "Use 0.6 for dim rooms");
+        report.check(
+            (1.0..=40.0).contains(&self.clahe_clip_limit),
+            "clahe_clip_limit",
+            "must be between 1.0 and 40.0",
+            "Use 2.0",
+        );
+        report.check((1..=32).contains(&self.clahe_tiles), "clahe_tiles", "must be between 1 and 32", "Use 8");
+        report
+    }
+
+    /// Whether frames are changed at all
+    pub fn is_enabled(&self) -> bool {
+        self.mode != PreprocessingMode::Off
+    }
+
+    /// Remap the luma of `image` in place
+    pub fn apply(&self, image: &mut RgbImage) {
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return;
+        }
+        let luma: Vec<u8> = image.pixels().map(|p| luma_of(p.0)).collect();
+
+        match self.mode {
+            PreprocessingMode::Off => {}
+            PreprocessingMode::Gamma => {
+                let curve = gamma_curve(self.gamma);
+                remap(image, &luma, |i| curve[luma[i] as usize] as f32);
+            }
+            PreprocessingMode::Clahe => {
+                let clahe = Clahe::new(&luma, width, height, self.clahe_tiles, self.clahe_clip_limit);
+                remap(image, &luma, |i| clahe.map(i as u32 % width, i as u32 / width, luma[i]));
+            }
+        }
+    }
+}
+
+/// BT.601 luma of an RGB pixel
+fn luma_of([r, g, b]: [u8; 3]) -> u8 {
+    ((77 * r as u32 + 150 * g as u32 + 29 * b as u32) >> 8) as u8
+}
+
+fn gamma_curve(gamma: f32) -> [u8; 256] {
+    std::array::from_fn(|v| (255.0 * (v as f32 / 255.0).powf(gamma)).round() as u8)
+}
+
+/// Scale every pixel's channels so its luma becomes `target(index)`
+fn remap(image: &mut RgbImage, luma: &[u8], target: impl Fn(usize) -> f32) {
+    for (i, pixel) in image.pixels_mut().enumerate() {
+        let (old, new) = (luma[i] as f32, target(i));
+        pixel.0 = if old > 0.0 {
+            pixel.0.map(|c| (c as f32 * new / old).round().min(255.0) as u8)
+        } else {
+            [new.round() as u8; 3]
+        };
+    }
+}
+
+/// Per-tile equalization curves of a frame
+struct Clahe {
+    tiles: u32,
+    tile_width: f32,
+    tile_height: f32,
+    /// Curve of each tile, row by row
+    curves: Vec<[u8; 256]>,
+}
+
+impl Clahe {
+    fn new(luma: &[u8], width: u32, height: u32, tiles: u32, clip_limit: f32) -> Self {
+        let tiles = tiles.clamp(1, width.min(height));
+        let (tile_width, tile_height) = (width.div_ceil(tiles), height.div_ceil(tiles));
+
+        let mut curves = Vec::with_capacity((tiles * tiles) as usize);
+        for ty in 0..tiles {
+            for tx in 0..tiles {
+                let mut histogram = [0u32; 256];
+                for y in ty * tile_height..((ty + 1) * tile_height).min(height) {
+                    for x in tx * tile_width..((tx + 1) * tile_width).min(width) {
+                        histogram[luma[(y * width + x) as usize] as usize] += 1;
+                    }
+                }
+                curves.push(equalize(histogram, clip_limit));
+            }
+        }
+
+        Self { tiles, tile_width: tile_width as f32, tile_height: tile_height as f32, curves }
+    }
+
+    /// Equalized luma at `(x, y)`, blended bilinearly between the curves of
+    /// the nearest tile centers
+    fn map(&self, x: u32, y: u32, luma: u8) -> f32 {
+        let last = (self.tiles - 1) as f32;
+        let position = |p: u32, size: f32| {
+            let t = ((p as f32 + 0.5) / size - 0.5).clamp(0.0, last);
+            let t0 = t.floor();
+            (t0 as usize, (t0 + 1.0).min(last) as usize, t - t0)
+        };
+        let (x0, x1, fx) = position(x, self.tile_width);
+        let (y0, y1, fy) = position(y, self.tile_height);
+
+        let value = |tx: usize, ty: usize| self.curves[ty * self.tiles as usize + tx][luma as usize] as f32;
+        let top = value(x0, y0) * (1.0 - fx) + value(x1, y0) * fx;
+        let bottom = value(x0, y1) * (1.0 - fx) + value(x1, y1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+/// Equalization curve of a histogram clipped at `clip_limit` times the mean
+/// bin height, with the clipped counts spread over all bins
+fn equalize(mut histogram: [u32; 256], clip_limit: f32) -> [u8; 256] {
+    let total: u32 = histogram.iter().sum();
+    if total == 0 {
+        return std::array::from_fn(|v| v as u8);
+    }
+    let limit = ((clip_limit * total as f32 / 256.0) as u32).max(1);
+    let mut excess = 0;
+    for count in histogram.iter_mut() {
+        excess += count.saturating_sub(limit);
+        *count = (*count).min(limit);
+    }
+    let (share, remainder) = (excess / 256, excess % 256);
+    for (v, count) in histogram.iter_mut().enumerate() {
+        *count += share + u32::from((v as u32) < remainder);
+    }
+
+    let mut cumulative = 0;
+    histogram.map(|count| {
+        cumulative += count;
+        (255.0 * cumulative as f32 / total as f32).round() as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn mean_luma(image: &RgbImage) -> f32 {
+        image.pixels().map(|p| luma_of(p.0) as f32).sum::<f32>() / (image.width() * image.height()) as f32
+    }
+
+    #[test]
+    fn test_gamma_brightens_and_keeps_hue() {
+        let mut image = RgbImage::from_pixel(16, 16, Rgb([40, 20, 10]));
+        let config = PreprocessingConfig { mode: PreprocessingMode::Gamma, ..PreprocessingConfig::default() };
+        config.apply(&mut image);
+
+        let [r, g, b] = image.get_pixel(0, 0).0;
+        assert!(r > 60, "{:?}", (r, g, b));
+        // Channel ratios survive the remapping
+        assert!((r as f32 / g as f32 - 2.0).abs() < 0.15 && (g as f32 / b as f32 - 2.0).abs() < 0.25);
+
+        let mut black = RgbImage::new(4, 4);
+        config.apply(&mut black);
+        assert_eq!(black.get_pixel(0, 0), &Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_clahe_spreads_dark_frame() {
+        // Dark noise using only the lowest 40 luma levels everywhere
+        let mut image = RgbImage::from_fn(128, 128, |x, y| {
+            let v = ((x * 7 + y * 13) % 40) as u8;
+            Rgb([v, v, v])
+        });
+        let before = mean_luma(&image);
+        let config = PreprocessingConfig { mode: PreprocessingMode::Clahe, ..PreprocessingConfig::default() };
+        config.apply(&mut image);
+
+        assert!(mean_luma(&image) > before * 2.0, "{} -> {}", before, mean_luma(&image));
+        // Darker pixels stay darker than their brighter neighbours
+        assert!(image.get_pixel(0, 0)[0] < image.get_pixel(5, 0)[0]);
+    }
+
+    #[test]
+    fn test_equalization_respects_clip_limit() {
+        let mut histogram = [0u32; 256];
+        histogram[10] = 1000;
+        // Without clipping a single-level tile would jump straight to white
+        let curve = equalize(histogram, 2.0);
+        assert!(curve[10] < 64, "{}", curve[10]);
+        assert!(curve.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(curve[255], 255);
+    }
+}
@@ -0,0 +1,163 @@
+//! Face detection quality
+//!
+//! Blurry frames, heads turned far away and partly hidden faces still
+//! produce detections, but their landmarks are unreliable. Each face is
+//! scored on the frame it was found in, so consumers (and
+//! `TrackerConfig::min_face_quality`) can skip bad detections instead of
+//! feeding them into smoothing, animation and statistics.
+
+use crate::models::*;
+use image::GrayImage;
+
+/// Most Laplacian samples taken along each side of a face
+const BLUR_SAMPLES: u32 = 96;
+
+/// Laplacian variance at which a face counts as half blurred; sharp faces
+/// lie well above it
+const BLUR_VARIANCE_SCALE: f32 = 100.0;
+
+/// Yaw (degrees) up to which landmarks stay reliable, and the range over
+/// which the pose penalty rises to 1.0 beyond it
+const YAW_LIMITS: (f32, f32) = (25.0, 40.0);
+
+/// Pitch (degrees) up to which landmarks stay reliable, and the range over
+/// which the pose penalty rises to 1.0 beyond it
+const PITCH_LIMITS: (f32, f32) = (20.0, 30.0);
+
+/// Landmark confidence below which a landmark counts as hidden
+const HIDDEN_LANDMARK_CONFIDENCE: f32 = 0.3;
+
+/// Assess a face found in the upright `luma` frame
+pub fn assess(luma: &GrayImage, face: &Face) -> FaceQuality {
+    let blur = blur(luma, &face.bounding_box);
+    let pose_penalty = face.pose.map_or(0.0, |pose| {
+        let excess = |angle: f32, (limit, range): (f32, f32)| ((angle.abs() - limit) / range).clamp(0.0, 1.0);
+        excess(pose.yaw, YAW_LIMITS).max(excess(pose.pitch, PITCH_LIMITS))
+    });
+    let occlusion = face.landmarks.as_ref().map_or(0.0, |landmarks| occlusion(luma, landmarks));
+
+    FaceQuality {
+        blur,
+        pose_penalty,
+        occlusion,
+        score: (1.0 - blur) * (1.0 - pose_penalty) * (1.0 - occlusion),
+    }
+}
+
+/// Assess every face found in the upright `luma` frame
+pub fn apply(luma: &GrayImage, faces: &mut [Face]) {
+    for face in faces.iter_mut() {
+        face.quality = assess(luma, face);
+    }
+}
+
+/// Blur from the variance of the Laplacian over the face region
+///
+/// Large faces are sampled on a coarser grid, which also keeps the estimate
+/// comparable across face sizes.
+fn blur(luma: &GrayImage, bbox: &BoundingBox) -> f32 {
+    let (width, height) = luma.dimensions();
+    let x0 = bbox.x.max(0.0) as u32;
+    let y0 = bbox.y.max(0.0) as u32;
+    let x1 = ((bbox.x + bbox.width).max(0.0) as u32).min(width);
+    let y1 = ((bbox.y + bbox.height).max(0.0) as u32).min(height);
+    let step = ((x1.saturating_sub(x0)).max(y1.saturating_sub(y0)) / BLUR_SAMPLES).max(1);
+    if x1 < x0 + 3 * step || y1 < y0 + 3 * step {
+        return 1.0;
+    }
+
+    let at = |x: u32, y: u32| luma.get_pixel(x, y)[0] as f32;
+    let (mut sum, mut sum_squares, mut count) = (0.0, 0.0, 0.0);
+    for y in (y0 + step..y1 - step).step_by(step as usize) {
+        for x in (x0 + step..x1 - step).step_by(step as usize) {
+            let laplacian = at(x - step, y) + at(x + step, y) + at(x, y - step) + at(x, y + step) - 4.0 * at(x, y);
+            sum += laplacian;
+            sum_squares += laplacian * laplacian;
+            count += 1.0;
+        }
+    }
+    let variance = sum_squares / count - (sum / count).powi(2);
+    BLUR_VARIANCE_SCALE / (BLUR_VARIANCE_SCALE + variance.max(0.0))
+}
+
+/// Share of landmarks that are low-confidence or outside the frame
+fn occlusion(luma: &GrayImage, landmarks: &FacialLandmarks) -> f32 {
+    if landmarks.points.is_empty() {
+        return 0.0;
+    }
+    let (width, height) = luma.dimensions();
+    let hidden = landmarks
+        .points
+        .iter()
+        .zip(landmarks.confidences.iter().copied().chain(std::iter::repeat(1.0)))
+        .filter(|(point, confidence)| {
+            *confidence < HIDDEN_LANDMARK_CONFIDENCE
+                || point.x < 0.0
+                || point.y < 0.0
+                || point.x >= width as f32
+                || point.y >= height as f32
+        })
+        .count();
+    hidden as f32 / landmarks.points.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn face(bounding_box: BoundingBox, pose: Option<HeadPose>, landmarks: Option<FacialLandmarks>) -> Face {
+        Face {
+            bounding_box,
+            confidence: 1.0,
+            landmarks,
+            pose,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_blur_separates_sharp_and_flat_regions() {
+        // Checkerboard on the left half, flat gray on the right
+        let luma = GrayImage::from_fn(200, 100, |x, y| {
+            if x >= 100 {
+                Luma([128])
+            } else if (x / 4 + y / 4) % 2 == 0 {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        });
+        let sharp = assess(&luma, &face(BoundingBox { x: 10.0, y: 10.0, width: 80.0, height: 80.0 }, None, None));
+        let flat = assess(&luma, &face(BoundingBox { x: 110.0, y: 10.0, width: 80.0, height: 80.0 }, None, None));
+
+        assert!(sharp.blur < 0.1, "{:?}", sharp);
+        assert!((flat.blur - 1.0).abs() < 1e-6, "{:?}", flat);
+        assert!(sharp.score > flat.score);
+    }
+
+    #[test]
+    fn test_pose_and_occlusion_penalties() {
+        let luma = GrayImage::new(100, 100);
+        let bbox = BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 };
+        let frontal = HeadPose::from_euler(5.0, 10.0, 30.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0);
+        let profile = HeadPose::from_euler(0.0, 65.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0);
+
+        assert_eq!(assess(&luma, &face(bbox, Some(frontal), None)).pose_penalty, 0.0);
+        assert_eq!(assess(&luma, &face(bbox, Some(profile), None)).pose_penalty, 1.0);
+
+        // Half the landmarks hidden: one with low confidence, one outside
+        let landmarks = FacialLandmarks {
+            points: vec![
+                Point2D { x: 10.0, y: 10.0 },
+                Point2D { x: 20.0, y: 20.0 },
+                Point2D { x: 30.0, y: 30.0 },
+                Point2D { x: 140.0, y: 30.0 },
+            ],
+            confidences: vec![0.9, 0.1, 0.9, 0.9],
+        };
+        let quality = assess(&luma, &face(bbox, None, Some(landmarks)));
+        assert_eq!(quality.occlusion, 0.5);
+        assert!(quality.score <= 0.5);
+    }
+}
@@ -0,0 +1,191 @@
+//! Known-person recognition
+//!
+//! People are registered under a name with the embeddings of a few frames
+//! showing their face. Tracked faces whose embedding is close enough to one
+//! of a person's embeddings get that person's name, so multi-user setups can
+//! tell who is in front of the camera. The database is kept in memory and,
+//! once a directory is registered, persisted there as a single JSON file.
+
+use crate::error::PluginError;
+use crate::face_tracking::embedding;
+use crate::models::*;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File name of the persisted database
+pub const DATABASE_FILE: &str = "people.json";
+
+/// Most embeddings kept per person
+pub const MAX_EMBEDDINGS_PER_PERSON: usize = 32;
+
+lazy_static! {
+    static ref PEOPLE: RwLock<PersonDatabase> = RwLock::new(PersonDatabase::default());
+}
+
+/// Registered people and their face embeddings
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonDatabase {
+    #[serde(skip)]
+    dir: Option<PathBuf>,
+    people: BTreeMap<String, Vec<Vec<f32>>>,
+}
+
+impl PersonDatabase {
+    /// Persist the database in `dir` from now on
+    ///
+    /// People stored there are added to the ones in memory; on a name
+    /// conflict the person in memory wins.
+    pub fn open_dir(&mut self, dir: PathBuf) -> Result<(), PluginError> {
+        let path = dir.join(DATABASE_FILE);
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                let stored: PersonDatabase = serde_json::from_slice(&bytes).map_err(|e| {
+                    PluginError::InvalidConfiguration(format!("Cannot read {}: {}", path.display(), e))
+                })?;
+                for (name, embeddings) in stored.people {
+                    self.people.entry(name).or_insert(embeddings);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(PluginError::InvalidConfiguration(format!("Cannot read {}: {}", path.display(), e)));
+            }
+        }
+
+        self.dir = Some(dir);
+        self.persist()
+    }
+
+    /// Add or replace a person
+    pub fn register(&mut self, name: &str, mut embeddings: Vec<Vec<f32>>) -> Result<(), PluginError> {
+        if name.trim().is_empty() {
+            return Err(PluginError::InvalidConfiguration("Person name must not be empty".to_string()));
+        }
+        if embeddings.is_empty() {
+            return Err(PluginError::ProcessingError(format!("No face found to register '{}' with", name)));
+        }
+        embeddings.truncate(MAX_EMBEDDINGS_PER_PERSON);
+        self.people.insert(name.to_string(), embeddings);
+        self.persist()
+    }
+
+    /// Remove a person, returning whether they were registered
+    pub fn unregister(&mut self, name: &str) -> Result<bool, PluginError> {
+        if self.people.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.persist()?;
+        Ok(true)
+    }
+
+    /// Names of all registered people, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.people.keys().cloned().collect()
+    }
+
+    /// Name of the registered person most similar to `embedding`, if their
+    /// similarity reaches `threshold`
+    pub fn identify(&self, embedding: &[f32], threshold: f32) -> Option<String> {
+        self.people
+            .iter()
+            .filter_map(|(name, embeddings)| {
+                embeddings
+                    .iter()
+                    .filter(|known| known.len() == embedding.len())
+                    .map(|known| embedding::similarity(known, embedding))
+                    .max_by(f32::total_cmp)
+                    .map(|score| (name, score))
+            })
+            .filter(|&(_, score)| score >= threshold)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(name, _)| name.clone())
+    }
+
+    fn persist(&self) -> Result<(), PluginError> {
+        match &self.dir {
+            Some(dir) => write_database(dir, self),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Persist the database in `dir` from now on, loading the people stored there
+pub fn set_database_dir(dir: PathBuf) -> Result<(), PluginError> {
+    PEOPLE.write().unwrap().open_dir(dir)
+}
+
+/// Add or replace a person
+pub fn register(name: &str, embeddings: Vec<Vec<f32>>) -> Result<(), PluginError> {
+    PEOPLE.write().unwrap().register(name, embeddings)
+}
+
+/// Remove a person, returning whether they were registered
+pub fn unregister(name: &str) -> Result<bool, PluginError> {
+    PEOPLE.write().unwrap().unregister(name)
+}
+
+/// Names of all registered people, sorted
+pub fn names() -> Vec<String> {
+    PEOPLE.read().unwrap().names()
+}
+
+/// Name the faces with embeddings that match a registered person
+pub fn apply(faces: &mut [Face], threshold: f32) {
+    let people = PEOPLE.read().unwrap();
+    for face in faces.iter_mut() {
+        face.recognized_name = face.embedding.as_ref().and_then(|embedding| people.identify(embedding, threshold));
+    }
+}
+
+fn write_database(dir: &Path, database: &PersonDatabase) -> Result<(), PluginError> {
+    let path = dir.join(DATABASE_FILE);
+    let bytes = serde_json::to_vec(database)
+        .map_err(|e| PluginError::ProcessingError(format!("Failed to serialize people: {}", e)))?;
+    std::fs::write(&path, bytes)
+        .map_err(|e| PluginError::ProcessingError(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifies_closest_person_above_threshold() {
+        let mut database = PersonDatabase::default();
+        database.register("alice", vec![vec![1.0, 0.0], vec![0.8, 0.6]]).unwrap();
+        database.register("bob", vec![vec![0.0, 1.0]]).unwrap();
+
+        assert_eq!(database.identify(&[0.6, 0.8], 0.5), Some("alice".to_string()));
+        assert_eq!(database.identify(&[0.0, 1.0], 0.5), Some("bob".to_string()));
+        assert_eq!(database.identify(&[-1.0, 0.0], 0.5), None);
+        // Embeddings of another model never match
+        assert_eq!(database.identify(&[1.0, 0.0, 0.0], 0.5), None);
+
+        assert!(database.register("", vec![vec![1.0]]).is_err());
+        assert!(database.register("carol", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_database_persists_in_directory() {
+        let dir = std::env::temp_dir().join(format!("osf_people_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut database = PersonDatabase::default();
+        database.register("guest", vec![vec![1.0, 0.0]]).unwrap();
+        database.open_dir(dir.clone()).unwrap();
+        database.register("streamer", vec![vec![0.0, 1.0]]).unwrap();
+        assert!(database.unregister("guest").unwrap());
+        assert!(!database.unregister("guest").unwrap());
+
+        // A fresh database sees what the first one left on disk
+        let mut reopened = PersonDatabase::default();
+        reopened.open_dir(dir.clone()).unwrap();
+        assert_eq!(reopened.names(), vec!["streamer".to_string()]);
+        assert_eq!(reopened.identify(&[0.0, 1.0], 0.9), Some("streamer".to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
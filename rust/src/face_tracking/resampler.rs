@@ -0,0 +1,362 @@
+//! Fixed-rate output resampling
+//!
+//! Avatar renderers animate at a steady rate, usually 60 Hz, while cameras
+//! deliver 24-30 fps with jitter. The resampler keeps the last tracked
+//! frames and emits faces on its own clock, interpolated between the two
+//! tracked frames around each output time: pose rotations are slerped,
+//! everything else is blended linearly. Output runs `delay_ms` behind the
+//! newest tracked frame so there is usually a later frame to interpolate
+//! towards; when there is not, the newest faces are held.
+//!
+//! The resampler receives faces as a network output, so it follows live
+//! tracking and session replays alike. One resampled stream runs at a time.
+
+use crate::error::PluginError;
+use crate::face_tracking::conventions::OutputConventions;
+use crate::models::*;
+use crate::protocols::{self, FaceOutput, FrameInfo};
+use flutter_rust_bridge::frb;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant, MissedTickBehavior};
+
+/// Name of the resampler in the output registry
+pub const OUTPUT_NAME: &str = "resampler";
+
+/// Tracked frames older than this behind the newest are forgotten (ms)
+const HISTORY_MS: i64 = 1000;
+
+lazy_static! {
+    static ref RESAMPLER_WORKER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Rate and latency of the resampled stream
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResamplerConfig {
+    /// Output frames per second (1 - 240)
+    pub output_fps: u32,
+    /// How far the output runs behind the newest tracked frame (ms, at most
+    /// 500); about one camera frame interval lets nearly every output frame
+    /// be interpolated
+    pub delay_ms: u32,
+}
+
+impl Default for ResamplerConfig {
+    fn default() -> Self {
+        Self { output_fps: 60, delay_ms: 40 }
+    }
+}
+
+impl ResamplerConfig {
+    /// Check that the rate and delay are in range
+    pub fn check(&self) -> Result<(), PluginError> {
+        if !(1..=240).contains(&self.output_fps) {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Output rate must be between 1 and 240 fps, got {}",
+                self.output_fps
+            )));
+        }
+        if self.delay_ms > 500 {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Resampling delay must be at most 500 ms, got {}",
+                self.delay_ms
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Recent tracked frames, interpolated at arbitrary times
+#[derive(Debug, Default)]
+pub struct FaceResampler {
+    /// Tracked frames by timestamp, oldest first
+    frames: VecDeque<(i64, Vec<Face>)>,
+}
+
+impl FaceResampler {
+    /// Add the faces tracked in the frame at `timestamp`
+    ///
+    /// Frames older than the newest one are ignored.
+    pub fn push(&mut self, timestamp: i64, faces: Vec<Face>) {
+        if self.frames.back().is_some_and(|&(newest, _)| timestamp <= newest) {
+            return;
+        }
+        self.frames.push_back((timestamp, faces));
+        while self.frames.len() > 2 && self.frames[1].0 < timestamp - HISTORY_MS {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Timestamp of the newest tracked frame
+    pub fn newest_timestamp(&self) -> Option<i64> {
+        self.frames.back().map(|&(timestamp, _)| timestamp)
+    }
+
+    /// Faces at `timestamp`, interpolated between the tracked frames around
+    /// it
+    ///
+    /// The faces are those of the later frame; a face that only appears
+    /// there is taken as is. Before the oldest or after the newest frame,
+    /// that frame's faces are held.
+    pub fn sample(&self, timestamp: i64) -> Vec<Face> {
+        let next = self.frames.partition_point(|&(t, _)| t < timestamp);
+        let mut faces = match (next.checked_sub(1).map(|i| &self.frames[i]), self.frames.get(next)) {
+            (Some((t0, before)), Some((t1, after))) => {
+                let t = (timestamp - t0) as f32 / (t1 - t0) as f32;
+                after
+                    .iter()
+                    .map(|face| match before.iter().find(|previous| previous.id == face.id) {
+                        Some(previous) => interpolate(previous, face, t),
+                        None => face.clone(),
+                    })
+                    .collect()
+            }
+            (_, Some((_, faces))) | (Some((_, faces)), None) => faces.clone(),
+            (None, None) => Vec::new(),
+        };
+        for face in faces.iter_mut() {
+            face.timestamp = timestamp;
+        }
+        faces
+    }
+
+    /// Forget the tracked frames
+    pub fn reset(&mut self) {
+        self.frames.clear();
+    }
+}
+
+/// Blend two samples of the same face, `t` of the way from `a` to `b`
+///
+/// Values without a counterpart and discrete values (blink state, names)
+/// come from the nearer sample.
+fn interpolate(a: &Face, b: &Face, t: f32) -> Face {
+    let mut face = if t < 0.5 { a.clone() } else { b.clone() };
+    face.bounding_box = BoundingBox {
+        x: lerp(a.bounding_box.x, b.bounding_box.x, t),
+        y: lerp(a.bounding_box.y, b.bounding_box.y, t),
+        width: lerp(a.bounding_box.width, b.bounding_box.width, t),
+        height: lerp(a.bounding_box.height, b.bounding_box.height, t),
+    };
+    face.confidence = lerp(a.confidence, b.confidence, t);
+
+    if let (Some(la), Some(lb), Some(landmarks)) = (&a.landmarks, &b.landmarks, face.landmarks.as_mut()) {
+        if la.points.len() == lb.points.len() {
+            landmarks.points = la.points.iter().zip(&lb.points).map(|(p, q)| lerp_2d(*p, *q, t)).collect();
+        }
+    }
+    if let (Some(pa), Some(pb)) = (&a.pose, &b.pose) {
+        face.pose = Some(HeadPose {
+            pitch: lerp(pa.pitch, pb.pitch, t),
+            yaw: lerp(pa.yaw, pb.yaw, t),
+            roll: lerp(pa.roll, pb.roll, t),
+            rotation_quaternion: pa.rotation_quaternion.slerp(pb.rotation_quaternion, t),
+            translation: lerp_3d(pa.translation, pb.translation, t),
+            confidence: lerp(pa.confidence, pb.confidence, t),
+        });
+    }
+    if let (Some(ga), Some(gb)) = (&a.gaze, &b.gaze) {
+        face.gaze = Some(EyeGaze {
+            left_eye_direction: normalize(lerp_3d(ga.left_eye_direction, gb.left_eye_direction, t)),
+            right_eye_direction: normalize(lerp_3d(ga.right_eye_direction, gb.right_eye_direction, t)),
+            combined_direction: normalize(lerp_3d(ga.combined_direction, gb.combined_direction, t)),
+            confidence: lerp(ga.confidence, gb.confidence, t),
+            screen_gaze: match (ga.screen_gaze, gb.screen_gaze) {
+                (Some(p), Some(q)) => Some(lerp_2d(p, q, t)),
+                (p, q) => if t < 0.5 { p } else { q },
+            },
+        });
+    }
+    if let (Some(ea), Some(eb)) = (&a.expressions, &b.expressions) {
+        face.expressions = Some(Expressions {
+            smile: lerp(ea.smile, eb.smile, t),
+            brow_raise_left: lerp(ea.brow_raise_left, eb.brow_raise_left, t),
+            brow_raise_right: lerp(ea.brow_raise_right, eb.brow_raise_right, t),
+            mouth_open: lerp(ea.mouth_open, eb.mouth_open, t),
+        });
+    }
+    if let (Some(ea), Some(eb)) = (&a.eyes, &b.eyes) {
+        face.eyes = Some(EyeState {
+            left_eye_openness: lerp(ea.left_eye_openness, eb.left_eye_openness, t),
+            right_eye_openness: lerp(ea.right_eye_openness, eb.right_eye_openness, t),
+            left_eye_ratio: lerp(ea.left_eye_ratio, eb.left_eye_ratio, t),
+            right_eye_ratio: lerp(ea.right_eye_ratio, eb.right_eye_ratio, t),
+        });
+    }
+    if let (Some(ma), Some(mb)) = (&a.mouth, &b.mouth) {
+        face.mouth = Some(MouthState {
+            jaw_open: lerp(ma.jaw_open, mb.jaw_open, t),
+            mouth_width: lerp(ma.mouth_width, mb.mouth_width, t),
+            pucker: lerp(ma.pucker, mb.pucker, t),
+            funnel: lerp(ma.funnel, mb.funnel, t),
+            width_ratio: lerp(ma.width_ratio, mb.width_ratio, t),
+            open_ratio: lerp(ma.open_ratio, mb.open_ratio, t),
+        });
+    }
+    if let (Some(ma), Some(mb), Some(mesh)) = (&a.mesh, &b.mesh, face.mesh.as_mut()) {
+        if ma.vertices.len() == mb.vertices.len() {
+            mesh.vertices = ma.vertices.iter().zip(&mb.vertices).map(|(p, q)| lerp_2d(*p, *q, t)).collect();
+        }
+    }
+    face
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn lerp_2d(a: Point2D, b: Point2D, t: f32) -> Point2D {
+    Point2D { x: lerp(a.x, b.x, t), y: lerp(a.y, b.y, t) }
+}
+
+fn lerp_3d(a: Point3D, b: Point3D, t: f32) -> Point3D {
+    Point3D { x: lerp(a.x, b.x, t), y: lerp(a.y, b.y, t), z: lerp(a.z, b.z, t) }
+}
+
+fn normalize(p: Point3D) -> Point3D {
+    let length = (p.x * p.x + p.y * p.y + p.z * p.z).sqrt();
+    if length > 0.0 {
+        Point3D { x: p.x / length, y: p.y / length, z: p.z / length }
+    } else {
+        p
+    }
+}
+
+/// Resampler state shared by the output and the emitting task
+#[derive(Default)]
+struct SharedState {
+    resampler: FaceResampler,
+    /// Size of the newest tracked frame
+    frame_size: (u32, u32),
+    /// When the newest tracked frame arrived
+    received: Option<Instant>,
+}
+
+/// Registered output feeding tracked frames into the resampler
+struct ResamplerInput {
+    state: Arc<Mutex<SharedState>>,
+}
+
+impl FaceOutput for ResamplerInput {
+    fn send_faces(&self, faces: &[Face], frame: &FrameInfo) -> Result<(), PluginError> {
+        let mut state = self.state.lock().unwrap();
+        state.resampler.push(frame.timestamp, faces.to_vec());
+        state.frame_size = (frame.width, frame.height);
+        state.received = Some(Instant::now());
+        Ok(())
+    }
+}
+
+/// Emit resampled faces, converted into `conventions`, to `send` at the
+/// configured rate
+///
+/// Stops once `send` reports the app's stream closed. Replaces any
+/// resampled stream still running.
+pub fn start<S>(config: ResamplerConfig, conventions: OutputConventions, send: S) -> Result<(), PluginError>
+where
+    S: Fn(Vec<Face>) -> bool + Send + 'static,
+{
+    config.check()?;
+    stop();
+
+    let state = Arc::new(Mutex::new(SharedState::default()));
+    protocols::register_output(OUTPUT_NAME, Box::new(ResamplerInput { state: state.clone() }));
+
+    let worker = crate::runtime().spawn(async move {
+        let mut ticks = tokio::time::interval(Duration::from_secs_f64(1.0 / config.output_fps as f64));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // Output time never goes backwards, even if a frame arrives early
+        let mut last_output = i64::MIN;
+        loop {
+            ticks.tick().await;
+            let (faces, (width, height)) = {
+                let state = state.lock().unwrap();
+                let (Some(newest), Some(received)) = (state.resampler.newest_timestamp(), state.received) else {
+                    continue;
+                };
+                // The frame clock advances with the wall clock since the newest frame
+                let now = newest + received.elapsed().as_millis() as i64 - config.delay_ms as i64;
+                last_output = last_output.max(now);
+                (state.resampler.sample(last_output), state.frame_size)
+            };
+            let mut faces = faces;
+            conventions.apply(&mut faces, width, height);
+            if !send(faces) {
+                log::debug!("Resampled stream closed, stopping resampler");
+                protocols::remove_output(OUTPUT_NAME);
+                return;
+            }
+        }
+    });
+    *RESAMPLER_WORKER.lock().unwrap() = Some(worker);
+    Ok(())
+}
+
+/// Stop the resampled stream, returning whether one was running
+pub fn stop() -> bool {
+    protocols::remove_output(OUTPUT_NAME);
+    match RESAMPLER_WORKER.lock().unwrap().take() {
+        Some(worker) => {
+            let running = !worker.is_finished();
+            worker.abort();
+            running
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(id: u32, x: f32, yaw: f32, smile: f32) -> Face {
+        Face {
+            id,
+            bounding_box: BoundingBox { x, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            landmarks: Some(FacialLandmarks { points: vec![Point2D { x, y: x }], confidences: vec![1.0] }),
+            pose: Some(HeadPose::from_euler(0.0, yaw, 0.0, Point3D { x: 0.0, y: 0.0, z: 50.0 }, 1.0)),
+            expressions: Some(Expressions { smile, brow_raise_left: 0.0, brow_raise_right: 0.0, mouth_open: 0.0 }),
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_interpolates_between_frames() {
+        let mut resampler = FaceResampler::default();
+        resampler.push(1000, vec![face(1, 0.0, 0.0, 0.0)]);
+        resampler.push(1040, vec![face(1, 40.0, 40.0, 1.0), face(2, 5.0, 0.0, 0.0)]);
+
+        let faces = resampler.sample(1010);
+        assert_eq!(faces.len(), 2);
+        let first = &faces[0];
+        assert_eq!(first.timestamp, 1010);
+        assert!((first.bounding_box.x - 10.0).abs() < 1e-4);
+        assert!((first.landmarks.as_ref().unwrap().points[0].x - 10.0).abs() < 1e-4);
+        assert!((first.expressions.unwrap().smile - 0.25).abs() < 1e-4);
+        let pose = first.pose.unwrap();
+        assert!((pose.yaw - 10.0).abs() < 1e-4);
+        let expected = Quaternion::from_euler(0.0, 10.0, 0.0);
+        assert!((pose.rotation_quaternion.y - expected.y).abs() < 1e-4);
+        // A face without an earlier sample is taken as is
+        assert_eq!(faces[1].bounding_box.x, 5.0);
+    }
+
+    #[test]
+    fn test_holds_outside_tracked_frames() {
+        let mut resampler = FaceResampler::default();
+        assert!(resampler.sample(0).is_empty());
+        resampler.push(1000, vec![face(1, 0.0, 0.0, 0.0)]);
+        resampler.push(1040, vec![face(1, 40.0, 0.0, 0.0)]);
+        // Late frames are ignored
+        resampler.push(1020, vec![face(1, 99.0, 0.0, 0.0)]);
+
+        assert_eq!(resampler.sample(900)[0].bounding_box.x, 0.0);
+        assert_eq!(resampler.sample(1040)[0].bounding_box.x, 40.0);
+        assert_eq!(resampler.sample(1100)[0].bounding_box.x, 40.0);
+    }
+}
@@ -0,0 +1,87 @@
+//! Detection resolution scaling
+//!
+//! The detector does not need 1080p input to find faces, so frames larger
+//! than the configured detection resolution are downscaled with a fast box
+//! filter first. Faces found in the smaller image are scaled back up to the
+//! coordinates of the full-resolution frame; the tracker then refines their
+//! landmarks on full-resolution crops around each face.
+
+use crate::models::*;
+use image::DynamicImage;
+
+/// Factors mapping detection image coordinates to full-resolution coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectionScale {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl DetectionScale {
+    pub const IDENTITY: Self = Self { x: 1.0, y: 1.0 };
+
+    /// Downscale `image` to fit within `max_width` x `max_height`
+    ///
+    /// The aspect ratio is kept and images are never upscaled; a zero limit
+    /// disables scaling along that axis. Returns `None` if `image` already
+    /// fits.
+    pub fn fit(image: &DynamicImage, max_width: u32, max_height: u32) -> Option<(DynamicImage, Self)> {
+        let (width, height) = (image.width(), image.height());
+        let max_width = if max_width == 0 { width } else { max_width };
+        let max_height = if max_height == 0 { height } else { max_height };
+
+        if width <= max_width && height <= max_height {
+            return None;
+        }
+
+        let scaled = image.thumbnail(max_width, max_height);
+        let scale = Self {
+            x: width as f32 / scaled.width() as f32,
+            y: height as f32 / scaled.height() as f32,
+        };
+        Some((scaled, scale))
+    }
+
+    /// Scale bounding boxes and landmarks back to full-resolution coordinates
+    pub fn unmap_faces(&self, faces: &mut [Face]) {
+        if *self == Self::IDENTITY {
+            return;
+        }
+
+        for face in faces {
+            let bounding_box = &mut face.bounding_box;
+            bounding_box.x *= self.x;
+            bounding_box.y *= self.y;
+            bounding_box.width *= self.x;
+            bounding_box.height *= self.y;
+
+            if let Some(landmarks) = &mut face.landmarks {
+                for point in &mut landmarks.points {
+                    point.x *= self.x;
+                    point.y *= self.y;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn test_fit_keeps_aspect_ratio() {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(1920, 1080));
+        let (scaled, scale) = DetectionScale::fit(&image, 640, 480).unwrap();
+
+        assert_eq!((scaled.width(), scaled.height()), (640, 360));
+        assert_eq!(scale, DetectionScale { x: 3.0, y: 3.0 });
+    }
+
+    #[test]
+    fn test_small_frames_are_not_scaled() {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(320, 240));
+        assert!(DetectionScale::fit(&image, 640, 480).is_none());
+        assert!(DetectionScale::fit(&image, 0, 0).is_none());
+    }
+}
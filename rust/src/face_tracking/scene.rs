@@ -0,0 +1,149 @@
+//! Scene lighting analysis
+//!
+//! Most tracking problems in the field are lighting problems: a dim room, a
+//! window behind the user or a camera that exposes for the background. Each
+//! processed frame is sampled on a coarse grid and summarized into
+//! [`SceneConditions`], together with an exposure offset the app can pass
+//! on to the camera (e.g. `CameraController.setExposureOffset`).
+
+use crate::models::*;
+use image::GrayImage;
+
+/// Most luma samples taken along each side of the frame
+const GRID_SAMPLES: u32 = 128;
+
+/// Brightness (0.0 - 1.0) the subject is exposed towards
+const TARGET_BRIGHTNESS: f32 = 0.45;
+
+/// Gamma of the encoded luma, to convert brightness ratios into stops
+const ENCODING_GAMMA: f32 = 2.2;
+
+/// Exposure offsets smaller than this (stops) are not worth a change
+const EXPOSURE_DEADBAND: f32 = 0.25;
+
+/// Largest suggested exposure offset (stops)
+const MAX_EXPOSURE_OFFSET: f32 = 2.0;
+
+/// Region mean and sample count accumulated over the grid
+#[derive(Default)]
+struct Accumulator {
+    sum: f32,
+    sum_squares: f32,
+    count: f32,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: f32) {
+        self.sum += value;
+        self.sum_squares += value * value;
+        self.count += 1.0;
+    }
+
+    fn mean(&self) -> Option<f32> {
+        (self.count > 0.0).then(|| self.sum / self.count)
+    }
+}
+
+/// Analyze the lighting of the upright `luma` frame with `faces` found in it
+pub fn analyze(luma: &GrayImage, faces: &[Face]) -> SceneConditions {
+    let (width, height) = luma.dimensions();
+    let step = (width.max(height) / GRID_SAMPLES).max(1);
+
+    // Without faces, the center third of the frame stands in for the subject
+    let center = BoundingBox {
+        x: width as f32 / 3.0,
+        y: height as f32 / 3.0,
+        width: width as f32 / 3.0,
+        height: height as f32 / 3.0,
+    };
+    let subjects: Vec<BoundingBox> = match faces.is_empty() {
+        true => vec![center],
+        false => faces.iter().map(|face| face.bounding_box).collect(),
+    };
+    let inside = |x: f32, y: f32| {
+        subjects
+            .iter()
+            .any(|b| x >= b.x && x < b.x + b.width && y >= b.y && y < b.y + b.height)
+    };
+
+    let (mut frame, mut subject, mut background) = (Accumulator::default(), Accumulator::default(), Accumulator::default());
+    for y in (step / 2..height).step_by(step as usize) {
+        for x in (step / 2..width).step_by(step as usize) {
+            let value = luma.get_pixel(x, y)[0] as f32 / 255.0;
+            frame.add(value);
+            if inside(x as f32, y as f32) {
+                subject.add(value);
+            } else {
+                background.add(value);
+            }
+        }
+    }
+
+    let brightness = frame.mean().unwrap_or(0.0);
+    let variance = frame.mean().map_or(0.0, |mean| frame.sum_squares / frame.count - mean * mean);
+    // A half-black, half-white frame has the largest deviation, 0.5
+    let contrast = (variance.max(0.0).sqrt() * 2.0).min(1.0);
+    let subject_brightness = subject.mean().unwrap_or(brightness);
+    let backlighting = match background.mean() {
+        Some(background) if background > subject_brightness => (background - subject_brightness) / background,
+        _ => 0.0,
+    };
+
+    SceneConditions {
+        brightness,
+        contrast,
+        subject_brightness,
+        backlighting,
+        suggested_exposure_offset: exposure_offset(subject_brightness),
+    }
+}
+
+/// Stops that bring `brightness` to the target brightness
+fn exposure_offset(brightness: f32) -> f32 {
+    let offset = ENCODING_GAMMA * (TARGET_BRIGHTNESS / brightness.max(1.0 / 255.0)).log2();
+    if offset.abs() < EXPOSURE_DEADBAND {
+        return 0.0;
+    }
+    offset.clamp(-MAX_EXPOSURE_OFFSET, MAX_EXPOSURE_OFFSET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    #[test]
+    fn test_well_exposed_frame() {
+        let luma = GrayImage::from_pixel(320, 240, Luma([115]));
+        let scene = analyze(&luma, &[]);
+        assert!((scene.brightness - 115.0 / 255.0).abs() < 1e-4);
+        assert!(scene.contrast < 1e-3);
+        assert!(scene.backlighting < 1e-3);
+        assert_eq!(scene.suggested_exposure_offset, 0.0);
+
+        let dark = analyze(&GrayImage::from_pixel(320, 240, Luma([20])), &[]);
+        assert_eq!(dark.suggested_exposure_offset, MAX_EXPOSURE_OFFSET);
+        let bright = analyze(&GrayImage::from_pixel(320, 240, Luma([200])), &[]);
+        assert!(bright.suggested_exposure_offset < -1.0);
+    }
+
+    #[test]
+    fn test_dark_face_in_front_of_window_is_backlit() {
+        // White frame with a dark face in the middle
+        let luma = GrayImage::from_fn(320, 240, |x, y| {
+            if (120..200).contains(&x) && (60..180).contains(&y) { Luma([30]) } else { Luma([250]) }
+        });
+        let face = Face {
+            bounding_box: BoundingBox { x: 120.0, y: 60.0, width: 80.0, height: 120.0 },
+            confidence: 1.0,
+            ..Face::default()
+        };
+        let scene = analyze(&luma, &[face]);
+
+        assert!((scene.subject_brightness - 30.0 / 255.0).abs() < 1e-4);
+        assert!(scene.backlighting > 0.8, "{:?}", scene);
+        assert!(scene.contrast > 0.5, "{:?}", scene);
+        // Exposing for the face, not the bright background
+        assert!(scene.suggested_exposure_offset > 0.0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Detection scheduling
+//!
+//! Running the detector on the whole frame every frame is wasteful while the
+//! tracked faces barely move. With a detection interval of N, the full frame
+//! is only searched every N-th frame; in between, the tracker runs on crops
+//! around each face's last position. A full detection is forced as soon as a
+//! face is lost or its confidence drops.
+
+use crate::models::*;
+
+/// Margin added around a face's last bounding box, relative to its size
+const REGION_MARGIN: f32 = 0.5;
+
+/// Crops smaller than this (in pixels) are not worth tracking on their own
+const MIN_REGION_SIZE: f32 = 16.0;
+
+/// Decides between full-frame detection and tracking in face regions
+pub struct DetectionScheduler {
+    /// Full detection runs every `interval` frames (1 = every frame)
+    interval: u32,
+    /// Faces below this confidence trigger a full detection on the next frame
+    redetect_confidence: f32,
+    frames_since_detection: u32,
+    /// Bounding boxes of the faces found in the previous frame
+    regions: Vec<BoundingBox>,
+    force_detection: bool,
+}
+
+impl DetectionScheduler {
+    pub fn new(interval: u32, redetect_confidence: f32) -> Self {
+        Self {
+            interval,
+            redetect_confidence,
+            frames_since_detection: 0,
+            regions: Vec::new(),
+            force_detection: true,
+        }
+    }
+
+    /// Plan the next frame of `width` x `height` pixels
+    ///
+    /// Returns `None` for a full-frame detection, or the regions to track in.
+    pub fn plan(&mut self, width: u32, height: u32) -> Option<Vec<BoundingBox>> {
+        let due = self.frames_since_detection + 1 >= self.interval;
+        if self.interval <= 1 || self.force_detection || self.regions.is_empty() || due {
+            self.frames_since_detection = 0;
+            return None;
+        }
+
+        self.frames_since_detection += 1;
+        Some(
+            self.regions
+                .iter()
+                .map(|region| expand_region(region, width as f32, height as f32))
+                .collect(),
+        )
+    }
+
+    /// Record the faces found in the planned frame
+    ///
+    /// `searched_regions` is the number of regions tracked in, or `None` after
+    /// a full detection.
+    pub fn update(&mut self, faces: &[Face], searched_regions: Option<usize>) {
+        let lost_face = searched_regions.is_some_and(|count| faces.len() < count);
+        let low_confidence = faces.iter().any(|face| face.confidence < self.redetect_confidence);
+
+        self.force_detection = lost_face || low_confidence;
+        self.regions = faces.iter().map(|face| face.bounding_box).collect();
+    }
+
+    /// Forget tracked regions so the next frame runs a full detection
+    pub fn reset(&mut self) {
+        self.regions.clear();
+        self.force_detection = true;
+        self.frames_since_detection = 0;
+    }
+}
+
+/// Grow a face box by the region margin and clamp it to the frame
+pub(crate) fn expand_region(region: &BoundingBox, width: f32, height: f32) -> BoundingBox {
+    let margin_x = region.width * REGION_MARGIN;
+    let margin_y = region.height * REGION_MARGIN;
+
+    let x = (region.x - margin_x).clamp(0.0, width);
+    let y = (region.y - margin_y).clamp(0.0, height);
+    let right = (region.x + region.width + margin_x).clamp(0.0, width);
+    let bottom = (region.y + region.height + margin_y).clamp(0.0, height);
+
+    BoundingBox {
+        x: x.floor(),
+        y: y.floor(),
+        width: (right - x).max(MIN_REGION_SIZE.min(width - x)).floor(),
+        height: (bottom - y).max(MIN_REGION_SIZE.min(height - y)).floor(),
+    }
+}
+
+/// Move a face found in a region crop into frame coordinates
+pub fn offset_face(face: &mut Face, dx: f32, dy: f32) {
+    face.bounding_box.x += dx;
+    face.bounding_box.y += dy;
+
+    if let Some(landmarks) = &mut face.landmarks {
+        for point in &mut landmarks.points {
+            point.x += dx;
+            point.y += dy;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(confidence: f32) -> Face {
+        Face {
+            bounding_box: BoundingBox { x: 100.0, y: 100.0, width: 100.0, height: 100.0 },
+            confidence,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_detects_every_n_frames() {
+        let mut scheduler = DetectionScheduler::new(3, 0.5);
+
+        assert!(scheduler.plan(640, 480).is_none());
+        scheduler.update(&[face(0.9)], None);
+
+        let regions = scheduler.plan(640, 480).unwrap();
+        assert_eq!(regions[0], BoundingBox { x: 50.0, y: 50.0, width: 200.0, height: 200.0 });
+        scheduler.update(&[face(0.9)], Some(1));
+
+        assert!(scheduler.plan(640, 480).is_some());
+        scheduler.update(&[face(0.9)], Some(1));
+
+        assert!(scheduler.plan(640, 480).is_none());
+    }
+
+    #[test]
+    fn test_redetects_on_low_confidence_or_lost_face() {
+        let mut scheduler = DetectionScheduler::new(10, 0.5);
+
+        scheduler.plan(640, 480);
+        scheduler.update(&[face(0.9)], None);
+        assert!(scheduler.plan(640, 480).is_some());
+
+        scheduler.update(&[face(0.3)], Some(1));
+        assert!(scheduler.plan(640, 480).is_none());
+        scheduler.update(&[face(0.9)], None);
+
+        assert!(scheduler.plan(640, 480).is_some());
+        scheduler.update(&[], Some(1));
+        assert!(scheduler.plan(640, 480).is_none());
+    }
+}
@@ -0,0 +1,219 @@
+//! Person segmentation
+//!
+//! A lightweight selfie segmentation ONNX model (e.g. MediaPipe Selfie
+//! Segmentation or a small MODNet) estimates for every pixel how likely it
+//! belongs to a person. The resulting alpha matte lets the app replace the
+//! background or composite the avatar over the user without running a
+//! second model in Dart. Like face embeddings, the model runs on tract and
+//! is only loaded once the first frame needs it.
+
+use crate::error::PluginError;
+use crate::face_tracking::orientation::FrameOrientation;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use image::{imageops, DynamicImage, GrayImage, RgbImage};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "tract")]
+use crate::face_tracking::backend::tract::load_tract_model;
+#[cfg(feature = "tract")]
+use tract_onnx::prelude::*;
+
+/// Largest accepted model input side (pixels)
+pub const MAX_SEGMENTATION_SIZE: u32 = 1024;
+
+/// Segmentation model settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentationConfig {
+    /// Path to the ONNX model, or the name of a model loaded with
+    /// `load_models_from_bytes`
+    ///
+    /// The model takes one `[1, 3, input_height, input_width]` RGB tensor
+    /// scaled to 0.0 - 1.0 and returns the person probability (0.0 - 1.0)
+    /// of every pixel as its first output, with the matte height and width
+    /// as its last two dimensions.
+    pub model_path: String,
+    /// Width of the model input (pixels)
+    pub input_width: u32,
+    /// Height of the model input (pixels)
+    pub input_height: u32,
+    /// Scale the matte up to the frame resolution; otherwise it keeps the
+    /// model's output resolution, which is cheaper to pass to Dart
+    pub full_resolution: bool,
+}
+
+impl Default for SegmentationConfig {
+    fn default() -> Self {
+        Self {
+            model_path: String::new(),
+            input_width: 256,
+            input_height: 256,
+            full_resolution: false,
+        }
+    }
+}
+
+impl SegmentationConfig {
+    /// Validate the model settings
+    pub fn validate(&self) -> Result<(), PluginError> {
+        self.report().into_result()
+    }
+
+    /// Every violation of the model settings
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            !self.model_path.is_empty(),
+            "model_path",
+            "must not be empty",
+            "Name a selfie segmentation ONNX model",
+        );
+        for (field, size) in [("input_width", self.input_width), ("input_height", self.input_height)] {
+            report.check(
+                (32..=MAX_SEGMENTATION_SIZE).contains(&size),
+                field,
+                format!("must be between 32 and {}", MAX_SEGMENTATION_SIZE).as_str(),
+                "Use 256 for MediaPipe Selfie Segmentation",
+            );
+        }
+        report
+    }
+}
+
+/// Person alpha matte of a frame
+///
+/// Oriented like the frame as delivered by the camera, so it lines up with
+/// the frame pixels whatever the frame rotation.
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentationMatte {
+    /// Timestamp of the segmented frame
+    pub timestamp: i64,
+    pub width: u32,
+    pub height: u32,
+    /// Row-major person coverage, 0 (background) to 255 (person)
+    pub alpha: Vec<u8>,
+}
+
+impl SegmentationMatte {
+    /// Build a matte from an upright model output, mapped back into the
+    /// orientation of the original frame and scaled to `frame_size` if given
+    pub(crate) fn from_upright(
+        matte: GrayImage,
+        orientation: &FrameOrientation,
+        timestamp: i64,
+        frame_size: Option<(u32, u32)>,
+    ) -> Self {
+        let matte = orientation.unmap_image(DynamicImage::ImageLuma8(matte)).into_luma8();
+        let matte = match frame_size {
+            Some((width, height)) if matte.dimensions() != (width, height) => {
+                imageops::resize(&matte, width, height, imageops::FilterType::Triangle)
+            }
+            _ => matte,
+        };
+        Self { timestamp, width: matte.width(), height: matte.height(), alpha: matte.into_raw() }
+    }
+
+    /// Coverage at frame pixel (`x`, `y`) of a `width` x `height` frame,
+    /// sampled from the matte whatever its resolution
+    pub fn sample(&self, x: u32, y: u32, width: u32, height: u32) -> u8 {
+        let mx = ((x as u64 * self.width as u64) / width.max(1) as u64).min(self.width as u64 - 1);
+        let my = ((y as u64 * self.height as u64) / height.max(1) as u64).min(self.height as u64 - 1);
+        self.alpha[(my * self.width as u64 + mx) as usize]
+    }
+}
+
+/// A loaded segmentation model
+#[derive(Clone)]
+#[cfg_attr(not(feature = "tract"), allow(dead_code))]
+pub struct Segmenter {
+    #[cfg(feature = "tract")]
+    model: TypedRunnableModel<TypedModel>,
+    input_width: u32,
+    input_height: u32,
+}
+
+impl Segmenter {
+    /// Load and optimize the configured model
+    #[cfg(feature = "tract")]
+    pub fn load(config: &SegmentationConfig) -> Result<Self, PluginError> {
+        config.validate()?;
+        let (width, height) = (config.input_width as usize, config.input_height as usize);
+        let model = load_tract_model(&config.model_path, [1, 3, height, width])?;
+
+        Ok(Self { model, input_width: config.input_width, input_height: config.input_height })
+    }
+
+    /// Load and optimize the configured model
+    #[cfg(not(feature = "tract"))]
+    pub fn load(_config: &SegmentationConfig) -> Result<Self, PluginError> {
+        Err(PluginError::InvalidConfiguration(
+            "Person segmentation requires building with the `tract` feature".to_string(),
+        ))
+    }
+
+    /// Person matte of an upright `image`, at the model's output resolution
+    pub fn segment(&self, image: &RgbImage) -> Result<GrayImage, PluginError> {
+        let input = imageops::resize(image, self.input_width, self.input_height, imageops::FilterType::Triangle);
+        self.run(&input)
+    }
+
+    #[cfg(feature = "tract")]
+    fn run(&self, input: &RgbImage) -> Result<GrayImage, PluginError> {
+        let (width, height) = (self.input_width as usize, self.input_height as usize);
+        let tensor: Tensor = tract_ndarray::Array4::from_shape_fn((1, 3, height, width), |(_, c, y, x)| {
+            input.get_pixel(x as u32, y as u32)[c] as f32 / 255.0
+        })
+        .into();
+
+        let outputs = self
+            .model
+            .run(tvec!(tensor.into()))
+            .map_err(|e| PluginError::ProcessingError(format!("Segmentation failed: {}", e)))?;
+        let output = outputs[0]
+            .to_array_view::<f32>()
+            .map_err(|_| PluginError::ProcessingError("Unexpected segmentation model output".to_string()))?;
+        let shape = output.shape();
+        let (rows, columns) = match shape.len() {
+            0 | 1 => (0, 0),
+            n => (shape[n - 2], shape[n - 1]),
+        };
+        if rows == 0 || columns == 0 || output.len() != rows * columns {
+            return Err(PluginError::ProcessingError(format!(
+                "Segmentation model output {:?} is not a single matte",
+                shape
+            )));
+        }
+        let alpha = output.iter().map(|p| (p.clamp(0.0, 1.0) * 255.0).round() as u8).collect();
+        GrayImage::from_raw(columns as u32, rows as u32, alpha)
+            .ok_or_else(|| PluginError::ProcessingError("Unexpected segmentation model output".to_string()))
+    }
+
+    #[cfg(not(feature = "tract"))]
+    fn run(&self, _input: &RgbImage) -> Result<GrayImage, PluginError> {
+        unreachable!("segmenters cannot be loaded without the tract feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    #[test]
+    fn test_matte_follows_frame_orientation() {
+        // Upright 2x4 matte of a frame delivered rotated by 90 degrees,
+        // with the person in the top row
+        let upright = GrayImage::from_fn(2, 4, |_, y| Luma([if y == 0 { 255 } else { 0 }]));
+        let orientation = FrameOrientation::new(90, false).unwrap();
+        let matte = SegmentationMatte::from_upright(upright, &orientation, 7, Some((8, 4)));
+
+        assert_eq!((matte.width, matte.height), (8, 4));
+        // The top of the upright image is the frame's left edge
+        assert_eq!(matte.sample(0, 2, 8, 4), 255);
+        assert_eq!(matte.sample(7, 2, 8, 4), 0);
+        // Sampling at another resolution maps onto the same place
+        assert_eq!(matte.sample(1, 6, 16, 8), 255);
+    }
+}
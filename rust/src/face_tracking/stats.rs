@@ -0,0 +1,152 @@
+//! Rolling tracking statistics
+//!
+//! Cumulative counters such as the total number of detected faces say little
+//! about how tracking is doing right now. [`StatsWindow`] keeps one sample per
+//! processed frame for the last few seconds and summarizes them into
+//! [`WindowedStats`] for live monitoring.
+
+use crate::models::*;
+use std::collections::VecDeque;
+use tokio::time::{Duration, Instant};
+
+/// Default length of the statistics window (ms)
+pub const DEFAULT_STATS_WINDOW_MS: u32 = 5000;
+
+/// Measurements of one processed frame
+#[derive(Debug, Clone, Copy)]
+struct FrameSample {
+    at: Instant,
+    faces: u32,
+    confidence_sum: f32,
+    times: ProcessingTimes,
+}
+
+/// Per-frame samples of the last `window` of tracking
+#[derive(Debug)]
+pub struct StatsWindow {
+    window: Duration,
+    samples: VecDeque<FrameSample>,
+}
+
+impl StatsWindow {
+    /// Create an empty window covering the last `window_ms` milliseconds
+    pub fn new(window_ms: u32) -> Self {
+        Self {
+            window: Duration::from_millis(window_ms as u64),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record a frame processed at `now`
+    pub fn record(&mut self, now: Instant, faces: &[Face], times: ProcessingTimes) {
+        self.samples.push_back(FrameSample {
+            at: now,
+            faces: faces.len() as u32,
+            confidence_sum: faces.iter().map(|f| f.confidence).sum(),
+            times,
+        });
+        self.prune(now);
+    }
+
+    /// Summarize the frames processed within the window before `now`
+    pub fn snapshot(&mut self, now: Instant) -> WindowedStats {
+        self.prune(now);
+
+        let frames = self.samples.len() as u32;
+        let faces: u64 = self.samples.iter().map(|s| s.faces as u64).sum();
+        let confidence_sum: f32 = self.samples.iter().map(|s| s.confidence_sum).sum();
+
+        // Before the window has filled up, rates are relative to the time
+        // since the oldest frame
+        let span = self.samples.front().map_or(Duration::ZERO, |oldest| now - oldest.at);
+        let fps = if span.is_zero() { 0.0 } else { frames as f32 / span.as_secs_f32() };
+
+        let mean = |time: fn(&ProcessingTimes) -> f32| {
+            if frames == 0 {
+                0.0
+            } else {
+                self.samples.iter().map(|s| time(&s.times)).sum::<f32>() / frames as f32
+            }
+        };
+
+        WindowedStats {
+            window_ms: self.window.as_millis() as u32,
+            frames,
+            fps,
+            faces_detected: faces,
+            average_faces: if frames == 0 { 0.0 } else { faces as f32 / frames as f32 },
+            average_confidence: if faces == 0 { 0.0 } else { confidence_sum / faces as f32 },
+            average_processing_times: ProcessingTimes {
+                conversion_ms: mean(|t| t.conversion_ms),
+                preprocessing_ms: mean(|t| t.preprocessing_ms),
+                detection_ms: mean(|t| t.detection_ms),
+                landmark_ms: mean(|t| t.landmark_ms),
+                pose_ms: mean(|t| t.pose_ms),
+                total_ms: mean(|t| t.total_ms),
+            },
+        }
+    }
+
+    /// Forget all samples
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self.samples.front().is_some_and(|oldest| now - oldest.at > self.window) {
+            self.samples.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(confidence: f32) -> Face {
+        Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence,
+            ..Face::default()
+        }
+    }
+
+    fn times(total_ms: f32) -> ProcessingTimes {
+        ProcessingTimes { total_ms, ..ProcessingTimes::default() }
+    }
+
+    #[test]
+    fn test_summarizes_recent_frames() {
+        let start = Instant::now();
+        let mut window = StatsWindow::new(1000);
+        for i in 0..10u64 {
+            let faces = if i % 2 == 0 { vec![face(0.8)] } else { vec![face(0.6), face(1.0)] };
+            window.record(start + Duration::from_millis(i * 100), &faces, times(10.0 + i as f32));
+        }
+
+        let stats = window.snapshot(start + Duration::from_millis(1000));
+        assert_eq!(stats.frames, 10);
+        assert!((stats.fps - 10.0).abs() < 1e-3);
+        assert_eq!(stats.faces_detected, 15);
+        assert!((stats.average_faces - 1.5).abs() < 1e-6);
+        assert!((stats.average_confidence - 0.8).abs() < 1e-6);
+        assert!((stats.average_processing_times.total_ms - 14.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_old_frames_leave_the_window() {
+        let start = Instant::now();
+        let mut window = StatsWindow::new(500);
+        window.record(start, &[face(0.9)], times(20.0));
+        window.record(start + Duration::from_millis(400), &[], times(10.0));
+
+        let stats = window.snapshot(start + Duration::from_millis(800));
+        assert_eq!(stats.frames, 1);
+        assert_eq!(stats.faces_detected, 0);
+        assert_eq!(stats.average_processing_times.total_ms, 10.0);
+
+        // A stalled tracker shows up as an empty window
+        let stats = window.snapshot(start + Duration::from_secs(2));
+        assert_eq!((stats.frames, stats.fps), (0, 0.0));
+    }
+}
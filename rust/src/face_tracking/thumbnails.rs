@@ -0,0 +1,138 @@
+//! Face thumbnails
+//!
+//! Multi-face selection UIs show a small picture of each tracked face. The
+//! tracker keeps an upright crop of every face from a recent frame, so the
+//! app can ask for one by face ID without sending frames back to Rust.
+//! Crops are refreshed a few times a second rather than every frame, and
+//! only JPEG-encoded when requested.
+
+use crate::error::PluginError;
+use crate::models::*;
+use image::codecs::jpeg::JpegEncoder;
+use image::{imageops, RgbImage};
+use std::collections::HashMap;
+
+/// Largest thumbnail side (pixels); crops are stored at this size
+pub const MAX_THUMBNAIL_SIZE: u32 = 256;
+
+/// Minimum time between crop refreshes (milliseconds)
+const REFRESH_INTERVAL_MS: i64 = 250;
+
+/// Share of the bounding box added on every side, so the crop shows some
+/// hair and chin
+const MARGIN: f32 = 0.2;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Latest crop of each tracked face
+#[derive(Debug, Default)]
+pub struct FaceThumbnails {
+    crops: HashMap<u32, RgbImage>,
+    last_refresh: Option<i64>,
+}
+
+impl FaceThumbnails {
+    /// Whether the frame at `timestamp` should refresh the crops
+    pub fn is_due(&self, timestamp: i64) -> bool {
+        self.last_refresh
+            .is_none_or(|last| (timestamp - last).abs() >= REFRESH_INTERVAL_MS)
+    }
+
+    /// Replace the crops with those of the faces in the frame at `timestamp`;
+    /// faces that are no longer tracked lose their thumbnail
+    pub fn refresh(&mut self, timestamp: i64, crops: HashMap<u32, RgbImage>) {
+        self.crops = crops;
+        self.last_refresh = Some(timestamp);
+    }
+
+    /// JPEG thumbnail of a face, no larger than `max_size` on either side
+    pub fn encode(&self, face_id: u32, max_size: u32) -> Result<Vec<u8>, PluginError> {
+        if max_size == 0 || max_size > MAX_THUMBNAIL_SIZE {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Thumbnail size must be between 1 and {}, got {}",
+                MAX_THUMBNAIL_SIZE, max_size
+            )));
+        }
+        let crop = self
+            .crops
+            .get(&face_id)
+            .ok_or_else(|| PluginError::ProcessingError(format!("No thumbnail for face {}", face_id)))?;
+
+        let mut jpeg = Vec::new();
+        let encoded = if crop.width().max(crop.height()) > max_size {
+            let (width, height) = fit(crop.width(), crop.height(), max_size);
+            JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(&imageops::thumbnail(crop, width, height))
+        } else {
+            JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(crop)
+        };
+        encoded.map_err(|e| PluginError::ImageConversion(format!("Failed to encode thumbnail: {}", e)))?;
+        Ok(jpeg)
+    }
+
+    /// Forget all crops
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Cut a face out of an upright frame with a margin around its bounding
+/// box, scaled down to at most [`MAX_THUMBNAIL_SIZE`]
+///
+/// Returns `None` if the box lies outside the frame.
+pub fn crop(image: &RgbImage, bounding_box: &BoundingBox) -> Option<RgbImage> {
+    let margin_x = bounding_box.width * MARGIN;
+    let margin_y = bounding_box.height * MARGIN;
+    let x0 = (bounding_box.x - margin_x).max(0.0) as u32;
+    let y0 = (bounding_box.y - margin_y).max(0.0) as u32;
+    let x1 = ((bounding_box.x + bounding_box.width + margin_x).min(image.width() as f32)) as u32;
+    let y1 = ((bounding_box.y + bounding_box.height + margin_y).min(image.height() as f32)) as u32;
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+
+    let crop = imageops::crop_imm(image, x0, y0, x1 - x0, y1 - y0);
+    let (width, height) = fit(x1 - x0, y1 - y0, MAX_THUMBNAIL_SIZE);
+    Some(if (width, height) == (x1 - x0, y1 - y0) {
+        crop.to_image()
+    } else {
+        imageops::thumbnail(&*crop, width, height)
+    })
+}
+
+/// Size of a `width` x `height` image scaled down to fit `max_size`,
+/// keeping its aspect ratio
+fn fit(width: u32, height: u32, max_size: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_size {
+        return (width, height);
+    }
+    let scale = max_size as f32 / longest as f32;
+    (((width as f32 * scale).round() as u32).max(1), ((height as f32 * scale).round() as u32).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crop_and_encode() {
+        let image = RgbImage::from_fn(1000, 800, |x, _| image::Rgb([(x / 4) as u8, 0, 0]));
+        let bounding_box = BoundingBox { x: 100.0, y: 100.0, width: 500.0, height: 400.0 };
+        let face = crop(&image, &bounding_box).unwrap();
+        // 700 x 560 with the margin, scaled to fit
+        assert_eq!(face.dimensions(), (256, 205));
+        assert!(crop(&image, &BoundingBox { x: 1200.0, y: 0.0, width: 50.0, height: 50.0 }).is_none());
+
+        let mut thumbnails = FaceThumbnails::default();
+        assert!(thumbnails.is_due(0));
+        thumbnails.refresh(0, HashMap::from([(3, face)]));
+        assert!(!thumbnails.is_due(100));
+        assert!(thumbnails.is_due(250));
+
+        let jpeg = thumbnails.encode(3, 64).unwrap();
+        let decoded = image::load_from_memory(&jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (64, 51));
+        assert!(thumbnails.encode(4, 64).is_err());
+        assert!(thumbnails.encode(3, 0).is_err());
+    }
+}
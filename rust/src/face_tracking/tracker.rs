@@ -4,33 +4,234 @@
 //! landmark tracking, and pose estimation using the openseeface-rs library.
 
 use crate::api::TrackerConfig;
+use crate::calibration::profiles::CalibrationProfile;
+use crate::calibration::range::RangeCapture;
 use crate::models::*;
+use crate::models::packed;
 use crate::error::PluginError;
-use openseeface::{Tracker as OpenSeeFaceTracker, TrackerConfig as OSFConfig};
+use crate::face_tracking::acceleration;
+use crate::face_tracking::adaptive::{AdaptiveController, BatteryState, QualityStep, ThermalState};
+use crate::face_tracking::alignment;
+use crate::face_tracking::attention::AttentionEstimator;
+use crate::face_tracking::backend::{self, handle::BackendHandle};
+use crate::face_tracking::blink::BlinkDetector;
+use crate::face_tracking::broadcast::{FaceBroadcast, FaceSubscriberOptions};
+use crate::face_tracking::association::{FaceAssociator, DEFAULT_LABEL_SIMILARITY, DEFAULT_MIN_IOU};
+use crate::face_tracking::color;
+use crate::face_tracking::embedding::FaceEmbedder;
+use crate::face_tracking::error_log::{ErrorLog, ErrorOrigin};
+use crate::face_tracking::expressions::{self, EyeCalibration, ExpressionCalibration, ExpressionFeature, MouthCalibration, ValueRange};
+use crate::face_tracking::filters::FaceSmoother;
+use crate::face_tracking::frame_queue::FrameQueue;
+use crate::face_tracking::fusion::ConfidenceFusion;
+use crate::face_tracking::gaze::GazeMapper;
+use crate::face_tracking::gestures::GestureRecognizer;
+use crate::face_tracking::drowsiness::DrowsinessAnalyzer;
+use crate::face_tracking::imu::{ImuFusion, ImuSample};
+use crate::face_tracking::mesh;
+use crate::face_tracking::orientation::FrameOrientation;
+use crate::face_tracking::pipeline::{self, FrameThrottle};
+use crate::face_tracking::pose;
+use crate::face_tracking::prediction::Predictor;
+use crate::face_tracking::quality;
+use crate::face_tracking::recognition;
+use crate::face_tracking::scaling::DetectionScale;
+use crate::face_tracking::scene;
+use crate::face_tracking::segmentation::{SegmentationMatte, Segmenter};
+use crate::face_tracking::scheduler::{expand_region, offset_face, DetectionScheduler};
+use crate::face_tracking::stats::StatsWindow;
+use crate::face_tracking::thumbnails::{self, FaceThumbnails};
+use crate::protocols::FrameInfo;
+use crate::utils::buffer_pool::{recycle_image, FRAME_BUFFERS};
+use crate::utils::panic;
+use crate::utils::shared_buffer::SharedFrame;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
-use tokio::sync::{RwLock, mpsc};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 use flutter_rust_bridge::StreamSink;
-use image::{RgbImage, DynamicImage};
+use image::{GrayImage, RgbImage, DynamicImage};
 use log::{debug, info, warn, error};
+use tracing::{info_span, Instrument};
 
 /// Main face tracker implementation
 pub struct FaceTracker {
-    /// OpenSeeFace tracker instance
-    tracker: Arc<RwLock<OpenSeeFaceTracker>>,
+    /// Inference backend running the tracking models
+    backend: BackendHandle,
     /// Tracker configuration
     config: TrackerConfig,
     /// Whether tracking is currently active
     is_running: AtomicBool,
     /// Total frames processed
     frames_processed: AtomicU64,
+    /// Frames dropped by throttling
+    frames_dropped: Arc<AtomicU64>,
+    /// Frames dropped by a full stream queue
+    queue_drops: Arc<AtomicU64>,
     /// Frame processing statistics
     stats: Arc<RwLock<TrackingStats>>,
+    /// Per-frame samples of the last few seconds
+    stats_window: Arc<RwLock<StatsWindow>>,
+    /// Recent frame processing failures
+    errors: Arc<Mutex<ErrorLog>>,
     /// Last processing time
     last_process_time: Arc<RwLock<Instant>>,
-    /// Stream sender for face data
-    face_sender: Option<mpsc::UnboundedSender<Vec<Face>>>,
+    /// Persistent face ID assignment across frames
+    associator: Arc<RwLock<FaceAssociator>>,
+    /// Blending of low-confidence measurements with the previous state
+    fusion: Arc<RwLock<ConfidenceFusion>>,
+    /// Temporal smoothing of tracker output
+    smoother: Arc<RwLock<FaceSmoother>>,
+    /// Latency compensation of the published output
+    predictor: Arc<RwLock<Predictor>>,
+    /// Eye aspect ratio range of the current user
+    eye_calibration: Arc<RwLock<EyeCalibration>>,
+    /// Mouth shape ranges of the current user
+    mouth_calibration: Arc<RwLock<MouthCalibration>>,
+    /// Expression value ranges of the current user
+    expression_calibration: Arc<RwLock<ExpressionCalibration>>,
+    /// Expression range being recorded, if any
+    range_capture: Arc<RwLock<Option<RangeCapture>>>,
+    /// Gaze calibration and mapping onto the screen
+    gaze_mapper: Arc<RwLock<GazeMapper>>,
+    /// Per-eye blink state across frames
+    blink_detector: Arc<RwLock<BlinkDetector>>,
+    /// Nod, shake and tilt recognition from the head pose
+    gesture_recognizer: Arc<RwLock<GestureRecognizer>>,
+    /// Attention to the camera over a time window
+    attention: Arc<RwLock<AttentionEstimator>>,
+    /// PERCLOS and prolonged eye closure detection
+    drowsiness: Arc<RwLock<DrowsinessAnalyzer>>,
+    /// Full-frame detection versus region tracking per frame
+    scheduler: Arc<RwLock<DetectionScheduler>>,
+    /// Faces of the last processed frame, in frame pixel coordinates
+    last_faces: Arc<RwLock<Vec<Face>>>,
+    /// Recent crop of each tracked face, for thumbnails
+    thumbnails: Arc<RwLock<FaceThumbnails>>,
+    /// Quality reduction for the reported thermal and battery state
+    adaptive: Arc<Mutex<AdaptiveController>>,
+    /// Gyroscope fusion into the head pose
+    imu: Arc<Mutex<ImuFusion>>,
+    /// Embedding model, loaded when the first frame is processed; `None`
+    /// if embeddings are disabled or the model failed to load
+    embedder: OnceLock<Option<FaceEmbedder>>,
+    /// Segmentation model, loaded when the first frame is processed; `None`
+    /// if segmentation is disabled or the model failed to load
+    segmenter: OnceLock<Option<Segmenter>>,
+    /// Person matte of the last segmented frame
+    matte: Arc<RwLock<Option<SegmentationMatte>>>,
+    /// Whether results are forwarded to the network outputs
+    publish_output: bool,
+    /// Queue feeding frames to the stream pipeline
+    frame_queue: Option<Arc<FrameQueue<QueuedFrame>>>,
+    /// Subscribers of the tracking stream
+    broadcast: Arc<FaceBroadcast>,
+}
+
+impl Drop for FaceTracker {
+    fn drop(&mut self) {
+        // Ends the stream pipeline and the subscriptions
+        if let Some(queue) = self.frame_queue.take() {
+            queue.close();
+        }
+        self.broadcast.close();
+    }
+}
+
+/// A frame waiting for the stream pipeline
+pub(crate) enum QueuedFrame {
+    /// Frame data copied from Dart
+    Camera(CameraFrame),
+    /// Frame in a shared buffer slot, released when dropped
+    Shared(SharedFrame),
+}
+
+/// A frame converted to an upright image, ready for detection
+pub(crate) struct ConvertedFrame {
+    image: DynamicImage,
+    /// Embedding model and the frame it embeds faces from, if embeddings run
+    embedding: Option<(FaceEmbedder, RgbImage)>,
+    /// Segmentation model and the frame it segments, if segmentation runs
+    segmentation: Option<(Segmenter, RgbImage)>,
+    context: FrameContext,
+}
+
+/// Faces detected in a frame, before ID assignment and analysis
+pub(crate) struct DetectedFrame {
+    faces: Vec<Face>,
+    context: FrameContext,
+}
+
+impl DetectedFrame {
+    pub(crate) fn timestamp(&self) -> i64 {
+        self.context.info.timestamp
+    }
+}
+
+/// What the later stages keep of a frame besides its image
+struct FrameContext {
+    orientation: FrameOrientation,
+    info: FrameInfo,
+    intrinsics: Option<CameraIntrinsics>,
+    /// Luma of the frame as the camera delivered it
+    luma: GrayImage,
+    /// Frame to cut thumbnails from, if they are due
+    thumbnail_image: Option<RgbImage>,
+    /// Quality step the frame is processed at
+    quality: QualityStep,
+    start_time: Instant,
+    /// Times of the stages run so far
+    times: ProcessingTimes,
+}
+
+/// Where a stream subscriber receives the faces of each frame
+pub enum FaceStreamSink {
+    /// Faces as bridge structs
+    Faces(StreamSink<Vec<Face>>),
+    /// Faces packed into one buffer per frame (see [`packed`])
+    Packed(StreamSink<Vec<u8>>),
+}
+
+impl FaceStreamSink {
+    /// Send the faces of the frame at `timestamp`, returning whether the
+    /// sink is still open
+    pub(crate) fn send(&self, faces: &[Face], timestamp: i64) -> bool {
+        match self {
+            FaceStreamSink::Faces(sink) => sink.add(faces.to_vec()).is_ok(),
+            FaceStreamSink::Packed(sink) => sink.add(packed::pack(faces, timestamp)).is_ok(),
+        }
+    }
+}
+
+/// Default number of frames waiting for the stream worker
+pub const FRAME_QUEUE_CAPACITY: u32 = 4;
+
+/// Producer side of the stream worker's frame queue
+///
+/// Taken from the tracker with [`FaceTracker::stream_queue`], so producers
+/// waiting on a full queue do not hold the global tracker lock.
+#[derive(Clone)]
+pub struct StreamQueue(Arc<FrameQueue<QueuedFrame>>);
+
+impl StreamQueue {
+    /// Queue a frame for the stream worker
+    ///
+    /// Returns `Ok(false)` if the frame was dropped under the queue policy.
+    pub async fn push_frame(&self, frame: CameraFrame) -> Result<bool, PluginError> {
+        self.0.push(QueuedFrame::Camera(frame)).await
+    }
+
+    /// Queue a shared buffer frame for the stream worker
+    ///
+    /// The slot is released right away if the frame is dropped.
+    pub async fn push_shared_frame(&self, frame: SharedFrame) -> Result<bool, PluginError> {
+        self.0.push(QueuedFrame::Shared(frame)).await
+    }
+}
+
+/// Milliseconds since `start`, with sub-millisecond precision
+fn elapsed_ms(start: Instant) -> f32 {
+    start.elapsed().as_secs_f32() * 1000.0
 }
 
 impl FaceTracker {
@@ -38,116 +239,748 @@ impl FaceTracker {
     pub fn new(config: TrackerConfig) -> Result<Self, PluginError> {
         info!("Creating face tracker with config: {:?}", config);
 
-        // Convert our config to OpenSeeFace config
-        let osf_config = OSFConfig {
-            // Map model type - openseeface-rs uses different model specification
-            model_name: match config.model_type {
-                ModelType::RetinaFace => "default".to_string(), // Use default model
-                ModelType::MTCNN => "light".to_string(),       // Use lighter model if available
-            },
-            confidence_threshold: config.confidence_threshold,
-            max_faces: config.max_faces as usize,
-            // Additional openseeface-rs specific settings
-            ..Default::default()
-        };
+        // Initialize the inference backend on the requested accelerator
+        let chain = config.inference.provider_chain(config.acceleration);
+        let (backend, acceleration_backend) = acceleration::initialize_with_fallback(
+            &chain,
+            |acceleration| backend::create(&config, acceleration),
+        )?;
+        let active_model = backend.active_model();
+        info!("Tracking with {} on {} ({:?})", active_model, backend.name(), acceleration_backend);
 
-        // Initialize the OpenSeeFace tracker
-        let tracker = OpenSeeFaceTracker::new(osf_config)
-            .map_err(|e| PluginError::TrackerInitialization(format!("Failed to create tracker: {}", e)))?;
-
-        let stats = TrackingStats {
-            total_faces_detected: 0,
-            active_faces: 0,
-            average_confidence: 0.0,
-            processing_times: ProcessingTimes {
-                detection_ms: 0.0,
-                landmark_ms: 0.0,
-                pose_ms: 0.0,
-                total_ms: 0.0,
-            },
-        };
+        let stats = TrackingStats::default();
+        let stats_window = StatsWindow::new(config.stats_window_ms);
+
+        let smoother = FaceSmoother::new(config.smoothing.clone(), config.target_fps);
 
         Ok(Self {
-            tracker: Arc::new(RwLock::new(tracker)),
+            backend: BackendHandle::new(backend, acceleration_backend),
+            associator: Arc::new(RwLock::new(
+                FaceAssociator::new(DEFAULT_MIN_IOU, config.track_memory_ms).with_label_similarity(
+                    config.face_embedding.as_ref().map_or(DEFAULT_LABEL_SIMILARITY, |e| e.recognition_threshold),
+                ),
+            )),
+            fusion: Arc::new(RwLock::new(ConfidenceFusion::new(config.confidence_fusion))),
+            smoother: Arc::new(RwLock::new(smoother)),
+            predictor: Arc::new(RwLock::new(Predictor::new(config.prediction))),
+            eye_calibration: Arc::new(RwLock::new(config.eye_calibration)),
+            mouth_calibration: Arc::new(RwLock::new(config.mouth_calibration)),
+            expression_calibration: Arc::new(RwLock::new(config.expression_calibration)),
+            range_capture: Arc::new(RwLock::new(None)),
+            gaze_mapper: Arc::new(RwLock::new(GazeMapper::new())),
+            blink_detector: Arc::new(RwLock::new(BlinkDetector::new(config.blink_detection.clone()))),
+            gesture_recognizer: Arc::new(RwLock::new(GestureRecognizer::new(config.head_gestures.clone()))),
+            attention: Arc::new(RwLock::new(AttentionEstimator::new(config.attention))),
+            drowsiness: Arc::new(RwLock::new(DrowsinessAnalyzer::new(config.drowsiness))),
+            scheduler: Arc::new(RwLock::new(DetectionScheduler::new(
+                config.detection_interval,
+                config.redetect_confidence,
+            ))),
+            last_faces: Arc::new(RwLock::new(Vec::new())),
+            thumbnails: Arc::new(RwLock::new(FaceThumbnails::default())),
+            adaptive: Arc::new(Mutex::new(AdaptiveController::new(config.adaptive_quality))),
+            imu: Arc::new(Mutex::new(ImuFusion::new(config.imu_fusion))),
+            embedder: OnceLock::new(),
+            segmenter: OnceLock::new(),
+            matte: Arc::new(RwLock::new(None)),
             config,
             is_running: AtomicBool::new(false),
             frames_processed: AtomicU64::new(0),
+            frames_dropped: Arc::new(AtomicU64::new(0)),
+            queue_drops: Arc::new(AtomicU64::new(0)),
             stats: Arc::new(RwLock::new(stats)),
+            stats_window: Arc::new(RwLock::new(stats_window)),
+            errors: Arc::new(Mutex::new(ErrorLog::new())),
             last_process_time: Arc::new(RwLock::new(Instant::now())),
-            face_sender: None,
+            publish_output: true,
+            frame_queue: None,
+            broadcast: Arc::new(FaceBroadcast::default()),
         })
     }
 
+    /// Keep results away from the network outputs (e.g. for benchmarks)
+    pub fn without_output(mut self) -> Self {
+        self.publish_output = false;
+        self
+    }
+
     /// Process a single camera frame
     pub async fn process_frame(&self, frame: CameraFrame) -> Result<Vec<Face>, PluginError> {
         let start_time = Instant::now();
         debug!("Processing frame: {}x{} format: {:?}", frame.width, frame.height, frame.format);
 
-        // Convert camera frame to image format expected by openseeface
-        let image = self.convert_frame_to_image(&frame)?;
-        let detection_start = Instant::now();
+        let frame_info = ErrorFrameInfo::of_camera_frame(&frame);
+        let mut stage = ErrorStage::Conversion;
+        let result = panic::guard_async(async {
+            let frame = self
+                .convert_camera_frame(frame, start_time)
+                .instrument(info_span!("convert", frame = frame_info.timestamp))
+                .await?;
+            self.track_converted(frame, &mut stage).await
+        }).await;
+        self.log_failure(result, ErrorOrigin { stage, frame: Some(frame_info), streamed: false })
+    }
 
-        // Process the frame with openseeface-rs
-        let mut tracker = self.tracker.write().await;
+    /// Detect faces in a frame without ID assignment, smoothing, statistics
+    /// or network output
+    pub async fn analyze_frame(&self, frame: CameraFrame) -> Result<Vec<Face>, PluginError> {
+        let orientation = FrameOrientation::new(frame.rotation, self.config.mirror_input)?;
+        let (width, height, timestamp) = (frame.width, frame.height, frame.timestamp);
+        let image = orientation.apply(self.convert_frame_to_image(frame)?);
         
-        // openseeface-rs expects the current timestamp
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let (mut faces, _) = self.detect_faces(image, timestamp).await?;
+        orientation.unmap_faces(&mut faces, width, height);
+        Ok(faces)
+    }
+
+    /// Track faces in an upright still image
+    ///
+    /// Runs detection and the stages that work on a single image (quality,
+    /// embeddings, expressions, mesh and the output conventions), but none
+    /// that follow faces over time: no ID assignment, smoothing, blinks,
+    /// gestures, statistics or network output. Live tracking state is left
+    /// untouched.
+    pub async fn process_still(&self, image: RgbImage) -> Result<Vec<Face>, PluginError> {
+        let (width, height) = image.dimensions();
+        let luma = image::imageops::grayscale(&image);
+        let mut image = image;
+        if self.config.preprocessing.is_enabled() {
+            self.config.preprocessing.apply(&mut image);
+        }
+        let embedder = self.embedder().cloned();
+        let embedding_image = embedder.as_ref().map(|_| image.clone());
+
+        let mut faces = self
+            .detect_full_frame(DynamicImage::ImageRgb8(image), chrono::Utc::now().timestamp_millis())
+            .await?;
+        if let (Some(embedder), Some(image)) = (embedder, embedding_image) {
+            faces = tokio::task::spawn_blocking(move || embedder.apply(&image, &mut faces).map(|_| faces))
+                .await
+                .map_err(|e| PluginError::ThreadingError(format!("Embedding task failed: {}", e)))??;
+            if let Some(config) = &self.config.face_embedding {
+                recognition::apply(&mut faces, config.recognition_threshold);
+            }
+        }
+        quality::apply(&luma, &mut faces);
+        faces.retain(|face| face.quality.score >= self.config.min_face_quality);
+
+        expressions::apply(
+            &mut faces,
+            &*self.eye_calibration.read().await,
+            &*self.mouth_calibration.read().await,
+        );
+        let expression_calibration = *self.expression_calibration.read().await;
+        for expressions in faces.iter_mut().filter_map(|face| face.expressions.as_mut()) {
+            expression_calibration.rescale(expressions);
+        }
+        if self.config.enable_face_mesh {
+            mesh::apply(&mut faces);
+        }
+        self.config.output_conventions.apply(&mut faces, width, height);
+        Ok(faces)
+    }
+
+    /// Embedding of the most confident face in a frame, `None` if no face
+    /// with landmarks is found
+    ///
+    /// Fails if face embeddings are not configured or the model cannot be
+    /// loaded.
+    pub async fn face_embedding(&self, frame: CameraFrame) -> Result<Option<Vec<f32>>, PluginError> {
+        let embedder = self.embedder().cloned().ok_or_else(|| {
+            PluginError::InvalidConfiguration("Face embeddings are not available; set `face_embedding`".to_string())
+        })?;
+        let orientation = FrameOrientation::new(frame.rotation, self.config.mirror_input)?;
+        let timestamp = frame.timestamp;
+        let image = orientation.apply(self.convert_frame_to_image(frame)?);
+        let rgb = image.to_rgb8();
+
+        let (faces, _) = self.detect_faces(image, timestamp).await?;
+        let Some(landmarks) = faces
+            .into_iter()
+            .filter(|face| face.landmarks.is_some())
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+            .and_then(|face| face.landmarks)
+        else {
+            return Ok(None);
+        };
+        tokio::task::spawn_blocking(move || embedder.embed(&rgb, &landmarks).map(Some))
+            .await
+            .map_err(|e| PluginError::ThreadingError(format!("Embedding task failed: {}", e)))?
+    }
+
+    /// Process a camera frame made of separate Y, U and V planes
+    pub async fn process_planar_frame(&self, frame: PlanarCameraFrame) -> Result<Vec<Face>, PluginError> {
+        let start_time = Instant::now();
+        debug!("Processing planar frame: {}x{}", frame.width, frame.height);
+
+        let frame_info = ErrorFrameInfo {
+            width: frame.width,
+            height: frame.height,
+            timestamp: frame.timestamp,
+            format: None,
+        };
+        let mut stage = ErrorStage::Conversion;
+        let result = panic::guard_async(async {
+            let orientation = FrameOrientation::new(frame.rotation, self.config.mirror_input)?;
+            let info = FrameInfo {
+                width: frame.width,
+                height: frame.height,
+                timestamp: frame.timestamp,
+            };
+            let intrinsics = frame.intrinsics.clone();
+            let frame = async {
+                let rgb_data = color::planar_yuv420_to_rgb(&frame)?;
+                for plane in frame.planes {
+                    FRAME_BUFFERS.release(plane.data);
+                }
+                
+                let image = RgbImage::from_raw(info.width, info.height, rgb_data)
+                    .ok_or_else(|| PluginError::ImageConversion("Failed to create RGB from planar YUV".to_string()))?;
+                
+                let image = orientation.apply(DynamicImage::ImageRgb8(image));
+                self.prepare(image, orientation, info, intrinsics, start_time).await
+            }
+            .instrument(info_span!("convert", frame = frame_info.timestamp))
+            .await?;
+            self.track_converted(frame, &mut stage).await
+        }).await;
+        self.log_failure(result, ErrorOrigin { stage, frame: Some(frame_info), streamed: false })
+    }
+
+    /// Process a frame stored in a shared buffer slot, converting it in place
+    pub async fn process_shared_frame(&self, frame: &SharedFrame) -> Result<Vec<Face>, PluginError> {
+        let start_time = Instant::now();
+        let metadata = &frame.metadata;
+        debug!("Processing shared frame: {}x{} format: {:?}", metadata.width, metadata.height, metadata.format);
+
+        let frame_info = ErrorFrameInfo::of_shared_frame(metadata);
+        let mut stage = ErrorStage::Conversion;
+        let result = panic::guard_async(async {
+            let frame = self
+                .convert_shared_frame(frame, start_time)
+                .instrument(info_span!("convert", frame = frame_info.timestamp))
+                .await?;
+            self.track_converted(frame, &mut stage).await
+        }).await;
+        self.log_failure(result, ErrorOrigin { stage, frame: Some(frame_info), streamed: false })
+    }
+
+    /// Convert a queued stream frame and prepare it for detection
+    ///
+    /// First stage of the stream pipeline; failures are logged like those of
+    /// [`FaceTracker::process_frame`].
+    pub(crate) async fn convert_queued(&self, frame: QueuedFrame) -> Result<ConvertedFrame, PluginError> {
+        let start_time = Instant::now();
+        let frame_info = match &frame {
+            QueuedFrame::Camera(frame) => ErrorFrameInfo::of_camera_frame(frame),
+            QueuedFrame::Shared(frame) => ErrorFrameInfo::of_shared_frame(&frame.metadata),
+        };
+        let result = panic::guard_async(async {
+            match frame {
+                QueuedFrame::Camera(frame) => self.convert_camera_frame(frame, start_time).await,
+                // The buffer slot is released once the frame is converted
+                QueuedFrame::Shared(frame) => self.convert_shared_frame(&frame, start_time).await,
+            }
+        }.instrument(info_span!("convert", frame = frame_info.timestamp))).await;
+        let origin = ErrorOrigin { stage: ErrorStage::Conversion, frame: Some(frame_info), streamed: true };
+        self.log_failure(result, origin)
+    }
+
+    /// Run detection on a converted stream frame
+    ///
+    /// Second stage of the stream pipeline.
+    pub(crate) async fn detect_converted(&self, frame: ConvertedFrame) -> Result<DetectedFrame, PluginError> {
+        let frame_info = ErrorFrameInfo::of_frame_info(&frame.context.info);
+        let span = info_span!("detect", frame = frame_info.timestamp);
+        let result = panic::guard_async(self.detect(frame).instrument(span)).await;
+        self.log_failure(result, ErrorOrigin { stage: ErrorStage::Detection, frame: Some(frame_info), streamed: true })
+    }
+
+    /// Analyze the detected faces of a stream frame and publish them
+    ///
+    /// Last stage of the stream pipeline.
+    pub(crate) async fn finish_detected(&self, frame: DetectedFrame) -> Result<Vec<Face>, PluginError> {
+        let frame_info = ErrorFrameInfo::of_frame_info(&frame.context.info);
+        let span = info_span!("landmarks", frame = frame_info.timestamp);
+        let result = panic::guard_async(self.finish(frame).instrument(span)).await;
+        self.log_failure(result, ErrorOrigin { stage: ErrorStage::Analysis, frame: Some(frame_info), streamed: true })
+    }
+
+    /// Convert a camera frame to an upright image and prepare it for detection
+    async fn convert_camera_frame(&self, frame: CameraFrame, start_time: Instant) -> Result<ConvertedFrame, PluginError> {
+        // Convert camera frame to image format expected by openseeface
+        let orientation = FrameOrientation::new(frame.rotation, self.config.mirror_input)?;
+        let info = FrameInfo {
+            width: frame.width,
+            height: frame.height,
+            timestamp: frame.timestamp,
+        };
+        let intrinsics = frame.intrinsics.clone();
+        let image = orientation.apply(self.convert_frame_to_image(frame)?);
         
-        // Detect faces in the image
-        tracker.detect(&image, timestamp)
-            .map_err(|e| PluginError::ProcessingError(format!("Detection failed: {}", e)))?;
+        self.prepare(image, orientation, info, intrinsics, start_time).await
+    }
 
-        let detection_time = detection_start.elapsed().as_millis() as f32;
+    /// Convert a shared buffer frame in place and prepare it for detection
+    async fn convert_shared_frame(&self, frame: &SharedFrame, start_time: Instant) -> Result<ConvertedFrame, PluginError> {
+        let metadata = &frame.metadata;
+        let orientation = FrameOrientation::new(metadata.rotation, self.config.mirror_input)?;
+        let rgb_data = color::to_rgb(frame.data(), metadata.width, metadata.height, metadata.format)?;
+        let image = RgbImage::from_raw(metadata.width, metadata.height, rgb_data)
+            .ok_or_else(|| PluginError::ImageConversion(format!("Failed to convert {:?} to RGB", metadata.format)))?;
         
-        // Convert detected faces to our format
-        let landmark_start = Instant::now();
-        let faces = self.convert_detected_faces(&*tracker, frame.timestamp).await?;
-        let landmark_time = landmark_start.elapsed().as_millis() as f32;
+        self.prepare(orientation.apply(DynamicImage::ImageRgb8(image)), orientation, FrameInfo {
+            width: metadata.width,
+            height: metadata.height,
+            timestamp: metadata.timestamp,
+        }, metadata.intrinsics.clone(), start_time).await
+    }
+
+    /// Embedding model, loading it on first use
+    fn embedder(&self) -> Option<&FaceEmbedder> {
+        let config = self.config.face_embedding.as_ref()?;
+        self.embedder
+            .get_or_init(|| match FaceEmbedder::load(config) {
+                Ok(embedder) => Some(embedder),
+                Err(e) => {
+                    error!("Face embeddings disabled: {}", e);
+                    let origin = ErrorOrigin { stage: ErrorStage::Detection, frame: None, streamed: false };
+                    let _ = self.log_failure::<()>(Err(e), origin);
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    /// Segmentation model, loading it on first use
+    fn segmenter(&self) -> Option<&Segmenter> {
+        let config = self.config.segmentation.as_ref()?;
+        self.segmenter
+            .get_or_init(|| match Segmenter::load(config) {
+                Ok(segmenter) => Some(segmenter),
+                Err(e) => {
+                    error!("Person segmentation disabled: {}", e);
+                    let origin = ErrorOrigin { stage: ErrorStage::Detection, frame: None, streamed: false };
+                    let _ = self.log_failure::<()>(Err(e), origin);
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    /// Record a failed or panicked frame in the error log and on the error
+    /// event stream
+    fn log_failure<T>(&self, result: Result<T, PluginError>, origin: ErrorOrigin) -> Result<T, PluginError> {
+        if let Err(e) = &result {
+            let event = self.errors.lock().unwrap().record(e, chrono::Utc::now().timestamp_millis(), origin);
+            if self.publish_output {
+                crate::events::emit_error(&event);
+            }
+        }
+        result
+    }
+
+    /// Run the detect and landmark stages on a converted frame, keeping
+    /// `stage` at the stage currently running
+    async fn track_converted(&self, frame: ConvertedFrame, stage: &mut ErrorStage) -> Result<Vec<Face>, PluginError> {
+        let timestamp = frame.context.info.timestamp;
+        *stage = ErrorStage::Detection;
+        let frame = self.detect(frame).instrument(info_span!("detect", frame = timestamp)).await?;
+        *stage = ErrorStage::Analysis;
+        self.finish(frame).instrument(info_span!("landmarks", frame = timestamp)).await
+    }
+
+    /// Convert stage: measure the frame, keep what thumbnails and embeddings
+    /// need and apply preprocessing
+    async fn prepare(
+        &self,
+        image: DynamicImage,
+        orientation: FrameOrientation,
+        frame: FrameInfo,
+        intrinsics: Option<CameraIntrinsics>,
+        start_time: Instant,
+    ) -> Result<ConvertedFrame, PluginError> {
+        let conversion_time = elapsed_ms(start_time);
+        let intrinsics = intrinsics.or_else(|| self.config.camera_intrinsics.clone());
+        if let Some(intrinsics) = &intrinsics {
+            intrinsics.validate()?;
+        }
+
+        // Face quality and lighting are measured on the frame as the camera
+        // delivered it, which detection consumes
+        let luma = image.to_luma8();
+        // Thumbnails show the face as the camera saw it, before preprocessing
+        let thumbnail_image = self.thumbnails.read().await.is_due(frame.timestamp).then(|| image.to_rgb8());
+
+        let preprocessing_start = Instant::now();
+        let image = if self.config.preprocessing.is_enabled() {
+            let mut image = image.into_rgb8();
+            info_span!("preprocessing", frame = frame.timestamp).in_scope(|| self.config.preprocessing.apply(&mut image));
+            DynamicImage::ImageRgb8(image)
+        } else {
+            image
+        };
+        let preprocessing_time = elapsed_ms(preprocessing_start);
+
+        // Embeddings are cut from the upright frame
+        let quality = self.quality_step();
+        let embedding = self
+            .embedder()
+            .filter(|_| quality.extras_enabled())
+            .map(|embedder| (embedder.clone(), image.to_rgb8()));
+        let segmentation = self
+            .segmenter()
+            .filter(|_| quality.extras_enabled())
+            .map(|segmenter| (segmenter.clone(), image.to_rgb8()));
+
+        Ok(ConvertedFrame {
+            image,
+            embedding,
+            segmentation,
+            context: FrameContext {
+                orientation,
+                info: frame,
+                intrinsics,
+                luma,
+                thumbnail_image,
+                quality,
+                start_time,
+                times: ProcessingTimes {
+                    conversion_ms: conversion_time,
+                    preprocessing_ms: preprocessing_time,
+                    ..ProcessingTimes::default()
+                },
+            },
+        })
+    }
+
+    /// Detect stage: run the inference backend, the embedding model and
+    /// the segmentation model
+    ///
+    /// Large frames are downscaled to the configured detection resolution
+    /// first.
+    async fn detect(&self, frame: ConvertedFrame) -> Result<DetectedFrame, PluginError> {
+        let ConvertedFrame { image, embedding, segmentation, mut context } = frame;
+
+        let detection_start = Instant::now();
+        let (mut faces, landmark_time) = self.detect_faces(image, context.info.timestamp).await?;
+        if let Some((embedder, image)) = embedding {
+            faces = tokio::task::spawn_blocking(move || embedder.apply(&image, &mut faces).map(|_| faces))
+                .instrument(info_span!("embedding", frame = context.info.timestamp))
+                .await
+                .map_err(|e| PluginError::ThreadingError(format!("Embedding task failed: {}", e)))??;
+            if let Some(config) = &self.config.face_embedding {
+                recognition::apply(&mut faces, config.recognition_threshold);
+            }
+        }
+        if let Some((segmenter, image)) = segmentation {
+            let matte = tokio::task::spawn_blocking(move || segmenter.segment(&image))
+                .instrument(info_span!("segmentation", frame = context.info.timestamp))
+                .await
+                .map_err(|e| PluginError::ThreadingError(format!("Segmentation task failed: {}", e)))??;
+            let full_resolution = self.config.segmentation.as_ref().is_some_and(|config| config.full_resolution);
+            let info = &context.info;
+            *self.matte.write().await = Some(SegmentationMatte::from_upright(
+                matte,
+                &context.orientation,
+                info.timestamp,
+                full_resolution.then_some((info.width, info.height)),
+            ));
+        }
+        context.times.detection_ms = elapsed_ms(detection_start) - landmark_time;
+        context.times.landmark_ms = landmark_time;
+
+        Ok(DetectedFrame { faces, context })
+    }
+
+    /// Landmark stage: filter the detected faces, follow them over time,
+    /// derive expressions, blinks and gestures from their landmarks and
+    /// publish the results
+    async fn finish(&self, frame: DetectedFrame) -> Result<Vec<Face>, PluginError> {
+        let DetectedFrame { mut faces, context } = frame;
+        let FrameContext { orientation, info: frame, intrinsics, luma, thumbnail_image, quality, start_time, times } = context;
+
+        // Low-quality detections are dropped before they reach the filters
+        quality::apply(&luma, &mut faces);
+        faces.retain(|face| face.quality.score >= self.config.min_face_quality);
+        let scene = scene::analyze(&luma, &faces);
+        // Crops are cut while the boxes are still upright, in face order
+        let thumbnail_crops: Option<Vec<_>> = thumbnail_image
+            .map(|image| faces.iter().map(|face| thumbnails::crop(&image, &face.bounding_box)).collect());
+        orientation.unmap_faces(&mut faces, frame.width, frame.height);
+
+        // Known intrinsics give a better pose than the backend's guessed focal length
+        if let (Some(intrinsics), true) = (&intrinsics, self.config.enable_pose_estimation) {
+            for face in faces.iter_mut() {
+                let confidence = face.pose.map_or(face.confidence, |pose| pose.confidence);
+                if let Some(landmarks) = &face.landmarks {
+                    if let Some(pose) = pose::solve_head_pose(landmarks, intrinsics, orientation, confidence) {
+                        face.pose = Some(pose);
+                    }
+                }
+            }
+        }
+
+        // Keep face IDs stable across frames before any per-face state is used
+        let lifecycle = self.associator.write().await.assign_ids(&mut faces, frame.timestamp);
+        if let Some(crops) = thumbnail_crops {
+            let crops = faces
+                .iter()
+                .zip(crops)
+                .filter_map(|(face, crop)| Some((face.id, crop?)))
+                .collect();
+            self.thumbnails.write().await.refresh(frame.timestamp, crops);
+        }
+
+        // Uncertain measurements lean on the face's previous state
+        self.fusion.write().await.apply(&mut faces);
+
+        // Gyroscope rotation steadies the pose before it is smoothed
+        self.imu.lock().unwrap().apply(&mut faces, frame.timestamp);
+
+        // Smooth landmarks, pose and gaze over time
+        self.smoother.write().await.apply(&mut faces);
+
+        // Expressions, eye state and mouth shape are derived from the smoothed landmarks
+        expressions::apply(
+            &mut faces,
+            &*self.eye_calibration.read().await,
+            &*self.mouth_calibration.read().await,
+        );
+
+        // Range calibration records the raw values before they are rescaled
+        if let Some(capture) = self.range_capture.write().await.as_mut() {
+            capture.observe(&faces);
+        }
+        let expression_calibration = *self.expression_calibration.read().await;
+        for expressions in faces.iter_mut().filter_map(|face| face.expressions.as_mut()) {
+            expression_calibration.rescale(expressions);
+        }
+        self.gaze_mapper.write().await.apply(&mut faces);
+        if self.config.enable_face_mesh && quality.extras_enabled() {
+            mesh::apply(&mut faces);
+        }
+        let eye_events = self.blink_detector.write().await.update(&mut faces);
+        let gestures = self.gesture_recognizer.write().await.update(&faces);
+        let drowsiness = self.drowsiness.write().await.update(&faces);
+
+        // Eye and mouth values get their own smoothing once blinks are detected
+        self.smoother.write().await.apply_derived(&mut faces);
+        self.attention.write().await.apply(&mut faces);
+
+        // Everything downstream receives the output extrapolated by the lead time
+        self.predictor.write().await.apply(&mut faces);
+
+        // Response curves shape the values exactly as they are emitted
+        self.config.output_curves.apply(&mut faces);
 
         // Update statistics
-        let total_time = start_time.elapsed().as_millis() as f32;
-        self.update_stats(&faces, ProcessingTimes {
-            detection_ms: detection_time,
-            landmark_ms: landmark_time,
-            pose_ms: 0.0, // Pose estimation is included in landmark time for openseeface-rs
-            total_ms: total_time,
-        }).await;
+        let total_time = elapsed_ms(start_time);
+        // Pose estimation is included in the inference backend's time
+        self.update_stats(&faces, ProcessingTimes { total_ms: total_time, ..times }, scene).await;
 
         // Update frame counter
         self.frames_processed.fetch_add(1, Ordering::Relaxed);
 
+        // Forward results to any active network outputs and event streams
+        if self.publish_output {
+            crate::protocols::broadcast_faces(&faces, &frame);
+            crate::events::emit_face_lifecycle(&lifecycle);
+            crate::events::emit_blinks(&eye_events.blinks);
+            crate::events::emit_winks(&eye_events.winks);
+            crate::events::emit_head_gestures(&gestures);
+            crate::events::emit_drowsiness(&drowsiness);
+        }
+
+        *self.last_faces.write().await = faces.clone();
+
+        // Network outputs follow their protocols; only the app sees the
+        // configured conventions
+        self.config.output_conventions.apply(&mut faces, frame.width, frame.height);
+
         debug!("Processed frame in {:.2}ms, found {} faces", total_time, faces.len());
         Ok(faces)
     }
 
-    /// Start continuous face tracking stream
-    pub async fn start_stream(&self) -> Result<StreamSink<Vec<Face>>, PluginError> {
-        info!("Starting face tracking stream");
-        
-        self.is_running.store(true, Ordering::Relaxed);
+    /// Run the inference backend on the full frame or on the scheduled face regions
+    ///
+    /// Returns faces in upright full-resolution coordinates, together with the
+    /// time spent tracking in face regions (reported as landmark time).
+    async fn detect_faces(&self, image: DynamicImage, timestamp: i64) -> Result<(Vec<Face>, f32), PluginError> {
+        let regions = self.scheduler.write().await.plan(image.width(), image.height());
+        let timeout = self.frame_timeout();
+        let mut landmark_time = 0.0;
         
-        // Create a channel for sending face data
-        let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<Face>>();
+        let faces = match &regions {
+            None => self.detect_full_frame(image, timestamp).await?,
+            Some(regions) => {
+                let landmark_start = Instant::now();
+                let faces = self.detect_in_regions(image, regions, timestamp, timeout).await?;
+                landmark_time = elapsed_ms(landmark_start);
+                faces.into_iter().flatten().collect()
+            }
+        };
         
-        // Create the Flutter stream sink
-        let (sink, stream) = flutter_rust_bridge::StreamSink::new();
+        self.scheduler.write().await.update(&faces, regions.as_ref().map(Vec::len));
+        Ok((faces, landmark_time))
+    }
+
+    /// Run the backend on the whole (downscaled) `image`
+    ///
+    /// Returns faces in full-resolution coordinates; the converted frame is
+    /// recycled afterwards. Faces found in a downscaled image get their
+    /// landmarks from a full-resolution crop around them, unless a reduced
+    /// quality step asks for less work.
+    async fn detect_full_frame(&self, image: DynamicImage, timestamp: i64) -> Result<Vec<Face>, PluginError> {
+        // A reduced quality step scales the detection size down, starting
+        // from the frame size on unlimited (0) axes
+        let resolution_scale = self.quality_step().resolution_scale();
+        let (mut width, mut height) = (self.config.detection_width, self.config.detection_height);
+        if resolution_scale < 1.0 {
+            let limit = |limit: u32, size: u32| (if limit == 0 { size } else { limit } as f32 * resolution_scale) as u32;
+            (width, height) = (limit(width, image.width()), limit(height, image.height()));
+        }
+        let timeout = self.frame_timeout();
+        let Some((scaled, scale)) = DetectionScale::fit(&image, width, height) else {
+            let mut detection = self.detect_with_fallback(vec![image], timestamp, timeout).await?;
+            return Ok(detection.pop().unwrap_or_default());
+        };
+
+        let mut detection = self.detect_with_fallback(vec![scaled], timestamp, timeout).await?;
+        let mut faces = detection.pop().unwrap_or_default();
+        scale.unmap_faces(&mut faces);
+        if faces.is_empty() || resolution_scale < 1.0 {
+            recycle_image(image);
+            return Ok(faces);
+        }
+
+        let (frame_width, frame_height) = (image.width() as f32, image.height() as f32);
+        let regions: Vec<BoundingBox> = faces
+            .iter()
+            .map(|face| expand_region(&face.bounding_box, frame_width, frame_height))
+            .collect();
+        let refined = self.detect_in_regions(image, &regions, timestamp, timeout).await?;
+        // A face the crop misses keeps its landmarks from the downscaled image
+        Ok(faces.into_iter().zip(refined).map(|(face, refined)| refined.unwrap_or(face)).collect())
+    }
+
+    /// Run the backend on crops of `image` around `regions`
+    ///
+    /// Returns the most confident face of each region in frame coordinates,
+    /// if it holds one; the converted frame is recycled afterwards.
+    async fn detect_in_regions(
+        &self,
+        image: DynamicImage,
+        regions: &[BoundingBox],
+        timestamp: i64,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Option<Face>>, PluginError> {
+        let crops = regions
+            .iter()
+            .map(|region| image.crop_imm(region.x as u32, region.y as u32, region.width as u32, region.height as u32))
+            .collect();
+        recycle_image(image);
+
+        let detection = self.detect_with_fallback(crops, timestamp, timeout).await?;
+        Ok(regions
+            .iter()
+            .zip(detection)
+            .map(|(region, detected)| {
+                // Each region holds at most one face
+                let mut face = detected.into_iter().max_by(|a, b| a.confidence.total_cmp(&b.confidence))?;
+                offset_face(&mut face, region.x, region.y);
+                Some(face)
+            })
+            .collect())
+    }
+
+    /// Longest time detection may take on one frame
+    fn frame_timeout(&self) -> Option<Duration> {
+        match self.config.frame_timeout_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms as u64)),
+        }
+    }
+
+    /// Run the backend on `images`, switching to CPU inference after a
+    /// timeout if configured
+    ///
+    /// The frame that timed out still fails; the CPU backend is used from
+    /// the next frame on.
+    async fn detect_with_fallback(
+        &self,
+        images: Vec<DynamicImage>,
+        timestamp: i64,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Vec<Face>>, PluginError> {
+        let result = self
+            .backend
+            .detect_all(images, timestamp, timeout)
+            .instrument(info_span!("inference", frame = timestamp))
+            .await;
         
-        // Spawn a task to forward data from the channel to the stream
-        let sink_clone = sink.clone();
-        tokio::spawn(async move {
-            while let Some(faces) = receiver.recv().await {
-                if let Err(e) = sink_clone.add(faces).await {
-                    error!("Failed to send faces to stream: {}", e);
-                    break;
-                }
+        let accelerated = self.backend.acceleration() != AccelerationBackend::CPU;
+        if matches!(result, Err(PluginError::Timeout { .. })) && self.config.fallback_on_timeout && accelerated {
+            warn!("Detection on {:?} timed out, falling back to CPU", self.backend.acceleration());
+            let config = self.config.clone();
+            match tokio::task::spawn_blocking(move || backend::create(&config, AccelerationBackend::CPU)).await {
+                Ok(Ok(cpu_backend)) => self.backend.replace(cpu_backend, AccelerationBackend::CPU),
+                Ok(Err(e)) => error!("CPU fallback failed: {}", e),
+                Err(e) => error!("CPU fallback failed: {}", e),
             }
-        });
+        }
+        
+        result
+    }
+
+    /// Start continuous face tracking stream with `sink` as a subscriber
+    ///
+    /// Frames queued through [`FaceTracker::stream_queue`] are processed by
+    /// the staged workers of the [`pipeline`] on the shared runtime and the
+    /// results are sent to every subscriber. If the stream is already
+    /// running, `sink` is added to its subscribers.
+    pub async fn start_stream(&mut self, sink: FaceStreamSink) -> Result<(), PluginError> {
+        self.subscribe(sink, FaceSubscriberOptions::default());
+        if self.frame_queue.is_some() {
+            return Ok(());
+        }
+        info!("Starting face tracking stream");
+        
+        let queue = Arc::new(FrameQueue::new(
+            self.config.queue_capacity as usize,
+            self.config.queue_policy,
+            self.queue_drops.clone(),
+        ));
+        self.frame_queue = Some(queue.clone());
+        self.is_running.store(true, Ordering::Relaxed);
         
-        // Store the sender for use in process_frame
-        // Note: In a real implementation, you'd need to make this mutable
-        // For now, we return the sink that can be used externally
+        let throttle = FrameThrottle::new(
+            self.config.target_fps,
+            self.config.frame_rate_policy,
+            self.frames_dropped.clone(),
+        )
+        .with_adaptive(self.adaptive.clone());
+        pipeline::spawn(queue, self.broadcast.clone(), throttle);
         
-        Ok(sink)
+        Ok(())
+    }
+
+    /// Add a subscriber to the tracking stream
+    ///
+    /// The subscriber receives the faces of every frame processed by the
+    /// stream from now on, under its own rate limit and queue policy, until
+    /// Dart cancels its stream or tracking stops.
+    pub fn subscribe(&self, sink: FaceStreamSink, options: FaceSubscriberOptions) {
+        self.broadcast.subscribe(sink, options);
+        debug!("Face stream has {} subscribers", self.broadcast.subscriber_count());
+    }
+
+    /// Producer handle of the running stream's frame queue
+    pub fn stream_queue(&self) -> Result<StreamQueue, PluginError> {
+        self.frame_queue
+            .clone()
+            .map(StreamQueue)
+            .ok_or_else(|| PluginError::ProcessingError("Tracking stream is not running".to_string()))
     }
 
     /// Stop face tracking
@@ -155,17 +988,215 @@ impl FaceTracker {
         info!("Stopping face tracking");
         self.is_running.store(false, Ordering::Relaxed);
         
-        // Close the face sender if it exists
-        if let Some(sender) = self.face_sender.take() {
-            drop(sender); // This will close the channel
+        // Closing the queue ends the stream pipeline
+        if let Some(queue) = self.frame_queue.take() {
+            queue.close();
+        }
+        self.broadcast.close();
+        
+        // Faces still tracked are lost with the stream
+        let lost = self.associator.write().await.reset();
+        if self.publish_output {
+            crate::events::emit_face_lifecycle(&lost);
         }
+        self.fusion.write().await.reset();
+        self.smoother.write().await.reset();
+        self.predictor.write().await.reset();
+        self.blink_detector.write().await.reset();
+        self.gesture_recognizer.write().await.reset();
+        self.attention.write().await.reset();
+        self.drowsiness.write().await.reset();
+        self.gaze_mapper.write().await.reset();
+        self.scheduler.write().await.reset();
+        self.imu.lock().unwrap().reset();
+        self.last_faces.write().await.clear();
+        *self.matte.write().await = None;
+        self.thumbnails.write().await.reset();
         
         Ok(())
     }
 
+    /// Configuration the tracker was created with
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+
+    /// Configuration the tracker was created with, updated with the
+    /// calibration changed since
+    pub async fn current_config(&self) -> TrackerConfig {
+        TrackerConfig {
+            eye_calibration: *self.eye_calibration.read().await,
+            mouth_calibration: *self.mouth_calibration.read().await,
+            expression_calibration: *self.expression_calibration.read().await,
+            ..self.config.clone()
+        }
+    }
+
+    /// Current calibration as a profile named `name`
+    pub async fn calibration_profile(&self, name: String) -> CalibrationProfile {
+        CalibrationProfile {
+            name,
+            eyes: *self.eye_calibration.read().await,
+            mouth: *self.mouth_calibration.read().await,
+            expressions: *self.expression_calibration.read().await,
+        }
+    }
+
+    /// Replace the whole calibration with a profile's
+    pub async fn apply_calibration_profile(&self, profile: &CalibrationProfile) {
+        *self.eye_calibration.write().await = profile.eyes;
+        *self.mouth_calibration.write().await = profile.mouth;
+        *self.expression_calibration.write().await = profile.expressions;
+    }
+
+    /// Start recording the range of one expression, replacing any running capture
+    pub async fn begin_range_calibration(&self, feature: ExpressionFeature) {
+        *self.range_capture.write().await = Some(RangeCapture::new(feature));
+    }
+
+    /// Finish the running range capture and use the recorded range from now on
+    pub async fn end_range_calibration(&self) -> Result<(ExpressionFeature, ValueRange), PluginError> {
+        let capture = self.range_capture.write().await.take().ok_or_else(|| {
+            PluginError::CalibrationError("Range calibration has not been started".to_string())
+        })?;
+        let feature = capture.feature();
+        let range = capture.finish()?;
+        self.expression_calibration.write().await.set_range(feature, range);
+        Ok((feature, range))
+    }
+
+    /// Replace the eye calibration used for subsequent frames
+    pub async fn set_eye_calibration(&self, calibration: EyeCalibration) {
+        *self.eye_calibration.write().await = calibration;
+    }
+
+    /// Replace the mouth calibration used for subsequent frames
+    pub async fn set_mouth_calibration(&self, calibration: MouthCalibration) {
+        *self.mouth_calibration.write().await = calibration;
+    }
+
+    /// Start a new gaze calibration, discarding the current one
+    pub async fn start_gaze_calibration(&self) -> Result<(), PluginError> {
+        if !self.config.enable_gaze_tracking {
+            return Err(PluginError::InvalidConfiguration(
+                "Gaze calibration requires gaze tracking to be enabled".to_string()
+            ));
+        }
+        self.gaze_mapper.write().await.start_calibration();
+        Ok(())
+    }
+
+    /// Pair the current gaze with a normalized screen point
+    ///
+    /// Returns whether the calibration is complete.
+    pub async fn add_gaze_calibration_point(&self, screen_x: f32, screen_y: f32) -> Result<bool, PluginError> {
+        self.gaze_mapper.write().await.add_point(screen_x, screen_y)
+    }
+
+    /// Get a snapshot of the tracking statistics
+    pub async fn get_stats(&self) -> TrackingStats {
+        let mut stats = self.stats.read().await.clone();
+        stats.queue_drops = self.queue_drops.load(Ordering::Relaxed);
+        stats.frames_dropped = self.frames_dropped.load(Ordering::Relaxed) + stats.queue_drops;
+        stats.queue_depth = self.frame_queue.as_ref().map_or(0, |queue| queue.depth() as u32);
+        stats.buffer_pool_hit_rate = FRAME_BUFFERS.hit_rate();
+        stats.window = self.stats_window.write().await.snapshot(Instant::now());
+        stats
+    }
+
+    /// Cut an upright, eye-aligned `size` x `size` RGB crop of a face
+    /// tracked in the last processed frame out of `frame`
+    ///
+    /// `frame` is normally the frame that was just processed; the face's
+    /// landmarks from that frame position the crop.
+    pub async fn extract_aligned_face(&self, frame: CameraFrame, face_id: u32, size: u32) -> Result<Vec<u8>, PluginError> {
+        let landmarks = self
+            .last_faces
+            .read()
+            .await
+            .iter()
+            .find(|face| face.id == face_id)
+            .ok_or_else(|| PluginError::ProcessingError(format!("Face {} was not tracked in the last frame", face_id)))?
+            .landmarks
+            .clone()
+            .ok_or_else(|| PluginError::ProcessingError(format!("Face {} has no landmarks", face_id)))?;
+
+        let image = self.convert_frame_to_image(frame)?.into_rgb8();
+        Ok(alignment::align_face(&image, &landmarks, size)?.into_raw())
+    }
+
+    /// JPEG thumbnail of a tracked face, no larger than `max_size` pixels on
+    /// either side
+    ///
+    /// Thumbnails are refreshed a few times a second from the processed
+    /// frames, upright and with a margin around the face.
+    pub async fn face_thumbnail(&self, face_id: u32, max_size: u32) -> Result<Vec<u8>, PluginError> {
+        self.thumbnails.read().await.encode(face_id, max_size)
+    }
+
+    /// Take the device's thermal and battery state into account, returning
+    /// the resulting quality step
+    pub fn report_device_state(&self, thermal: ThermalState, battery: BatteryState) -> QualityStep {
+        self.adaptive.lock().unwrap().report(thermal, battery, Instant::now())
+    }
+
+    /// Person matte of `frame`, segmented right away
+    ///
+    /// Fails if segmentation is not configured or the model cannot be
+    /// loaded. Live tracking state is left untouched.
+    pub async fn segment_frame(&self, frame: CameraFrame) -> Result<SegmentationMatte, PluginError> {
+        let segmenter = self.segmenter().cloned().ok_or_else(|| {
+            PluginError::InvalidConfiguration("Person segmentation is not available; set `segmentation`".to_string())
+        })?;
+        let full_resolution = self.config.segmentation.as_ref().is_some_and(|config| config.full_resolution);
+        let orientation = FrameOrientation::new(frame.rotation, self.config.mirror_input)?;
+        let (timestamp, width, height) = (frame.timestamp, frame.width, frame.height);
+        let rgb = orientation.apply(self.convert_frame_to_image(frame)?).into_rgb8();
+
+        let matte = tokio::task::spawn_blocking(move || segmenter.segment(&rgb))
+            .await
+            .map_err(|e| PluginError::ThreadingError(format!("Segmentation task failed: {}", e)))??;
+        Ok(SegmentationMatte::from_upright(matte, &orientation, timestamp, full_resolution.then_some((width, height))))
+    }
+
+    /// Person matte of the most recently segmented frame, if segmentation
+    /// is configured
+    pub async fn segmentation_matte(&self) -> Option<SegmentationMatte> {
+        self.matte.read().await.clone()
+    }
+
+    /// Label the tracked face `face_id`, or remove its label with `None`
+    ///
+    /// The face keeps the label until its track ends; with face embeddings
+    /// enabled, a face that returns later gets it back.
+    pub async fn set_face_label(&self, face_id: u32, label: Option<String>) -> Result<(), PluginError> {
+        if self.associator.write().await.set_label(face_id, label) {
+            Ok(())
+        } else {
+            Err(PluginError::ProcessingError(format!("Face {} is not tracked", face_id)))
+        }
+    }
+
+    /// Integrate a gyroscope and accelerometer reading for IMU fusion
+    pub fn push_imu_sample(&self, sample: ImuSample) {
+        self.imu.lock().unwrap().push(sample);
+    }
+
+    /// Quality step in effect for the next frame
+    pub fn quality_step(&self) -> QualityStep {
+        self.adaptive.lock().unwrap().step(Instant::now())
+    }
+
+    /// Clear the cumulative and windowed statistics
+    pub async fn reset_stats(&self) {
+        *self.stats.write().await = TrackingStats::default();
+        self.stats_window.write().await.reset();
+        self.frames_dropped.store(0, Ordering::Relaxed);
+        self.queue_drops.store(0, Ordering::Relaxed);
+    }
+
     /// Get current tracker status
     pub async fn get_status(&self) -> TrackerStatus {
-        let stats = self.stats.read().await;
         let frames_processed = self.frames_processed.load(Ordering::Relaxed);
         
         // Calculate average FPS
@@ -177,225 +1208,46 @@ impl FaceTracker {
             0.0
         };
 
+        let errors = self.errors.lock().unwrap();
         TrackerStatus {
             is_initialized: true,
             is_running: self.is_running.load(Ordering::Relaxed),
             frames_processed,
             average_fps,
-            last_error: None, // TODO: Implement error tracking
+            last_error: errors.last().map(|e| e.message.clone()),
+            recent_errors: errors.recent(),
+            acceleration_backend: self.backend.acceleration(),
+            active_model: Some(self.backend.active_model()),
         }
     }
 
-    /// Convert camera frame to image format that openseeface-rs expects
-    fn convert_frame_to_image(&self, frame: &CameraFrame) -> Result<DynamicImage, PluginError> {
-        let rgb_image = match frame.format {
-            ImageFormat::RGB => {
-                RgbImage::from_raw(frame.width, frame.height, frame.image_data.clone())
-                    .ok_or_else(|| PluginError::ImageConversion("Failed to create RGB image".to_string()))?
-            }
-            ImageFormat::RGBA => {
-                // Convert RGBA to RGB
-                let rgba_image = image::RgbaImage::from_raw(frame.width, frame.height, frame.image_data.clone())
-                    .ok_or_else(|| PluginError::ImageConversion("Failed to create RGBA image".to_string()))?;
-                
-                let rgb_data: Vec<u8> = rgba_image
-                    .pixels()
-                    .flat_map(|p| [p[0], p[1], p[2]])
-                    .collect();
-                
-                RgbImage::from_raw(frame.width, frame.height, rgb_data)
-                    .ok_or_else(|| PluginError::ImageConversion("Failed to convert RGBA to RGB".to_string()))?
-            }
-            ImageFormat::YUV420 => {
-                // Convert YUV420 to RGB
-                let rgb_data = self.yuv420_to_rgb(&frame.image_data, frame.width, frame.height)?;
-                RgbImage::from_raw(frame.width, frame.height, rgb_data)
-                    .ok_or_else(|| PluginError::ImageConversion("Failed to create RGB from YUV420".to_string()))?
-            }
-            ImageFormat::NV21 => {
-                // Convert NV21 to RGB (similar to YUV420 but with different UV layout)
-                let rgb_data = self.nv21_to_rgb(&frame.image_data, frame.width, frame.height)?;
-                RgbImage::from_raw(frame.width, frame.height, rgb_data)
-                    .ok_or_else(|| PluginError::ImageConversion("Failed to create RGB from NV21".to_string()))?
-            }
-            ImageFormat::BGRA => {
-                // Convert BGRA to RGB
-                let bgra_image = image::RgbaImage::from_raw(frame.width, frame.height, frame.image_data.clone())
-                    .ok_or_else(|| PluginError::ImageConversion("Failed to create BGRA image".to_string()))?;
-                
-                let rgb_data: Vec<u8> = bgra_image
-                    .pixels()
-                    .flat_map(|p| [p[2], p[1], p[0]]) // Swap B and R channels
-                    .collect();
-                
-                RgbImage::from_raw(frame.width, frame.height, rgb_data)
-                    .ok_or_else(|| PluginError::ImageConversion("Failed to convert BGRA to RGB".to_string()))?
-            }
-        };
-
-        Ok(DynamicImage::ImageRgb8(rgb_image))
-    }
-
-    /// Convert YUV420 to RGB (standard conversion)
-    fn yuv420_to_rgb(&self, yuv_data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
-        let y_size = (width * height) as usize;
-        let uv_size = y_size / 4;
-        
-        if yuv_data.len() < y_size + 2 * uv_size {
-            return Err(PluginError::ImageConversion("Invalid YUV420 data size".to_string()));
-        }
-
-        let mut rgb_data = Vec::with_capacity(y_size * 3);
-        
-        for y in 0..height {
-            for x in 0..width {
-                let y_index = (y * width + x) as usize;
-                let uv_index = ((y / 2) * (width / 2) + (x / 2)) as usize;
-                
-                let y_val = yuv_data[y_index] as f32;
-                let u_val = yuv_data[y_size + uv_index] as f32 - 128.0;
-                let v_val = yuv_data[y_size + uv_size + uv_index] as f32 - 128.0;
-                
-                // YUV to RGB conversion using standard coefficients
-                let r = (y_val + 1.402 * v_val).clamp(0.0, 255.0) as u8;
-                let g = (y_val - 0.344 * u_val - 0.714 * v_val).clamp(0.0, 255.0) as u8;
-                let b = (y_val + 1.772 * u_val).clamp(0.0, 255.0) as u8;
-                
-                rgb_data.extend_from_slice(&[r, g, b]);
-            }
-        }
-        
-        Ok(rgb_data)
+    /// Up to `limit` of the latest errors with the stage and frame they
+    /// occurred on, oldest first
+    pub fn error_history(&self, limit: usize) -> Vec<ErrorHistoryEntry> {
+        self.errors.lock().unwrap().history(limit)
     }
 
-    /// Convert NV21 to RGB (Android camera format)
-    fn nv21_to_rgb(&self, nv21_data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PluginError> {
-        let y_size = (width * height) as usize;
-        let uv_size = y_size / 2;
-        
-        if nv21_data.len() < y_size + uv_size {
-            return Err(PluginError::ImageConversion("Invalid NV21 data size".to_string()));
-        }
-
-        let mut rgb_data = Vec::with_capacity(y_size * 3);
-        
-        for y in 0..height {
-            for x in 0..width {
-                let y_index = (y * width + x) as usize;
-                let uv_index = y_size + ((y / 2) * width + (x & !1)) as usize;
-                
-                let y_val = nv21_data[y_index] as f32;
-                let v_val = nv21_data[uv_index] as f32 - 128.0;     // V comes first in NV21
-                let u_val = nv21_data[uv_index + 1] as f32 - 128.0; // U comes second
-                
-                // YUV to RGB conversion
-                let r = (y_val + 1.402 * v_val).clamp(0.0, 255.0) as u8;
-                let g = (y_val - 0.344 * u_val - 0.714 * v_val).clamp(0.0, 255.0) as u8;
-                let b = (y_val + 1.772 * u_val).clamp(0.0, 255.0) as u8;
-                
-                rgb_data.extend_from_slice(&[r, g, b]);
-            }
-        }
-        
-        Ok(rgb_data)
-    }
-
-    /// Convert detected faces from OpenSeeFace format to our format
-    async fn convert_detected_faces(
-        &self,
-        tracker: &OpenSeeFaceTracker,
-        timestamp: i64,
-    ) -> Result<Vec<Face>, PluginError> {
-        let mut faces = Vec::new();
+    /// Convert camera frame to image format that openseeface-rs expects
+    ///
+    /// RGB frames are used without copying; other formats are converted into
+    /// a pooled buffer and the frame data is returned to the pool.
+    fn convert_frame_to_image(&self, frame: CameraFrame) -> Result<DynamicImage, PluginError> {
+        let rgb_data = if frame.format == ImageFormat::RGB {
+            frame.image_data
+        } else {
+            let rgb_data = color::to_rgb(&frame.image_data, frame.width, frame.height, frame.format)?;
+            FRAME_BUFFERS.release(frame.image_data);
+            rgb_data
+        };
         
-        // Get faces from openseeface-rs tracker
-        for (id, osf_face) in tracker.faces().iter().enumerate() {
-            let bounding_box = BoundingBox {
-                x: osf_face.bbox.x,
-                y: osf_face.bbox.y,
-                width: osf_face.bbox.width,
-                height: osf_face.bbox.height,
-            };
+        let rgb_image = RgbImage::from_raw(frame.width, frame.height, rgb_data)
+            .ok_or_else(|| PluginError::ImageConversion(format!("Failed to convert {:?} to RGB", frame.format)))?;
 
-            // Convert landmarks if enabled and available
-            let landmarks = if self.config.enable_landmarks && !osf_face.landmarks.is_empty() {
-                let points: Vec<Point2D> = osf_face.landmarks
-                    .iter()
-                    .map(|lm| Point2D { x: lm.x, y: lm.y })
-                    .collect();
-                
-                // openseeface-rs provides confidence per face, not per landmark
-                let confidences = vec![osf_face.confidence; points.len()];
-                
-                Some(FacialLandmarks { points, confidences })
-            } else {
-                None
-            };
-
-            // Convert pose if enabled and available
-            let pose = if self.config.enable_pose_estimation && osf_face.pose.is_some() {
-                let osf_pose = osf_face.pose.as_ref().unwrap();
-                Some(HeadPose {
-                    pitch: osf_pose.rotation.x,
-                    yaw: osf_pose.rotation.y,
-                    roll: osf_pose.rotation.z,
-                    translation: Point3D {
-                        x: osf_pose.translation.x,
-                        y: osf_pose.translation.y,
-                        z: osf_pose.translation.z,
-                    },
-                    confidence: osf_pose.confidence,
-                })
-            } else {
-                None
-            };
-
-            // Eye gaze tracking (if supported by openseeface-rs)
-            let gaze = if self.config.enable_gaze_tracking {
-                // Check if openseeface-rs provides gaze data
-                if let Some(osf_gaze) = &osf_face.gaze {
-                    Some(EyeGaze {
-                        left_eye_direction: Point3D {
-                            x: osf_gaze.left_eye.x,
-                            y: osf_gaze.left_eye.y,
-                            z: osf_gaze.left_eye.z,
-                        },
-                        right_eye_direction: Point3D {
-                            x: osf_gaze.right_eye.x,
-                            y: osf_gaze.right_eye.y,
-                            z: osf_gaze.right_eye.z,
-                        },
-                        combined_direction: Point3D {
-                            x: (osf_gaze.left_eye.x + osf_gaze.right_eye.x) / 2.0,
-                            y: (osf_gaze.left_eye.y + osf_gaze.right_eye.y) / 2.0,
-                            z: (osf_gaze.left_eye.z + osf_gaze.right_eye.z) / 2.0,
-                        },
-                        confidence: osf_gaze.confidence,
-                    })
-                } else {
-                    // Fallback: estimate gaze from eye landmarks if available
-                    None
-                }
-            } else {
-                None
-            };
-
-            faces.push(Face {
-                id: id as u32,
-                bounding_box,
-                confidence: osf_face.confidence,
-                landmarks,
-                pose,
-                gaze,
-                timestamp,
-            });
-        }
-
-        Ok(faces)
+        Ok(DynamicImage::ImageRgb8(rgb_image))
     }
 
     /// Update tracking statistics
-    async fn update_stats(&self, faces: &[Face], processing_times: ProcessingTimes) {
+    async fn update_stats(&self, faces: &[Face], processing_times: ProcessingTimes, scene: SceneConditions) {
         let mut stats = self.stats.write().await;
         
         stats.total_faces_detected += faces.len() as u64;
@@ -407,10 +1259,13 @@ impl FaceTracker {
         }
         
         stats.processing_times = processing_times;
+        stats.scene = Some(scene);
         
         // Update last process time
+        let now = Instant::now();
+        self.stats_window.write().await.record(now, faces, processing_times);
         let mut last_time = self.last_process_time.write().await;
-        *last_time = Instant::now();
+        *last_time = now;
     }
 }
 
@@ -436,23 +1291,4 @@ mod tests {
             assert!(!status.is_running);
         }
     }
-
-    #[test]
-    fn test_yuv420_conversion() {
-        let tracker_config = TrackerConfig::default();
-        if let Ok(tracker) = FaceTracker::new(tracker_config) {
-            // Test with minimal valid YUV420 data
-            let width = 4;
-            let height = 4;
-            let y_size = (width * height) as usize;
-            let uv_size = y_size / 4;
-            let mut yuv_data = vec![128u8; y_size + 2 * uv_size]; // Gray image
-            
-            let result = tracker.yuv420_to_rgb(&yuv_data, width, height);
-            assert!(result.is_ok());
-            
-            let rgb_data = result.unwrap();
-            assert_eq!(rgb_data.len(), (width * height * 3) as usize);
-        }
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,253 @@
+//! gRPC tracking service
+//!
+//! Desktop and server deployments can run the tracker as a standalone daemon
+//! instead of inside the Flutter app. The service defined in
+//! `proto/tracking.proto` tracks single frames or frame streams and manages
+//! the tracker configuration; the `tracking-daemon` binary serves it.
+//!
+//! Only built with the `grpc` cargo feature, which needs `protoc` at build
+//! time.
+
+pub mod proto {
+    tonic::include_proto!("vtuber.tracking.v1");
+}
+
+use crate::api::{self, TrackerConfig};
+use crate::error::PluginError;
+use crate::face_tracking::tracker::FaceTracker;
+use crate::models::*;
+use crate::protocols::arkit_blendshapes;
+use futures::{Stream, StreamExt};
+use log::info;
+use proto::face_tracking_server::{FaceTracking, FaceTrackingServer};
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Default address of the daemon
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:50051";
+
+/// Largest request accepted, enough for an uncompressed 4K RGB frame
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Results buffered per `TrackFaces` stream
+const STREAM_QUEUE_CAPACITY: usize = 4;
+
+/// Serve the tracking service on `address` until the server fails
+pub async fn serve(address: SocketAddr, config: TrackerConfig) -> Result<(), PluginError> {
+    let service = TrackingService::new(config)?;
+    info!("gRPC tracking service listening on {}", address);
+    tonic::transport::Server::builder()
+        .add_service(
+            FaceTrackingServer::new(service)
+                .max_decoding_message_size(MAX_MESSAGE_BYTES)
+                .max_encoding_message_size(MAX_MESSAGE_BYTES),
+        )
+        .serve(address)
+        .await
+        .map_err(|e| PluginError::NetworkError(format!("gRPC server failed: {}", e)))
+}
+
+/// Tracking service with its own tracker, replaced when the configuration
+/// changes
+pub struct TrackingService {
+    tracker: RwLock<Arc<FaceTracker>>,
+}
+
+impl TrackingService {
+    pub fn new(config: TrackerConfig) -> Result<Self, PluginError> {
+        Ok(Self { tracker: RwLock::new(Arc::new(create_tracker(config)?)) })
+    }
+
+    async fn tracker(&self) -> Arc<FaceTracker> {
+        self.tracker.read().await.clone()
+    }
+
+    /// Replace the tracker, keeping the current one if `config` is invalid
+    async fn reconfigure(&self, config: TrackerConfig) -> Result<proto::TrackerConfig, Status> {
+        let tracker = tokio::task::spawn_blocking(move || create_tracker(config))
+            .await
+            .map_err(|e| PluginError::ThreadingError(e.to_string()))??;
+        let config = config_message(tracker.config())?;
+        *self.tracker.write().await = Arc::new(tracker);
+        info!("gRPC tracking service reconfigured");
+        Ok(config)
+    }
+}
+
+fn create_tracker(config: TrackerConfig) -> Result<FaceTracker, PluginError> {
+    api::check_config(&config).into_result()?;
+    FaceTracker::new(config)
+}
+
+async fn track(tracker: &FaceTracker, frame: proto::Frame) -> Result<proto::FrameResult, Status> {
+    let frame = camera_frame(frame)?;
+    api::check_frame_data(&frame)?;
+    let timestamp = frame.timestamp;
+    let faces = tracker.process_frame(frame).await?;
+    Ok(proto::FrameResult { timestamp, faces: faces.iter().map(face_message).collect() })
+}
+
+#[tonic::async_trait]
+impl FaceTracking for TrackingService {
+    async fn process_frame(&self, request: Request<proto::Frame>) -> Result<Response<proto::FrameResult>, Status> {
+        let tracker = self.tracker().await;
+        Ok(Response::new(track(&tracker, request.into_inner()).await?))
+    }
+
+    type TrackFacesStream = Pin<Box<dyn Stream<Item = Result<proto::FrameResult, Status>> + Send>>;
+
+    async fn track_faces(
+        &self,
+        request: Request<Streaming<proto::Frame>>,
+    ) -> Result<Response<Self::TrackFacesStream>, Status> {
+        let tracker = self.tracker().await;
+        let mut frames = request.into_inner();
+        let (results, receiver) = mpsc::channel(STREAM_QUEUE_CAPACITY);
+
+        // A stream keeps the tracker it started with, so reconfiguring does
+        // not reset the face IDs of running streams
+        tokio::spawn(async move {
+            while let Some(frame) = frames.next().await {
+                let result = match frame {
+                    Ok(frame) => track(&tracker, frame).await,
+                    Err(status) => Err(status),
+                };
+                let failed = result.is_err();
+                if results.send(result).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    async fn get_config(
+        &self,
+        _request: Request<proto::GetConfigRequest>,
+    ) -> Result<Response<proto::TrackerConfig>, Status> {
+        let config = self.tracker().await.current_config().await;
+        Ok(Response::new(config_message(&config)?))
+    }
+
+    async fn set_config(
+        &self,
+        request: Request<proto::TrackerConfig>,
+    ) -> Result<Response<proto::TrackerConfig>, Status> {
+        let config = serde_json::from_str(&request.into_inner().json).map_err(invalid_config)?;
+        Ok(Response::new(self.reconfigure(config).await?))
+    }
+
+    async fn update_config(
+        &self,
+        request: Request<proto::TrackerConfig>,
+    ) -> Result<Response<proto::TrackerConfig>, Status> {
+        let changes: Value = serde_json::from_str(&request.into_inner().json).map_err(invalid_config)?;
+        let Value::Object(changes) = changes else {
+            return Err(Status::invalid_argument("Configuration changes must be a JSON object"));
+        };
+        let current = self.tracker().await.current_config().await;
+        let mut config = serde_json::to_value(current).map_err(|e| Status::internal(e.to_string()))?;
+        if let Value::Object(fields) = &mut config {
+            fields.extend(changes);
+        }
+        let config = serde_json::from_value(config).map_err(invalid_config)?;
+        Ok(Response::new(self.reconfigure(config).await?))
+    }
+}
+
+impl From<PluginError> for Status {
+    fn from(error: PluginError) -> Self {
+        let message = error.to_string();
+        match error {
+            PluginError::InvalidConfiguration(_)
+            | PluginError::ImageConversion(_)
+            | PluginError::UnsupportedImageFormat(_) => Status::invalid_argument(message),
+            PluginError::TrackerNotInitialized => Status::failed_precondition(message),
+            PluginError::Timeout { .. } => Status::deadline_exceeded(message),
+            PluginError::NetworkError(_) => Status::unavailable(message),
+            _ => Status::internal(message),
+        }
+    }
+}
+
+fn invalid_config(error: serde_json::Error) -> Status {
+    Status::invalid_argument(format!("Invalid configuration JSON: {}", error))
+}
+
+fn config_message(config: &TrackerConfig) -> Result<proto::TrackerConfig, Status> {
+    let json = serde_json::to_string(config).map_err(|e| Status::internal(e.to_string()))?;
+    Ok(proto::TrackerConfig { json })
+}
+
+fn camera_frame(frame: proto::Frame) -> Result<CameraFrame, Status> {
+    let format = match proto::ImageFormat::try_from(frame.format) {
+        Ok(proto::ImageFormat::Rgb) => ImageFormat::RGB,
+        Ok(proto::ImageFormat::Rgba) => ImageFormat::RGBA,
+        Ok(proto::ImageFormat::Yuv420) => ImageFormat::YUV420,
+        Ok(proto::ImageFormat::Nv21) => ImageFormat::NV21,
+        Ok(proto::ImageFormat::Bgra) => ImageFormat::BGRA,
+        Ok(proto::ImageFormat::Nv12) => ImageFormat::NV12,
+        Ok(proto::ImageFormat::Yuy2) => ImageFormat::YUY2,
+        Ok(proto::ImageFormat::Gray8) => ImageFormat::GRAY8,
+        Err(_) => return Err(Status::invalid_argument(format!("Unknown image format {}", frame.format))),
+    };
+    Ok(CameraFrame {
+        image_data: frame.image_data,
+        width: frame.width,
+        height: frame.height,
+        format,
+        timestamp: frame.timestamp,
+        rotation: frame.rotation,
+        intrinsics: None,
+    })
+}
+
+fn point3(point: Point3D) -> proto::Point3D {
+    proto::Point3D { x: point.x, y: point.y, z: point.z }
+}
+
+fn face_message(face: &Face) -> proto::Face {
+    let bounding_box = face.bounding_box;
+    proto::Face {
+        id: face.id,
+        bounding_box: Some(proto::BoundingBox {
+            x: bounding_box.x,
+            y: bounding_box.y,
+            width: bounding_box.width,
+            height: bounding_box.height,
+        }),
+        confidence: face.confidence,
+        landmarks: face
+            .landmarks
+            .iter()
+            .flat_map(|landmarks| &landmarks.points)
+            .map(|point| proto::Point2D { x: point.x, y: point.y })
+            .collect(),
+        pose: face.pose.map(|pose| proto::HeadPose {
+            pitch: pose.pitch,
+            yaw: pose.yaw,
+            roll: pose.roll,
+            rotation_quaternion: pose.rotation_quaternion.to_array().to_vec(),
+            translation: Some(point3(pose.translation)),
+            confidence: pose.confidence,
+        }),
+        gaze: face.gaze.map(|gaze| proto::EyeGaze {
+            left_eye_direction: Some(point3(gaze.left_eye_direction)),
+            right_eye_direction: Some(point3(gaze.right_eye_direction)),
+            combined_direction: Some(point3(gaze.combined_direction)),
+            confidence: gaze.confidence,
+        }),
+        blendshapes: arkit_blendshapes(face)
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        quality: face.quality.score,
+        label: face.label.clone().unwrap_or_default(),
+        attention_score: face.attention_score,
+    }
+}
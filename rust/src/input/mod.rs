@@ -0,0 +1,141 @@
+//! Frame sources other than the app's camera
+//!
+//! Frames pushed from Dart are the main input of the tracker; the modules
+//! here read frames from elsewhere and run them through the same pipeline.
+//! Live sources (network streams, the LAN frame receiver, native cameras)
+//! feed the running tracking stream exactly like frames pushed from Dart, so
+//! results arrive on the app's face stream and the network outputs. One
+//! live source runs at a time; starting another stops it.
+
+pub mod multi;
+pub mod network;
+pub mod remote;
+pub mod still;
+pub mod video;
+
+use crate::error::PluginError;
+use crate::models::CameraFrame;
+use crate::utils::panic;
+use flutter_rust_bridge::frb;
+use futures::future::BoxFuture;
+use lazy_static::lazy_static;
+use log::{debug, error, info};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Longest wait for the next frame of a live source before it counts as lost
+pub const DEFAULT_FRAME_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    static ref SOURCE_WORKER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// State of a live frame source
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameSourceEvent {
+    /// The first frame arrived
+    Connected { width: u32, height: u32 },
+    /// The source ended
+    Ended { frames_received: u64 },
+    /// The source stopped because of an error
+    Failed { message: String },
+}
+
+/// A live source of camera frames
+pub trait FrameSource: Send {
+    /// Where the frames come from, for logs
+    fn description(&self) -> String;
+
+    /// Wait for the next frame; `None` once the source has ended
+    fn next_frame(&mut self) -> BoxFuture<'_, Result<Option<CameraFrame>, PluginError>>;
+
+    /// Longest wait for a frame before the source counts as lost; `None`
+    /// waits forever
+    fn frame_timeout(&self) -> Option<Duration> {
+        Some(DEFAULT_FRAME_TIMEOUT)
+    }
+}
+
+/// Read frames from the source `open` resolves to into the running
+/// tracking stream
+///
+/// Events go to `send`, which returns `false` once nobody listens any more;
+/// the source then stops. Replaces any source still running.
+pub fn start_source<F, S>(open: F, send: S)
+where
+    F: Future<Output = Result<Box<dyn FrameSource>, PluginError>> + Send + 'static,
+    S: Fn(FrameSourceEvent) -> bool + Send + Sync + 'static,
+{
+    let worker = crate::runtime().spawn(async move {
+        let event = match panic::guard_async(run(open, &send)).await {
+            Ok(frames_received) => FrameSourceEvent::Ended { frames_received },
+            Err(e) => {
+                error!("Frame source failed: {}", e);
+                FrameSourceEvent::Failed { message: e.to_string() }
+            }
+        };
+        send(event);
+    });
+
+    if let Some(previous) = SOURCE_WORKER.lock().unwrap().replace(worker) {
+        previous.abort();
+    }
+}
+
+/// Stop the running source, returning whether one was running
+pub fn stop_source() -> bool {
+    match SOURCE_WORKER.lock().unwrap().take() {
+        Some(worker) => {
+            let running = !worker.is_finished();
+            worker.abort();
+            running
+        }
+        None => false,
+    }
+}
+
+/// Wait for the next frame of `source`, failing once its timeout passes
+pub(crate) async fn next_frame(source: &mut dyn FrameSource) -> Result<Option<CameraFrame>, PluginError> {
+    match source.frame_timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, source.next_frame())
+            .await
+            .map_err(|_| PluginError::Timeout { timeout_ms: timeout.as_millis() as u32 })?,
+        None => source.next_frame().await,
+    }
+}
+
+/// Feed every frame of the source to the tracker, returning the number of
+/// frames received
+async fn run(
+    open: impl Future<Output = Result<Box<dyn FrameSource>, PluginError>>,
+    send: &impl Fn(FrameSourceEvent) -> bool,
+) -> Result<u64, PluginError> {
+    let mut source = open.await?;
+    let description = source.description();
+    info!("Reading frames from {}", description);
+
+    let mut frames_received = 0;
+    loop {
+        let Some(frame) = next_frame(source.as_mut()).await? else {
+            return Ok(frames_received);
+        };
+
+        if frames_received == 0 && !send(FrameSourceEvent::Connected { width: frame.width, height: frame.height }) {
+            return Ok(0);
+        }
+        frames_received += 1;
+
+        // Live frames are dropped, or the source waits, when the tracker falls behind
+        let queue = match crate::GLOBAL_TRACKER.read().await.as_ref() {
+            Some(tracker) => tracker.stream_queue()?,
+            None => return Err(PluginError::TrackerNotInitialized),
+        };
+        let queued = queue.push_frame(frame).await?;
+        if !queued {
+            debug!("Tracker busy, dropped frame {} from {}", frames_received, description);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Multi-source tracking
+//!
+//! Streaming rigs often combine cameras, e.g. a face cam and a wide shot.
+//! Every source added here gets a tracker of its own, so face IDs, smoothing
+//! and blink state never mix between cameras, and its results go to one
+//! shared stream tagged with the source ID. These trackers do not drive the
+//! network outputs, which keep following the main tracker.
+
+use crate::api::TrackerConfig;
+use crate::camera::{self, CameraCaptureConfig};
+use crate::error::PluginError;
+use crate::face_tracking::tracker::FaceTracker;
+use crate::input::remote::{FrameReceiver, FrameReceiverConfig};
+use crate::input::{self, network, FrameSource};
+use crate::models::*;
+use crate::utils::panic;
+use flutter_rust_bridge::frb;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+type UpdateSender = Arc<dyn Fn(SourceUpdate) -> bool + Send + Sync>;
+
+lazy_static! {
+    static ref OUTPUT: Mutex<Option<UpdateSender>> = Mutex::new(None);
+    static ref SOURCES: Mutex<HashMap<String, JoinHandle<()>>> = Mutex::new(HashMap::new());
+}
+
+/// Where a tracked source reads its frames from
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone)]
+pub enum SourceSpec {
+    /// An `http(s)://` MJPEG or `rtsp://` stream
+    Network { url: String },
+    /// Frames sent by another device's frame sender
+    Receiver { config: FrameReceiverConfig },
+    /// A native camera (requires the `camera` feature)
+    Camera { config: CameraCaptureConfig },
+}
+
+/// Update of one tracked source
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceUpdate {
+    /// The first frame of the source arrived
+    Connected { source_id: String, width: u32, height: u32 },
+    /// Faces tracked in one frame of the source
+    Faces { source_id: String, timestamp: i64, faces: Vec<Face> },
+    /// The source ended
+    Ended { source_id: String, frames_processed: u64 },
+    /// The source stopped because of an error
+    Failed { source_id: String, message: String },
+}
+
+/// Send the updates of every source to `send`, replacing the previous
+/// receiver
+///
+/// Sources stop once `send` reports the app's stream closed.
+pub fn set_output<S>(send: S)
+where
+    S: Fn(SourceUpdate) -> bool + Send + Sync + 'static,
+{
+    *OUTPUT.lock().unwrap() = Some(Arc::new(send));
+}
+
+/// Stop every source and drop the output, returning the number of sources
+/// that were running
+pub fn close() -> usize {
+    *OUTPUT.lock().unwrap() = None;
+    let mut sources = SOURCES.lock().unwrap();
+    let running = sources.values().filter(|worker| !worker.is_finished()).count();
+    for (_, worker) in sources.drain() {
+        worker.abort();
+    }
+    running
+}
+
+/// Start tracking `spec` with a tracker built from `config`
+pub fn add(source_id: String, spec: SourceSpec, config: TrackerConfig) -> Result<(), PluginError> {
+    let send = OUTPUT
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| PluginError::ProcessingError("The multi-source stream is not open".to_string()))?;
+    // Loading the models takes a while; the source list stays available
+    let tracker = FaceTracker::new(config)?.without_output();
+    let mut sources = SOURCES.lock().unwrap();
+    if sources.get(&source_id).is_some_and(|worker| !worker.is_finished()) {
+        return Err(PluginError::InvalidConfiguration(format!("Source {} is already running", source_id)));
+    }
+
+    let id = source_id.clone();
+    let worker = crate::runtime().spawn(async move {
+        let update = match panic::guard_async(run(&id, spec, tracker, send.as_ref())).await {
+            Ok(frames_processed) => SourceUpdate::Ended { source_id: id, frames_processed },
+            Err(e) => {
+                error!("Source {} failed: {}", id, e);
+                SourceUpdate::Failed { source_id: id, message: e.to_string() }
+            }
+        };
+        send(update);
+    });
+    info!("Tracking source {}", source_id);
+    sources.insert(source_id, worker);
+    Ok(())
+}
+
+/// Stop tracking a source, returning whether it was running
+pub fn remove(source_id: &str) -> bool {
+    match SOURCES.lock().unwrap().remove(source_id) {
+        Some(worker) => {
+            let running = !worker.is_finished();
+            worker.abort();
+            running
+        }
+        None => false,
+    }
+}
+
+/// IDs of the running sources, sorted
+pub fn source_ids() -> Vec<String> {
+    let mut ids: Vec<String> = SOURCES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, worker)| !worker.is_finished())
+        .map(|(id, _)| id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+async fn open(spec: SourceSpec) -> Result<Box<dyn FrameSource>, PluginError> {
+    match spec {
+        SourceSpec::Network { url } => network::open(&url).await,
+        SourceSpec::Receiver { config } => Ok(Box::new(FrameReceiver::bind(config)?)),
+        SourceSpec::Camera { config } => camera::open(&config),
+    }
+}
+
+/// Track every frame of one source, returning the number of frames processed
+///
+/// Reading and tracking run side by side. Frames that arrive while the
+/// tracker is busy are dropped, so a slow tracker never builds up latency.
+async fn run(
+    source_id: &str,
+    spec: SourceSpec,
+    tracker: FaceTracker,
+    send: &(dyn Fn(SourceUpdate) -> bool + Send + Sync),
+) -> Result<u64, PluginError> {
+    let mut source = open(spec).await?;
+    info!("Source {} reads from {}", source_id, source.description());
+    let (frames, mut queue) = mpsc::channel::<CameraFrame>(1);
+
+    let reader = async move {
+        let mut connected = false;
+        while let Some(frame) = input::next_frame(source.as_mut()).await? {
+            if !connected {
+                connected = true;
+                let (width, height) = (frame.width, frame.height);
+                if !send(SourceUpdate::Connected { source_id: source_id.to_string(), width, height }) {
+                    break;
+                }
+            }
+            if frames.try_send(frame).is_err() && frames.is_closed() {
+                break;
+            }
+        }
+        Ok::<_, PluginError>(())
+    };
+
+    let processor = async {
+        let mut frames_processed = 0;
+        while let Some(frame) = queue.recv().await {
+            let timestamp = frame.timestamp;
+            let faces = tracker.process_frame(frame).await.unwrap_or_else(|e| {
+                warn!("Failed to process frame from source {}: {}", source_id, e);
+                Vec::new()
+            });
+            frames_processed += 1;
+            if !send(SourceUpdate::Faces { source_id: source_id.to_string(), timestamp, faces }) {
+                break;
+            }
+        }
+        frames_processed
+    };
+    tokio::pin!(processor);
+
+    tokio::select! {
+        // The source ended; track the frame still queued
+        result = reader => {
+            result?;
+            Ok(processor.await)
+        }
+        frames_processed = &mut processor => Ok(frames_processed),
+    }
+}
@@ -0,0 +1,310 @@
+//! Network video sources
+//!
+//! Desktop builds can track faces from IP cameras instead of frames pushed
+//! by the app. `http://` URLs are read as MJPEG streams, which most IP
+//! cameras and webcam streaming apps serve; `rtsp://` URLs (IP cameras, OBS
+//! with an RTSP server plugin) are decoded by the `ffmpeg` command line
+//! tool, which must be on the `PATH`.
+
+use crate::error::PluginError;
+use crate::input::video::{self, FfmpegInput, VideoInfo};
+use crate::input::{self, still, FrameSource, FrameSourceEvent};
+use crate::models::*;
+use futures::future::{BoxFuture, FutureExt};
+use log::debug;
+use tokio::sync::mpsc;
+
+/// Largest MJPEG frame accepted; bigger frames are discarded
+const MAX_JPEG_BYTES: usize = 16 * 1024 * 1024;
+
+/// How a URL is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceKind {
+    Mjpeg,
+    Rtsp,
+}
+
+fn source_kind(url: &str) -> Result<SourceKind, PluginError> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+    match scheme.as_deref() {
+        Some("http" | "https") => Ok(SourceKind::Mjpeg),
+        Some("rtsp" | "rtsps") => Ok(SourceKind::Rtsp),
+        _ => Err(PluginError::InvalidConfiguration(format!(
+            "Unsupported network source {}: expected an http(s):// MJPEG or rtsp:// URL",
+            url
+        ))),
+    }
+}
+
+/// Open the source at `url`
+pub async fn open(url: &str) -> Result<Box<dyn FrameSource>, PluginError> {
+    match source_kind(url)? {
+        SourceKind::Mjpeg => Ok(Box::new(MjpegSource::connect(url).await?)),
+        SourceKind::Rtsp => Ok(Box::new(RtspSource::connect(url).await?)),
+    }
+}
+
+/// Read frames from `url` into the running tracking stream, see
+/// [`input::start_source`]
+pub fn start<S>(url: String, send: S) -> Result<(), PluginError>
+where
+    S: Fn(FrameSourceEvent) -> bool + Send + Sync + 'static,
+{
+    source_kind(&url)?;
+    input::start_source(async move { open(&url).await }, send);
+    Ok(())
+}
+
+/// Motion JPEG over HTTP: a never-ending response of JPEG images, usually
+/// as `multipart/x-mixed-replace` parts
+pub struct MjpegSource {
+    url: String,
+    response: reqwest::Response,
+    parser: MjpegParser,
+}
+
+impl MjpegSource {
+    pub async fn connect(url: &str) -> Result<Self, PluginError> {
+        let response = reqwest::Client::new()
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| PluginError::NetworkError(format!("Cannot connect to {}: {}", url, e)))?;
+        Ok(Self { url: url.to_string(), response, parser: MjpegParser::default() })
+    }
+
+    async fn read_frame(&mut self) -> Result<Option<CameraFrame>, PluginError> {
+        let jpeg = loop {
+            if let Some(jpeg) = self.parser.next_image() {
+                break jpeg;
+            }
+            let chunk = self
+                .response
+                .chunk()
+                .await
+                .map_err(|e| PluginError::NetworkError(format!("Reading {} failed: {}", self.url, e)))?;
+            match chunk {
+                Some(chunk) => self.parser.push(&chunk),
+                None => return Ok(None),
+            }
+        };
+
+        let image = tokio::task::spawn_blocking(move || still::decode(&jpeg))
+            .await
+            .map_err(|e| PluginError::ThreadingError(e.to_string()))??;
+        Ok(Some(CameraFrame {
+            width: image.width(),
+            height: image.height(),
+            image_data: image.into_raw(),
+            format: ImageFormat::RGB,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            rotation: 0,
+            intrinsics: None,
+        }))
+    }
+}
+
+impl FrameSource for MjpegSource {
+    fn description(&self) -> String {
+        format!("MJPEG stream {}", self.url)
+    }
+
+    fn next_frame(&mut self) -> BoxFuture<'_, Result<Option<CameraFrame>, PluginError>> {
+        self.read_frame().boxed()
+    }
+}
+
+/// RTSP stream decoded by `ffmpeg`
+pub struct RtspSource {
+    url: String,
+    info: VideoInfo,
+    frames: mpsc::Receiver<Result<Vec<u8>, PluginError>>,
+}
+
+impl RtspSource {
+    pub async fn connect(url: &str) -> Result<Self, PluginError> {
+        let input = FfmpegInput::rtsp(url);
+        let info = {
+            let input = input.clone();
+            tokio::task::spawn_blocking(move || video::probe_input(&input))
+                .await
+                .map_err(|e| PluginError::ThreadingError(e.to_string()))??
+        };
+        let frames = video::spawn_decoder(input, info);
+        Ok(Self { url: url.to_string(), info, frames })
+    }
+}
+
+impl FrameSource for RtspSource {
+    fn description(&self) -> String {
+        format!("RTSP stream {} ({}x{})", self.url, self.info.width, self.info.height)
+    }
+
+    fn next_frame(&mut self) -> BoxFuture<'_, Result<Option<CameraFrame>, PluginError>> {
+        async move {
+            let Some(data) = self.frames.recv().await else {
+                return Ok(None);
+            };
+            Ok(Some(CameraFrame {
+                image_data: data?,
+                width: self.info.width,
+                height: self.info.height,
+                format: ImageFormat::RGB,
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                rotation: self.info.rotation,
+                intrinsics: None,
+            }))
+        }
+        .boxed()
+    }
+}
+
+/// Splits a byte stream into JPEG images
+///
+/// Multipart boundaries and part headers are skipped by looking for the
+/// start of image marker; the end of an image is found by walking its
+/// segments, so thumbnails embedded in EXIF data do not cut it short.
+#[derive(Debug, Default)]
+struct MjpegParser {
+    buffer: Vec<u8>,
+}
+
+/// Result of scanning for the end of a JPEG image
+#[derive(Debug, PartialEq)]
+enum JpegScan {
+    /// The image is complete and this many bytes long
+    Complete(usize),
+    /// More data is needed
+    Incomplete,
+    /// The data is not a valid JPEG image
+    Invalid,
+}
+
+impl MjpegParser {
+    fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Take the next complete image out of the buffer
+    fn next_image(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let Some(start) = self.buffer.windows(2).position(|pair| pair == [0xFF, 0xD8]) else {
+                // Keep a trailing 0xFF, which may begin the next marker
+                let keep = usize::from(self.buffer.last() == Some(&0xFF));
+                self.buffer.drain(..self.buffer.len() - keep);
+                return None;
+            };
+            self.buffer.drain(..start);
+
+            match scan_jpeg(&self.buffer) {
+                JpegScan::Complete(length) => return Some(self.buffer.drain(..length).collect()),
+                JpegScan::Incomplete if self.buffer.len() <= MAX_JPEG_BYTES => return None,
+                JpegScan::Incomplete | JpegScan::Invalid => {
+                    debug!("Skipping damaged MJPEG frame");
+                    self.buffer.drain(..2);
+                }
+            }
+        }
+    }
+}
+
+/// Find the end of the JPEG image at the start of `data`
+fn scan_jpeg(data: &[u8]) -> JpegScan {
+    // Skip the start of image marker
+    let mut pos = 2;
+    loop {
+        let marker = match data.get(pos..pos + 2) {
+            None => return JpegScan::Incomplete,
+            Some(&[0xFF, marker]) => marker,
+            Some(_) => return JpegScan::Invalid,
+        };
+        match marker {
+            // Fill byte before a marker
+            0xFF => pos += 1,
+            // End of image
+            0xD9 => return JpegScan::Complete(pos + 2),
+            // Markers without a payload
+            0x01 | 0xD0..=0xD7 => pos += 2,
+            _ => {
+                let length = match data.get(pos + 2..pos + 4) {
+                    None => return JpegScan::Incomplete,
+                    Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]) as usize,
+                };
+                if length < 2 {
+                    return JpegScan::Invalid;
+                }
+                pos += 2 + length;
+
+                // Entropy-coded data follows a start of scan segment and runs
+                // to the next marker that is neither a stuffed byte nor a restart
+                if marker == 0xDA {
+                    loop {
+                        let Some(offset) = data.get(pos..).and_then(|rest| rest.iter().position(|&b| b == 0xFF)) else {
+                            return JpegScan::Incomplete;
+                        };
+                        match data.get(pos + offset + 1) {
+                            None => return JpegScan::Incomplete,
+                            Some(0x00 | 0xD0..=0xD7) => pos += offset + 2,
+                            Some(_) => {
+                                pos += offset;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal JPEG byte layout: an APP1 segment holding a thumbnail with
+    /// its own end marker, then a scan with stuffed and restart bytes
+    fn jpeg(fill: u8) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        data.extend([0xFF, 0xE1, 0x00, 0x06, 0xFF, 0xD8, 0xFF, 0xD9]);
+        data.extend([0xFF, 0xDA, 0x00, 0x04, 0x01, 0x02]);
+        data.extend([fill, 0xFF, 0x00, fill, 0xFF, 0xD3, fill]);
+        data.extend([0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn test_splits_multipart_stream() {
+        let mut stream = b"--frame\r\nContent-Type: image/jpeg\r\n\r\n".to_vec();
+        stream.extend(jpeg(0x11));
+        stream.extend(b"\r\n--frame\r\nContent-Type: image/jpeg\r\n\r\n");
+        stream.extend(jpeg(0x22));
+
+        // Chunk boundaries fall anywhere, including inside markers
+        let mut parser = MjpegParser::default();
+        let mut images = Vec::new();
+        for chunk in stream.chunks(3) {
+            parser.push(chunk);
+            images.extend(std::iter::from_fn(|| parser.next_image()));
+        }
+        assert_eq!(images, vec![jpeg(0x11), jpeg(0x22)]);
+    }
+
+    #[test]
+    fn test_scan_rejects_damaged_data() {
+        let image = jpeg(0x11);
+        assert_eq!(scan_jpeg(&image), JpegScan::Complete(image.len()));
+        assert_eq!(scan_jpeg(&image[..image.len() - 1]), JpegScan::Incomplete);
+        assert_eq!(scan_jpeg(&[0xFF, 0xD8, 0x12, 0x34]), JpegScan::Invalid);
+
+        // Garbage between frames does not hide the next image
+        let mut parser = MjpegParser::default();
+        parser.push(&[0xFF, 0xD8, 0x00, 0x00]);
+        parser.push(&image);
+        assert_eq!(parser.next_image(), Some(image));
+
+        assert_eq!(source_kind("rtsp://camera.local/stream").unwrap(), SourceKind::Rtsp);
+        assert_eq!(source_kind("HTTP://192.168.1.20/video").unwrap(), SourceKind::Mjpeg);
+        assert!(source_kind("file:///tmp/video.mp4").is_err());
+    }
+}
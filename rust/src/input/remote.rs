@@ -0,0 +1,653 @@
+//! LAN frame relay ("phone camera, PC tracker")
+//!
+//! A phone running the app captures frames and sends them, JPEG compressed,
+//! to a desktop build that runs the heavy models. Both ends live here: the
+//! sender encodes camera frames and the receiver is a [`FrameSource`] that
+//! feeds them into the desktop's tracking stream.
+//!
+//! Every message starts with a fixed header (big-endian):
+//!
+//! | bytes | field |
+//! |-------|-------|
+//! | 4 | magic `VTFR` |
+//! | 1 | protocol version (1) |
+//! | 1 | reserved (0) |
+//! | 2 | frame rotation (degrees clockwise) |
+//! | 4 | frame id, increasing by one per frame |
+//! | 8 | frame timestamp (ms since epoch, sender clock) |
+//! | 2 | fragment index |
+//! | 2 | fragment count |
+//! | 4 | payload length |
+//!
+//! followed by the payload. Over TCP each message carries a whole JPEG
+//! image. Over UDP images are split into fragments that fit a datagram;
+//! a frame is dropped if a fragment is lost, which keeps latency low on a
+//! busy network.
+
+use crate::error::PluginError;
+use crate::face_tracking::color;
+use crate::input::{still, FrameSource};
+use crate::models::*;
+use crate::utils::buffer_pool::FRAME_BUFFERS;
+use flutter_rust_bridge::frb;
+use futures::future::{BoxFuture, FutureExt};
+use image::codecs::jpeg::JpegEncoder;
+use image::RgbImage;
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Port the receiver listens on by default
+pub const DEFAULT_PORT: u16 = 11580;
+
+const MAGIC: [u8; 4] = *b"VTFR";
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = 28;
+
+/// Payload bytes per UDP datagram, so datagrams fit a 1500 byte Ethernet MTU
+const UDP_PAYLOAD_BYTES: usize = 1400;
+
+/// Largest image accepted from a sender
+const MAX_IMAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Largest decoded image accepted from a sender (a 4K frame with alpha)
+const MAX_DECODED_BYTES: u64 = 3840 * 2160 * 4;
+
+/// UDP frames collected at the same time; the oldest is dropped when
+/// fragments of another frame arrive
+const MAX_PENDING_FRAMES: usize = 4;
+
+/// How often the receiver thread checks whether it is still needed
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Longest time a TCP send may block before the receiver counts as gone
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    static ref FRAME_SENDER: Mutex<Option<FrameSender>> = Mutex::new(None);
+}
+
+/// Network transport between the sender and the receiver
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameTransport {
+    /// Lowest latency; frames with a lost fragment are dropped
+    Udp,
+    /// Every frame arrives, at the cost of stalls on a lossy network
+    Tcp,
+}
+
+/// Configuration of the frame receiver
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct FrameReceiverConfig {
+    /// Port to listen on, on all network interfaces
+    pub port: u16,
+    pub transport: FrameTransport,
+}
+
+impl Default for FrameReceiverConfig {
+    fn default() -> Self {
+        Self { port: DEFAULT_PORT, transport: FrameTransport::Udp }
+    }
+}
+
+/// Configuration of the frame sender
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct FrameSenderConfig {
+    /// Receiver host name or IP address
+    pub host: String,
+    /// Receiver port
+    pub port: u16,
+    pub transport: FrameTransport,
+    /// JPEG quality (1 - 100)
+    pub jpeg_quality: u8,
+}
+
+impl Default for FrameSenderConfig {
+    fn default() -> Self {
+        Self {
+            host: "192.168.1.2".to_string(),
+            port: DEFAULT_PORT,
+            transport: FrameTransport::Udp,
+            jpeg_quality: 80,
+        }
+    }
+}
+
+/// Header of a relay message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PacketHeader {
+    rotation: u16,
+    frame_id: u32,
+    timestamp: i64,
+    fragment_index: u16,
+    fragment_count: u16,
+    payload_len: u32,
+}
+
+impl PacketHeader {
+    fn encode(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4] = VERSION;
+        bytes[6..8].copy_from_slice(&self.rotation.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.frame_id.to_be_bytes());
+        bytes[12..20].copy_from_slice(&self.timestamp.to_be_bytes());
+        bytes[20..22].copy_from_slice(&self.fragment_index.to_be_bytes());
+        bytes[22..24].copy_from_slice(&self.fragment_count.to_be_bytes());
+        bytes[24..28].copy_from_slice(&self.payload_len.to_be_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, PluginError> {
+        let invalid = |reason: &str| PluginError::NetworkError(format!("Invalid frame packet: {}", reason));
+        if bytes.len() < HEADER_SIZE || bytes[0..4] != MAGIC {
+            return Err(invalid("bad header"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid(&format!("unsupported protocol version {}", bytes[4])));
+        }
+        let header = Self {
+            rotation: u16::from_be_bytes([bytes[6], bytes[7]]),
+            frame_id: u32::from_be_bytes(bytes[8..12].try_into().unwrap()),
+            timestamp: i64::from_be_bytes(bytes[12..20].try_into().unwrap()),
+            fragment_index: u16::from_be_bytes([bytes[20], bytes[21]]),
+            fragment_count: u16::from_be_bytes([bytes[22], bytes[23]]),
+            payload_len: u32::from_be_bytes(bytes[24..28].try_into().unwrap()),
+        };
+        if header.fragment_index >= header.fragment_count {
+            return Err(invalid("fragment out of range"));
+        }
+        // Bounds what the receiver allocates for a frame before its
+        // fragments arrive
+        if header.payload_len as usize > MAX_IMAGE_BYTES
+            || header.fragment_count as usize > MAX_IMAGE_BYTES.div_ceil(UDP_PAYLOAD_BYTES)
+        {
+            return Err(invalid("image too large"));
+        }
+        Ok(header)
+    }
+}
+
+/// Split an encoded image into messages of at most `max_payload` bytes
+fn packets(frame_id: u32, timestamp: i64, rotation: u16, image: &[u8], max_payload: usize) -> Vec<Vec<u8>> {
+    let fragment_count = image.len().div_ceil(max_payload).max(1);
+    (0..fragment_count)
+        .map(|index| {
+            let payload = &image[(index * max_payload).min(image.len())..((index + 1) * max_payload).min(image.len())];
+            let header = PacketHeader {
+                rotation,
+                frame_id,
+                timestamp,
+                fragment_index: index as u16,
+                fragment_count: fragment_count as u16,
+                payload_len: payload.len() as u32,
+            };
+            let mut packet = Vec::with_capacity(HEADER_SIZE + payload.len());
+            packet.extend_from_slice(&header.encode());
+            packet.extend_from_slice(payload);
+            packet
+        })
+        .collect()
+}
+
+/// Collects the UDP fragments of the newest frames
+#[derive(Debug, Default)]
+struct Reassembler {
+    /// Incomplete frames, oldest first
+    pending: VecDeque<PartialFrame>,
+    /// ID of the last completed frame
+    last_frame_id: Option<u32>,
+}
+
+#[derive(Debug)]
+struct PartialFrame {
+    header: PacketHeader,
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+impl Reassembler {
+    /// Add a datagram, returning the frame it completes
+    fn push(&mut self, datagram: &[u8]) -> Result<Option<(PacketHeader, Vec<u8>)>, PluginError> {
+        let header = PacketHeader::decode(datagram)?;
+        let payload = &datagram[HEADER_SIZE..];
+        if payload.len() != header.payload_len as usize {
+            return Err(PluginError::NetworkError("Invalid frame packet: truncated".to_string()));
+        }
+        if payload.len() > UDP_PAYLOAD_BYTES {
+            return Err(PluginError::NetworkError("Invalid frame packet: fragment too large".to_string()));
+        }
+
+        let is_older = |a: u32, b: u32| (a.wrapping_sub(b) as i32) < 0;
+        // Fragments of frames older than the last completed one are late
+        if self.last_frame_id.is_some_and(|last| !is_older(last, header.frame_id)) {
+            return Ok(None);
+        }
+
+        let position = match self.pending.iter().position(|frame| frame.header.frame_id == header.frame_id) {
+            Some(position) => position,
+            None => {
+                let mut position = self
+                    .pending
+                    .iter()
+                    .position(|frame| is_older(header.frame_id, frame.header.frame_id))
+                    .unwrap_or(self.pending.len());
+                if self.pending.len() == MAX_PENDING_FRAMES {
+                    // Make room by dropping the oldest frame, unless this is it
+                    if position == 0 {
+                        return Ok(None);
+                    }
+                    self.pending.pop_front();
+                    position -= 1;
+                }
+                self.pending.insert(position, PartialFrame {
+                    header,
+                    fragments: vec![None; header.fragment_count as usize],
+                    received: 0,
+                });
+                position
+            }
+        };
+
+        let frame = &mut self.pending[position];
+        let Some(slot) = frame.fragments.get_mut(header.fragment_index as usize) else {
+            return Ok(None);
+        };
+        if slot.is_none() {
+            *slot = Some(payload.to_vec());
+            frame.received += 1;
+        }
+        if frame.received < frame.fragments.len() {
+            return Ok(None);
+        }
+
+        // Frames older than the completed one will not be delivered anymore
+        let frame = self.pending.remove(position).unwrap();
+        self.pending.drain(..position);
+        self.last_frame_id = Some(frame.header.frame_id);
+        let image = frame.fragments.into_iter().flatten().flatten().collect();
+        Ok(Some((header, image)))
+    }
+}
+
+/// Sends camera frames to a receiver
+pub struct FrameSender {
+    link: Link,
+    jpeg_quality: u8,
+    next_frame_id: u32,
+}
+
+enum Link {
+    Udp { socket: UdpSocket, target: SocketAddr },
+    /// The stream is dropped after a failed write, which may have sent part
+    /// of a message, and reconnected on the next frame
+    Tcp { stream: Option<TcpStream>, target: SocketAddr },
+}
+
+/// Connect to a TCP receiver
+fn connect_tcp(target: SocketAddr) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect_timeout(&target, SEND_TIMEOUT)?;
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(SEND_TIMEOUT))?;
+    Ok(stream)
+}
+
+impl FrameSender {
+    /// Resolve the receiver and, for TCP, connect to it
+    pub fn connect(config: &FrameSenderConfig) -> Result<Self, PluginError> {
+        if !(1..=100).contains(&config.jpeg_quality) {
+            return Err(PluginError::InvalidConfiguration("JPEG quality must be between 1 and 100".to_string()));
+        }
+        let target = (config.host.as_str(), config.port)
+            .to_socket_addrs()
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Invalid receiver address: {}", e)))?
+            .next()
+            .ok_or_else(|| PluginError::InvalidConfiguration(format!("Could not resolve receiver host {}", config.host)))?;
+        let network_error = |e: io::Error| PluginError::NetworkError(format!("Cannot reach frame receiver {}: {}", target, e));
+
+        let link = match config.transport {
+            FrameTransport::Udp => {
+                let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(network_error)?;
+                Link::Udp { socket, target }
+            }
+            FrameTransport::Tcp => {
+                let stream = connect_tcp(target).map_err(network_error)?;
+                Link::Tcp { stream: Some(stream), target }
+            }
+        };
+        Ok(Self { link, jpeg_quality: config.jpeg_quality, next_frame_id: 0 })
+    }
+
+    /// Compress and send a frame, returning the size of the encoded image
+    pub fn send(&mut self, frame: &CameraFrame) -> Result<usize, PluginError> {
+        let rgb = color::to_rgb(&frame.image_data, frame.width, frame.height, frame.format)?;
+        let image = RgbImage::from_raw(frame.width, frame.height, rgb)
+            .ok_or_else(|| PluginError::ImageConversion(format!("Failed to convert {:?} to RGB", frame.format)))?;
+        let mut jpeg = Vec::new();
+        let encoded = JpegEncoder::new_with_quality(&mut jpeg, self.jpeg_quality).encode_image(&image);
+        FRAME_BUFFERS.release(image.into_raw());
+        encoded.map_err(|e| PluginError::ImageConversion(format!("Failed to encode frame: {}", e)))?;
+
+        if jpeg.len() > MAX_IMAGE_BYTES {
+            return Err(PluginError::ImageConversion(format!("Encoded frame is too large ({} bytes)", jpeg.len())));
+        }
+
+        let frame_id = self.next_frame_id;
+        self.next_frame_id = self.next_frame_id.wrapping_add(1);
+        let rotation = frame.rotation as u16;
+        let send_error = |e: io::Error| PluginError::NetworkError(format!("Failed to send frame: {}", e));
+        match &mut self.link {
+            Link::Udp { socket, target } => {
+                for packet in packets(frame_id, frame.timestamp, rotation, &jpeg, UDP_PAYLOAD_BYTES) {
+                    socket.send_to(&packet, *target).map_err(send_error)?;
+                }
+            }
+            Link::Tcp { stream, target } => {
+                let [packet] = &packets(frame_id, frame.timestamp, rotation, &jpeg, MAX_IMAGE_BYTES)[..] else {
+                    unreachable!("a TCP message holds a whole image");
+                };
+                let mut connection = match stream.take() {
+                    Some(connection) => connection,
+                    None => {
+                        info!("Reconnecting to frame receiver {}", target);
+                        connect_tcp(*target).map_err(send_error)?
+                    }
+                };
+                connection.write_all(packet).map_err(send_error)?;
+                *stream = Some(connection);
+            }
+        }
+        Ok(jpeg.len())
+    }
+}
+
+/// Start sending frames passed to [`send_frame`], replacing any previous sender
+pub fn start_sender(config: &FrameSenderConfig) -> Result<(), PluginError> {
+    let sender = FrameSender::connect(config)?;
+    *FRAME_SENDER.lock().unwrap() = Some(sender);
+    Ok(())
+}
+
+/// Send a frame with the active sender
+pub fn send_frame(frame: &CameraFrame) -> Result<usize, PluginError> {
+    match FRAME_SENDER.lock().unwrap().as_mut() {
+        Some(sender) => sender.send(frame),
+        None => Err(PluginError::ProcessingError("Frame sender is not running".to_string())),
+    }
+}
+
+/// Stop the active sender, returning whether one was running
+pub fn stop_sender() -> bool {
+    FRAME_SENDER.lock().unwrap().take().is_some()
+}
+
+/// Receives frames from a [`FrameSender`]
+///
+/// Waits for frames indefinitely, since the sender may connect at any time
+/// and reconnect after losing the network.
+pub struct FrameReceiver {
+    config: FrameReceiverConfig,
+    frames: mpsc::Receiver<Result<CameraFrame, PluginError>>,
+}
+
+impl FrameReceiver {
+    /// Listen on the configured port
+    pub fn bind(config: FrameReceiverConfig) -> Result<Self, PluginError> {
+        let bind_error = |e: io::Error| PluginError::NetworkError(format!("Cannot listen on port {}: {}", config.port, e));
+        let address = ("0.0.0.0", config.port);
+        let (sender, frames) = mpsc::channel(1);
+
+        match config.transport {
+            FrameTransport::Udp => {
+                let socket = UdpSocket::bind(address).map_err(bind_error)?;
+                socket.set_read_timeout(Some(POLL_INTERVAL)).map_err(bind_error)?;
+                std::thread::spawn(move || receive_udp(socket, sender));
+            }
+            FrameTransport::Tcp => {
+                let listener = TcpListener::bind(address).map_err(bind_error)?;
+                listener.set_nonblocking(true).map_err(bind_error)?;
+                std::thread::spawn(move || receive_tcp(listener, sender));
+            }
+        }
+        info!("Frame receiver listening on {:?} port {}", config.transport, config.port);
+        Ok(Self { config, frames })
+    }
+}
+
+impl FrameSource for FrameReceiver {
+    fn description(&self) -> String {
+        format!("frame receiver ({:?} port {})", self.config.transport, self.config.port)
+    }
+
+    fn next_frame(&mut self) -> BoxFuture<'_, Result<Option<CameraFrame>, PluginError>> {
+        async move { self.frames.recv().await.transpose() }.boxed()
+    }
+
+    fn frame_timeout(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Decode a received image and pass it on, unless the tracker side is still
+/// busy with the previous frame
+fn deliver(header: PacketHeader, jpeg: &[u8], sender: &mpsc::Sender<Result<CameraFrame, PluginError>>) {
+    if sender.capacity() == 0 {
+        debug!("Dropping received frame {}, the previous one is still queued", header.frame_id);
+        return;
+    }
+    let image = match still::decode_limited(jpeg, MAX_DECODED_BYTES) {
+        Ok(image) => image,
+        Err(e) => {
+            warn!("Dropping received frame {}: {}", header.frame_id, e);
+            return;
+        }
+    };
+    let frame = CameraFrame {
+        width: image.width(),
+        height: image.height(),
+        image_data: image.into_raw(),
+        format: ImageFormat::RGB,
+        timestamp: header.timestamp,
+        rotation: header.rotation as u32,
+        intrinsics: None,
+    };
+    let _ = sender.try_send(Ok(frame));
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+fn receive_udp(socket: UdpSocket, sender: mpsc::Sender<Result<CameraFrame, PluginError>>) {
+    let mut datagram = vec![0u8; 65536];
+    let mut reassembler = Reassembler::default();
+    while !sender.is_closed() {
+        match socket.recv_from(&mut datagram) {
+            Ok((length, _)) => match reassembler.push(&datagram[..length]) {
+                Ok(Some((header, jpeg))) => deliver(header, &jpeg, &sender),
+                Ok(None) => {}
+                Err(e) => debug!("{}", e),
+            },
+            Err(e) if is_timeout(&e) => {}
+            Err(e) => {
+                let _ = sender.blocking_send(Err(PluginError::NetworkError(format!("Frame receiver failed: {}", e))));
+                return;
+            }
+        }
+    }
+    debug!("UDP frame receiver stopped");
+}
+
+fn receive_tcp(listener: TcpListener, sender: mpsc::Sender<Result<CameraFrame, PluginError>>) {
+    while !sender.is_closed() {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                info!("Frame sender {} connected", peer);
+                match read_tcp_frames(stream, &sender) {
+                    Ok(()) => info!("Frame sender {} disconnected", peer),
+                    Err(e) => warn!("Frame sender {} dropped: {}", peer, e),
+                }
+            }
+            Err(e) if is_timeout(&e) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                let _ = sender.blocking_send(Err(PluginError::NetworkError(format!("Frame receiver failed: {}", e))));
+                return;
+            }
+        }
+    }
+    debug!("TCP frame receiver stopped");
+}
+
+/// Read frames from one connected sender until it disconnects
+fn read_tcp_frames(mut stream: TcpStream, sender: &mpsc::Sender<Result<CameraFrame, PluginError>>) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut header = [0u8; HEADER_SIZE];
+    loop {
+        if !read_full(&mut stream, &mut header, sender)? {
+            return Ok(());
+        }
+        let header = PacketHeader::decode(&header).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut jpeg = vec![0u8; header.payload_len as usize];
+        if !read_full(&mut stream, &mut jpeg, sender)? {
+            return Ok(());
+        }
+        deliver(header, &jpeg, sender);
+    }
+}
+
+/// Fill `buffer` from `stream`, returning `false` if the connection closed
+/// or the receiver is no longer needed
+fn read_full<T>(stream: &mut TcpStream, buffer: &mut [u8], sender: &mpsc::Sender<T>) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        if sender.is_closed() {
+            return Ok(false);
+        }
+        match stream.read(&mut buffer[filled..]) {
+            Ok(0) => return Ok(false),
+            Ok(read) => filled += read,
+            Err(e) if is_timeout(&e) || e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = PacketHeader {
+            rotation: 270,
+            frame_id: 42,
+            timestamp: 1_700_000_000_123,
+            fragment_index: 2,
+            fragment_count: 5,
+            payload_len: 1400,
+        };
+        assert_eq!(PacketHeader::decode(&header.encode()).unwrap(), header);
+
+        let mut bad_magic = header.encode();
+        bad_magic[0] = b'X';
+        assert!(PacketHeader::decode(&bad_magic).is_err());
+        let out_of_range = PacketHeader { fragment_index: 5, ..header };
+        assert!(PacketHeader::decode(&out_of_range.encode()).is_err());
+        let too_many_fragments = PacketHeader { fragment_count: u16::MAX, ..header };
+        assert!(PacketHeader::decode(&too_many_fragments.encode()).is_err());
+    }
+
+    #[test]
+    fn test_reassembles_fragments() {
+        let image: Vec<u8> = (0..3000u32).map(|i| i as u8).collect();
+        let first = packets(7, 1_000, 90, &image, UDP_PAYLOAD_BYTES);
+        assert_eq!(first.len(), 3);
+
+        // Fragments arrive out of order; a duplicate is ignored
+        let mut reassembler = Reassembler::default();
+        assert_eq!(reassembler.push(&first[2]).unwrap(), None);
+        assert_eq!(reassembler.push(&first[0]).unwrap(), None);
+        assert_eq!(reassembler.push(&first[0]).unwrap(), None);
+        let (header, data) = reassembler.push(&first[1]).unwrap().unwrap();
+        assert_eq!((header.frame_id, header.timestamp, header.rotation), (7, 1_000, 90));
+        assert_eq!(data, image);
+
+        // A newer frame abandons an incomplete one and late fragments are ignored
+        let second = packets(8, 1_033, 90, &image, UDP_PAYLOAD_BYTES);
+        let third = packets(9, 1_066, 90, &image[..100], UDP_PAYLOAD_BYTES);
+        assert_eq!(reassembler.push(&second[0]).unwrap(), None);
+        let (header, data) = reassembler.push(&third[0]).unwrap().unwrap();
+        assert_eq!((header.frame_id, data.len()), (9, 100));
+        assert_eq!(reassembler.push(&second[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_limits_pending_frames() {
+        let image = vec![0u8; 3000];
+        let frames: Vec<_> = (0..=MAX_PENDING_FRAMES as u32)
+            .map(|frame_id| packets(frame_id, 1_000, 0, &image, UDP_PAYLOAD_BYTES))
+            .collect();
+
+        // Starting one frame too many drops the oldest incomplete one
+        let mut reassembler = Reassembler::default();
+        for fragments in &frames {
+            assert_eq!(reassembler.push(&fragments[0]).unwrap(), None);
+        }
+        assert_eq!(reassembler.pending.len(), MAX_PENDING_FRAMES);
+        assert_eq!(reassembler.push(&frames[0][1]).unwrap(), None);
+        assert_eq!(reassembler.push(&frames[0][2]).unwrap(), None);
+
+        // Frames still pending complete out of order
+        assert_eq!(reassembler.push(&frames[1][1]).unwrap(), None);
+        assert_eq!(reassembler.push(&frames[1][2]).unwrap().unwrap().0.frame_id, 1);
+
+        let oversized = [&PacketHeader::decode(&frames[2][0]).unwrap().encode()[..], &[0u8; UDP_PAYLOAD_BYTES + 1]].concat();
+        assert!(reassembler.push(&oversized).is_err());
+    }
+
+    #[test]
+    fn test_tcp_sender_reconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = FrameSenderConfig {
+            host: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port(),
+            transport: FrameTransport::Tcp,
+            ..FrameSenderConfig::default()
+        };
+        let mut sender = FrameSender::connect(&config).unwrap();
+        let frame = CameraFrame {
+            width: 8,
+            height: 8,
+            image_data: vec![128; 8 * 8 * 3],
+            format: ImageFormat::RGB,
+            timestamp: 0,
+            rotation: 0,
+            intrinsics: None,
+        };
+
+        // The receiver drops the connection; sending fails once it notices
+        drop(listener.accept().unwrap());
+        let failed = (0..100).any(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            sender.send(&frame).is_err()
+        });
+        assert!(failed);
+
+        // The next frame goes out on a new connection
+        sender.send(&frame).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut header = [0u8; HEADER_SIZE];
+        stream.read_exact(&mut header).unwrap();
+        assert!(PacketHeader::decode(&header).is_ok());
+    }
+}
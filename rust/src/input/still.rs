@@ -0,0 +1,62 @@
+//! Still image decoding
+//!
+//! Gallery photos arrive as encoded JPEG or PNG files. Phones store them in
+//! sensor orientation with an EXIF tag saying how to turn them upright, so
+//! the tag is applied while decoding; faces are then reported in the
+//! coordinates of the image as it is displayed.
+
+use crate::error::PluginError;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader, RgbImage};
+use std::io::Cursor;
+
+/// Decode a JPEG or PNG image and turn it upright
+pub fn decode(bytes: &[u8]) -> Result<RgbImage, PluginError> {
+    decode_limited(bytes, u64::MAX)
+}
+
+/// Decode like [`decode`], rejecting images that take more than `max_bytes`
+/// to decode before any pixels are allocated
+///
+/// For images from the network, where a small file may declare huge
+/// dimensions.
+pub fn decode_limited(bytes: &[u8], max_bytes: u64) -> Result<RgbImage, PluginError> {
+    let decode_error = |e: image::ImageError| PluginError::ImageConversion(format!("Cannot decode image: {}", e));
+
+    let reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| PluginError::ImageConversion(e.to_string()))?;
+    let mut decoder = reader.into_decoder().map_err(decode_error)?;
+    // A damaged EXIF block should not make the photo unusable
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+
+    let (width, height) = decoder.dimensions();
+    let rgb_bytes = width as u64 * height as u64 * 3;
+    if decoder.total_bytes().max(rgb_bytes) > max_bytes {
+        return Err(PluginError::ImageConversion(format!("Image of {}x{} is too large", width, height)));
+    }
+
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+    Ok(image.into_rgb8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb};
+
+    #[test]
+    fn test_decodes_png() {
+        let original = RgbImage::from_fn(4, 2, |x, _| Rgb([x as u8 * 60, 0, 0]));
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(original.clone())
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        assert_eq!(decode(&png).unwrap(), original);
+        assert!(matches!(decode(b"not an image"), Err(PluginError::ImageConversion(_))));
+        assert_eq!(decode_limited(&png, 24).unwrap(), original);
+        assert!(matches!(decode_limited(&png, 23), Err(PluginError::ImageConversion(_))));
+    }
+}
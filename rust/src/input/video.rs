@@ -0,0 +1,281 @@
+//! Offline video file processing
+//!
+//! Video files are decoded by the `ffmpeg` command line tool, which must be
+//! on the `PATH` (desktop builds; mobile apps should extract frames with the
+//! platform decoder and use `process_frame`). `ffprobe` reads the stream
+//! properties first, then `ffmpeg` pipes raw RGB frames into a tracker of
+//! their own, so the full pipeline (smoothing, expressions, blinks) runs on
+//! every frame without touching the live tracker or the network outputs.
+
+use crate::api::TrackerConfig;
+use crate::error::PluginError;
+use crate::face_tracking::tracker::FaceTracker;
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use log::{debug, warn};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tokio::sync::mpsc;
+
+/// Frames decoded ahead of the tracker
+const DECODE_AHEAD: usize = 2;
+
+/// Decoder error output kept for the error message (bytes)
+const MAX_STDERR_BYTES: u64 = 16 * 1024;
+
+/// Update reported while processing a video file
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum VideoProcessingUpdate {
+    /// The video was opened
+    Started {
+        width: u32,
+        height: u32,
+        frame_rate: f32,
+        /// Number of frames, if the container reports it
+        total_frames: Option<u64>,
+    },
+    /// Faces tracked in one frame
+    Frame {
+        /// Position of the frame in the video (0-based)
+        index: u64,
+        /// Presentation time of the frame (ms from the start of the video)
+        timestamp_ms: i64,
+        faces: Vec<Face>,
+        /// Fraction of the video processed (0.0 - 1.0), if the frame count
+        /// is known
+        progress: Option<f32>,
+    },
+    /// Every frame was processed
+    Finished { frames_processed: u64 },
+    /// Processing stopped; the frames reported so far are valid
+    Failed { message: String },
+}
+
+/// Stream properties of a video file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoInfo {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub total_frames: Option<u64>,
+    /// Clockwise rotation needed to show the frames upright (degrees)
+    pub rotation: u32,
+}
+
+/// A file or stream URL that `ffmpeg` can open, with its input options
+#[derive(Debug, Clone)]
+pub(crate) struct FfmpegInput {
+    options: Vec<&'static str>,
+    location: OsString,
+}
+
+impl FfmpegInput {
+    pub(crate) fn file(path: &Path) -> Self {
+        Self { options: Vec::new(), location: path.as_os_str().to_owned() }
+    }
+
+    /// An RTSP stream, received over TCP so lost packets do not tear frames
+    pub(crate) fn rtsp(url: &str) -> Self {
+        Self { options: vec!["-rtsp_transport", "tcp"], location: url.into() }
+    }
+
+    fn args(&self) -> impl Iterator<Item = &OsStr> {
+        let input = [OsStr::new("-i"), self.location.as_os_str()];
+        self.options.iter().map(OsStr::new).chain(input)
+    }
+}
+
+/// Read the properties of the first video stream of `path` with `ffprobe`
+pub fn probe(path: &Path) -> Result<VideoInfo, PluginError> {
+    probe_input(&FfmpegInput::file(path))
+}
+
+pub(crate) fn probe_input(input: &FfmpegInput) -> Result<VideoInfo, PluginError> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries"])
+        .arg("stream=width,height,r_frame_rate,nb_frames:stream_side_data=rotation")
+        .args(["-of", "default=noprint_wrappers=1"])
+        .args(input.args())
+        .output()
+        .map_err(|e| PluginError::InvalidConfiguration(format!("Video decoding requires ffprobe: {}", e)))?;
+    if !output.status.success() {
+        return Err(PluginError::ProcessingError(format!(
+            "Cannot read video {}: {}",
+            input.location.to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    parse_probe(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the `key=value` lines printed by `ffprobe`
+fn parse_probe(output: &str) -> Result<VideoInfo, PluginError> {
+    let value = |key: &str| {
+        output
+            .lines()
+            .filter_map(|line| line.trim().split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.trim())
+    };
+    let dimension = |key: &str| {
+        value(key)
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|&v| v > 0)
+            .ok_or_else(|| PluginError::ProcessingError("The file has no video stream".to_string()))
+    };
+
+    // Frame rates are fractions such as 30000/1001
+    let frame_rate = value("r_frame_rate")
+        .and_then(|rate| match rate.split_once('/') {
+            Some((num, den)) => Some(num.parse::<f64>().ok()? / den.parse::<f64>().ok()?),
+            None => rate.parse().ok(),
+        })
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .ok_or_else(|| PluginError::ProcessingError("The video has no frame rate".to_string()))?;
+    // The display matrix rotates counter-clockwise
+    let rotation = value("rotation").and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+
+    Ok(VideoInfo {
+        width: dimension("width")?,
+        height: dimension("height")?,
+        frame_rate,
+        total_frames: value("nb_frames").and_then(|v| v.parse().ok()).filter(|&n: &u64| n > 0),
+        rotation: (-rotation.round() as i64).rem_euclid(360) as u32,
+    })
+}
+
+/// Decode `input` to raw RGB frames on a separate thread
+///
+/// Frames are not rotated; the decoder stops once the receiver is dropped.
+pub(crate) fn spawn_decoder(input: FfmpegInput, info: VideoInfo) -> mpsc::Receiver<Result<Vec<u8>, PluginError>> {
+    let (sender, receiver) = mpsc::channel(DECODE_AHEAD);
+    std::thread::spawn(move || {
+        let child = Command::new("ffmpeg")
+            .args(["-v", "error", "-noautorotate"])
+            .args(input.args())
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                let error = PluginError::InvalidConfiguration(format!("Video decoding requires ffmpeg: {}", e));
+                let _ = sender.blocking_send(Err(error));
+                return;
+            }
+        };
+
+        // Drained on its own thread, so a decoder that logs a lot cannot
+        // fill the pipe and stall while frames are read
+        let stderr = child.stderr.take().map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut text = String::new();
+                let _ = (&mut pipe).take(MAX_STDERR_BYTES).read_to_string(&mut text);
+                let _ = io::copy(&mut pipe, &mut io::sink());
+                text
+            })
+        });
+
+        let frame_size = info.width as usize * info.height as usize * 3;
+        let mut stdout = child.stdout.take().unwrap();
+        loop {
+            let mut frame = vec![0u8; frame_size];
+            if stdout.read_exact(&mut frame).is_err() || sender.blocking_send(Ok(frame)).is_err() {
+                break;
+            }
+        }
+
+        // The tracker stopped early or the stream ended (possibly with an error)
+        if sender.is_closed() {
+            let _ = child.kill();
+        }
+        let status = child.wait();
+        let stderr = stderr.and_then(|reader| reader.join().ok()).unwrap_or_default();
+        match status {
+            Ok(status) if !status.success() && !sender.is_closed() => {
+                let error = PluginError::ProcessingError(format!("Decoding failed: {}", stderr.trim()));
+                let _ = sender.blocking_send(Err(error));
+            }
+            _ => debug!("Video decoder for {} finished", input.location.to_string_lossy()),
+        }
+    });
+    receiver
+}
+
+/// Run the tracking pipeline on every frame of the video at `path`
+///
+/// Updates go to `send`, which returns `false` once nobody listens any
+/// more; processing then stops. Returns the number of frames processed.
+pub async fn process(
+    path: PathBuf,
+    config: TrackerConfig,
+    send: impl Fn(VideoProcessingUpdate) -> bool,
+) -> Result<u64, PluginError> {
+    let info = {
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || probe(&path))
+            .await
+            .map_err(|e| PluginError::ThreadingError(e.to_string()))??
+    };
+    let tracker = FaceTracker::new(config)?.without_output();
+
+    if !send(VideoProcessingUpdate::Started {
+        width: info.width,
+        height: info.height,
+        frame_rate: info.frame_rate as f32,
+        total_frames: info.total_frames,
+    }) {
+        return Ok(0);
+    }
+
+    let mut frames = spawn_decoder(FfmpegInput::file(&path), info);
+    let mut index = 0;
+    while let Some(data) = frames.recv().await {
+        let timestamp_ms = (index as f64 * 1000.0 / info.frame_rate).round() as i64;
+        let frame = CameraFrame {
+            image_data: data?,
+            width: info.width,
+            height: info.height,
+            format: ImageFormat::RGB,
+            timestamp: timestamp_ms,
+            rotation: info.rotation,
+            intrinsics: None,
+        };
+
+        // A frame that fails is reported without faces; the video goes on
+        let faces = tracker.process_frame(frame).await.unwrap_or_else(|e| {
+            warn!("Failed to process video frame {}: {}", index, e);
+            Vec::new()
+        });
+        let progress = info.total_frames.map(|total| ((index + 1) as f32 / total as f32).min(1.0));
+        if !send(VideoProcessingUpdate::Frame { index, timestamp_ms, faces, progress }) {
+            break;
+        }
+        index += 1;
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_ffprobe_output() {
+        let info = parse_probe("width=1920\nheight=1080\nr_frame_rate=30000/1001\nnb_frames=300\nrotation=-90\n").unwrap();
+        assert_eq!((info.width, info.height, info.total_frames, info.rotation), (1920, 1080, Some(300), 90));
+        assert!((info.frame_rate - 29.97).abs() < 0.01);
+
+        // Streams without a frame count or rotation
+        let info = parse_probe("width=640\nheight=480\nr_frame_rate=25/1\nnb_frames=N/A\n").unwrap();
+        assert_eq!((info.total_frames, info.rotation), (None, 0));
+        assert_eq!(info.frame_rate, 25.0);
+
+        assert!(parse_probe("").is_err());
+        assert!(parse_probe("width=640\nheight=480\nr_frame_rate=0/0\n").is_err());
+    }
+}
@@ -4,9 +4,21 @@
 //! using the openseeface-rs library for high-performance face detection and landmark tracking.
 
 pub mod api;
+pub mod calibration;
+pub mod camera;
+pub mod events;
 pub mod face_tracking;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod input;
+pub mod lifecycle;
+pub mod logging;
+pub mod mapping;
 pub mod models;
+pub mod protocols;
+pub mod session;
 pub mod utils;
+pub mod validation;
 pub mod error;
 
 use flutter_rust_bridge::frb;
@@ -17,31 +29,46 @@ use tokio::sync::RwLock;
 use crate::face_tracking::tracker::FaceTracker;
 use crate::error::PluginError;
 
-// Global tracker instance
+// Global tracker instance and the shared runtime used by all API entry points
 lazy_static! {
     static ref GLOBAL_TRACKER: Arc<RwLock<Option<FaceTracker>>> = Arc::new(RwLock::new(None));
+    static ref RUNTIME: tokio::runtime::Runtime = create_runtime();
 }
 
 /// Initialize the native library
 #[frb(init)]
 pub fn init_app() {
-    // Initialize logging
-    #[cfg(target_os = "android")]
-    android_logger::init_once(
-        android_logger::Config::default()
-            .with_max_level(log::LevelFilter::Info)
-            .with_tag("FlutterOpenSeeFace")
-    );
-
-    #[cfg(not(target_os = "android"))]
-    env_logger::init();
+    logging::init();
+    utils::panic::install_hook();
+    lifecycle::on_isolate_start();
 
     log::info!("Flutter OpenSeeFace Plugin initialized");
 }
 
 /// Create and initialize the Rust async runtime for handling async operations
+///
+/// Sized and prioritized by the threading configuration in effect when the
+/// runtime is first used.
 pub fn create_runtime() -> tokio::runtime::Runtime {
-    tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime")
+    tokio::runtime::Builder::new_multi_thread()
+        .thread_name("openseeface-worker")
+        .worker_threads(utils::threading::runtime_worker_threads())
+        .on_thread_start(utils::threading::apply_current_priority)
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime")
+}
+
+/// Get the shared long-lived runtime, creating it on first use
+pub(crate) fn runtime() -> &'static tokio::runtime::Runtime {
+    &RUNTIME
+}
+
+/// Run a future to completion on the shared runtime
+///
+/// Must not be called from within a runtime worker thread.
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    RUNTIME.block_on(future)
 }
 
 #[cfg(test)]
@@ -54,4 +81,12 @@ mod tests {
         // Basic initialization test
         assert!(true);
     }
+
+    #[test]
+    fn test_shared_runtime_is_reused() {
+        let first = runtime() as *const _;
+        let second = runtime() as *const _;
+        assert_eq!(first, second);
+        assert_eq!(block_on(async { 21 * 2 }), 42);
+    }
 }
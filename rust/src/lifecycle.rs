@@ -0,0 +1,125 @@
+//! Resource lifetime across Dart isolates
+//!
+//! The native library outlives the Dart isolate that loaded it: after a
+//! Flutter hot restart the new isolate talks to the same process-wide
+//! tracker, workers and sockets the old one started, and streams whose Dart
+//! side is gone keep running. [`on_isolate_start`] runs from the bridge's
+//! init hook and releases whatever a previous isolate left behind;
+//! [`shutdown`] does the same on demand when an isolate shuts down.
+//!
+//! Apps that prefer scoped ownership hold the tracker through a
+//! [`TrackerHandle`]: dropping it, from Dart's `dispose()` or its finalizer,
+//! stops and releases the tracker it was created for.
+
+use crate::camera;
+use crate::face_tracking::resampler;
+use crate::input::{self, multi, remote};
+use crate::protocols;
+use crate::session::replay;
+use crate::utils::shared_buffer;
+use crate::GLOBAL_TRACKER;
+use flutter_rust_bridge::frb;
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Whether an isolate has initialized the library before
+static SESSION_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Incremented whenever the global tracker is replaced or released
+static TRACKER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Owns the global tracker; dropping it stops and releases the tracker
+///
+/// A handle only releases the tracker it was created with, so dropping an
+/// old handle after the tracker was re-initialized leaves the new one alone.
+#[frb(opaque)]
+#[derive(Debug)]
+pub struct TrackerHandle {
+    generation: u64,
+}
+
+impl TrackerHandle {
+    /// Handle of the tracker installed as `generation`
+    pub(crate) fn new(generation: u64) -> Self {
+        Self { generation }
+    }
+
+    /// Whether the tracker of this handle is still the global tracker
+    #[frb(sync)]
+    pub fn is_active(&self) -> bool {
+        TRACKER_GENERATION.load(Ordering::SeqCst) == self.generation
+    }
+}
+
+impl Drop for TrackerHandle {
+    fn drop(&mut self) {
+        // Dart may drop the handle on any thread, including a runtime worker,
+        // so the tracker is released asynchronously
+        let generation = self.generation;
+        crate::runtime().spawn(async move {
+            let mut global_tracker = GLOBAL_TRACKER.write().await;
+            if TRACKER_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if let Some(mut tracker) = global_tracker.take() {
+                if let Err(e) = tracker.stop().await {
+                    warn!("Failed to stop tracker of a dropped handle: {}", e);
+                }
+                info!("Released tracker of a dropped handle");
+            }
+            TRACKER_GENERATION.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Mark the global tracker as replaced, returning the new generation
+///
+/// Called with the global tracker's write lock held.
+pub(crate) fn next_generation() -> u64 {
+    TRACKER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Called when a Dart isolate initializes the library
+///
+/// Releases the resources of a previous isolate, which only happens after a
+/// hot restart.
+pub(crate) fn on_isolate_start() {
+    if SESSION_STARTED.swap(true, Ordering::SeqCst) {
+        info!("Library initialized again, releasing resources of the previous isolate");
+        shutdown();
+    }
+}
+
+/// Stop and release everything started from Dart
+///
+/// Stops the tracker and its streams, frame sources, tracked sources, the
+/// frame sender, camera watching, replays, the resampled stream, all
+/// network outputs, event streams and the log stream, and frees the shared
+/// frame buffer.
+/// Loaded models, enrolled people and calibration profiles are kept.
+///
+/// Must not be called from within a runtime worker thread.
+pub fn shutdown() {
+    crate::block_on(async {
+        let mut global_tracker = GLOBAL_TRACKER.write().await;
+        if let Some(mut tracker) = global_tracker.take() {
+            if let Err(e) = tracker.stop().await {
+                warn!("Failed to stop tracker: {}", e);
+            }
+        }
+        next_generation();
+    });
+
+    input::stop_source();
+    multi::close();
+    remote::stop_sender();
+    camera::stop_watching();
+    replay::stop();
+    resampler::stop();
+    let outputs = protocols::clear_outputs();
+    crate::events::unsubscribe_all();
+    crate::logging::unsubscribe();
+    shared_buffer::release();
+
+    info!("Released all tracker resources ({} outputs)", outputs);
+}
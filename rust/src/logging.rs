@@ -0,0 +1,459 @@
+//! Native log output and runtime log control
+//!
+//! Every `log` record of the library and its dependencies passes through one
+//! process-wide logger. It writes to the platform console (logcat on
+//! Android, stderr elsewhere) and, while Dart listens on the log stream,
+//! forwards the record to Dart, so production apps can collect native logs
+//! without adb or Console access. With a [`LoggingConfig::file_path`] set,
+//! records are also appended to a log file that is rotated by size, so long
+//! unattended sessions leave traces on disk.
+//!
+//! Records are filtered by a level and optional per-module directives in the
+//! `RUST_LOG` syntax. Both can be changed while the app runs; `RUST_LOG`
+//! sets the initial directives where the environment is available.
+
+use crate::error::PluginError;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::{frb, StreamSink};
+use log::{warn, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+/// Default size at which the log file is rotated
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Smallest accepted rotation size
+const MIN_MAX_FILE_SIZE: u64 = 16 * 1024;
+
+/// Tag of the library's records in logcat
+#[cfg(target_os = "android")]
+const ANDROID_TAG: &str = "FlutterOpenSeeFace";
+
+static LOGGER: OnceLock<PluginLogger> = OnceLock::new();
+
+thread_local! {
+    /// Set while a record is forwarded to Dart, so records logged on the way
+    /// are not forwarded again
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Verbosity of native log output
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LogLevel {
+    /// No output
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn to_filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+
+    fn of(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn => LogLevel::Warn,
+            log::Level::Info => LogLevel::Info,
+            log::Level::Debug => LogLevel::Debug,
+            log::Level::Trace => LogLevel::Trace,
+        }
+    }
+}
+
+/// A native log record forwarded to Dart
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Milliseconds since epoch
+    pub timestamp: i64,
+    pub level: LogLevel,
+    /// Module path or target the record was logged from
+    pub target: String,
+    pub message: String,
+}
+
+/// Log file output
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// File to append log records to (`None` = no file logging); missing
+    /// directories are created
+    pub file_path: Option<String>,
+    /// Size in bytes at which the file is rotated
+    pub max_file_size: u64,
+    /// Rotated files kept next to the log file as `<file_path>.1` (newest)
+    /// to `<file_path>.<max_files>`; 0 truncates the file instead
+    pub max_files: u32,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file_path: None,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_files: 3,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            self.file_path.as_ref().is_none_or(|path| !path.is_empty()),
+            "file_path",
+            "must not be empty",
+            "Use None to disable file logging",
+        );
+        report.check(
+            self.max_file_size >= MIN_MAX_FILE_SIZE,
+            "max_file_size",
+            &format!("must be at least {} bytes", MIN_MAX_FILE_SIZE),
+            format!("Use {}", DEFAULT_MAX_FILE_SIZE),
+        );
+        report
+    }
+}
+
+/// Log file rotated once it reaches its size limit
+struct RotatingFile {
+    config: LoggingConfig,
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Open the file of `config` for appending
+    fn open(config: &LoggingConfig, path: &Path) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            config: config.clone(),
+            path: path.to_path_buf(),
+            file,
+            written,
+        })
+    }
+
+    /// Append `line`, rotating first if it would exceed the size limit
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.written > 0 && self.written + line.len() as u64 > self.config.max_file_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift the rotated files up by one, dropping the oldest, and start an
+    /// empty log file
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let max_files = self.config.max_files;
+        if max_files > 0 {
+            let _ = fs::remove_file(self.rotated_path(max_files));
+            for index in (1..max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+}
+
+/// Level and module directives records are filtered by
+struct LogFilter {
+    level: LogLevel,
+    directives: String,
+    filter: env_filter::Filter,
+}
+
+impl LogFilter {
+    /// Filter at `level`, with `directives` overriding it for their modules
+    fn new(level: LogLevel, directives: &str) -> Result<Self, PluginError> {
+        let mut builder = env_filter::Builder::new();
+        builder.filter_level(level.to_filter());
+        builder.try_parse(directives).map_err(|e| {
+            PluginError::InvalidConfiguration(format!("Invalid log filter '{}': {}", directives, e))
+        })?;
+        Ok(Self {
+            level,
+            directives: directives.to_string(),
+            filter: builder.build(),
+        })
+    }
+}
+
+struct PluginLogger {
+    console: Box<dyn Log>,
+    filter: RwLock<LogFilter>,
+    file: Mutex<Option<RotatingFile>>,
+    sink: Mutex<Option<StreamSink<LogRecord>>>,
+}
+
+impl Log for PluginLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.read().unwrap().filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.filter.read().unwrap().filter.matches(record) {
+            return;
+        }
+        self.console.log(record);
+        self.write_to_file(record);
+        if FORWARDING.with(|forwarding| forwarding.replace(true)) {
+            return;
+        }
+        self.forward(record);
+        FORWARDING.with(|forwarding| forwarding.set(false));
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+impl PluginLogger {
+    /// Append `record` to the log file, if any
+    ///
+    /// A failing file is closed after reporting the failure on the console.
+    fn write_to_file(&self, record: &Record) {
+        let mut file = self.file.lock().unwrap();
+        if let Some(log_file) = file.as_mut() {
+            let line = format!(
+                "{} {:<5} {}: {}\n",
+                chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+            if let Err(e) = log_file.write_line(&line) {
+                let path = log_file.path.display().to_string();
+                *file = None;
+                drop(file);
+                self.console.log(
+                    &Record::builder()
+                        .level(log::Level::Error)
+                        .target(module_path!())
+                        .args(format_args!("File logging to {} stopped: {}", path, e))
+                        .build(),
+                );
+            }
+        }
+    }
+
+    /// Send `record` to the Dart log stream, if any
+    fn forward(&self, record: &Record) {
+        let mut sink = self.sink.lock().unwrap();
+        if let Some(subscriber) = sink.as_ref() {
+            let record = LogRecord {
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                level: LogLevel::of(record.level()),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            };
+            if subscriber.add(record).is_err() {
+                *sink = None;
+            }
+        }
+    }
+
+    fn set_filter(&self, filter: LogFilter) {
+        log::set_max_level(filter.filter.filter());
+        *self.filter.write().unwrap() = filter;
+    }
+}
+
+/// Install the library's logger
+///
+/// Runs on every library initialization; after a hot restart the logger is
+/// already installed and kept with its filter.
+pub(crate) fn init() {
+    let mut installed = false;
+    let logger = LOGGER.get_or_init(|| {
+        installed = true;
+        let directives = std::env::var("RUST_LOG").unwrap_or_default();
+        let filter = LogFilter::new(default_level(), &directives)
+            .or_else(|_| LogFilter::new(default_level(), ""))
+            .expect("an empty log filter is valid");
+        PluginLogger {
+            console: console_logger(),
+            filter: RwLock::new(filter),
+            file: Mutex::new(None),
+            sink: Mutex::new(None),
+        }
+    });
+    if installed {
+        if log::set_logger(logger).is_ok() {
+            log::set_max_level(logger.filter.read().unwrap().filter.filter());
+        } else {
+            // Reaches whichever logger the host application installed
+            warn!("A logger was already installed; native log control is unavailable");
+        }
+    }
+}
+
+/// Level used until the app sets one
+fn default_level() -> LogLevel {
+    if cfg!(target_os = "android") {
+        LogLevel::Info
+    } else {
+        LogLevel::Error
+    }
+}
+
+/// Platform console output; filtering is left to [`PluginLogger`]
+fn console_logger() -> Box<dyn Log> {
+    #[cfg(target_os = "android")]
+    {
+        Box::new(android_logger::AndroidLogger::new(
+            android_logger::Config::default()
+                .with_max_level(LevelFilter::Trace)
+                .with_tag(ANDROID_TAG),
+        ))
+    }
+
+    #[cfg(not(target_os = "android"))]
+    {
+        Box::new(env_logger::Builder::new().filter_level(LevelFilter::Trace).build())
+    }
+}
+
+fn logger() -> Result<&'static PluginLogger, PluginError> {
+    LOGGER
+        .get()
+        .ok_or_else(|| PluginError::InvalidConfiguration("Logging is not initialized".to_string()))
+}
+
+/// Log records at `level` and above, keeping the module directives
+pub fn set_level(level: LogLevel) -> Result<(), PluginError> {
+    let logger = logger()?;
+    let directives = logger.filter.read().unwrap().directives.clone();
+    logger.set_filter(LogFilter::new(level, &directives)?);
+    Ok(())
+}
+
+/// Replace the module directives, e.g.
+/// `flutter_openseeface_plugin::face_tracking=debug,tokio=off`
+///
+/// Directives take precedence over the level for the modules they name; a
+/// bare level in `directives` replaces the level. An empty string removes
+/// all directives.
+pub fn set_module_filter(directives: &str) -> Result<(), PluginError> {
+    let logger = logger()?;
+    let level = logger.filter.read().unwrap().level;
+    logger.set_filter(LogFilter::new(level, directives)?);
+    Ok(())
+}
+
+/// Apply the file output settings of `config`
+///
+/// The log file is kept open if its settings are unchanged and reopened for
+/// appending otherwise; without a `file_path` file logging stops.
+pub fn configure(config: &LoggingConfig) -> Result<(), PluginError> {
+    config.report().into_result()?;
+    let logger = logger()?;
+    let mut file = logger.file.lock().unwrap();
+    if file.as_ref().map(|file| &file.config) == Some(config) {
+        return Ok(());
+    }
+    *file = match &config.file_path {
+        Some(path) => Some(RotatingFile::open(config, Path::new(path)).map_err(|e| {
+            PluginError::InvalidConfiguration(format!("Cannot open log file {}: {}", path, e))
+        })?),
+        None => None,
+    };
+    Ok(())
+}
+
+/// Forward every logged record to `sink`, replacing a previous subscriber
+pub fn subscribe(sink: StreamSink<LogRecord>) -> Result<(), PluginError> {
+    *logger()?.sink.lock().unwrap() = Some(sink);
+    Ok(())
+}
+
+/// Stop forwarding records to Dart
+pub(crate) fn unsubscribe() {
+    if let Some(logger) = LOGGER.get() {
+        *logger.sink.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives_override_level() {
+        let filter = LogFilter::new(LogLevel::Warn, "flutter_openseeface_plugin::face_tracking=debug").unwrap();
+        let enabled = |level, target| {
+            filter.filter.enabled(&Metadata::builder().level(level).target(target).build())
+        };
+
+        assert!(enabled(log::Level::Warn, "tokio"));
+        assert!(!enabled(log::Level::Info, "tokio"));
+        assert!(enabled(log::Level::Debug, "flutter_openseeface_plugin::face_tracking::tracker"));
+        assert!(!enabled(log::Level::Debug, "flutter_openseeface_plugin::protocols"));
+
+        assert!(LogFilter::new(LogLevel::Info, "face_tracking=loud").is_err());
+    }
+
+    #[test]
+    fn test_rotates_log_file() {
+        let dir = std::env::temp_dir().join(format!("osf_logs_{}", std::process::id()));
+        let path = dir.join("tracker.log");
+        let config = LoggingConfig {
+            file_path: Some(path.display().to_string()),
+            max_file_size: 100,
+            max_files: 2,
+        };
+        let mut file = RotatingFile::open(&config, &path).unwrap();
+        let line = format!("{}\n", "x".repeat(59));
+        for _ in 0..5 {
+            file.write_line(&line).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), line);
+        assert_eq!(fs::read_to_string(file.rotated_path(1)).unwrap(), line);
+        assert_eq!(fs::read_to_string(file.rotated_path(2)).unwrap(), line);
+        assert!(!file.rotated_path(3).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+//! Response curves of output parameters
+//!
+//! Every avatar rig reacts differently: one needs a small deadzone so the
+//! head does not drift at rest, another a stronger smile or a softer jaw.
+//! A [`ResponseCurve`] reshapes one parameter with a deadzone, a gain, a
+//! gamma and a clamp. [`OutputCurves`] holds the curves of the head pose and
+//! expression values and runs as the last tracker stage; Perfect Sync
+//! blendshapes take their curves from
+//! [`PerfectSyncConfig`](crate::protocols::perfect_sync::PerfectSyncConfig).
+
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+
+/// Deadzone, gain, gamma and clamp of one output parameter
+///
+/// Values are reshaped by magnitude, so signed values such as head angles
+/// bend the same way in both directions.
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseCurve {
+    /// Magnitude of the full-scale value: 1.0 for 0.0 - 1.0 values, e.g.
+    /// 45.0 for head angles in degrees
+    pub range: f32,
+    /// Magnitudes up to this value are output as 0.0; the rest of the
+    /// range is stretched so the output does not jump at the edge
+    pub deadzone: f32,
+    /// Exponent applied to the magnitude relative to `range`; above 1.0
+    /// softens small values, below 1.0 boosts them
+    pub gamma: f32,
+    /// Multiplier applied after the gamma
+    pub gain: f32,
+    /// Lowest output value
+    pub min: Option<f32>,
+    /// Highest output value
+    pub max: Option<f32>,
+}
+
+impl Default for ResponseCurve {
+    fn default() -> Self {
+        Self { range: 1.0, deadzone: 0.0, gamma: 1.0, gain: 1.0, min: None, max: None }
+    }
+}
+
+impl ResponseCurve {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(self.range > 0.0, "range", "must be greater than 0.0", "Use 1.0 for 0.0 - 1.0 values");
+        report.check(
+            self.deadzone >= 0.0 && self.deadzone < self.range,
+            "deadzone",
+            "must be at least 0.0 and less than range",
+            "Use 0.0",
+        );
+        report.check(self.gamma > 0.0, "gamma", "must be greater than 0.0", "Use 1.0");
+        report.check(self.gain >= 0.0, "gain", "must not be negative", "Use 1.0");
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            report.check(min <= max, "min", "must not be greater than max", format!("Use at most {}", max));
+        }
+        report
+    }
+
+    /// Reshape one value
+    pub fn apply(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        let shaped = if magnitude <= self.deadzone {
+            0.0
+        } else {
+            let relative = (magnitude - self.deadzone) / (self.range - self.deadzone);
+            relative.powf(self.gamma) * self.range * self.gain
+        };
+        let mut output = shaped.copysign(value);
+        if let Some(min) = self.min {
+            output = output.max(min);
+        }
+        if let Some(max) = self.max {
+            output = output.min(max);
+        }
+        output
+    }
+}
+
+/// Curves of the head pose and expression values; parameters without a
+/// curve are output as tracked
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputCurves {
+    /// Head pitch (degrees)
+    pub pitch: Option<ResponseCurve>,
+    /// Head yaw (degrees)
+    pub yaw: Option<ResponseCurve>,
+    /// Head roll (degrees)
+    pub roll: Option<ResponseCurve>,
+    /// Smile expression
+    pub smile: Option<ResponseCurve>,
+    /// Brow raise expressions, both sides
+    pub brow_raise: Option<ResponseCurve>,
+    /// Mouth open expression
+    pub mouth_open: Option<ResponseCurve>,
+    /// Eye openness, both eyes
+    pub eye_openness: Option<ResponseCurve>,
+    /// Jaw opening of the mouth shape
+    pub jaw_open: Option<ResponseCurve>,
+    /// Mouth width of the mouth shape
+    pub mouth_width: Option<ResponseCurve>,
+    /// Lip pucker of the mouth shape
+    pub pucker: Option<ResponseCurve>,
+    /// Lip funnel of the mouth shape
+    pub funnel: Option<ResponseCurve>,
+}
+
+impl OutputCurves {
+    /// Check every configured curve, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        for (field, curve) in self.named() {
+            if let Some(curve) = curve {
+                report.nest(field, curve.report());
+            }
+        }
+        report
+    }
+
+    fn named(&self) -> [(&'static str, Option<ResponseCurve>); 11] {
+        [
+            ("pitch", self.pitch),
+            ("yaw", self.yaw),
+            ("roll", self.roll),
+            ("smile", self.smile),
+            ("brow_raise", self.brow_raise),
+            ("mouth_open", self.mouth_open),
+            ("eye_openness", self.eye_openness),
+            ("jaw_open", self.jaw_open),
+            ("mouth_width", self.mouth_width),
+            ("pucker", self.pucker),
+            ("funnel", self.funnel),
+        ]
+    }
+
+    /// Reshape the output parameters of all faces in place
+    pub fn apply(&self, faces: &mut [Face]) {
+        if *self == Self::default() {
+            return;
+        }
+        let reshape = |curve: Option<ResponseCurve>, value: &mut f32| {
+            if let Some(curve) = curve {
+                *value = curve.apply(*value);
+            }
+        };
+        for face in faces.iter_mut() {
+            if let Some(pose) = face.pose.as_mut() {
+                reshape(self.pitch, &mut pose.pitch);
+                reshape(self.yaw, &mut pose.yaw);
+                reshape(self.roll, &mut pose.roll);
+                pose.update_quaternion();
+            }
+            if let Some(expressions) = face.expressions.as_mut() {
+                reshape(self.smile, &mut expressions.smile);
+                reshape(self.brow_raise, &mut expressions.brow_raise_left);
+                reshape(self.brow_raise, &mut expressions.brow_raise_right);
+                reshape(self.mouth_open, &mut expressions.mouth_open);
+            }
+            if let Some(eyes) = face.eyes.as_mut() {
+                reshape(self.eye_openness, &mut eyes.left_eye_openness);
+                reshape(self.eye_openness, &mut eyes.right_eye_openness);
+            }
+            if let Some(mouth) = face.mouth.as_mut() {
+                reshape(self.jaw_open, &mut mouth.jaw_open);
+                reshape(self.mouth_width, &mut mouth.mouth_width);
+                reshape(self.pucker, &mut mouth.pucker);
+                reshape(self.funnel, &mut mouth.funnel);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_shapes_values() {
+        assert_eq!(ResponseCurve::default().apply(0.37), 0.37);
+
+        let pose = ResponseCurve { range: 40.0, deadzone: 2.0, ..ResponseCurve::default() };
+        assert_eq!(pose.apply(1.5), 0.0);
+        assert_eq!(pose.apply(-1.5), 0.0);
+        // Stretched past the deadzone: the full range still reaches 40 degrees
+        assert!((pose.apply(40.0) - 40.0).abs() < 1e-4);
+        assert!((pose.apply(-21.0) + 20.0).abs() < 1e-4);
+
+        let smile = ResponseCurve { gamma: 2.0, gain: 1.5, max: Some(1.0), ..ResponseCurve::default() };
+        assert!((smile.apply(0.5) - 0.375).abs() < 1e-6);
+        assert_eq!(smile.apply(1.0), 1.0);
+
+        let invalid = ResponseCurve {
+            deadzone: 1.0,
+            gamma: 0.0,
+            min: Some(1.0),
+            max: Some(0.0),
+            ..ResponseCurve::default()
+        };
+        assert_eq!(invalid.report().violations.len(), 3);
+    }
+}
@@ -0,0 +1,6 @@
+//! Output mapping
+//!
+//! Reshaping of tracked values into the response an avatar needs, applied
+//! to the final tracker output right before it is emitted.
+
+pub mod curves;
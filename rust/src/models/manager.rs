@@ -0,0 +1,546 @@
+//! Model download and storage
+//!
+//! Tracking models are not bundled with the native library. Apps download a
+//! [`ModelSet`] into their data directory once; the tracker then loads the
+//! models from the directory registered here when it is initialized. Apps
+//! that ship models as Flutter assets hand them over with [`load_blobs`]
+//! instead, or point `TrackerConfig::model_path` at their own directory.
+//!
+//! Files are written to a `.part` file first and renamed when complete, so an
+//! interrupted download never leaves a truncated model behind. Files that are
+//! already present are skipped.
+//!
+//! Every downloaded file is checked against its SHA-256 checksum before it
+//! is moved into place and again when the tracker loads it. Checksums come
+//! only from the built-in manifest (`checksums.sha256`), which lists the
+//! models of [`MODEL_REVISION`]; a file it does not list is not downloaded.
+//! Models an app supplies itself are checked against the manifest where it
+//! lists them.
+
+use crate::error::PluginError;
+use crate::models::ModelPrecision;
+use flutter_rust_bridge::frb;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::io::AsyncWriteExt;
+
+/// Revision of the OpenSeeFace repository the models are downloaded from
+///
+/// `checksums.sha256` must list the models of exactly this revision.
+pub const MODEL_REVISION: &str = "master";
+
+/// Location the OpenSeeFace models are downloaded from
+pub const MODEL_BASE_URL: &str = "https://github.com/emilianavt/OpenSeeFace/raw";
+
+/// Face detection model shared by all sets
+const DETECTION_MODEL: &str = "mnv3_detection_opt.onnx";
+
+/// Gaze model shared by all sets
+const GAZE_MODEL: &str = "mnv3_gaze32_split_opt.onnx";
+
+/// Fastest OpenSeeFace landmark model quality level
+pub const MIN_QUALITY_LEVEL: i32 = -1;
+
+/// Most accurate OpenSeeFace landmark model quality level
+pub const MAX_QUALITY_LEVEL: i32 = 4;
+
+/// Checksums of the published models, in `sha256sum` format
+const BUILTIN_MANIFEST: &str = include_str!("checksums.sha256");
+
+/// Directory bundled models are written to for runtimes that load from disk
+const BUNDLED_MODEL_DIR: &str = "openseeface_models";
+
+lazy_static! {
+    /// Directory the tracker loads models from, once models are installed
+    static ref MODEL_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+    /// Models handed over from Dart, by file name
+    static ref MODEL_BLOBS: RwLock<HashMap<String, Arc<Vec<u8>>>> = RwLock::new(HashMap::new());
+}
+
+/// Model file contents passed in from Dart (e.g. a Flutter asset)
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct ModelBlob {
+    /// File name the runtime expects, e.g. `lm_model3_opt.onnx`
+    pub name: String,
+    /// File contents
+    pub data: Vec<u8>,
+}
+
+/// Group of model files needed for a tracking quality level
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelSet {
+    /// Fast landmark model (quality level 0)
+    Lightweight,
+    /// Default landmark model (quality level 3)
+    Standard,
+    /// Every landmark model plus the RetinaFace detector
+    Complete,
+}
+
+impl ModelSet {
+    /// Smallest set containing the landmark model for `quality_level`
+    pub fn for_quality_level(quality_level: i32) -> Self {
+        match quality_level {
+            0 => ModelSet::Lightweight,
+            3 => ModelSet::Standard,
+            _ => ModelSet::Complete,
+        }
+    }
+
+    /// File names belonging to this set
+    pub fn files(&self) -> &'static [&'static str] {
+        match self {
+            ModelSet::Lightweight => &[DETECTION_MODEL, "lm_model0_opt.onnx", GAZE_MODEL],
+            ModelSet::Standard => &[DETECTION_MODEL, "lm_model3_opt.onnx", GAZE_MODEL],
+            ModelSet::Complete => &[
+                DETECTION_MODEL,
+                "lm_model0_opt.onnx",
+                "lm_model1_opt.onnx",
+                "lm_model2_opt.onnx",
+                "lm_model3_opt.onnx",
+                "lm_model4_opt.onnx",
+                "lm_modelT_opt.onnx",
+                "lm_modelU_opt.onnx",
+                "lm_modelV_opt.onnx",
+                GAZE_MODEL,
+                "retinaface_640x640_opt.onnx",
+                "priorbox_640x640.json",
+            ],
+        }
+    }
+}
+
+/// File name of the landmark model for an OpenSeeFace quality level
+///
+/// Level -1 is the tiny model; 0 to 3 trade speed for accuracy, and 4 is
+/// level 3 tuned for wink detection.
+pub fn landmark_model(quality_level: i32) -> Option<&'static str> {
+    match quality_level {
+        -1 => Some("lm_modelT_opt.onnx"),
+        0 => Some("lm_model0_opt.onnx"),
+        1 => Some("lm_model1_opt.onnx"),
+        2 => Some("lm_model2_opt.onnx"),
+        3 => Some("lm_model3_opt.onnx"),
+        4 => Some("lm_model4_opt.onnx"),
+        _ => None,
+    }
+}
+
+/// File name of a model in the given precision
+///
+/// Quantized models are not published upstream; apps supply them through
+/// `TrackerConfig::model_path` or `load_models_from_bytes`.
+pub fn variant_file(file: &str, precision: ModelPrecision) -> String {
+    match precision {
+        ModelPrecision::Float32 => file.to_string(),
+        ModelPrecision::Int8 => match file.strip_suffix(".onnx") {
+            Some(stem) => format!("{}_int8.onnx", stem),
+            None => file.to_string(),
+        },
+    }
+}
+
+/// Event reported while downloading a model set
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelDownloadEvent {
+    /// Bytes received for the file currently downloading
+    Progress {
+        file: String,
+        /// Position of the file in the set (0-based)
+        file_index: u32,
+        file_count: u32,
+        downloaded_bytes: u64,
+        /// Size of the file, if the server reported it
+        total_bytes: Option<u64>,
+    },
+    /// A file is stored in the target directory (or was already present)
+    FileCompleted { file: String },
+    /// All files are installed and the tracker will load them from `model_dir`
+    Finished { model_dir: String },
+    /// The download stopped; files completed so far are kept
+    Failed { message: String },
+}
+
+/// Directory the tracker should load models from, if one is registered
+pub fn model_dir() -> Option<PathBuf> {
+    MODEL_DIR.read().unwrap().clone()
+}
+
+/// Register the directory the tracker loads models from
+pub fn set_model_dir(dir: PathBuf) {
+    *MODEL_DIR.write().unwrap() = Some(dir);
+}
+
+/// Keep model files in memory for trackers initialized afterwards
+///
+/// Blobs are checked against the built-in manifest and replace earlier blobs
+/// with the same name.
+pub fn load_blobs(blobs: Vec<ModelBlob>) -> Result<(), PluginError> {
+    for blob in &blobs {
+        if blob.name.is_empty() || blob.name.contains(['/', '\\']) || blob.name.starts_with('.') {
+            return Err(PluginError::InvalidConfiguration(format!("Invalid model file name '{}'", blob.name)));
+        }
+
+        if let Some(expected) = builtin_checksum(&blob.name) {
+            let actual = to_hex(&Sha256::digest(&blob.data));
+            if expected != actual {
+                return Err(PluginError::ModelCorrupted {
+                    file: blob.name.clone(),
+                    expected: expected.to_string(),
+                    actual,
+                });
+            }
+        }
+    }
+
+    let mut stored = MODEL_BLOBS.write().unwrap();
+    for blob in blobs {
+        info!("Loaded model {} ({} bytes) from memory", blob.name, blob.data.len());
+        stored.insert(blob.name, Arc::new(blob.data));
+    }
+    Ok(())
+}
+
+/// Contents of a model handed over with [`load_blobs`]
+pub fn blob(name: &str) -> Option<Arc<Vec<u8>>> {
+    MODEL_BLOBS.read().unwrap().get(name).cloned()
+}
+
+/// Directory the tracker should load `set` from, if not its built-in default
+///
+/// An explicit `model_path` wins over models loaded with [`load_blobs`],
+/// which win over the downloaded model directory. The downloaded directory
+/// is verified against the manifest; like blobs, an explicit directory only
+/// has to match the manifest for the files it lists.
+pub fn resolve_model_dir(model_path: Option<&str>, set: ModelSet) -> Result<Option<PathBuf>, PluginError> {
+    if let Some(path) = model_path {
+        let dir = PathBuf::from(path);
+        if !dir.is_dir() {
+            return Err(PluginError::InvalidConfiguration(format!("Model path {} is not a directory", path)));
+        }
+        verify_listed(set, &dir)?;
+        return Ok(Some(dir));
+    }
+
+    if !MODEL_BLOBS.read().unwrap().is_empty() {
+        return write_blobs().map(Some);
+    }
+
+    match model_dir() {
+        Some(dir) => {
+            verify_set(set, &dir)?;
+            Ok(Some(dir))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Write the loaded blobs to a private directory for runtimes that only
+/// load models from disk
+fn write_blobs() -> Result<PathBuf, PluginError> {
+    let dir = std::env::temp_dir().join(BUNDLED_MODEL_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+
+    for (name, data) in MODEL_BLOBS.read().unwrap().iter() {
+        let path = dir.join(name);
+        // Skip files left by an earlier initialization
+        let unchanged = std::fs::metadata(&path).map(|m| m.len() == data.len() as u64).unwrap_or(false);
+        if !unchanged {
+            std::fs::write(&path, data.as_slice()).map_err(|e| io_error(&path, e))?;
+        }
+    }
+    Ok(dir)
+}
+
+/// Whether every file of `set` is present in `dir`
+pub fn is_installed(set: ModelSet, dir: &Path) -> bool {
+    set.files().iter().all(|file| dir.join(file).is_file())
+}
+
+/// Download the files of `set` into `target_dir`
+///
+/// `on_event` receives progress for each file; returning `false` from it
+/// cancels the download. On success `target_dir` becomes the model directory.
+/// Fails before downloading anything if the manifest lacks a file of `set`.
+pub async fn download(
+    set: ModelSet,
+    target_dir: &Path,
+    mut on_event: impl FnMut(ModelDownloadEvent) -> bool,
+) -> Result<(), PluginError> {
+    if let Some(file) = set.files().iter().find(|file| builtin_checksum(file).is_none()) {
+        return Err(unlisted(file));
+    }
+    tokio::fs::create_dir_all(target_dir)
+        .await
+        .map_err(|e| io_error(target_dir, e))?;
+
+    let client = reqwest::Client::new();
+    let files = set.files();
+
+    for (index, file) in files.iter().enumerate() {
+        let path = target_dir.join(file);
+        if path.is_file() {
+            if let Err(e) = verify_file(target_dir, file) {
+                warn!("{}, downloading it again", e);
+                tokio::fs::remove_file(&path).await.map_err(|e| io_error(&path, e))?;
+            }
+        }
+        
+        if !path.is_file() {
+            download_file(&client, file, target_dir, |downloaded_bytes, total_bytes| {
+                on_event(ModelDownloadEvent::Progress {
+                    file: file.to_string(),
+                    file_index: index as u32,
+                    file_count: files.len() as u32,
+                    downloaded_bytes,
+                    total_bytes,
+                })
+            })
+            .await?;
+        }
+
+        if !on_event(ModelDownloadEvent::FileCompleted { file: file.to_string() }) {
+            return Err(cancelled());
+        }
+    }
+
+    info!("Model set {:?} installed in {}", set, target_dir.display());
+    set_model_dir(target_dir.to_path_buf());
+    Ok(())
+}
+
+/// Download a single file into `dir` via a temporary `.part` file
+///
+/// The file is only moved into place if it matches its expected checksum.
+async fn download_file(
+    client: &reqwest::Client,
+    file: &str,
+    dir: &Path,
+    mut on_progress: impl FnMut(u64, Option<u64>) -> bool,
+) -> Result<(), PluginError> {
+    let expected = builtin_checksum(file).ok_or_else(|| unlisted(file))?;
+    let url = format!("{}/{}/models/{}", MODEL_BASE_URL, MODEL_REVISION, file);
+    let mut response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| PluginError::NetworkError(format!("Failed to download {}: {}", file, e)))?;
+
+    let total_bytes = response.content_length();
+    let path = dir.join(file);
+    let part_path = path.with_extension("part");
+    let mut output = tokio::fs::File::create(&part_path)
+        .await
+        .map_err(|e| io_error(&part_path, e))?;
+    let mut hasher = Sha256::new();
+    let mut downloaded_bytes = 0;
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| PluginError::NetworkError(format!("Failed to download {}: {}", file, e)))?
+    {
+        output.write_all(&chunk).await.map_err(|e| io_error(&part_path, e))?;
+        hasher.update(&chunk);
+        downloaded_bytes += chunk.len() as u64;
+
+        if !on_progress(downloaded_bytes, total_bytes) {
+            drop(output);
+            let _ = tokio::fs::remove_file(&part_path).await;
+            return Err(cancelled());
+        }
+    }
+
+    output.flush().await.map_err(|e| io_error(&part_path, e))?;
+    drop(output);
+
+    let actual = to_hex(&hasher.finalize());
+    if expected != actual {
+        let _ = tokio::fs::remove_file(&part_path).await;
+        return Err(PluginError::ModelCorrupted {
+            file: file.to_string(),
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+
+    tokio::fs::rename(&part_path, &path)
+        .await
+        .map_err(|e| io_error(&path, e))
+}
+
+/// Check a model file in `dir` against the built-in manifest
+///
+/// Files the manifest does not list fail verification.
+pub fn verify_file(dir: &Path, file: &str) -> Result<(), PluginError> {
+    let expected = builtin_checksum(file).ok_or_else(|| unlisted(file))?;
+    verify_checksum(dir, file, expected)
+}
+
+/// Verify every file of `set` that is present in `dir`
+pub fn verify_set(set: ModelSet, dir: &Path) -> Result<(), PluginError> {
+    set.files()
+        .iter()
+        .filter(|file| dir.join(file).is_file())
+        .try_for_each(|file| verify_file(dir, file))
+}
+
+/// Verify the files of `set` present in `dir` that the manifest lists
+fn verify_listed(set: ModelSet, dir: &Path) -> Result<(), PluginError> {
+    set.files()
+        .iter()
+        .filter(|file| dir.join(file).is_file())
+        .filter_map(|file| Some((file, builtin_checksum(file)?)))
+        .try_for_each(|(file, expected)| verify_checksum(dir, file, expected))
+}
+
+fn verify_checksum(dir: &Path, file: &str, expected: &str) -> Result<(), PluginError> {
+    let actual = file_checksum(&dir.join(file))?;
+    if actual != expected {
+        return Err(PluginError::ModelCorrupted {
+            file: file.to_string(),
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Entries of a manifest in `sha256sum` format
+fn parse_manifest(manifest: &str) -> impl Iterator<Item = (&str, &str)> {
+    manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (checksum, file) = line.split_once(char::is_whitespace)?;
+            Some((file.trim_start().trim_start_matches('*'), checksum))
+        })
+}
+
+fn builtin_checksum(file: &str) -> Option<&'static str> {
+    parse_manifest(BUILTIN_MANIFEST)
+        .find(|(name, _)| *name == file)
+        .map(|(_, checksum)| checksum)
+}
+
+fn file_checksum(path: &Path) -> Result<String, PluginError> {
+    let read_error = |e: std::io::Error| {
+        PluginError::ProcessingError(format!("Failed to read {}: {}", path.display(), e))
+    };
+
+    let mut input = std::fs::File::open(path).map_err(read_error)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let count = input.read(&mut buffer).map_err(read_error)?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(&buffer[..count]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> PluginError {
+    PluginError::ProcessingError(format!("Failed to write {}: {}", path.display(), error))
+}
+
+fn unlisted(file: &str) -> PluginError {
+    PluginError::ProcessingError(format!("Model {} is not listed in the checksum manifest", file))
+}
+
+fn cancelled() -> PluginError {
+    PluginError::ProcessingError("Model download was cancelled".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sets_share_detection_model() {
+        for set in [ModelSet::Lightweight, ModelSet::Standard, ModelSet::Complete] {
+            assert!(set.files().contains(&DETECTION_MODEL));
+        }
+    }
+
+    #[test]
+    fn test_quality_level_sets_contain_model() {
+        for level in MIN_QUALITY_LEVEL..=MAX_QUALITY_LEVEL {
+            let model = landmark_model(level).unwrap();
+            assert!(ModelSet::for_quality_level(level).files().contains(&model));
+        }
+        assert_eq!(landmark_model(MAX_QUALITY_LEVEL + 1), None);
+    }
+
+    #[test]
+    fn test_variant_file() {
+        assert_eq!(variant_file("lm_model3_opt.onnx", ModelPrecision::Float32), "lm_model3_opt.onnx");
+        assert_eq!(variant_file("lm_model3_opt.onnx", ModelPrecision::Int8), "lm_model3_opt_int8.onnx");
+        assert_eq!(variant_file("priorbox_640x640.json", ModelPrecision::Int8), "priorbox_640x640.json");
+    }
+
+    #[test]
+    fn test_is_installed() {
+        let dir = std::env::temp_dir().join(format!("osf_models_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(!is_installed(ModelSet::Lightweight, &dir));
+
+        for file in ModelSet::Lightweight.files() {
+            std::fs::write(dir.join(file), b"model").unwrap();
+        }
+        assert!(is_installed(ModelSet::Lightweight, &dir));
+        assert!(!is_installed(ModelSet::Standard, &dir));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_detects_corrupted_file() {
+        let dir = std::env::temp_dir().join(format!("osf_checksums_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(dir.join("lm_modelX_opt.onnx"), b"model").unwrap();
+        let checksum = file_checksum(&dir.join("lm_modelX_opt.onnx")).unwrap();
+        assert_eq!(checksum, "9372c470eeadd5ecd9c3c74c2b3cb633f8e2f2fad799250a0f70d652b6b825e4");
+        assert!(verify_checksum(&dir, "lm_modelX_opt.onnx", &checksum).is_ok());
+        // A file missing from the manifest is never trusted
+        assert!(matches!(verify_file(&dir, "lm_modelX_opt.onnx"), Err(PluginError::ProcessingError(_))));
+
+        std::fs::write(dir.join("lm_modelX_opt.onnx"), b"modem").unwrap();
+        assert!(matches!(
+            verify_checksum(&dir, "lm_modelX_opt.onnx", &checksum),
+            Err(PluginError::ModelCorrupted { expected, .. }) if expected == checksum
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_blob_paths() {
+        let blob = |name: &str| ModelBlob { name: name.to_string(), data: vec![0] };
+        assert!(load_blobs(vec![blob("../lm_model3_opt.onnx")]).is_err());
+        assert!(load_blobs(vec![blob("")]).is_err());
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = "# comment\nabc123  a.onnx\n\ndef456 *b.onnx\n";
+        let entries: Vec<_> = parse_manifest(manifest).collect();
+        assert_eq!(entries, vec![("a.onnx", "abc123"), ("b.onnx", "def456")]);
+    }
+}
@@ -0,0 +1,887 @@
+//! Data models for face tracking
+//! 
+//! This module contains all the data structures used for face tracking,
+//! including face data, landmarks, pose information, etc.
+
+pub mod manager;
+pub mod packed;
+
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Supported model types for face detection
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelType {
+    /// RetinaFace model (recommended)
+    RetinaFace,
+    /// MTCNN model (fallback)
+    MTCNN,
+}
+
+/// Numeric precision of the model files
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelPrecision {
+    /// Published 32-bit float models
+    Float32,
+    /// 8-bit quantized models (`*_int8.onnx`), faster on most mobile CPUs
+    Int8,
+}
+
+/// Image format for camera frames
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageFormat {
+    /// RGB 24-bit format
+    RGB,
+    /// RGBA 32-bit format
+    RGBA,
+    /// YUV420 format (common for camera)
+    YUV420,
+    /// NV21 format (Android camera)
+    NV21,
+    /// BGRA format (iOS camera)
+    BGRA,
+    /// NV12 format (Windows Media Foundation)
+    NV12,
+    /// YUY2 packed 4:2:2 format (UVC webcams)
+    YUY2,
+    /// 8-bit grayscale (IR cameras)
+    GRAY8,
+}
+
+/// Hardware backend used to run the tracking models
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccelerationBackend {
+    /// Portable CPU inference (always available)
+    CPU,
+    /// Android Neural Networks API
+    NNAPI,
+    /// Apple Core ML (iOS and macOS)
+    CoreML,
+    /// DirectML (Windows)
+    DirectML,
+}
+
+/// What to do with frames arriving faster than the target frame rate
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameRatePolicy {
+    /// Drop frames that arrive before the next frame is due
+    Drop,
+    /// Wait until the next frame is due and process only the newest frame
+    Coalesce,
+}
+
+/// What to do with a frame pushed while the stream queue is full
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueuePolicy {
+    /// Drop the oldest queued frame to make room, keeping latency low
+    DropOldest,
+    /// Drop the pushed frame
+    DropNewest,
+    /// Wait until the stream worker takes a frame, slowing the producer down
+    Block,
+}
+
+/// Pinhole camera parameters of the frames as delivered by the camera
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraIntrinsics {
+    /// Focal length along X (pixels)
+    pub fx: f32,
+    /// Focal length along Y (pixels)
+    pub fy: f32,
+    /// Principal point X (pixels)
+    pub cx: f32,
+    /// Principal point Y (pixels)
+    pub cy: f32,
+    /// Lens distortion coefficients `k1, k2, p1, p2, k3` as reported by
+    /// OpenCV calibration; missing coefficients are zero
+    pub distortion: Vec<f32>,
+}
+
+/// Camera frame data
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct CameraFrame {
+    /// Image data bytes
+    pub image_data: Vec<u8>,
+    /// Frame width in pixels
+    pub width: u32,
+    /// Frame height in pixels
+    pub height: u32,
+    /// Image format
+    pub format: ImageFormat,
+    /// Frame timestamp in milliseconds since epoch
+    pub timestamp: i64,
+    /// Clockwise rotation needed to make the frame upright (0, 90, 180, 270 degrees)
+    pub rotation: u32,
+    /// Intrinsics of the camera for this frame, overriding
+    /// `TrackerConfig::camera_intrinsics`
+    pub intrinsics: Option<CameraIntrinsics>,
+}
+
+/// Description of a frame written into a shared buffer slot
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct SharedFrameMetadata {
+    /// Frame width in pixels
+    pub width: u32,
+    /// Frame height in pixels
+    pub height: u32,
+    /// Image format
+    pub format: ImageFormat,
+    /// Frame timestamp in milliseconds since epoch
+    pub timestamp: i64,
+    /// Clockwise rotation needed to make the frame upright (0, 90, 180, 270 degrees)
+    pub rotation: u32,
+    /// Intrinsics of the camera for this frame, overriding
+    /// `TrackerConfig::camera_intrinsics`
+    pub intrinsics: Option<CameraIntrinsics>,
+}
+
+/// Location and layout of the shared frame buffer
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy)]
+pub struct SharedFrameBufferInfo {
+    /// Native address of slot 0
+    pub address: usize,
+    /// Number of slots in the ring
+    pub slot_count: u32,
+    /// Size of each slot in bytes; slot `i` starts at `address + i * slot_size`
+    pub slot_size: u32,
+}
+
+/// A single image plane with its memory layout
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct ImagePlane {
+    /// Plane bytes
+    pub data: Vec<u8>,
+    /// Bytes between the starts of two consecutive rows
+    pub row_stride: u32,
+    /// Bytes between two consecutive samples in a row
+    pub pixel_stride: u32,
+}
+
+impl ImagePlane {
+    /// Bytes needed to hold `columns` x `rows` samples with this layout
+    ///
+    /// The last row only has to contain its own samples, as Android leaves
+    /// out the row padding after the final row.
+    pub fn required_len(&self, columns: u32, rows: u32) -> usize {
+        if columns == 0 || rows == 0 {
+            return 0;
+        }
+        (rows as usize - 1) * self.row_stride as usize + (columns as usize - 1) * self.pixel_stride as usize + 1
+    }
+}
+
+/// Camera frame delivered as separate Y, U and V planes
+///
+/// Matches Android's `YUV_420_888` (CameraX `ImageProxy`), where every plane
+/// has its own row stride and the chroma planes may be interleaved
+/// (pixel stride 2).
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct PlanarCameraFrame {
+    /// Y, U and V planes, in that order
+    pub planes: Vec<ImagePlane>,
+    /// Frame width in pixels
+    pub width: u32,
+    /// Frame height in pixels
+    pub height: u32,
+    /// Frame timestamp in milliseconds since epoch
+    pub timestamp: i64,
+    /// Clockwise rotation needed to make the frame upright (0, 90, 180, 270 degrees)
+    pub rotation: u32,
+    /// Intrinsics of the camera for this frame, overriding
+    /// `TrackerConfig::camera_intrinsics`
+    pub intrinsics: Option<CameraIntrinsics>,
+}
+
+/// 2D point coordinates
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point2D {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// 3D point coordinates
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point3D {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// Bounding box for face detection
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Facial landmarks (68-point model)
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacialLandmarks {
+    /// All 68 landmark points
+    pub points: Vec<Point2D>,
+    /// Confidence scores for each landmark
+    pub confidences: Vec<f32>,
+}
+
+impl FacialLandmarks {
+    /// Points of a region of the 68-point layout
+    ///
+    /// Returns `None` if the landmarks have too few points for the region,
+    /// e.g. from a model that does not produce the full layout.
+    pub fn region(&self, region: LandmarkRegion) -> Option<&[Point2D]> {
+        self.points.get(region.landmark_range())
+    }
+
+    /// Get jaw line points (0-16)
+    pub fn jaw_line(&self) -> Option<&[Point2D]> {
+        self.region(LandmarkRegion::JawLine)
+    }
+
+    /// Get right eyebrow points (17-21)
+    pub fn right_eyebrow(&self) -> Option<&[Point2D]> {
+        self.region(LandmarkRegion::RightEyebrow)
+    }
+
+    /// Get left eyebrow points (22-26)
+    pub fn left_eyebrow(&self) -> Option<&[Point2D]> {
+        self.region(LandmarkRegion::LeftEyebrow)
+    }
+
+    /// Get nose points (27-35)
+    pub fn nose(&self) -> Option<&[Point2D]> {
+        self.region(LandmarkRegion::Nose)
+    }
+
+    /// Get right eye points (36-41)
+    pub fn right_eye(&self) -> Option<&[Point2D]> {
+        self.region(LandmarkRegion::RightEye)
+    }
+
+    /// Get left eye points (42-47)
+    pub fn left_eye(&self) -> Option<&[Point2D]> {
+        self.region(LandmarkRegion::LeftEye)
+    }
+
+    /// Get mouth points (48-67)
+    pub fn mouth(&self) -> Option<&[Point2D]> {
+        self.region(LandmarkRegion::Mouth)
+    }
+}
+
+/// Point layout that landmark indices refer to
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LandmarkModel {
+    /// `FacialLandmarks::points`, in the 68-point iBUG 300-W layout
+    Ibug68,
+    /// `FaceMesh::vertices`: the 68 landmarks followed by the interpolated
+    /// vertices (see `get_face_mesh_topology`)
+    FaceMesh,
+}
+
+/// Facial region of the landmark layouts
+///
+/// Right and left are the subject's, i.e. the right eye appears on the
+/// image left in an unmirrored frame.
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LandmarkRegion {
+    JawLine,
+    RightEyebrow,
+    LeftEyebrow,
+    /// Nose bridge and nostrils
+    Nose,
+    RightEye,
+    LeftEye,
+    OuterLips,
+    InnerLips,
+    /// Outer and inner lips
+    Mouth,
+}
+
+impl LandmarkRegion {
+    /// Every region
+    pub const ALL: [LandmarkRegion; 9] = [
+        LandmarkRegion::JawLine,
+        LandmarkRegion::RightEyebrow,
+        LandmarkRegion::LeftEyebrow,
+        LandmarkRegion::Nose,
+        LandmarkRegion::RightEye,
+        LandmarkRegion::LeftEye,
+        LandmarkRegion::OuterLips,
+        LandmarkRegion::InnerLips,
+        LandmarkRegion::Mouth,
+    ];
+
+    /// Indices of the region in the 68-point layout
+    ///
+    /// The face mesh starts with the same 68 points, so these are also the
+    /// region's mesh vertices that are landmarks.
+    pub fn landmark_range(self) -> Range<usize> {
+        match self {
+            LandmarkRegion::JawLine => 0..17,
+            LandmarkRegion::RightEyebrow => 17..22,
+            LandmarkRegion::LeftEyebrow => 22..27,
+            LandmarkRegion::Nose => 27..36,
+            LandmarkRegion::RightEye => 36..42,
+            LandmarkRegion::LeftEye => 42..48,
+            LandmarkRegion::OuterLips => 48..60,
+            LandmarkRegion::InnerLips => 60..68,
+            LandmarkRegion::Mouth => 48..68,
+        }
+    }
+}
+
+/// Head pose estimation
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HeadPose {
+    /// Rotation around X-axis (pitch) in degrees
+    pub pitch: f32,
+    /// Rotation around Y-axis (yaw) in degrees
+    pub yaw: f32,
+    /// Rotation around Z-axis (roll) in degrees
+    pub roll: f32,
+    /// Rotation as a quaternion, equivalent to pitch, yaw and roll
+    pub rotation_quaternion: Quaternion,
+    /// Translation vector
+    pub translation: Point3D,
+    /// Pose confidence (0.0 - 1.0)
+    pub confidence: f32,
+}
+
+impl HeadPose {
+    /// Create a pose from Euler angles in degrees
+    pub fn from_euler(pitch: f32, yaw: f32, roll: f32, translation: Point3D, confidence: f32) -> Self {
+        Self {
+            pitch,
+            yaw,
+            roll,
+            rotation_quaternion: Quaternion::from_euler(pitch, yaw, roll),
+            translation,
+            confidence,
+        }
+    }
+
+    /// Recompute `rotation_quaternion` after the Euler angles changed
+    pub fn update_quaternion(&mut self) {
+        self.rotation_quaternion = Quaternion::from_euler(self.pitch, self.yaw, self.roll);
+    }
+}
+
+/// Unit quaternion rotation
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quaternion {
+    /// No rotation
+    pub const IDENTITY: Quaternion = Quaternion { x: 0.0, y: 0.0, z: 0.0, w: 1.0 };
+
+    /// Convert Euler angles in degrees to a Unity-style quaternion
+    ///
+    /// Rotation order is Z, then X, then Y, matching Unity's convention.
+    pub fn from_euler(pitch: f32, yaw: f32, roll: f32) -> Self {
+        let (sx, cx) = (pitch.to_radians() * 0.5).sin_cos();
+        let (sy, cy) = (yaw.to_radians() * 0.5).sin_cos();
+        let (sz, cz) = (roll.to_radians() * 0.5).sin_cos();
+
+        Self {
+            x: cy * sx * cz + sy * cx * sz,
+            y: sy * cx * cz - cy * sx * sz,
+            z: cy * cx * sz - sy * sx * cz,
+            w: cy * cx * cz + sy * sx * sz,
+        }
+    }
+
+    /// Components in `[x, y, z, w]` order
+    pub fn to_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+
+    /// Inverse of a unit quaternion
+    pub fn conjugate(self) -> Self {
+        Self { x: -self.x, y: -self.y, z: -self.z, w: self.w }
+    }
+
+    /// Rotation applying `other` first, then `self`
+    pub fn multiply(self, other: Quaternion) -> Self {
+        let (a, b) = (self, other);
+        Self {
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        }
+    }
+
+    /// Spherical interpolation from `self` (`t` = 0) to `other` (`t` = 1)
+    /// along the shorter arc
+    pub fn slerp(self, other: Quaternion, t: f32) -> Self {
+        let mut dot = self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w;
+        let other = if dot < 0.0 {
+            dot = -dot;
+            Self { x: -other.x, y: -other.y, z: -other.z, w: -other.w }
+        } else {
+            other
+        };
+
+        // Nearly identical rotations: interpolate linearly to avoid dividing
+        // by a vanishing sine
+        let (wa, wb) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = dot.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        let q = Self {
+            x: wa * self.x + wb * other.x,
+            y: wa * self.y + wb * other.y,
+            z: wa * self.z + wb * other.z,
+            w: wa * self.w + wb * other.w,
+        };
+        let norm = q.to_array().iter().map(|c| c * c).sum::<f32>().sqrt();
+        Self { x: q.x / norm, y: q.y / norm, z: q.z / norm, w: q.w / norm }
+    }
+}
+
+/// Eye gaze information
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EyeGaze {
+    /// Left eye gaze direction (normalized)
+    pub left_eye_direction: Point3D,
+    /// Right eye gaze direction (normalized)
+    pub right_eye_direction: Point3D,
+    /// Combined gaze direction
+    pub combined_direction: Point3D,
+    /// Gaze confidence (0.0 - 1.0)
+    pub confidence: f32,
+    /// Point looked at in normalized screen coordinates (0.0 - 1.0), once
+    /// gaze calibration is complete
+    pub screen_gaze: Option<Point2D>,
+}
+
+/// Facial expression values derived from 68-point landmarks
+///
+/// Every value is normalized to 0.0 (neutral) - 1.0 (fully expressed).
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Expressions {
+    /// Smile, from mouth width and lifted mouth corners
+    pub smile: f32,
+    /// Left eyebrow raise
+    pub brow_raise_left: f32,
+    /// Right eyebrow raise
+    pub brow_raise_right: f32,
+    /// Mouth opening
+    pub mouth_open: f32,
+}
+
+/// Continuous per-eye openness derived from the eye landmarks
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EyeState {
+    /// Left eye openness (0.0 closed - 1.0 open), after calibration
+    pub left_eye_openness: f32,
+    /// Right eye openness (0.0 closed - 1.0 open), after calibration
+    pub right_eye_openness: f32,
+    /// Raw left eye aspect ratio, for recording a calibration
+    pub left_eye_ratio: f32,
+    /// Raw right eye aspect ratio, for recording a calibration
+    pub right_eye_ratio: f32,
+}
+
+/// Mouth shape derived from the mouth landmarks, for Live2D-style mouth driving
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MouthState {
+    /// Jaw opening (0.0 closed - 1.0 fully open), after calibration
+    pub jaw_open: f32,
+    /// Mouth stretch beyond the relaxed width (0.0 - 1.0), after calibration
+    pub mouth_width: f32,
+    /// Lip pucker, narrowing below the relaxed width (0.0 - 1.0)
+    pub pucker: f32,
+    /// Lip funnel, a narrowed mouth with parted lips (0.0 - 1.0)
+    pub funnel: f32,
+    /// Raw mouth width relative to the eye span, for recording a calibration
+    pub width_ratio: f32,
+    /// Raw inner lip gap relative to the eye span, for recording a calibration
+    pub open_ratio: f32,
+}
+
+/// Side of the face an eye is on, from the subject's point of view
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EyeSide {
+    /// Subject's left eye
+    Left,
+    /// Subject's right eye
+    Right,
+}
+
+/// Per-eye blink state
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BlinkState {
+    /// Whether the left eye is currently closed
+    pub left_eye_closed: bool,
+    /// Whether the right eye is currently closed
+    pub right_eye_closed: bool,
+}
+
+/// Detected face information
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Face {
+    /// Unique face ID for tracking
+    pub id: u32,
+    /// Face bounding box
+    pub bounding_box: BoundingBox,
+    /// Detection confidence (0.0 - 1.0)
+    pub confidence: f32,
+    /// Facial landmarks (if enabled)
+    pub landmarks: Option<FacialLandmarks>,
+    /// Head pose estimation (if enabled)
+    pub pose: Option<HeadPose>,
+    /// Eye gaze information (if enabled)
+    pub gaze: Option<EyeGaze>,
+    /// Expression values (if landmarks are available)
+    pub expressions: Option<Expressions>,
+    /// Per-eye openness (if landmarks are available)
+    pub eyes: Option<EyeState>,
+    /// Mouth shape (if landmarks are available)
+    pub mouth: Option<MouthState>,
+    /// Blink state of each eye (if blink detection is enabled)
+    pub blink: Option<BlinkState>,
+    /// Dense face mesh (if enabled and landmarks are available)
+    pub mesh: Option<FaceMesh>,
+    /// Unit-length face embedding (if an embedding model is configured and
+    /// landmarks are available)
+    pub embedding: Option<Vec<f32>>,
+    /// Name of the registered person the face belongs to (if face
+    /// embeddings are enabled and a registered person matches)
+    pub recognized_name: Option<String>,
+    /// Label given to the face with `set_face_label`, kept while the face
+    /// is tracked and through re-identification
+    pub label: Option<String>,
+    /// How attentive the person is to the camera over the last moments,
+    /// from 0.0 (looking away or eyes closed) to 1.0 (if attention
+    /// estimation is enabled and the face has a pose)
+    pub attention_score: Option<f32>,
+    /// How usable the detection is for smoothing and animation
+    pub quality: FaceQuality,
+    /// Frame timestamp when detected
+    pub timestamp: i64,
+}
+
+impl Default for Face {
+    /// An empty detection at the origin, with nothing derived yet
+    fn default() -> Self {
+        Self {
+            id: 0,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 0.0, height: 0.0 },
+            confidence: 0.0,
+            landmarks: None,
+            pose: None,
+            gaze: None,
+            expressions: None,
+            eyes: None,
+            mouth: None,
+            blink: None,
+            mesh: None,
+            embedding: None,
+            recognized_name: None,
+            label: None,
+            attention_score: None,
+            quality: FaceQuality::default(),
+            timestamp: 0,
+        }
+    }
+}
+
+/// Quality of a face detection
+///
+/// Each penalty ranges from 0.0 (none) to 1.0 (detection unusable). Faces
+/// that were not assessed have no penalties and a score of 1.0.
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FaceQuality {
+    /// Motion or focus blur of the face region
+    pub blur: f32,
+    /// Penalty for a head turned or tilted too far for reliable landmarks
+    pub pose_penalty: f32,
+    /// Likelihood that part of the face is hidden or outside the frame
+    pub occlusion: f32,
+    /// Overall quality (0.0 - 1.0), the product of the complements of the
+    /// penalties
+    pub score: f32,
+}
+
+impl Default for FaceQuality {
+    fn default() -> Self {
+        Self { blur: 0.0, pose_penalty: 0.0, occlusion: 0.0, score: 1.0 }
+    }
+}
+
+/// Dense face mesh interpolated from the landmarks
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaceMesh {
+    /// Vertex positions in frame coordinates, in the order of
+    /// `FaceMeshTopology`
+    pub vertices: Vec<Point2D>,
+}
+
+/// Fixed triangulation shared by every `FaceMesh`
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaceMeshTopology {
+    /// Number of vertices of each mesh; the first 68 are the landmarks
+    pub vertex_count: u32,
+    /// Triangle index buffer, three vertex indices per triangle with
+    /// consistent winding
+    pub triangles: Vec<u32>,
+    /// Texture coordinates (0.0 - 1.0) of each vertex on a frontal face
+    pub uvs: Vec<Point2D>,
+}
+
+/// Tracker status information
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackerStatus {
+    /// Whether tracker is initialized
+    pub is_initialized: bool,
+    /// Whether tracker is currently running
+    pub is_running: bool,
+    /// Total frames processed
+    pub frames_processed: u64,
+    /// Average processing FPS
+    pub average_fps: f32,
+    /// Last error message (if any)
+    pub last_error: Option<String>,
+    /// Recent errors, oldest first
+    pub recent_errors: Vec<TrackerErrorEvent>,
+    /// Backend the models are running on, after any CPU fallback
+    pub acceleration_backend: AccelerationBackend,
+    /// Landmark or detection model in use (if initialized)
+    pub active_model: Option<String>,
+}
+
+/// How serious a tracker error is
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorSeverity {
+    /// A single frame was rejected (e.g. bad input); tracking continues
+    Warning,
+    /// Processing failed in the tracker itself
+    Error,
+}
+
+/// An error that occurred while tracking
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackerErrorEvent {
+    /// Time the error occurred (ms since the Unix epoch)
+    pub timestamp: i64,
+    /// How serious the error is
+    pub severity: ErrorSeverity,
+    /// Stable code of the underlying `PluginError`
+    pub code: u32,
+    /// Error message
+    pub message: String,
+}
+
+/// Processing stage a tracker error occurred in
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorStage {
+    /// Pixel format conversion, orientation and preprocessing
+    Conversion,
+    /// Face detection, landmarks and embeddings
+    Detection,
+    /// ID assignment, smoothing, expressions and output
+    Analysis,
+}
+
+/// Frame a tracker error occurred on
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ErrorFrameInfo {
+    /// Frame width in pixels
+    pub width: u32,
+    /// Frame height in pixels
+    pub height: u32,
+    /// Frame timestamp in milliseconds since epoch
+    pub timestamp: i64,
+    /// Pixel format as delivered; known for conversion errors of
+    /// interleaved frames
+    pub format: Option<ImageFormat>,
+}
+
+/// An entry of the tracker's error history
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorHistoryEntry {
+    /// The error as sent on the error event stream
+    pub error: TrackerErrorEvent,
+    /// Stage the error occurred in
+    pub stage: ErrorStage,
+    /// Frame being processed, if the error concerned a frame
+    pub frame: Option<ErrorFrameInfo>,
+    /// Whether the frame came through the tracking stream rather than a
+    /// direct processing call
+    pub streamed: bool,
+}
+
+/// Face tracking statistics
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackingStats {
+    /// Total faces detected
+    pub total_faces_detected: u64,
+    /// Currently tracked faces
+    pub active_faces: u32,
+    /// Average detection confidence
+    pub average_confidence: f32,
+    /// Processing time statistics
+    pub processing_times: ProcessingTimes,
+    /// Frames dropped by frame rate throttling or a full queue
+    pub frames_dropped: u64,
+    /// Frames waiting in the stream queue
+    pub queue_depth: u32,
+    /// Frames dropped because the stream queue was full (included in
+    /// `frames_dropped`)
+    pub queue_drops: u64,
+    /// Fraction of frame buffers served from the buffer pool (0.0 - 1.0)
+    pub buffer_pool_hit_rate: f32,
+    /// Statistics over the last few seconds only
+    pub window: WindowedStats,
+    /// Lighting of the last processed frame (`None` before the first frame)
+    pub scene: Option<SceneConditions>,
+}
+
+impl Default for TrackingStats {
+    fn default() -> Self {
+        Self {
+            total_faces_detected: 0,
+            active_faces: 0,
+            average_confidence: 0.0,
+            processing_times: ProcessingTimes::default(),
+            frames_dropped: 0,
+            queue_depth: 0,
+            queue_drops: 0,
+            buffer_pool_hit_rate: 0.0,
+            window: WindowedStats::default(),
+            scene: None,
+        }
+    }
+}
+
+/// Lighting of a processed frame
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SceneConditions {
+    /// Mean luma of the frame (0.0 = black, 1.0 = white)
+    pub brightness: f32,
+    /// Standard deviation of the luma, scaled so 1.0 is the largest possible
+    pub contrast: f32,
+    /// Mean luma of the faces, or of the frame center if no face was found
+    pub subject_brightness: f32,
+    /// How much brighter the background is than the subject (0.0 = not
+    /// backlit, 1.0 = subject black against a bright background)
+    pub backlighting: f32,
+    /// Suggested change of the camera exposure (stops, -2.0 - 2.0); 0.0
+    /// when the subject is exposed well
+    pub suggested_exposure_offset: f32,
+}
+
+/// Tracking statistics over a rolling time window
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowedStats {
+    /// Length of the window (ms)
+    pub window_ms: u32,
+    /// Frames processed within the window
+    pub frames: u32,
+    /// Processed frames per second within the window
+    pub fps: f32,
+    /// Faces detected within the window, summed over frames
+    pub faces_detected: u64,
+    /// Average faces per frame
+    pub average_faces: f32,
+    /// Average detection confidence
+    pub average_confidence: f32,
+    /// Average processing time breakdown
+    pub average_processing_times: ProcessingTimes,
+}
+
+/// Processing time breakdown
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessingTimes {
+    /// Frame conversion and orientation time (ms)
+    pub conversion_ms: f32,
+    /// Low-light preprocessing time (ms)
+    pub preprocessing_ms: f32,
+    /// Face detection time (ms)
+    pub detection_ms: f32,
+    /// Landmark detection time (ms)
+    pub landmark_ms: f32,
+    /// Pose estimation time (ms)
+    pub pose_ms: f32,
+    /// Total processing time (ms)
+    pub total_ms: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_quaternion() {
+        assert_eq!(Quaternion::from_euler(0.0, 0.0, 0.0), Quaternion::IDENTITY);
+    }
+
+    #[test]
+    fn test_landmark_regions_need_enough_points() {
+        let points = vec![Point2D { x: 0.0, y: 0.0 }; 48];
+        let landmarks = FacialLandmarks { confidences: vec![1.0; points.len()], points };
+        assert_eq!(landmarks.left_eye().map(<[_]>::len), Some(6));
+        assert!(landmarks.mouth().is_none());
+        assert_eq!(LandmarkRegion::ALL.map(|r| r.landmark_range().len()).iter().sum::<usize>(), 68 + 20);
+    }
+
+    #[test]
+    fn test_yaw_quaternion() {
+        let q = Quaternion::from_euler(0.0, 90.0, 0.0);
+        let expected = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((q.y - expected).abs() < 1e-5);
+        assert!((q.w - expected).abs() < 1e-5);
+    }
+}
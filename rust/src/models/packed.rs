@@ -0,0 +1,170 @@
+//! Packed face buffers
+//!
+//! Streaming nested `Face` structs to Dart costs one bridge object per
+//! field, which dominates at high frame rates with several faces. The packed
+//! format puts a frame's faces into one little-endian byte buffer with a
+//! fixed layout that Dart reads with `ByteData`.
+//!
+//! Layout, all values little-endian:
+//!
+//! | Offset | Type  | Field                         |
+//! |--------|-------|-------------------------------|
+//! | 0      | u16   | format version ([`VERSION`])  |
+//! | 2      | u16   | face count                    |
+//! | 4      | i64   | frame timestamp (ms)          |
+//!
+//! followed by one record per face:
+//!
+//! | Offset | Type     | Field                                                |
+//! |--------|----------|------------------------------------------------------|
+//! | 0      | u32      | face ID                                              |
+//! | 4      | u8       | present fields ([`HAS_LANDMARKS`] ...)               |
+//! | 5      | u8       | blink: bit 0 left eye closed, bit 1 right eye closed |
+//! | 6      | u16      | landmark count `n`                                   |
+//! | 8      | f32 x 2  | confidence, quality score                            |
+//! | 16     | f32 x 4  | bounding box x, y, width, height                     |
+//! | 32     | f32 x 11 | pose pitch, yaw, roll, quaternion x, y, z, w, translation x, y, z, confidence |
+//! | 76     | f32 x 12 | gaze left, right and combined direction (x, y, z each), confidence, screen x, y |
+//! | 124    | f32 x 4  | expressions smile, brow raise left, right, mouth open |
+//! | 140    | f32 x 4  | eyes left, right openness, left, right ratio         |
+//! | 156    | f32 x 6  | mouth jaw open, width, pucker, funnel, width ratio, open ratio |
+//! | 180    | f32 x 2n | landmark x, y                                        |
+//!
+//! Fields that are not present are zero, except a missing screen gaze point,
+//! which is NaN. Landmark confidences, quality penalties, meshes, embeddings
+//! and recognized names are not packed.
+
+use super::*;
+
+/// Current layout version
+pub const VERSION: u16 = 1;
+
+/// Size of the frame header in bytes
+pub const HEADER_SIZE: usize = 12;
+
+/// Size of a face record without its landmarks in bytes
+pub const FACE_RECORD_SIZE: usize = 180;
+
+pub const HAS_LANDMARKS: u8 = 1 << 0;
+pub const HAS_POSE: u8 = 1 << 1;
+pub const HAS_GAZE: u8 = 1 << 2;
+pub const HAS_EXPRESSIONS: u8 = 1 << 3;
+pub const HAS_EYES: u8 = 1 << 4;
+pub const HAS_MOUTH: u8 = 1 << 5;
+pub const HAS_BLINK: u8 = 1 << 6;
+
+/// Pack the faces of the frame at `timestamp` into one buffer
+pub fn pack(faces: &[Face], timestamp: i64) -> Vec<u8> {
+    let landmark_count: usize = faces
+        .iter()
+        .map(|face| face.landmarks.as_ref().map_or(0, |landmarks| landmarks.points.len()))
+        .sum();
+    let mut buffer = Vec::with_capacity(HEADER_SIZE + faces.len() * FACE_RECORD_SIZE + landmark_count * 8);
+
+    buffer.extend_from_slice(&VERSION.to_le_bytes());
+    buffer.extend_from_slice(&(faces.len().min(u16::MAX as usize) as u16).to_le_bytes());
+    buffer.extend_from_slice(&timestamp.to_le_bytes());
+    for face in faces.iter().take(u16::MAX as usize) {
+        pack_face(face, &mut buffer);
+    }
+    buffer
+}
+
+fn pack_face(face: &Face, buffer: &mut Vec<u8>) {
+    let points = face
+        .landmarks
+        .as_ref()
+        .map_or(&[][..], |landmarks| &landmarks.points[..landmarks.points.len().min(u16::MAX as usize)]);
+    let present = [
+        (face.landmarks.is_some(), HAS_LANDMARKS),
+        (face.pose.is_some(), HAS_POSE),
+        (face.gaze.is_some(), HAS_GAZE),
+        (face.expressions.is_some(), HAS_EXPRESSIONS),
+        (face.eyes.is_some(), HAS_EYES),
+        (face.mouth.is_some(), HAS_MOUTH),
+        (face.blink.is_some(), HAS_BLINK),
+    ]
+    .iter()
+    .filter(|(present, _)| *present)
+    .fold(0, |flags, (_, flag)| flags | flag);
+    let blink = face.blink.map_or(0u8, |blink| blink.left_eye_closed as u8 | (blink.right_eye_closed as u8) << 1);
+
+    buffer.extend_from_slice(&face.id.to_le_bytes());
+    buffer.push(present);
+    buffer.push(blink);
+    buffer.extend_from_slice(&(points.len() as u16).to_le_bytes());
+
+    let mut put = |values: &[f32]| {
+        for value in values {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+    };
+    put(&[face.confidence, face.quality.score]);
+    let bounding_box = face.bounding_box;
+    put(&[bounding_box.x, bounding_box.y, bounding_box.width, bounding_box.height]);
+    put(&face.pose.map_or([0.0; 11], |pose| {
+        let [qx, qy, qz, qw] = pose.rotation_quaternion.to_array();
+        let t = pose.translation;
+        [pose.pitch, pose.yaw, pose.roll, qx, qy, qz, qw, t.x, t.y, t.z, pose.confidence]
+    }));
+    put(&face.gaze.as_ref().map_or([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, f32::NAN, f32::NAN], |gaze| {
+        let (l, r, c) = (gaze.left_eye_direction, gaze.right_eye_direction, gaze.combined_direction);
+        let screen = gaze.screen_gaze.map_or([f32::NAN; 2], |p| [p.x, p.y]);
+        [l.x, l.y, l.z, r.x, r.y, r.z, c.x, c.y, c.z, gaze.confidence, screen[0], screen[1]]
+    }));
+    put(&face.expressions.map_or([0.0; 4], |e| [e.smile, e.brow_raise_left, e.brow_raise_right, e.mouth_open]));
+    put(&face.eyes.map_or([0.0; 4], |e| {
+        [e.left_eye_openness, e.right_eye_openness, e.left_eye_ratio, e.right_eye_ratio]
+    }));
+    put(&face.mouth.map_or([0.0; 6], |m| [m.jaw_open, m.mouth_width, m.pucker, m.funnel, m.width_ratio, m.open_ratio]));
+    for point in points {
+        put(&[point.x, point.y]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn f32_at(buffer: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_packed_layout() {
+        let face = |id, landmarks: Option<FacialLandmarks>| Face {
+            id,
+            bounding_box: BoundingBox { x: 1.0, y: 2.0, width: 3.0, height: 4.0 },
+            confidence: 0.9,
+            landmarks,
+            pose: Some(HeadPose::from_euler(10.0, 20.0, 30.0, Point3D { x: 0.0, y: 0.0, z: 5.0 }, 0.8)),
+            blink: Some(BlinkState { left_eye_closed: false, right_eye_closed: true }),
+            timestamp: 1234,
+            ..Face::default()
+        };
+        let landmarks = FacialLandmarks {
+            points: vec![Point2D { x: 7.0, y: 8.0 }, Point2D { x: 9.0, y: 10.0 }],
+            confidences: vec![1.0; 2],
+        };
+        let buffer = pack(&[face(3, Some(landmarks)), face(4, None)], 1234);
+
+        assert_eq!(buffer.len(), HEADER_SIZE + 2 * FACE_RECORD_SIZE + 2 * 8);
+        assert_eq!(buffer[0..4], [1, 0, 2, 0]);
+        assert_eq!(i64::from_le_bytes(buffer[4..12].try_into().unwrap()), 1234);
+
+        let first = &buffer[HEADER_SIZE..];
+        assert_eq!(first[0..4], 3u32.to_le_bytes());
+        assert_eq!(first[4], HAS_LANDMARKS | HAS_POSE | HAS_BLINK);
+        assert_eq!(first[5], 0b10);
+        assert_eq!(first[6..8], 2u16.to_le_bytes());
+        assert_eq!(f32_at(first, 16 + 12), 4.0);
+        assert_eq!(f32_at(first, 32 + 4), 20.0);
+        assert_eq!(f32_at(first, 32 + 36), 5.0);
+        assert!(f32_at(first, 76 + 40).is_nan());
+        assert_eq!(f32_at(first, FACE_RECORD_SIZE + 12), 10.0);
+
+        let second = &buffer[HEADER_SIZE + FACE_RECORD_SIZE + 16..];
+        assert_eq!(second[0..4], 4u32.to_le_bytes());
+        assert_eq!(second.len(), FACE_RECORD_SIZE);
+    }
+}
@@ -0,0 +1,170 @@
+//! iFacialMocap / MeowFace protocol output
+//!
+//! Encodes head pose and landmark-derived blendshapes in the text-based UDP
+//! format used by the iFacialMocap iOS app (and MeowFace on Android), so
+//! desktop receivers such as VSeeFace or VBridger can use this tracker over LAN.
+//!
+//! A packet looks like:
+//!
+//! ```text
+//! eyeBlink_L-12|eyeBlink_R-10|jawOpen-40|...|=head#pitch,yaw,roll,x,y,z|rightEye#pitch,yaw,roll|leftEye#pitch,yaw,roll|
+//! ```
+//!
+//! Blendshape values are integers in 0-100, angles are in degrees.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::protocols::{eye_openness, mouth_openness, mouth_wideness, FaceOutput, FrameInfo};
+use flutter_rust_bridge::frb;
+use std::fmt::Write;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// Name the iFacialMocap sender is registered under in the output registry
+pub const OUTPUT_NAME: &str = "ifacialmocap";
+
+/// Configuration for the iFacialMocap output
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct IFacialMocapConfig {
+    /// Receiver host name or IP address
+    pub host: String,
+    /// Receiver UDP port (49983 is the iFacialMocap default)
+    pub port: u16,
+}
+
+impl Default for IFacialMocapConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 49983,
+        }
+    }
+}
+
+/// UDP sender emitting iFacialMocap packets
+pub struct IFacialMocapSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+}
+
+impl IFacialMocapSender {
+    /// Create a sender bound to an ephemeral local port
+    pub fn new(config: IFacialMocapConfig) -> Result<Self, PluginError> {
+        let target = (config.host.as_str(), config.port)
+            .to_socket_addrs()
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Invalid iFacialMocap target: {}", e)))?
+            .next()
+            .ok_or_else(|| PluginError::InvalidConfiguration(format!("Could not resolve iFacialMocap host {}", config.host)))?;
+
+        let socket = UdpSocket::bind(("0.0.0.0", 0))
+            .map_err(|e| PluginError::NetworkError(format!("Failed to bind iFacialMocap socket: {}", e)))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| PluginError::NetworkError(e.to_string()))?;
+
+        log::info!("iFacialMocap output sending to {}", target);
+
+        Ok(Self { socket, target })
+    }
+}
+
+impl FaceOutput for IFacialMocapSender {
+    fn send_faces(&self, faces: &[Face], _frame: &FrameInfo) -> Result<(), PluginError> {
+        // The protocol describes a single performer
+        let face = match faces
+            .iter()
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+        {
+            Some(face) => face,
+            None => return Ok(()),
+        };
+
+        self.socket
+            .send_to(encode_face(face).as_bytes(), self.target)
+            .map_err(|e| PluginError::NetworkError(format!("iFacialMocap send failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Blendshape values (0.0 - 1.0) derived from 68-point landmarks, using ARKit names
+fn blendshapes(landmarks: &FacialLandmarks) -> Vec<(&'static str, f32)> {
+    let (Some(left_eye), Some(right_eye)) = (landmarks.left_eye(), landmarks.right_eye()) else {
+        return Vec::new();
+    };
+    let smile = mouth_wideness(landmarks);
+
+    vec![
+        ("eyeBlink_L", 1.0 - eye_openness(left_eye)),
+        ("eyeBlink_R", 1.0 - eye_openness(right_eye)),
+        ("jawOpen", mouth_openness(landmarks)),
+        ("mouthSmile_L", smile),
+        ("mouthSmile_R", smile),
+    ]
+}
+
+/// Convert a gaze direction into (pitch, yaw, roll) angles in degrees
+fn direction_angles(direction: &Point3D) -> (f32, f32, f32) {
+    let yaw = direction.x.atan2(direction.z).to_degrees();
+    let pitch = (-direction.y)
+        .atan2((direction.x * direction.x + direction.z * direction.z).sqrt())
+        .to_degrees();
+    (pitch, yaw, 0.0)
+}
+
+/// Encode a single face as an iFacialMocap packet
+pub fn encode_face(face: &Face) -> String {
+    let mut packet = String::with_capacity(256);
+
+    if let Some(landmarks) = face.landmarks.as_ref().filter(|l| l.points.len() >= 68) {
+        for (name, value) in blendshapes(landmarks) {
+            let _ = write!(packet, "{}-{}|", name, (value * 100.0).round() as i32);
+        }
+    }
+
+    let (pitch, yaw, roll, translation) = match &face.pose {
+        Some(pose) => (pose.pitch, pose.yaw, pose.roll, pose.translation),
+        None => (0.0, 0.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }),
+    };
+    let _ = write!(
+        packet,
+        "=head#{:.3},{:.3},{:.3},{:.4},{:.4},{:.4}|",
+        pitch, yaw, roll, translation.x, translation.y, translation.z
+    );
+
+    let (right_eye, left_eye) = match &face.gaze {
+        Some(gaze) => (
+            direction_angles(&gaze.right_eye_direction),
+            direction_angles(&gaze.left_eye_direction),
+        ),
+        None => ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
+    };
+    let _ = write!(packet, "rightEye#{:.3},{:.3},{:.3}|", right_eye.0, right_eye.1, right_eye.2);
+    let _ = write!(packet, "leftEye#{:.3},{:.3},{:.3}|", left_eye.0, left_eye.1, left_eye.2);
+
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_pose_only() {
+        let face = Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 1.0, height: 1.0 },
+            confidence: 1.0,
+            pose: Some(HeadPose::from_euler(1.0, -2.0, 3.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0)),
+            ..Face::default()
+        };
+
+        let packet = encode_face(&face);
+        assert!(packet.starts_with("=head#1.000,-2.000,3.000,"));
+        assert!(packet.ends_with("leftEye#0.000,0.000,0.000|"));
+    }
+
+    #[test]
+    fn test_straight_gaze_has_zero_angles() {
+        let (pitch, yaw, roll) = direction_angles(&Point3D { x: 0.0, y: 0.0, z: 1.0 });
+        assert_eq!((pitch, yaw, roll), (0.0, 0.0, 0.0));
+    }
+}
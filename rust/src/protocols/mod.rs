@@ -0,0 +1,106 @@
+//! Network output protocols
+//!
+//! Output modules receive every batch of tracked faces right after a frame is
+//! processed and forward them to external avatar software (VSeeFace, VMC
+//! receivers, ...) without a round-trip through Dart.
+
+pub mod ifacialmocap;
+pub mod osf;
+pub mod perfect_sync;
+pub mod vmc;
+pub mod vtube_studio;
+pub mod websocket;
+
+use crate::error::PluginError;
+use crate::models::Face;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+pub(crate) use crate::face_tracking::expressions::{eye_openness, mouth_openness, mouth_wideness};
+
+/// Metadata of the frame the faces were detected in
+#[derive(Debug, Clone, Copy)]
+pub struct FrameInfo {
+    /// Frame width in pixels
+    pub width: u32,
+    /// Frame height in pixels
+    pub height: u32,
+    /// Frame timestamp in milliseconds since epoch
+    pub timestamp: i64,
+}
+
+/// A destination that tracked faces are forwarded to after each frame
+pub trait FaceOutput: Send + Sync {
+    /// Send the faces detected in a single frame
+    fn send_faces(&self, faces: &[Face], frame: &FrameInfo) -> Result<(), PluginError>;
+}
+
+lazy_static! {
+    static ref OUTPUTS: RwLock<HashMap<&'static str, Box<dyn FaceOutput>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Register an output under `name`, replacing any existing output with that name
+pub(crate) fn register_output(name: &'static str, output: Box<dyn FaceOutput>) {
+    log::info!("Registering face output: {}", name);
+    OUTPUTS.write().unwrap().insert(name, output);
+}
+
+/// Remove the output registered under `name`, returning whether it existed
+pub(crate) fn remove_output(name: &str) -> bool {
+    log::info!("Removing face output: {}", name);
+    OUTPUTS.write().unwrap().remove(name).is_some()
+}
+
+/// Remove every output, returning how many there were
+pub(crate) fn clear_outputs() -> usize {
+    let mut outputs = OUTPUTS.write().unwrap();
+    let count = outputs.len();
+    outputs.clear();
+    count
+}
+
+/// Check whether an output is registered under `name`
+pub(crate) fn is_output_active(name: &str) -> bool {
+    OUTPUTS.read().unwrap().contains_key(name)
+}
+
+/// Forward the faces of one processed frame to every active output
+///
+/// Output failures are logged and never fail frame processing.
+pub(crate) fn broadcast_faces(faces: &[Face], frame: &FrameInfo) {
+    let outputs = OUTPUTS.read().unwrap();
+    for (name, output) in outputs.iter() {
+        if let Err(e) = output.send_faces(faces, frame) {
+            log::debug!("Output {} failed to send faces: {}", name, e);
+        }
+    }
+}
+
+/// ARKit blendshape values (0.0 - 1.0) from the calibrated face state
+pub(crate) fn arkit_blendshapes(face: &Face) -> Vec<(&'static str, f32)> {
+    let mut values = Vec::new();
+    if let Some(eyes) = &face.eyes {
+        values.push(("eyeBlinkLeft", 1.0 - eyes.left_eye_openness));
+        values.push(("eyeBlinkRight", 1.0 - eyes.right_eye_openness));
+    }
+    if let Some(mouth) = &face.mouth {
+        values.push(("jawOpen", mouth.jaw_open));
+        values.push(("mouthStretchLeft", mouth.mouth_width));
+        values.push(("mouthStretchRight", mouth.mouth_width));
+        values.push(("mouthPucker", mouth.pucker));
+        values.push(("mouthFunnel", mouth.funnel));
+    }
+    if let Some(expressions) = &face.expressions {
+        values.push(("mouthSmileLeft", expressions.smile));
+        values.push(("mouthSmileRight", expressions.smile));
+        values.push(("browOuterUpLeft", expressions.brow_raise_left));
+        values.push(("browOuterUpRight", expressions.brow_raise_right));
+        values.push(("browInnerUp", (expressions.brow_raise_left + expressions.brow_raise_right) / 2.0));
+    }
+    for (_, value) in values.iter_mut() {
+        *value = value.clamp(0.0, 1.0);
+    }
+    values
+}
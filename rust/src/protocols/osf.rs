@@ -0,0 +1,250 @@
+//! OpenSeeFace UDP packet output
+//!
+//! Serializes faces into the binary packet emitted by the original OpenSeeFace
+//! Python tracker (`facetracker.py`), so VSeeFace and other OpenSeeFace
+//! receivers can use this plugin as a drop-in tracker.
+//!
+//! Each face is sent as its own 1785 byte little-endian packet:
+//!
+//! | Field | Type |
+//! |-------|------|
+//! | timestamp (seconds) | f64 |
+//! | face id | i32 |
+//! | frame width, height | 2 × f32 |
+//! | right, left eye openness | 2 × f32 |
+//! | success | u8 |
+//! | PnP error | f32 |
+//! | rotation quaternion (x, y, z, w) | 4 × f32 |
+//! | rotation Euler (x, y, z) | 3 × f32 |
+//! | translation (x, y, z) | 3 × f32 |
+//! | landmark confidences | 68 × f32 |
+//! | landmarks (y, x) | 68 × 2 × f32 |
+//! | 3D points (x, -y, -z) | 70 × 3 × f32 |
+//! | features | 14 × f32 |
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::protocols::{eye_openness, mouth_openness, mouth_wideness, FaceOutput, FrameInfo};
+use flutter_rust_bridge::frb;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// Name the OpenSeeFace sender is registered under in the output registry
+pub const OUTPUT_NAME: &str = "openseeface";
+
+/// Number of 2D landmarks in an OpenSeeFace packet
+pub const LANDMARK_COUNT: usize = 68;
+
+/// Number of 3D points in an OpenSeeFace packet (68 landmarks + 2 pupils)
+pub const POINT_3D_COUNT: usize = 70;
+
+/// Feature values in packet order
+pub const FEATURE_NAMES: [&str; 14] = [
+    "eye_l",
+    "eye_r",
+    "eyebrow_steepness_l",
+    "eyebrow_updown_l",
+    "eyebrow_quirk_l",
+    "eyebrow_steepness_r",
+    "eyebrow_updown_r",
+    "eyebrow_quirk_r",
+    "mouth_corner_updown_l",
+    "mouth_corner_inout_l",
+    "mouth_corner_updown_r",
+    "mouth_corner_inout_r",
+    "mouth_open",
+    "mouth_wide",
+];
+
+/// Size of a single face packet in bytes
+pub const PACKET_SIZE: usize = 8 + 4 + 2 * 4 + 2 * 4 + 1 + 4 + 4 * 4 + 3 * 4 + 3 * 4
+    + LANDMARK_COUNT * 4
+    + LANDMARK_COUNT * 2 * 4
+    + POINT_3D_COUNT * 3 * 4
+    + FEATURE_NAMES.len() * 4;
+
+/// Configuration for the OpenSeeFace packet output
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct OsfOutputConfig {
+    /// Receiver host name or IP address
+    pub host: String,
+    /// Receiver UDP port (11573 is the OpenSeeFace default)
+    pub port: u16,
+}
+
+impl Default for OsfOutputConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 11573,
+        }
+    }
+}
+
+/// UDP sender emitting OpenSeeFace packets
+pub struct OsfSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+}
+
+impl OsfSender {
+    /// Create a sender bound to an ephemeral local port
+    pub fn new(config: OsfOutputConfig) -> Result<Self, PluginError> {
+        let target = (config.host.as_str(), config.port)
+            .to_socket_addrs()
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Invalid OpenSeeFace target: {}", e)))?
+            .next()
+            .ok_or_else(|| PluginError::InvalidConfiguration(format!("Could not resolve OpenSeeFace host {}", config.host)))?;
+
+        let socket = UdpSocket::bind(("0.0.0.0", 0))
+            .map_err(|e| PluginError::NetworkError(format!("Failed to bind OpenSeeFace socket: {}", e)))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| PluginError::NetworkError(e.to_string()))?;
+
+        log::info!("OpenSeeFace output sending to {}", target);
+
+        Ok(Self { socket, target })
+    }
+}
+
+impl FaceOutput for OsfSender {
+    fn send_faces(&self, faces: &[Face], frame: &FrameInfo) -> Result<(), PluginError> {
+        for face in faces {
+            let packet = encode_face(face, frame);
+            self.socket
+                .send_to(&packet, self.target)
+                .map_err(|e| PluginError::NetworkError(format!("OpenSeeFace send failed: {}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Encode a single face as an OpenSeeFace packet
+pub fn encode_face(face: &Face, frame: &FrameInfo) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(PACKET_SIZE);
+
+    let landmarks = face
+        .landmarks
+        .as_ref()
+        .filter(|landmarks| landmarks.points.len() >= LANDMARK_COUNT);
+
+    let (eye_right, eye_left) = match landmarks.and_then(|l| l.right_eye().zip(l.left_eye())) {
+        Some((right_eye, left_eye)) => (eye_openness(right_eye), eye_openness(left_eye)),
+        None => (1.0, 1.0),
+    };
+
+    packet.extend_from_slice(&(face.timestamp as f64 / 1000.0).to_le_bytes());
+    packet.extend_from_slice(&(face.id as i32).to_le_bytes());
+    put_f32(&mut packet, frame.width as f32);
+    put_f32(&mut packet, frame.height as f32);
+    put_f32(&mut packet, eye_right);
+    put_f32(&mut packet, eye_left);
+    packet.push(u8::from(face.pose.is_some()));
+
+    // PnP error
+    put_f32(&mut packet, 0.0);
+
+    match &face.pose {
+        Some(pose) => {
+            for value in pose.rotation_quaternion.to_array() {
+                put_f32(&mut packet, value);
+            }
+            for value in [pose.pitch, pose.yaw, pose.roll] {
+                put_f32(&mut packet, value);
+            }
+            for value in [pose.translation.x, pose.translation.y, pose.translation.z] {
+                put_f32(&mut packet, value);
+            }
+        }
+        None => {
+            for value in [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0] {
+                put_f32(&mut packet, value);
+            }
+        }
+    }
+
+    for i in 0..LANDMARK_COUNT {
+        let confidence = landmarks.and_then(|l| l.confidences.get(i).copied()).unwrap_or(0.0);
+        put_f32(&mut packet, confidence);
+    }
+
+    // OpenSeeFace writes landmark coordinates as (y, x)
+    for i in 0..LANDMARK_COUNT {
+        let point = landmarks.map(|l| l.points[i]).unwrap_or(Point2D { x: 0.0, y: 0.0 });
+        put_f32(&mut packet, point.y);
+        put_f32(&mut packet, point.x);
+    }
+
+    // 3D model points are not estimated by this tracker
+    for _ in 0..POINT_3D_COUNT * 3 {
+        put_f32(&mut packet, 0.0);
+    }
+
+    let mut features = [0.0f32; 14];
+    if let Some(landmarks) = landmarks {
+        features[0] = eye_left;
+        features[1] = eye_right;
+        features[12] = mouth_openness(landmarks);
+        features[13] = mouth_wideness(landmarks);
+    }
+    for value in features {
+        put_f32(&mut packet, value);
+    }
+
+    packet
+}
+
+fn put_f32(packet: &mut Vec<u8>, value: f32) {
+    packet.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_face() -> Face {
+        Face {
+            id: 3,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 0.9,
+            landmarks: Some(FacialLandmarks {
+                points: (0..68).map(|i| Point2D { x: i as f32, y: 2.0 * i as f32 }).collect(),
+                confidences: vec![0.9; 68],
+            }),
+            pose: Some(HeadPose::from_euler(0.0, 0.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 0.9)),
+            timestamp: 1500,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_packet_size() {
+        assert_eq!(PACKET_SIZE, 1785);
+        let frame = FrameInfo { width: 640, height: 480, timestamp: 1500 };
+        assert_eq!(encode_face(&test_face(), &frame).len(), PACKET_SIZE);
+    }
+
+    #[test]
+    fn test_packet_header() {
+        let frame = FrameInfo { width: 640, height: 480, timestamp: 1500 };
+        let packet = encode_face(&test_face(), &frame);
+
+        assert_eq!(f64::from_le_bytes(packet[0..8].try_into().unwrap()), 1.5);
+        assert_eq!(i32::from_le_bytes(packet[8..12].try_into().unwrap()), 3);
+        assert_eq!(f32::from_le_bytes(packet[12..16].try_into().unwrap()), 640.0);
+        assert_eq!(packet[28], 1);
+    }
+
+    #[test]
+    fn test_landmarks_are_written_y_first() {
+        let frame = FrameInfo { width: 640, height: 480, timestamp: 0 };
+        let packet = encode_face(&test_face(), &frame);
+
+        // Header (29) + PnP error (4) + quaternion/euler/translation (40) + confidences (272)
+        let offset = 29 + 4 + 40 + 68 * 4 + 8;
+        let y = f32::from_le_bytes(packet[offset..offset + 4].try_into().unwrap());
+        let x = f32::from_le_bytes(packet[offset + 4..offset + 8].try_into().unwrap());
+        assert_eq!((x, y), (1.0, 2.0));
+    }
+}
@@ -0,0 +1,348 @@
+//! "Perfect Sync" blendshape profile
+//!
+//! Perfect Sync avatars carry one blendshape clip for each of the 52 ARKit
+//! blendshapes, named like the ARKit blendshape with an upper case first
+//! letter (`EyeBlinkLeft`, `JawOpen`, ...) and driven with values in
+//! 0.0 - 1.0. This profile always produces the full set, so receivers such
+//! as VSeeFace switch to Perfect Sync mode. Shapes the 68-point landmarks
+//! cannot observe, such as cheek puff or tongue out, stay at 0.0.
+
+use crate::error::PluginError;
+use crate::face_tracking::expressions::ValueRange;
+use crate::mapping::curves::ResponseCurve;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+
+/// Gaze angle in degrees mapped to a full eye look blendshape
+const EYE_LOOK_RANGE: f32 = 30.0;
+
+/// Group of blendshapes that can be switched off together
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendshapeGroup {
+    Brows,
+    Eyes,
+    /// Mouth, jaw and tongue
+    Mouth,
+    /// Cheeks and nose
+    Cheeks,
+}
+
+/// Every Perfect Sync blendshape with its group, in ARKit order
+const BLENDSHAPES: [(&str, BlendshapeGroup); 52] = [
+    ("BrowDownLeft", BlendshapeGroup::Brows),
+    ("BrowDownRight", BlendshapeGroup::Brows),
+    ("BrowInnerUp", BlendshapeGroup::Brows),
+    ("BrowOuterUpLeft", BlendshapeGroup::Brows),
+    ("BrowOuterUpRight", BlendshapeGroup::Brows),
+    ("CheekPuff", BlendshapeGroup::Cheeks),
+    ("CheekSquintLeft", BlendshapeGroup::Cheeks),
+    ("CheekSquintRight", BlendshapeGroup::Cheeks),
+    ("EyeBlinkLeft", BlendshapeGroup::Eyes),
+    ("EyeBlinkRight", BlendshapeGroup::Eyes),
+    ("EyeLookDownLeft", BlendshapeGroup::Eyes),
+    ("EyeLookDownRight", BlendshapeGroup::Eyes),
+    ("EyeLookInLeft", BlendshapeGroup::Eyes),
+    ("EyeLookInRight", BlendshapeGroup::Eyes),
+    ("EyeLookOutLeft", BlendshapeGroup::Eyes),
+    ("EyeLookOutRight", BlendshapeGroup::Eyes),
+    ("EyeLookUpLeft", BlendshapeGroup::Eyes),
+    ("EyeLookUpRight", BlendshapeGroup::Eyes),
+    ("EyeSquintLeft", BlendshapeGroup::Eyes),
+    ("EyeSquintRight", BlendshapeGroup::Eyes),
+    ("EyeWideLeft", BlendshapeGroup::Eyes),
+    ("EyeWideRight", BlendshapeGroup::Eyes),
+    ("JawForward", BlendshapeGroup::Mouth),
+    ("JawLeft", BlendshapeGroup::Mouth),
+    ("JawOpen", BlendshapeGroup::Mouth),
+    ("JawRight", BlendshapeGroup::Mouth),
+    ("MouthClose", BlendshapeGroup::Mouth),
+    ("MouthDimpleLeft", BlendshapeGroup::Mouth),
+    ("MouthDimpleRight", BlendshapeGroup::Mouth),
+    ("MouthFrownLeft", BlendshapeGroup::Mouth),
+    ("MouthFrownRight", BlendshapeGroup::Mouth),
+    ("MouthFunnel", BlendshapeGroup::Mouth),
+    ("MouthLeft", BlendshapeGroup::Mouth),
+    ("MouthLowerDownLeft", BlendshapeGroup::Mouth),
+    ("MouthLowerDownRight", BlendshapeGroup::Mouth),
+    ("MouthPressLeft", BlendshapeGroup::Mouth),
+    ("MouthPressRight", BlendshapeGroup::Mouth),
+    ("MouthPucker", BlendshapeGroup::Mouth),
+    ("MouthRight", BlendshapeGroup::Mouth),
+    ("MouthRollLower", BlendshapeGroup::Mouth),
+    ("MouthRollUpper", BlendshapeGroup::Mouth),
+    ("MouthShrugLower", BlendshapeGroup::Mouth),
+    ("MouthShrugUpper", BlendshapeGroup::Mouth),
+    ("MouthSmileLeft", BlendshapeGroup::Mouth),
+    ("MouthSmileRight", BlendshapeGroup::Mouth),
+    ("MouthStretchLeft", BlendshapeGroup::Mouth),
+    ("MouthStretchRight", BlendshapeGroup::Mouth),
+    ("MouthUpperUpLeft", BlendshapeGroup::Mouth),
+    ("MouthUpperUpRight", BlendshapeGroup::Mouth),
+    ("NoseSneerLeft", BlendshapeGroup::Cheeks),
+    ("NoseSneerRight", BlendshapeGroup::Cheeks),
+    ("TongueOut", BlendshapeGroup::Mouth),
+];
+
+/// Calibrated range of one blendshape
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlendshapeRange {
+    /// Perfect Sync blendshape name, e.g. `MouthSmileLeft`
+    pub name: String,
+    /// Tracked values stretched onto 0.0 - 1.0
+    pub range: ValueRange,
+}
+
+/// Response curve of one blendshape
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlendshapeCurve {
+    /// Perfect Sync blendshape name, e.g. `JawOpen`
+    pub name: String,
+    /// Applied after the calibration range
+    pub curve: ResponseCurve,
+}
+
+/// Named blendshape value
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlendshapeValue {
+    pub name: String,
+    pub value: f32,
+}
+
+/// Settings of the Perfect Sync profile
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerfectSyncConfig {
+    /// Send the brow blendshapes
+    pub brows: bool,
+    /// Send the eye blendshapes
+    pub eyes: bool,
+    /// Send the mouth, jaw and tongue blendshapes
+    pub mouth: bool,
+    /// Send the cheek and nose blendshapes
+    pub cheeks: bool,
+    /// Per-blendshape ranges, e.g. recorded with
+    /// [`capture_neutral`]; blendshapes without a range are sent as tracked
+    pub calibration: Vec<BlendshapeRange>,
+    /// Per-blendshape response curves; blendshapes without a curve are sent
+    /// as calibrated
+    #[serde(default)]
+    pub curves: Vec<BlendshapeCurve>,
+}
+
+impl Default for PerfectSyncConfig {
+    fn default() -> Self {
+        Self {
+            brows: true,
+            eyes: true,
+            mouth: true,
+            cheeks: true,
+            calibration: Vec::new(),
+            curves: Vec::new(),
+        }
+    }
+}
+
+impl PerfectSyncConfig {
+    /// Check that every calibrated blendshape exists and has a usable range
+    pub fn check(&self) -> Result<(), PluginError> {
+        for entry in &self.calibration {
+            if !BLENDSHAPES.iter().any(|(name, _)| *name == entry.name) {
+                return Err(PluginError::InvalidConfiguration(format!(
+                    "Unknown Perfect Sync blendshape {}",
+                    entry.name
+                )));
+            }
+            if entry.range.min >= entry.range.max {
+                return Err(PluginError::InvalidConfiguration(format!(
+                    "Calibration range of {} must not be empty",
+                    entry.name
+                )));
+            }
+        }
+        for entry in &self.curves {
+            if !BLENDSHAPES.iter().any(|(name, _)| *name == entry.name) {
+                return Err(PluginError::InvalidConfiguration(format!(
+                    "Unknown Perfect Sync blendshape {}",
+                    entry.name
+                )));
+            }
+            let mut report = ValidationReport::new();
+            report.nest(&format!("curves.{}", entry.name), entry.curve.report());
+            report.into_result()?;
+        }
+        Ok(())
+    }
+
+    /// Whether blendshapes of `group` are sent
+    pub fn is_enabled(&self, group: BlendshapeGroup) -> bool {
+        match group {
+            BlendshapeGroup::Brows => self.brows,
+            BlendshapeGroup::Eyes => self.eyes,
+            BlendshapeGroup::Mouth => self.mouth,
+            BlendshapeGroup::Cheeks => self.cheeks,
+        }
+    }
+}
+
+/// Perfect Sync blendshape values of a face, in ARKit order
+///
+/// Blendshapes of disabled groups are left out rather than sent as 0.0, so
+/// the avatar app can drive them from another source.
+pub fn blendshapes(face: &Face, config: &PerfectSyncConfig) -> Vec<(&'static str, f32)> {
+    let tracked = tracked_values(face);
+    BLENDSHAPES
+        .iter()
+        .filter(|(_, group)| config.is_enabled(*group))
+        .map(|&(name, _)| {
+            let value = tracked.iter().find(|(tracked, _)| *tracked == name).map_or(0.0, |(_, value)| *value);
+            let value = match config.calibration.iter().find(|entry| entry.name == name) {
+                Some(entry) => entry.range.rescale(value),
+                None => value.clamp(0.0, 1.0),
+            };
+            let value = match config.curves.iter().find(|entry| entry.name == name) {
+                Some(entry) => entry.curve.apply(value),
+                None => value,
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// Record the neutral face: each blendshape's highest value over `samples`
+/// becomes the value mapped to 0.0, so a relaxed face sends no expression
+///
+/// Existing ranges keep their upper end. Blendshapes that stay at 0.0 get no
+/// calibration entry.
+pub fn capture_neutral(samples: &[Face], config: &PerfectSyncConfig) -> Result<PerfectSyncConfig, PluginError> {
+    if samples.is_empty() {
+        return Err(PluginError::CalibrationError("No faces to calibrate with".to_string()));
+    }
+    let uncalibrated = PerfectSyncConfig { calibration: Vec::new(), ..PerfectSyncConfig::default() };
+    let mut calibrated = PerfectSyncConfig { calibration: Vec::new(), ..config.clone() };
+    for (name, _) in BLENDSHAPES {
+        let neutral = samples
+            .iter()
+            .flat_map(|face| blendshapes(face, &uncalibrated))
+            .filter(|(sampled, _)| *sampled == name)
+            .map(|(_, value)| value)
+            .fold(0.0f32, f32::max);
+        let max = config
+            .calibration
+            .iter()
+            .find(|entry| entry.name == name)
+            .map_or(1.0, |entry| entry.range.max);
+        if neutral > 0.0 && neutral < max {
+            calibrated.calibration.push(BlendshapeRange {
+                name: name.to_string(),
+                range: ValueRange { min: neutral, max },
+            });
+        } else if let Some(entry) = config.calibration.iter().find(|entry| entry.name == name) {
+            calibrated.calibration.push(entry.clone());
+        }
+    }
+    Ok(calibrated)
+}
+
+/// Blendshapes the tracker can estimate, before calibration
+fn tracked_values(face: &Face) -> Vec<(&'static str, f32)> {
+    let mut values = Vec::new();
+    if let Some(expressions) = &face.expressions {
+        values.push(("BrowInnerUp", (expressions.brow_raise_left + expressions.brow_raise_right) / 2.0));
+        values.push(("BrowOuterUpLeft", expressions.brow_raise_left));
+        values.push(("BrowOuterUpRight", expressions.brow_raise_right));
+        values.push(("MouthSmileLeft", expressions.smile));
+        values.push(("MouthSmileRight", expressions.smile));
+        // Cheeks lift along with a smile
+        values.push(("CheekSquintLeft", expressions.smile));
+        values.push(("CheekSquintRight", expressions.smile));
+    }
+    if let Some(eyes) = &face.eyes {
+        values.push(("EyeBlinkLeft", 1.0 - eyes.left_eye_openness));
+        values.push(("EyeBlinkRight", 1.0 - eyes.right_eye_openness));
+    }
+    if let Some(gaze) = &face.gaze {
+        // Looking towards +X turns the left eye outwards and the right eye
+        // inwards
+        let (left_yaw, left_pitch) = gaze_angles(&gaze.left_eye_direction);
+        let (right_yaw, right_pitch) = gaze_angles(&gaze.right_eye_direction);
+        values.push(("EyeLookOutLeft", left_yaw));
+        values.push(("EyeLookInLeft", -left_yaw));
+        values.push(("EyeLookInRight", right_yaw));
+        values.push(("EyeLookOutRight", -right_yaw));
+        values.push(("EyeLookUpLeft", left_pitch));
+        values.push(("EyeLookDownLeft", -left_pitch));
+        values.push(("EyeLookUpRight", right_pitch));
+        values.push(("EyeLookDownRight", -right_pitch));
+    }
+    if let Some(mouth) = &face.mouth {
+        values.push(("JawOpen", mouth.jaw_open));
+        values.push(("MouthFunnel", mouth.funnel));
+        values.push(("MouthPucker", mouth.pucker));
+        values.push(("MouthStretchLeft", mouth.mouth_width));
+        values.push(("MouthStretchRight", mouth.mouth_width));
+    }
+    values
+}
+
+/// Yaw and upward pitch of a head-relative gaze direction, as fractions of
+/// [`EYE_LOOK_RANGE`]
+fn gaze_angles(direction: &Point3D) -> (f32, f32) {
+    let yaw = direction.x.atan2(direction.z).to_degrees();
+    let pitch = (-direction.y)
+        .atan2((direction.x * direction.x + direction.z * direction.z).sqrt())
+        .to_degrees();
+    (yaw / EYE_LOOK_RANGE, pitch / EYE_LOOK_RANGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smiling_face(smile: f32) -> Face {
+        Face {
+            id: 1,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            expressions: Some(Expressions { smile, brow_raise_left: 0.0, brow_raise_right: 0.0, mouth_open: 0.0 }),
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_full_set_with_group_toggles() {
+        let face = smiling_face(0.6);
+        let values = blendshapes(&face, &PerfectSyncConfig::default());
+        assert_eq!(values.len(), 52);
+        assert!(values.contains(&("MouthSmileLeft", 0.6)));
+        assert!(values.contains(&("TongueOut", 0.0)));
+
+        let config = PerfectSyncConfig { mouth: false, cheeks: false, ..PerfectSyncConfig::default() };
+        let values = blendshapes(&face, &config);
+        assert_eq!(values.len(), 19);
+        assert!(values.iter().all(|(name, _)| name.starts_with("Brow") || name.starts_with("Eye")));
+    }
+
+    #[test]
+    fn test_neutral_calibration() {
+        let config = capture_neutral(&[smiling_face(0.1), smiling_face(0.2)], &PerfectSyncConfig::default()).unwrap();
+        config.check().unwrap();
+        let smile = config.calibration.iter().find(|entry| entry.name == "MouthSmileLeft").unwrap();
+        assert_eq!(smile.range, ValueRange { min: 0.2, max: 1.0 });
+
+        let values = blendshapes(&smiling_face(0.6), &config);
+        let smile = values.iter().find(|(name, _)| *name == "MouthSmileLeft").unwrap().1;
+        assert!((smile - 0.5).abs() < 1e-6);
+
+        let invalid = PerfectSyncConfig {
+            calibration: vec![BlendshapeRange { name: "Smile".to_string(), range: ValueRange::default() }],
+            ..PerfectSyncConfig::default()
+        };
+        assert!(invalid.check().is_err());
+    }
+}
@@ -0,0 +1,250 @@
+//! VMC protocol output
+//!
+//! Sends head bone rotation and blendshape values as OSC bundles over UDP,
+//! following the Virtual Motion Capture protocol understood by VSeeFace,
+//! VMC-compatible avatar apps and Unity/VRM receivers.
+
+use crate::error::PluginError;
+use crate::face_tracking::humanoid::{self, BoneRotation, HumanoidBone, HumanoidConfig};
+use crate::models::*;
+use crate::protocols::perfect_sync::{self, PerfectSyncConfig};
+use crate::protocols::{eye_openness, mouth_openness, FaceOutput, FrameInfo};
+use flutter_rust_bridge::frb;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Instant;
+
+/// Name the VMC sender is registered under in the output registry
+pub const OUTPUT_NAME: &str = "vmc";
+
+/// Configuration for the VMC output
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct VmcConfig {
+    /// Receiver host name or IP address
+    pub host: String,
+    /// Receiver UDP port (39539 is the VMC default)
+    pub port: u16,
+    /// Also send blendshape values derived from landmarks
+    pub send_blendshapes: bool,
+    /// Split the head rotation over the Neck and Head bones and send eye
+    /// bones from the gaze, instead of rotating only the Head bone
+    pub bone_mapping: Option<HumanoidConfig>,
+    /// Send the 52 Perfect Sync blendshapes instead of the VRM preset
+    /// blendshapes
+    pub perfect_sync: Option<PerfectSyncConfig>,
+}
+
+impl Default for VmcConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 39539,
+            send_blendshapes: true,
+            bone_mapping: None,
+            perfect_sync: None,
+        }
+    }
+}
+
+/// UDP sender encoding faces as VMC messages
+pub struct VmcSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+    config: VmcConfig,
+    started: Instant,
+}
+
+impl VmcSender {
+    /// Create a sender bound to an ephemeral local port
+    pub fn new(config: VmcConfig) -> Result<Self, PluginError> {
+        if let Some(mapping) = &config.bone_mapping {
+            mapping.check()?;
+        }
+        if let Some(perfect_sync) = &config.perfect_sync {
+            perfect_sync.check()?;
+        }
+        let target = (config.host.as_str(), config.port)
+            .to_socket_addrs()
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Invalid VMC target: {}", e)))?
+            .next()
+            .ok_or_else(|| PluginError::InvalidConfiguration(format!("Could not resolve VMC host {}", config.host)))?;
+
+        let socket = UdpSocket::bind(("0.0.0.0", 0))
+            .map_err(|e| PluginError::NetworkError(format!("Failed to bind VMC socket: {}", e)))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| PluginError::NetworkError(e.to_string()))?;
+
+        log::info!("VMC output sending to {}", target);
+
+        Ok(Self {
+            socket,
+            target,
+            config,
+            started: Instant::now(),
+        })
+    }
+
+    /// Encode a single face as one VMC OSC bundle
+    fn encode_face(&self, face: &Face) -> Vec<u8> {
+        let mut messages = Vec::new();
+
+        messages.push(encode_message("/VMC/Ext/OK", &[OscArg::Int(1)]));
+        messages.push(encode_message(
+            "/VMC/Ext/T",
+            &[OscArg::Float(self.started.elapsed().as_secs_f32())],
+        ));
+
+        let bones = match &self.config.bone_mapping {
+            Some(mapping) => humanoid::bone_rotations(face, mapping),
+            None => face
+                .pose
+                .iter()
+                .map(|pose| BoneRotation { bone: HumanoidBone::Head, rotation: pose.rotation_quaternion })
+                .collect(),
+        };
+        for bone in bones {
+            let [qx, qy, qz, qw] = bone.rotation.to_array();
+            messages.push(encode_message(
+                "/VMC/Ext/Bone/Pos",
+                &[
+                    OscArg::Str(bone.bone.unity_name().to_string()),
+                    OscArg::Float(0.0),
+                    OscArg::Float(0.0),
+                    OscArg::Float(0.0),
+                    OscArg::Float(qx),
+                    OscArg::Float(qy),
+                    OscArg::Float(qz),
+                    OscArg::Float(qw),
+                ],
+            ));
+        }
+
+        if self.config.send_blendshapes {
+            let blendshapes = match &self.config.perfect_sync {
+                Some(perfect_sync) => perfect_sync::blendshapes(face, perfect_sync),
+                None => blendshapes_for_face(face),
+            };
+            for (name, value) in blendshapes {
+                messages.push(encode_message(
+                    "/VMC/Ext/Blend/Val",
+                    &[OscArg::Str(name.to_string()), OscArg::Float(value)],
+                ));
+            }
+            messages.push(encode_message("/VMC/Ext/Blend/Apply", &[]));
+        }
+
+        encode_bundle(&messages)
+    }
+}
+
+impl FaceOutput for VmcSender {
+    fn send_faces(&self, faces: &[Face], _frame: &FrameInfo) -> Result<(), PluginError> {
+        // VMC drives a single avatar, so only the most confident face is sent
+        let face = match faces
+            .iter()
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+        {
+            Some(face) => face,
+            None => return Ok(()),
+        };
+
+        let packet = self.encode_face(face);
+        self.socket
+            .send_to(&packet, self.target)
+            .map_err(|e| PluginError::NetworkError(format!("VMC send failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Derive basic VRM blendshape values from 68-point landmarks
+fn blendshapes_for_face(face: &Face) -> Vec<(&'static str, f32)> {
+    let landmarks = match &face.landmarks {
+        Some(landmarks) if landmarks.points.len() >= 68 => landmarks,
+        _ => return Vec::new(),
+    };
+    let (Some(left_eye), Some(right_eye)) = (landmarks.left_eye(), landmarks.right_eye()) else {
+        return Vec::new();
+    };
+
+    vec![
+        ("Blink_L", 1.0 - eye_openness(left_eye)),
+        ("Blink_R", 1.0 - eye_openness(right_eye)),
+        ("A", mouth_openness(landmarks)),
+    ]
+}
+
+/// OSC message argument
+enum OscArg {
+    Int(i32),
+    Float(f32),
+    Str(String),
+}
+
+/// Append an OSC string (null terminated, padded to 4 bytes)
+fn write_osc_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(value.as_bytes());
+    buffer.resize((buffer.len() + 1).next_multiple_of(4), 0);
+}
+
+/// Encode a single OSC message
+fn encode_message(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(64);
+    write_osc_string(&mut buffer, address);
+
+    let mut type_tags = String::from(",");
+    for arg in args {
+        type_tags.push(match arg {
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+            OscArg::Str(_) => 's',
+        });
+    }
+    write_osc_string(&mut buffer, &type_tags);
+
+    for arg in args {
+        match arg {
+            OscArg::Int(v) => buffer.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Float(v) => buffer.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Str(v) => write_osc_string(&mut buffer, v),
+        }
+    }
+
+    buffer
+}
+
+/// Wrap encoded messages in an OSC bundle with an "immediately" time tag
+fn encode_bundle(messages: &[Vec<u8>]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(16 + messages.iter().map(|m| m.len() + 4).sum::<usize>());
+    write_osc_string(&mut buffer, "#bundle");
+    buffer.extend_from_slice(&1u64.to_be_bytes());
+
+    for message in messages {
+        buffer.extend_from_slice(&(message.len() as i32).to_be_bytes());
+        buffer.extend_from_slice(message);
+    }
+
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc_message_padding() {
+        let message = encode_message("/VMC/Ext/OK", &[OscArg::Int(1)]);
+        // "/VMC/Ext/OK\0" (12) + ",i\0\0" (4) + int (4)
+        assert_eq!(message.len(), 20);
+        assert_eq!(&message[12..14], b",i");
+        assert_eq!(&message[16..], &1i32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_osc_bundle_header() {
+        let bundle = encode_bundle(&[encode_message("/VMC/Ext/Blend/Apply", &[])]);
+        assert_eq!(&bundle[0..8], b"#bundle\0");
+        assert_eq!(&bundle[8..16], &1u64.to_be_bytes());
+        assert_eq!(bundle.len() % 4, 0);
+    }
+}
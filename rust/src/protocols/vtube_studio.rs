@@ -0,0 +1,319 @@
+//! VTube Studio plugin API client
+//!
+//! Authenticates against the VTube Studio public WebSocket API and injects
+//! tracking parameters (head rotation, eye open, mouth open) for every frame.
+//! The first connection requests a plugin token, which the user has to approve
+//! inside VTube Studio; the token is returned so apps can persist it and skip
+//! the approval prompt next time.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::protocols::{eye_openness, mouth_openness, FaceOutput, FrameInfo};
+use flutter_rust_bridge::frb;
+use futures::{SinkExt, StreamExt};
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::sync::RwLock;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+/// Name the VTube Studio client is registered under in the output registry
+pub const OUTPUT_NAME: &str = "vtube_studio";
+
+/// Default VTube Studio API address
+pub const DEFAULT_URL: &str = "ws://127.0.0.1:8001";
+
+/// Developer name reported to VTube Studio
+const PLUGIN_DEVELOPER: &str = "flutter_openseeface_plugin";
+
+/// Frames waiting to be injected before new ones are dropped
+const INJECT_QUEUE_CAPACITY: usize = 2;
+
+type VtsSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Tracking value that can be mapped onto a VTube Studio parameter
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VtsSource {
+    /// Head yaw in degrees
+    HeadYaw,
+    /// Head pitch in degrees
+    HeadPitch,
+    /// Head roll in degrees
+    HeadRoll,
+    /// Left eye openness (0.0 - 1.0)
+    EyeOpenLeft,
+    /// Right eye openness (0.0 - 1.0)
+    EyeOpenRight,
+    /// Mouth openness (0.0 - 1.0)
+    MouthOpen,
+}
+
+/// Mapping from a tracking value to a VTube Studio input parameter
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VtsParameterMapping {
+    /// Tracking value to read
+    pub source: VtsSource,
+    /// VTube Studio parameter ID (e.g. "FaceAngleX")
+    pub parameter_id: String,
+    /// Multiplier applied to the source value
+    pub scale: f32,
+}
+
+impl VtsParameterMapping {
+    fn new(source: VtsSource, parameter_id: &str, scale: f32) -> Self {
+        Self {
+            source,
+            parameter_id: parameter_id.to_string(),
+            scale,
+        }
+    }
+}
+
+/// Default mapping onto the built-in VTube Studio tracking parameters
+pub fn default_parameter_mapping() -> Vec<VtsParameterMapping> {
+    vec![
+        VtsParameterMapping::new(VtsSource::HeadYaw, "FaceAngleX", 1.0),
+        VtsParameterMapping::new(VtsSource::HeadPitch, "FaceAngleY", 1.0),
+        VtsParameterMapping::new(VtsSource::HeadRoll, "FaceAngleZ", 1.0),
+        VtsParameterMapping::new(VtsSource::EyeOpenLeft, "EyeOpenLeft", 1.0),
+        VtsParameterMapping::new(VtsSource::EyeOpenRight, "EyeOpenRight", 1.0),
+        VtsParameterMapping::new(VtsSource::MouthOpen, "MouthOpen", 1.0),
+    ]
+}
+
+lazy_static! {
+    static ref PARAMETER_MAPPING: RwLock<Vec<VtsParameterMapping>> =
+        RwLock::new(default_parameter_mapping());
+}
+
+/// Replace the active parameter mapping
+pub fn set_parameter_mapping(mapping: Vec<VtsParameterMapping>) {
+    *PARAMETER_MAPPING.write().unwrap() = mapping;
+}
+
+/// Get the active parameter mapping
+pub fn parameter_mapping() -> Vec<VtsParameterMapping> {
+    PARAMETER_MAPPING.read().unwrap().clone()
+}
+
+/// Output forwarding faces to the VTube Studio injection task
+pub struct VtsOutput {
+    sender: mpsc::Sender<Option<Face>>,
+}
+
+impl FaceOutput for VtsOutput {
+    fn send_faces(&self, faces: &[Face], _frame: &FrameInfo) -> Result<(), PluginError> {
+        let face = faces
+            .iter()
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+            .cloned();
+
+        match self.sender.try_send(face) {
+            Ok(()) | Err(mpsc::error::TrySendError::Full(_)) => Ok(()),
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                Err(PluginError::NetworkError("VTube Studio connection closed".to_string()))
+            }
+        }
+    }
+}
+
+/// Connect and authenticate, returning the output and the authentication token
+///
+/// The injection task runs on the shared runtime until the connection drops
+/// or the output is removed from the registry.
+pub async fn connect(
+    url: &str,
+    plugin_name: &str,
+    auth_token: Option<String>,
+) -> Result<(VtsOutput, String), PluginError> {
+    log::info!("Connecting to VTube Studio at {}", url);
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .map_err(|e| PluginError::NetworkError(format!("VTube Studio connection failed: {}", e)))?;
+
+    let token = match auth_token {
+        Some(token) => token,
+        None => {
+            let response = request(
+                &mut socket,
+                "AuthenticationTokenRequest",
+                json!({ "pluginName": plugin_name, "pluginDeveloper": PLUGIN_DEVELOPER }),
+            )
+            .await?;
+            response["data"]["authenticationToken"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| PluginError::NetworkError("VTube Studio denied the token request".to_string()))?
+        }
+    };
+
+    let response = request(
+        &mut socket,
+        "AuthenticationRequest",
+        json!({
+            "pluginName": plugin_name,
+            "pluginDeveloper": PLUGIN_DEVELOPER,
+            "authenticationToken": token,
+        }),
+    )
+    .await?;
+
+    if response["data"]["authenticated"].as_bool() != Some(true) {
+        return Err(PluginError::NetworkError(format!(
+            "VTube Studio authentication failed: {}",
+            response["data"]["reason"].as_str().unwrap_or("unknown reason")
+        )));
+    }
+
+    log::info!("Authenticated with VTube Studio as {}", plugin_name);
+
+    let (sender, receiver) = mpsc::channel(INJECT_QUEUE_CAPACITY);
+    crate::runtime().spawn(run_injection(socket, receiver));
+
+    Ok((VtsOutput { sender }, token))
+}
+
+/// Send a request and wait for the matching response
+async fn request(socket: &mut VtsSocket, message_type: &str, data: Value) -> Result<Value, PluginError> {
+    let request_id = format!("{}-{}", message_type, chrono::Utc::now().timestamp_millis());
+    socket
+        .send(Message::Text(envelope(&request_id, message_type, data).to_string()))
+        .await
+        .map_err(|e| PluginError::NetworkError(e.to_string()))?;
+
+    while let Some(message) = socket.next().await {
+        let message = message.map_err(|e| PluginError::NetworkError(e.to_string()))?;
+        if let Message::Text(text) = message {
+            let response: Value = serde_json::from_str(&text)
+                .map_err(|e| PluginError::NetworkError(format!("Invalid VTube Studio response: {}", e)))?;
+            if response["requestID"] == request_id.as_str() {
+                if response["messageType"] == "APIError" {
+                    return Err(PluginError::NetworkError(format!(
+                        "VTube Studio API error: {}",
+                        response["data"]["message"].as_str().unwrap_or("unknown error")
+                    )));
+                }
+                return Ok(response);
+            }
+        }
+    }
+
+    Err(PluginError::NetworkError("VTube Studio closed the connection".to_string()))
+}
+
+/// Inject parameters for every queued face until the connection or queue closes
+async fn run_injection(socket: VtsSocket, mut receiver: mpsc::Receiver<Option<Face>>) {
+    let (mut write, mut read) = socket.split();
+
+    // Drain responses so the socket buffer does not fill up
+    crate::runtime().spawn(async move {
+        while let Some(Ok(_)) = read.next().await {}
+    });
+
+    let mut counter: u64 = 0;
+    while let Some(face) = receiver.recv().await {
+        counter += 1;
+        let data = injection_data(face.as_ref(), &parameter_mapping());
+        let message = envelope(&format!("inject-{}", counter), "InjectParameterDataRequest", data);
+
+        if let Err(e) = write.send(Message::Text(message.to_string())).await {
+            log::warn!("VTube Studio injection failed, disconnecting: {}", e);
+            break;
+        }
+    }
+
+    let _ = write.close().await;
+    log::info!("VTube Studio injection stopped");
+}
+
+/// Build the `InjectParameterDataRequest` payload for a face
+fn injection_data(face: Option<&Face>, mapping: &[VtsParameterMapping]) -> Value {
+    let values: Vec<Value> = match face {
+        Some(face) => mapping
+            .iter()
+            .filter_map(|m| {
+                source_value(face, m.source).map(|value| json!({ "id": m.parameter_id, "value": value * m.scale }))
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    json!({
+        "faceFound": face.is_some(),
+        "mode": "set",
+        "parameterValues": values,
+    })
+}
+
+/// Read a tracking value from a face, if available
+fn source_value(face: &Face, source: VtsSource) -> Option<f32> {
+    let landmarks = face.landmarks.as_ref().filter(|l| l.points.len() >= 68);
+
+    match source {
+        VtsSource::HeadYaw => face.pose.map(|p| p.yaw),
+        VtsSource::HeadPitch => face.pose.map(|p| p.pitch),
+        VtsSource::HeadRoll => face.pose.map(|p| p.roll),
+        // Prefer the tracker's calibrated openness over the default mapping
+        VtsSource::EyeOpenLeft => face
+            .eyes
+            .map(|e| e.left_eye_openness)
+            .or_else(|| landmarks.and_then(|l| l.left_eye()).map(eye_openness)),
+        VtsSource::EyeOpenRight => face
+            .eyes
+            .map(|e| e.right_eye_openness)
+            .or_else(|| landmarks.and_then(|l| l.right_eye()).map(eye_openness)),
+        VtsSource::MouthOpen => landmarks.map(mouth_openness),
+    }
+}
+
+/// Wrap request data in the VTube Studio API envelope
+fn envelope(request_id: &str, message_type: &str, data: Value) -> Value {
+    json!({
+        "apiName": "VTubeStudioPublicAPI",
+        "apiVersion": "1.0",
+        "requestID": request_id,
+        "messageType": message_type,
+        "data": data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_format() {
+        let message = envelope("abc", "APIStateRequest", json!({}));
+        assert_eq!(message["apiName"], "VTubeStudioPublicAPI");
+        assert_eq!(message["requestID"], "abc");
+        assert_eq!(message["messageType"], "APIStateRequest");
+    }
+
+    #[test]
+    fn test_injection_without_face() {
+        let data = injection_data(None, &default_parameter_mapping());
+        assert_eq!(data["faceFound"], false);
+        assert_eq!(data["parameterValues"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_injection_maps_pose() {
+        let face = Face {
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 1.0, height: 1.0 },
+            confidence: 1.0,
+            pose: Some(HeadPose::from_euler(5.0, -10.0, 2.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0)),
+            ..Face::default()
+        };
+
+        let mapping = vec![VtsParameterMapping::new(VtsSource::HeadYaw, "FaceAngleX", 2.0)];
+        let data = injection_data(Some(&face), &mapping);
+        let values = data["parameterValues"].as_array().unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0]["id"], "FaceAngleX");
+        assert_eq!(values[0]["value"], -20.0);
+    }
+}
@@ -0,0 +1,363 @@
+//! WebSocket server output
+//!
+//! Serves live tracking results to local clients such as OBS browser-source
+//! overlays and browser-based avatar renderers. Every processed frame is sent
+//! to each connected client as one message:
+//!
+//! ```text
+//! {"type":"frame","timestamp":1700000000000,"width":640,"height":480,"faces":[{"id":1,"confidence":0.98,"bounding_box":{...},"pose":{...},"blendshapes":{"eyeBlinkLeft":0.1,...}}]}
+//! ```
+//!
+//! Clients choose what they receive by sending a JSON text message at any
+//! time; only the keys present are changed:
+//!
+//! ```text
+//! {"subscribe":["pose","blendshapes"],"format":"msgpack","primary_only":true}
+//! ```
+//!
+//! `subscribe` lists the face fields to include besides the ID, confidence
+//! and bounding box (see [`Topic`]), `format` switches between JSON text and
+//! binary MessagePack messages, and `primary_only` limits each frame to the
+//! most confident face. Invalid subscriptions are answered with
+//! `{"type":"error","message":"..."}`.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::protocols::{arkit_blendshapes, FaceOutput, FrameInfo};
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Name the WebSocket server is registered under in the output registry
+pub const OUTPUT_NAME: &str = "websocket";
+
+/// Frames buffered per client before a slow client starts skipping frames
+const CLIENT_QUEUE_CAPACITY: usize = 4;
+
+/// Longest time a client may take to complete the WebSocket handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Face fields a client can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Topic {
+    Landmarks,
+    Pose,
+    Gaze,
+    Expressions,
+    Blendshapes,
+}
+
+impl Topic {
+    const ALL: [Topic; 5] = [Topic::Landmarks, Topic::Pose, Topic::Gaze, Topic::Expressions, Topic::Blendshapes];
+
+    fn name(self) -> &'static str {
+        match self {
+            Topic::Landmarks => "landmarks",
+            Topic::Pose => "pose",
+            Topic::Gaze => "gaze",
+            Topic::Expressions => "expressions",
+            Topic::Blendshapes => "blendshapes",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|topic| topic.name() == name)
+            .ok_or_else(|| format!("Unknown topic {}", name))
+    }
+}
+
+/// Message encoding chosen by a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Json,
+    MessagePack,
+}
+
+/// What one client receives
+#[derive(Debug, Clone, PartialEq)]
+struct Subscription {
+    topics: Vec<Topic>,
+    encoding: Encoding,
+    primary_only: bool,
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Self {
+            topics: vec![Topic::Pose, Topic::Gaze, Topic::Blendshapes],
+            encoding: Encoding::Json,
+            primary_only: false,
+        }
+    }
+}
+
+impl Subscription {
+    /// Apply a subscription request sent by the client
+    fn update(&mut self, request: &str) -> Result<(), String> {
+        let request: Value = serde_json::from_str(request).map_err(|e| format!("Invalid subscription: {}", e))?;
+        let mut updated = self.clone();
+        if let Some(topics) = request.get("subscribe") {
+            let topics = topics.as_array().ok_or("subscribe must be a list of topics")?;
+            updated.topics = topics
+                .iter()
+                .map(|topic| topic.as_str().ok_or_else(|| "Topics must be strings".to_string()).and_then(Topic::parse))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(format) = request.get("format") {
+            updated.encoding = match format.as_str() {
+                Some("json") => Encoding::Json,
+                Some("msgpack") => Encoding::MessagePack,
+                _ => return Err("format must be \"json\" or \"msgpack\"".to_string()),
+            };
+        }
+        if let Some(primary_only) = request.get("primary_only") {
+            updated.primary_only = primary_only.as_bool().ok_or("primary_only must be a boolean")?;
+        }
+        *self = updated;
+        Ok(())
+    }
+
+    /// Encode one frame for this client
+    fn encode(&self, update: &FrameUpdate) -> Message {
+        let faces: Vec<FaceMessage> = if self.primary_only {
+            update
+                .faces
+                .iter()
+                .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+                .map(|face| self.face_message(face))
+                .into_iter()
+                .collect()
+        } else {
+            update.faces.iter().map(|face| self.face_message(face)).collect()
+        };
+        let message = FrameMessage {
+            kind: "frame",
+            timestamp: update.frame.timestamp,
+            width: update.frame.width,
+            height: update.frame.height,
+            faces,
+        };
+
+        match self.encoding {
+            Encoding::Json => Message::Text(serde_json::to_string(&message).expect("frame messages serialize")),
+            Encoding::MessagePack => Message::Binary(rmp_serde::to_vec_named(&message).expect("frame messages serialize")),
+        }
+    }
+
+    fn face_message<'a>(&self, face: &'a Face) -> FaceMessage<'a> {
+        let subscribed = |topic| self.topics.contains(&topic);
+        FaceMessage {
+            id: face.id,
+            confidence: face.confidence,
+            bounding_box: &face.bounding_box,
+            label: face.label.as_deref(),
+            attention_score: face.attention_score,
+            landmarks: subscribed(Topic::Landmarks).then(|| {
+                face.landmarks.as_ref().map(|landmarks| landmarks.points.iter().map(|p| [p.x, p.y]).collect())
+            }),
+            pose: subscribed(Topic::Pose).then_some(face.pose.as_ref()),
+            gaze: subscribed(Topic::Gaze).then_some(face.gaze.as_ref()),
+            expressions: subscribed(Topic::Expressions).then_some(face.expressions.as_ref()),
+            blendshapes: subscribed(Topic::Blendshapes).then(|| arkit_blendshapes(face).into_iter().collect()),
+        }
+    }
+}
+
+/// A frame as sent to clients
+#[derive(Debug, Serialize)]
+struct FrameMessage<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    timestamp: i64,
+    width: u32,
+    height: u32,
+    faces: Vec<FaceMessage<'a>>,
+}
+
+/// A face as sent to clients
+///
+/// Subscribed topics are `Some` and sent as `null` if the face has no value
+/// for them; the others are left out.
+#[derive(Debug, Serialize)]
+struct FaceMessage<'a> {
+    id: u32,
+    confidence: f32,
+    bounding_box: &'a BoundingBox,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attention_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    landmarks: Option<Option<Vec<[f32; 2]>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pose: Option<Option<&'a HeadPose>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gaze: Option<Option<&'a EyeGaze>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expressions: Option<Option<&'a Expressions>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blendshapes: Option<BTreeMap<&'static str, f32>>,
+}
+
+/// Faces of one processed frame, shared by every client
+#[derive(Debug)]
+struct FrameUpdate {
+    faces: Vec<Face>,
+    frame: FrameInfo,
+}
+
+/// WebSocket server broadcasting every processed frame to its clients
+///
+/// The server stops when the output is dropped; connected clients are then
+/// sent a close message.
+pub struct WebSocketServer {
+    updates: broadcast::Sender<Arc<FrameUpdate>>,
+    listener: JoinHandle<()>,
+}
+
+impl WebSocketServer {
+    /// Listen on `port` of the loopback interface
+    pub fn bind(port: u16) -> Result<Self, PluginError> {
+        let bind_error = |e: std::io::Error| PluginError::NetworkError(format!("Cannot listen on port {}: {}", port, e));
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port)).map_err(bind_error)?;
+        listener.set_nonblocking(true).map_err(bind_error)?;
+        let listener = {
+            let _runtime = crate::runtime().enter();
+            TcpListener::from_std(listener).map_err(bind_error)?
+        };
+
+        let (updates, _) = broadcast::channel(CLIENT_QUEUE_CAPACITY);
+        let listener = crate::runtime().spawn(accept_clients(listener, updates.clone()));
+        info!("WebSocket server listening on ws://127.0.0.1:{}", port);
+        Ok(Self { updates, listener })
+    }
+}
+
+impl FaceOutput for WebSocketServer {
+    fn send_faces(&self, faces: &[Face], frame: &FrameInfo) -> Result<(), PluginError> {
+        if self.updates.receiver_count() > 0 {
+            let _ = self.updates.send(Arc::new(FrameUpdate { faces: faces.to_vec(), frame: *frame }));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WebSocketServer {
+    fn drop(&mut self) {
+        self.listener.abort();
+        info!("WebSocket server stopped");
+    }
+}
+
+async fn accept_clients(listener: TcpListener, updates: broadcast::Sender<Arc<FrameUpdate>>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                crate::runtime().spawn(serve_client(stream, peer, updates.subscribe()));
+            }
+            Err(e) => warn!("WebSocket server failed to accept a client: {}", e),
+        }
+    }
+}
+
+/// Send frames to one client until it disconnects or the server stops
+async fn serve_client(stream: TcpStream, peer: SocketAddr, mut updates: broadcast::Receiver<Arc<FrameUpdate>>) {
+    let socket = match tokio::time::timeout(HANDSHAKE_TIMEOUT, tokio_tungstenite::accept_async(stream)).await {
+        Ok(Ok(socket)) => socket,
+        Ok(Err(e)) => {
+            debug!("WebSocket handshake with {} failed: {}", peer, e);
+            return;
+        }
+        Err(_) => {
+            debug!("WebSocket handshake with {} timed out", peer);
+            return;
+        }
+    };
+    info!("WebSocket client {} connected", peer);
+    let (mut write, mut read) = socket.split();
+    let mut subscription = Subscription::default();
+
+    loop {
+        let message = tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => subscription.encode(&update),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("WebSocket client {} skipped {} frames", peer, skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            request = read.next() => match request {
+                Some(Ok(Message::Text(request))) => match subscription.update(&request) {
+                    Ok(()) => continue,
+                    Err(e) => Message::Text(json!({ "type": "error", "message": e }).to_string()),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by the socket itself
+                Some(Ok(_)) => continue,
+            },
+        };
+        if let Err(e) = write.send(message).await {
+            debug!("Sending to WebSocket client {} failed: {}", peer, e);
+            break;
+        }
+    }
+
+    let _ = write.close().await;
+    info!("WebSocket client {} disconnected", peer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_filters_faces() {
+        let mut subscription = Subscription::default();
+        assert!(subscription.update(r#"{"subscribe":["pose","bogus"]}"#).is_err());
+        assert_eq!(subscription, Subscription::default());
+        subscription.update(r#"{"subscribe":["pose"],"primary_only":true}"#).unwrap();
+
+        let pose = HeadPose::from_euler(10.0, 0.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0);
+        let faces = [0.4, 0.9].map(|confidence| Face {
+            id: 1,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence,
+            pose: Some(pose),
+            timestamp: 5,
+            ..Face::default()
+        });
+        let update = FrameUpdate { faces: faces.to_vec(), frame: FrameInfo { width: 640, height: 480, timestamp: 5 } };
+        let Message::Text(text) = subscription.encode(&update) else {
+            panic!("expected a text message");
+        };
+        let message: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(message["faces"].as_array().unwrap().len(), 1);
+        let face = message["faces"][0].as_object().unwrap();
+        assert_eq!(face["confidence"].as_f64(), Some(0.9));
+        assert_eq!(face["pose"]["pitch"].as_f64(), Some(10.0));
+        assert!(!face.contains_key("blendshapes"));
+
+        subscription.update(r#"{"format":"msgpack"}"#).unwrap();
+        let Message::Binary(data) = subscription.encode(&update) else {
+            panic!("expected a binary message");
+        };
+        let message: Value = rmp_serde::from_slice(&data).unwrap();
+        assert_eq!(message["type"], "frame");
+        let face = message["faces"][0].as_object().unwrap();
+        assert_eq!(face["confidence"].as_f64(), Some(0.9f32 as f64));
+        assert_eq!(face["pose"]["pitch"].as_f64(), Some(10.0));
+        assert!(!face.contains_key("blendshapes"));
+    }
+}
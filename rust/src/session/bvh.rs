@@ -0,0 +1,161 @@
+//! Head pose animation export to BVH
+//!
+//! BVH is the lowest common denominator of motion capture formats; Blender,
+//! Maya and MotionBuilder import it directly. The head pose track of one
+//! face becomes a single `Head` joint, resampled to a fixed frame rate as
+//! BVH requires. The Z axis is flipped to turn the tracker's left-handed
+//! axes into the right-handed ones importers expect; positions are relative
+//! to the first sample.
+
+use crate::error::PluginError;
+use crate::face_tracking::conventions::OutputConventions;
+use crate::models::*;
+use crate::session::RecordedFrame;
+use flutter_rust_bridge::frb;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Length of the end site above the head joint, in export units
+const HEAD_LENGTH: f32 = 20.0;
+
+/// BVH export settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BvhExportOptions {
+    /// Face whose head pose is exported; `None` = the first face recorded
+    pub face_id: Option<u32>,
+    /// Frame rate of the animation (FPS)
+    pub frame_rate: u32,
+    /// Export head movement as well as rotation, in the recorded
+    /// translation units (millimetres when intrinsics are known)
+    pub include_translation: bool,
+}
+
+impl Default for BvhExportOptions {
+    fn default() -> Self {
+        Self { face_id: None, frame_rate: 30, include_translation: false }
+    }
+}
+
+/// Write the head pose track of a session as BVH, returning the number of
+/// animation frames
+///
+/// Each animation frame holds the last pose recorded at or before its time,
+/// so frames where the face was lost repeat the previous pose.
+pub fn export(frames: &[RecordedFrame], options: &BvhExportOptions, out: &mut impl Write) -> Result<u32, PluginError> {
+    if !(1..=240).contains(&options.frame_rate) {
+        return Err(PluginError::InvalidConfiguration("BVH frame rate must be between 1 and 240".to_string()));
+    }
+    let face_id = match options.face_id {
+        Some(id) => id,
+        None => frames
+            .iter()
+            .find_map(|frame| frame.faces.iter().find(|face| face.pose.is_some()).map(|face| face.id))
+            .ok_or_else(|| PluginError::ProcessingError("The session has no head pose to export".to_string()))?,
+    };
+
+    // Head pose samples of the face, converted into BVH axes
+    let to_bvh = OutputConventions { flip_z: true, ..OutputConventions::default() };
+    let samples: Vec<(i64, HeadPose)> = frames
+        .iter()
+        .filter_map(|frame| {
+            let mut face = frame.faces.iter().find(|face| face.id == face_id && face.pose.is_some())?.clone();
+            to_bvh.apply(std::slice::from_mut(&mut face), frame.width, frame.height);
+            Some((frame.timestamp, face.pose?))
+        })
+        .collect();
+    let (start, origin) = match samples.first() {
+        Some(&(timestamp, pose)) => (timestamp, pose.translation),
+        None => return Err(PluginError::ProcessingError(format!("Face {} has no head pose to export", face_id))),
+    };
+    let end = samples.last().map_or(start, |&(timestamp, _)| timestamp);
+
+    let frame_ms = 1000.0 / options.frame_rate as f64;
+    let frame_count = ((end - start) as f64 / frame_ms).floor() as u32 + 1;
+    let write_error = |e: std::io::Error| PluginError::ProcessingError(format!("Failed to write BVH: {}", e));
+
+    let channels = match options.include_translation {
+        true => "CHANNELS 6 Xposition Yposition Zposition Yrotation Xrotation Zrotation",
+        false => "CHANNELS 3 Yrotation Xrotation Zrotation",
+    };
+    write!(
+        out,
+        "HIERARCHY\nROOT Head\n{{\n\tOFFSET 0.000000 0.000000 0.000000\n\t{}\n\tEnd Site\n\t{{\n\t\tOFFSET 0.000000 {:.6} 0.000000\n\t}}\n}}\n",
+        channels, HEAD_LENGTH
+    )
+    .map_err(write_error)?;
+    writeln!(out, "MOTION\nFrames: {}\nFrame Time: {:.6}", frame_count, frame_ms / 1000.0).map_err(write_error)?;
+
+    let mut next = 0;
+    for frame in 0..frame_count {
+        let time = start + (frame as f64 * frame_ms).round() as i64;
+        while next + 1 < samples.len() && samples[next + 1].0 <= time {
+            next += 1;
+        }
+        let pose = samples[next].1;
+
+        // Channel order Y, X, Z composes the tracker's Z-then-X-then-Y rotation
+        let mut values = Vec::with_capacity(6);
+        if options.include_translation {
+            values.extend([
+                pose.translation.x - origin.x,
+                pose.translation.y - origin.y,
+                pose.translation.z - origin.z,
+            ]);
+        }
+        values.extend([pose.yaw, pose.pitch, pose.roll]);
+        let line: Vec<String> = values.iter().map(|v| format!("{:.6}", v)).collect();
+        writeln!(out, "{}", line.join(" ")).map_err(write_error)?;
+    }
+    out.flush().map_err(write_error)?;
+    Ok(frame_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(timestamp: i64, yaw: f32) -> RecordedFrame {
+        let face = Face {
+            id: 4,
+            bounding_box: BoundingBox { x: 0.0, y: 0.0, width: 100.0, height: 100.0 },
+            confidence: 1.0,
+            pose: Some(HeadPose::from_euler(0.0, yaw, 0.0, Point3D { x: 0.0, y: 0.0, z: 500.0 }, 1.0)),
+            timestamp,
+            ..Face::default()
+        };
+        RecordedFrame { timestamp, width: 640, height: 480, faces: vec![face] }
+    }
+
+    #[test]
+    fn test_resamples_head_track() {
+        // 10 FPS recording with a gap, exported at 20 FPS
+        let frames = [frame(1_000, 0.0), frame(1_100, 10.0), frame(1_300, 20.0)];
+        let options = BvhExportOptions { frame_rate: 20, ..BvhExportOptions::default() };
+        let mut out = Vec::new();
+        assert_eq!(export(&frames, &options, &mut out).unwrap(), 7);
+
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("HIERARCHY\nROOT Head\n"));
+        assert!(text.contains("Frames: 7\nFrame Time: 0.050000\n"));
+        let motion: Vec<f32> = text
+            .lines()
+            .skip_while(|line| !line.starts_with("Frame Time"))
+            .skip(1)
+            .map(|line| line.split(' ').next().unwrap().parse().unwrap())
+            .collect();
+        // Flipping Z into right-handed axes mirrors the yaw
+        assert_eq!(motion, vec![0.0, 0.0, -10.0, -10.0, -10.0, -10.0, -20.0]);
+    }
+
+    #[test]
+    fn test_requires_a_head_pose() {
+        let mut empty = frame(1_000, 0.0);
+        empty.faces[0].pose = None;
+        assert!(export(&[empty], &BvhExportOptions::default(), &mut Vec::new()).is_err());
+
+        let other_face = BvhExportOptions { face_id: Some(9), ..BvhExportOptions::default() };
+        assert!(export(&[frame(1_000, 0.0)], &other_face, &mut Vec::new()).is_err());
+    }
+}
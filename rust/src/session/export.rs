@@ -0,0 +1,173 @@
+//! Session export
+//!
+//! Converts a recorded session into formats standard tools read directly:
+//! CSV with one row per face per frame and a fixed set of columns (pose, key
+//! landmarks and expression values), or JSON Lines with every face in full.
+//! Values a face does not have are left empty in CSV.
+
+use crate::error::PluginError;
+use crate::models::*;
+use crate::session::RecordedFrame;
+use flutter_rust_bridge::frb;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// File format of an exported session
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON `Face` per line
+    JsonLines,
+}
+
+/// Landmarks exported to CSV, by name and 68-point index
+const KEY_LANDMARKS: [(&str, usize); 8] = [
+    ("chin", 8),
+    ("nose_tip", 30),
+    ("right_eye_outer", 36),
+    ("right_eye_inner", 39),
+    ("left_eye_inner", 42),
+    ("left_eye_outer", 45),
+    ("mouth_right", 48),
+    ("mouth_left", 54),
+];
+
+/// Write `frames` to `out` in `format`, returning the number of face rows
+pub fn export(frames: &[RecordedFrame], format: SessionExportFormat, out: &mut impl Write) -> Result<u64, PluginError> {
+    let write_error = |e: std::io::Error| PluginError::ProcessingError(format!("Failed to write export: {}", e));
+
+    if format == SessionExportFormat::Csv {
+        writeln!(out, "{}", csv_header()).map_err(write_error)?;
+    }
+    let mut rows = 0;
+    for frame in frames {
+        for face in &frame.faces {
+            let line = match format {
+                SessionExportFormat::Csv => csv_row(frame, face),
+                SessionExportFormat::JsonLines => serde_json::to_string(face)
+                    .map_err(|e| PluginError::ProcessingError(format!("Failed to serialize face: {}", e)))?,
+            };
+            writeln!(out, "{}", line).map_err(write_error)?;
+            rows += 1;
+        }
+    }
+    out.flush().map_err(write_error)?;
+    Ok(rows)
+}
+
+fn csv_header() -> String {
+    let mut columns: Vec<String> = [
+        "timestamp", "frame_width", "frame_height", "face_id", "confidence", "bbox_x", "bbox_y", "bbox_width",
+        "bbox_height", "pitch", "yaw", "roll", "translation_x", "translation_y", "translation_z",
+    ]
+    .map(String::from)
+    .to_vec();
+    for (name, _) in KEY_LANDMARKS {
+        columns.push(format!("{}_x", name));
+        columns.push(format!("{}_y", name));
+    }
+    columns.extend(
+        [
+            "smile", "brow_raise_left", "brow_raise_right", "mouth_open", "left_eye_openness", "right_eye_openness",
+            "jaw_open", "mouth_width", "recognized_name",
+        ]
+        .map(String::from),
+    );
+    columns.join(",")
+}
+
+fn csv_row(frame: &RecordedFrame, face: &Face) -> String {
+    let mut row = format!("{},{},{},{},{}", frame.timestamp, frame.width, frame.height, face.id, face.confidence);
+    let mut push = |value: Option<f32>| {
+        row.push(',');
+        if let Some(value) = value {
+            let _ = write!(row, "{}", value);
+        }
+    };
+
+    let bbox = face.bounding_box;
+    [bbox.x, bbox.y, bbox.width, bbox.height].into_iter().for_each(|v| push(Some(v)));
+    let pose = face.pose.as_ref();
+    push(pose.map(|p| p.pitch));
+    push(pose.map(|p| p.yaw));
+    push(pose.map(|p| p.roll));
+    push(pose.map(|p| p.translation.x));
+    push(pose.map(|p| p.translation.y));
+    push(pose.map(|p| p.translation.z));
+    for (_, index) in KEY_LANDMARKS {
+        let point = face.landmarks.as_ref().and_then(|landmarks| landmarks.points.get(index));
+        push(point.map(|p| p.x));
+        push(point.map(|p| p.y));
+    }
+    let expressions = face.expressions.as_ref();
+    push(expressions.map(|e| e.smile));
+    push(expressions.map(|e| e.brow_raise_left));
+    push(expressions.map(|e| e.brow_raise_right));
+    push(expressions.map(|e| e.mouth_open));
+    push(face.eyes.map(|e| e.left_eye_openness));
+    push(face.eyes.map(|e| e.right_eye_openness));
+    push(face.mouth.map(|m| m.jaw_open));
+    push(face.mouth.map(|m| m.mouth_width));
+
+    row.push(',');
+    if let Some(name) = &face.recognized_name {
+        row.push_str(&csv_field(name));
+    }
+    row
+}
+
+/// Quote a text field if it contains separators, quotes or line breaks
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(id: u32, name: Option<&str>) -> Face {
+        Face {
+            id,
+            bounding_box: BoundingBox { x: 10.0, y: 20.0, width: 100.0, height: 120.0 },
+            confidence: 0.9,
+            pose: Some(HeadPose::from_euler(5.0, -10.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 50.0 }, 1.0)),
+            recognized_name: name.map(String::from),
+            timestamp: 1_000,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_csv_has_one_row_per_face() {
+        let frames = [
+            RecordedFrame { timestamp: 1_000, width: 640, height: 480, faces: vec![face(0, None), face(1, Some("Smith, J"))] },
+            RecordedFrame { timestamp: 1_033, width: 640, height: 480, faces: Vec::new() },
+        ];
+        let mut out = Vec::new();
+        assert_eq!(export(&frames, SessionExportFormat::Csv, &mut out).unwrap(), 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let columns = lines[0].split(',').count();
+        assert!(lines[1].starts_with("1000,640,480,0,0.9,10,20,100,120,5,-10,0,0,0,50,"), "{}", lines[1]);
+        assert_eq!(lines[1].split(',').count(), columns);
+        assert!(lines[2].ends_with(",\"Smith, J\""), "{}", lines[2]);
+    }
+
+    #[test]
+    fn test_json_lines_round_trip() {
+        let frames = [RecordedFrame { timestamp: 1_000, width: 640, height: 480, faces: vec![face(3, None)] }];
+        let mut out = Vec::new();
+        export(&frames, SessionExportFormat::JsonLines, &mut out).unwrap();
+
+        let parsed: Face = serde_json::from_slice(out.trim_ascii_end()).unwrap();
+        assert_eq!(parsed, face(3, None));
+    }
+}
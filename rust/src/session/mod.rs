@@ -0,0 +1,91 @@
+//! Recorded tracking sessions
+//!
+//! A session file holds the faces of every processed frame as JSON Lines,
+//! one [`RecordedFrame`] per line, in the tracker's own coordinate
+//! conventions. The [`recorder::SessionRecorder`] writes them as a network
+//! output would send them, and [`replay`] feeds them back through the output
+//! streams, so app UI and avatar mapping can be worked on without a camera.
+//! [`export`] converts sessions for analysis in other tools and [`bvh`] turns
+//! their head pose into an animation.
+
+pub mod bvh;
+pub mod export;
+pub mod recorder;
+pub mod replay;
+
+use crate::error::PluginError;
+use crate::models::Face;
+use crate::protocols::FrameInfo;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use std::path::Path;
+
+/// Faces of one frame of a recorded session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Frame timestamp (ms since the Unix epoch)
+    pub timestamp: i64,
+    /// Frame width in pixels
+    pub width: u32,
+    /// Frame height in pixels
+    pub height: u32,
+    /// Faces tracked in the frame
+    pub faces: Vec<Face>,
+}
+
+impl RecordedFrame {
+    /// Metadata of the recorded frame
+    pub fn info(&self) -> FrameInfo {
+        FrameInfo { width: self.width, height: self.height, timestamp: self.timestamp }
+    }
+}
+
+/// Read every frame of the session file at `path`
+///
+/// Blank lines are skipped; a line that is not a recorded frame fails the
+/// whole session, naming the line.
+pub fn load(path: &Path) -> Result<Vec<RecordedFrame>, PluginError> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| PluginError::InvalidConfiguration(format!("Cannot open session {}: {}", path.display(), e)))?;
+    parse(std::io::BufReader::new(file))
+}
+
+fn parse(reader: impl BufRead) -> Result<Vec<RecordedFrame>, PluginError> {
+    let mut frames = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| PluginError::ProcessingError(format!("Failed to read session: {}", e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = serde_json::from_str(&line).map_err(|e| {
+            PluginError::ProcessingError(format!("Invalid session frame on line {}: {}", number + 1, e))
+        })?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocols::FaceOutput;
+    use crate::session::recorder::SessionRecorder;
+
+    #[test]
+    fn test_recorded_session_loads_back() {
+        let path = std::env::temp_dir().join(format!("osf_session_{}.jsonl", std::process::id()));
+        let recorder = SessionRecorder::create(&path).unwrap();
+        for timestamp in [1_000, 1_033] {
+            recorder.send_faces(&[], &FrameInfo { width: 640, height: 480, timestamp }).unwrap();
+        }
+        drop(recorder);
+
+        let frames = load(&path).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1], RecordedFrame { timestamp: 1_033, width: 640, height: 480, faces: Vec::new() });
+
+        let error = parse("{}\n".as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("line 1"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,51 @@
+//! Session recording
+//!
+//! The recorder is registered as a face output, so it sees exactly what the
+//! network outputs send: every processed frame, before the app's output
+//! conventions are applied.
+
+use crate::error::PluginError;
+use crate::models::Face;
+use crate::protocols::{FaceOutput, FrameInfo};
+use crate::session::RecordedFrame;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Name the recorder is registered under
+pub const OUTPUT_NAME: &str = "session_recorder";
+
+/// Writes processed frames to a session file
+pub struct SessionRecorder {
+    // Each frame is flushed as it is written, so a crash loses at most one
+    writer: Mutex<LineWriter<File>>,
+}
+
+impl SessionRecorder {
+    /// Create (or truncate) the session file at `path`
+    pub fn create(path: &Path) -> Result<Self, PluginError> {
+        let file = File::create(path)
+            .map_err(|e| PluginError::InvalidConfiguration(format!("Cannot create session {}: {}", path.display(), e)))?;
+        Ok(Self { writer: Mutex::new(LineWriter::new(file)) })
+    }
+}
+
+impl FaceOutput for SessionRecorder {
+    fn send_faces(&self, faces: &[Face], frame: &FrameInfo) -> Result<(), PluginError> {
+        let recorded = RecordedFrame {
+            timestamp: frame.timestamp,
+            width: frame.width,
+            height: frame.height,
+            faces: faces.to_vec(),
+        };
+        let mut line = serde_json::to_vec(&recorded)
+            .map_err(|e| PluginError::ProcessingError(format!("Failed to serialize frame: {}", e)))?;
+        line.push(b'\n');
+        self.writer
+            .lock()
+            .unwrap()
+            .write_all(&line)
+            .map_err(|e| PluginError::ProcessingError(format!("Failed to write session: {}", e)))
+    }
+}
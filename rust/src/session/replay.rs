@@ -0,0 +1,110 @@
+//! Session replay
+//!
+//! A recorded session is played back on the shared runtime with its
+//! original frame timing, optionally sped up. Each frame goes to the active
+//! network outputs and, in the app's output conventions, to the replay's face
+//! stream, just like a live frame. One replay runs at a time; starting
+//! another stops it.
+
+use crate::error::PluginError;
+use crate::face_tracking::conventions::OutputConventions;
+use crate::models::Face;
+use crate::session::RecordedFrame;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+
+/// Fastest supported playback, as a multiple of the original speed
+pub const MAX_SPEED: f32 = 100.0;
+
+lazy_static! {
+    static ref REPLAY_WORKER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Time after the start of playback at which each frame is due
+pub fn schedule(frames: &[RecordedFrame], speed: f32) -> Result<Vec<Duration>, PluginError> {
+    if !(speed > 0.0 && speed <= MAX_SPEED) {
+        return Err(PluginError::InvalidConfiguration(format!(
+            "Replay speed must be greater than 0 and at most {}",
+            MAX_SPEED
+        )));
+    }
+    let first = frames.first().map_or(0, |frame| frame.timestamp);
+    // Out-of-order timestamps play immediately instead of going back in time
+    let mut latest = 0;
+    Ok(frames
+        .iter()
+        .map(|frame| {
+            latest = latest.max(frame.timestamp - first);
+            Duration::from_secs_f64(latest as f64 / 1000.0 / speed as f64)
+        })
+        .collect())
+}
+
+/// Play `frames` back at `speed`, sending each to the network outputs and,
+/// converted into `conventions`, to `send`
+///
+/// Stops early once `send` reports the app's stream closed. Replaces any
+/// replay still running.
+pub fn start<S>(frames: Vec<RecordedFrame>, speed: f32, conventions: OutputConventions, send: S) -> Result<(), PluginError>
+where
+    S: Fn(Vec<Face>) -> bool + Send + 'static,
+{
+    let due = schedule(&frames, speed)?;
+    let worker = crate::runtime().spawn(async move {
+        let start = Instant::now();
+        for (frame, offset) in frames.into_iter().zip(due) {
+            tokio::time::sleep_until(start + offset).await;
+
+            let info = frame.info();
+            crate::protocols::broadcast_faces(&frame.faces, &info);
+            let mut faces = frame.faces;
+            conventions.apply(&mut faces, info.width, info.height);
+            if !send(faces) {
+                log::debug!("Replay stream closed, stopping replay");
+                return;
+            }
+        }
+        log::info!("Session replay finished");
+    });
+
+    if let Some(previous) = REPLAY_WORKER.lock().unwrap().replace(worker) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+/// Stop the running replay, returning whether one was running
+pub fn stop() -> bool {
+    match REPLAY_WORKER.lock().unwrap().take() {
+        Some(worker) => {
+            let running = !worker.is_finished();
+            worker.abort();
+            running
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(timestamp: i64) -> RecordedFrame {
+        RecordedFrame { timestamp, width: 640, height: 480, faces: Vec::new() }
+    }
+
+    #[test]
+    fn test_schedule_keeps_frame_timing() {
+        let frames = [frame(1_000), frame(1_033), frame(1_020), frame(1_100)];
+        let due = schedule(&frames, 1.0).unwrap();
+        assert_eq!(due, [0, 33, 33, 100].map(Duration::from_millis));
+
+        let fast = schedule(&frames, 2.0).unwrap();
+        assert_eq!(fast[3], Duration::from_millis(50));
+
+        assert!(schedule(&frames, 0.0).is_err());
+        assert!(schedule(&frames, f32::NAN).is_err());
+    }
+}
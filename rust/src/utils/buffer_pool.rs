@@ -0,0 +1,158 @@
+//! Reusable byte buffers for frame processing
+//!
+//! Converting a camera frame needs several megabytes of scratch memory, and
+//! allocating it fresh for every frame shows up in profiles on mobile devices.
+//! Buffers are returned to the pool once a frame is done and handed out again
+//! for later frames. They are bucketed by power-of-two capacity, so frames of
+//! the same resolution always reuse the same bucket.
+
+use image::DynamicImage;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Buffers kept per size bucket; a few frames can be in flight at once
+pub const MAX_BUFFERS_PER_BUCKET: usize = 4;
+
+lazy_static! {
+    /// Pool shared by frame conversion and batch processing
+    pub static ref FRAME_BUFFERS: BufferPool = BufferPool::new(MAX_BUFFERS_PER_BUCKET);
+}
+
+/// Size-bucketed pool of `Vec<u8>` buffers
+pub struct BufferPool {
+    /// Free buffers keyed by the power of two their capacity is at least
+    buckets: Mutex<HashMap<usize, Vec<Vec<u8>>>>,
+    max_per_bucket: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl BufferPool {
+    pub fn new(max_per_bucket: usize) -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+            max_per_bucket,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Get an empty buffer with room for at least `capacity` bytes
+    pub fn acquire(&self, capacity: usize) -> Vec<u8> {
+        let bucket = capacity.next_power_of_two();
+
+        let reused = self
+            .buckets
+            .lock()
+            .unwrap()
+            .get_mut(&bucket)
+            .and_then(|buffers| buffers.pop());
+
+        match reused {
+            Some(mut buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer.clear();
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(bucket)
+            }
+        }
+    }
+
+    /// Get a zero-filled buffer of exactly `len` bytes
+    pub fn acquire_zeroed(&self, len: usize) -> Vec<u8> {
+        let mut buffer = self.acquire(len);
+        buffer.resize(len, 0);
+        buffer
+    }
+
+    /// Return a buffer to the pool
+    ///
+    /// Buffers are dropped instead if their bucket is already full.
+    pub fn release(&self, buffer: Vec<u8>) {
+        let capacity = buffer.capacity();
+        if capacity == 0 {
+            return;
+        }
+
+        // Largest power of two the buffer can serve
+        let bucket = 1usize << (usize::BITS - 1 - capacity.leading_zeros());
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let buffers = buckets.entry(bucket).or_default();
+        if buffers.len() < self.max_per_bucket {
+            buffers.push(buffer);
+        }
+    }
+
+    /// Fraction of acquisitions served from the pool (0.0 - 1.0)
+    pub fn hit_rate(&self) -> f32 {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let total = hits + self.misses.load(Ordering::Relaxed);
+        if total == 0 {
+            0.0
+        } else {
+            hits as f32 / total as f32
+        }
+    }
+
+    /// Drop all pooled buffers and reset the hit statistics
+    pub fn clear(&self) {
+        self.buckets.lock().unwrap().clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Return the pixel buffer of a converted RGB frame to the shared pool
+pub fn recycle_image(image: DynamicImage) {
+    if let DynamicImage::ImageRgb8(rgb_image) = image {
+        FRAME_BUFFERS.release(rgb_image.into_raw());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_released_buffer_is_reused() {
+        let pool = BufferPool::new(2);
+
+        let buffer = pool.acquire(1000);
+        assert!(buffer.capacity() >= 1000);
+        let pointer = buffer.as_ptr();
+        pool.release(buffer);
+
+        // Any size in the same bucket gets the same allocation back
+        let buffer = pool.acquire(600);
+        assert_eq!(buffer.as_ptr(), pointer);
+        assert!(buffer.is_empty());
+        assert_eq!(pool.hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_foreign_buffer_serves_smaller_bucket() {
+        let pool = BufferPool::new(2);
+
+        // 3000 bytes can serve requests up to 2048
+        pool.release(Vec::with_capacity(3000));
+        assert_eq!(pool.acquire(4000).capacity(), 4096);
+        assert!(pool.acquire(2000).capacity() >= 3000);
+    }
+
+    #[test]
+    fn test_bucket_size_is_bounded() {
+        let pool = BufferPool::new(1);
+
+        pool.release(Vec::with_capacity(64));
+        pool.release(Vec::with_capacity(64));
+        pool.acquire(64);
+        pool.acquire(64);
+        assert_eq!(pool.hit_rate(), 0.5);
+    }
+}
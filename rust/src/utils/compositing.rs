@@ -0,0 +1,135 @@
+//! Virtual background compositing
+//!
+//! Replaces the real background of a camera frame with an image supplied by
+//! the app, keyed by the person matte of the frame. Blending every pixel of
+//! a camera frame is too slow to do in Dart at frame rate, so the app passes
+//! the frame and background once and displays the returned RGBA image.
+
+use crate::error::PluginError;
+use crate::face_tracking::color;
+use crate::face_tracking::orientation::FrameOrientation;
+use crate::face_tracking::segmentation::SegmentationMatte;
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+
+/// Image shown behind the person
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct BackgroundImage {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major RGBA pixels; transparent areas let the app's own
+    /// background show through the returned frame
+    pub rgba: Vec<u8>,
+}
+
+impl BackgroundImage {
+    /// Validate that the pixels match the dimensions
+    pub fn validate(&self) -> Result<(), PluginError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(PluginError::InvalidConfiguration("Background image must not be empty".to_string()));
+        }
+        if self.rgba.len() != self.width as usize * self.height as usize * 4 {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Background image has {} bytes, expected {} for {}x{} RGBA",
+                self.rgba.len(),
+                self.width as usize * self.height as usize * 4,
+                self.width,
+                self.height
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Composite the person in `frame` over `background`, returning RGBA
+/// pixels of the frame size
+///
+/// The background is scaled to cover the upright frame, cropping what
+/// sticks out, and turned with the frame so it appears upright wherever
+/// the frame is displayed upright. `mirrored` must match
+/// `TrackerConfig::mirror_input`.
+pub fn composite(
+    frame: &CameraFrame,
+    matte: &SegmentationMatte,
+    background: &BackgroundImage,
+    mirrored: bool,
+) -> Result<Vec<u8>, PluginError> {
+    background.validate()?;
+    let orientation = FrameOrientation::new(frame.rotation, mirrored)?;
+    let rgb = color::to_rgb(&frame.image_data, frame.width, frame.height, frame.format)?;
+    if rgb.len() < frame.width as usize * frame.height as usize * 3 {
+        return Err(PluginError::ImageConversion("Frame data is smaller than its dimensions".to_string()));
+    }
+    if matte.width == 0 || matte.height == 0 || matte.alpha.len() != matte.width as usize * matte.height as usize {
+        return Err(PluginError::InvalidConfiguration("Segmentation matte does not match its dimensions".to_string()));
+    }
+
+    let backdrop = RgbaImage::from_raw(background.width, background.height, background.rgba.clone())
+        .ok_or_else(|| PluginError::InvalidConfiguration("Background image does not match its dimensions".to_string()))?;
+    let (upright_width, upright_height) = match frame.rotation {
+        90 | 270 => (frame.height, frame.width),
+        _ => (frame.width, frame.height),
+    };
+    let backdrop = DynamicImage::ImageRgba8(cover(&backdrop, upright_width, upright_height));
+    let backdrop = orientation.unmap_image(backdrop).into_rgba8();
+
+    let image = RgbaImage::from_fn(frame.width, frame.height, |x, y| {
+        let i = (y as usize * frame.width as usize + x as usize) * 3;
+        let person = matte.sample(x, y, frame.width, frame.height) as u32;
+        let behind = backdrop.get_pixel(x, y);
+        // Person over the background, both with straight alpha
+        let behind_alpha = behind[3] as u32 * (255 - person) / 255;
+        let alpha = person + behind_alpha;
+        if alpha == 0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let channel = |c: usize| ((rgb[i + c] as u32 * person + behind[c] as u32 * behind_alpha) / alpha) as u8;
+        Rgba([channel(0), channel(1), channel(2), alpha as u8])
+    });
+    Ok(image.into_raw())
+}
+
+/// Scale `image` to cover `width` x `height`, cropping the overhang evenly
+fn cover(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let scale = (width as f32 / image.width() as f32).max(height as f32 / image.height() as f32);
+    let scaled_width = ((image.width() as f32 * scale).round() as u32).max(width);
+    let scaled_height = ((image.height() as f32 * scale).round() as u32).max(height);
+    let scaled = imageops::resize(image, scaled_width, scaled_height, imageops::FilterType::Triangle);
+    imageops::crop_imm(&scaled, (scaled_width - width) / 2, (scaled_height - height) / 2, width, height).to_image()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_out_background() {
+        let frame = CameraFrame {
+            image_data: vec![100; 4 * 2 * 3],
+            width: 4,
+            height: 2,
+            format: ImageFormat::RGB,
+            timestamp: 0,
+            rotation: 0,
+            intrinsics: None,
+        };
+        // Person on the left half, at half the frame resolution
+        let matte = SegmentationMatte { timestamp: 0, width: 2, height: 1, alpha: vec![255, 0] };
+        // Red on the left, transparent on the right
+        let background = BackgroundImage {
+            width: 2,
+            height: 1,
+            rgba: vec![255, 0, 0, 255, 0, 0, 0, 0],
+        };
+        let pixels = composite(&frame, &matte, &background, false).unwrap();
+        let image = RgbaImage::from_raw(4, 2, pixels).unwrap();
+
+        assert_eq!(*image.get_pixel(0, 1), Rgba([100, 100, 100, 255]));
+        assert_eq!(image.get_pixel(3, 0)[3], 0);
+
+        let invalid = BackgroundImage { width: 2, height: 2, rgba: vec![0; 4] };
+        assert!(composite(&frame, &matte, &invalid, false).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! Process memory statistics
+
+/// Peak resident memory of the process so far, in bytes
+///
+/// Returns `None` on platforms without `getrusage`.
+pub fn peak_resident_bytes() -> Option<u64> {
+    #[cfg(unix)]
+    {
+        // SAFETY: getrusage only writes into the zeroed struct passed to it
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+            return None;
+        }
+
+        let max_rss = usage.ru_maxrss.max(0) as u64;
+        // Apple platforms report bytes, everything else kilobytes
+        if cfg!(any(target_os = "macos", target_os = "ios")) {
+            Some(max_rss)
+        } else {
+            Some(max_rss * 1024)
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reports_peak_memory() {
+        assert!(peak_resident_bytes().unwrap() > 0);
+    }
+}
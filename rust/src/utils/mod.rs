@@ -0,0 +1,14 @@
+//! Shared utilities
+//!
+//! Helpers used across the tracking pipeline that do not belong to a single
+//! processing stage.
+
+pub mod buffer_pool;
+pub mod compositing;
+pub mod memory;
+pub mod overlay;
+pub mod panic;
+pub mod privacy;
+pub mod profiling;
+pub mod shared_buffer;
+pub mod threading;
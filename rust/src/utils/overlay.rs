@@ -0,0 +1,196 @@
+//! Debug overlay rendering
+//!
+//! Draws tracking results onto a copy of the frame, so the app can show
+//! diagnostics by displaying one RGBA image instead of drawing 68 landmarks
+//! per face per frame in Dart.
+
+use crate::error::PluginError;
+use crate::face_tracking::color;
+use crate::face_tracking::orientation::FrameOrientation;
+use crate::models::*;
+use flutter_rust_bridge::frb;
+use image::{Rgba, RgbaImage};
+
+const BOX_COLOR: Rgba<u8> = Rgba([0, 255, 0, 255]);
+const LANDMARK_COLOR: Rgba<u8> = Rgba([255, 255, 0, 255]);
+const AXIS_COLORS: [Rgba<u8>; 3] = [Rgba([255, 0, 0, 255]), Rgba([0, 255, 0, 255]), Rgba([0, 0, 255, 255])];
+const GAZE_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// What the debug overlay draws
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct OverlayOptions {
+    /// Face bounding boxes
+    pub bounding_boxes: bool,
+    /// Landmark points
+    pub landmarks: bool,
+    /// Head pose axes (X red, Y green, Z blue) from the nose tip
+    pub pose_axes: bool,
+    /// Gaze rays from the eye centers
+    pub gaze: bool,
+    /// Line width and landmark point diameter in pixels
+    pub line_width: u32,
+}
+
+impl Default for OverlayOptions {
+    fn default() -> Self {
+        Self {
+            bounding_boxes: true,
+            landmarks: true,
+            pose_axes: true,
+            gaze: true,
+            line_width: 2,
+        }
+    }
+}
+
+/// Render `faces` onto `frame`, returning RGBA pixels of the frame size
+///
+/// Face coordinates are expected in pixels of the frame as delivered by the
+/// camera, which is what the tracker returns with the default output
+/// conventions. `mirrored` must match `TrackerConfig::mirror_input`, so pose
+/// axes and gaze rays point the way the tracker saw them.
+pub fn render(frame: &CameraFrame, faces: &[Face], options: &OverlayOptions, mirrored: bool) -> Result<Vec<u8>, PluginError> {
+    let orientation = FrameOrientation::new(frame.rotation, mirrored)?;
+    let rgb = color::to_rgb(&frame.image_data, frame.width, frame.height, frame.format)?;
+    if rgb.len() < frame.width as usize * frame.height as usize * 3 {
+        return Err(PluginError::ImageConversion("Frame data is smaller than its dimensions".to_string()));
+    }
+    let mut image = RgbaImage::from_fn(frame.width, frame.height, |x, y| {
+        let i = (y as usize * frame.width as usize + x as usize) * 3;
+        Rgba([rgb[i], rgb[i + 1], rgb[i + 2], 255])
+    });
+
+    let mut canvas = Canvas { image: &mut image, radius: options.line_width.max(1) as f32 / 2.0 };
+    for face in faces {
+        canvas.draw_face(face, options, &orientation);
+    }
+    Ok(image.into_raw())
+}
+
+/// Image being drawn on with a fixed stroke width
+struct Canvas<'a> {
+    image: &'a mut RgbaImage,
+    radius: f32,
+}
+
+impl Canvas<'_> {
+    fn draw_face(&mut self, face: &Face, options: &OverlayOptions, orientation: &FrameOrientation) {
+        let bounding_box = face.bounding_box;
+        let landmarks = face.landmarks.as_ref().map_or(&[][..], |landmarks| &landmarks.points[..]);
+        // Rays and axes scale with the face
+        let length = bounding_box.width.max(bounding_box.height) * 0.5;
+
+        if options.bounding_boxes {
+            let (x0, y0) = (bounding_box.x, bounding_box.y);
+            let (x1, y1) = (x0 + bounding_box.width, y0 + bounding_box.height);
+            let corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)];
+            for pair in corners.windows(2) {
+                self.line(pair[0], pair[1], BOX_COLOR);
+            }
+        }
+
+        if options.landmarks {
+            for point in landmarks {
+                self.disk((point.x, point.y), self.radius, LANDMARK_COLOR);
+            }
+        }
+
+        if let (true, Some(pose)) = (options.pose_axes, &face.pose) {
+            let origin = match landmarks.get(30) {
+                Some(nose_tip) => (nose_tip.x, nose_tip.y),
+                None => (bounding_box.x + bounding_box.width / 2.0, bounding_box.y + bounding_box.height / 2.0),
+            };
+            let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+            for (axis, color) in axes.into_iter().zip(AXIS_COLORS) {
+                let [x, y, _] = rotate(pose.rotation_quaternion, axis);
+                // The pose is Y-up, the image Y-down
+                let (dx, dy) = orientation.unmap_direction(x, -y);
+                self.line(origin, (origin.0 + dx * length, origin.1 + dy * length), color);
+            }
+        }
+
+        if let (true, Some(gaze), true) = (options.gaze, &face.gaze, landmarks.len() >= 48) {
+            for (eye, direction) in [(&landmarks[36..42], gaze.right_eye_direction), (&landmarks[42..48], gaze.left_eye_direction)] {
+                let center = (
+                    eye.iter().map(|p| p.x).sum::<f32>() / eye.len() as f32,
+                    eye.iter().map(|p| p.y).sum::<f32>() / eye.len() as f32,
+                );
+                let (dx, dy) = orientation.unmap_direction(direction.x, direction.y);
+                self.line(center, (center.0 + dx * length, center.1 + dy * length), GAZE_COLOR);
+            }
+        }
+    }
+
+    /// Thick line drawn as overlapping disks
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), color: Rgba<u8>) {
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let point = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+            self.disk(point, self.radius, color);
+        }
+    }
+
+    fn disk(&mut self, center: (f32, f32), radius: f32, color: Rgba<u8>) {
+        let (width, height) = self.image.dimensions();
+        let reach = radius + 0.5;
+        let x_range = (center.0 - reach).floor().max(0.0) as i64..=((center.0 + reach).ceil() as i64).min(width as i64 - 1);
+        let y_range = (center.1 - reach).floor().max(0.0) as i64..=((center.1 + reach).ceil() as i64).min(height as i64 - 1);
+        for y in y_range {
+            for x in x_range.clone() {
+                let (dx, dy) = (x as f32 + 0.5 - center.0, y as f32 + 0.5 - center.1);
+                if dx * dx + dy * dy <= reach * reach {
+                    self.image.put_pixel(x as u32, y as u32, color);
+                }
+            }
+        }
+    }
+}
+
+/// Rotate `v` by the unit quaternion `q`
+fn rotate(q: Quaternion, v: [f32; 3]) -> [f32; 3] {
+    let p = Quaternion { x: v[0], y: v[1], z: v[2], w: 0.0 };
+    let r = q.multiply(p).multiply(q.conjugate());
+    [r.x, r.y, r.z]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draws_box_and_landmarks() {
+        let frame = CameraFrame {
+            image_data: vec![0; 40 * 30 * 3],
+            width: 40,
+            height: 30,
+            format: ImageFormat::RGB,
+            timestamp: 0,
+            rotation: 0,
+            intrinsics: None,
+        };
+        let face = Face {
+            id: 1,
+            bounding_box: BoundingBox { x: 5.0, y: 5.0, width: 20.0, height: 20.0 },
+            confidence: 1.0,
+            // Points outside the frame are clipped
+            landmarks: Some(FacialLandmarks {
+                points: vec![Point2D { x: 10.0, y: 20.0 }, Point2D { x: -8.0, y: 100.0 }],
+                confidences: vec![1.0; 2],
+            }),
+            pose: Some(HeadPose::from_euler(0.0, 0.0, 0.0, Point3D { x: 0.0, y: 0.0, z: 0.0 }, 1.0)),
+            ..Face::default()
+        };
+        let options = OverlayOptions { line_width: 1, ..OverlayOptions::default() };
+        let pixels = render(&frame, &[face], &options, false).unwrap();
+        let image = RgbaImage::from_raw(40, 30, pixels).unwrap();
+
+        assert_eq!(*image.get_pixel(15, 5), BOX_COLOR);
+        assert_eq!(*image.get_pixel(25, 20), BOX_COLOR);
+        assert_eq!(*image.get_pixel(10, 20), LANDMARK_COLOR);
+        assert_eq!(*image.get_pixel(2, 2), Rgba([0, 0, 0, 255]));
+        // Without a nose tip landmark the X axis starts at the box center
+        assert_eq!(*image.get_pixel(20, 15), AXIS_COLORS[0]);
+    }
+}
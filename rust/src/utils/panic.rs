@@ -0,0 +1,145 @@
+//! Panic containment
+//!
+//! A panic that crosses the FFI boundary aborts the whole app. Every exported
+//! API function and worker loop therefore runs its work through [`guard`] or
+//! [`guard_async`], which turn a panic into [`PluginError::Internal`] carrying
+//! the panic message and a backtrace. Exports that cannot return an error
+//! use [`guard_or`] instead. Tracker state lives behind tokio locks,
+//! which are released while unwinding, so the tracker stays usable.
+
+use crate::error::PluginError;
+use futures::FutureExt;
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+use tokio::task::JoinError;
+
+static INSTALL_HOOK: Once = Once::new();
+
+thread_local! {
+    /// Backtrace of the last panic on this thread, taken by the panic hook
+    static LAST_BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Record a backtrace for every panic, then run the previous hook
+///
+/// Called from `init_app`; [`guard`] installs it on first use as well.
+pub fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let backtrace = Backtrace::force_capture().to_string();
+            LAST_BACKTRACE.with(|last| *last.borrow_mut() = Some(backtrace));
+            previous(info);
+        }));
+    });
+}
+
+/// Run `f`, converting a panic into [`PluginError::Internal`]
+pub fn guard<T>(f: impl FnOnce() -> Result<T, PluginError>) -> Result<T, PluginError> {
+    install_hook();
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(internal_error(payload)))
+}
+
+/// Run `f`, building the result from the [`PluginError::Internal`] of a
+/// panic with `fallback`
+///
+/// For exports that have no error to return, e.g. status getters.
+pub fn guard_or<T>(fallback: impl FnOnce(PluginError) -> T, f: impl FnOnce() -> T) -> T {
+    install_hook();
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| fallback(internal_error(payload)))
+}
+
+/// Await `future`, converting a panic while polling it into
+/// [`PluginError::Internal`]
+pub async fn guard_async<T>(future: impl Future<Output = Result<T, PluginError>>) -> Result<T, PluginError> {
+    install_hook();
+    AssertUnwindSafe(future)
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|payload| Err(internal_error(payload)))
+}
+
+/// Convert the failure of a spawned task, treating a panic that escaped the
+/// task like one caught by [`guard_async`]
+///
+/// Spawned work should still be wrapped in [`guard_async`]: only then is
+/// the backtrace taken on the thread that panicked.
+pub fn join_error(error: JoinError) -> PluginError {
+    if error.is_panic() {
+        internal_error(error.into_panic())
+    } else {
+        PluginError::ThreadingError(error.to_string())
+    }
+}
+
+/// Build the error for a caught panic on the current thread
+fn internal_error(payload: Box<dyn Any + Send>) -> PluginError {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let backtrace = LAST_BACKTRACE.with(|last| last.borrow_mut().take()).unwrap_or_default();
+
+    log::error!("Caught panic: {}", message);
+    PluginError::Internal { message, backtrace }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_becomes_internal_error() {
+        let result: Result<(), _> = guard(|| panic!("landmark index out of range"));
+        match result {
+            Err(PluginError::Internal { message, backtrace }) => {
+                assert_eq!(message, "landmark index out of range");
+                assert!(!backtrace.is_empty());
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        assert_eq!(guard(|| Ok(7)).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_panic_returns_fallback() {
+        let names = guard_or(|e| vec![e.to_string()], || -> Vec<String> { panic!("profile store corrupt") });
+        assert_eq!(names.len(), 1);
+        assert!(names[0].contains("profile store corrupt"));
+
+        assert!(guard_or(|_| false, || true));
+    }
+
+    #[test]
+    fn test_async_panic_releases_locks() {
+        let lock = tokio::sync::RwLock::new(0);
+        crate::block_on(async {
+            let result = guard_async(async {
+                let _value = lock.write().await;
+                if true {
+                    panic!("poisoned frame");
+                }
+                Ok(())
+            })
+            .await;
+            assert!(matches!(result, Err(PluginError::Internal { .. })));
+
+            // The lock was released while unwinding
+            assert_eq!(*lock.read().await, 0);
+        });
+    }
+
+    #[test]
+    fn test_task_panic_becomes_internal_error() {
+        let result: Result<(), _> = crate::block_on(async {
+            crate::runtime().spawn(async { panic!("worker died") }).await.map_err(join_error)
+        });
+        assert!(matches!(result, Err(PluginError::Internal { message, .. }) if message == "worker died"));
+    }
+}
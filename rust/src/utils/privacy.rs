@@ -0,0 +1,266 @@
+//! Privacy filtering of camera frames
+//!
+//! Produces a copy of the frame that can be recorded or streamed without
+//! exposing bystanders: the background behind the primary person is blurred
+//! or made transparent, and every other face is pixelated. The person is
+//! taken from a segmentation matte of the frame if one is available;
+//! otherwise it is estimated from the primary face's bounding box as a head
+//! ellipse on a widening torso below it, following the frame rotation, with
+//! a feathered edge. A matte keeps everyone it segments in the foreground,
+//! so bystanders' faces are still pixelated.
+
+use crate::error::PluginError;
+use crate::face_tracking::color;
+use crate::face_tracking::orientation::FrameOrientation;
+use crate::face_tracking::segmentation::SegmentationMatte;
+use crate::models::*;
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use image::{imageops, GrayImage, Luma, Rgba, RgbaImage};
+
+/// Pixelated area around a face's bounding box, relative to the box size
+const PIXELATE_MARGIN: f32 = 0.2;
+
+/// Feathered edge of the person mask, relative to the face size
+const MASK_FEATHER: f32 = 0.15;
+
+/// What happens to the background behind the primary person
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundTreatment {
+    /// Leave the background as captured
+    Keep,
+    /// Blur the background
+    Blur,
+    /// Make the background transparent
+    Remove,
+}
+
+/// How frames are anonymized
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone)]
+pub struct PrivacyOptions {
+    /// Treatment of the background
+    pub background: BackgroundTreatment,
+    /// Pixelate every face except the primary one
+    pub pixelate_bystanders: bool,
+    /// Label of the primary face (see `set_face_label`); the most confident
+    /// face if unset or no face carries the label
+    pub primary_label: Option<String>,
+    /// Pixel blocks across a pixelated face (1 - 64); fewer blocks hide
+    /// more
+    pub pixelation_blocks: u32,
+    /// Standard deviation of the background blur (pixels)
+    pub blur_sigma: f32,
+}
+
+impl Default for PrivacyOptions {
+    fn default() -> Self {
+        Self {
+            background: BackgroundTreatment::Blur,
+            pixelate_bystanders: true,
+            primary_label: None,
+            pixelation_blocks: 8,
+            blur_sigma: 12.0,
+        }
+    }
+}
+
+impl PrivacyOptions {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            (1..=64).contains(&self.pixelation_blocks),
+            "pixelation_blocks",
+            "must be between 1 and 64",
+            "Use 8",
+        );
+        report.check(
+            self.blur_sigma > 0.0 && self.blur_sigma <= 100.0,
+            "blur_sigma",
+            "must be greater than 0.0 and at most 100.0",
+            "Use 12.0",
+        );
+        report
+    }
+}
+
+/// Anonymize `frame`, returning RGBA pixels of the frame size
+///
+/// Face coordinates are expected in pixels of the frame as delivered by the
+/// camera, which is what the tracker returns with the default output
+/// conventions. `mirrored` must match `TrackerConfig::mirror_input`.
+/// `matte` is the person matte of this frame, if it was segmented.
+pub fn render(
+    frame: &CameraFrame,
+    faces: &[Face],
+    options: &PrivacyOptions,
+    mirrored: bool,
+    matte: Option<&SegmentationMatte>,
+) -> Result<Vec<u8>, PluginError> {
+    options.report().into_result()?;
+    let orientation = FrameOrientation::new(frame.rotation, mirrored)?;
+    let rgb = color::to_rgb(&frame.image_data, frame.width, frame.height, frame.format)?;
+    if rgb.len() < frame.width as usize * frame.height as usize * 3 {
+        return Err(PluginError::ImageConversion("Frame data is smaller than its dimensions".to_string()));
+    }
+    let mut image = RgbaImage::from_fn(frame.width, frame.height, |x, y| {
+        let i = (y as usize * frame.width as usize + x as usize) * 3;
+        Rgba([rgb[i], rgb[i + 1], rgb[i + 2], 255])
+    });
+
+    let primary = primary_face(faces, options.primary_label.as_deref());
+    if options.background != BackgroundTreatment::Keep {
+        let mask = match (matte, primary) {
+            (Some(matte), _) => GrayImage::from_fn(frame.width, frame.height, |x, y| {
+                Luma([matte.sample(x, y, frame.width, frame.height)])
+            }),
+            (None, Some(face)) => person_mask(frame.width, frame.height, face, &orientation),
+            (None, None) => GrayImage::new(frame.width, frame.height),
+        };
+        match options.background {
+            BackgroundTreatment::Blur => {
+                let blurred = imageops::fast_blur(&image, options.blur_sigma);
+                blend(&mut image, &blurred, &mask);
+            }
+            BackgroundTreatment::Remove => {
+                for (pixel, alpha) in image.pixels_mut().zip(mask.pixels()) {
+                    pixel[3] = alpha[0];
+                }
+            }
+            BackgroundTreatment::Keep => {}
+        }
+    }
+
+    if options.pixelate_bystanders {
+        for face in faces.iter().filter(|face| primary.is_none_or(|primary| primary.id != face.id)) {
+            pixelate(&mut image, &face.bounding_box, options.pixelation_blocks);
+        }
+    }
+    Ok(image.into_raw())
+}
+
+/// The face carrying `label`, otherwise the most confident face
+pub fn primary_face<'a>(faces: &'a [Face], label: Option<&str>) -> Option<&'a Face> {
+    label
+        .and_then(|label| faces.iter().find(|face| face.label.as_deref() == Some(label)))
+        .or_else(|| faces.iter().max_by(|a, b| a.confidence.total_cmp(&b.confidence)))
+}
+
+/// Estimated coverage of the person behind `face` (0 - 255 per pixel)
+pub(crate) fn person_mask(width: u32, height: u32, face: &Face, orientation: &FrameOrientation) -> GrayImage {
+    let bounding_box = face.bounding_box;
+    let center = (bounding_box.x + bounding_box.width / 2.0, bounding_box.y + bounding_box.height / 2.0);
+    // Axes of the upright image in frame pixels
+    let down = orientation.unmap_direction(0.0, 1.0);
+    let across = orientation.unmap_direction(1.0, 0.0);
+    let face_width = (across.0.abs() * bounding_box.width + across.1.abs() * bounding_box.height).max(1.0);
+    let face_height = (down.0.abs() * bounding_box.width + down.1.abs() * bounding_box.height).max(1.0);
+
+    let mask = GrayImage::from_fn(width, height, |x, y| {
+        let (dx, dy) = (x as f32 + 0.5 - center.0, y as f32 + 0.5 - center.1);
+        let u = (dx * across.0 + dy * across.1) / face_width;
+        let v = (dx * down.0 + dy * down.1) / face_height;
+        // Head with hair above the detected face
+        let head = (u / 0.75).powi(2) + ((v + 0.15) / 0.85).powi(2) <= 1.0;
+        // Neck and shoulders widening below the chin
+        let torso = v > 0.3 && u.abs() <= (0.5 + (v - 0.3) * 1.5).min(1.8);
+        Luma([if head || torso { 255 } else { 0 }])
+    });
+    imageops::fast_blur(&mask, MASK_FEATHER * face_width.max(face_height))
+}
+
+/// Replace the background of `image` with `background` where `mask` is low
+fn blend(image: &mut RgbaImage, background: &RgbaImage, mask: &GrayImage) {
+    for ((pixel, behind), alpha) in image.pixels_mut().zip(background.pixels()).zip(mask.pixels()) {
+        let alpha = alpha[0] as u32;
+        for channel in 0..3 {
+            pixel[channel] = ((pixel[channel] as u32 * alpha + behind[channel] as u32 * (255 - alpha)) / 255) as u8;
+        }
+    }
+}
+
+/// Replace the area around `bounding_box` with `blocks` blocks across of
+/// their average color
+fn pixelate(image: &mut RgbaImage, bounding_box: &BoundingBox, blocks: u32) {
+    let (width, height) = image.dimensions();
+    let margin_x = bounding_box.width * PIXELATE_MARGIN;
+    let margin_y = bounding_box.height * PIXELATE_MARGIN;
+    let x0 = (bounding_box.x - margin_x).floor().clamp(0.0, width as f32) as u32;
+    let y0 = (bounding_box.y - margin_y).floor().clamp(0.0, height as f32) as u32;
+    let x1 = (bounding_box.x + bounding_box.width + margin_x).ceil().clamp(0.0, width as f32) as u32;
+    let y1 = (bounding_box.y + bounding_box.height + margin_y).ceil().clamp(0.0, height as f32) as u32;
+    let block = ((x1.saturating_sub(x0)).max(y1.saturating_sub(y0)) / blocks).max(1);
+
+    for by in (y0..y1).step_by(block as usize) {
+        for bx in (x0..x1).step_by(block as usize) {
+            let (ex, ey) = ((bx + block).min(x1), (by + block).min(y1));
+            let mut sum = [0u64; 4];
+            for y in by..ey {
+                for x in bx..ex {
+                    for (total, value) in sum.iter_mut().zip(image.get_pixel(x, y).0) {
+                        *total += value as u64;
+                    }
+                }
+            }
+            let count = ((ex - bx) * (ey - by)) as u64;
+            let average = Rgba(sum.map(|total| (total / count) as u8));
+            for y in by..ey {
+                for x in bx..ex {
+                    image.put_pixel(x, y, average);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_at(id: u32, x: f32, y: f32, size: f32, confidence: f32) -> Face {
+        Face {
+            id,
+            bounding_box: BoundingBox { x, y, width: size, height: size },
+            confidence,
+            ..Face::default()
+        }
+    }
+
+    #[test]
+    fn test_removes_background_and_pixelates_bystanders() {
+        // Vertical stripes, so pixelation visibly averages them
+        let (width, height) = (120, 90);
+        let image_data = (0..width * height).flat_map(|i| [if i % 2 == 0 { 200 } else { 0 }; 3]).collect();
+        let frame = CameraFrame {
+            image_data,
+            width,
+            height,
+            format: ImageFormat::RGB,
+            timestamp: 0,
+            rotation: 0,
+            intrinsics: None,
+        };
+        let streamer = Face { label: Some("streamer".to_string()), ..face_at(1, 40.0, 10.0, 30.0, 0.6) };
+        let guest = face_at(2, 90.0, 50.0, 20.0, 0.9);
+        let options = PrivacyOptions {
+            background: BackgroundTreatment::Remove,
+            primary_label: Some("streamer".to_string()),
+            ..PrivacyOptions::default()
+        };
+        let pixels = render(&frame, &[streamer, guest], &options, false, None).unwrap();
+        let image = RgbaImage::from_raw(width, height, pixels).unwrap();
+
+        // The labelled face is kept even though the guest is more confident
+        assert_eq!(*image.get_pixel(55, 25), Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(56, 25), Rgba([200, 200, 200, 255]));
+        // Shoulders are kept, the far corner is removed
+        assert!(image.get_pixel(55, 80)[3] > 200);
+        assert_eq!(image.get_pixel(2, 2)[3], 0);
+        // Adjacent stripes of the guest's face share their block's color
+        let (left, right) = (image.get_pixel(96, 56), image.get_pixel(97, 56));
+        assert_eq!(left.0[..3], right.0[..3]);
+        assert!((50..=150).contains(&left[0]));
+    }
+}
@@ -0,0 +1,293 @@
+//! Pipeline profiling
+//!
+//! The processing stages open `tracing` spans carrying the frame timestamp
+//! as the `frame` field. While profiling runs, the [`Profiler`] subscriber
+//! records how long every span lived and [`stop`] exports the most recent
+//! spans as a Chrome trace, which chrome://tracing and Perfetto open
+//! directly. Spans of one frame share a track, so a frame spike shows which
+//! stage it came from.
+//!
+//! Spans are measured from creation to close rather than per poll: an
+//! instrumented stage awaiting inference on a blocking thread still covers
+//! the whole wait. Outside of profiling, spans are disabled and cost a
+//! relaxed atomic load.
+
+use crate::error::PluginError;
+use log::info;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
+
+/// Most spans kept; older spans are dropped first
+pub const MAX_PROFILE_SPANS: usize = 16_384;
+
+/// The installed profiler; `None` if another subscriber was installed first
+static PROFILER: OnceLock<Option<Arc<Profiler>>> = OnceLock::new();
+
+/// A span that is still open
+struct OpenSpan {
+    name: &'static str,
+    category: &'static str,
+    args: Map<String, Value>,
+    start: Instant,
+    references: usize,
+}
+
+/// A closed span
+struct SpanRecord {
+    id: u64,
+    name: &'static str,
+    category: &'static str,
+    args: Map<String, Value>,
+    /// Microseconds since profiling started
+    start_us: f64,
+    end_us: f64,
+}
+
+#[derive(Default)]
+struct Session {
+    started: Option<Instant>,
+    spans: VecDeque<SpanRecord>,
+    dropped: u64,
+}
+
+/// `tracing` subscriber recording span lifetimes while profiling runs
+#[derive(Default)]
+pub struct Profiler {
+    active: AtomicBool,
+    next_id: AtomicU64,
+    open: Mutex<HashMap<u64, OpenSpan>>,
+    session: Mutex<Session>,
+}
+
+impl Profiler {
+    fn finish(&self, id: u64, span: OpenSpan) {
+        let end = Instant::now();
+        let mut session = self.session.lock().unwrap();
+        let Some(started) = session.started else {
+            return;
+        };
+        // Spans opened before profiling started are clipped to its start
+        let micros = |instant: Instant| instant.saturating_duration_since(started).as_secs_f64() * 1e6;
+        if session.spans.len() == MAX_PROFILE_SPANS {
+            session.spans.pop_front();
+            session.dropped += 1;
+        }
+        session.spans.push_back(SpanRecord {
+            id,
+            name: span.name,
+            category: span.category,
+            args: span.args,
+            start_us: micros(span.start),
+            end_us: micros(end),
+        });
+    }
+}
+
+impl Subscriber for Profiler {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // Checked on every span, since profiling is switched at runtime
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_span() && self.active.load(Ordering::Relaxed)
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut args = ArgsVisitor::default();
+        attributes.record(&mut args);
+        let metadata = attributes.metadata();
+        self.open.lock().unwrap().insert(
+            id,
+            OpenSpan {
+                name: metadata.name(),
+                category: metadata.target().rsplit("::").next().unwrap_or("tracker"),
+                args: args.0,
+                start: Instant::now(),
+                references: 1,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(open) = self.open.lock().unwrap().get_mut(&span.into_u64()) {
+            let mut args = ArgsVisitor(std::mem::take(&mut open.args));
+            values.record(&mut args);
+            open.args = args.0;
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Some(open) = self.open.lock().unwrap().get_mut(&id.into_u64()) {
+            open.references += 1;
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let closed = {
+            let mut open = self.open.lock().unwrap();
+            match open.get_mut(&id.into_u64()) {
+                Some(span) if span.references > 1 => {
+                    span.references -= 1;
+                    None
+                }
+                Some(_) => open.remove(&id.into_u64()),
+                None => None,
+            }
+        };
+        match closed {
+            Some(span) => {
+                self.finish(id.into_u64(), span);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Collects span fields as trace event arguments
+#[derive(Default)]
+struct ArgsVisitor(Map<String, Value>);
+
+impl Visit for ArgsVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+/// Install the profiler as the global `tracing` subscriber on first use
+fn profiler() -> Result<&'static Profiler, PluginError> {
+    PROFILER
+        .get_or_init(|| {
+            let profiler = Arc::new(Profiler::default());
+            tracing::subscriber::set_global_default(profiler.clone()).ok().map(|_| profiler)
+        })
+        .as_deref()
+        .ok_or_else(|| {
+            PluginError::ProcessingError("Another tracing subscriber is installed; profiling is unavailable".to_string())
+        })
+}
+
+/// Start recording spans, discarding a previous unfinished profile
+pub fn start() -> Result<(), PluginError> {
+    let profiler = profiler()?;
+    *profiler.session.lock().unwrap() = Session {
+        started: Some(Instant::now()),
+        ..Session::default()
+    };
+    profiler.active.store(true, Ordering::Relaxed);
+    info!("Profiling started");
+    Ok(())
+}
+
+/// Stop recording and export the recorded spans as Chrome trace JSON
+pub fn stop() -> Result<Vec<u8>, PluginError> {
+    let profiler = PROFILER
+        .get()
+        .and_then(Option::as_deref)
+        .filter(|profiler| profiler.active.swap(false, Ordering::Relaxed))
+        .ok_or_else(|| PluginError::ProcessingError("Profiling is not running".to_string()))?;
+    let session = std::mem::take(&mut *profiler.session.lock().unwrap());
+    info!("Profiling stopped with {} spans ({} dropped)", session.spans.len(), session.dropped);
+
+    serde_json::to_vec(&chrome_trace(&session))
+        .map_err(|e| PluginError::ProcessingError(format!("Failed to serialize profile: {}", e)))
+}
+
+/// Trace in the Chrome trace event format
+///
+/// Every span becomes a pair of nestable async events; spans with a `frame`
+/// field are grouped on a track per frame.
+fn chrome_trace(session: &Session) -> Value {
+    let mut events = Vec::with_capacity(session.spans.len() * 2);
+    for span in &session.spans {
+        let id = match span.args.get("frame") {
+            Some(frame) => format!("frame-{}", frame),
+            None => format!("span-{}", span.id),
+        };
+        for (phase, ts) in [("b", span.start_us), ("e", span.end_us)] {
+            events.push(json!({
+                "name": span.name,
+                "cat": span.category,
+                "ph": phase,
+                "id": id,
+                "ts": ts,
+                "pid": 1,
+                "args": span.args,
+            }));
+        }
+    }
+    json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+        "otherData": { "droppedSpans": session.dropped },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exports_spans_as_async_events() {
+        let session = Session {
+            started: Some(Instant::now()),
+            spans: VecDeque::from([SpanRecord {
+                id: 7,
+                name: "detect",
+                category: "tracker",
+                args: Map::from_iter([("frame".to_string(), json!(42))]),
+                start_us: 100.0,
+                end_us: 350.0,
+            }]),
+            dropped: 0,
+        };
+
+        let trace = chrome_trace(&session);
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["ph"], "b");
+        assert_eq!(events[0]["id"], "frame-42");
+        assert_eq!(events[1]["ph"], "e");
+        assert_eq!(events[1]["ts"], 350.0);
+    }
+}
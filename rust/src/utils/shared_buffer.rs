@@ -0,0 +1,228 @@
+//! Shared-memory frame ring buffer
+//!
+//! Passing a `CameraFrame` copies the pixel data across the FFI boundary,
+//! which costs several milliseconds per frame at 1080p. In shared mode the
+//! plugin allocates a ring of fixed-size slots once and hands its address to
+//! Dart. Dart writes pixels straight into a slot (via `Pointer.fromAddress`)
+//! and only submits the slot index and frame metadata; the tracker then
+//! converts the pixels in place.
+//!
+//! Each slot moves through `Free -> Writing -> Queued -> Free`. Dart may only
+//! write into a slot it acquired and must not touch it again after pushing;
+//! the slot becomes free once the tracker is done with the frame (or dropped
+//! it).
+
+use crate::error::PluginError;
+use crate::models::*;
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Largest shared buffer that may be allocated (512 MiB)
+pub const MAX_SHARED_BUFFER_SIZE: usize = 512 * 1024 * 1024;
+
+const SLOT_FREE: u8 = 0;
+const SLOT_WRITING: u8 = 1;
+const SLOT_QUEUED: u8 = 2;
+
+lazy_static! {
+    /// Active shared buffer, if shared mode is enabled
+    static ref SHARED_BUFFER: RwLock<Option<Arc<SharedFrameBuffer>>> = RwLock::new(None);
+}
+
+/// Ring of fixed-size frame slots in memory shared with Dart
+pub struct SharedFrameBuffer {
+    /// Start of the slot memory, owned by this buffer
+    memory: *mut u8,
+    slot_size: usize,
+    slots: Vec<AtomicU8>,
+    /// Slot the next acquisition starts searching from
+    cursor: AtomicUsize,
+}
+
+// The memory is only read while a slot is queued, during which Dart does not
+// write to it; slot ownership is tracked with atomics.
+unsafe impl Send for SharedFrameBuffer {}
+unsafe impl Sync for SharedFrameBuffer {}
+
+impl SharedFrameBuffer {
+    pub fn new(slot_count: u32, slot_size: u32) -> Result<Self, PluginError> {
+        let total_size = slot_count as usize * slot_size as usize;
+        if total_size == 0 || total_size > MAX_SHARED_BUFFER_SIZE {
+            return Err(PluginError::InvalidConfiguration(format!(
+                "Shared buffer of {} x {} bytes is out of range",
+                slot_count, slot_size
+            )));
+        }
+
+        let memory = Box::into_raw(vec![0u8; total_size].into_boxed_slice()) as *mut u8;
+
+        Ok(Self {
+            memory,
+            slot_size: slot_size as usize,
+            slots: (0..slot_count).map(|_| AtomicU8::new(SLOT_FREE)).collect(),
+            cursor: AtomicUsize::new(0),
+        })
+    }
+
+    /// Address and layout reported to Dart
+    pub fn info(&self) -> SharedFrameBufferInfo {
+        SharedFrameBufferInfo {
+            address: self.memory as usize,
+            slot_count: self.slots.len() as u32,
+            slot_size: self.slot_size as u32,
+        }
+    }
+
+    /// Reserve the next free slot for writing
+    pub fn acquire_slot(&self) -> Option<u32> {
+        let count = self.slots.len();
+        let start = self.cursor.load(Ordering::Relaxed);
+
+        (0..count).map(|offset| (start + offset) % count).find(|&index| {
+            let acquired = self.slots[index]
+                .compare_exchange(SLOT_FREE, SLOT_WRITING, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok();
+            if acquired {
+                self.cursor.store((index + 1) % count, Ordering::Relaxed);
+            }
+            acquired
+        }).map(|index| index as u32)
+    }
+
+    /// Hand a written slot over to the tracker
+    pub fn submit(self: &Arc<Self>, slot: u32, metadata: SharedFrameMetadata) -> Result<SharedFrame, PluginError> {
+        let state = self.slots.get(slot as usize).ok_or_else(|| {
+            PluginError::ProcessingError(format!("Shared slot {} does not exist", slot))
+        })?;
+
+        state
+            .compare_exchange(SLOT_WRITING, SLOT_QUEUED, Ordering::AcqRel, Ordering::Relaxed)
+            .map_err(|_| PluginError::ProcessingError(format!("Shared slot {} was not acquired", slot)))?;
+
+        Ok(SharedFrame {
+            buffer: self.clone(),
+            slot,
+            metadata,
+        })
+    }
+
+    fn release(&self, slot: u32) {
+        self.slots[slot as usize].store(SLOT_FREE, Ordering::Release);
+    }
+
+    fn slot_data(&self, slot: u32) -> &[u8] {
+        // SAFETY: the slot lies within the allocation, and it is queued, so
+        // Dart does not write to it while the slice is alive
+        unsafe { std::slice::from_raw_parts(self.memory.add(slot as usize * self.slot_size), self.slot_size) }
+    }
+}
+
+impl Drop for SharedFrameBuffer {
+    fn drop(&mut self) {
+        let total_size = self.slots.len() * self.slot_size;
+        // SAFETY: `memory` came from a boxed slice of exactly this length
+        unsafe { drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.memory, total_size))) };
+    }
+}
+
+/// A queued frame living in a shared buffer slot
+///
+/// The slot is released when the frame is dropped.
+pub struct SharedFrame {
+    buffer: Arc<SharedFrameBuffer>,
+    slot: u32,
+    pub metadata: SharedFrameMetadata,
+}
+
+impl SharedFrame {
+    /// Pixel data of the frame, read in place
+    pub fn data(&self) -> &[u8] {
+        self.buffer.slot_data(self.slot)
+    }
+}
+
+impl Drop for SharedFrame {
+    fn drop(&mut self) {
+        self.buffer.release(self.slot);
+    }
+}
+
+/// Allocate a new shared buffer, replacing the current one
+///
+/// Frames still queued from a replaced buffer keep its memory alive until
+/// they have been processed.
+pub fn create(slot_count: u32, slot_size: u32) -> Result<SharedFrameBufferInfo, PluginError> {
+    let buffer = SharedFrameBuffer::new(slot_count, slot_size)?;
+    let info = buffer.info();
+    *SHARED_BUFFER.write().unwrap() = Some(Arc::new(buffer));
+    Ok(info)
+}
+
+/// Get the active shared buffer
+pub fn active() -> Result<Arc<SharedFrameBuffer>, PluginError> {
+    SHARED_BUFFER
+        .read()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| PluginError::ProcessingError("Shared frame buffer has not been created".to_string()))
+}
+
+/// Disable shared mode
+pub fn release() {
+    *SHARED_BUFFER.write().unwrap() = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> SharedFrameMetadata {
+        SharedFrameMetadata {
+            width: 2,
+            height: 2,
+            format: ImageFormat::GRAY8,
+            timestamp: 0,
+            rotation: 0,
+            intrinsics: None,
+        }
+    }
+
+    #[test]
+    fn test_slot_lifecycle() {
+        let buffer = Arc::new(SharedFrameBuffer::new(2, 16).unwrap());
+
+        assert_eq!(buffer.acquire_slot(), Some(0));
+        assert_eq!(buffer.acquire_slot(), Some(1));
+        assert_eq!(buffer.acquire_slot(), None);
+
+        let frame = buffer.submit(0, metadata()).unwrap();
+        assert_eq!(frame.data().len(), 16);
+
+        // Submitting twice is rejected
+        assert!(buffer.submit(0, metadata()).is_err());
+
+        drop(frame);
+        assert_eq!(buffer.acquire_slot(), Some(0));
+    }
+
+    #[test]
+    fn test_reads_written_pixels() {
+        let buffer = Arc::new(SharedFrameBuffer::new(2, 4).unwrap());
+        let info = buffer.info();
+
+        let slot = buffer.acquire_slot().unwrap();
+        // Simulate Dart writing through the reported address
+        let address = info.address + slot as usize * info.slot_size as usize;
+        unsafe { std::ptr::copy_nonoverlapping([1u8, 2, 3, 4].as_ptr(), address as *mut u8, 4) };
+
+        let frame = buffer.submit(slot, metadata()).unwrap();
+        assert_eq!(frame.data(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_rejects_oversized_buffer() {
+        assert!(SharedFrameBuffer::new(1024, u32::MAX).is_err());
+        assert!(SharedFrameBuffer::new(0, 16).is_err());
+    }
+}
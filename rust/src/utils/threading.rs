@@ -0,0 +1,204 @@
+//! Worker thread pool size and priority
+//!
+//! Desktop builds can spend every core on tracking, while phones get hot and
+//! starve the UI thread if the tracker does. [`ThreadingConfig`] sizes the
+//! shared async runtime and the inference runtimes and sets the OS priority
+//! of the runtime's threads. Unset counts fall back to [`default_threads`],
+//! which stays within two threads on mobile.
+//!
+//! The runtime is created once per process, so its worker count is taken
+//! from the configuration present when it starts; later changes only affect
+//! the inference threads and the priority of new threads.
+
+use crate::validation::ValidationReport;
+use flutter_rust_bridge::frb;
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+
+/// Largest accepted thread count
+pub const MAX_THREADS: u32 = 64;
+
+/// Most threads used by default on phones and tablets
+const MOBILE_MAX_THREADS: usize = 2;
+
+lazy_static! {
+    static ref CURRENT: Mutex<ThreadingConfig> = Mutex::new(ThreadingConfig::default());
+}
+
+/// Worker count the runtime was started with
+static RUNTIME_WORKERS: OnceLock<usize> = OnceLock::new();
+
+/// OS scheduling priority of the tracker's threads
+#[frb(dart_metadata=("freezed"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThreadPriority {
+    /// Yield to the UI and other work
+    Low,
+    /// Platform default
+    Normal,
+    /// Favor tracking; may need elevated permissions and falls back to
+    /// `Normal` without them
+    High,
+}
+
+/// Thread pool settings
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThreadingConfig {
+    /// Threads of the shared async runtime (0 = platform default)
+    pub worker_threads: u32,
+    /// Threads within a single model run when
+    /// `InferenceOptions::num_threads` is not set (0 = platform default)
+    pub inference_threads: u32,
+    /// Priority of the runtime's threads
+    pub priority: ThreadPriority,
+}
+
+impl Default for ThreadingConfig {
+    fn default() -> Self {
+        Self {
+            worker_threads: 0,
+            inference_threads: 0,
+            priority: ThreadPriority::Normal,
+        }
+    }
+}
+
+impl ThreadingConfig {
+    /// Check the settings, listing every violated constraint
+    pub fn report(&self) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        report.check(
+            self.worker_threads <= MAX_THREADS,
+            "worker_threads",
+            &format!("must be at most {}", MAX_THREADS),
+            "Use 0 for the platform default",
+        );
+        report.check(
+            self.inference_threads <= MAX_THREADS,
+            "inference_threads",
+            &format!("must be at most {}", MAX_THREADS),
+            "Use 0 for the platform default",
+        );
+        report
+    }
+
+    /// Runtime worker count, resolving 0 to the platform default
+    pub fn resolved_worker_threads(&self) -> usize {
+        match self.worker_threads {
+            0 => default_threads(),
+            threads => threads as usize,
+        }
+    }
+
+    /// Inference thread count; an explicit `num_threads` from the inference
+    /// options wins over `inference_threads`, 0 resolves to the platform
+    /// default
+    pub fn resolved_inference_threads(&self, num_threads: u32) -> usize {
+        match (num_threads, self.inference_threads) {
+            (0, 0) => default_threads(),
+            (0, threads) | (threads, _) => threads as usize,
+        }
+    }
+}
+
+/// Thread count used when none is configured: every core on desktop, at most
+/// two on mobile
+pub fn default_threads() -> usize {
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    if cfg!(any(target_os = "android", target_os = "ios")) {
+        cores.min(MOBILE_MAX_THREADS)
+    } else {
+        cores
+    }
+}
+
+/// Make `config` the process-wide threading configuration
+///
+/// Takes effect for the runtime if it has not started yet, otherwise only for
+/// threads started from now on.
+pub fn configure(config: &ThreadingConfig) {
+    *CURRENT.lock().unwrap() = *config;
+    if let Some(&workers) = RUNTIME_WORKERS.get() {
+        if config.worker_threads != 0 && config.worker_threads as usize != workers {
+            warn!(
+                "Runtime already runs {} worker threads; worker_threads = {} takes effect after the app restarts",
+                workers, config.worker_threads
+            );
+        }
+    }
+}
+
+/// Current process-wide threading configuration
+pub fn current() -> ThreadingConfig {
+    *CURRENT.lock().unwrap()
+}
+
+/// Worker count for the runtime being created, remembered for [`configure`]
+pub(crate) fn runtime_worker_threads() -> usize {
+    *RUNTIME_WORKERS.get_or_init(|| current().resolved_worker_threads())
+}
+
+/// Apply the configured priority to the calling thread
+///
+/// Installed as the runtime's thread start hook.
+pub(crate) fn apply_current_priority() {
+    let priority = current().priority;
+    if priority != ThreadPriority::Normal {
+        if let Err(e) = set_current_thread_priority(priority) {
+            warn!("Could not set thread priority {:?}: {}", priority, e);
+        }
+    }
+}
+
+/// Set the OS priority of the calling thread
+///
+/// Supported on Linux and Android through the thread's nice value; a no-op
+/// elsewhere.
+pub fn set_current_thread_priority(priority: ThreadPriority) -> std::io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let nice = match priority {
+            ThreadPriority::Low => 10,
+            ThreadPriority::Normal => 0,
+            ThreadPriority::High => -5,
+        };
+        // On Linux the nice value is per thread and `who = 0` names the
+        // calling thread
+        // SAFETY: setpriority takes no pointers
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        log::debug!("Thread priority {:?} is not supported on this platform", priority);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_thread_counts() {
+        let config = ThreadingConfig { worker_threads: 3, inference_threads: 2, ..ThreadingConfig::default() };
+        assert_eq!(config.resolved_worker_threads(), 3);
+        assert_eq!(config.resolved_inference_threads(0), 2);
+        assert_eq!(config.resolved_inference_threads(4), 4);
+
+        let defaults = ThreadingConfig::default();
+        assert_eq!(defaults.resolved_worker_threads(), default_threads());
+        assert_eq!(defaults.resolved_inference_threads(0), default_threads());
+        assert!(default_threads() >= 1);
+
+        let too_many = ThreadingConfig { worker_threads: MAX_THREADS + 1, ..ThreadingConfig::default() };
+        assert!(!too_many.report().is_valid());
+    }
+}
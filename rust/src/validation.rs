@@ -0,0 +1,100 @@
+//! Configuration validation reports
+//!
+//! Configuration types describe every problem they find in a
+//! [`ValidationReport`] instead of stopping at the first one, so the app can
+//! show all of them at once. `validate` methods wrap the report into a single
+//! [`PluginError::InvalidConfiguration`] listing every violation.
+
+use crate::error::PluginError;
+use flutter_rust_bridge::frb;
+
+/// A configuration value that breaks a constraint
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigViolation {
+    /// Path of the offending field, e.g. `blink_detection.close_threshold`
+    pub field: String,
+    /// Constraint the value breaks
+    pub constraint: String,
+    /// How to fix the value
+    pub suggestion: String,
+}
+
+/// All constraint violations found in a configuration
+#[frb(dart_metadata=("freezed", "immutable"))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Violations in field order; empty if the configuration is valid
+    pub violations: Vec<ConfigViolation>,
+}
+
+impl ValidationReport {
+    /// Create an empty report
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether no violations were found
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Record a violation of `constraint` on `field` unless `ok`
+    pub(crate) fn check(&mut self, ok: bool, field: &str, constraint: &str, suggestion: impl Into<String>) {
+        if !ok {
+            self.violations.push(ConfigViolation {
+                field: field.to_string(),
+                constraint: constraint.to_string(),
+                suggestion: suggestion.into(),
+            });
+        }
+    }
+
+    /// Add the violations of a nested configuration stored in `field`
+    pub(crate) fn nest(&mut self, field: &str, report: ValidationReport) {
+        self.violations.extend(report.violations.into_iter().map(|violation| ConfigViolation {
+            field: format!("{}.{}", field, violation.field),
+            ..violation
+        }));
+    }
+
+    /// Turn the report into an error listing every violation
+    pub fn into_result(self) -> Result<(), PluginError> {
+        if self.is_valid() {
+            return Ok(());
+        }
+        let messages: Vec<String> = self
+            .violations
+            .iter()
+            .map(|violation| format!("{} {}", violation.field, violation.constraint))
+            .collect();
+        Err(PluginError::InvalidConfiguration(messages.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_every_violation() {
+        let mut nested = ValidationReport::new();
+        nested.check(false, "sensitivity", "must be between 0.0 and 1.0", "Use 0.5");
+
+        let mut report = ValidationReport::new();
+        report.check(true, "max_faces", "must be greater than 0", "Use 1");
+        report.check(false, "target_fps", "must be between 1 and 120", "Use 30");
+        report.nest("head_gestures", nested);
+
+        let fields: Vec<_> = report.violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(fields, vec!["target_fps", "head_gestures.sensitivity"]);
+        match report.into_result() {
+            Err(PluginError::InvalidConfiguration(message)) => assert_eq!(
+                message,
+                "target_fps must be between 1 and 120; head_gestures.sensitivity must be between 0.0 and 1.0"
+            ),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(ValidationReport::new().into_result().is_ok());
+    }
+}